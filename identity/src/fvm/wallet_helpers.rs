@@ -4,7 +4,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use blake2b_simd::Params;
-use bls_signatures::{PrivateKey as BlsPrivate, Serialize};
+use bls_signatures::{PrivateKey as BlsPrivate, Serialize, Signature as BlsSignature};
 use fvm_shared::{
     address::Address,
     crypto::signature::{Signature, SignatureType},
@@ -87,6 +87,26 @@ pub fn sign(sig_type: SignatureType, private_key: &[u8], msg: &[u8]) -> Result<S
     }
 }
 
+/// Aggregates the BLS `signatures` into a single one, verified against the public keys of all
+/// their signers at once
+pub fn aggregate_bls(signatures: &[Signature]) -> Result<Signature, Error> {
+    let signatures = signatures
+        .iter()
+        .map(|s| {
+            if s.sig_type != SignatureType::BLS {
+                return Err(Error::Other(format!(
+                    "cannot aggregate {:?} signature",
+                    s.sig_type
+                )));
+            }
+            BlsSignature::from_bytes(&s.bytes).map_err(|err| Error::Other(err.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let aggregate =
+        bls_signatures::aggregate(&signatures).map_err(|err| Error::Other(err.to_string()))?;
+    Ok(Signature::new_bls(aggregate.as_bytes()))
+}
+
 /// Generate a new private key
 pub fn generate(sig_type: SignatureType) -> Result<Vec<u8>, Error> {
     let rng = &mut OsRng::default();
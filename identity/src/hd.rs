@@ -0,0 +1,165 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Hierarchical deterministic derivation of keys from a BIP39 mnemonic.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use ethers::signers::coins_bip39::{English, Mnemonic};
+use ethers::signers::MnemonicBuilder;
+use fvm_shared::crypto::signature::SignatureType;
+
+use crate::evm::KeyInfo as EvmKeyInfo;
+use crate::fvm::KeyInfo;
+
+/// The SLIP-44 coin type of Filecoin.
+pub const FIL_COIN_TYPE: u32 = 461;
+/// The SLIP-44 coin type of Ethereum.
+pub const ETH_COIN_TYPE: u32 = 60;
+/// The number of words of the mnemonics generated.
+pub const DEFAULT_WORD_COUNT: usize = 24;
+
+/// The role an account plays in a subnet. Each role is derived under its own BIP44 account, so
+/// that the keys of a role can be rotated by index without touching the others. The default role
+/// derives the same keys as the standard Filecoin and Ethereum wallets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountRole {
+    Default,
+    Validator,
+    Worker,
+    Relayer,
+}
+
+impl AccountRole {
+    /// The BIP44 account the keys of the role are derived from.
+    pub fn account(&self) -> u32 {
+        match self {
+            AccountRole::Default => 0,
+            AccountRole::Validator => 1,
+            AccountRole::Worker => 2,
+            AccountRole::Relayer => 3,
+        }
+    }
+}
+
+impl FromStr for AccountRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "default" => Self::Default,
+            "validator" => Self::Validator,
+            "worker" => Self::Worker,
+            "relayer" => Self::Relayer,
+            _ => return Err(anyhow!("invalid account role")),
+        })
+    }
+}
+
+impl Display for AccountRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AccountRole::Default => "default",
+            AccountRole::Validator => "validator",
+            AccountRole::Worker => "worker",
+            AccountRole::Relayer => "relayer",
+        };
+        write!(f, "{s:}")
+    }
+}
+
+/// Generates a new random english mnemonic with `word_count` words.
+pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), word_count)?;
+    Ok(mnemonic.to_phrase())
+}
+
+/// Returns the BIP44 derivation path, i.e. `m/44'/coin_type'/account'/0/index`, of the key at
+/// `index` for the role.
+pub fn derivation_path(coin_type: u32, role: AccountRole, index: u32) -> String {
+    format!("m/44'/{coin_type:}'/{}'/0/{index:}", role.account())
+}
+
+/// Derives the secp256k1 private key at `path` from the mnemonic, optionally protected with a
+/// BIP39 passphrase.
+pub fn derive_private_key(phrase: &str, passphrase: Option<&str>, path: &str) -> Result<Vec<u8>> {
+    let mut builder = MnemonicBuilder::<English>::default()
+        .phrase(phrase)
+        .derivation_path(path)?;
+    if let Some(passphrase) = passphrase {
+        builder = builder.password(passphrase);
+    }
+    let wallet = builder.build()?;
+    Ok(wallet.signer().to_bytes().to_vec())
+}
+
+/// Derives the fvm key of the role at `index` using the Filecoin coin type.
+pub fn derive_fvm_key(
+    phrase: &str,
+    passphrase: Option<&str>,
+    role: AccountRole,
+    index: u32,
+) -> Result<KeyInfo> {
+    let path = derivation_path(FIL_COIN_TYPE, role, index);
+    let private_key = derive_private_key(phrase, passphrase, &path)?;
+    Ok(KeyInfo::new(SignatureType::Secp256k1, private_key))
+}
+
+/// Derives the evm key of the role at `index` using the Ethereum coin type.
+pub fn derive_evm_key(
+    phrase: &str,
+    passphrase: Option<&str>,
+    role: AccountRole,
+    index: u32,
+) -> Result<EvmKeyInfo> {
+    let path = derivation_path(ETH_COIN_TYPE, role, index);
+    let private_key = derive_private_key(phrase, passphrase, &path)?;
+    Ok(EvmKeyInfo::new(private_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const PHRASE: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn test_derivation_path() {
+        assert_eq!(
+            derivation_path(FIL_COIN_TYPE, AccountRole::Default, 0),
+            "m/44'/461'/0'/0/0"
+        );
+        assert_eq!(
+            derivation_path(ETH_COIN_TYPE, AccountRole::Worker, 3),
+            "m/44'/60'/2'/0/3"
+        );
+    }
+
+    #[test]
+    fn test_derive_evm_key() {
+        let key = derive_evm_key(PHRASE, None, AccountRole::Default, 0).unwrap();
+        let addr = ethers::types::Address::try_from(key).unwrap();
+        assert_eq!(
+            addr,
+            ethers::types::Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_roles_derive_different_keys() {
+        let default = derive_fvm_key(PHRASE, None, AccountRole::Default, 0).unwrap();
+        let validator = derive_fvm_key(PHRASE, None, AccountRole::Validator, 0).unwrap();
+        assert_ne!(default.private_key(), validator.private_key());
+    }
+
+    #[test]
+    fn test_generate_mnemonic() {
+        let phrase = generate_mnemonic(DEFAULT_WORD_COUNT).unwrap();
+        assert_eq!(phrase.split_whitespace().count(), DEFAULT_WORD_COUNT);
+        derive_evm_key(&phrase, None, AccountRole::Default, 0).unwrap();
+    }
+}
@@ -5,6 +5,8 @@
 
 mod evm;
 mod fvm;
+#[cfg(feature = "with-ethers")]
+pub mod hd;
 
 #[cfg(feature = "with-ethers")]
 pub use crate::evm::random_key_info;
@@ -0,0 +1,324 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Alert rules of the config, evaluated by the agent itself.
+//!
+//! Every rule is evaluated once per interval against the targets it covers, e.g. every account of
+//! a subnet for a balance rule. A target crossing the threshold of a rule fires a notification,
+//! and a second one is sent when it is back within the threshold, so that an operator without an
+//! external alerting stack is told about a degradation once instead of on every evaluation.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use tokio::time::sleep;
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+
+use crate::checkpoint::{checkpoint_direction, setup_manager_from_subnet};
+use crate::config::{AlertMetric, AlertRule, ReloadableConfig};
+use crate::notify::{Notification, NotificationKind};
+use crate::server::subnet::SubnetManagerPool;
+use crate::state::AgentState;
+
+/// How often the rules are evaluated.
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The value of the metric of a rule for one of its targets.
+struct Sample {
+    /// The subnet of the target, not set for the metrics of the agent as a whole.
+    subnet: Option<SubnetID>,
+    target: String,
+    value: f64,
+}
+
+/// Evaluates the alert rules of the config until the agent shuts down.
+pub struct AlertsSubsystem {
+    config: Arc<ReloadableConfig>,
+    pool: SubnetManagerPool,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    /// The rule and target of the alerts firing, as `<rule>/<target>`.
+    firing: BTreeSet<String>,
+    /// The checkpoint errors counted at the last evaluation, and when.
+    last_errors: Option<(Instant, u64)>,
+}
+
+impl AlertsSubsystem {
+    pub fn new(
+        config: Arc<ReloadableConfig>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
+    ) -> Self {
+        Self {
+            pool: SubnetManagerPool::new(
+                config.clone(),
+                fvm_wallet.clone(),
+                evm_keystore.clone(),
+                state,
+            ),
+            config,
+            fvm_wallet,
+            evm_keystore,
+            firing: BTreeSet::new(),
+            last_errors: None,
+        }
+    }
+
+    async fn evaluate(&mut self) {
+        // the rules are read on every evaluation to follow the reloads of the config
+        let rules = self.config.get_config().alerts.clone();
+        self.firing.retain(|key| {
+            rules
+                .iter()
+                .any(|r| key.starts_with(&format!("{}/", r.name)))
+        });
+
+        let error_rate = self.error_rate();
+        for rule in rules.iter() {
+            let samples = match rule.metric {
+                AlertMetric::CheckpointLag => self.checkpoint_lags(rule).await,
+                AlertMetric::Balance => self.balances(rule).await,
+                AlertMetric::QuorumStall => self.quorum_stalls(rule),
+                AlertMetric::ErrorRate => Ok(error_rate
+                    .map(|value| Sample {
+                        subnet: None,
+                        target: String::from("agent"),
+                        value,
+                    })
+                    .into_iter()
+                    .collect()),
+            };
+            let samples = match samples {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("cannot evaluate alert {}: {e:}", rule.name);
+                    continue;
+                }
+            };
+
+            for sample in samples {
+                if let Some(kind) = update(&mut self.firing, rule, &sample.target, sample.value) {
+                    let message = alert_message(kind, rule, &sample);
+                    let notification = match &sample.subnet {
+                        Some(subnet) => Notification::new(kind, subnet, message),
+                        None => Notification::of_agent(kind, message),
+                    };
+                    self.pool.state().notifier.notify(notification);
+                }
+            }
+        }
+    }
+
+    /// The checkpoint errors per minute since the last evaluation, none on the first one.
+    fn error_rate(&mut self) -> Option<f64> {
+        let now = Instant::now();
+        let errors = self.pool.state().counters.checkpoint_errors.get();
+        let rate = self.last_errors.and_then(|(at, last)| {
+            let minutes = now.duration_since(at).as_secs_f64() / 60.0;
+            (minutes > 0.0).then(|| errors.saturating_sub(last) as f64 / minutes)
+        });
+        self.last_errors = Some((now, errors));
+        rate
+    }
+
+    /// The subnets of `rule`, all the subnets of the config if it does not set one.
+    fn subnets(&self, rule: &AlertRule) -> Result<Vec<SubnetID>> {
+        match &rule.subnet {
+            Some(s) => Ok(vec![SubnetID::from_str(s)?]),
+            None => Ok(self.pool.subnets().into_keys().collect()),
+        }
+    }
+
+    /// The checkpoints not executed yet, in checkpoint periods, of the directions of the subnets
+    /// of `rule` checkpointed by the agent.
+    async fn checkpoint_lags(&self, rule: &AlertRule) -> Result<Vec<Sample>> {
+        let subnets = self.pool.subnets();
+        let mut samples = vec![];
+        for id in self.subnets(rule)? {
+            let subnet = subnets
+                .get(&id)
+                .ok_or_else(|| anyhow!("subnet {id:} not configured"))?;
+            // only the subnets with a parent in the config are checkpointed
+            if !id.parent().map_or(false, |p| subnets.contains_key(&p)) {
+                continue;
+            }
+
+            let managers = setup_manager_from_subnet(
+                &subnets,
+                subnet,
+                self.fvm_wallet.clone(),
+                self.evm_keystore.clone(),
+                self.pool.state(),
+            )
+            .await?;
+            for m in managers.iter() {
+                let period = m.checkpoint_period();
+                if period <= 0 {
+                    continue;
+                }
+                let lag = m.current_epoch().await? - m.last_executed_epoch().await?;
+                samples.push(Sample {
+                    subnet: Some(id.clone()),
+                    target: format!("{id:} {}", checkpoint_direction(m.as_ref())),
+                    value: lag.max(0) as f64 / period as f64,
+                });
+            }
+        }
+        Ok(samples)
+    }
+
+    /// The epochs the checkpoints of the subnets of `rule` have been short of their quorum, as
+    /// last counted by the quorum monitor.
+    fn quorum_stalls(&self, rule: &AlertRule) -> Result<Vec<Sample>> {
+        let subnets = self.subnets(rule)?;
+        let samples = self
+            .pool
+            .state()
+            .quorum_monitor
+            .all()
+            .into_iter()
+            .filter_map(|d| {
+                let id = SubnetID::from_str(&d.subnet).ok()?;
+                subnets.contains(&id).then(|| Sample {
+                    target: format!("{id:} {}", d.direction),
+                    subnet: Some(id),
+                    value: d.stalled_epochs() as f64,
+                })
+            })
+            .collect();
+        Ok(samples)
+    }
+
+    /// The balances, in FIL, of the accounts of `rule` in its subnets.
+    async fn balances(&self, rule: &AlertRule) -> Result<Vec<Sample>> {
+        let mut samples = vec![];
+        for id in self.subnets(rule)? {
+            let conn = self
+                .pool
+                .get(&id)
+                .ok_or_else(|| anyhow!("subnet {id:} not configured"))?;
+            let accounts = match &rule.account {
+                Some(a) => vec![Address::from_str(a)?],
+                None => conn.subnet().accounts(),
+            };
+            for account in accounts {
+                let balance = conn.manager().wallet_balance(&account).await?;
+                samples.push(Sample {
+                    subnet: Some(id.clone()),
+                    target: format!("{account:} in {id:}"),
+                    value: balance.to_string().parse()?,
+                });
+            }
+        }
+        Ok(samples)
+    }
+}
+
+#[async_trait]
+impl IntoSubsystem<anyhow::Error> for AlertsSubsystem {
+    async fn run(mut self, subsys: SubsystemHandle) -> Result<()> {
+        loop {
+            tokio::select! {
+                _ = sleep(EVALUATION_INTERVAL) => self.evaluate().await,
+                _ = subsys.on_shutdown_requested() => {
+                    tracing::info!("Shutting down alerts subsystem");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Records the `value` of the metric of `rule` for `target` in the alerts `firing`, and returns
+/// the notification to send if the alert starts firing or is resolved.
+fn update(
+    firing: &mut BTreeSet<String>,
+    rule: &AlertRule,
+    target: &str,
+    value: f64,
+) -> Option<NotificationKind> {
+    let key = format!("{}/{target:}", rule.name);
+    match (rule.breached(value), firing.contains(&key)) {
+        (true, false) => {
+            firing.insert(key);
+            Some(NotificationKind::AlertFiring)
+        }
+        (false, true) => {
+            firing.remove(&key);
+            Some(NotificationKind::AlertResolved)
+        }
+        _ => None,
+    }
+}
+
+fn alert_message(kind: NotificationKind, rule: &AlertRule, sample: &Sample) -> String {
+    match kind {
+        NotificationKind::AlertFiring => format!(
+            "alert {}: {} of {} is {:.2}, {}",
+            rule.name,
+            rule.metric,
+            sample.target,
+            sample.value,
+            rule.thresholds()
+        ),
+        _ => format!(
+            "alert {} resolved: {} of {} is back to {:.2}",
+            rule.name, rule.metric, sample.target, sample.value
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::alerts::update;
+    use crate::config::{AlertMetric, AlertRule};
+    use crate::notify::NotificationKind;
+
+    #[test]
+    fn test_alert_transitions() {
+        let rule = AlertRule {
+            name: String::from("low-balance"),
+            metric: AlertMetric::Balance,
+            subnet: None,
+            account: None,
+            above: None,
+            below: Some(10.0),
+        };
+        let mut firing = BTreeSet::new();
+
+        assert_eq!(update(&mut firing, &rule, "t1", 20.0), None);
+        assert_eq!(
+            update(&mut firing, &rule, "t1", 5.0),
+            Some(NotificationKind::AlertFiring)
+        );
+        // notified once while it keeps firing
+        assert_eq!(update(&mut firing, &rule, "t1", 4.0), None);
+        // the targets fire independently
+        assert_eq!(
+            update(&mut firing, &rule, "t2", 1.0),
+            Some(NotificationKind::AlertFiring)
+        );
+        assert_eq!(
+            update(&mut firing, &rule, "t1", 10.0),
+            Some(NotificationKind::AlertResolved)
+        );
+        assert_eq!(update(&mut firing, &rule, "t1", 11.0), None);
+        assert!(firing.contains("low-balance/t2"));
+
+        // a rule without threshold never fires
+        let rule = AlertRule {
+            below: None,
+            ..rule
+        };
+        assert_eq!(update(&mut firing, &rule, "t3", -1.0), None);
+    }
+}
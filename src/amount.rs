@@ -0,0 +1,142 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Token amounts as returned by the json rpc api of the agent, and validation of the amounts
+//! passed to it.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+
+/// The unit of the human readable amounts.
+pub const FIL_UNIT: &str = "FIL";
+
+/// We only support up to 9 decimal digits for transaction
+const FIL_AMOUNT_NANO_DIGITS: u32 = 9;
+
+/// The maximum supply of FIL, no amount can exceed it.
+pub const MAX_SUPPLY_FIL: u64 = 2_000_000_000;
+
+/// The smallest non zero amount accepted, in nanoFIL. Smaller amounts would be truncated to
+/// zero when converted.
+pub const DUST_THRESHOLD_NANO: u64 = 1;
+
+/// Parses the amount in whole FIL of the `field` of a request, e.g. `amount` or `collateral`.
+///
+/// Rejects the amounts that cannot be sent to the chain as is, i.e. that are negative, not a
+/// number, infinite, above the maximum supply or non zero but below the dust threshold, with an
+/// error naming the field, instead of letting the float to integer conversion saturate or
+/// truncate them silently.
+pub fn parse_fil(field: &str, f: f64) -> anyhow::Result<TokenAmount> {
+    if !f.is_finite() {
+        return Err(anyhow!("invalid {field:}: {f:} is not a finite number"));
+    }
+    if f < 0.0 {
+        return Err(anyhow!("invalid {field:}: {f:} {FIL_UNIT} is negative"));
+    }
+    if f > MAX_SUPPLY_FIL as f64 {
+        return Err(anyhow!(
+            "invalid {field:}: {f:} {FIL_UNIT} exceeds the maximum supply of {MAX_SUPPLY_FIL:} {FIL_UNIT}"
+        ));
+    }
+
+    // no rounding, just the integer part
+    let nano = f64::trunc(f * (10u64.pow(FIL_AMOUNT_NANO_DIGITS) as f64));
+    if f > 0.0 && nano < DUST_THRESHOLD_NANO as f64 {
+        return Err(anyhow!(
+            "invalid {field:}: {f:} {FIL_UNIT} is below the dust threshold of {} {FIL_UNIT}",
+            TokenAmount::from_nano(DUST_THRESHOLD_NANO)
+        ));
+    }
+    Ok(TokenAmount::from_nano(nano as u128))
+}
+
+/// A token amount, both exact in attoFIL, to do maths with, and in FIL, to show to users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Amount {
+    /// The amount in attoFIL, as a string not to lose precision.
+    pub atto: String,
+    /// The amount in `unit` as a decimal, e.g. `1.5`.
+    pub fil: String,
+    pub unit: String,
+}
+
+impl From<&TokenAmount> for Amount {
+    fn from(t: &TokenAmount) -> Self {
+        Self {
+            atto: t.atto().to_string(),
+            fil: t.to_string(),
+            unit: FIL_UNIT.to_string(),
+        }
+    }
+}
+
+impl From<TokenAmount> for Amount {
+    fn from(t: TokenAmount) -> Self {
+        Self::from(&t)
+    }
+}
+
+impl TryFrom<&Amount> for TokenAmount {
+    type Error = anyhow::Error;
+
+    fn try_from(a: &Amount) -> Result<Self, Self::Error> {
+        Ok(TokenAmount::from_atto(BigInt::from_str(&a.atto)?))
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.fil, self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::econ::TokenAmount;
+
+    use crate::amount::{parse_fil, Amount};
+
+    #[test]
+    fn test_amount() {
+        let t = TokenAmount::from_nano(1_500_000_000u64);
+        let amount = Amount::from(&t);
+        assert_eq!(amount.atto, "1500000000000000000");
+        assert_eq!(amount.fil, "1.5");
+        assert_eq!(amount.to_string(), "1.5 FIL");
+        assert_eq!(TokenAmount::try_from(&amount).unwrap(), t);
+
+        let json = serde_json::to_value(&amount).unwrap();
+        assert_eq!(json["unit"], "FIL");
+        assert_eq!(serde_json::from_value::<Amount>(json).unwrap(), amount);
+    }
+
+    #[test]
+    fn test_parse_fil() {
+        assert_eq!(
+            parse_fil("amount", 1000000.1f64).unwrap(),
+            TokenAmount::from_nano(1000000100000000u128)
+        );
+        assert_eq!(parse_fil("amount", 0.0).unwrap(), TokenAmount::from_nano(0));
+        assert_eq!(
+            parse_fil("amount", 2_000_000_000.0).unwrap(),
+            TokenAmount::from_whole(2_000_000_000)
+        );
+
+        for (f, reason) in [
+            (-1.0, "negative"),
+            (f64::NAN, "not a finite number"),
+            (f64::INFINITY, "not a finite number"),
+            (2_000_000_000.1, "maximum supply"),
+            (1e30, "maximum supply"),
+            (1e-10, "dust threshold"),
+        ] {
+            let e = parse_fil("collateral", f).unwrap_err().to_string();
+            assert!(e.starts_with("invalid collateral"), "{e:}");
+            assert!(e.contains(reason), "{e:}");
+        }
+    }
+}
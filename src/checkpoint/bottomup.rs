@@ -2,12 +2,20 @@
 // SPDX-License-Identifier: MIT
 //! Bottom up checkpoint manager
 
-use crate::checkpoint::{CheckpointManager, CheckpointMetadata, CheckpointQuery};
+use crate::checkpoint::{
+    content_cid, publish_relayed, CheckpointManager, CheckpointMetadata, CheckpointQuery,
+    IdleTracker, PendingSubmissions, PendingVotes, PollSchedule, SubmittedCheckpoint,
+};
+use crate::config::subnet::CheckpointSigning;
 use crate::config::Subnet;
+use crate::manager::offline::OfflineCall;
+use crate::manager::signer::{checkpoint_payload, sign_checkpoint, CheckpointSigner};
+use crate::state::AgentState;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use base64::Engine;
-use fvm_shared::address::Address;
+use cid::Cid;
+use fvm_shared::address::{Address, Protocol};
 use fvm_shared::clock::ChainEpoch;
 use ipc_gateway::checkpoint::BatchCrossMsgs;
 use ipc_sdk::subnet_id::SubnetID;
@@ -15,13 +23,14 @@ use num_traits::ToPrimitive;
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
 /// Native bottom up checkpoint struct independent of chain specific implementations.
 /// The goal of this struct is to have a common checkpoint data structure that can be
 /// eventually converted into their runtime-specific representations.
 /// We need this type because some fields take different types in different runtime implementations,
 /// such as `prev_check` is a cid in fvm but bytes in evm.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NativeBottomUpCheckpoint {
     pub source: SubnetID,
     pub proof: Option<Vec<u8>>,
@@ -39,6 +48,12 @@ pub struct NativeChildCheck {
     pub checks: Vec<Vec<u8>>,
 }
 
+/// Returns the epoch of the checkpoint window that is still open at `current_epoch`, i.e. the
+/// epoch of the next bottom-up checkpoint to be cut given the checkpoint `period`.
+pub fn next_checkpoint_epoch(current_epoch: ChainEpoch, period: ChainEpoch) -> ChainEpoch {
+    (current_epoch / period + 1) * period
+}
+
 /// The trait that handles the bottom up checkpoint submission data preparation and actual submission.
 #[async_trait]
 pub trait BottomUpHandler: Send + Sync + CheckpointQuery<NativeBottomUpCheckpoint> {
@@ -58,13 +73,15 @@ pub trait BottomUpHandler: Send + Sync + CheckpointQuery<NativeBottomUpCheckpoin
         &self,
         validator: &Address,
         checkpoint: NativeBottomUpCheckpoint,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<SubmittedCheckpoint>;
 }
 
 pub struct BottomUpManager<P, C> {
     metadata: CheckpointMetadata,
     parent_handler: P,
     child_handler: C,
+    /// Signs the checkpoints when the child subnet requires it, see [`CheckpointSigning`].
+    signer: Option<Arc<dyn CheckpointSigner>>,
 }
 
 impl<P: BottomUpHandler, C: BottomUpHandler> BottomUpManager<P, C> {
@@ -73,7 +90,17 @@ impl<P: BottomUpHandler, C: BottomUpHandler> BottomUpManager<P, C> {
         child: Subnet,
         parent_handler: P,
         child_handler: C,
+        state: Arc<AgentState>,
     ) -> Result<Self> {
+        // the current epoch of the manager is the one of the child
+        let schedule = PollSchedule::new(child.block_time())
+            .with_initial_block_time(
+                state
+                    .epoch_calibrations
+                    .get(&child.id)
+                    .map(|c| c.block_time),
+            )
+            .with_poll_interval(child.checkpoint_poll_interval());
         let period = parent_handler
             .checkpoint_period(&child.id)
             .await
@@ -83,11 +110,94 @@ impl<P: BottomUpHandler, C: BottomUpHandler> BottomUpManager<P, C> {
                 parent,
                 child,
                 period,
+                pending: Default::default(),
+                idle: Default::default(),
+                schedule,
+                state,
             },
             parent_handler,
             child_handler,
+            signer: None,
         })
     }
+
+    pub fn with_signer(mut self, signer: Arc<dyn CheckpointSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Returns the signature of `checkpoint` submitted by `validator`, serialized for its `sig`
+    /// field, if the child subnet requires one.
+    async fn sign(
+        &self,
+        checkpoint: &NativeBottomUpCheckpoint,
+        validator: &Address,
+    ) -> Result<Option<Vec<u8>>> {
+        let signers = match self.metadata.child.checkpoint_signing {
+            CheckpointSigning::None => return Ok(None),
+            CheckpointSigning::Validator => vec![*validator],
+            CheckpointSigning::BlsAggregate => {
+                if validator.protocol() != Protocol::BLS {
+                    return Err(anyhow!(
+                        "validator {validator:} cannot aggregate its checkpoint signature, not a BLS address"
+                    ));
+                }
+                // all the validators of the agent sign, in the order of the validator set
+                let managed = self.metadata.parent.accounts();
+                self.validators()
+                    .await?
+                    .into_iter()
+                    .filter(|v| v.protocol() == Protocol::BLS && managed.contains(v))
+                    .collect()
+            }
+        };
+
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("no checkpoint signer in manager {self:}"))?;
+        let signature =
+            sign_checkpoint(signer.as_ref(), &signers, &checkpoint_payload(checkpoint)?)?;
+        tracing::debug!(
+            "bottom up checkpoint at epoch {} signed by {:?}",
+            checkpoint.epoch,
+            signature.signers
+        );
+        Ok(Some(signature.to_bytes()?))
+    }
+
+    /// Assembles the checkpoint `validator` votes at `epoch`: the template cut by the child
+    /// gateway, with its proof, the hash of the previous checkpoint and the signature the child
+    /// subnet requires.
+    /// Builds the checkpoint at `epoch` from the template of the child, before it is signed.
+    async fn unsigned_checkpoint(&self, epoch: ChainEpoch) -> Result<NativeBottomUpCheckpoint> {
+        let mut template = self.child_handler.checkpoint_template(epoch).await?;
+        tracing::debug!("bottom up template: {template:?}");
+
+        self.child_handler.populate_proof(&mut template).await?;
+        tracing::debug!("bottom up checkpoint proof: {:?}", template.proof);
+
+        let prev_epoch = epoch - self.metadata.period;
+        self.parent_handler
+            .populate_prev_hash(&mut template, &self.metadata.child.id, prev_epoch)
+            .await?;
+        tracing::debug!("bottom up checkpoint prev check: {:?}", template.prev_check);
+
+        Ok(template)
+    }
+
+    async fn checkpoint(
+        &self,
+        epoch: ChainEpoch,
+        validator: &Address,
+    ) -> Result<NativeBottomUpCheckpoint> {
+        let mut template = self.unsigned_checkpoint(epoch).await?;
+        if let Some(sig) = self.sign(&template, validator).await? {
+            template.sig = sig;
+        }
+
+        Ok(template)
+    }
 }
 
 impl<P: BottomUpHandler, C: BottomUpHandler> Display for BottomUpManager<P, C> {
@@ -119,6 +229,10 @@ impl<P: BottomUpHandler, C: BottomUpHandler> CheckpointManager for BottomUpManag
         &self.metadata.child
     }
 
+    fn epoch_subnet(&self) -> &Subnet {
+        &self.metadata.child
+    }
+
     /// The checkpoint period that the current manager is submitting upon
     fn checkpoint_period(&self) -> ChainEpoch {
         self.metadata.period
@@ -145,27 +259,50 @@ impl<P: BottomUpHandler, C: BottomUpHandler> CheckpointManager for BottomUpManag
 
     /// Submit the checkpoint based on the current epoch to submit and the previous epoch that was
     /// already submitted.
-    async fn submit_checkpoint(&self, epoch: ChainEpoch, validator: &Address) -> Result<()> {
-        let mut template = self.child_handler.checkpoint_template(epoch).await?;
-        log::debug!("bottom up template: {template:?}");
-
-        self.child_handler.populate_proof(&mut template).await?;
-        log::debug!("bottom up checkpoint proof: {:?}", template.proof);
-
-        let prev_epoch = epoch - self.metadata.period;
-        self.parent_handler
-            .populate_prev_hash(&mut template, &self.metadata.child.id, prev_epoch)
-            .await?;
-        log::debug!("bottom up checkpoint prev check: {:?}", template.prev_check);
-
-        log::info!("bottom up checkpoint to submit: {template:?}");
+    async fn submit_checkpoint(
+        &self,
+        epoch: ChainEpoch,
+        validator: &Address,
+    ) -> Result<SubmittedCheckpoint> {
+        let template = self.checkpoint(epoch, validator).await?;
+        tracing::info!("bottom up checkpoint to submit: {template:?}");
 
-        self.parent_handler
+        let relayed = template.cross_msgs.cross_msgs.clone().unwrap_or_default();
+        let submitted = self
+            .parent_handler
             .submit(validator, template)
             .await
-            .map_err(|e| anyhow!("cannot submit bottom up checkpoint due to: {e:}"))?;
+            .map_err(|e| e.context("cannot submit bottom up checkpoint"))?;
+        publish_relayed(self, epoch, &relayed);
+
+        Ok(submitted)
+    }
 
-        Ok(())
+    /// The vote of `validator` for the checkpoint at `epoch`, to be signed offline and submitted
+    /// to the subnet actor in the parent.
+    async fn offline_vote(&self, epoch: ChainEpoch, validator: &Address) -> Result<OfflineCall> {
+        Ok(OfflineCall::CheckpointVote {
+            checkpoint: self.checkpoint(epoch, validator).await?,
+        })
+    }
+
+    /// The cid of the checkpoint at `epoch` without its signature, the source, proof, previous
+    /// checkpoint, children and cross messages being all covered.
+    async fn checkpoint_cid(&self, epoch: ChainEpoch) -> Result<Cid> {
+        let checkpoint = self.unsigned_checkpoint(epoch).await?;
+        let children = checkpoint
+            .children
+            .iter()
+            .map(|c| (&c.source, &c.checks))
+            .collect::<Vec<_>>();
+        content_cid(&(
+            &checkpoint.source,
+            &checkpoint.proof,
+            checkpoint.epoch,
+            &checkpoint.prev_check,
+            children,
+            &checkpoint.cross_msgs,
+        ))
     }
 
     /// Checks if the validator has already submitted in the epoch
@@ -183,6 +320,32 @@ impl<P: BottomUpHandler, C: BottomUpHandler> CheckpointManager for BottomUpManag
     async fn presubmission_check(&self) -> Result<bool> {
         Ok(true)
     }
+
+    /// Get the checkpoint votes of the validator pending in the mempool of the parent
+    async fn pending_votes(&self, validator: &Address) -> Result<PendingVotes> {
+        self.parent_handler
+            .pending_votes(&self.metadata.child.id, validator)
+            .await
+    }
+
+    /// The checkpoint votes of the managed validators found pending in the parent
+    fn pending_submissions(&self) -> &PendingSubmissions {
+        &self.metadata.pending
+    }
+
+    /// The activity of the child subnet, to slow down its polling when idle
+    fn idle_tracker(&self) -> &IdleTracker {
+        &self.metadata.idle
+    }
+
+    /// When to poll the manager next, following the block time of the child subnet
+    fn poll_schedule(&self) -> &PollSchedule {
+        &self.metadata.schedule
+    }
+
+    fn state(&self) -> &AgentState {
+        &self.metadata.state
+    }
 }
 
 // Serialization related
@@ -290,7 +453,7 @@ impl<'a> Serialize for SerializeToJson<&'a BatchCrossMsgs> {
 
 #[cfg(test)]
 mod tests {
-    use crate::checkpoint::{NativeBottomUpCheckpoint, NativeChildCheck};
+    use crate::checkpoint::{next_checkpoint_epoch, NativeBottomUpCheckpoint, NativeChildCheck};
     use ipc_gateway::checkpoint::BatchCrossMsgs;
     use ipc_sdk::subnet_id::SubnetID;
 
@@ -312,4 +475,12 @@ mod tests {
         let v = serde_json::to_string(&cp);
         assert!(v.is_ok());
     }
+
+    #[test]
+    fn test_next_checkpoint_epoch() {
+        assert_eq!(next_checkpoint_epoch(0, 10), 10);
+        assert_eq!(next_checkpoint_epoch(9, 10), 10);
+        assert_eq!(next_checkpoint_epoch(10, 10), 20);
+        assert_eq!(next_checkpoint_epoch(25, 10), 30);
+    }
 }
@@ -0,0 +1,118 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Catch-up of the top-down checkpoints missed while the agent was offline.
+//!
+//! A poll only submits the checkpoints of the next few periods after the last executed one, which
+//! keeps up with the parent but takes a poll per period to recover from a long downtime, during
+//! which the top-down messages are not applied in the child. When the last executed checkpoint is
+//! too far behind the current epoch of the parent, the manager catches up: every poll submits a
+//! larger batch of the missing checkpoints, several at a time, until the gap is closed.
+
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use fvm_shared::clock::ChainEpoch;
+
+/// The gap, in checkpoint periods, between the last executed checkpoint and the current epoch
+/// beyond which a manager catches up.
+const CATCH_UP_GAP_PERIODS: ChainEpoch = 10;
+/// The number of checkpoints submitted in a poll while catching up.
+pub(crate) const CATCH_UP_BATCH_PERIODS: ChainEpoch = 20;
+/// The number of checkpoints being submitted at the same time while catching up. They are
+/// started in the order of their epochs.
+pub(crate) const CATCH_UP_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct CatchUpState {
+    /// The last executed epoch when the catch-up started.
+    from: ChainEpoch,
+    started: Instant,
+}
+
+/// Tracks whether a manager is catching up, and how far it got.
+#[derive(Default)]
+pub struct CatchUp {
+    state: Mutex<Option<CatchUpState>>,
+}
+
+impl CatchUp {
+    /// Whether `manager` is to catch up with `current_epoch` given its `last_executed` epoch,
+    /// logging when the catch-up starts and ends, and its progress in between.
+    pub fn observe(
+        &self,
+        manager: impl Display,
+        last_executed: ChainEpoch,
+        current_epoch: ChainEpoch,
+        period: ChainEpoch,
+    ) -> bool {
+        let behind = is_behind(last_executed, current_epoch, period);
+        let mut state = self.state.lock().unwrap();
+        match (*state, behind) {
+            (None, true) => {
+                tracing::info!(
+                    "manager {manager:} is {} checkpoints behind epoch {current_epoch:}, catching up",
+                    (current_epoch - last_executed) / period
+                );
+                *state = Some(CatchUpState {
+                    from: last_executed,
+                    started: Instant::now(),
+                });
+            }
+            (Some(s), true) => tracing::info!(
+                "manager {manager:} catching up: last executed epoch {last_executed:} of {current_epoch:}, {:.1}% done in {}s",
+                progress(s.from, last_executed, current_epoch),
+                s.started.elapsed().as_secs()
+            ),
+            (Some(s), false) => {
+                tracing::info!(
+                    "manager {manager:} caught up with epoch {current_epoch:} in {}s",
+                    s.started.elapsed().as_secs()
+                );
+                *state = None;
+            }
+            (None, false) => {}
+        }
+        behind
+    }
+}
+
+/// Whether the checkpoint executed at `last_executed` is too far behind `current_epoch`.
+fn is_behind(last_executed: ChainEpoch, current_epoch: ChainEpoch, period: ChainEpoch) -> bool {
+    period > 0 && current_epoch - last_executed > CATCH_UP_GAP_PERIODS * period
+}
+
+/// The share of the gap between `from` and `current_epoch` closed at `last_executed`, in percent.
+fn progress(from: ChainEpoch, last_executed: ChainEpoch, current_epoch: ChainEpoch) -> f64 {
+    if current_epoch <= from {
+        return 100.0;
+    }
+    (last_executed - from) as f64 * 100.0 / (current_epoch - from) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checkpoint::catchup::{is_behind, progress, CatchUp, CATCH_UP_GAP_PERIODS};
+
+    #[test]
+    fn test_catch_up() {
+        let period = 10;
+        let gap = CATCH_UP_GAP_PERIODS * period;
+        assert!(!is_behind(100, 100 + gap, period));
+        assert!(is_behind(100, 101 + gap, period));
+        assert!(!is_behind(100, 1000, 0));
+
+        assert_eq!(progress(100, 100, 200), 0.0);
+        assert_eq!(progress(100, 150, 200), 50.0);
+        assert_eq!(progress(100, 100, 100), 100.0);
+
+        let catch_up = CatchUp::default();
+        assert!(catch_up.observe("test", 0, 1000, period));
+        assert!(catch_up.state.lock().unwrap().is_some());
+        assert!(catch_up.observe("test", 500, 1010, period));
+        // the start of the catch-up is kept for the progress
+        assert_eq!(catch_up.state.lock().unwrap().unwrap().from, 0);
+        assert!(!catch_up.observe("test", 1000, 1020, period));
+        assert!(catch_up.state.lock().unwrap().is_none());
+    }
+}
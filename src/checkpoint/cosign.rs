@@ -0,0 +1,336 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Co-signing of the checkpoint votes with a second agent.
+//!
+//! Validators that need a 2-of-2 control over their votes can run two agents. The agent holding
+//! the key of the validator asks the other one to co-sign every vote before submitting it, and
+//! the other agent only co-signs the votes it agrees with after checking them against its own
+//! node, rebuilding the checkpoint voted to compare its cid. Requests and responses are signed with
+//! a key of the evm keystore of each agent, and only accepted from the key configured for the
+//! peer. When the json rpc api of the peer requires auth, the requests also carry the token
+//! configured for it.
+
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use ethers::signers::LocalWallet;
+use ethers::types::Signature;
+use ethers::utils::hash_message;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_identity::PersistentKeyStore;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::policy::VoteDecision;
+use crate::checkpoint::{checkpoint_direction, CheckpointDirection, CheckpointManager};
+use crate::config::json_rpc_methods;
+use crate::config::subnet::CoSigningConfig;
+use crate::jsonrpc::tap::DebugTap;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+
+/// The maximum age, in seconds, of the co-signing requests accepted, so that captured requests
+/// cannot be replayed later on.
+pub const MAX_REQUEST_AGE_SECS: u64 = 5 * 60;
+
+/// A request to co-sign the vote of `validator` on a checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoSignRequest {
+    /// The child subnet of the checkpoint.
+    pub subnet: String,
+    pub direction: CheckpointDirection,
+    pub validator: String,
+    pub epoch: ChainEpoch,
+    /// The cid of the checkpoint voted, see [`CheckpointManager::checkpoint_cid`].
+    pub checkpoint: String,
+    /// The unix timestamp, in seconds, the request was signed at.
+    pub timestamp: u64,
+    /// The signature of the requesting agent, in hex.
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoSignResponse {
+    pub approved: bool,
+    /// Why the vote was not approved.
+    pub reason: Option<String>,
+    /// The signature of the co-signing agent over the response and the request, in hex.
+    pub signature: String,
+}
+
+impl CoSignRequest {
+    fn payload(&self) -> String {
+        format!(
+            "ipc-cosign-request:{}:{}:{}:{}:{}:{}",
+            self.subnet,
+            self.direction,
+            self.validator,
+            self.epoch,
+            self.checkpoint,
+            self.timestamp
+        )
+    }
+
+    /// Checks that the request is recent and signed by the peer of `config`.
+    pub fn verify(&self, config: &CoSigningConfig) -> Result<()> {
+        if now().saturating_sub(self.timestamp) > MAX_REQUEST_AGE_SECS {
+            return Err(anyhow!("co-signing request expired"));
+        }
+        verify(&self.payload(), &self.signature, config.peer_address)
+    }
+}
+
+impl CoSignResponse {
+    /// Creates the response to `request`, signed with the key of `config`.
+    pub fn new(
+        request: &CoSignRequest,
+        approved: bool,
+        reason: Option<String>,
+        config: &CoSigningConfig,
+        keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    ) -> Result<Self> {
+        let payload = response_payload(request, approved);
+        Ok(CoSignResponse {
+            approved,
+            reason,
+            signature: sign(&payload, &config.signer, keystore)?,
+        })
+    }
+}
+
+fn response_payload(request: &CoSignRequest, approved: bool) -> String {
+    format!("ipc-cosign-response:{approved:}:{}", request.payload())
+}
+
+/// Returns the cid of the cbor encoding of the content of a checkpoint.
+pub(crate) fn content_cid<T: Serialize>(content: &T) -> Result<Cid> {
+    let hash = Code::Blake2b256.digest(&fvm_ipld_encoding::to_vec(content)?);
+    Ok(Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, hash))
+}
+
+/// Asks the peer of `config` to co-sign the vote of `validator` on the checkpoint of `manager` at
+/// `epoch`.
+pub async fn request_co_signature(
+    config: &CoSigningConfig,
+    keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    manager: &dyn CheckpointManager,
+    validator: &Address,
+    epoch: ChainEpoch,
+) -> Result<VoteDecision> {
+    let mut request = CoSignRequest {
+        subnet: manager.child_subnet().id.to_string(),
+        direction: checkpoint_direction(manager),
+        validator: validator.to_string(),
+        epoch,
+        checkpoint: manager.checkpoint_cid(epoch).await?.to_string(),
+        timestamp: now(),
+        signature: String::new(),
+    };
+    request.signature = sign(&request.payload(), &config.signer, keystore)?;
+
+    let response = send_request(config, &request, &manager.state().debug_tap).await?;
+    if response.approved {
+        Ok(VoteDecision::Vote)
+    } else {
+        Ok(VoteDecision::Abstain(format!(
+            "not co-signed by the peer agent: {}",
+            response.reason.as_deref().unwrap_or("no reason given")
+        )))
+    }
+}
+
+/// Sends `request` to the peer of `config`, with its token if any, and checks that the response is
+/// signed by the peer. The request is recorded in `tap`.
+async fn send_request(
+    config: &CoSigningConfig,
+    request: &CoSignRequest,
+    tap: &Arc<DebugTap>,
+) -> Result<CoSignResponse> {
+    let client = JsonRpcClientImpl::new(config.peer_url.clone(), config.token.as_deref())
+        .with_tap(tap.clone());
+    let response = client
+        .request::<CoSignResponse>(
+            json_rpc_methods::CO_SIGN_VOTE,
+            serde_json::to_value(request)?,
+        )
+        .await?;
+    verify(
+        &response_payload(request, response.approved),
+        &response.signature,
+        config.peer_address,
+    )
+    .map_err(|e| anyhow!("invalid co-signing response: {e:}"))?;
+    Ok(response)
+}
+
+/// Signs `payload` with the key of `signer` in the evm keystore of the agent. The messages
+/// exchanged with peer agents are all signed this way.
+pub(crate) fn sign(
+    payload: &str,
+    signer: &ethers::types::Address,
+    keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+) -> Result<String> {
+    let keystore = keystore.read().unwrap();
+    let key_info = keystore.get(signer)?.ok_or_else(|| {
+        anyhow!("signing address {signer:?} does not have private key in key store")
+    })?;
+    let wallet = LocalWallet::from_bytes(key_info.private_key())?;
+    Ok(wallet.sign_hash(hash_message(payload))?.to_string())
+}
+
+/// Checks that `signature` is the signature of `payload` by the key of the peer agent, `peer`.
+pub(crate) fn verify(payload: &str, signature: &str, peer: ethers::types::Address) -> Result<()> {
+    Signature::from_str(signature)?
+        .verify(payload, peer)
+        .map_err(|_| anyhow!("not signed by the peer agent"))
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use ethers::core::rand::thread_rng;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::hash_message;
+    use serde_json::{json, Value};
+    use warp::http::StatusCode;
+    use warp::Filter;
+
+    use crate::checkpoint::cosign::{
+        now, response_payload, send_request, verify, CoSignRequest, CoSignResponse,
+        MAX_REQUEST_AGE_SECS,
+    };
+    use crate::checkpoint::CheckpointDirection;
+    use crate::config::subnet::CoSigningConfig;
+    use crate::config::{AuthConfig, AuthToken, Permission};
+    use crate::jsonrpc::tap::DebugTap;
+    use crate::server::auth::authorize;
+
+    fn request() -> CoSignRequest {
+        CoSignRequest {
+            subnet: String::from("/r123/f0100"),
+            direction: CheckpointDirection::BottomUp,
+            validator: String::from("f0100"),
+            epoch: 10,
+            checkpoint: String::from(
+                "bafy2bzacecnamqgqmifpluoeldx7zzglxcljo6oja4vrmtj7432rphldpdmm2",
+            ),
+            timestamp: now(),
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_verify_request() {
+        let peer = LocalWallet::new(&mut thread_rng());
+        let config = CoSigningConfig {
+            peer_url: "http://127.0.0.1:3030/json_rpc".parse().unwrap(),
+            peer_address: peer.address(),
+            signer: LocalWallet::new(&mut thread_rng()).address(),
+            token: None,
+        };
+
+        let mut request = request();
+        request.signature = peer
+            .sign_hash(hash_message(request.payload()))
+            .unwrap()
+            .to_string();
+        request.verify(&config).unwrap();
+
+        // a request for another vote does not match the signature
+        let mut other = request.clone();
+        other.epoch = 20;
+        assert!(other.verify(&config).is_err());
+        let mut other = request.clone();
+        other.checkpoint =
+            String::from("bafy2bzaceaxnigqyplwmgyw4dwsfo6iofwd5xwfbawsulncmkiihgrb3v6eea");
+        assert!(other.verify(&config).is_err());
+
+        let mut expired = request.clone();
+        expired.timestamp -= MAX_REQUEST_AGE_SECS + 1;
+        assert!(expired.verify(&config).is_err());
+
+        let signature = LocalWallet::new(&mut thread_rng())
+            .sign_hash(hash_message(request.payload()))
+            .unwrap()
+            .to_string();
+        assert!(verify(&request.payload(), &signature, config.peer_address).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_request_with_auth() {
+        let peer = LocalWallet::new(&mut thread_rng());
+        let auth = AuthConfig {
+            tokens: vec![AuthToken {
+                token: String::from("peer"),
+                permission: Permission::Write,
+            }],
+            methods: HashMap::new(),
+            anonymous_read: true,
+        };
+
+        // the json rpc api of the peer, approving every vote once authorized
+        let signer = peer.clone();
+        let api = warp::post()
+            .and(warp::header::optional::<String>("authorization"))
+            .and(warp::body::json::<Value>())
+            .map(move |authorization: Option<String>, body: Value| {
+                let method = body["method"].as_str().unwrap_or_default();
+                if authorize(&auth, authorization.as_deref(), method).is_err() {
+                    return warp::reply::with_status(
+                        warp::reply::json(&"UNAUTHORIZED"),
+                        StatusCode::UNAUTHORIZED,
+                    );
+                }
+                let request =
+                    serde_json::from_value::<CoSignRequest>(body["params"].clone()).unwrap();
+                let response = CoSignResponse {
+                    approved: true,
+                    reason: None,
+                    signature: signer
+                        .sign_hash(hash_message(response_payload(&request, true)))
+                        .unwrap()
+                        .to_string(),
+                };
+                warp::reply::with_status(
+                    warp::reply::json(
+                        &json!({"jsonrpc": "2.0", "id": body["id"], "result": response}),
+                    ),
+                    StatusCode::OK,
+                )
+            });
+        let (addr, server) = warp::serve(api).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let mut config = CoSigningConfig {
+            peer_url: format!("http://{addr:}/json_rpc").parse().unwrap(),
+            peer_address: peer.address(),
+            signer: LocalWallet::new(&mut thread_rng()).address(),
+            token: Some(String::from("peer")),
+        };
+        let tap = Arc::new(DebugTap::new());
+        assert!(
+            send_request(&config, &request(), &tap)
+                .await
+                .unwrap()
+                .approved
+        );
+
+        // co-signing needs a token with the write permission
+        config.token = None;
+        assert!(send_request(&config, &request(), &tap).await.is_err());
+        config.token = Some(String::from("unknown"));
+        assert!(send_request(&config, &request(), &tap).await.is_err());
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Comparison of the bottom-up checkpoint template the agent builds for an epoch with the
+//! checkpoint committed for it in the parent.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::cross::CrossMsg;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::NativeBottomUpCheckpoint;
+
+/// A cross message of a checkpoint, with the fields that identify it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffCrossMsg {
+    pub from: String,
+    pub to: String,
+    pub method: u64,
+    /// In whole FIL
+    pub value: String,
+    pub nonce: u64,
+}
+
+impl TryFrom<&CrossMsg> for DiffCrossMsg {
+    type Error = anyhow::Error;
+
+    fn try_from(c: &CrossMsg) -> Result<Self> {
+        Ok(DiffCrossMsg {
+            from: c.msg.from.to_string()?,
+            to: c.msg.to.to_string()?,
+            method: c.msg.method,
+            value: c.msg.value.to_string(),
+            nonce: c.msg.nonce,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointDiff {
+    pub epoch: ChainEpoch,
+    /// Whether a checkpoint has been committed for the epoch.
+    pub committed: bool,
+    /// Whether the committed checkpoint has the same cross messages, fee and child checks as the
+    /// template.
+    pub matches: bool,
+    /// The cross messages in the template that are not in the committed checkpoint.
+    pub only_in_template: Vec<DiffCrossMsg>,
+    /// The cross messages in the committed checkpoint that are not in the template.
+    pub only_in_committed: Vec<DiffCrossMsg>,
+    /// In whole FIL
+    pub template_fee: String,
+    /// In whole FIL, not set if no checkpoint has been committed.
+    pub committed_fee: Option<String>,
+    /// The child subnets whose checks differ between the template and the committed checkpoint.
+    pub children: Vec<String>,
+}
+
+impl CheckpointDiff {
+    /// Compares `template` with the checkpoint committed for the same epoch, if any.
+    pub fn new(
+        template: &NativeBottomUpCheckpoint,
+        committed: Option<&NativeBottomUpCheckpoint>,
+    ) -> Result<Self> {
+        let template_msgs = cross_msgs(template)?;
+        let committed_msgs = match committed {
+            Some(c) => cross_msgs(c)?,
+            None => vec![],
+        };
+
+        let only_in_template = template_msgs
+            .iter()
+            .filter(|m| !committed_msgs.contains(m))
+            .cloned()
+            .collect::<Vec<_>>();
+        let only_in_committed = committed_msgs
+            .iter()
+            .filter(|m| !template_msgs.contains(m))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let template_fee = template.cross_msgs.fee.to_string();
+        let committed_fee = committed.map(|c| c.cross_msgs.fee.to_string());
+
+        let children = match committed {
+            Some(c) => children_diff(template, c),
+            None => vec![],
+        };
+
+        Ok(CheckpointDiff {
+            epoch: template.epoch,
+            committed: committed.is_some(),
+            matches: committed.is_some()
+                && only_in_template.is_empty()
+                && only_in_committed.is_empty()
+                && committed_fee.as_ref() == Some(&template_fee)
+                && children.is_empty(),
+            only_in_template,
+            only_in_committed,
+            template_fee,
+            committed_fee,
+            children,
+        })
+    }
+}
+
+fn cross_msgs(checkpoint: &NativeBottomUpCheckpoint) -> Result<Vec<DiffCrossMsg>> {
+    checkpoint
+        .cross_msgs
+        .cross_msgs
+        .iter()
+        .flatten()
+        .map(DiffCrossMsg::try_from)
+        .collect()
+}
+
+/// Returns the child subnets whose checks are not the same in both checkpoints.
+fn children_diff(a: &NativeBottomUpCheckpoint, b: &NativeBottomUpCheckpoint) -> Vec<String> {
+    let checks = |c: &NativeBottomUpCheckpoint| {
+        c.children
+            .iter()
+            .map(|child| (child.source.clone(), child.checks.clone()))
+            .collect::<HashMap<SubnetID, Vec<Vec<u8>>>>()
+    };
+    let (a, b) = (checks(a), checks(b));
+
+    let mut differ = a
+        .keys()
+        .chain(b.keys())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .filter(|s| a.get(*s) != b.get(*s))
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    differ.sort();
+    differ
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_gateway::checkpoint::BatchCrossMsgs;
+    use ipc_sdk::address::IPCAddress;
+    use ipc_sdk::cross::{CrossMsg, StorableMsg};
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::checkpoint::diff::CheckpointDiff;
+    use crate::checkpoint::{NativeBottomUpCheckpoint, NativeChildCheck};
+
+    fn cross_msg(subnet: &SubnetID, nonce: u64) -> CrossMsg {
+        let addr = IPCAddress::new(subnet, &Address::new_id(100)).unwrap();
+        CrossMsg {
+            msg: StorableMsg {
+                from: addr.clone(),
+                to: addr,
+                method: 0,
+                params: RawBytes::default(),
+                value: TokenAmount::from_whole(1),
+                nonce,
+            },
+            wrapped: false,
+        }
+    }
+
+    fn checkpoint(
+        subnet: &SubnetID,
+        nonces: &[u64],
+        checks: Vec<Vec<u8>>,
+    ) -> NativeBottomUpCheckpoint {
+        NativeBottomUpCheckpoint {
+            source: subnet.clone(),
+            proof: None,
+            epoch: 10,
+            prev_check: None,
+            children: vec![NativeChildCheck {
+                source: SubnetID::from_str("/r123/f0200").unwrap(),
+                checks,
+            }],
+            cross_msgs: BatchCrossMsgs {
+                cross_msgs: Some(nonces.iter().map(|n| cross_msg(subnet, *n)).collect()),
+                fee: TokenAmount::from_atto(nonces.len()),
+            },
+            sig: vec![],
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_diff() {
+        let subnet = SubnetID::new_root(123);
+        let template = checkpoint(&subnet, &[0, 1], vec![vec![1]]);
+
+        let diff = CheckpointDiff::new(
+            &template,
+            Some(&checkpoint(&subnet, &[0, 1], vec![vec![1]])),
+        )
+        .unwrap();
+        assert!(diff.matches);
+
+        let diff = CheckpointDiff::new(
+            &template,
+            Some(&checkpoint(&subnet, &[1, 2], vec![vec![2]])),
+        )
+        .unwrap();
+        assert!(!diff.matches);
+        assert_eq!(diff.only_in_template.len(), 1);
+        assert_eq!(diff.only_in_template[0].nonce, 0);
+        assert_eq!(diff.only_in_committed[0].nonce, 2);
+        assert_eq!(diff.children.len(), 1);
+
+        let diff = CheckpointDiff::new(&template, None).unwrap();
+        assert!(!diff.committed && !diff.matches);
+        assert_eq!(diff.only_in_template.len(), 2);
+        assert!(diff.committed_fee.is_none());
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Notices of top-down checkpoints between federated agents.
+//!
+//! In split deployments, the agent next to the parent of a subnet notifies the agents next to the
+//! subnet whenever the parent reaches the epoch of a top-down checkpoint, so that these only poll
+//! the remote parent when there is a checkpoint to vote instead of on every block. The notices
+//! are signed with the identity key of the notifying agent, which does not hold any funds, so
+//! that no key transacting in the subnets is exposed to the agents notified, and only accepted
+//! from the identity configured as trusted. A notice only triggers a poll, the checkpoint voted
+//! is still built from the parent, so a notice cannot make an agent vote anything it would not
+//! have voted by polling.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::cosign::{now, verify, MAX_REQUEST_AGE_SECS};
+use crate::checkpoint::{checkpoint_direction, CheckpointDirection, CheckpointManager};
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::server::status::AgentIdentity;
+
+/// The interval the parent is polled at without notices, so that lost notices, e.g. while the
+/// notifying agent restarts, only delay the top-down checkpoints.
+pub const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The notice that the parent of `subnet` reached the epoch of a top-down checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopDownNotice {
+    /// The child subnet of the checkpoint.
+    pub subnet: String,
+    pub epoch: ChainEpoch,
+    /// The unix timestamp, in seconds, the notice was signed at.
+    pub timestamp: u64,
+    /// The signature of the notifying agent, in hex.
+    pub signature: String,
+}
+
+impl TopDownNotice {
+    fn payload(&self) -> String {
+        format!(
+            "ipc-federation-topdown:{}:{}:{}",
+            self.subnet, self.epoch, self.timestamp
+        )
+    }
+
+    /// Checks that the notice is recent and signed by the identity key of `peer`.
+    pub fn verify(&self, peer: ethers::types::Address) -> Result<()> {
+        if now().saturating_sub(self.timestamp) > MAX_REQUEST_AGE_SECS {
+            return Err(anyhow!("top-down notice expired"));
+        }
+        verify(&self.payload(), &self.signature, peer)
+    }
+}
+
+#[derive(Default)]
+struct NoticeState {
+    /// The latest epoch notified.
+    notified: Option<ChainEpoch>,
+    /// The latest epoch notified when the parent was last polled.
+    polled: Option<ChainEpoch>,
+    last_poll: Option<Instant>,
+}
+
+/// The top-down checkpoints notified to this agent, and the polls of the parent they triggered,
+/// along with the ones notified by this agent to its federated agents.
+pub struct TopDownNotices {
+    subnets: Mutex<Option<HashMap<SubnetID, NoticeState>>>,
+    /// The latest epoch notified to the federated agents of each subnet by this agent.
+    sent: Mutex<Option<HashMap<SubnetID, ChainEpoch>>>,
+}
+
+impl TopDownNotices {
+    pub const fn new() -> Self {
+        Self {
+            subnets: Mutex::new(None),
+            sent: Mutex::new(None),
+        }
+    }
+
+    /// Records the notice of a top-down checkpoint of `subnet` at `epoch`. Returns `false` if a
+    /// later one was already notified.
+    pub fn record(&self, subnet: &SubnetID, epoch: ChainEpoch) -> bool {
+        let mut subnets = self.subnets.lock().unwrap();
+        let state = subnets
+            .get_or_insert_with(HashMap::new)
+            .entry(subnet.clone())
+            .or_default();
+        if state.notified.map_or(false, |e| e >= epoch) {
+            return false;
+        }
+        state.notified = Some(epoch);
+        true
+    }
+
+    /// Whether the top-down manager of `subnet` polls the parent at `now`: on its first poll,
+    /// once a checkpoint was notified since its last poll, or `FALLBACK_POLL_INTERVAL` after it.
+    pub fn should_poll(&self, subnet: &SubnetID, now: Instant) -> bool {
+        let mut subnets = self.subnets.lock().unwrap();
+        let state = subnets
+            .get_or_insert_with(HashMap::new)
+            .entry(subnet.clone())
+            .or_default();
+        let poll = match state.last_poll {
+            None => true,
+            Some(at) => {
+                state.notified > state.polled || now.duration_since(at) >= FALLBACK_POLL_INTERVAL
+            }
+        };
+        if poll {
+            state.polled = state.notified;
+            state.last_poll = Some(now);
+        }
+        poll
+    }
+
+    /// Records the notice of a top-down checkpoint of `subnet` at `epoch` sent by this agent.
+    /// Returns `false` if it, or a later one, was already sent.
+    fn mark_sent(&self, subnet: &SubnetID, epoch: ChainEpoch) -> bool {
+        let mut sent = self.sent.lock().unwrap();
+        let last = sent
+            .get_or_insert_with(HashMap::new)
+            .entry(subnet.clone())
+            .or_default();
+        if *last >= epoch {
+            return false;
+        }
+        *last = epoch;
+        true
+    }
+}
+
+impl Default for TopDownNotices {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `manager` polls at `now`. Only the top-down managers of the subnets with a trusted
+/// peer wait for its notices, the others poll on every round.
+pub fn should_poll(manager: &dyn CheckpointManager, now: Instant) -> bool {
+    let trusted = manager
+        .child_subnet()
+        .federation
+        .as_ref()
+        .map_or(false, |f| f.trusted_peer.is_some());
+    if !trusted || checkpoint_direction(manager) != CheckpointDirection::TopDown {
+        return true;
+    }
+    manager
+        .state()
+        .top_down_notices
+        .should_poll(&manager.child_subnet().id, now)
+}
+
+/// Notifies the federated agents of the child subnet of `manager`, if any, once the parent
+/// reaches the epoch of a top-down checkpoint not notified yet. The notices are signed with
+/// `identity`, the identity of this agent if it could be loaded.
+pub async fn notify_peers(
+    manager: &dyn CheckpointManager,
+    identity: Option<&AgentIdentity>,
+) -> Result<()> {
+    let config = match &manager.child_subnet().federation {
+        Some(config) if !config.notify.is_empty() => config,
+        _ => return Ok(()),
+    };
+    if checkpoint_direction(manager) != CheckpointDirection::TopDown {
+        return Ok(());
+    }
+    let identity = identity
+        .ok_or_else(|| anyhow!("no identity to sign the notices to the federated agents"))?;
+
+    let subnet = &manager.child_subnet().id;
+    let head = manager.current_epoch().await?;
+    let period = manager.checkpoint_period();
+    let epoch = head - head % period;
+    if !manager.state().top_down_notices.mark_sent(subnet, epoch) {
+        return Ok(());
+    }
+
+    let mut notice = TopDownNotice {
+        subnet: subnet.to_string(),
+        epoch,
+        timestamp: now(),
+        signature: String::new(),
+    };
+    notice.signature = identity.sign(&notice.payload())?;
+
+    for url in &config.notify {
+        let client =
+            JsonRpcClientImpl::new(url.clone(), None).with_tap(manager.state().debug_tap.clone());
+        match client
+            .request::<()>(
+                json_rpc_methods::NOTIFY_TOP_DOWN_CHECKPOINT,
+                serde_json::to_value(&notice)?,
+            )
+            .await
+        {
+            Ok(()) => tracing::debug!("notified {url:} of the top-down checkpoint at epoch {epoch:} of subnet {subnet:}"),
+            Err(e) => tracing::warn!("cannot notify {url:} of the top-down checkpoint at epoch {epoch:} of subnet {subnet:}: {e:}"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use ethers::core::rand::thread_rng;
+    use ethers::signers::{LocalWallet, Signer};
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::checkpoint::cosign::now;
+    use crate::checkpoint::federation::{TopDownNotice, TopDownNotices, FALLBACK_POLL_INTERVAL};
+    use crate::persistence::DataDir;
+    use crate::server::status::AgentIdentity;
+
+    #[test]
+    fn test_verify_notice() {
+        let dir = tempfile::tempdir().unwrap();
+        let peer = AgentIdentity::load_or_generate(&DataDir::new(dir.path())).unwrap();
+        let mut notice = TopDownNotice {
+            subnet: String::from("/r123/f0100"),
+            epoch: 10,
+            timestamp: now(),
+            signature: String::new(),
+        };
+        notice.signature = peer.sign(&notice.payload()).unwrap();
+        notice.verify(peer.address()).unwrap();
+
+        let mut other = notice.clone();
+        other.epoch = 20;
+        assert!(other.verify(peer.address()).is_err());
+        assert!(notice
+            .verify(LocalWallet::new(&mut thread_rng()).address())
+            .is_err());
+    }
+
+    #[test]
+    fn test_poll_on_notice() {
+        let subnet = SubnetID::new_root(123);
+        let notices = TopDownNotices::new();
+        let start = Instant::now();
+
+        // the first poll does not wait for a notice
+        assert!(notices.should_poll(&subnet, start));
+        assert!(!notices.should_poll(&subnet, start));
+
+        assert!(notices.record(&subnet, 10));
+        assert!(!notices.record(&subnet, 10));
+        assert!(notices.should_poll(&subnet, start));
+        assert!(!notices.should_poll(&subnet, start));
+
+        // lost notices only delay the polls
+        assert!(notices.should_poll(&subnet, start + FALLBACK_POLL_INTERVAL));
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Notifications of the new heads of the subnets driving the epochs of the managers.
+//!
+//! The managers whose epochs follow an fvm subnet are polled as soon as the node of the subnet
+//! notifies a new head over its websocket endpoint, instead of at their next scheduled poll.
+//! Subnets whose node cannot be subscribed to, and fevm subnets, keep on being polled.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_channel::{Receiver, Sender};
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use tokio::time::timeout;
+
+use crate::checkpoint::{CheckpointManager, ManagerTask};
+use crate::config::subnet::SubnetConfig;
+use crate::config::Subnet;
+use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::message::chain::head_height;
+use crate::lotus::LotusClient;
+use crate::state::AgentState;
+
+/// How long to wait for the websocket connection to a node.
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Subscribes to the heads of the fvm subnets driving the epochs of `managers`. The returned
+/// channel receives the new epochs of the subnets, and is closed once none of the
+/// subscriptions is alive.
+pub(crate) async fn subscribe(
+    managers: &[Box<dyn CheckpointManager>],
+    state: &Arc<AgentState>,
+) -> Receiver<(SubnetID, ChainEpoch)> {
+    let (send, recv) = async_channel::unbounded();
+
+    let mut subscribed = HashSet::new();
+    for subnet in managers.iter().map(|m| m.epoch_subnet()) {
+        if !matches!(subnet.config, SubnetConfig::Fvm(_)) || !subscribed.insert(&subnet.id) {
+            continue;
+        }
+        subscribe_subnet(subnet, send.clone(), state.clone()).await;
+    }
+
+    recv
+}
+
+async fn subscribe_subnet(
+    subnet: &Subnet,
+    send: Sender<(SubnetID, ChainEpoch)>,
+    state: Arc<AgentState>,
+) {
+    let client = LotusJsonRPCClient::from_subnet(subnet, state);
+    let heads = match timeout(SUBSCRIBE_TIMEOUT, client.chain_notify()).await {
+        Ok(Ok(heads)) => heads,
+        Ok(Err(e)) => {
+            tracing::info!(
+                "cannot subscribe to the heads of subnet {}, polling it: {e:}",
+                subnet.id
+            );
+            return;
+        }
+        Err(_) => {
+            tracing::info!(
+                "subscription to the heads of subnet {} timed out, polling it",
+                subnet.id
+            );
+            return;
+        }
+    };
+    tracing::info!("subscribed to the heads of subnet {}", subnet.id);
+
+    let id = subnet.id.clone();
+    tokio::spawn(async move {
+        while let Ok(changes) = heads.recv().await {
+            let height = match head_height(&changes) {
+                Some(h) => h,
+                None => continue,
+            };
+            if send.send((id.clone(), height as ChainEpoch)).await.is_err() {
+                return;
+            }
+        }
+        tracing::warn!("head subscription of subnet {id:} closed, polling it until the checkpointing subsystem reloads");
+    });
+}
+
+/// Makes the managers whose epochs follow `subnet` due for a poll, and wakes their tasks up.
+pub(super) fn wake_managers(tasks: &[ManagerTask], subnet: &SubnetID, epoch: ChainEpoch) {
+    tracing::debug!("new head at epoch {epoch:} in subnet {subnet:}");
+    let now = Instant::now();
+    for task in tasks
+        .iter()
+        .filter(|t| &t.manager.epoch_subnet().id == subnet)
+    {
+        task.manager.poll_schedule().poll_now(now);
+        task.wake.notify_one();
+    }
+}
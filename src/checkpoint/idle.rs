@@ -0,0 +1,154 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Detection of the idle subnets, whose polling is slowed down.
+//!
+//! An agent observing many dormant subnets, e.g. on a testnet, would otherwise query every one of
+//! them at every poll for nothing. A manager is idle when the agent manages none of the validators of
+//! the subnet, so that it has no checkpoint to submit, the validator set is unchanged and no
+//! checkpoint got executed, so that no cross message went through. Idle managers skip a growing
+//! number of polls, and are polled at every one again as soon as their activity changes.
+
+use std::sync::Mutex;
+
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+
+/// The number of polls without activity after which a manager is considered idle.
+const IDLE_AFTER_ROUNDS: u32 = 4;
+/// The maximum number of polls skipped in a row of an idle manager.
+const MAX_SKIPPED_ROUNDS: u32 = 16;
+
+/// What changes in a subnet when it is active.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    validators: Vec<Address>,
+    last_executed_epoch: ChainEpoch,
+}
+
+impl Activity {
+    pub fn new(mut validators: Vec<Address>, last_executed_epoch: ChainEpoch) -> Self {
+        // the order the validators are listed in is not significant
+        validators.sort_by_key(|v| v.to_bytes());
+        Self {
+            validators,
+            last_executed_epoch,
+        }
+    }
+}
+
+#[derive(Default)]
+struct IdleState {
+    /// The activity observed in the last poll.
+    last: Option<Activity>,
+    /// The number of consecutive polls with no activity.
+    quiet_polls: u32,
+    /// The number of polls left to skip.
+    skip: u32,
+}
+
+/// Tracks the activity of the subnet of a manager across polls.
+#[derive(Default)]
+pub struct IdleTracker {
+    state: Mutex<IdleState>,
+}
+
+impl IdleTracker {
+    /// Whether the manager should be polled now, consuming one of the polls to skip otherwise.
+    pub fn should_poll(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.skip == 0 {
+            return true;
+        }
+        state.skip -= 1;
+        false
+    }
+
+    /// Whether the manager is idle.
+    pub fn is_idle(&self) -> bool {
+        self.state.lock().unwrap().quiet_polls >= IDLE_AFTER_ROUNDS
+    }
+
+    /// Records the activity observed in a poll, `None` when the manager has checkpoints to submit.
+    /// Returns `true` if the manager just became idle or active again.
+    pub fn observe(&self, activity: Option<Activity>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let was_idle = state.quiet_polls >= IDLE_AFTER_ROUNDS;
+
+        match activity {
+            Some(a) if state.last.as_ref() == Some(&a) => {
+                state.quiet_polls += 1;
+            }
+            activity => {
+                state.last = activity;
+                state.quiet_polls = 0;
+            }
+        }
+
+        // back off exponentially once idle, so that a subnet waking up is noticed quickly
+        state.skip = match state.quiet_polls.checked_sub(IDLE_AFTER_ROUNDS) {
+            Some(n) => 2u32.saturating_pow(n).min(MAX_SKIPPED_ROUNDS),
+            None => 0,
+        };
+
+        was_idle != (state.quiet_polls >= IDLE_AFTER_ROUNDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+
+    use crate::checkpoint::idle::{Activity, IdleTracker, IDLE_AFTER_ROUNDS};
+
+    /// Returns the number of polls skipped before the next one.
+    fn skipped_rounds(tracker: &IdleTracker) -> u32 {
+        let mut skipped = 0;
+        while !tracker.should_poll() {
+            skipped += 1;
+        }
+        skipped
+    }
+
+    #[test]
+    fn test_idle_tracker() {
+        let tracker = IdleTracker::default();
+        let activity = || Activity::new(vec![Address::new_id(100), Address::new_id(101)], 10);
+
+        assert!(tracker.should_poll());
+        assert!(!tracker.observe(Some(activity())));
+        for _ in 1..IDLE_AFTER_ROUNDS {
+            assert!(!tracker.observe(Some(activity())));
+            assert_eq!(skipped_rounds(&tracker), 0);
+        }
+
+        // no activity for long enough, back off
+        assert!(tracker.observe(Some(activity())));
+        assert!(tracker.is_idle());
+        assert_eq!(skipped_rounds(&tracker), 1);
+        tracker.observe(Some(activity()));
+        assert_eq!(skipped_rounds(&tracker), 2);
+        for _ in 0..10 {
+            tracker.observe(Some(activity()));
+        }
+        assert_eq!(skipped_rounds(&tracker), 16);
+
+        // the order of the validators does not matter
+        tracker.observe(Some(Activity::new(
+            vec![Address::new_id(101), Address::new_id(100)],
+            10,
+        )));
+        assert!(tracker.is_idle());
+
+        // a checkpoint got executed, back to full cadence
+        assert!(tracker.observe(Some(Activity::new(vec![Address::new_id(100)], 20))));
+        assert!(!tracker.is_idle());
+        assert_eq!(skipped_rounds(&tracker), 0);
+
+        // the agent validates in the subnet, never idle
+        for _ in 0..2 * IDLE_AFTER_ROUNDS {
+            tracker.observe(None);
+        }
+        assert!(!tracker.is_idle());
+        assert_eq!(skipped_rounds(&tracker), 0);
+    }
+}
@@ -2,34 +2,82 @@
 // SPDX-License-Identifier: MIT
 
 use crate::config::{ReloadableConfig, Subnet};
+use crate::events::{AgentEvent, Topic};
+use crate::manager::funds::InsufficientFunds;
+use crate::manager::offline::OfflineCall;
+use crate::notify::{Notification, NotificationKind};
+use crate::persistence::journal::{CheckpointJournal, SubmissionRecord, SubmissionStatus};
+use crate::persistence::DataDir;
+use crate::server::status::AgentIdentity;
+use crate::state::AgentState;
 use anyhow::{anyhow, Result};
+use async_channel::Receiver;
 use async_trait::async_trait;
-use futures_util::future::join_all;
+use cid::Cid;
+use futures_util::{stream, FutureExt, StreamExt};
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use ipc_identity::Wallet;
-use std::borrow::Borrow;
-use std::collections::HashSet;
-use std::sync::{Arc, RwLock};
+use ipc_sdk::cross::CrossMsg;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use tokio::select;
-use tokio::time::sleep;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, sleep_until};
 use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+use tracing::Instrument;
 
 pub use bottomup::*;
+pub use catchup::CatchUp;
+pub(crate) use cosign::content_cid;
+pub use cosign::{CoSignRequest, CoSignResponse};
+pub use diff::{CheckpointDiff, DiffCrossMsg};
+pub use federation::{TopDownNotice, TopDownNotices};
+pub use idle::{Activity, IdleTracker};
 use ipc_identity::PersistentKeyStore;
 use ipc_sdk::subnet_id::SubnetID;
+pub(crate) use policy::{earliest_vote_epoch, vote_policy, VoteDecision};
+pub use policy::{ApprovalStatus, VoteApproval, VoteApprovals};
 pub use proof::create_proof;
+pub use schedule::PollSchedule;
+pub(crate) use setup::{check_subnets_health, probe_subnet, setup_manager_from_subnet};
 use std::fmt::Display;
 pub use topdown::*;
+use watchdog::IterationTrace;
 
 mod bottomup;
+mod catchup;
+mod cosign;
+mod diff;
+mod federation;
+mod heads;
+mod idle;
+mod policy;
 mod proof;
+pub mod quorum;
+mod schedule;
 mod setup;
 mod topdown;
+mod watchdog;
 
-const TASKS_PROCESS_THRESHOLD_SEC: u64 = 15;
+/// The interval between the checks of the unhealthy subnets.
+const HEALTH_RETRY_INTERVAL: Duration = Duration::from_secs(30);
 const SUBMISSION_LOOK_AHEAD_ROUNDS: i64 = 3;
+/// How often the managers check whether the agent left maintenance.
+const MAINTENANCE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A checkpoint vote executed in the subnet it was submitted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmittedCheckpoint {
+    /// The cid of the message, or the hash of the transaction in evm subnets, of the vote.
+    pub message: String,
+    /// The epoch the vote was executed at.
+    pub epoch: ChainEpoch,
+}
 
 /// Checkpoint manager that handles a specific parent - child - checkpoint type tuple.
 /// For example, we might have `/r123` subnet and `/r123/t01` as child, one implementation of manager
@@ -44,6 +92,13 @@ pub trait VoteQuery<T> {
         epoch: ChainEpoch,
         validator: &Address,
     ) -> Result<bool>;
+    /// Get the checkpoint votes of the validator still pending in the mempool of the subnet the
+    /// checkpoints are submitted to.
+    async fn pending_votes(
+        &self,
+        subnet_id: &SubnetID,
+        validator: &Address,
+    ) -> Result<PendingVotes>;
 }
 
 /// Checkpoint submission utility query trait
@@ -68,6 +123,10 @@ pub trait CheckpointManager: Display + Send + Sync {
     /// Getter for the target subnet this checkpoint manager is handling
     fn child_subnet(&self) -> &Subnet;
 
+    /// The subnet whose epochs drive the submissions: the parent for top-down checkpoints, the
+    /// child for bottom-up ones
+    fn epoch_subnet(&self) -> &Subnet;
+
     /// The checkpoint period that the current manager is submitting upon
     fn checkpoint_period(&self) -> ChainEpoch;
 
@@ -82,7 +141,23 @@ pub trait CheckpointManager: Display + Send + Sync {
 
     /// Submit the checkpoint based on the current epoch to submit and the previous epoch that was
     /// already submitted.
-    async fn submit_checkpoint(&self, epoch: ChainEpoch, validator: &Address) -> Result<()>;
+    async fn submit_checkpoint(
+        &self,
+        epoch: ChainEpoch,
+        validator: &Address,
+    ) -> Result<SubmittedCheckpoint>;
+
+    /// The cid of the content of the checkpoint at `epoch` as built from the nodes of this agent,
+    /// before any validator signs it, so that two agents can check they vote the same checkpoint.
+    async fn checkpoint_cid(&self, epoch: ChainEpoch) -> Result<Cid>;
+
+    /// Builds the vote of `validator` for the checkpoint at `epoch` as a call to be signed
+    /// offline, see [`crate::manager::offline`]. Only bottom-up votes can be signed offline.
+    async fn offline_vote(&self, _epoch: ChainEpoch, _validator: &Address) -> Result<OfflineCall> {
+        Err(anyhow!(
+            "the checkpoints of {self:} cannot be voted offline"
+        ))
+    }
 
     /// Checks if the validator has already submitted in the epoch
     async fn should_submit_in_epoch(&self, validator: &Address, epoch: ChainEpoch) -> Result<bool>;
@@ -90,6 +165,147 @@ pub trait CheckpointManager: Display + Send + Sync {
     /// Performs checks to see if the subnet is ready for checkpoint submission. If `true` means the
     /// subnet is ready for submission, else means the subnet is not ready.
     async fn presubmission_check(&self) -> Result<bool>;
+
+    /// Get the checkpoint votes of the validator pending in the mempool of the target subnet
+    async fn pending_votes(&self, validator: &Address) -> Result<PendingVotes>;
+
+    /// The checkpoint votes of the managed validators found pending in the target subnet
+    fn pending_submissions(&self) -> &PendingSubmissions;
+
+    /// The activity of the subnet, to slow down its polling when idle
+    fn idle_tracker(&self) -> &IdleTracker;
+
+    /// When to poll the manager next, following the block time of the subnet whose epochs
+    /// drive the submissions
+    fn poll_schedule(&self) -> &PollSchedule;
+
+    /// The state of the agent, shared with its other subsystems
+    fn state(&self) -> &AgentState;
+
+    /// The catch-up of the missed checkpoints, for the managers that catch up when far behind
+    fn catch_up(&self) -> Option<&CatchUp> {
+        None
+    }
+}
+
+/// The direction of the checkpoints of a manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckpointDirection {
+    BottomUp,
+    TopDown,
+}
+
+impl CheckpointDirection {
+    /// The name of the direction in the records, metrics and events of the agent.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckpointDirection::BottomUp => "bottom-up",
+            CheckpointDirection::TopDown => "top-down",
+        }
+    }
+}
+
+impl Display for CheckpointDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns the direction of the checkpoints of `manager`.
+pub fn checkpoint_direction(manager: &dyn CheckpointManager) -> CheckpointDirection {
+    if manager.target_subnet().id == manager.parent_subnet().id {
+        CheckpointDirection::BottomUp
+    } else {
+        CheckpointDirection::TopDown
+    }
+}
+
+/// The checkpoint votes of a validator still pending in the mempool of a subnet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingVotes {
+    /// The epochs of the checkpoints voted in the pending messages.
+    Epochs(HashSet<ChainEpoch>),
+    /// The pending messages of the validator could not be inspected, so any of the epochs may be
+    /// among them.
+    Unknown,
+}
+
+impl PendingVotes {
+    pub fn none() -> Self {
+        PendingVotes::Epochs(HashSet::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            PendingVotes::Epochs(epochs) => epochs.is_empty(),
+            PendingVotes::Unknown => false,
+        }
+    }
+
+    pub fn contains(&self, epoch: ChainEpoch) -> bool {
+        match self {
+            PendingVotes::Epochs(epochs) => epochs.contains(&epoch),
+            PendingVotes::Unknown => true,
+        }
+    }
+}
+
+/// Tracks the checkpoint votes of the managed validators that are pending in the mempool of the
+/// target subnet, e.g. because they were submitted right before the agent restarted, so that they
+/// are not submitted again. The mempool is queried when the manager starts, and then on every
+/// round for as long as some of the votes found are still pending.
+#[derive(Default)]
+pub struct PendingSubmissions {
+    /// `None` until the mempool is queried for the first time.
+    votes: Mutex<Option<HashMap<Address, PendingVotes>>>,
+}
+
+impl PendingSubmissions {
+    /// Whether the pending votes need to be queried again from the mempool.
+    pub fn needs_reconcile(&self) -> bool {
+        match &*self.votes.lock().unwrap() {
+            None => true,
+            Some(votes) => !votes.is_empty(),
+        }
+    }
+
+    /// Replaces the tracked votes with those currently pending in the mempool. Returns the
+    /// validators and epochs of the votes that were not tracked yet.
+    pub fn update(&self, votes: HashMap<Address, PendingVotes>) -> Vec<(Address, ChainEpoch)> {
+        let votes = votes
+            .into_iter()
+            .filter(|(_, v)| !v.is_empty())
+            .collect::<HashMap<_, _>>();
+
+        let mut tracked = self.votes.lock().unwrap();
+        let mut new = vec![];
+        for (validator, v) in votes.iter() {
+            let epochs = match v {
+                PendingVotes::Epochs(epochs) => epochs,
+                PendingVotes::Unknown => continue,
+            };
+            let known = tracked.as_ref().and_then(|t| t.get(validator));
+            for epoch in epochs {
+                if !matches!(known, Some(PendingVotes::Epochs(k)) if k.contains(epoch)) {
+                    new.push((*validator, *epoch));
+                }
+            }
+        }
+        new.sort_by_key(|(_, epoch)| *epoch);
+        *tracked = Some(votes);
+        new
+    }
+
+    /// Whether the vote of the validator at the epoch is pending in the mempool.
+    pub fn is_pending(&self, validator: &Address, epoch: ChainEpoch) -> bool {
+        self.votes
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|votes| votes.get(validator))
+            .map_or(false, |v| v.contains(epoch))
+    }
 }
 
 pub struct CheckpointSubsystem {
@@ -98,19 +314,47 @@ pub struct CheckpointSubsystem {
     config: Arc<ReloadableConfig>,
     fvm_wallet: Arc<RwLock<Wallet>>,
     evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    /// The journal of the votes submitted, in the data dir.
+    journal: CheckpointJournal,
+    /// The identity of the agent, signing the notices to the federated agents.
+    identity: Option<Arc<AgentIdentity>>,
+    state: Arc<AgentState>,
 }
 
 impl CheckpointSubsystem {
-    /// Creates a new `CheckpointSubsystem` with a configuration `config`.
+    /// Creates a new `CheckpointSubsystem` with a configuration `config`, journaling its votes in
+    /// `data_dir`.
     pub fn new(
+        data_dir: &DataDir,
         config: Arc<ReloadableConfig>,
         fvm_wallet: Arc<RwLock<Wallet>>,
         evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        identity: Option<Arc<AgentIdentity>>,
+        state: Arc<AgentState>,
     ) -> Self {
+        let journal = CheckpointJournal::new(&data_dir);
         Self {
             config,
             fvm_wallet,
             evm_keystore,
+            journal,
+            identity,
+            state,
+        }
+    }
+
+    /// Reports the votes the agent was submitting when it last stopped.
+    async fn recover_journal(&self) {
+        match self.journal.recover().await {
+            Ok(interrupted) => {
+                for r in interrupted {
+                    tracing::warn!(
+                        "{} checkpoint of subnet {} at epoch {} was being submitted for validator {} when the agent stopped, checking its vote again",
+                        r.direction, r.subnet, r.epoch, r.validator
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("cannot recover the checkpoint journal: {e:}"),
         }
     }
 }
@@ -120,20 +364,34 @@ impl IntoSubsystem<anyhow::Error> for CheckpointSubsystem {
     async fn run(self, subsys: SubsystemHandle) -> anyhow::Result<()> {
         // Each event in this channel is notification of a new config.
         let mut config_chan = self.config.new_subscriber();
+        self.recover_journal().await;
 
         loop {
             // Load the latest config.
             let config = self.config.get_config();
+
+            // Subnets whose node cannot be reached are marked as unhealthy and left out of the
+            // managers, so that they do not prevent checkpointing in the other subnets.
+            self.state.subnet_health.retain(config.subnets.keys());
+            setup::check_subnets_health(
+                config.subnets.values(),
+                self.fvm_wallet.clone(),
+                self.evm_keystore.clone(),
+                &self.state,
+            )
+            .await;
+
             let managers = match setup::setup_managers_from_config(
                 &config.subnets,
                 self.fvm_wallet.clone(),
                 self.evm_keystore.clone(),
+                &self.state,
             )
             .await
             {
                 Ok(r) => r,
                 Err(e) => {
-                    log::error!("Please check configuration! Cannot start the checkpoint subsystem due to config error: {e:}. Update and reload config.");
+                    tracing::error!("Please check configuration! Cannot start the checkpoint subsystem due to config error: {e:}. Update and reload config.");
                     match config_chan.recv().await {
                         Ok(_) => continue,
                         Err(e) => {
@@ -146,149 +404,722 @@ impl IntoSubsystem<anyhow::Error> for CheckpointSubsystem {
                 }
             };
 
-            loop {
-                select! {
-                    _ = process_managers(managers.as_slice()) => {},
-                    r = config_chan.recv() => {
-                        log::info!("Config changed, reloading checkpointing subsystem");
-                        match r {
-                            // Config updated, return to caller
-                            Ok(_) => { break; },
-                            Err(_) => {
-                                return Err(anyhow!("Config channel unexpectedly closed, shutting down checkpointing subsystem"))
-                            },
-                        }
+            let unhealthy = match self.state.subnet_health.summary() {
+                Some(summary) => {
+                    tracing::warn!("{summary:}");
+                    true
+                }
+                None => {
+                    tracing::info!("all {} subnets are healthy", config.subnets.len());
+                    false
+                }
+            };
+
+            let heads = heads::subscribe(managers.as_slice(), &self.state).await;
+            let _tasks = ManagerTasks::spawn(
+                managers,
+                heads,
+                self.evm_keystore.clone(),
+                self.journal.clone(),
+                self.identity.clone(),
+            );
+
+            // the managers are polled by their tasks until the subsystem reloads or shuts down
+            let recovery = wait_for_recovery(
+                &config.subnets,
+                self.fvm_wallet.clone(),
+                self.evm_keystore.clone(),
+                &self.state,
+            );
+            select! {
+                _ = recovery, if unhealthy => {
+                    tracing::info!("Unhealthy subnets recovered, reloading checkpointing subsystem");
+                }
+                r = config_chan.recv() => {
+                    tracing::info!("Config changed, reloading checkpointing subsystem");
+                    if r.is_err() {
+                        return Err(anyhow!("Config channel unexpectedly closed, shutting down checkpointing subsystem"));
                     }
-                    _ = subsys.on_shutdown_requested() => {
-                        log::info!("Shutting down checkpointing subsystem");
-                        return Ok(());
+                }
+                _ = subsys.on_shutdown_requested() => {
+                    tracing::info!("Shutting down checkpointing subsystem");
+                    // the managers finish the submissions of their epoch before their tasks are
+                    // aborted
+                    self.state
+                        .shutdown
+                        .drain(self.config.get_config().server.shutdown_timeout())
+                        .await;
+                    if let Err(e) = self.journal.sync().await {
+                        tracing::warn!("cannot sync the checkpoint journal: {e:}");
                     }
+                    return Ok(());
                 }
             }
         }
     }
 }
 
+/// Checks the unhealthy subnets every `HEALTH_RETRY_INTERVAL`, returning once any of them is
+/// healthy again.
+async fn wait_for_recovery(
+    subnets: &HashMap<SubnetID, Subnet>,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
+) {
+    loop {
+        sleep(HEALTH_RETRY_INTERVAL).await;
+
+        let unhealthy = state.subnet_health.unhealthy();
+        setup::check_subnets_health(
+            unhealthy.iter().filter_map(|id| subnets.get(id)),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+            state,
+        )
+        .await;
+
+        if unhealthy
+            .iter()
+            .any(|id| state.subnet_health.is_healthy(id))
+        {
+            return;
+        }
+    }
+}
+
 fn handle_err_response(manager: &dyn CheckpointManager, response: anyhow::Result<()>) {
     if response.is_err() {
-        log::warn!("manager {manager:} had error: {:}", response.unwrap_err());
+        manager.state().counters.checkpoint_errors.inc();
+        tracing::warn!("manager {manager:} had error: {:#}", response.unwrap_err());
     }
 }
 
-async fn process_managers(managers: &[Box<dyn CheckpointManager>]) -> anyhow::Result<()> {
-    // Tracks the start time of the processing, will use this to determine should sleep
-    let start_time = Instant::now();
+/// The task polling a manager, with the handle waking it up before its next scheduled poll.
+struct ManagerTask {
+    manager: Arc<dyn CheckpointManager>,
+    wake: Arc<Notify>,
+}
+
+/// The tasks polling the managers of a config, one per manager so that a slow or failing subnet
+/// does not delay the checkpoints of the others. The tasks are aborted when dropped, on a reload
+/// of the subsystem.
+struct ManagerTasks {
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ManagerTasks {
+    fn spawn(
+        managers: Vec<Box<dyn CheckpointManager>>,
+        heads: Receiver<(SubnetID, ChainEpoch)>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        journal: CheckpointJournal,
+        identity: Option<Arc<AgentIdentity>>,
+    ) -> Self {
+        let tasks = managers
+            .into_iter()
+            .map(|m| ManagerTask {
+                manager: Arc::from(m),
+                wake: Arc::new(Notify::new()),
+            })
+            .collect::<Vec<_>>();
+
+        let mut handles = tasks
+            .iter()
+            .map(|t| {
+                // the logs of a manager are structured with its subnet and direction
+                let span = tracing::info_span!(
+                    "checkpoint",
+                    subnet = %t.manager.child_subnet().id,
+                    direction = checkpoint_direction(t.manager.as_ref()).as_str(),
+                );
+                tokio::spawn(
+                    run_manager(
+                        t.manager.clone(),
+                        t.wake.clone(),
+                        evm_keystore.clone(),
+                        journal.clone(),
+                        identity.clone(),
+                    )
+                    .instrument(span),
+                )
+            })
+            .collect::<Vec<_>>();
+        handles.push(tokio::spawn(async move {
+            while let Ok((subnet, epoch)) = heads.recv().await {
+                heads::wake_managers(&tasks, &subnet, epoch);
+            }
+        }));
 
-    let futures = managers
-        .iter()
-        .map(|manager| async {
-            let response = submit_till_current_epoch(manager.borrow()).await;
-            handle_err_response(manager.borrow(), response);
-        })
-        .collect::<Vec<_>>();
+        Self { handles }
+    }
+}
 
-    join_all(futures).await;
+impl Drop for ManagerTasks {
+    fn drop(&mut self) {
+        for h in self.handles.iter() {
+            h.abort();
+        }
+    }
+}
 
-    sleep_or_continue(start_time).await;
+/// Polls `manager` whenever it is due, until its task is aborted.
+async fn run_manager(
+    manager: Arc<dyn CheckpointManager>,
+    wake: Arc<Notify>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    journal: CheckpointJournal,
+    identity: Option<Arc<AgentIdentity>>,
+) {
+    let manager = manager.as_ref();
+    loop {
+        if manager.state().maintenance.is_enabled() {
+            sleep(MAINTENANCE_POLL_INTERVAL).await;
+            continue;
+        }
 
-    Ok(())
+        if should_poll(manager, Instant::now()) {
+            // a panic only ends the iteration, the manager is polled again on its next round
+            let iteration = AssertUnwindSafe(poll_manager(
+                manager,
+                &evm_keystore,
+                &journal,
+                identity.as_deref(),
+            ))
+            .catch_unwind()
+            .await;
+            if iteration.is_err() {
+                manager.state().counters.checkpoint_errors.inc();
+                tracing::error!("checkpoint iteration of manager {manager:} panicked");
+            }
+        }
+
+        select! {
+            _ = sleep_until(manager.poll_schedule().next_poll().into()) => {}
+            _ = wake.notified() => {}
+        }
+    }
+}
+
+/// Whether `manager` is to be polled at `now`, scheduling its next poll if it was due.
+fn should_poll(manager: &dyn CheckpointManager, now: Instant) -> bool {
+    let schedule = manager.poll_schedule();
+    if !schedule.is_due(now) {
+        return false;
+    }
+    schedule.schedule_next(now);
+
+    let poll = manager.idle_tracker().should_poll();
+    if !poll {
+        tracing::debug!("manager {manager:} idle, skip poll");
+        return false;
+    }
+
+    let poll = federation::should_poll(manager, now);
+    if !poll {
+        tracing::debug!("manager {manager:} waiting for a top-down notice, skip poll");
+    }
+    poll
 }
 
-async fn sleep_or_continue(start_time: Instant) {
-    let elapsed = start_time.elapsed().as_secs();
-    if elapsed < TASKS_PROCESS_THRESHOLD_SEC {
-        sleep(Duration::from_secs(TASKS_PROCESS_THRESHOLD_SEC - elapsed)).await
+async fn poll_manager(
+    manager: &dyn CheckpointManager,
+    evm_keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    journal: &CheckpointJournal,
+    identity: Option<&AgentIdentity>,
+) {
+    let _guard = match manager
+        .state()
+        .maintenance
+        .start(format!("checkpoints of manager {manager:}"))
+    {
+        Some(g) => g,
+        None => return,
+    };
+    if let Err(e) = federation::notify_peers(manager, identity).await {
+        tracing::warn!("cannot notify the federated agents of manager {manager:}: {e:}");
     }
+    let trace = IterationTrace::new();
+    let schedule = manager.poll_schedule();
+    let block_time = schedule.block_time().unwrap_or_else(|| schedule.interval());
+    let response = watchdog::watch(
+        manager,
+        watchdog::iteration_timeout(block_time, manager.checkpoint_period()),
+        &trace,
+        &manager.state().counters.checkpoint_task_restarts,
+        submit_till_current_epoch(manager, &trace, evm_keystore, journal),
+    )
+    .await;
+    handle_err_response(manager, response);
 }
 
 /// Attempts to submit checkpoints from the last executed epoch all the way to the current epoch for
 /// all the validators in the provided manager.
-async fn submit_till_current_epoch(manager: &dyn CheckpointManager) -> Result<()> {
+async fn submit_till_current_epoch(
+    manager: &dyn CheckpointManager,
+    trace: &IterationTrace,
+    evm_keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    journal: &CheckpointJournal,
+) -> Result<()> {
+    trace.enter("presubmission check");
     if !manager.presubmission_check().await? {
-        log::info!("subnet in manager: {manager:} not ready to submit checkpoint");
+        tracing::info!("subnet in manager: {manager:} not ready to submit checkpoint");
         return Ok(());
     }
 
     // we might have to obtain the list of validators as some validators might leave the subnet
     // we can improve the performance by caching if this slows down the process significantly.
+    trace.enter("get validators");
     let mut validators = manager
         .validators()
         .await
         .map_err(|e| anyhow!("cannot get child validators for {manager:} due to {e:}"))?;
-    log::debug!("list of validators from on chain: {validators:?} for manager: {manager:}");
+    tracing::debug!("list of validators from on chain: {validators:?} for manager: {manager:}");
+    let on_chain_validators = validators.clone();
     remove_not_managed(&mut validators, &manager.target_subnet().accounts());
-    log::debug!("list of validators: {validators:?} for manager: {manager:}");
+    tracing::debug!("list of validators: {validators:?} for manager: {manager:}");
 
     if validators.is_empty() {
-        log::info!(
+        tracing::info!(
             "no validators: {validators:?} for manager: {manager:}, do not submit checkpoints"
         );
+
+        trace.enter("check activity");
+        let last_executed_epoch = manager
+            .last_executed_epoch()
+            .await
+            .map_err(|e| anyhow!("cannot get last executed epoch for {manager:} due to {e:}"))?;
+        let tracker = manager.idle_tracker();
+        if tracker.observe(Some(Activity::new(
+            on_chain_validators,
+            last_executed_epoch,
+        ))) {
+            if tracker.is_idle() {
+                tracing::info!("no activity in manager: {manager:}, slowing down its polling");
+            } else {
+                tracing::info!("activity resumed in manager: {manager:}, polling every round");
+            }
+        }
         return Ok(());
     }
+    manager.idle_tracker().observe(None);
+
+    trace.enter("reconcile pending votes");
+    reconcile_pending_votes(manager, &validators, journal).await;
 
     let period = manager.checkpoint_period();
-    log::debug!("checkpoint period: {period} for manager: {manager}");
+    tracing::debug!("checkpoint period: {period} for manager: {manager}");
 
+    trace.enter("get last executed and current epochs");
     let last_executed_epoch = manager
         .last_executed_epoch()
         .await
         .map_err(|e| anyhow!("cannot get last executed epoch for {manager:} due to {e:}"))?;
-    log::debug!("obtained last executed epoch: {last_executed_epoch:} for manager: {manager:}");
+    tracing::debug!("obtained last executed epoch: {last_executed_epoch:} for manager: {manager:}");
+    manager
+        .state()
+        .vote_approvals
+        .prune(&manager.to_string(), last_executed_epoch);
+    notify_committed(manager, last_executed_epoch);
     let current_epoch = manager
         .current_epoch()
         .await
         .map_err(|e| anyhow!("cannot get the current eopch for {manager:} due to {e:}"))?;
-    log::debug!("obtained current epoch: {last_executed_epoch:} for manager: {manager:}");
+    tracing::debug!("obtained current epoch: {last_executed_epoch:} for manager: {manager:}");
+    manager
+        .poll_schedule()
+        .observe_epoch(current_epoch, Instant::now());
 
-    log::info!(
+    tracing::info!(
         "latest epoch {:?}, last executed epoch: {:?} for checkpointing: {:}",
         current_epoch,
         last_executed_epoch,
         manager,
     );
 
-    let mut next_epoch = last_executed_epoch + period;
-    let cut_off_epoch = std::cmp::min(
-        current_epoch,
-        SUBMISSION_LOOK_AHEAD_ROUNDS * period + last_executed_epoch,
-    );
+    // a top-down manager far behind the parent submits larger batches of checkpoints, several at
+    // a time, until it caught up
+    let catching_up = manager.catch_up().map_or(false, |c| {
+        c.observe(manager, last_executed_epoch, current_epoch, period)
+    });
+    let (look_ahead, concurrency) = if catching_up {
+        (
+            catchup::CATCH_UP_BATCH_PERIODS,
+            catchup::CATCH_UP_CONCURRENCY,
+        )
+    } else {
+        (SUBMISSION_LOOK_AHEAD_ROUNDS, 1)
+    };
 
+    let cut_off_epoch = std::cmp::min(current_epoch, look_ahead * period + last_executed_epoch);
+    let mut epochs = vec![];
+    let mut next_epoch = last_executed_epoch + period;
     // Instead of loop all the way to `current_epoch`, we loop till `cut_off_epoch`.
     // Reason because if the current epoch is significantly greater than last_executed_epoch and there
     // are lots of validators in the network, loop all the way to current epoch might have some outdated
     // data. Set a cut off epoch such that validators can sync with chain more regularly.
     while next_epoch < cut_off_epoch {
-        // now we process each validator
-        for validator in &validators {
-            log::debug!("submit checkpoint for validator: {validator:?} in manager: {manager:}");
+        epochs.push(next_epoch);
+        next_epoch += period;
+    }
+
+    let context = SubmissionContext {
+        trace,
+        evm_keystore,
+        journal,
+        vote_policy: policy::vote_policy(&manager.child_subnet().vote_policy),
+        underfunded: Mutex::new(HashSet::new()),
+        catching_up,
+    };
+    // the epochs are submitted in order, the next one starting as soon as one completes
+    let mut submissions = stream::iter(epochs)
+        .map(|epoch| submit_epoch(manager, &validators, epoch, &context))
+        .buffered(concurrency);
+    while let Some(submitted) = submissions.next().await {
+        if !submitted? {
+            return Ok(());
+        }
+    }
+
+    tracing::info!("process checkpoint from epoch: {last_executed_epoch:} to {current_epoch:} in manager: {manager:}");
 
-            if !manager
-                .should_submit_in_epoch(validator, next_epoch)
-                .await?
+    Ok(())
+}
+
+/// What the submissions of the checkpoints of a poll share.
+struct SubmissionContext<'a> {
+    trace: &'a IterationTrace,
+    evm_keystore: &'a Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    journal: &'a CheckpointJournal,
+    vote_policy: Box<dyn policy::VotePolicy>,
+    /// The validators whose balance does not cover the fees of a vote in this poll.
+    underfunded: Mutex<HashSet<Address>>,
+    /// Whether the manager is catching up, its votes then being submitted without delay.
+    catching_up: bool,
+}
+
+/// Submits the checkpoint at `epoch` for the `validators` that did not vote it yet. Returns
+/// `false` if the submissions are paused for maintenance.
+async fn submit_epoch(
+    manager: &dyn CheckpointManager,
+    validators: &[Address],
+    epoch: ChainEpoch,
+    context: &SubmissionContext<'_>,
+) -> Result<bool> {
+    // the submissions of an epoch are all made before pausing for maintenance, so that the
+    // validators do not restart from a partially voted epoch
+    if manager.state().maintenance.is_enabled() {
+        tracing::info!(
+            "in maintenance, pausing checkpoints of manager: {manager:} before epoch {epoch:}"
+        );
+        return Ok(false);
+    }
+    // the votes of the epoch are delayed once, before the first of them, so that the delays of
+    // a poll add up to at most one per epoch
+    let mut delay = match &manager.child_subnet().submission_delay {
+        Some(delay) if !context.catching_up => Some(delay.sample()),
+        _ => None,
+    };
+    for validator in validators {
+        tracing::debug!("submit checkpoint for validator: {validator:?} in manager: {manager:}");
+
+        if context.underfunded.lock().unwrap().contains(validator) {
+            continue;
+        }
+
+        if manager.pending_submissions().is_pending(validator, epoch) {
+            tracing::debug!(
+                "checkpoint at epoch {epoch:} for validator: {validator:} still pending in the mempool in manager: {manager:}"
+            );
+            continue;
+        }
+
+        context.trace.enter(format!(
+            "check vote of validator {validator:} at epoch {epoch:}"
+        ));
+        if !manager.should_submit_in_epoch(validator, epoch).await? {
+            tracing::debug!(
+                "next submission epoch {epoch:?} already voted for validator: {:?} in manager: {manager:}",
+                validator.to_string()
+            );
+            continue;
+        }
+
+        tracing::debug!(
+            "next submission epoch {epoch:} not voted for validator: {validator:} in manager: {manager:}, should vote"
+        );
+
+        // Submissions of previous validators or epochs may take a while to be executed, in
+        // the meantime the votes of other validators can close the window of this epoch, and
+        // the message would be guaranteed to fail.
+        let last_executed_epoch = manager
+            .last_executed_epoch()
+            .await
+            .map_err(|e| anyhow!("cannot get last executed epoch for {manager:} due to {e:}"))?;
+        if let Some(reason) = closed_window_reason(epoch, last_executed_epoch) {
+            tracing::info!("skip checkpoint at epoch {epoch:} for validator {validator:} in manager: {manager:}: {reason:}");
+            manager.state().counters.checkpoints_skipped.inc();
+            continue;
+        }
+
+        context.trace.enter(format!(
+            "vote policy of validator {validator:} at epoch {epoch:}"
+        ));
+        if let policy::VoteDecision::Abstain(reason) = context
+            .vote_policy
+            .decide(manager, validator, epoch)
+            .await?
+        {
+            tracing::info!("not voting checkpoint at epoch {epoch:} for validator {validator:} in manager: {manager:}: {reason:}");
+            continue;
+        }
+
+        if let Some(co_signing) = &manager.child_subnet().co_signing {
+            context.trace.enter(format!(
+                "co-signing of validator {validator:} at epoch {epoch:}"
+            ));
+            match cosign::request_co_signature(
+                co_signing,
+                context.evm_keystore,
+                manager,
+                validator,
+                epoch,
+            )
+            .await
             {
-                log::debug!(
-                    "next submission epoch {next_epoch:?} already voted for validator: {:?} in manager: {manager:}",
-                    validator.to_string()
-                );
-                continue;
+                Ok(policy::VoteDecision::Vote) => {}
+                Ok(policy::VoteDecision::Abstain(reason)) => {
+                    tracing::info!("not voting checkpoint at epoch {epoch:} for validator {validator:} in manager: {manager:}: {reason:}");
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("cannot get checkpoint at epoch {epoch:} for validator {validator:} co-signed in manager: {manager:}: {e:}");
+                    continue;
+                }
             }
+        }
 
-            log::debug!(
-                "next submission epoch {next_epoch:} not voted for validator: {validator:} in manager: {manager:}, should vote"
-            );
+        if let Some(delay) = delay.take() {
+            context.trace.enter(format!(
+                "delay vote of validator {validator:} at epoch {epoch:} by {delay:?}"
+            ));
+            tracing::debug!("delaying checkpoint at epoch {epoch:} for validator {validator:} in manager: {manager:} by {delay:?}");
+            sleep(delay).await;
 
-            manager.submit_checkpoint(next_epoch, validator).await?;
+            // the votes of the other validators submitted meanwhile may have reached the quorum,
+            // a vote executed after it would be rejected
+            let last_executed_epoch = manager.last_executed_epoch().await.map_err(|e| {
+                anyhow!("cannot get last executed epoch for {manager:} due to {e:}")
+            })?;
+            if let Some(reason) = closed_window_reason(epoch, last_executed_epoch) {
+                tracing::info!("skip delayed checkpoint at epoch {epoch:} for validator {validator:} in manager: {manager:}: {reason:}");
+                manager.state().counters.checkpoints_skipped.inc();
+                continue;
+            }
+        }
 
-            log::info!("checkpoint at epoch {next_epoch:} submitted for validator {validator:} in manager: {manager:}");
+        context.trace.enter(format!(
+            "submit checkpoint of validator {validator:} at epoch {epoch:}"
+        ));
+        let attempt = SubmissionRecord::new(
+            &manager.child_subnet().id,
+            checkpoint_direction(manager).as_str(),
+            epoch,
+            validator,
+            SubmissionStatus::Attempted,
+        );
+        journal_record(context.journal, &attempt).await;
+        let submitted = manager.submit_checkpoint(epoch, validator).await;
+        journal_record(context.journal, &attempt.outcome(&submitted)).await;
+        manager.state().metrics.observe_checkpoint_submission(
+            &manager.child_subnet().id,
+            checkpoint_direction(manager).as_str(),
+            submitted.is_ok(),
+        );
+        let submitted = match submitted {
+            Ok(submitted) => submitted,
+            Err(e) => {
+                match e.downcast_ref::<InsufficientFunds>() {
+                    Some(funds) => {
+                        // nothing was sent, the vote is attempted again in the next polls until
+                        // the balance is topped up
+                        tracing::warn!("skip checkpoint at epoch {epoch:} for validator {validator:} in manager: {manager:}: {funds:}");
+                        manager.state().counters.checkpoints_skipped.inc();
+                        context.underfunded.lock().unwrap().insert(*validator);
+                        let subnet = &manager.target_subnet().id;
+                        if manager
+                            .state()
+                            .underfunded_signers
+                            .mark_underfunded(subnet, funds)
+                        {
+                            manager.state().notifier.notify(Notification::new(
+                            NotificationKind::InsufficientFunds,
+                            subnet,
+                            format!("validator {validator:} cannot vote checkpoints in manager {manager:}: {funds:}"),
+                        ));
+                        }
+                        continue;
+                    }
+                    None => return Err(e),
+                }
+            }
+        };
+        manager.state().counters.checkpoints_submitted.inc();
+        manager.state().last_submissions.record(
+            &manager.child_subnet().id,
+            checkpoint_direction(manager).as_str(),
+            epoch,
+        );
+        manager
+            .state()
+            .events
+            .publish(AgentEvent::CheckpointSubmitted {
+                subnet: manager.child_subnet().id.to_string(),
+                target: manager.target_subnet().id.to_string(),
+                direction: checkpoint_direction(manager).to_string(),
+                epoch,
+                validator: validator.to_string(),
+                message: submitted.message,
+                executed_epoch: submitted.epoch,
+            });
+        let subnet = &manager.target_subnet().id;
+        if manager
+            .state()
+            .underfunded_signers
+            .mark_funded(subnet, validator)
+        {
+            manager.state().notifier.notify(Notification::new(
+                NotificationKind::FundsRecovered,
+                subnet,
+                format!("validator {validator:} votes checkpoints again in manager {manager:}"),
+            ));
         }
 
-        // increment next epoch
-        next_epoch += period;
+        tracing::info!("checkpoint at epoch {epoch:} submitted for validator {validator:} in manager: {manager:}");
     }
 
-    log::info!("process checkpoint from epoch: {last_executed_epoch:} to {current_epoch:} in manager: {manager:}");
+    Ok(true)
+}
 
-    Ok(())
+/// Records `record` in the journal, the votes are still submitted if it cannot be written.
+async fn journal_record(journal: &CheckpointJournal, record: &SubmissionRecord) {
+    if let Err(e) = journal.record(record).await {
+        tracing::warn!("cannot record checkpoint submission in the journal: {e:}");
+    }
+}
+
+/// Notifies the last checkpoint committed by `manager`, seen again in every poll until the next
+/// one is committed, and only delivered once.
+fn notify_committed(manager: &dyn CheckpointManager, epoch: ChainEpoch) {
+    if epoch <= 0 {
+        return;
+    }
+    let direction = checkpoint_direction(manager);
+    let kind = match direction {
+        CheckpointDirection::BottomUp => NotificationKind::BottomUpCommitted,
+        CheckpointDirection::TopDown => NotificationKind::TopDownCommitted,
+    };
+    let direction = direction.as_str();
+    let subnet = &manager.child_subnet().id;
+    let state = manager.state();
+    state
+        .events
+        .publish_once(Topic::Checkpoints, subnet, direction, epoch, || {
+            vec![AgentEvent::CheckpointCommitted {
+                subnet: subnet.to_string(),
+                target: manager.target_subnet().id.to_string(),
+                direction: direction.to_string(),
+                epoch,
+            }]
+        });
+    state.notifier.notify(
+        Notification::new(
+            kind,
+            subnet,
+            format!(
+                "checkpoint at epoch {epoch:} committed in {}",
+                manager.target_subnet().id
+            ),
+        )
+        .chain_event(epoch, None),
+    );
+}
+
+/// Publishes the cross messages `msgs` carried by the checkpoint of `manager` at `epoch`, once for
+/// all the validators that submit it.
+fn publish_relayed(manager: &dyn CheckpointManager, epoch: ChainEpoch, msgs: &[CrossMsg]) {
+    if msgs.is_empty() {
+        return;
+    }
+    let subnet = &manager.child_subnet().id;
+    let direction = checkpoint_direction(manager).as_str();
+    manager
+        .state()
+        .events
+        .publish_once(Topic::CrossMsgs, subnet, direction, epoch, || {
+            msgs.iter()
+                .filter_map(|m| DiffCrossMsg::try_from(m).ok())
+                .map(|msg| AgentEvent::CrossMsgRelayed {
+                    subnet: subnet.to_string(),
+                    target: manager.target_subnet().id.to_string(),
+                    direction: direction.to_string(),
+                    epoch,
+                    msg,
+                })
+                .collect()
+        });
+}
+
+/// Queries the mempool of the target subnet for the checkpoint votes of the validators that are
+/// still pending, so that they are not submitted twice. The votes found that were not tracked
+/// yet, e.g. sent before the agent restarted, are adopted: they are recorded in the journal.
+async fn reconcile_pending_votes(
+    manager: &dyn CheckpointManager,
+    validators: &[Address],
+    journal: &CheckpointJournal,
+) {
+    let pending = manager.pending_submissions();
+    if !pending.needs_reconcile() {
+        return;
+    }
+
+    let mut votes = HashMap::new();
+    for validator in validators {
+        let v = match manager.pending_votes(validator).await {
+            Ok(v) => v,
+            Err(e) => {
+                // any of its votes may be pending, they are held until the mempool answers
+                tracing::warn!("cannot get pending votes of {validator:} for {manager:}, holding its votes: {e:}");
+                PendingVotes::Unknown
+            }
+        };
+        if !v.is_empty() {
+            tracing::info!("checkpoint votes of validator: {validator:} pending in the mempool: {v:?}, tracking them in manager: {manager:}");
+        }
+        votes.insert(*validator, v);
+    }
+
+    for (validator, epoch) in pending.update(votes) {
+        let record = SubmissionRecord::new(
+            &manager.child_subnet().id,
+            checkpoint_direction(manager).as_str(),
+            epoch,
+            &validator,
+            SubmissionStatus::Adopted,
+        );
+        journal_record(journal, &record).await;
+    }
+}
+
+/// Returns the reason why the window of the checkpoint at `epoch` is no longer open on chain, if
+/// it is closed.
+fn closed_window_reason(epoch: ChainEpoch, last_executed_epoch: ChainEpoch) -> Option<String> {
+    if epoch <= last_executed_epoch {
+        return Some(format!(
+            "window already closed, last executed epoch: {last_executed_epoch:}"
+        ));
+    }
+    None
 }
 
 /// Removes the not managed accounts from the list of validators
@@ -303,4 +1134,77 @@ pub struct CheckpointMetadata {
     pub(crate) parent: Subnet,
     pub(crate) child: Subnet,
     pub(crate) period: ChainEpoch,
+    pub(crate) pending: PendingSubmissions,
+    pub(crate) idle: IdleTracker,
+    pub(crate) schedule: PollSchedule,
+    /// The state of the agent the manager shares with the other subsystems.
+    pub(crate) state: Arc<AgentState>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::time::Duration;
+
+    use fvm_shared::address::Address;
+
+    use crate::checkpoint::watchdog::MIN_ITERATION_TIMEOUT;
+    use crate::checkpoint::{
+        closed_window_reason, PendingSubmissions, PendingVotes, SUBMISSION_LOOK_AHEAD_ROUNDS,
+    };
+    use crate::config::subnet::MAX_SUBMISSION_DELAY_SECS;
+
+    #[test]
+    fn test_closed_window_reason() {
+        assert!(closed_window_reason(20, 10).is_none());
+        assert!(closed_window_reason(20, 20).is_some());
+        assert!(closed_window_reason(10, 20).is_some());
+    }
+
+    #[test]
+    fn test_submission_delays_within_iteration_timeout() {
+        // a poll delays each of the epochs it votes once, and leaves time to submit the votes
+        let delays =
+            Duration::from_secs(MAX_SUBMISSION_DELAY_SECS * SUBMISSION_LOOK_AHEAD_ROUNDS as u64);
+        assert!(delays <= MIN_ITERATION_TIMEOUT / 2);
+    }
+
+    #[test]
+    fn test_pending_submissions() {
+        let pending = PendingSubmissions::default();
+        let validator = Address::new_id(100);
+        let other = Address::new_id(101);
+        assert!(pending.needs_reconcile());
+
+        // nothing pending at startup, no need to query the mempool again
+        assert!(pending
+            .update(HashMap::from([(validator, PendingVotes::none())]))
+            .is_empty());
+        assert!(!pending.needs_reconcile());
+        assert!(!pending.is_pending(&validator, 10));
+
+        // the votes found are adopted, the votes of validators whose mempool could not be
+        // inspected are held without being adopted
+        let adopted = pending.update(HashMap::from([
+            (validator, PendingVotes::Epochs(HashSet::from([10, 20]))),
+            (other, PendingVotes::Unknown),
+        ]));
+        assert_eq!(adopted, vec![(validator, 10), (validator, 20)]);
+        assert!(pending.needs_reconcile());
+        assert!(pending.is_pending(&validator, 20));
+        assert!(!pending.is_pending(&validator, 30));
+        assert!(pending.is_pending(&other, 30));
+
+        // only the votes not tracked yet are adopted
+        let adopted = pending.update(HashMap::from([(
+            validator,
+            PendingVotes::Epochs(HashSet::from([20, 30])),
+        )]));
+        assert_eq!(adopted, vec![(validator, 30)]);
+
+        // the votes got executed
+        pending.update(HashMap::from([(validator, PendingVotes::none())]));
+        assert!(!pending.needs_reconcile());
+        assert!(!pending.is_pending(&other, 30));
+    }
 }
@@ -0,0 +1,350 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Policies deciding whether the validators of the agent vote a checkpoint.
+//!
+//! The checkpoint loop only asks the policy of the subnet about checkpoints that the validator
+//! has not voted yet and whose window is still open. Policies that do not vote a checkpoint yet
+//! are asked again in the next round, until its window is closed.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::CheckpointManager;
+use crate::config::subnet::{VotePolicyConfig, VotePolicyKind};
+
+/// The finality used by the `strict-local` policy if not set in the config.
+pub const DEFAULT_VOTE_FINALITY: ChainEpoch = 20;
+
+/// The decision of a policy on a checkpoint.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VoteDecision {
+    Vote,
+    /// Do not vote the checkpoint for now, with the reason why.
+    Abstain(String),
+}
+
+#[async_trait]
+pub trait VotePolicy: Send + Sync {
+    /// Decides whether `validator` votes the checkpoint of `manager` at `epoch`.
+    async fn decide(
+        &self,
+        manager: &dyn CheckpointManager,
+        validator: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<VoteDecision>;
+}
+
+/// Returns the policy set in the config of a subnet.
+pub fn vote_policy(config: &VotePolicyConfig) -> Box<dyn VotePolicy> {
+    match config.kind {
+        VotePolicyKind::Default => Box::new(DefaultPolicy),
+        VotePolicyKind::FollowMajority => Box::new(FollowMajorityPolicy),
+        VotePolicyKind::StrictLocal => Box::new(StrictLocalPolicy {
+            finality: config.finality.unwrap_or(DEFAULT_VOTE_FINALITY),
+        }),
+        VotePolicyKind::ManualApproval => Box::new(ManualApprovalPolicy),
+    }
+}
+
+/// The earliest epoch of the subnet whose epochs drive the submissions at which the policy set in
+/// `config` may vote the checkpoint at `epoch`. The checkpoint loop only submits the checkpoints
+/// whose epoch the head is past.
+pub fn earliest_vote_epoch(config: &VotePolicyConfig, epoch: ChainEpoch) -> ChainEpoch {
+    match config.kind {
+        VotePolicyKind::StrictLocal => {
+            epoch + config.finality.unwrap_or(DEFAULT_VOTE_FINALITY).max(1)
+        }
+        _ => epoch + 1,
+    }
+}
+
+/// Votes every checkpoint.
+pub struct DefaultPolicy;
+
+#[async_trait]
+impl VotePolicy for DefaultPolicy {
+    async fn decide(
+        &self,
+        _manager: &dyn CheckpointManager,
+        _validator: &Address,
+        _epoch: ChainEpoch,
+    ) -> Result<VoteDecision> {
+        Ok(VoteDecision::Vote)
+    }
+}
+
+/// Votes a checkpoint once at least half of the validators not managed by the agent have voted
+/// it, so that the agent never leads a vote.
+pub struct FollowMajorityPolicy;
+
+#[async_trait]
+impl VotePolicy for FollowMajorityPolicy {
+    async fn decide(
+        &self,
+        manager: &dyn CheckpointManager,
+        _validator: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<VoteDecision> {
+        let mut others = manager.validators().await?;
+        let managed = manager.target_subnet().accounts();
+        others.retain(|v| !managed.contains(v));
+        if others.is_empty() {
+            return Ok(VoteDecision::Vote);
+        }
+
+        let mut voted = 0;
+        for v in others.iter() {
+            if !manager.should_submit_in_epoch(v, epoch).await? {
+                voted += 1;
+            }
+        }
+
+        if voted * 2 >= others.len() {
+            Ok(VoteDecision::Vote)
+        } else {
+            Ok(VoteDecision::Abstain(format!(
+                "{voted:} of {} other validators voted, waiting for a majority",
+                others.len()
+            )))
+        }
+    }
+}
+
+/// Votes a checkpoint once the head of the local node of the subnet it comes from is at least
+/// `finality` epochs past it.
+pub struct StrictLocalPolicy {
+    finality: ChainEpoch,
+}
+
+#[async_trait]
+impl VotePolicy for StrictLocalPolicy {
+    async fn decide(
+        &self,
+        manager: &dyn CheckpointManager,
+        _validator: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<VoteDecision> {
+        let head = manager.current_epoch().await?;
+        if head - epoch >= self.finality {
+            Ok(VoteDecision::Vote)
+        } else {
+            Ok(VoteDecision::Abstain(format!(
+                "not final on the local node, head at epoch {head:}, finality {} epochs",
+                self.finality
+            )))
+        }
+    }
+}
+
+/// Votes a checkpoint once an operator approves it.
+pub struct ManualApprovalPolicy;
+
+#[async_trait]
+impl VotePolicy for ManualApprovalPolicy {
+    async fn decide(
+        &self,
+        manager: &dyn CheckpointManager,
+        validator: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<VoteDecision> {
+        let approval =
+            manager
+                .state()
+                .vote_approvals
+                .request(&manager.to_string(), validator, epoch);
+        Ok(match approval.status {
+            ApprovalStatus::Approved => VoteDecision::Vote,
+            ApprovalStatus::Pending => VoteDecision::Abstain(format!(
+                "waiting for approval {}, approve it with ipc_approveVote",
+                approval.id
+            )),
+            ApprovalStatus::Rejected => VoteDecision::Abstain(format!(
+                "rejected by the operator in approval {}",
+                approval.id
+            )),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A vote waiting for, or decided by, an operator.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VoteApproval {
+    pub id: u64,
+    /// The checkpoint manager of the vote, i.e. its direction and subnets.
+    pub checkpoint: String,
+    pub validator: String,
+    pub epoch: ChainEpoch,
+    pub status: ApprovalStatus,
+    /// The unix timestamp, in seconds, of the first time the vote was requested.
+    pub requested_at: u64,
+}
+
+#[derive(Default)]
+struct Approvals {
+    next_id: u64,
+    votes: HashMap<(String, Address, ChainEpoch), VoteApproval>,
+}
+
+pub struct VoteApprovals {
+    approvals: Mutex<Option<Approvals>>,
+}
+
+impl VoteApprovals {
+    pub const fn new() -> Self {
+        Self {
+            approvals: Mutex::new(None),
+        }
+    }
+
+    /// Returns the approval of the vote of `validator` at `epoch` in `checkpoint`, queuing it
+    /// for the operator if it has not been requested yet.
+    pub fn request(
+        &self,
+        checkpoint: &str,
+        validator: &Address,
+        epoch: ChainEpoch,
+    ) -> VoteApproval {
+        let mut approvals = self.approvals.lock().unwrap();
+        let approvals = approvals.get_or_insert_with(Approvals::default);
+
+        let key = (checkpoint.to_string(), *validator, epoch);
+        if let Some(approval) = approvals.votes.get(&key) {
+            return approval.clone();
+        }
+
+        approvals.next_id += 1;
+        let approval = VoteApproval {
+            id: approvals.next_id,
+            checkpoint: checkpoint.to_string(),
+            validator: validator.to_string(),
+            epoch,
+            status: ApprovalStatus::Pending,
+            requested_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        tracing::info!("vote of validator {validator:} at epoch {epoch:} in {checkpoint:} waiting for approval {}", approval.id);
+        approvals.votes.insert(key, approval.clone());
+        approval
+    }
+
+    /// Approves or rejects the vote of approval `id`.
+    pub fn decide(&self, id: u64, approve: bool) -> Result<VoteApproval> {
+        let mut approvals = self.approvals.lock().unwrap();
+        let approval = approvals
+            .as_mut()
+            .and_then(|a| a.votes.values_mut().find(|v| v.id == id))
+            .ok_or_else(|| anyhow!("no vote waiting for approval {id:}"))?;
+        if approval.status != ApprovalStatus::Pending {
+            return Err(anyhow!(
+                "vote of approval {id:} already {:?}",
+                approval.status
+            ));
+        }
+
+        approval.status = if approve {
+            ApprovalStatus::Approved
+        } else {
+            ApprovalStatus::Rejected
+        };
+        Ok(approval.clone())
+    }
+
+    /// Forgets the votes in `checkpoint` at or before `last_executed_epoch`, whose window is
+    /// closed.
+    pub fn prune(&self, checkpoint: &str, last_executed_epoch: ChainEpoch) {
+        let mut approvals = self.approvals.lock().unwrap();
+        if let Some(a) = approvals.as_mut() {
+            a.votes
+                .retain(|(c, _, epoch), _| c != checkpoint || *epoch > last_executed_epoch);
+        }
+    }
+
+    /// Returns all the votes requested, sorted by id.
+    pub fn all(&self) -> Vec<VoteApproval> {
+        let approvals = self.approvals.lock().unwrap();
+        let mut all = approvals
+            .as_ref()
+            .map(|a| a.votes.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        all.sort_by_key(|a| a.id);
+        all
+    }
+}
+
+impl Default for VoteApprovals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+
+    use crate::checkpoint::policy::{
+        earliest_vote_epoch, ApprovalStatus, VoteApprovals, DEFAULT_VOTE_FINALITY,
+    };
+    use crate::config::subnet::{VotePolicyConfig, VotePolicyKind};
+
+    #[test]
+    fn test_vote_approvals() {
+        let approvals = VoteApprovals::new();
+        let validator = Address::new_id(100);
+
+        let first = approvals.request("bottom-up", &validator, 10);
+        assert_eq!(first.status, ApprovalStatus::Pending);
+        // requesting the same vote again does not queue it twice
+        assert_eq!(approvals.request("bottom-up", &validator, 10).id, first.id);
+        let second = approvals.request("bottom-up", &validator, 20);
+
+        assert_eq!(
+            approvals.decide(first.id, true).unwrap().status,
+            ApprovalStatus::Approved
+        );
+        assert!(approvals.decide(first.id, false).is_err());
+        approvals.decide(second.id, false).unwrap();
+        assert_eq!(
+            approvals.request("bottom-up", &validator, 20).status,
+            ApprovalStatus::Rejected
+        );
+
+        approvals.prune("bottom-up", 10);
+        assert_eq!(approvals.all().len(), 1);
+        assert!(approvals.decide(first.id, true).is_err());
+    }
+
+    #[test]
+    fn test_earliest_vote_epoch() {
+        let mut config = VotePolicyConfig::default();
+        assert_eq!(earliest_vote_epoch(&config, 100), 101);
+
+        config.kind = VotePolicyKind::StrictLocal;
+        assert_eq!(
+            earliest_vote_epoch(&config, 100),
+            100 + DEFAULT_VOTE_FINALITY
+        );
+        config.finality = Some(0);
+        assert_eq!(earliest_vote_epoch(&config, 100), 101);
+
+        // the other policies wait for votes or approvals, not for epochs
+        config.kind = VotePolicyKind::ManualApproval;
+        assert_eq!(earliest_vote_epoch(&config, 100), 101);
+    }
+}
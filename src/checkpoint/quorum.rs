@@ -0,0 +1,385 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Monitoring of the votes of all the validators of the checkpointed subnets.
+//!
+//! The votes of the checkpoints not executed yet are counted periodically, for every validator of
+//! the subnet and not only those of the agent, against the quorum the checkpoint needs to be
+//! executed. A checkpoint that stays short of its quorum while its subnet moves on means that
+//! other validators are down, which the `quorum_stall` alert metric reports. The time each
+//! checkpoint took to reach its quorum is kept to follow the trend.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+
+use crate::checkpoint::{checkpoint_direction, setup_manager_from_subnet, CheckpointManager};
+use crate::config::ReloadableConfig;
+use crate::manager::clock::now;
+use crate::state::AgentState;
+
+/// How often the votes are counted.
+const QUORUM_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// The number of pending checkpoints of a direction whose votes are counted, the oldest first,
+/// as every validator is queried for each of them.
+const MAX_MONITORED_CHECKPOINTS: usize = 5;
+/// The number of checkpoints that reached their quorum kept for the trend of each direction.
+const MAX_QUORUM_SAMPLES: usize = 100;
+
+/// The number of votes a checkpoint needs to be executed among `validators`: two thirds of them.
+/// The validators are counted one each, their weights are not known to the agent.
+pub fn quorum(validators: usize) -> usize {
+    (validators * 2 + 2) / 3
+}
+
+/// The votes of a checkpoint not executed yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingQuorum {
+    pub epoch: ChainEpoch,
+    pub voted: usize,
+    pub validators: usize,
+    pub quorum: usize,
+    /// The head of the subnet whose epochs drive the submissions when the votes were counted.
+    pub head_epoch: ChainEpoch,
+    /// The unix timestamp, in seconds, the checkpoint was first seen pending.
+    pub first_seen: u64,
+}
+
+impl PendingQuorum {
+    /// The epochs the checkpoint has been waiting for its quorum, zero if it has it.
+    pub fn stalled_epochs(&self) -> ChainEpoch {
+        if self.voted >= self.quorum {
+            return 0;
+        }
+        (self.head_epoch - self.epoch).max(0)
+    }
+}
+
+/// A checkpoint that reached its quorum and was executed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuorumReached {
+    pub epoch: ChainEpoch,
+    /// The epochs past the checkpoint when it was seen executed.
+    pub epochs: ChainEpoch,
+    /// The seconds between the checkpoint being first seen pending and executed.
+    pub secs: u64,
+    /// The unix timestamp, in seconds, it was seen executed.
+    pub at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DirectionQuorum {
+    pub subnet: String,
+    /// `bottom-up` or `top-down`
+    pub direction: String,
+    pub last_executed_epoch: ChainEpoch,
+    pub pending: Vec<PendingQuorum>,
+    /// The last checkpoints that reached their quorum, the latest last.
+    pub reached: VecDeque<QuorumReached>,
+}
+
+impl DirectionQuorum {
+    fn new(subnet: &SubnetID, direction: &str) -> Self {
+        Self {
+            subnet: subnet.to_string(),
+            direction: direction.to_string(),
+            last_executed_epoch: 0,
+            pending: vec![],
+            reached: VecDeque::new(),
+        }
+    }
+
+    /// Records the votes counted on the `pending` checkpoints, as `(epoch, voted, validators)`,
+    /// with the subnet at `head_epoch`. Returns the checkpoints that were pending at the last
+    /// count and were executed since.
+    fn observe(
+        &mut self,
+        last_executed_epoch: ChainEpoch,
+        head_epoch: ChainEpoch,
+        pending: &[(ChainEpoch, usize, usize)],
+        now: u64,
+    ) -> Vec<QuorumReached> {
+        let mut reached = vec![];
+        for p in self.pending.iter() {
+            if p.epoch <= last_executed_epoch {
+                reached.push(QuorumReached {
+                    epoch: p.epoch,
+                    epochs: (head_epoch - p.epoch).max(0),
+                    secs: now.saturating_sub(p.first_seen),
+                    at: now,
+                });
+            }
+        }
+        self.reached.extend(reached.iter().cloned());
+        while self.reached.len() > MAX_QUORUM_SAMPLES {
+            self.reached.pop_front();
+        }
+
+        self.pending = pending
+            .iter()
+            .map(|(epoch, voted, validators)| PendingQuorum {
+                epoch: *epoch,
+                voted: *voted,
+                validators: *validators,
+                quorum: quorum(*validators),
+                head_epoch,
+                first_seen: self
+                    .pending
+                    .iter()
+                    .find(|p| p.epoch == *epoch)
+                    .map_or(now, |p| p.first_seen),
+            })
+            .collect();
+        self.last_executed_epoch = last_executed_epoch;
+        reached
+    }
+
+    /// The longest a pending checkpoint has been waiting for its quorum, in epochs.
+    pub fn stalled_epochs(&self) -> ChainEpoch {
+        self.pending
+            .iter()
+            .map(PendingQuorum::stalled_epochs)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The average time to quorum of the last checkpoints, in epochs and seconds.
+    pub fn average_time_to_quorum(&self) -> Option<(f64, f64)> {
+        if self.reached.is_empty() {
+            return None;
+        }
+        let n = self.reached.len() as f64;
+        let epochs = self.reached.iter().map(|r| r.epochs as f64).sum::<f64>();
+        let secs = self.reached.iter().map(|r| r.secs as f64).sum::<f64>();
+        Some((epochs / n, secs / n))
+    }
+}
+
+pub struct QuorumLog {
+    directions: Mutex<Option<HashMap<(SubnetID, String), DirectionQuorum>>>,
+}
+
+impl QuorumLog {
+    pub const fn new() -> Self {
+        Self {
+            directions: Mutex::new(None),
+        }
+    }
+
+    /// Records the votes counted on the pending checkpoints of the child `subnet` in
+    /// `direction`, see [`DirectionQuorum::observe`].
+    pub fn observe(
+        &self,
+        subnet: &SubnetID,
+        direction: &str,
+        last_executed_epoch: ChainEpoch,
+        head_epoch: ChainEpoch,
+        pending: &[(ChainEpoch, usize, usize)],
+    ) -> Vec<QuorumReached> {
+        let mut directions = self.directions.lock().unwrap();
+        directions
+            .get_or_insert_with(HashMap::new)
+            .entry((subnet.clone(), direction.to_string()))
+            .or_insert_with(|| DirectionQuorum::new(subnet, direction))
+            .observe(last_executed_epoch, head_epoch, pending, now())
+    }
+
+    /// Drops the directions of the subnets that are not checkpointed anymore.
+    pub fn retain(&self, keep: impl Fn(&SubnetID) -> bool) {
+        if let Some(directions) = self.directions.lock().unwrap().as_mut() {
+            directions.retain(|(subnet, _), _| keep(subnet));
+        }
+    }
+
+    /// Returns the votes of every subnet and direction, sorted by subnet.
+    pub fn all(&self) -> Vec<DirectionQuorum> {
+        let directions = self.directions.lock().unwrap();
+        let mut all = directions
+            .as_ref()
+            .map(|d| d.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        all.sort_by(|a, b| (&a.subnet, &a.direction).cmp(&(&b.subnet, &b.direction)));
+        all
+    }
+}
+
+impl Default for QuorumLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts the votes of the checkpoints of the subnets of the config until the agent shuts down.
+pub struct QuorumMonitor {
+    config: Arc<ReloadableConfig>,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: Arc<AgentState>,
+}
+
+impl QuorumMonitor {
+    pub fn new(
+        config: Arc<ReloadableConfig>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
+    ) -> Self {
+        Self {
+            config,
+            fvm_wallet,
+            evm_keystore,
+            state,
+        }
+    }
+
+    async fn poll(&self) {
+        // the subnets are read on every poll to follow the reloads of the config
+        let subnets = self.config.get_config().subnets.clone();
+        self.state
+            .quorum_monitor
+            .retain(|s| subnets.contains_key(s));
+        for subnet in subnets.values() {
+            if !subnet
+                .id
+                .parent()
+                .map_or(false, |p| subnets.contains_key(&p))
+            {
+                continue;
+            }
+            let managers = match setup_manager_from_subnet(
+                &subnets,
+                subnet,
+                self.fvm_wallet.clone(),
+                self.evm_keystore.clone(),
+                &self.state,
+            )
+            .await
+            {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!("cannot monitor the votes of subnet {}: {e:}", subnet.id);
+                    continue;
+                }
+            };
+            for m in managers.iter() {
+                if let Err(e) = count_votes(m.as_ref()).await {
+                    tracing::warn!("cannot count the votes of manager {m:}: {e:}");
+                }
+            }
+        }
+    }
+}
+
+/// Counts the votes of the validators of `manager` on its pending checkpoints.
+async fn count_votes(manager: &dyn CheckpointManager) -> Result<()> {
+    let period = manager.checkpoint_period();
+    if period <= 0 {
+        return Ok(());
+    }
+    let last_executed_epoch = manager.last_executed_epoch().await?;
+    let head_epoch = manager.current_epoch().await?;
+    let validators = manager.validators().await?;
+
+    let mut pending = vec![];
+    let mut epoch = last_executed_epoch + period;
+    while epoch < head_epoch && pending.len() < MAX_MONITORED_CHECKPOINTS {
+        let mut voted = 0;
+        for v in validators.iter() {
+            if !manager.should_submit_in_epoch(v, epoch).await? {
+                voted += 1;
+            }
+        }
+        pending.push((epoch, voted, validators.len()));
+        epoch += period;
+    }
+
+    let subnet = &manager.child_subnet().id;
+    let direction = checkpoint_direction(manager).as_str();
+    for reached in manager.state().quorum_monitor.observe(
+        subnet,
+        direction,
+        last_executed_epoch,
+        head_epoch,
+        &pending,
+    ) {
+        tracing::debug!(
+            "checkpoint of epoch {} of manager {manager:} reached quorum {} epochs after it",
+            reached.epoch,
+            reached.epochs
+        );
+        manager
+            .state()
+            .metrics
+            .observe_time_to_quorum(subnet, direction, reached.epochs);
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl IntoSubsystem<anyhow::Error> for QuorumMonitor {
+    async fn run(self, subsys: SubsystemHandle) -> Result<()> {
+        loop {
+            tokio::select! {
+                _ = sleep(QUORUM_POLL_INTERVAL) => self.poll().await,
+                _ = subsys.on_shutdown_requested() => {
+                    tracing::info!("Shutting down quorum monitor");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::checkpoint::quorum::{quorum, DirectionQuorum};
+
+    #[test]
+    fn test_quorum() {
+        assert_eq!(quorum(0), 0);
+        assert_eq!(quorum(1), 1);
+        assert_eq!(quorum(3), 2);
+        assert_eq!(quorum(4), 3);
+        assert_eq!(quorum(10), 7);
+    }
+
+    #[test]
+    fn test_direction_quorum() {
+        let mut d = DirectionQuorum::new(&SubnetID::new_root(123), "bottom-up");
+
+        assert!(d.observe(0, 25, &[(10, 1, 4), (20, 3, 4)], 1000).is_empty());
+        // the checkpoint at 10 is short of its quorum of 3 since epoch 10
+        assert_eq!(d.stalled_epochs(), 15);
+        assert!(d.average_time_to_quorum().is_none());
+
+        assert!(d
+            .observe(0, 35, &[(10, 2, 4), (20, 3, 4), (30, 0, 4)], 1030)
+            .is_empty());
+        assert_eq!(d.pending[0].first_seen, 1000);
+        assert_eq!(d.pending[2].first_seen, 1030);
+        assert_eq!(d.stalled_epochs(), 25);
+
+        let reached = d.observe(20, 45, &[(30, 3, 4), (40, 0, 4)], 1060);
+        assert_eq!(reached.len(), 2);
+        assert_eq!(
+            (reached[0].epoch, reached[0].epochs, reached[0].secs),
+            (10, 35, 60)
+        );
+        assert_eq!(
+            (reached[1].epoch, reached[1].epochs, reached[1].secs),
+            (20, 25, 60)
+        );
+        assert_eq!(d.average_time_to_quorum(), Some((30.0, 60.0)));
+        assert_eq!(d.stalled_epochs(), 5);
+    }
+}
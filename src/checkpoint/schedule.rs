@@ -0,0 +1,209 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Polling schedule of the checkpoint managers.
+//!
+//! Managers are polled about once per block of the subnet whose epochs drive their submissions,
+//! so that subnets with 1s blocks are not polled too slowly and chains with 30s blocks not more
+//! than needed. The block time is measured from the epochs observed in the polls, unless set
+//! in the config of the subnet, and the polls are jittered so that agents sharing a node do not
+//! query it in lockstep. Operators can also set the interval of the polls of a subnet outright.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ethers::core::rand::{thread_rng, Rng};
+use fvm_shared::clock::ChainEpoch;
+
+/// The bounds of the polling interval, whatever the block time of the subnet.
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// The polling interval until the block time of the subnet is measured.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// The weight of the last measurement in the block time, smoothing out null rounds.
+const BLOCK_TIME_SMOOTHING: f64 = 0.3;
+/// The maximum deviation of a poll from the interval, as a fraction of it.
+const POLL_JITTER: f64 = 0.1;
+
+struct ScheduleState {
+    /// The block time, configured or measured.
+    block_time: Option<Duration>,
+    /// The last epoch that advanced, and when it was observed.
+    last_epoch: Option<(ChainEpoch, Instant)>,
+    next_poll: Instant,
+}
+
+/// When to poll a manager next, derived from the block time of its subnet.
+pub struct PollSchedule {
+    /// Whether the block time is set in the config, and not measured.
+    configured: bool,
+    /// The interval between the polls set in the config, whatever the block time.
+    poll_interval: Option<Duration>,
+    state: Mutex<ScheduleState>,
+}
+
+impl PollSchedule {
+    /// Creates the schedule of a manager, `block_time` being the block time configured for the
+    /// subnet, if any. The first poll is due right away.
+    pub fn new(block_time: Option<Duration>) -> Self {
+        Self {
+            configured: block_time.is_some(),
+            poll_interval: None,
+            state: Mutex::new(ScheduleState {
+                block_time,
+                last_epoch: None,
+                next_poll: Instant::now(),
+            }),
+        }
+    }
+
+    /// Starts from `block_time`, e.g. calibrated from the chain, until the block time is
+    /// measured from the polls. The configured block time takes precedence.
+    pub fn with_initial_block_time(self, block_time: Option<Duration>) -> Self {
+        if !self.configured {
+            self.state.lock().unwrap().block_time = block_time;
+        }
+        self
+    }
+
+    /// Polls every `poll_interval`, if set, instead of once per block.
+    pub fn with_poll_interval(mut self, poll_interval: Option<Duration>) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Records the current epoch of the subnet observed `at`, measuring the block time from the
+    /// last epoch observed.
+    pub fn observe_epoch(&self, epoch: ChainEpoch, at: Instant) {
+        let mut state = self.state.lock().unwrap();
+        match state.last_epoch {
+            // the epoch did not advance since the last poll, measure from the last that did
+            Some((last, _)) if epoch <= last => return,
+            Some((last, last_at)) if !self.configured => {
+                let measured = at.duration_since(last_at).as_secs_f64() / (epoch - last) as f64;
+                let block_time = match state.block_time {
+                    Some(b) => {
+                        BLOCK_TIME_SMOOTHING * measured
+                            + (1.0 - BLOCK_TIME_SMOOTHING) * b.as_secs_f64()
+                    }
+                    None => measured,
+                };
+                state.block_time = Some(Duration::from_secs_f64(block_time));
+            }
+            _ => {}
+        }
+        state.last_epoch = Some((epoch, at));
+    }
+
+    /// The block time of the subnet, if known.
+    pub fn block_time(&self) -> Option<Duration> {
+        self.state.lock().unwrap().block_time
+    }
+
+    /// The interval between the polls, before jitter.
+    pub fn interval(&self) -> Duration {
+        if let Some(interval) = self.poll_interval {
+            return interval.max(MIN_POLL_INTERVAL);
+        }
+        self.block_time().map_or(DEFAULT_POLL_INTERVAL, |b| {
+            b.clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL)
+        })
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.state.lock().unwrap().next_poll <= now
+    }
+
+    pub fn next_poll(&self) -> Instant {
+        self.state.lock().unwrap().next_poll
+    }
+
+    /// Makes the next poll due at `now`, e.g. when notified of a new epoch.
+    pub fn poll_now(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.next_poll = state.next_poll.min(now);
+    }
+
+    /// Schedules the next poll one jittered interval after `now`.
+    pub fn schedule_next(&self, now: Instant) {
+        let jitter = thread_rng().gen_range(-POLL_JITTER..=POLL_JITTER);
+        let next_poll = now + self.interval().mul_f64(1.0 + jitter);
+        self.state.lock().unwrap().next_poll = next_poll;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::checkpoint::schedule::{PollSchedule, DEFAULT_POLL_INTERVAL};
+
+    #[test]
+    fn test_measured_block_time() {
+        let schedule = PollSchedule::new(None);
+        let start = Instant::now();
+        assert_eq!(schedule.interval(), DEFAULT_POLL_INTERVAL);
+        assert!(schedule.is_due(start));
+
+        schedule.observe_epoch(100, start);
+        assert_eq!(schedule.block_time(), None);
+        // the epoch did not advance
+        schedule.observe_epoch(100, start + Duration::from_secs(15));
+        assert_eq!(schedule.block_time(), None);
+
+        schedule.observe_epoch(101, start + Duration::from_secs(30));
+        assert_eq!(schedule.block_time(), Some(Duration::from_secs(30)));
+        assert_eq!(schedule.interval(), Duration::from_secs(30));
+
+        // a null round is smoothed out
+        schedule.observe_epoch(102, start + Duration::from_secs(90));
+        let block_time = schedule.block_time().unwrap().as_secs_f64();
+        assert!((block_time - 39.0).abs() < 1e-6, "{block_time:}");
+
+        // the calibrated block time is used until the polls measure it
+        let schedule =
+            PollSchedule::new(None).with_initial_block_time(Some(Duration::from_secs(5)));
+        assert_eq!(schedule.interval(), Duration::from_secs(5));
+        let schedule = PollSchedule::new(Some(Duration::from_secs(2)))
+            .with_initial_block_time(Some(Duration::from_secs(5)));
+        assert_eq!(schedule.interval(), Duration::from_secs(2));
+
+        // fast subnets are polled at most every second
+        let schedule = PollSchedule::new(None);
+        schedule.observe_epoch(100, start);
+        schedule.observe_epoch(110, start + Duration::from_secs(1));
+        assert_eq!(schedule.interval(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_configured_block_time() {
+        let schedule = PollSchedule::new(Some(Duration::from_secs(2)));
+        let start = Instant::now();
+        schedule.observe_epoch(100, start);
+        schedule.observe_epoch(101, start + Duration::from_secs(30));
+        assert_eq!(schedule.interval(), Duration::from_secs(2));
+
+        schedule.schedule_next(start);
+        assert!(!schedule.is_due(start));
+        let next = schedule.next_poll() - start;
+        assert!(next >= Duration::from_millis(1800) && next <= Duration::from_millis(2200));
+        assert!(schedule.is_due(start + Duration::from_secs(3)));
+
+        // a new epoch was notified before the next poll
+        schedule.poll_now(start + Duration::from_secs(1));
+        assert!(schedule.is_due(start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_configured_poll_interval() {
+        let schedule = PollSchedule::new(Some(Duration::from_secs(2)))
+            .with_poll_interval(Some(Duration::from_secs(120)));
+        let start = Instant::now();
+        schedule.observe_epoch(100, start);
+        schedule.observe_epoch(101, start + Duration::from_secs(1));
+        // not bound by the block time, nor by the maximum interval
+        assert_eq!(schedule.interval(), Duration::from_secs(120));
+
+        let schedule = PollSchedule::new(None).with_poll_interval(Some(Duration::ZERO));
+        assert_eq!(schedule.interval(), Duration::from_secs(1));
+    }
+}
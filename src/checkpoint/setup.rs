@@ -1,23 +1,33 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 use crate::checkpoint::CheckpointManager;
-use crate::config::subnet::NetworkType;
+use crate::config::subnet::{NetworkType, SubnetMode};
 use crate::config::Subnet;
 use crate::lotus::client::LotusJsonRPCClient;
+use crate::manager::clock;
 use crate::manager::fevm::FevmSubnetManager;
-use crate::manager::{EthSubnetManager, LotusSubnetManager};
+use crate::manager::signer::WalletSigner;
+use crate::manager::{EthSubnetManager, LotusSubnetManager, SubnetManager};
+use crate::state::AgentState;
 use anyhow::anyhow;
+use futures_util::future::join_all;
+use fvm_shared::clock::ChainEpoch;
 use ipc_identity::PersistentKeyStore;
 use ipc_identity::Wallet;
 use ipc_sdk::subnet_id::SubnetID;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The time to wait for the node of a subnet to answer a health check.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
 
 async fn parent_fevm_child_fvm(
     parent: &Subnet,
     child: &Subnet,
     fvm_wallet_store: Arc<RwLock<Wallet>>,
     evm_wallet_store: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
 ) -> anyhow::Result<Vec<Box<dyn CheckpointManager>>> {
     if parent.network_type() != NetworkType::Fevm || child.network_type() != NetworkType::Fvm {
         return Err(anyhow!("parent not fevm or child not fvm"));
@@ -25,35 +35,80 @@ async fn parent_fevm_child_fvm(
 
     let mut managers = vec![];
 
-    let fevm = FevmSubnetManager::new(
-        EthSubnetManager::from_subnet_with_wallet_store(parent, evm_wallet_store.clone())?,
-        LotusJsonRPCClient::from_subnet_with_wallet_store(child, fvm_wallet_store.clone()),
-    );
-    let fvm = LotusSubnetManager::new(
-        LotusJsonRPCClient::from_subnet_with_wallet_store(child, fvm_wallet_store.clone()),
-        child.gateway_addr(),
-    );
-    let m: Box<dyn CheckpointManager> = Box::new(
-        crate::checkpoint::bottomup::BottomUpManager::new(parent.clone(), child.clone(), fevm, fvm)
-            .await?,
-    );
-
-    managers.push(m);
-
-    let fevm = FevmSubnetManager::new(
-        EthSubnetManager::from_subnet_with_wallet_store(parent, evm_wallet_store.clone())?,
-        LotusJsonRPCClient::from_subnet_with_wallet_store(child, fvm_wallet_store.clone()),
-    );
-    let fvm = LotusSubnetManager::new(
-        LotusJsonRPCClient::from_subnet_with_wallet_store(child, fvm_wallet_store.clone()),
-        child.gateway_addr(),
-    );
-    let m: Box<dyn CheckpointManager> = Box::new(
-        crate::checkpoint::topdown::TopDownManager::new(parent.clone(), child.clone(), fevm, fvm)
+    if child.features.bottom_up {
+        let fevm = FevmSubnetManager::new(
+            EthSubnetManager::from_subnet_with_wallet_store(
+                parent,
+                evm_wallet_store.clone(),
+                state.clone(),
+            )?,
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                child,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+        );
+        let fvm = LotusSubnetManager::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                child,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+            child.gateway_addr(),
+        );
+        let m: Box<dyn CheckpointManager> = Box::new(
+            crate::checkpoint::bottomup::BottomUpManager::new(
+                parent.clone(),
+                child.clone(),
+                fevm,
+                fvm,
+                state.clone(),
+            )
+            .await?
+            .with_signer(Arc::new(WalletSigner::new(fvm_wallet_store.clone()))),
+        );
+
+        managers.push(m);
+    } else {
+        tracing::info!("bottom-up checkpoints disabled for subnet: {:}", child.id);
+    }
+
+    if child.features.top_down {
+        let fevm = FevmSubnetManager::new(
+            EthSubnetManager::from_subnet_with_wallet_store(
+                parent,
+                evm_wallet_store.clone(),
+                state.clone(),
+            )?,
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                child,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+        );
+        let fvm = LotusSubnetManager::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                child,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+            child.gateway_addr(),
+        );
+        let m: Box<dyn CheckpointManager> = Box::new(
+            crate::checkpoint::topdown::TopDownManager::new(
+                parent.clone(),
+                child.clone(),
+                fevm,
+                fvm,
+                state.clone(),
+            )
             .await?,
-    );
+        );
 
-    managers.push(m);
+        managers.push(m);
+    } else {
+        tracing::info!("top-down checkpoints disabled for subnet: {:}", child.id);
+    }
 
     Ok(managers)
 }
@@ -62,6 +117,7 @@ async fn parent_fvm_child_fvm(
     parent: &Subnet,
     child: &Subnet,
     fvm_wallet_store: Arc<RwLock<Wallet>>,
+    state: &Arc<AgentState>,
 ) -> anyhow::Result<Vec<Box<dyn CheckpointManager>>> {
     if parent.network_type() != NetworkType::Fevm || child.network_type() != NetworkType::Fvm {
         return Err(anyhow!("parent not fevm or child not fvm"));
@@ -69,45 +125,72 @@ async fn parent_fvm_child_fvm(
 
     let mut managers = vec![];
 
-    let fvm_p = LotusSubnetManager::new(
-        LotusJsonRPCClient::from_subnet_with_wallet_store(parent, fvm_wallet_store.clone()),
-        parent.gateway_addr(),
-    );
-    let fvm_c = LotusSubnetManager::new(
-        LotusJsonRPCClient::from_subnet_with_wallet_store(child, fvm_wallet_store.clone()),
-        child.gateway_addr(),
-    );
-    let m: Box<dyn CheckpointManager> = Box::new(
-        crate::checkpoint::bottomup::BottomUpManager::new(
-            parent.clone(),
-            child.clone(),
-            fvm_p,
-            fvm_c,
-        )
-        .await?,
-    );
-
-    managers.push(m);
-
-    let fvm_p = LotusSubnetManager::new(
-        LotusJsonRPCClient::from_subnet_with_wallet_store(parent, fvm_wallet_store.clone()),
-        parent.gateway_addr(),
-    );
-    let fvm_c = LotusSubnetManager::new(
-        LotusJsonRPCClient::from_subnet_with_wallet_store(child, fvm_wallet_store),
-        child.gateway_addr(),
-    );
-    let m: Box<dyn CheckpointManager> = Box::new(
-        crate::checkpoint::topdown::TopDownManager::new(
-            parent.clone(),
-            child.clone(),
-            fvm_p,
-            fvm_c,
-        )
-        .await?,
-    );
+    if child.features.bottom_up {
+        let fvm_p = LotusSubnetManager::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                parent,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+            parent.gateway_addr(),
+        );
+        let fvm_c = LotusSubnetManager::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                child,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+            child.gateway_addr(),
+        );
+        let m: Box<dyn CheckpointManager> = Box::new(
+            crate::checkpoint::bottomup::BottomUpManager::new(
+                parent.clone(),
+                child.clone(),
+                fvm_p,
+                fvm_c,
+                state.clone(),
+            )
+            .await?
+            .with_signer(Arc::new(WalletSigner::new(fvm_wallet_store.clone()))),
+        );
 
-    managers.push(m);
+        managers.push(m);
+    } else {
+        tracing::info!("bottom-up checkpoints disabled for subnet: {:}", child.id);
+    }
+
+    if child.features.top_down {
+        let fvm_p = LotusSubnetManager::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                parent,
+                fvm_wallet_store.clone(),
+                state.clone(),
+            ),
+            parent.gateway_addr(),
+        );
+        let fvm_c = LotusSubnetManager::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(
+                child,
+                fvm_wallet_store,
+                state.clone(),
+            ),
+            child.gateway_addr(),
+        );
+        let m: Box<dyn CheckpointManager> = Box::new(
+            crate::checkpoint::topdown::TopDownManager::new(
+                parent.clone(),
+                child.clone(),
+                fvm_p,
+                fvm_c,
+                state.clone(),
+            )
+            .await?,
+        );
+
+        managers.push(m);
+    } else {
+        tracing::info!("top-down checkpoints disabled for subnet: {:}", child.id);
+    }
 
     Ok(managers)
 }
@@ -117,25 +200,26 @@ pub async fn setup_manager_from_subnet(
     s: &Subnet,
     fvm_wallet_store: Arc<RwLock<Wallet>>,
     evm_wallet_store: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
 ) -> anyhow::Result<Vec<Box<dyn CheckpointManager>>> {
     let parent = if let Some(p) = s.id.parent() && subnets.contains_key(&p) {
         subnets.get(&p).unwrap()
     } else {
-        log::info!("subnet has no parent configured: {:}, not managing checkpoints", s.id);
+        tracing::info!("subnet has no parent configured: {:}, not managing checkpoints", s.id);
         return Ok(vec![]);
     };
 
     match (parent.network_type(), s.network_type()) {
         (NetworkType::Fvm, NetworkType::Fvm) => {
-            log::info!("setup parent: {:?} fvm, child: {:?} fvm", parent.id, s.id);
-            parent_fvm_child_fvm(parent, s, fvm_wallet_store).await
+            tracing::info!("setup parent: {:?} fvm, child: {:?} fvm", parent.id, s.id);
+            parent_fvm_child_fvm(parent, s, fvm_wallet_store, state).await
         }
         (NetworkType::Fvm, NetworkType::Fevm) => {
             unimplemented!()
         }
         (NetworkType::Fevm, NetworkType::Fvm) => {
-            log::info!("setup parent: {:?} fevm, child: {:?} fvm", parent.id, s.id);
-            parent_fevm_child_fvm(parent, s, fvm_wallet_store, evm_wallet_store).await
+            tracing::info!("setup parent: {:?} fevm, child: {:?} fvm", parent.id, s.id);
+            parent_fevm_child_fvm(parent, s, fvm_wallet_store, evm_wallet_store, state).await
         }
         (NetworkType::Fevm, NetworkType::Fevm) => {
             todo!()
@@ -143,28 +227,145 @@ pub async fn setup_manager_from_subnet(
     }
 }
 
+/// Checks that the node of each of `subnets` can be reached and serves the network the subnet is
+/// pinned to, recording the result in the subnet health log of `state`.
+pub async fn check_subnets_health(
+    subnets: impl IntoIterator<Item = &Subnet>,
+    fvm_wallet_store: Arc<RwLock<Wallet>>,
+    evm_wallet_store: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
+) {
+    let checks = subnets.into_iter().map(|s| {
+        let fvm_wallet_store = fvm_wallet_store.clone();
+        let evm_wallet_store = evm_wallet_store.clone();
+        async move {
+            let r = check_subnet_health(s, fvm_wallet_store, evm_wallet_store, state).await;
+            match r {
+                Ok(epoch) => {
+                    tracing::debug!("subnet {:} is reachable, head at epoch {epoch:}", s.id);
+                    state.subnet_health.mark_healthy(&s.id);
+                }
+                Err(e) => {
+                    tracing::warn!("subnet {:} is unhealthy: {e:}", s.id);
+                    state.subnet_health.mark_unhealthy(&s.id, e);
+                }
+            }
+        }
+    });
+    join_all(checks).await;
+}
+
+async fn check_subnet_health(
+    s: &Subnet,
+    fvm_wallet_store: Arc<RwLock<Wallet>>,
+    evm_wallet_store: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
+) -> anyhow::Result<ChainEpoch> {
+    let manager: Box<dyn SubnetManager> = match s.network_type() {
+        NetworkType::Fvm => Box::new(LotusSubnetManager::from_subnet_with_wallet_store(
+            s,
+            fvm_wallet_store,
+            state.clone(),
+        )),
+        NetworkType::Fevm => Box::new(EthSubnetManager::from_subnet_with_wallet_store(
+            s,
+            evm_wallet_store,
+            state.clone(),
+        )?),
+    };
+
+    let epoch = probe_subnet(s, manager.as_ref()).await?;
+
+    // calibrated while healthy, so that the managers set up next start polling at the pace of
+    // the subnet
+    match tokio::time::timeout(
+        HEALTH_CHECK_TIMEOUT,
+        clock::calibration(&state.epoch_calibrations, s, manager.as_ref()),
+    )
+    .await
+    {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::debug!("cannot calibrate the clock of subnet {:}: {e:}", s.id),
+        Err(_) => tracing::debug!("calibration of the clock of subnet {:} timed out", s.id),
+    }
+
+    Ok(epoch)
+}
+
+/// Checks that the node of `s`, reached through `manager`, answers in time and serves the network
+/// the subnet is pinned to. Returns the epoch of its head.
+pub async fn probe_subnet(s: &Subnet, manager: &dyn SubnetManager) -> anyhow::Result<ChainEpoch> {
+    let check = async {
+        let epoch = manager
+            .chain_head_epoch()
+            .await
+            .map_err(|e| anyhow!("node at {} is unreachable: {e:}", s.rpc_http()))?;
+        manager.ensure_network().await?;
+        Ok::<_, anyhow::Error>(epoch)
+    };
+    tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check)
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "node at {} did not answer in {}s",
+                s.rpc_http(),
+                HEALTH_CHECK_TIMEOUT.as_secs()
+            )
+        })?
+}
+
 pub async fn setup_managers_from_config(
     subnets: &HashMap<SubnetID, Subnet>,
     fvm_wallet_store: Arc<RwLock<Wallet>>,
     evm_wallet_store: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
 ) -> anyhow::Result<Vec<Box<dyn CheckpointManager>>> {
     let mut managers = vec![];
 
     for s in subnets.values() {
-        log::info!("config checkpoint manager for subnet: {:}", s.id);
+        // The managers of a subnet need the nodes of both the subnet and its parent.
+        let health = &state.subnet_health;
+        let parent_healthy = s.id.parent().map_or(true, |p| health.is_healthy(&p));
+        if !health.is_healthy(&s.id) || !parent_healthy {
+            tracing::warn!(
+                "not managing checkpoints for subnet: {:} until it and its parent are healthy",
+                s.id
+            );
+            continue;
+        }
+
+        tracing::info!("config checkpoint manager for subnet: {:}", s.id);
 
-        let subnet_managers = setup_manager_from_subnet(
+        match setup_manager_from_subnet(
             subnets,
             s,
             fvm_wallet_store.clone(),
             evm_wallet_store.clone(),
+            state,
         )
-        .await?;
-        managers.extend(subnet_managers);
+        .await
+        {
+            Ok(subnet_managers) => managers.extend(subnet_managers),
+            Err(e) => {
+                tracing::warn!(
+                    "cannot setup checkpoint managers for subnet: {:}: {e:}",
+                    s.id
+                );
+                state.subnet_health.mark_unhealthy(&s.id, e);
+            }
+        }
     }
 
+    managers.retain(|m| {
+        let observed = m.target_subnet().mode == SubnetMode::Observe;
+        if observed {
+            tracing::info!("not submitting checkpoints with: {m:}, target subnet in observe mode");
+        }
+        !observed
+    });
+
     for m in managers.iter() {
-        log::info!("we are managing checkpoints with: {m:}");
+        tracing::info!("we are managing checkpoints with: {m:}");
     }
 
     Ok(managers)
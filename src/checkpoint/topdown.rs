@@ -1,15 +1,21 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
-use crate::checkpoint::{CheckpointManager, CheckpointMetadata, CheckpointQuery};
+use crate::checkpoint::{
+    content_cid, publish_relayed, CatchUp, CheckpointManager, CheckpointMetadata, CheckpointQuery,
+    IdleTracker, PendingSubmissions, PendingVotes, PollSchedule, SubmittedCheckpoint,
+};
 use crate::config::Subnet;
+use crate::state::AgentState;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use cid::Cid;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use ipc_gateway::TopDownCheckpoint;
 use ipc_sdk::cross::CrossMsg;
 use ipc_sdk::subnet_id::SubnetID;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
 /// The trait that handles the bottom up checkpoint submission data preparation and actual submission.
 #[async_trait]
@@ -30,13 +36,14 @@ pub trait TopDownHandler: Send + Sync + CheckpointQuery<TopDownCheckpoint> {
         &self,
         validator: &Address,
         checkpoint: TopDownCheckpoint,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<SubmittedCheckpoint>;
 }
 
 pub struct TopDownManager<P: TopDownHandler, C: TopDownHandler> {
     metadata: CheckpointMetadata,
     parent_handler: P,
     child_handler: C,
+    catch_up: CatchUp,
 }
 
 impl<P: TopDownHandler, C: TopDownHandler> TopDownManager<P, C> {
@@ -45,7 +52,17 @@ impl<P: TopDownHandler, C: TopDownHandler> TopDownManager<P, C> {
         child: Subnet,
         parent_handler: P,
         child_handler: C,
+        state: Arc<AgentState>,
     ) -> Result<Self> {
+        // the current epoch of the manager is the one of the parent
+        let schedule = PollSchedule::new(parent.block_time())
+            .with_initial_block_time(
+                state
+                    .epoch_calibrations
+                    .get(&parent.id)
+                    .map(|c| c.block_time),
+            )
+            .with_poll_interval(child.checkpoint_poll_interval());
         let period = child_handler
             .checkpoint_period(&child.id)
             .await
@@ -55,9 +72,38 @@ impl<P: TopDownHandler, C: TopDownHandler> TopDownManager<P, C> {
                 parent,
                 child,
                 period,
+                pending: Default::default(),
+                idle: Default::default(),
+                schedule,
+                state,
             },
             parent_handler,
             child_handler,
+            catch_up: Default::default(),
+        })
+    }
+
+    /// Builds the checkpoint at `epoch` with the top-down messages of the parent after the nonce
+    /// already applied in the child.
+    async fn checkpoint(&self, epoch: ChainEpoch) -> Result<TopDownCheckpoint> {
+        let nonce = self
+            .child_handler
+            .applied_topdown_nonce(&self.metadata.child.id)
+            .await?;
+        tracing::info!("latest applied top down nonce for {self:}: {nonce}");
+
+        let top_down_msgs = self
+            .parent_handler
+            .top_down_msgs(&self.metadata.child.id, nonce, epoch)
+            .await?;
+        tracing::info!(
+            "top down messages to execute for {self:}: {:}",
+            top_down_msgs.len()
+        );
+
+        Ok(TopDownCheckpoint {
+            epoch,
+            top_down_msgs,
         })
     }
 }
@@ -88,6 +134,10 @@ impl<P: TopDownHandler, C: TopDownHandler> CheckpointManager for TopDownManager<
         &self.metadata.child
     }
 
+    fn epoch_subnet(&self) -> &Subnet {
+        &self.metadata.parent
+    }
+
     fn checkpoint_period(&self) -> ChainEpoch {
         self.metadata.period
     }
@@ -108,43 +158,36 @@ impl<P: TopDownHandler, C: TopDownHandler> CheckpointManager for TopDownManager<
         self.parent_handler.current_epoch().await
     }
 
-    async fn submit_checkpoint(&self, epoch: ChainEpoch, validator: &Address) -> Result<()> {
-        let nonce = self
-            .child_handler
-            .applied_topdown_nonce(&self.metadata.child.id)
-            .await?;
-        log::info!("latest applied top down nonce for {self:}: {nonce}");
-
-        let top_down_msgs = self
-            .parent_handler
-            .top_down_msgs(&self.metadata.child.id, nonce, epoch)
-            .await?;
-        log::info!(
-            "top down messages to execute for {self:}: {:}",
-            top_down_msgs.len()
-        );
+    async fn submit_checkpoint(
+        &self,
+        epoch: ChainEpoch,
+        validator: &Address,
+    ) -> Result<SubmittedCheckpoint> {
+        let topdown_checkpoint = self.checkpoint(epoch).await?;
+        tracing::info!("top down checkpoint to submit: {topdown_checkpoint:?}");
 
         // we submit the topdown messages to the CHILD subnet.
-        let topdown_checkpoint = TopDownCheckpoint {
-            epoch,
-            top_down_msgs,
-        };
-
-        log::info!("top down checkpoint to submit: {topdown_checkpoint:?}");
-
-        let submitted_epoch = self
+        let relayed = topdown_checkpoint.top_down_msgs.clone();
+        let submitted = self
             .child_handler
             .submit(validator, topdown_checkpoint)
             .await?;
 
-        log::info!(
+        tracing::info!(
             "checkpoint at epoch {:} for manager: {:} published with at epoch: {:?}, executed",
             epoch,
             self,
-            submitted_epoch,
+            submitted.epoch,
         );
+        publish_relayed(self, epoch, &relayed);
+
+        Ok(submitted)
+    }
 
-        Ok(())
+    /// The cid of the checkpoint at `epoch`, i.e. of the top-down messages after the nonce applied
+    /// in the child.
+    async fn checkpoint_cid(&self, epoch: ChainEpoch) -> Result<Cid> {
+        content_cid(&self.checkpoint(epoch).await?)
     }
 
     async fn should_submit_in_epoch(&self, validator: &Address, epoch: ChainEpoch) -> Result<bool> {
@@ -162,4 +205,30 @@ impl<P: TopDownHandler, C: TopDownHandler> CheckpointManager for TopDownManager<
             self.parent_handler.gateway_initialized().await
         }
     }
+
+    async fn pending_votes(&self, validator: &Address) -> Result<PendingVotes> {
+        self.child_handler
+            .pending_votes(&self.metadata.child.id, validator)
+            .await
+    }
+
+    fn pending_submissions(&self) -> &PendingSubmissions {
+        &self.metadata.pending
+    }
+
+    fn idle_tracker(&self) -> &IdleTracker {
+        &self.metadata.idle
+    }
+
+    fn poll_schedule(&self) -> &PollSchedule {
+        &self.metadata.schedule
+    }
+
+    fn state(&self) -> &AgentState {
+        &self.metadata.state
+    }
+
+    fn catch_up(&self) -> Option<&CatchUp> {
+        Some(&self.catch_up)
+    }
 }
@@ -0,0 +1,169 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Watchdog of the checkpoint iterations of the managers.
+//!
+//! An iteration that hangs, e.g. on an RPC call to a node that never answers, would block the
+//! submissions of its manager forever. The watchdog cancels the iterations that do not complete in
+//! time, so that they start over in the next round, and logs the stages the iteration went
+//! through to help find where it got stuck. The deadline of an iteration follows the checkpoint
+//! period of its manager, as an iteration stuck for longer already misses the next checkpoint.
+//!
+//! The iterations are only cancelled at an `.await`: an iteration blocked without yielding, e.g.
+//! deadlocked on a std `Mutex` or in a blocking call, is not cancelled by the watchdog and keeps
+//! the thread it runs on blocked.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+
+use crate::metrics::Counter;
+
+/// The minimum time a checkpoint iteration of a manager can take. Submissions are delayed and wait
+/// for their message to be executed, so an iteration can take a few blocks per validator.
+pub const MIN_ITERATION_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+/// The maximum time a checkpoint iteration can take, whatever the checkpoint period.
+pub const MAX_ITERATION_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// The time an iteration of a manager can take: two checkpoint periods of `period` epochs of
+/// `block_time`, within [`MIN_ITERATION_TIMEOUT`] and [`MAX_ITERATION_TIMEOUT`].
+pub fn iteration_timeout(block_time: Duration, period: ChainEpoch) -> Duration {
+    let periods = u32::try_from(period.max(1))
+        .unwrap_or(u32::MAX)
+        .saturating_mul(2);
+    block_time
+        .saturating_mul(periods)
+        .clamp(MIN_ITERATION_TIMEOUT, MAX_ITERATION_TIMEOUT)
+}
+
+/// The stages an iteration went through, with the time they were entered at.
+pub struct IterationTrace {
+    start: Instant,
+    stages: Mutex<Vec<(Duration, String)>>,
+}
+
+impl IterationTrace {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            stages: Mutex::new(vec![]),
+        }
+    }
+
+    /// Records that the iteration entered `stage`.
+    pub fn enter(&self, stage: impl Into<String>) {
+        self.stages
+            .lock()
+            .unwrap()
+            .push((self.start.elapsed(), stage.into()));
+    }
+
+    /// Returns the stages entered so far, one per line, the last one being where the iteration is.
+    pub fn dump(&self) -> String {
+        self.stages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(at, stage)| format!("  +{}ms {stage:}", at.as_millis()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for IterationTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs an iteration of `manager`, cancelling it if it does not complete within `timeout` and
+/// counting the cancellation in `restarts`.
+pub async fn watch<M, F>(
+    manager: M,
+    timeout: Duration,
+    trace: &IterationTrace,
+    restarts: &Counter,
+    iteration: F,
+) -> Result<()>
+where
+    M: Display,
+    F: Future<Output = Result<()>>,
+{
+    match tokio::time::timeout(timeout, iteration).await {
+        Ok(r) => r,
+        Err(_) => {
+            tracing::error!(
+                "checkpoint iteration of manager: {manager:} did not complete in {}s, cancelling it to restart in the next round. Stages reached:\n{}",
+                timeout.as_secs(),
+                trace.dump()
+            );
+            restarts.inc();
+            Err(anyhow!("checkpoint iteration timed out"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::checkpoint::watchdog::{
+        iteration_timeout, watch, IterationTrace, MAX_ITERATION_TIMEOUT, MIN_ITERATION_TIMEOUT,
+    };
+    use crate::metrics::Counter;
+
+    #[test]
+    fn test_iteration_timeout() {
+        assert_eq!(
+            iteration_timeout(Duration::from_secs(1), 10),
+            MIN_ITERATION_TIMEOUT
+        );
+        // two periods of 60 epochs of 30s
+        assert_eq!(
+            iteration_timeout(Duration::from_secs(30), 60),
+            Duration::from_secs(60 * 60)
+        );
+        assert_eq!(
+            iteration_timeout(Duration::from_secs(30), 1000),
+            MAX_ITERATION_TIMEOUT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancels_stuck_iteration() {
+        let trace = IterationTrace::new();
+        let restarts = Counter::new("checkpoint_task_restarts");
+
+        let r = watch(
+            "test",
+            Duration::from_millis(10),
+            &trace,
+            &restarts,
+            async {
+                trace.enter("waiting for the node");
+                std::future::pending::<()>().await;
+                Ok(())
+            },
+        )
+        .await;
+
+        assert!(r.is_err());
+        assert_eq!(restarts.get(), 1);
+        assert!(trace.dump().ends_with("waiting for the node"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_iteration() {
+        let trace = IterationTrace::new();
+        let restarts = Counter::new("checkpoint_task_restarts");
+        let r = watch("test", Duration::from_secs(1), &trace, &restarts, async {
+            Ok(())
+        })
+        .await;
+        assert!(r.is_ok());
+        assert_eq!(restarts.get(), 0);
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Abort pending operation cli command
+
+use std::fmt::Debug;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to abort an operation parked for approval, or abandon a pending message.
+pub(crate) struct AbortPendingOperation;
+
+#[async_trait]
+impl CommandLineHandler for AbortPendingOperation {
+    type Arguments = AbortPendingOperationArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("abort pending operation with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = match (arguments.id, &arguments.subnet, arguments.nonce) {
+            (Some(id), None, None) => client.abort_operation(id).await?,
+            (None, Some(subnet), Some(nonce)) => {
+                client
+                    .abandon_pending_message(subnet, arguments.from.clone(), nonce)
+                    .await?
+            }
+            _ => return Err(anyhow!("either --id, or --subnet and --nonce, must be set")),
+        };
+
+        if let Some(op) = response.operation {
+            tracing::info!("operation {} ({}) aborted", op.id, op.method);
+        }
+        if let Some(msg) = response.abandoned {
+            tracing::info!(
+                "message {} of {} with nonce {} abandoned, the next message of {} replaces it",
+                msg.cid,
+                msg.from,
+                msg.nonce,
+                msg.from
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Abort an operation parked for approval, or abandon a message pending in a subnet so that the next message of its sender reuses its nonce"
+)]
+pub(crate) struct AbortPendingOperationArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, help = "The id of the parked operation")]
+    pub id: Option<u64>,
+    #[arg(long, help = "The subnet the message is pending in")]
+    pub subnet: Option<String>,
+    #[arg(
+        long,
+        help = "The sender of the message, the first account of the subnet if not set"
+    )]
+    pub from: Option<String>,
+    #[arg(long, help = "The nonce of the pending message")]
+    pub nonce: Option<u64>,
+}
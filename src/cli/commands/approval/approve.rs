@@ -0,0 +1,56 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Approve operation cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to approve, and execute, or reject a parked operation.
+pub(crate) struct ApproveOperation;
+
+#[async_trait]
+impl CommandLineHandler for ApproveOperation {
+    type Arguments = ApproveOperationArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("approve operation with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = client
+            .approve_operation(arguments.id, !arguments.reject)
+            .await?;
+
+        match response.result {
+            Some(result) => tracing::info!(
+                "operation {} ({}) approved and executed with result: {result:}",
+                response.operation.id,
+                response.operation.method
+            ),
+            None => tracing::info!(
+                "operation {} ({}) rejected",
+                response.operation.id,
+                response.operation.method
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Approve, and execute, or reject an operation parked for approval")]
+pub(crate) struct ApproveOperationArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, help = "The id of the parked operation")]
+    pub id: u64,
+    #[arg(long, help = "Reject the operation instead of approving it")]
+    pub reject: bool,
+}
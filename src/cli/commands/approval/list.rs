@@ -0,0 +1,51 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List operation approvals cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the operations parked for approval.
+pub(crate) struct ListOperationApprovals;
+
+#[async_trait]
+impl CommandLineHandler for ListOperationApprovals {
+    type Arguments = ListOperationApprovalsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list operation approvals with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let operations = client.list_operation_approvals().await?;
+
+        if operations.is_empty() {
+            tracing::info!("no operations parked for approval");
+        }
+        for op in operations.iter() {
+            tracing::info!(
+                "operation {}: {:?} - {}, method={}, expires_at={}",
+                op.id,
+                op.status,
+                op.reason,
+                op.method,
+                op.expires_at
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the operations parked for approval")]
+pub(crate) struct ListOperationApprovalsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+}
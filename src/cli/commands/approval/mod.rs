@@ -0,0 +1,41 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::cli::{CommandLineHandler, GlobalArguments};
+
+use clap::{Args, Subcommand};
+
+use self::abort::{AbortPendingOperation, AbortPendingOperationArgs};
+use self::approve::{ApproveOperation, ApproveOperationArgs};
+use self::list::{ListOperationApprovals, ListOperationApprovalsArgs};
+
+mod abort;
+mod approve;
+mod list;
+
+#[derive(Debug, Args)]
+#[command(
+    name = "approval",
+    about = "approval of the operations parked by the agent"
+)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct ApprovalCommandsArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl ApprovalCommandsArgs {
+    pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
+        match &self.command {
+            Commands::List(args) => ListOperationApprovals::handle(global, args).await,
+            Commands::Approve(args) => ApproveOperation::handle(global, args).await,
+            Commands::Abort(args) => AbortPendingOperation::handle(global, args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    List(ListOperationApprovalsArgs),
+    Approve(ApproveOperationArgs),
+    Abort(AbortPendingOperationArgs),
+}
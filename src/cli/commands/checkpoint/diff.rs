@@ -0,0 +1,96 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bottom-up checkpoint diff cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to compare the bottom-up checkpoint template of an epoch with the one committed.
+pub(crate) struct BottomUpCheckpointDiff;
+
+#[async_trait]
+impl CommandLineHandler for BottomUpCheckpointDiff {
+    type Arguments = BottomUpCheckpointDiffArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("bottom up checkpoint diff with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let diff = client
+            .bottom_up_checkpoint_diff(&arguments.subnet, arguments.epoch)
+            .await?;
+
+        if !diff.committed {
+            tracing::info!(
+                "no checkpoint committed for epoch {}, the template has {} message(s) with fee {}",
+                diff.epoch,
+                diff.only_in_template.len(),
+                diff.template_fee
+            );
+            return Ok(());
+        }
+        if diff.matches {
+            tracing::info!(
+                "committed checkpoint at epoch {} matches the template",
+                diff.epoch
+            );
+            return Ok(());
+        }
+
+        tracing::info!(
+            "committed checkpoint at epoch {} differs from the template",
+            diff.epoch
+        );
+        for m in diff.only_in_template.iter() {
+            tracing::info!(
+                "- only in template: nonce {} - from={}, to={}, value={}, method={}",
+                m.nonce,
+                m.from,
+                m.to,
+                m.value,
+                m.method
+            );
+        }
+        for m in diff.only_in_committed.iter() {
+            tracing::info!(
+                "+ only committed: nonce {} - from={}, to={}, value={}, method={}",
+                m.nonce,
+                m.from,
+                m.to,
+                m.value,
+                m.method
+            );
+        }
+        if diff.committed_fee.as_ref() != Some(&diff.template_fee) {
+            tracing::info!(
+                "fee: template {}, committed {}",
+                diff.template_fee,
+                diff.committed_fee.unwrap_or_default()
+            );
+        }
+        for child in diff.children.iter() {
+            tracing::info!("checks of child subnet {child:} differ");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Compare the bottom-up checkpoint template of an epoch with the committed one")]
+pub(crate) struct BottomUpCheckpointDiffArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id of the checkpointing subnet")]
+    pub subnet: String,
+    #[arg(long, short, help = "The epoch of the checkpoint")]
+    pub epoch: ChainEpoch,
+}
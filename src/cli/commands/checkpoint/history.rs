@@ -0,0 +1,133 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Checkpoint history cli commands
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::history::backfill::BackfillParams;
+use crate::sdk::IpcAgentClient;
+
+/// The command to backfill the checkpoint history of a subnet.
+pub(crate) struct BackfillHistory;
+
+#[async_trait]
+impl CommandLineHandler for BackfillHistory {
+    type Arguments = BackfillHistoryArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("backfill history with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let params = BackfillParams {
+            subnet: arguments.subnet.clone(),
+            from_epoch: arguments.from_epoch,
+            to_epoch: arguments.to_epoch,
+            endpoint: arguments.endpoint.clone(),
+            batch_epochs: arguments.batch_epochs,
+            max_requests_per_sec: arguments.max_requests_per_sec,
+        };
+        let job = client.backfill_history(params).await?;
+
+        tracing::info!(
+            "backfill of {} running as job {} ({:.1}%)",
+            arguments.subnet,
+            job.id,
+            job.progress
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Backfill the checkpoint history of a subnet, resuming the last backfill if unfinished"
+)]
+pub(crate) struct BackfillHistoryArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id of the checkpointing subnet")]
+    pub subnet: String,
+    #[arg(
+        long,
+        short,
+        help = "Backfill from this epoch, after the last checkpoint indexed by default"
+    )]
+    pub from_epoch: Option<ChainEpoch>,
+    #[arg(
+        long,
+        short,
+        help = "Backfill up to this epoch, the parent head by default"
+    )]
+    pub to_epoch: Option<ChainEpoch>,
+    #[arg(
+        long,
+        short,
+        help = "The endpoint of an archival node of the parent to backfill from"
+    )]
+    pub endpoint: Option<String>,
+    #[arg(long, help = "The number of epochs listed in every request")]
+    pub batch_epochs: Option<ChainEpoch>,
+    #[arg(long, help = "The maximum number of requests per second to the node")]
+    pub max_requests_per_sec: Option<f64>,
+}
+
+/// The command to list the checkpoint history indexed for a subnet.
+pub(crate) struct CheckpointHistory;
+
+#[async_trait]
+impl CommandLineHandler for CheckpointHistory {
+    type Arguments = CheckpointHistoryArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("checkpoint history with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let history = client
+            .checkpoint_history(&arguments.subnet, arguments.from_epoch, arguments.to_epoch)
+            .await?;
+
+        for c in history.checkpoints.iter() {
+            tracing::info!(
+                "epoch {} - cross_msgs={}, fee={} FIL, child_checks={}",
+                c.epoch,
+                c.cross_msgs.len(),
+                c.fee,
+                c.children.len()
+            );
+        }
+        match history.backfill {
+            Some(b) if !b.done() => tracing::info!(
+                "backfill at epoch {} of {} ({:.1}%)",
+                b.next_epoch,
+                b.to_epoch,
+                b.progress()
+            ),
+            Some(b) => tracing::info!("history backfilled up to epoch {}", b.to_epoch),
+            None => tracing::info!("history never backfilled"),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the checkpoint history indexed for a subnet")]
+pub(crate) struct CheckpointHistoryArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id of the checkpointing subnet")]
+    pub subnet: String,
+    #[arg(long, short, help = "Include checkpoints from this epoch")]
+    pub from_epoch: ChainEpoch,
+    #[arg(long, short, help = "Include checkpoints up to this epoch")]
+    pub to_epoch: ChainEpoch,
+}
@@ -20,7 +20,7 @@ impl CommandLineHandler for ListBottomUpCheckpoints {
     type Arguments = ListBottomUpCheckpointsArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("list checkpoints with args: {:?}", arguments);
+        tracing::debug!("list checkpoints with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
         let client = IpcAgentClient::default_from_url(url);
@@ -29,7 +29,7 @@ impl CommandLineHandler for ListBottomUpCheckpoints {
             .await?;
 
         for c in checkpoints.iter() {
-            log::info!(
+            tracing::info!(
                 "epoch {} - prev_check={}, cross_msgs={}, child_checks={}",
                 c["epoch"],
                 c["prev_check"],
@@ -6,10 +6,28 @@ use crate::cli::commands::checkpoint::list_checkpoints::{
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use clap::{Args, Subcommand};
 
+use self::diff::{BottomUpCheckpointDiff, BottomUpCheckpointDiffArgs};
+use self::history::{
+    BackfillHistory, BackfillHistoryArgs, CheckpointHistory, CheckpointHistoryArgs,
+};
+use self::pending_bottomup::{PendingBottomUpMsgs, PendingBottomUpMsgsArgs};
+use self::quorum::{CheckpointQuorum, CheckpointQuorumArgs};
+use self::submission_time::{NextSubmission, NextSubmissionArgs};
+use self::submissions::{ListSubmissions, ListSubmissionsArgs};
 use self::topdown_executed::{LastTopDownExec, LastTopDownExecArgs};
+use self::vote_approval::{ApproveVote, ApproveVoteArgs, ListVoteApprovals, ListVoteApprovalsArgs};
+use self::vote_history::{VoteHistory, VoteHistoryArgs};
 
+mod diff;
+mod history;
 mod list_checkpoints;
+mod pending_bottomup;
+mod quorum;
+mod submission_time;
+mod submissions;
 mod topdown_executed;
+mod vote_approval;
+mod vote_history;
 
 #[derive(Debug, Args)]
 #[command(name = "checkpoint", about = "checkpoint related commands")]
@@ -24,6 +42,16 @@ impl CheckpointCommandsArgs {
         match &self.command {
             Commands::ListBottomup(args) => ListBottomUpCheckpoints::handle(global, args).await,
             Commands::LastTopdown(args) => LastTopDownExec::handle(global, args).await,
+            Commands::PendingBottomup(args) => PendingBottomUpMsgs::handle(global, args).await,
+            Commands::DiffBottomup(args) => BottomUpCheckpointDiff::handle(global, args).await,
+            Commands::ListApprovals(args) => ListVoteApprovals::handle(global, args).await,
+            Commands::Approve(args) => ApproveVote::handle(global, args).await,
+            Commands::Backfill(args) => BackfillHistory::handle(global, args).await,
+            Commands::History(args) => CheckpointHistory::handle(global, args).await,
+            Commands::VoteHistory(args) => VoteHistory::handle(global, args).await,
+            Commands::Submissions(args) => ListSubmissions::handle(global, args).await,
+            Commands::NextSubmission(args) => NextSubmission::handle(global, args).await,
+            Commands::Quorum(args) => CheckpointQuorum::handle(global, args).await,
         }
     }
 }
@@ -32,4 +60,14 @@ impl CheckpointCommandsArgs {
 pub(crate) enum Commands {
     ListBottomup(ListBottomUpCheckpointsArgs),
     LastTopdown(LastTopDownExecArgs),
+    PendingBottomup(PendingBottomUpMsgsArgs),
+    DiffBottomup(BottomUpCheckpointDiffArgs),
+    ListApprovals(ListVoteApprovalsArgs),
+    Approve(ApproveVoteArgs),
+    Backfill(BackfillHistoryArgs),
+    History(CheckpointHistoryArgs),
+    VoteHistory(VoteHistoryArgs),
+    Submissions(ListSubmissionsArgs),
+    NextSubmission(NextSubmissionArgs),
+    Quorum(CheckpointQuorumArgs),
 }
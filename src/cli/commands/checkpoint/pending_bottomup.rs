@@ -0,0 +1,57 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Pending bottom-up cross messages cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the bottom-up messages queued for the next checkpoint of a subnet.
+pub(crate) struct PendingBottomUpMsgs;
+
+#[async_trait]
+impl CommandLineHandler for PendingBottomUpMsgs {
+    type Arguments = PendingBottomUpMsgsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("pending bottom up msgs with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let pending = client.pending_bottom_up_msgs(&arguments.subnet).await?;
+
+        tracing::info!(
+            "{} message(s) pending for checkpoint at epoch {} (in ~{}s) with fee {}",
+            pending.msgs.len(),
+            pending.checkpoint.epoch,
+            pending.checkpoint.eta_secs,
+            pending.fee
+        );
+        for m in pending.msgs.iter() {
+            tracing::info!(
+                "nonce {} - from={}, to={}, value={}, method={}",
+                m.nonce,
+                m.from,
+                m.to,
+                m.value,
+                m.method
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List bottom-up messages pending for the next checkpoint")]
+pub(crate) struct PendingBottomUpMsgsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id where the messages were sent from")]
+    pub subnet: String,
+}
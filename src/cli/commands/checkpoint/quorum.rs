@@ -0,0 +1,67 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Checkpoint quorum cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to show the votes of all the validators on the pending checkpoints.
+pub(crate) struct CheckpointQuorum;
+
+#[async_trait]
+impl CommandLineHandler for CheckpointQuorum {
+    type Arguments = CheckpointQuorumArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("checkpoint quorum with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let directions = client
+            .checkpoint_quorum(arguments.subnet.as_deref())
+            .await?;
+
+        if directions.is_empty() {
+            tracing::info!("no checkpoint votes counted yet");
+        }
+        for d in directions.iter() {
+            let average = match d.average_epochs_to_quorum {
+                Some(epochs) => format!("{epochs:.1} epochs"),
+                None => String::from("unknown"),
+            };
+            tracing::info!(
+                "{} {} - last executed epoch {}, average time to quorum {average:}, stalled for {} epochs",
+                d.subnet,
+                d.direction,
+                d.last_executed_epoch,
+                d.stalled_epochs
+            );
+            for p in d.pending.iter() {
+                tracing::info!(
+                    "    epoch {}: {} of {} validators voted, quorum {}",
+                    p.epoch,
+                    p.voted,
+                    p.validators,
+                    p.quorum
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Show the votes of all the validators on the pending checkpoints")]
+pub(crate) struct CheckpointQuorumArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "Only show the checkpoints of this subnet")]
+    pub subnet: Option<String>,
+}
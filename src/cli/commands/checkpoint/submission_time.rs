@@ -0,0 +1,55 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Checkpoint submission time estimate cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to estimate when the agent submits the next checkpoints of a subnet.
+pub(crate) struct NextSubmission;
+
+#[async_trait]
+impl CommandLineHandler for NextSubmission {
+    type Arguments = NextSubmissionArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("next submission with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let estimate = client
+            .estimate_checkpoint_submission_time(&arguments.subnet)
+            .await?;
+
+        for d in estimate.directions.iter() {
+            tracing::info!(
+                "{} - checkpoint at epoch {} submitted at epoch {}, in about {}s (unix timestamp {})",
+                d.direction,
+                d.checkpoint_epoch,
+                d.submission.epoch,
+                d.submission.eta_secs,
+                d.submission.timestamp
+            );
+            if let Some(condition) = &d.condition {
+                tracing::info!("    waiting for {condition:}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Estimate when the agent submits the next checkpoints of a subnet")]
+pub(crate) struct NextSubmissionArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id of the checkpointing subnet")]
+    pub subnet: String,
+}
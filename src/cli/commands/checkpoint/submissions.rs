@@ -0,0 +1,68 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List checkpoint submissions cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the checkpoint votes submitted by the agent, from its journal.
+pub(crate) struct ListSubmissions;
+
+#[async_trait]
+impl CommandLineHandler for ListSubmissions {
+    type Arguments = ListSubmissionsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list checkpoint submissions with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = client
+            .list_checkpoint_submissions(arguments.subnet.clone(), arguments.limit)
+            .await?;
+
+        for s in response.submissions.iter() {
+            let outcome = match (&s.message, &s.error) {
+                (Some(message), _) => format!("message {message:}"),
+                (_, Some(error)) => format!("error: {error:}"),
+                _ => String::new(),
+            };
+            tracing::info!(
+                "{} {} {} at epoch {} by {}: {:?} {outcome:}",
+                s.timestamp,
+                s.subnet,
+                s.direction,
+                s.epoch,
+                s.validator,
+                s.status
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the checkpoint votes submitted by the agent, the latest first")]
+pub(crate) struct ListSubmissionsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The child subnet of the checkpoints, all subnets if not set"
+    )]
+    pub subnet: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The maximum number of submissions listed, 100 by default"
+    )]
+    pub limit: Option<usize>,
+}
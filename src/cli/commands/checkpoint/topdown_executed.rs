@@ -19,13 +19,13 @@ impl CommandLineHandler for LastTopDownExec {
     type Arguments = LastTopDownExecArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("last topdown exec with args: {:?}", arguments);
+        tracing::debug!("last topdown exec with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
         let client = IpcAgentClient::default_from_url(url);
         let epoch = client.last_top_down_executed(&arguments.subnet).await?;
 
-        log::info!("Last top-down checkpoint executed in epoch: {epoch:}");
+        tracing::info!("Last top-down checkpoint executed in epoch: {epoch:}");
 
         Ok(())
     }
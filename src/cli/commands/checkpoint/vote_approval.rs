@@ -0,0 +1,87 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Checkpoint vote approval cli commands
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the checkpoint votes waiting for approval.
+pub(crate) struct ListVoteApprovals;
+
+#[async_trait]
+impl CommandLineHandler for ListVoteApprovals {
+    type Arguments = ListVoteApprovalsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list vote approvals with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let approvals = client.list_vote_approvals().await?;
+
+        if approvals.is_empty() {
+            tracing::info!("no checkpoint votes waiting for approval");
+        }
+        for a in approvals.iter() {
+            tracing::info!(
+                "approval {}: {:?} - validator={}, epoch={}, checkpoint={}",
+                a.id,
+                a.status,
+                a.validator,
+                a.epoch,
+                a.checkpoint
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the checkpoint votes waiting for approval")]
+pub(crate) struct ListVoteApprovalsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+}
+
+/// The command to approve or reject a checkpoint vote.
+pub(crate) struct ApproveVote;
+
+#[async_trait]
+impl CommandLineHandler for ApproveVote {
+    type Arguments = ApproveVoteArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("approve vote with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let approval = client.approve_vote(arguments.id, !arguments.reject).await?;
+
+        tracing::info!(
+            "vote of validator {} at epoch {} {:?}",
+            approval.validator,
+            approval.epoch,
+            approval.status
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Approve or reject a checkpoint vote waiting for approval")]
+pub(crate) struct ApproveVoteArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, help = "The id of the approval")]
+    pub id: u64,
+    #[arg(long, help = "Reject the vote instead of approving it")]
+    pub reject: bool,
+}
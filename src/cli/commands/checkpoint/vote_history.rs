@@ -0,0 +1,67 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Validator vote history cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::history::votes::VoteStatus;
+use crate::sdk::IpcAgentClient;
+
+/// The command to show the checkpoint votes of a validator over a range of epochs.
+pub(crate) struct VoteHistory;
+
+#[async_trait]
+impl CommandLineHandler for VoteHistory {
+    type Arguments = VoteHistoryArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("vote history with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let history = client
+            .validator_vote_history(
+                &arguments.subnet,
+                &arguments.validator,
+                arguments.from_epoch,
+                arguments.to_epoch,
+            )
+            .await?;
+
+        for d in history.directions.iter() {
+            tracing::info!(
+                "{} - voted {} and missed {} of {} checkpoints",
+                d.direction,
+                d.voted,
+                d.missed,
+                d.votes.len()
+            );
+            for v in d.votes.iter().filter(|v| v.status != VoteStatus::Voted) {
+                tracing::info!("    epoch {} {:?}", v.epoch, v.status);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Show the checkpoint votes of a validator over a range of epochs")]
+pub(crate) struct VoteHistoryArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id of the checkpointing subnet")]
+    pub subnet: String,
+    #[arg(long, short, help = "The fvm or evm address of the validator")]
+    pub validator: String,
+    #[arg(long, short, help = "Include checkpoints from this epoch")]
+    pub from_epoch: ChainEpoch,
+    #[arg(long, short, help = "Include checkpoints up to this epoch")]
+    pub to_epoch: ChainEpoch,
+}
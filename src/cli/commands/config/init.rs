@@ -16,23 +16,23 @@ impl CommandLineHandler for InitConfig {
 
     async fn handle(global: &GlobalArguments, _arguments: &Self::Arguments) -> anyhow::Result<()> {
         let path = global.config_path();
-        log::debug!("initializing empty config file in {}", path);
+        tracing::debug!("initializing empty config file in {}", path);
 
         let file_path = std::path::Path::new(&path);
         if let Some(parent) = file_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
         let mut file = std::fs::File::create(&path).map_err(|e| {
-            log::error!("couldn't create config file");
+            tracing::error!("couldn't create config file");
             e
         })?;
         file.write_all(DEFAULT_CONFIG_TEMPLATE.as_bytes())
             .map_err(|e| {
-                log::error!("error populating empty config template");
+                tracing::error!("error populating empty config template");
                 e
             })?;
 
-        log::info!("Empty config populated successful in {}", &path);
+        tracing::info!("Empty config populated successful in {}", &path);
 
         Ok(())
     }
@@ -4,12 +4,14 @@
 
 mod init;
 mod reload;
+mod validate;
 
 use clap::{Args, Subcommand};
 use std::fmt::Debug;
 
 use crate::cli::commands::config::init::{InitConfig, InitConfigArgs};
 use crate::cli::commands::config::reload::{ReloadConfig, ReloadConfigArgs};
+use crate::cli::commands::config::validate::{ValidateConfig, ValidateConfigArgs};
 use crate::cli::{CommandLineHandler, GlobalArguments};
 
 #[derive(Debug, Args)]
@@ -25,6 +27,7 @@ impl ConfigCommandsArgs {
         match &self.command {
             Commands::Reload(args) => ReloadConfig::handle(global, args).await,
             Commands::Init(args) => InitConfig::handle(global, args).await,
+            Commands::Validate(args) => ValidateConfig::handle(global, args).await,
         }
     }
 }
@@ -33,4 +36,5 @@ impl ConfigCommandsArgs {
 pub(crate) enum Commands {
     Reload(ReloadConfigArgs),
     Init(InitConfigArgs),
+    Validate(ValidateConfigArgs),
 }
@@ -14,14 +14,14 @@ impl CommandLineHandler for ReloadConfig {
     type Arguments = ReloadConfigArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("reload config with args: {:?}", arguments);
+        tracing::debug!("reload config with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
         let client = IpcAgentClient::default_from_url(url);
 
         client.reload_config(arguments.path.clone()).await?;
 
-        log::info!("Reload json rpc config successful");
+        tracing::info!("Reload json rpc config successful");
 
         Ok(())
     }
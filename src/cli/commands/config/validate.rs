@@ -0,0 +1,69 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+
+/// The command to validate the subnets of the agent config
+pub(crate) struct ValidateConfig;
+
+#[async_trait]
+impl CommandLineHandler for ValidateConfig {
+    type Arguments = ValidateConfigArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("validate config with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+
+        let validation = client
+            .validate_config(arguments.path.clone(), arguments.offline)
+            .await?;
+        for error in validation.errors.iter() {
+            tracing::error!("{error:}");
+        }
+        for subnet in validation.subnets.iter() {
+            let reachable = match subnet.reachable {
+                Some(true) => "reachable",
+                Some(false) => "unreachable",
+                None => "not probed",
+            };
+            tracing::info!("{} at {} ({reachable:})", subnet.subnet, subnet.endpoint);
+            for issue in subnet.errors.iter() {
+                tracing::error!("  {}: {}", issue.field, issue.message);
+            }
+            for issue in subnet.warnings.iter() {
+                tracing::warn!("  {}: {}", issue.field, issue.message);
+            }
+        }
+
+        if !validation.valid {
+            return Err(anyhow!("the config is not valid"));
+        }
+        tracing::info!("the config is valid");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Validate the subnets of the config of the IPC Agent JSON RPC server")]
+pub(crate) struct ValidateConfigArgs {
+    #[arg(
+        short,
+        long,
+        help = "The path of a config to validate instead of the one loaded by the server, optional"
+    )]
+    pub path: Option<String>,
+    #[arg(
+        long,
+        help = "Only check the config, without probing the nodes of the subnets"
+    )]
+    pub offline: bool,
+    #[arg(short, long, help = "The JSON RPC server url for ipc agent, optional")]
+    pub ipc_agent_url: Option<String>,
+}
@@ -0,0 +1,68 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Cross messages by account cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the cross messages of the history an account sent or received.
+pub(crate) struct ByAccount;
+
+#[async_trait]
+impl CommandLineHandler for ByAccount {
+    type Arguments = ByAccountArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("cross messages by account with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let r = client
+            .cross_msgs_by_account(&arguments.account, arguments.offset, arguments.limit)
+            .await?;
+
+        for m in r.msgs.iter() {
+            tracing::info!(
+                "{} at epoch {}: {} {} FIL from {} to {} (nonce {})",
+                m.subnet,
+                m.epoch,
+                if m.sent { "sent" } else { "received" },
+                m.msg.value,
+                m.msg.from,
+                m.msg.to,
+                m.msg.nonce
+            );
+        }
+        match r.next_offset {
+            Some(offset) => tracing::info!(
+                "{} of {} messages, next page at offset {offset:}",
+                r.msgs.len(),
+                r.total
+            ),
+            None => tracing::info!("{} of {} messages", r.msgs.len(), r.total),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "by-account",
+    about = "List the cross messages of the checkpoint history an account sent or received"
+)]
+pub(crate) struct ByAccountArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The address of the account")]
+    pub account: String,
+    #[arg(long, default_value_t = 0, help = "The number of messages to skip")]
+    pub offset: usize,
+    #[arg(long, help = "The number of messages to list, 100 by default")]
+    pub limit: Option<usize>,
+}
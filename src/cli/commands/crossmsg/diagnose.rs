@@ -0,0 +1,75 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Diagnose cross message cli command handler.
+
+use async_trait::async_trait;
+use base64::Engine;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::diagnose_cross::DiagnoseCrossMsgParams;
+
+/// The command to find where a cross message is stuck.
+pub(crate) struct Diagnose;
+
+#[async_trait]
+impl CommandLineHandler for Diagnose {
+    type Arguments = DiagnoseArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("diagnose cross message with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let postbox_msg_key = arguments
+            .postbox_msg_key
+            .as_ref()
+            .map(|k| base64::engine::general_purpose::STANDARD.decode(k))
+            .transpose()?;
+        let params = DiagnoseCrossMsgParams {
+            subnet: arguments.subnet.clone(),
+            destination: arguments.destination.clone(),
+            nonce: arguments.nonce,
+            postbox_msg_key,
+            from: arguments.from.clone(),
+            execute: arguments.execute,
+        };
+        let r = client.diagnose_cross_msg(params).await?;
+
+        tracing::info!("route: {}", r.route.join(" -> "));
+        tracing::info!("waiting in {}: {:?}, {}", r.waiting_in, r.reason, r.detail);
+        if r.executed {
+            tracing::info!("done: {}", r.remediation);
+        } else {
+            tracing::info!("remediation: {}", r.remediation);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Find where a cross message is stuck on its route, and how to get it moving")]
+pub(crate) struct DiagnoseArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The subnet the message was sent, or last propagated, from"
+    )]
+    pub subnet: String,
+    #[arg(long, short, help = "The destination subnet of the message")]
+    pub destination: String,
+    #[arg(long, help = "The nonce of the message in the gateway of the subnet")]
+    pub nonce: u64,
+    #[arg(long, help = "The key of the message in the postbox, in base64")]
+    pub postbox_msg_key: Option<String>,
+    #[arg(long, help = "The address that pays for the propagation")]
+    pub from: Option<String>,
+    #[arg(long, help = "Execute the remediation if the agent can")]
+    pub execute: bool,
+}
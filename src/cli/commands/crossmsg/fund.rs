@@ -18,20 +18,40 @@ impl CommandLineHandler for Fund {
     type Arguments = FundArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("fund operation with args: {:?}", arguments);
+        tracing::debug!("fund operation with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
         let client = IpcAgentClient::default_from_url(url);
-        let epoch = client
+        let response = client
             .fund(
                 &arguments.subnet,
                 arguments.from.clone(),
                 arguments.to.clone(),
                 arguments.amount,
+                arguments.source.clone(),
             )
             .await?;
 
-        log::info!("funded subnet: {:} at epoch: {epoch:}", arguments.subnet);
+        tracing::info!(
+            "funded subnet: {:} with {} at epoch: {}",
+            arguments.subnet,
+            response.amount,
+            response.epoch
+        );
+        if let Some(eta) = response.eta {
+            tracing::info!(
+                "funds expected in the subnet with the top-down checkpoint at epoch: {} (in ~{}s)",
+                eta.epoch,
+                eta.eta_secs
+            );
+        }
+        if let Some(job) = response.job {
+            tracing::info!(
+                "funds moved down to subnet {} by job {}, follow it with `job list`",
+                arguments.subnet,
+                job.id
+            );
+        }
 
         Ok(())
     }
@@ -52,6 +72,11 @@ pub(crate) struct FundArgs {
     pub to: Option<String>,
     #[arg(long, short, help = "The subnet to fund")]
     pub subnet: String,
+    #[arg(
+        long,
+        help = "The subnet to send the funds from, an ancestor of the subnet (defaults to its parent)"
+    )]
+    pub source: Option<String>,
     #[arg(help = "The amount to fund in FIL, in whole FIL")]
     pub amount: f64,
 }
@@ -1,18 +1,33 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use crate::cli::commands::crossmsg::by_account::ByAccount;
+use crate::cli::commands::crossmsg::diagnose::Diagnose;
 use crate::cli::commands::crossmsg::fund::Fund;
+use crate::cli::commands::crossmsg::nonces::Nonces;
+use crate::cli::commands::crossmsg::pending_releases::PendingReleases;
 use crate::cli::commands::crossmsg::propagate::Propagate;
 use crate::cli::commands::crossmsg::release::Release;
+use crate::cli::commands::crossmsg::resend_topdown::ResendTopDown;
 use crate::cli::{CommandLineHandler, GlobalArguments};
+use by_account::ByAccountArgs;
+use diagnose::DiagnoseArgs;
 use fund::FundArgs;
+use nonces::NoncesArgs;
+use pending_releases::PendingReleasesArgs;
 use propagate::PropagateArgs;
 use release::ReleaseArgs;
+use resend_topdown::ResendTopDownArgs;
 
 use clap::{Args, Subcommand};
 
+pub mod by_account;
+pub mod diagnose;
 pub mod fund;
+pub mod nonces;
+pub mod pending_releases;
 pub mod propagate;
 pub mod release;
+pub mod resend_topdown;
 
 #[derive(Debug, Args)]
 #[command(name = "crossmsg", about = "cross network messages related commands")]
@@ -28,6 +43,11 @@ impl CrossMsgsCommandsArgs {
             Commands::Fund(args) => Fund::handle(global, args).await,
             Commands::Release(args) => Release::handle(global, args).await,
             Commands::Propagate(args) => Propagate::handle(global, args).await,
+            Commands::Nonces(args) => Nonces::handle(global, args).await,
+            Commands::ResendTopdown(args) => ResendTopDown::handle(global, args).await,
+            Commands::ByAccount(args) => ByAccount::handle(global, args).await,
+            Commands::Diagnose(args) => Diagnose::handle(global, args).await,
+            Commands::PendingReleases(args) => PendingReleases::handle(global, args).await,
         }
     }
 }
@@ -37,4 +57,9 @@ pub(crate) enum Commands {
     Fund(FundArgs),
     Release(ReleaseArgs),
     Propagate(PropagateArgs),
+    Nonces(NoncesArgs),
+    ResendTopdown(ResendTopDownArgs),
+    ByAccount(ByAccountArgs),
+    Diagnose(DiagnoseArgs),
+    PendingReleases(PendingReleasesArgs),
 }
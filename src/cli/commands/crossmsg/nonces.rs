@@ -0,0 +1,60 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Cross message nonces cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::nonces::DirectionNonces;
+
+/// The command to show the applied and pending nonces of the cross messages of a subnet.
+pub(crate) struct Nonces;
+
+#[async_trait]
+impl CommandLineHandler for Nonces {
+    type Arguments = NoncesArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("nonces with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let nonces = client.nonces(&arguments.subnet).await?;
+
+        log_direction("top-down", &nonces.top_down);
+        log_direction("bottom-up", &nonces.bottom_up);
+
+        Ok(())
+    }
+}
+
+fn log_direction(direction: &str, nonces: &DirectionNonces) {
+    match nonces.highest_pending {
+        Some(highest) => tracing::info!(
+            "{direction:} - applied nonce: {}, pending: {} messages up to nonce {highest:}",
+            nonces.applied,
+            nonces.pending
+        ),
+        None => tracing::info!(
+            "{direction:} - applied nonce: {}, no message pending",
+            nonces.applied
+        ),
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Show the applied and pending nonces of the cross messages of a subnet")]
+pub(crate) struct NoncesArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The subnet, both it and its parent must be in the config"
+    )]
+    pub subnet: String,
+}
@@ -0,0 +1,60 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Pending releases cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the releases queued for the next bottom-up checkpoint of a subnet.
+pub(crate) struct PendingReleases;
+
+#[async_trait]
+impl CommandLineHandler for PendingReleases {
+    type Arguments = PendingReleasesArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("pending releases with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let pending = client
+            .pending_releases(&arguments.subnet, arguments.from.clone())
+            .await?;
+
+        tracing::info!(
+            "{} release(s) of {} pending for checkpoint at epoch {} (in ~{}s)",
+            pending.releases.len(),
+            pending.total,
+            pending.checkpoint.epoch,
+            pending.checkpoint.eta_secs
+        );
+        for r in pending.releases.iter() {
+            tracing::info!(
+                "nonce {} - from={}, to={}, amount={}",
+                r.nonce,
+                r.from,
+                r.to,
+                r.amount
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List releases pending for the next bottom-up checkpoint")]
+pub(crate) struct PendingReleasesArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id the funds are released from")]
+    pub subnet: String,
+    #[arg(long, short, help = "Only list the releases initiated by this address")]
+    pub from: Option<String>,
+}
@@ -11,6 +11,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::propagate::PropagateParams;
 
 /// The command to propagate a message in the postbox.
@@ -21,10 +22,10 @@ impl CommandLineHandler for Propagate {
     type Arguments = PropagateArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("propagate operation with args: {:?}", arguments);
+        tracing::debug!("propagate operation with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let postbox_msg_key =
             base64::engine::general_purpose::STANDARD.decode(&arguments.postbox_msg_key)?;
@@ -32,12 +33,13 @@ impl CommandLineHandler for Propagate {
             subnet: arguments.subnet.clone(),
             from: arguments.from.clone(),
             postbox_msg_key,
+            fee: arguments.fee,
         };
         json_rpc_client
             .request::<()>(json_rpc_methods::PROPAGATE, serde_json::to_value(params)?)
             .await?;
 
-        log::info!("propagated subnet: {:}", arguments.subnet);
+        tracing::info!("propagated subnet: {:}", arguments.subnet);
 
         Ok(())
     }
@@ -52,6 +54,11 @@ pub(crate) struct PropagateArgs {
     pub from: Option<String>,
     #[arg(long, short, help = "The subnet of the message to propagate")]
     pub subnet: String,
+    #[arg(
+        long,
+        help = "Extra fee to attach, in whole FIL, when the fee of the message does not cover the propagation"
+    )]
+    pub fee: Option<f64>,
     #[arg(help = "The message cid to propagate")]
     pub postbox_msg_key: String,
 }
@@ -18,11 +18,11 @@ impl CommandLineHandler for Release {
     type Arguments = ReleaseArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("release operation with args: {:?}", arguments);
+        tracing::debug!("release operation with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
         let client = IpcAgentClient::default_from_url(url);
-        let epoch = client
+        let response = client
             .release(
                 &arguments.subnet,
                 arguments.from.clone(),
@@ -31,7 +31,19 @@ impl CommandLineHandler for Release {
             )
             .await?;
 
-        log::info!("released subnet: {:} at epoch {epoch:}", arguments.subnet);
+        tracing::info!(
+            "released {} from subnet: {:} at epoch {}",
+            response.amount,
+            arguments.subnet,
+            response.epoch
+        );
+        if let Some(eta) = response.eta {
+            tracing::info!(
+                "funds expected to leave the subnet with the bottom-up checkpoint at epoch: {} (in ~{}s)",
+                eta.epoch,
+                eta.eta_secs
+            );
+        }
 
         Ok(())
     }
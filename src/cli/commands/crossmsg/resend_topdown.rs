@@ -0,0 +1,81 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Resend top-down messages cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::resend_topdown::ResendTopDownMsgsParams;
+
+/// The command to resend a range of top-down messages to a subnet.
+pub(crate) struct ResendTopDown;
+
+#[async_trait]
+impl CommandLineHandler for ResendTopDown {
+    type Arguments = ResendTopDownArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("resend topdown with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let params = ResendTopDownMsgsParams {
+            subnet: arguments.subnet.clone(),
+            from_nonce: arguments.from_nonce,
+            to_nonce: arguments.to_nonce,
+            from: arguments.from.clone(),
+            dry_run: arguments.dry_run,
+        };
+        let r = client.resend_top_down_msgs(params).await?;
+
+        match r.submitted_at {
+            Some(submitted_at) => tracing::info!(
+                "voted {} top-down messages with nonces {}..={} in the checkpoint of epoch {}, at epoch {submitted_at:}",
+                r.msgs,
+                r.from_nonce,
+                r.to_nonce,
+                r.epoch
+            ),
+            None => tracing::info!(
+                "dry run: {} top-down messages with nonces {}..={} would be voted in the checkpoint of epoch {}",
+                r.msgs,
+                r.from_nonce,
+                r.to_nonce,
+                r.epoch
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Resend a range of top-down messages to a subnet, e.g. after it was reset")]
+pub(crate) struct ResendTopDownArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The subnet, both it and its parent must be in the config"
+    )]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The nonce of the first message, the next one applied by the subnet"
+    )]
+    pub from_nonce: u64,
+    #[arg(
+        long,
+        help = "The nonce of the last message, all pending ones if not set"
+    )]
+    pub to_nonce: Option<u64>,
+    #[arg(long, help = "The validator voting the checkpoint")]
+    pub from: Option<String>,
+    #[arg(long, help = "Only check the range, without voting the checkpoint")]
+    pub dry_run: bool,
+}
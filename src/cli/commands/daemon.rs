@@ -6,35 +6,61 @@ use std::fmt::Debug;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
-use async_trait::async_trait;
+use anyhow::anyhow;
 use clap::Args;
 use ipc_identity::Wallet;
 use tokio_graceful_shutdown::{IntoSubsystem, Toplevel};
 
+use crate::alerts::AlertsSubsystem;
+use crate::checkpoint::quorum::QuorumMonitor;
 use crate::checkpoint::CheckpointSubsystem;
-use crate::cli::{CommandLineHandler, GlobalArguments};
-use crate::config::ReloadableConfig;
+use crate::cli::GlobalArguments;
+use crate::config::{ConfigWatcher, ReloadableConfig, RunMode};
+use crate::jobs::JobsSubsystem;
+use crate::logging::Logger;
+use crate::metrics::{MetricsExporter, MetricsSubsystem};
+use crate::persistence::DataDir;
 use crate::server::jsonrpc::JsonRPCServer;
+use crate::server::status::AgentIdentity;
 use crate::server::{new_evm_keystore_from_config, new_fvm_wallet_from_config};
+use crate::state::AgentState;
 
 /// The number of seconds to wait for a subsystem to start before returning an error.
-const SUBSYSTEM_WAIT_TIME_SECS: Duration = Duration::from_secs(10);
+pub(crate) const SUBSYSTEM_WAIT_TIME_SECS: Duration = Duration::from_secs(10);
 
 /// The command to start the ipc agent json rpc server in the foreground.
 pub(crate) struct LaunchDaemon;
 
-#[async_trait]
-impl CommandLineHandler for LaunchDaemon {
-    type Arguments = LaunchDaemonArgs;
-
-    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!(
+impl LaunchDaemon {
+    /// Runs the daemon until it is shut down, with the logs set up by `logger` following its
+    /// config.
+    pub(crate) async fn handle(
+        global: &GlobalArguments,
+        arguments: &LaunchDaemonArgs,
+        logger: &Logger,
+    ) -> anyhow::Result<()> {
+        tracing::debug!(
             "launching json rpc server with args: {:?} and global params: {:?}",
             arguments,
             global
         );
 
         let reloadable_config = Arc::new(ReloadableConfig::new(global.config_path())?);
+        logger
+            .configure(reloadable_config.get_config().log.as_ref())
+            .map_err(|e| anyhow!("cannot set up the logs: {e:}"))?;
+        let mode = arguments.mode;
+        mode.validate(&reloadable_config.get_config())
+            .map_err(|e| anyhow!("invalid config for {mode:} mode: {e:}"))?;
+        tracing::info!("running in {mode:} mode");
+
+        // Migrate the state of a previous release before anything is read from the data dir.
+        let data_dir = DataDir::from_config(&reloadable_config)?;
+        if let Some(repo) = reloadable_config.get_config_repo() {
+            data_dir.check_relocated(&DataDir::new(repo))?;
+        }
+        data_dir.migrate()?;
+        tracing::info!("using data dir {:?}", data_dir.root());
         let fvm_wallet = Arc::new(RwLock::new(Wallet::new(new_fvm_wallet_from_config(
             reloadable_config.clone(),
         )?)));
@@ -42,22 +68,85 @@ impl CommandLineHandler for LaunchDaemon {
             reloadable_config.clone(),
         )?));
 
-        // Start subsystems.
+        // Start subsystems, all sharing the state of the agent.
+        let state = Arc::new(AgentState::new());
+        state
+            .notifier
+            .restore(&data_dir.notifications())
+            .map_err(|e| anyhow!("cannot restore the notification deliveries: {e:}"))?;
+        let metrics = MetricsSubsystem::new(&data_dir, state.clone());
+        let jobs = JobsSubsystem::new(
+            &data_dir,
+            reloadable_config.clone(),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+            state.clone(),
+        );
+        let identity = match AgentIdentity::load_or_generate(&data_dir) {
+            Ok(identity) => Some(Arc::new(identity)),
+            Err(e) => {
+                tracing::warn!("cannot load the identity of the agent: {e:}");
+                None
+            }
+        };
         let checkpointing = CheckpointSubsystem::new(
+            &data_dir,
             reloadable_config.clone(),
             fvm_wallet.clone(),
             evm_keystore.clone(),
+            identity,
+            state.clone(),
         );
-        let server = JsonRPCServer::new(
+        let alerts = AlertsSubsystem::new(
             reloadable_config.clone(),
             fvm_wallet.clone(),
             evm_keystore.clone(),
+            state.clone(),
         );
-        Toplevel::new()
-            .start("Checkpoint subsystem", checkpointing.into_subsystem())
+        let mut server = JsonRPCServer::new(
+            reloadable_config.clone(),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+            state.clone(),
+        );
+        server.mode(mode);
+
+        let mut toplevel = Toplevel::new().start("Metrics subsystem", metrics.into_subsystem());
+        // the address of the exporter is only read at startup
+        if let Some(config) = reloadable_config.get_config().server.metrics.clone() {
+            let exporter = MetricsExporter::new(
+                config,
+                reloadable_config.clone(),
+                fvm_wallet.clone(),
+                evm_keystore.clone(),
+                state.clone(),
+            );
+            toplevel = toplevel.start("Metrics exporter subsystem", exporter.into_subsystem());
+        }
+        if mode.runs_checkpointing() {
+            let quorum = QuorumMonitor::new(
+                reloadable_config.clone(),
+                fvm_wallet.clone(),
+                evm_keystore.clone(),
+                state.clone(),
+            );
+            toplevel = toplevel
+                .start("Checkpoint subsystem", checkpointing.into_subsystem())
+                .start("Quorum monitor subsystem", quorum.into_subsystem());
+        }
+        if reloadable_config.get_config().server.watch_config {
+            let watcher = ConfigWatcher::new(reloadable_config.clone(), mode);
+            toplevel = toplevel.start("Config watcher subsystem", watcher.into_subsystem());
+        }
+        // SIGINT and SIGTERM stop the subsystems once the work in flight is drained, see
+        // `crate::manager::shutdown`; the shutdown timeout is only read at startup
+        let shutdown_timeout = reloadable_config.get_config().server.shutdown_timeout();
+        toplevel
+            .start("Jobs subsystem", jobs.into_subsystem())
+            .start("Alerts subsystem", alerts.into_subsystem())
             .start("JSON-RPC server subsystem", server.into_subsystem())
             .catch_signals()
-            .handle_shutdown_requests(SUBSYSTEM_WAIT_TIME_SECS)
+            .handle_shutdown_requests(shutdown_timeout + SUBSYSTEM_WAIT_TIME_SECS)
             .await?;
 
         Ok(())
@@ -66,4 +155,11 @@ impl CommandLineHandler for LaunchDaemon {
 
 #[derive(Debug, Args)]
 #[command(about = "Launch the ipc agent daemon process")]
-pub(crate) struct LaunchDaemonArgs {}
+pub(crate) struct LaunchDaemonArgs {
+    #[arg(
+        long,
+        default_value_t = RunMode::Full,
+        help = "The workload of the daemon: full, relayer (cross-net messages only) or checkpointer (checkpoints only)"
+    )]
+    pub mode: RunMode,
+}
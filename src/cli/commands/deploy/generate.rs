@@ -0,0 +1,390 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Generate deployment cli command
+
+use std::fmt::Debug;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+
+use crate::cli::commands::daemon::SUBSYSTEM_WAIT_TIME_SECS;
+use crate::cli::{CommandLineHandler, GlobalArguments, DEFAULT_CONFIG_NAME, DEFAULT_REPO_PATH};
+use crate::config::{RunMode, HEALTHZ_ENDPOINT, JSON_RPC_ENDPOINT, READYZ_ENDPOINT};
+
+/// The time allowed to the daemon to stop on top of the time it waits for its subsystems, before
+/// it is killed.
+const STOP_GRACE_MARGIN_SECS: u64 = 5;
+
+/// The command to generate the files deploying the agent with its current config.
+pub(crate) struct GenerateDeployment;
+
+#[async_trait]
+impl CommandLineHandler for GenerateDeployment {
+    type Arguments = GenerateDeploymentArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("generate deployment with args: {:?}", arguments);
+
+        let config = global.config()?;
+        let config_path = std::fs::canonicalize(global.config_path())?;
+        let data_dir = config_path
+            .parent()
+            .ok_or_else(|| anyhow!("no data dir for config {config_path:?}"))?
+            .to_path_buf();
+        if arguments.format != DeploymentFormat::Systemd
+            && config.server.json_rpc_address.ip().is_loopback()
+        {
+            tracing::warn!(
+                "the json rpc server listens on {}, it cannot be reached from outside of its container",
+                config.server.json_rpc_address
+            );
+        }
+
+        // the log file is written next to the data of the agent, or in a dir of its own
+        let log_dir = config
+            .log
+            .as_ref()
+            .and_then(|l| l.file.as_ref())
+            .filter(|file| file.is_absolute() && !file.starts_with(&data_dir))
+            .and_then(|file| file.parent())
+            .map(Path::to_path_buf);
+        // the state is kept in the config dir, unless the config moves it out
+        let state_dir = config
+            .data_dir
+            .as_ref()
+            .map(|dir| data_dir.join(dir))
+            .filter(|dir| !dir.starts_with(&data_dir));
+        if let Some(dir) = &state_dir && arguments.format != DeploymentFormat::Systemd {
+            tracing::warn!(
+                "the data dir {dir:?} of the config is outside of its dir, it is not mounted in the container"
+            );
+        }
+
+        let deployment = Deployment {
+            name: arguments.name.clone(),
+            mode: arguments.mode,
+            config_path,
+            data_dir,
+            log_dir,
+            state_dir,
+            port: config.server.json_rpc_address.port(),
+            shutdown_timeout: config.server.shutdown_timeout(),
+            image: arguments.image.clone(),
+            binary: arguments.binary.clone(),
+            user: arguments.user.clone(),
+        };
+        let rendered = match arguments.format {
+            DeploymentFormat::Systemd => deployment.systemd(),
+            DeploymentFormat::Compose => deployment.compose(),
+            DeploymentFormat::Kubernetes => deployment.kubernetes(),
+        };
+        match &arguments.output {
+            Some(p) => {
+                let mut file = std::fs::File::create(p)?;
+                file.write_all(rendered.as_bytes())?;
+                tracing::info!("{:?} deployment written to file {:?}", arguments.format, p);
+            }
+            None => tracing::info!("{:?} deployment:\n{rendered:}", arguments.format),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum DeploymentFormat {
+    /// A systemd unit running the installed binary
+    Systemd,
+    /// A docker compose file running the image of the agent
+    Compose,
+    /// Kubernetes manifests running the image of the agent
+    Kubernetes,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Generate the systemd unit, docker compose file or kubernetes manifests of the agent"
+)]
+pub(crate) struct GenerateDeploymentArgs {
+    #[arg(long, short, value_enum, help = "The kind of deployment to generate")]
+    pub format: DeploymentFormat,
+    #[arg(
+        long,
+        short,
+        default_value_t = RunMode::Full,
+        help = "The workload of the daemon: full, relayer or checkpointer"
+    )]
+    pub mode: RunMode,
+    #[arg(
+        long,
+        default_value = "ipc-agent",
+        help = "The name of the service, container or kubernetes resources"
+    )]
+    pub name: String,
+    #[arg(
+        long,
+        default_value = "ipc-agent:latest",
+        help = "The image of the agent, for compose and kubernetes"
+    )]
+    pub image: String,
+    #[arg(
+        long,
+        default_value = "/usr/local/bin/ipc-agent",
+        help = "The path of the agent binary, for systemd"
+    )]
+    pub binary: String,
+    #[arg(long, short, help = "The user the agent runs as, for systemd")]
+    pub user: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "Optional parameter that outputs the deployment into the file specified"
+    )]
+    pub output: Option<String>,
+}
+
+/// What the deployments of the agent are parameterized with.
+struct Deployment {
+    name: String,
+    mode: RunMode,
+    /// The config on the host, whose dir is the data dir of the agent.
+    config_path: PathBuf,
+    data_dir: PathBuf,
+    /// The dir of the log file of the config, if it is outside of the data dir.
+    log_dir: Option<PathBuf>,
+    /// The `data_dir` of the config, if it is outside of the dir of the config.
+    state_dir: Option<PathBuf>,
+    port: u16,
+    /// The time the daemon waits for the work in flight when it is stopped.
+    shutdown_timeout: Duration,
+    image: String,
+    binary: String,
+    user: Option<String>,
+}
+
+impl Deployment {
+    /// The seconds the daemon gets to stop on SIGTERM, which it handles by draining the work in
+    /// flight and shutting its subsystems down.
+    fn stop_grace_secs(&self) -> u64 {
+        self.shutdown_timeout.as_secs()
+            + SUBSYSTEM_WAIT_TIME_SECS.as_secs()
+            + STOP_GRACE_MARGIN_SECS
+    }
+
+    /// The data dir of the agent in its image, which runs as root.
+    fn container_data_dir(&self) -> String {
+        format!("/root/{DEFAULT_REPO_PATH:}")
+    }
+
+    /// The arguments of the daemon in its image.
+    fn container_args(&self) -> String {
+        format!(
+            r#"["--config-path", "{}/{DEFAULT_CONFIG_NAME:}", "daemon", "--mode", "{}"]"#,
+            self.container_data_dir(),
+            self.mode
+        )
+    }
+
+    fn header(&self) -> String {
+        format!(
+            "# Generated by `ipc-agent deploy generate` from {}, in {} mode.\n\
+             # The JSON-RPC API is served on port {} at /{JSON_RPC_ENDPOINT:}.\n",
+            self.config_path.display(),
+            self.mode,
+            self.port
+        )
+    }
+
+    fn systemd(&self) -> String {
+        let mut writable = vec![self.data_dir.display().to_string()];
+        for dir in [&self.log_dir, &self.state_dir].into_iter().flatten() {
+            writable.push(dir.display().to_string());
+        }
+        let user = match &self.user {
+            Some(user) => format!("User={user:}\n"),
+            None => String::new(),
+        };
+        format!(
+            r#"{header}
+[Unit]
+Description=IPC agent ({mode} mode)
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=simple
+{user}ExecStart={binary} --config-path {config} daemon --mode {mode}
+Environment=RUST_LOG=info
+Restart=on-failure
+RestartSec=5
+# the daemon stops its subsystems on SIGTERM
+KillSignal=SIGTERM
+TimeoutStopSec={grace}
+NoNewPrivileges=true
+ProtectSystem=strict
+ReadWritePaths={writable}
+
+[Install]
+WantedBy=multi-user.target
+"#,
+            header = self.header(),
+            mode = self.mode,
+            binary = self.binary,
+            config = self.config_path.display(),
+            grace = self.stop_grace_secs(),
+            writable = writable.join(" "),
+        )
+    }
+
+    fn compose(&self) -> String {
+        let log_volume = match &self.log_dir {
+            Some(dir) => format!("      - {0}:{0}\n", dir.display()),
+            None => String::new(),
+        };
+        format!(
+            r#"{header}# The data dir holds the keystores of the agent and the tokens of its config, keep it private.
+services:
+  {name}:
+    image: {image}
+    command: {args}
+    restart: unless-stopped
+    environment:
+      RUST_LOG: info
+    ports:
+      - "{port}:{port}"
+    volumes:
+      - {data_dir}:{container_data_dir}
+{log_volume}    stop_signal: SIGTERM
+    stop_grace_period: {grace}s
+    healthcheck:
+      test: ["CMD", "bash", "-c", "</dev/tcp/127.0.0.1/{port}"]
+      interval: 30s
+      timeout: 5s
+      retries: 3
+"#,
+            header = self.header(),
+            name = self.name,
+            image = self.image,
+            args = self.container_args(),
+            port = self.port,
+            data_dir = self.data_dir.display(),
+            container_data_dir = self.container_data_dir(),
+            grace = self.stop_grace_secs(),
+        )
+    }
+
+    fn kubernetes(&self) -> String {
+        let (log_mount, log_volume) = match &self.log_dir {
+            Some(dir) => (
+                format!(
+                    "            - name: logs\n              mountPath: {}\n",
+                    dir.display()
+                ),
+                String::from("        - name: logs\n          emptyDir: {}\n"),
+            ),
+            None => (String::new(), String::new()),
+        };
+        format!(
+            r#"{header}# The config holds the tokens of the nodes and of the clients of the agent, create its secret with:
+#   kubectl create secret generic {name}-config --from-file={config_name}={config}
+# It is copied to the data volume at every start, as the agent writes to its config.
+apiVersion: v1
+kind: PersistentVolumeClaim
+metadata:
+  name: {name}-data
+spec:
+  accessModes: ["ReadWriteOnce"]
+  resources:
+    requests:
+      storage: 1Gi
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: {name}
+  labels:
+    app: {name}
+spec:
+  # a single agent at a time signs with the keys of the data volume
+  replicas: 1
+  strategy:
+    type: Recreate
+  selector:
+    matchLabels:
+      app: {name}
+  template:
+    metadata:
+      labels:
+        app: {name}
+    spec:
+      terminationGracePeriodSeconds: {grace}
+      initContainers:
+        - name: config
+          image: {image}
+          command: ["cp", "/etc/ipc-agent/{config_name}", "{container_data_dir}/{config_name}"]
+          volumeMounts:
+            - name: config
+              mountPath: /etc/ipc-agent
+              readOnly: true
+            - name: data
+              mountPath: {container_data_dir}
+      containers:
+        - name: agent
+          image: {image}
+          args: {args}
+          env:
+            - name: RUST_LOG
+              value: info
+          ports:
+            - name: json-rpc
+              containerPort: {port}
+          readinessProbe:
+            httpGet:
+              path: /{READYZ_ENDPOINT:}
+              port: json-rpc
+            periodSeconds: 10
+          livenessProbe:
+            httpGet:
+              path: /{HEALTHZ_ENDPOINT:}
+              port: json-rpc
+            initialDelaySeconds: 30
+            periodSeconds: 30
+          volumeMounts:
+            - name: data
+              mountPath: {container_data_dir}
+{log_mount}      volumes:
+        - name: data
+          persistentVolumeClaim:
+            claimName: {name}-data
+        - name: config
+          secret:
+            secretName: {name}-config
+{log_volume}---
+apiVersion: v1
+kind: Service
+metadata:
+  name: {name}
+spec:
+  selector:
+    app: {name}
+  ports:
+    - name: json-rpc
+      port: {port}
+      targetPort: json-rpc
+"#,
+            header = self.header(),
+            name = self.name,
+            config_name = DEFAULT_CONFIG_NAME,
+            config = self.config_path.display(),
+            grace = self.stop_grace_secs(),
+            image = self.image,
+            container_data_dir = self.container_data_dir(),
+            args = self.container_args(),
+            port = self.port,
+        )
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::cli::{CommandLineHandler, GlobalArguments};
+
+use clap::{Args, Subcommand};
+
+use self::generate::{GenerateDeployment, GenerateDeploymentArgs};
+
+mod generate;
+
+#[derive(Debug, Args)]
+#[command(name = "deploy", about = "deployment related commands")]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct DeployCommandsArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl DeployCommandsArgs {
+    pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
+        match &self.command {
+            Commands::Generate(args) => GenerateDeployment::handle(global, args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    Generate(GenerateDeploymentArgs),
+}
@@ -0,0 +1,449 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The doctor command line handler that runs a battery of self-tests against the agent setup.
+
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::address::Address;
+use ipc_identity::{EvmKeyStore, PersistentKeyStore, Wallet};
+use num_traits::Zero;
+
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::config::subnet::{FvmSigner, SubnetConfig, SubnetMode};
+use crate::config::{ReloadableConfig, Subnet};
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::{LotusClient, NetworkVersion};
+use crate::manager::clock::{self, DEFAULT_BLOCK_TIME};
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::manager::SubnetManager;
+use crate::server::subnet::SubnetManagerPool;
+use crate::server::{new_evm_keystore_from_config, new_fvm_wallet_from_config};
+use crate::state::AgentState;
+
+/// The maximum drift tolerated between the local clock and the chain head, on top of the
+/// block time of the subnet.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+/// The versions of the builtin actors the IPC actors of this agent are built against.
+const SUPPORTED_ACTORS_VERSIONS: RangeInclusive<u32> = 10..=11;
+/// The IPC actors that must be in the builtin actors manifest of fvm nodes.
+const IPC_ACTORS: [&str; 2] = ["ipc_gateway", "ipc_subnet_actor"];
+
+/// The result of a single check of the report.
+struct Check {
+    name: String,
+    /// The details of the check if it passed, the reason it failed otherwise.
+    outcome: anyhow::Result<String>,
+    /// What to do to fix the check if it fails.
+    hint: String,
+}
+
+impl Check {
+    fn new(
+        name: impl Into<String>,
+        outcome: anyhow::Result<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            outcome,
+            hint: hint.into(),
+        }
+    }
+
+    fn passed(&self) -> bool {
+        self.outcome.is_ok()
+    }
+
+    fn print(&self) {
+        match &self.outcome {
+            Ok(details) => tracing::info!("[PASS] {}: {}", self.name, details),
+            Err(e) => {
+                tracing::error!("[FAIL] {}: {}", self.name, e);
+                tracing::error!("       hint: {}", self.hint);
+            }
+        }
+    }
+}
+
+/// The command to check that the agent is correctly set up.
+pub(crate) struct Doctor;
+
+#[async_trait]
+impl CommandLineHandler for Doctor {
+    type Arguments = DoctorArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("running doctor with args: {:?}", arguments);
+
+        let checks = run_checks(global).await;
+        for check in checks.iter() {
+            check.print();
+        }
+
+        let failed = checks.iter().filter(|c| !c.passed()).count();
+        if failed > 0 {
+            return Err(anyhow!("{failed:} out of {} checks failed", checks.len()));
+        }
+        tracing::info!("all {} checks passed", checks.len());
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Check the agent config, endpoints, wallets and subnets for common issues")]
+pub(crate) struct DoctorArgs {}
+
+async fn run_checks(global: &GlobalArguments) -> Vec<Check> {
+    let config_path = global.config_path();
+    let reloadable_config = match ReloadableConfig::new(config_path.clone()) {
+        Ok(c) => Arc::new(c),
+        Err(e) => {
+            return vec![Check::new(
+                "config",
+                Err(e),
+                format!(
+                "fix the config at {config_path:} or create a new one with `ipc-agent config init`"
+            ),
+            )]
+        }
+    };
+    let mut checks = vec![Check::new(
+        "config",
+        Ok(format!("{config_path:} is valid")),
+        "",
+    )];
+
+    let keystores: anyhow::Result<_> = try {
+        let fvm_wallet = Arc::new(RwLock::new(Wallet::new(new_fvm_wallet_from_config(
+            reloadable_config.clone(),
+        )?)));
+        let evm_keystore = Arc::new(RwLock::new(new_evm_keystore_from_config(
+            reloadable_config.clone(),
+        )?));
+        (fvm_wallet, evm_keystore)
+    };
+    let (fvm_wallet, evm_keystore) = match keystores {
+        Ok(k) => k,
+        Err(e) => {
+            checks.push(Check::new(
+                "keystore",
+                Err(e),
+                "make sure the keystores in the agent repo are readable and not corrupted",
+            ));
+            return checks;
+        }
+    };
+
+    // the checks run outside of the daemon, with a state of their own
+    let state = Arc::new(AgentState::new());
+    let pool = SubnetManagerPool::new(
+        reloadable_config.clone(),
+        fvm_wallet.clone(),
+        evm_keystore.clone(),
+        state.clone(),
+    );
+
+    let config = reloadable_config.get_config();
+    let mut subnets = config.subnets.values().collect::<Vec<_>>();
+    subnets.sort_by_key(|s| s.id.to_string());
+
+    for subnet in subnets {
+        let conn = match pool.get(&subnet.id) {
+            Some(conn) => conn,
+            None => {
+                checks.push(Check::new(
+                    format!("{} manager", subnet.id),
+                    Err(anyhow!("cannot connect to the subnet")),
+                    "check the addresses and endpoints of the subnet in the config",
+                ));
+                continue;
+            }
+        };
+        checks
+            .extend(check_subnet(subnet, conn.manager(), &fvm_wallet, &evm_keystore, &state).await);
+    }
+
+    checks
+}
+
+async fn check_subnet(
+    subnet: &Subnet,
+    manager: &dyn SubnetManager,
+    fvm_wallet: &Arc<RwLock<Wallet>>,
+    evm_keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
+) -> Vec<Check> {
+    let mut checks = vec![];
+
+    let reachable = manager.chain_head_epoch().await;
+    let reachable_ok = reachable.is_ok();
+    checks.push(Check::new(
+        format!("{} endpoint", subnet.id),
+        reachable
+            .map(|epoch| format!("{} is reachable, head at epoch {epoch:}", subnet.rpc_http())),
+        format!(
+            "make sure the node at {} is running and reachable from this host",
+            subnet.rpc_http()
+        ),
+    ));
+    // None of the other checks can succeed without the endpoint.
+    if !reachable_ok {
+        return checks;
+    }
+
+    if subnet.auth_token().is_some() {
+        checks.push(Check::new(
+            format!("{} auth token", subnet.id),
+            check_auth_token(subnet, manager, state).await,
+            "generate a new token with write permissions in the node and update `auth_token`",
+        ));
+    }
+
+    checks.push(Check::new(
+        format!("{} network", subnet.id),
+        manager
+            .ensure_network()
+            .await
+            .map(|_| String::from("the node serves the network the subnet is pinned to")),
+        "check that the endpoint points to a node of the subnet, or fix `expected_network_name` or `chain_id`",
+    ));
+
+    checks.push(Check::new(
+        format!("{} features", subnet.id),
+        Ok(format!("{:?} mode, {}", subnet.mode, subnet.features)),
+        "",
+    ));
+
+    checks.push(Check::new(
+        format!("{} gateway", subnet.id),
+        manager
+            .bottom_up_check_period(&subnet.gateway_addr())
+            .await
+            .map(|period| {
+                format!(
+                    "gateway at {} is compatible, checkpoint period {period:}",
+                    subnet.gateway_addr()
+                )
+            }),
+        "check `gateway_addr` and that the actors deployed in the subnet match this agent version",
+    ));
+
+    // The IPC contracts of fevm subnets are not versioned, the gateway check covers them.
+    if let SubnetConfig::Fvm(_) = subnet.config {
+        checks.push(Check::new(
+            format!("{} actors version", subnet.id),
+            check_actors_version(subnet, state).await,
+            format!(
+                "run the subnet on a node with builtin actors v{} to v{} and the IPC actors",
+                SUPPORTED_ACTORS_VERSIONS.start(),
+                SUPPORTED_ACTORS_VERSIONS.end()
+            ),
+        ));
+    }
+
+    // Observed subnets are only read from, their accounts don't need keys nor funds.
+    let accounts = match subnet.mode {
+        SubnetMode::Validate => subnet.accounts(),
+        SubnetMode::Observe => vec![],
+    };
+    for account in accounts {
+        checks.push(Check::new(
+            format!("{} key {account:}", subnet.id),
+            has_key(subnet, &account, fvm_wallet, evm_keystore, state)
+                .await
+                .map(|_| String::from("present in the keystore")),
+            match subnet.fvm_signer() {
+                FvmSigner::Agent => "import the key with `ipc-agent wallet import` or remove the account from the config",
+                FvmSigner::Node => "import the key into the keystore of the node or remove the account from the config",
+            },
+        ));
+
+        let balance: anyhow::Result<String> = try {
+            let balance = manager.wallet_balance(&account).await?;
+            if balance.is_zero() {
+                Err(anyhow!("account has no funds"))?;
+            }
+            balance.to_string()
+        };
+        checks.push(Check::new(
+            format!("{} balance {account:}", subnet.id),
+            balance.map(|b| format!("{b:} FIL")),
+            "fund the account so that it can pay for the gas of its messages",
+        ));
+    }
+
+    checks.push(Check::new(
+        format!("{} clock", subnet.id),
+        check_clock_skew(subnet, manager, state).await,
+        "sync the local clock with NTP and make sure the node is not lagging behind the chain",
+    ));
+
+    checks
+}
+
+async fn check_auth_token(
+    subnet: &Subnet,
+    manager: &dyn SubnetManager,
+    state: &Arc<AgentState>,
+) -> anyhow::Result<String> {
+    let token = subnet
+        .auth_token()
+        .ok_or_else(|| anyhow!("no auth token configured"))?;
+    match subnet.config {
+        // Lotus reports the permissions of a token, which must let the agent push messages, and
+        // sign them if the node is the signer.
+        SubnetConfig::Fvm(_) => {
+            let required = match subnet.fvm_signer() {
+                FvmSigner::Agent => "write",
+                FvmSigner::Node => "sign",
+            };
+            let permissions = LotusJsonRPCClient::from_subnet(subnet, state.clone())
+                .auth_verify(&token)
+                .await?;
+            if !permissions.iter().any(|p| p == required) {
+                return Err(anyhow!(
+                    "the token grants {permissions:?}, the agent needs `{required:}`"
+                ));
+            }
+            Ok(format!("accepted by the node, grants {permissions:?}"))
+        }
+        // The token is sent in every request to the provider, so it is only tested if the
+        // provider refuses the requests without it.
+        SubnetConfig::Fevm(_) => {
+            manager.chain_head_epoch().await?;
+            let anonymous = JsonRpcClientImpl::new(subnet.rpc_http().clone(), None)
+                .with_headers(subnet.http_headers())
+                .request::<String>("eth_blockNumber", serde_json::json!([]))
+                .await;
+            match anonymous {
+                Ok(_) => Ok(String::from(
+                    "the endpoint does not require a token, it accepts any",
+                )),
+                Err(_) => Ok(String::from(
+                    "accepted by the endpoint, which refuses requests without it",
+                )),
+            }
+        }
+    }
+}
+
+/// Checks that the node of an fvm subnet runs a version of the builtin actors supported by the
+/// agent, with the IPC actors in its manifest.
+async fn check_actors_version(subnet: &Subnet, state: &Arc<AgentState>) -> anyhow::Result<String> {
+    let client = LotusJsonRPCClient::from_subnet(subnet, state.clone());
+    let network_version = client.state_network_version(vec![]).await?;
+    let version = actors_version(network_version).ok_or_else(|| {
+        anyhow!("network version {network_version:} is unknown to this agent version")
+    })?;
+    if !SUPPORTED_ACTORS_VERSIONS.contains(&version) {
+        return Err(anyhow!(
+            "the node runs builtin actors v{version:}, the agent supports v{} to v{}",
+            SUPPORTED_ACTORS_VERSIONS.start(),
+            SUPPORTED_ACTORS_VERSIONS.end()
+        ));
+    }
+
+    let manifest = client.state_actor_code_cids(network_version).await?;
+    let missing = IPC_ACTORS
+        .iter()
+        .filter(|a| !manifest.contains_key(**a))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(anyhow!("the actors manifest of the node lacks {missing:?}"));
+    }
+
+    Ok(format!(
+        "builtin actors v{version:} at network version {network_version:}, with the IPC actors"
+    ))
+}
+
+/// The version of the builtin actors of a network version, see:
+/// https://github.com/filecoin-project/lotus/blob/master/chain/actors/version.go
+fn actors_version(network_version: NetworkVersion) -> Option<u32> {
+    let version = match network_version {
+        0..=3 => 0,
+        4..=9 => 2,
+        10..=11 => 3,
+        12 => 4,
+        13 => 5,
+        14 => 6,
+        15 => 7,
+        16 => 8,
+        17 => 9,
+        18 => 10,
+        19..=20 => 11,
+        21 => 12,
+        _ => return None,
+    };
+    Some(version)
+}
+
+async fn has_key(
+    subnet: &Subnet,
+    account: &Address,
+    fvm_wallet: &Arc<RwLock<Wallet>>,
+    evm_keystore: &Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: &Arc<AgentState>,
+) -> anyhow::Result<()> {
+    let found = match subnet.config {
+        // The node signs the messages, so the key must be in its keystore instead.
+        SubnetConfig::Fvm(_) if subnet.fvm_signer() == FvmSigner::Node => {
+            LotusJsonRPCClient::from_subnet(subnet, state.clone())
+                .wallet_list()
+                .await?
+                .iter()
+                .any(|a| Address::from_str(a).map_or(false, |a| a == *account))
+        }
+        SubnetConfig::Fvm(_) => fvm_wallet.write().unwrap().has_key(account),
+        SubnetConfig::Fevm(_) => evm_keystore
+            .read()
+            .unwrap()
+            .list()?
+            .iter()
+            .filter_map(|a| ethers_address_to_fil_address(a).ok())
+            .any(|a| a == *account),
+    };
+    if !found {
+        return Err(anyhow!("key not found in the keystore"));
+    }
+    Ok(())
+}
+
+async fn check_clock_skew(
+    subnet: &Subnet,
+    manager: &dyn SubnetManager,
+    state: &AgentState,
+) -> anyhow::Result<String> {
+    let head = manager.chain_head_timestamp().await?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    if head > now + MAX_CLOCK_SKEW.as_secs() {
+        return Err(anyhow!(
+            "chain head is {}s ahead of the local clock",
+            head - now
+        ));
+    }
+
+    // The head lags behind by up to a block time in normal operation.
+    let block_time = match subnet.block_time() {
+        Some(block_time) => block_time,
+        None => clock::calibration(&state.epoch_calibrations, subnet, manager)
+            .await
+            .map_or(DEFAULT_BLOCK_TIME, |c| c.block_time),
+    };
+    let lag = now.saturating_sub(head);
+    if lag > (MAX_CLOCK_SKEW + block_time).as_secs() {
+        return Err(anyhow!("chain head is {lag:}s behind the local clock"));
+    }
+
+    Ok(format!("chain head is {lag:}s behind the local clock"))
+}
@@ -0,0 +1,62 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Control job cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::jobs::JobAction;
+use crate::sdk::IpcAgentClient;
+
+/// The command to pause, resume or cancel a background job.
+pub(crate) struct ControlJob;
+
+#[async_trait]
+impl CommandLineHandler for ControlJob {
+    type Arguments = ControlJobArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("control job with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let action = match arguments.action {
+            ControlAction::Pause => JobAction::Pause,
+            ControlAction::Resume => JobAction::Resume,
+            ControlAction::Cancel => JobAction::Cancel,
+        };
+        let job = client.control_job(arguments.id, action).await?;
+
+        tracing::info!(
+            "job {} ({}) is now {:?} at {:.1}%",
+            job.id,
+            job.kind,
+            job.status,
+            job.progress
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum ControlAction {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Pause, resume or cancel a background job")]
+pub(crate) struct ControlJobArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, help = "The id of the job")]
+    pub id: u64,
+    #[arg(value_enum, help = "The action to run on the job")]
+    pub action: ControlAction,
+}
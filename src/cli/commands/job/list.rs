@@ -0,0 +1,55 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List jobs cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the background jobs of the agent.
+pub(crate) struct ListJobs;
+
+#[async_trait]
+impl CommandLineHandler for ListJobs {
+    type Arguments = ListJobsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list jobs with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let jobs = client.list_jobs().await?;
+
+        if jobs.is_empty() {
+            tracing::info!("no jobs");
+        }
+        for job in jobs.iter() {
+            tracing::info!(
+                "job {}: {} {:?} ({:.1}%), params={}{}",
+                job.id,
+                job.kind,
+                job.status,
+                job.progress,
+                job.params,
+                job.error
+                    .as_ref()
+                    .map(|e| format!(", error={e:}"))
+                    .unwrap_or_default()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the background jobs of the agent")]
+pub(crate) struct ListJobsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+}
@@ -0,0 +1,37 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::cli::{CommandLineHandler, GlobalArguments};
+
+use clap::{Args, Subcommand};
+
+use self::control::{ControlJob, ControlJobArgs};
+use self::list::{ListJobs, ListJobsArgs};
+
+mod control;
+mod list;
+
+#[derive(Debug, Args)]
+#[command(
+    name = "job",
+    about = "background jobs of the agent, like history backfills"
+)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct JobCommandsArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl JobCommandsArgs {
+    pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
+        match &self.command {
+            Commands::List(args) => ListJobs::handle(global, args).await,
+            Commands::Control(args) => ControlJob::handle(global, args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    List(ListJobsArgs),
+    Control(ControlJobArgs),
+}
@@ -0,0 +1,87 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The maintenance command line handler, to upgrade or restart the agent safely.
+
+use std::fmt::Debug;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::manager::maintenance::{MaintenanceAction, MaintenanceStatus};
+use crate::sdk::IpcAgentClient;
+
+/// How often the status is checked while waiting for the agent to be safe to upgrade.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The command to put the agent into maintenance, take it out of it or check its status.
+pub(crate) struct Maintenance;
+
+#[async_trait]
+impl CommandLineHandler for Maintenance {
+    type Arguments = MaintenanceArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("maintenance with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let action = match arguments.action {
+            Action::Enter => MaintenanceAction::Enter,
+            Action::Exit => MaintenanceAction::Exit,
+            Action::Status => MaintenanceAction::Status,
+        };
+        let mut status = client.maintenance(action, arguments.reason.clone()).await?;
+
+        if arguments.wait && status.enabled {
+            while !status.safe_to_upgrade {
+                tracing::info!("waiting for: {}", status.in_flight.join(", "));
+                tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+                status = client.maintenance(MaintenanceAction::Status, None).await?;
+            }
+        }
+        tracing::info!("{}", describe(&status));
+
+        Ok(())
+    }
+}
+
+fn describe(status: &MaintenanceStatus) -> String {
+    if !status.enabled {
+        return String::from("the agent is running normally");
+    }
+    if status.safe_to_upgrade {
+        return String::from("the agent is in maintenance and safe to upgrade");
+    }
+    format!(
+        "the agent is in maintenance, not safe to upgrade yet, still running: {}",
+        status.in_flight.join(", ")
+    )
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum Action {
+    /// Finish the running submissions and jobs, and pause them until the agent leaves maintenance
+    Enter,
+    /// Resume the submissions and jobs
+    Exit,
+    Status,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Put the agent into maintenance before upgrading or restarting it")]
+pub(crate) struct MaintenanceArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(value_enum, help = "The maintenance action")]
+    pub action: Action,
+    #[arg(long, help = "Why the agent is put into maintenance")]
+    pub reason: Option<String>,
+    #[arg(
+        long,
+        help = "Wait until the agent is safe to upgrade before returning"
+    )]
+    pub wait: bool,
+}
@@ -2,19 +2,36 @@
 // SPDX-License-Identifier: MIT
 //! This mod contains the different command line implementations.
 
+mod approval;
 mod checkpoint;
 mod config;
 mod crossmsg;
 mod daemon;
+mod deploy;
+mod doctor;
+mod job;
+mod maintenance;
+mod notification;
 mod subnet;
+mod topology;
+mod tx;
 mod util;
 pub mod wallet;
 
+use crate::cli::commands::approval::ApprovalCommandsArgs;
 use crate::cli::commands::checkpoint::CheckpointCommandsArgs;
 use crate::cli::commands::crossmsg::CrossMsgsCommandsArgs;
 use crate::cli::commands::daemon::{LaunchDaemon, LaunchDaemonArgs};
+use crate::cli::commands::deploy::DeployCommandsArgs;
+use crate::cli::commands::doctor::{Doctor, DoctorArgs};
+use crate::cli::commands::job::JobCommandsArgs;
+use crate::cli::commands::maintenance::{Maintenance, MaintenanceArgs};
+use crate::cli::commands::notification::NotificationCommandsArgs;
+use crate::cli::commands::topology::{Topology, TopologyArgs};
+use crate::cli::commands::tx::TxCommandsArgs;
 use crate::cli::commands::util::UtilCommandsArgs;
 use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::logging::Logger;
 use crate::server::{new_evm_keystore_from_path, new_keystore_from_path};
 use anyhow::{Context, Result};
 
@@ -32,8 +49,6 @@ use crate::cli::commands::wallet::WalletCommandsArgs;
 
 pub use subnet::*;
 
-use super::default_repo_path;
-
 /// The collection of all subcommands to be called, see clap's documentation for usage. Internal
 /// to the current mode. Register a new command accordingly.
 #[derive(Debug, Subcommand)]
@@ -44,12 +59,24 @@ enum Commands {
     /// and not in the background as what daemon processes are. Still, this struct contains `Daemon`
     /// due to the convention from `lotus` and the expected behavior from the filecoin user group.
     Daemon(LaunchDaemonArgs),
+    /// Run a battery of checks against the agent setup and print a report.
+    Doctor(DoctorArgs),
+    /// Export the hierarchy of the subnets as a dot, mermaid or json diagram.
+    Topology(TopologyArgs),
+    /// Put the agent into maintenance before upgrading it, or take it out of it.
+    Maintenance(MaintenanceArgs),
+    /// Generate the systemd unit, docker compose file or kubernetes manifests of the agent.
+    Deploy(DeployCommandsArgs),
     Config(ConfigCommandsArgs),
     Subnet(SubnetCommandsArgs),
     Wallet(WalletCommandsArgs),
     CrossMsg(CrossMsgsCommandsArgs),
     Checkpoint(CheckpointCommandsArgs),
+    Tx(TxCommandsArgs),
     Util(UtilCommandsArgs),
+    Approval(ApprovalCommandsArgs),
+    Job(JobCommandsArgs),
+    Notification(NotificationCommandsArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -80,7 +107,7 @@ struct IPCAgentCliCommands {
 ///
 /// To register a new command, add the command to
 /// ```ignore
-/// pub async fn cli() {
+/// pub async fn cli(logger: Logger) {
 ///
 ///     // ... other code
 ///
@@ -97,7 +124,7 @@ struct IPCAgentCliCommands {
 ///     NewCommand(NewCommandArgs),
 /// }
 /// ```
-pub async fn cli() -> anyhow::Result<()> {
+pub async fn cli(logger: Logger) -> anyhow::Result<()> {
     // parse the arguments
     let args = IPCAgentCliCommands::parse();
 
@@ -109,13 +136,21 @@ pub async fn cli() -> anyhow::Result<()> {
         let global = &args.global_params;
         if let Some(c) = &args.command {
             let r = match &c {
-                Commands::Daemon(args) => LaunchDaemon::handle(global, args).await,
+                Commands::Daemon(args) => LaunchDaemon::handle(global, args, &logger).await,
+                Commands::Doctor(args) => Doctor::handle(global, args).await,
+                Commands::Topology(args) => Topology::handle(global, args).await,
+                Commands::Maintenance(args) => Maintenance::handle(global, args).await,
+                Commands::Deploy(args) => args.handle(global).await,
                 Commands::Config(args) => args.handle(global).await,
                 Commands::Subnet(args) => args.handle(global).await,
                 Commands::CrossMsg(args) => args.handle(global).await,
                 Commands::Wallet(args) => args.handle(global).await,
                 Commands::Checkpoint(args) => args.handle(global).await,
+                Commands::Tx(args) => args.handle(global).await,
                 Commands::Util(args) => args.handle(global).await,
+                Commands::Approval(args) => args.handle(global).await,
+                Commands::Job(args) => args.handle(global).await,
+                Commands::Notification(args) => args.handle(global).await,
             };
 
             r.with_context(|| format!("error processing command {:?}", args.command))
@@ -147,19 +182,22 @@ pub(crate) fn get_ipc_agent_url(
     Ok(url)
 }
 
-pub(crate) fn get_fvm_store(path: Option<String>) -> Result<KeyStore> {
+/// The fvm keystore in `path`, or in the data dir of the agent if not set.
+pub(crate) fn get_fvm_store(global: &GlobalArguments, path: Option<String>) -> Result<KeyStore> {
     let path = match path {
         Some(p) => p,
-        None => default_repo_path(),
+        None => global.data_dir()?.keystore_repo()?,
     };
     new_keystore_from_path(&path)
 }
 
+/// The evm keystore in `path`, or in the data dir of the agent if not set.
 pub(crate) fn get_evm_keystore(
+    global: &GlobalArguments,
     path: &Option<String>,
 ) -> Result<PersistentKeyStore<ethers::types::Address>> {
     match path {
         Some(p) => new_evm_keystore_from_path(p),
-        None => new_evm_keystore_from_path(&default_repo_path()),
+        None => new_evm_keystore_from_path(&global.data_dir()?.keystore_repo()?),
     }
 }
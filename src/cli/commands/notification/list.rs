@@ -0,0 +1,60 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List notifications cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::notify::Delivery;
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the deliveries of the notifications of chain events.
+pub(crate) struct ListNotifications;
+
+#[async_trait]
+impl CommandLineHandler for ListNotifications {
+    type Arguments = ListNotificationsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list notifications with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let deliveries = client.list_notifications().await?;
+
+        if deliveries.is_empty() {
+            tracing::info!("no notifications");
+        }
+        for d in deliveries.iter() {
+            log_delivery(d);
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn log_delivery(d: &Delivery) {
+    tracing::info!(
+        "{} at epoch {} in {}: {:?} after {} attempts{}",
+        d.notification.kind,
+        d.notification.epoch.unwrap_or_default(),
+        d.notification.subnet,
+        d.status,
+        d.attempts,
+        d.error
+            .as_ref()
+            .map(|e| format!(", error={e:}"))
+            .unwrap_or_default()
+    );
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the deliveries of the notifications of chain events")]
+pub(crate) struct ListNotificationsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+}
@@ -0,0 +1,37 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::cli::{CommandLineHandler, GlobalArguments};
+
+use clap::{Args, Subcommand};
+
+use self::list::{ListNotifications, ListNotificationsArgs};
+use self::redeliver::{RedeliverNotifications, RedeliverNotificationsArgs};
+
+mod list;
+mod redeliver;
+
+#[derive(Debug, Args)]
+#[command(
+    name = "notification",
+    about = "notifications of chain events, like committed checkpoints"
+)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct NotificationCommandsArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl NotificationCommandsArgs {
+    pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
+        match &self.command {
+            Commands::List(args) => ListNotifications::handle(global, args).await,
+            Commands::Redeliver(args) => RedeliverNotifications::handle(global, args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    List(ListNotificationsArgs),
+    Redeliver(RedeliverNotificationsArgs),
+}
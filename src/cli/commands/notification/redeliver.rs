@@ -0,0 +1,58 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Redeliver notifications cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::commands::notification::list::log_delivery;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to post the notifications of chain events already delivered again.
+pub(crate) struct RedeliverNotifications;
+
+#[async_trait]
+impl CommandLineHandler for RedeliverNotifications {
+    type Arguments = RedeliverNotificationsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("redeliver notifications with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let deliveries = client
+            .redeliver_notifications(
+                arguments.subnet.clone(),
+                arguments.from_epoch,
+                arguments.to_epoch,
+            )
+            .await?;
+
+        if deliveries.is_empty() {
+            tracing::info!("no notifications to redeliver");
+        }
+        for d in deliveries.iter() {
+            log_delivery(d);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Post the notifications of the chain events of a subnet again")]
+pub(crate) struct RedeliverNotificationsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet of the events")]
+    pub subnet: String,
+    #[arg(long, help = "The epoch of the first event")]
+    pub from_epoch: ChainEpoch,
+    #[arg(long, help = "The epoch of the last event, the first one if not set")]
+    pub to_epoch: Option<ChainEpoch>,
+}
@@ -0,0 +1,63 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Circulating supply cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::circulating_supply::CirculatingSupplyParams;
+
+/// The command to get the circulating supply of a subnet.
+pub(crate) struct CirculatingSupply;
+
+#[async_trait]
+impl CommandLineHandler for CirculatingSupply {
+    type Arguments = CirculatingSupplyArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("circulating supply with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let supply = client
+            .circulating_supply(CirculatingSupplyParams {
+                subnet_id: arguments.subnet.clone(),
+                parent_epoch: arguments.parent_epoch,
+                child_epoch: arguments.child_epoch,
+            })
+            .await?;
+
+        tracing::info!("circulating supply: {}", supply.circulating_supply);
+        match supply.locked {
+            Some(locked) => tracing::info!("locked in the subnet gateway: {locked:}"),
+            None => tracing::info!("locked in the subnet gateway: unknown, subnet not configured"),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Get the circulating supply of a subnet and the amount locked in its gateway")]
+pub(crate) struct CirculatingSupplyArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet to get the circulating supply of")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The epoch of the parent to read the supply at, defaults to the head"
+    )]
+    pub parent_epoch: Option<ChainEpoch>,
+    #[arg(
+        long,
+        help = "The epoch of the subnet to read the locked amount at, defaults to the head"
+    )]
+    pub child_epoch: Option<ChainEpoch>,
+}
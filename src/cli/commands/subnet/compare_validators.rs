@@ -0,0 +1,82 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Compare validator sets cli command
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
+use crate::server::compare_validators::{CompareValidatorSetsParams, CompareValidatorSetsResponse};
+
+/// The command to compare the validator set of a subnet in its parent with the membership of its
+/// consensus.
+pub(crate) struct CompareValidatorSets;
+
+#[async_trait]
+impl CommandLineHandler for CompareValidatorSets {
+    type Arguments = CompareValidatorSetsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("compare validator sets with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
+
+        let params = CompareValidatorSetsParams {
+            subnet: arguments.subnet.clone(),
+        };
+
+        let r = json_rpc_client
+            .request::<CompareValidatorSetsResponse>(
+                json_rpc_methods::COMPARE_VALIDATOR_SETS,
+                serde_json::to_value(params)?,
+            )
+            .await?;
+
+        tracing::info!(
+            "configuration number in the parent: {}, in the subnet: {}",
+            r.parent_configuration_number,
+            r.child_configuration_number
+        );
+        if r.consistent {
+            tracing::info!(
+                "the membership of subnet {} matches its validator set in the parent",
+                r.subnet
+            );
+            return Ok(());
+        }
+        for v in r.missing_in_child {
+            tracing::warn!("{v} is a validator in the parent but not in the subnet consensus");
+        }
+        for v in r.missing_in_parent {
+            tracing::warn!("{v} is in the subnet consensus but not a validator in the parent");
+        }
+        for m in r.weight_mismatches {
+            tracing::warn!(
+                "{} has weight {} in the parent but {} in the subnet consensus",
+                m.validator,
+                m.parent_weight,
+                m.child_weight
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "compare-validators",
+    about = "Check that the membership of the subnet consensus matches its validator set in the parent"
+)]
+pub(crate) struct CompareValidatorSetsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet id to compare the validators of")]
+    pub subnet: String,
+}
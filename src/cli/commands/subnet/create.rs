@@ -3,14 +3,16 @@
 //! Create subnet cli command handler.
 
 use async_trait::async_trait;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use fvm_shared::clock::ChainEpoch;
 use std::fmt::Debug;
 
+use crate::amount::Amount;
 use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::lotus::message::ipc::PermissionMode;
 use crate::sdk::IpcAgentClient;
-use crate::server::create::CreateSubnetParams;
+use crate::server::create::{CreateSubnetParams, SubnetConsensus, SupplySource};
 
 /// The command to create a new subnet actor.
 pub struct CreateSubnet;
@@ -21,18 +23,33 @@ impl CreateSubnet {
         arguments: &CreateSubnetArgs,
     ) -> anyhow::Result<String> {
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let params = CreateSubnetParams {
+        let client = IpcAgentClient::default_from_url(url);
+        client.create_subnet(Self::params(arguments)).await
+    }
+
+    fn params(arguments: &CreateSubnetArgs) -> CreateSubnetParams {
+        CreateSubnetParams {
             from: arguments.from.clone(),
             parent: arguments.parent.clone(),
             name: arguments.name.clone(),
+            consensus: match arguments.consensus {
+                CreateSubnetConsensus::Mir => SubnetConsensus::Mir,
+            },
             min_validator_stake: arguments.min_validator_stake,
             min_validators: arguments.min_validators,
             bottomup_check_period: arguments.bottomup_check_period,
             topdown_check_period: arguments.topdown_check_period,
-        };
-
-        let client = IpcAgentClient::default_from_url(url);
-        client.create_subnet(params).await
+            active_validators_limit: arguments.active_validators_limit,
+            permission_mode: arguments.permission_mode.map(|m| match m {
+                CreateSubnetPermissionMode::Collateral => PermissionMode::Collateral,
+                CreateSubnetPermissionMode::Federated => PermissionMode::Federated,
+                CreateSubnetPermissionMode::Static => PermissionMode::Static,
+            }),
+            supply_source: arguments
+                .supply_source_token
+                .clone()
+                .map(|token| SupplySource::Erc20 { token }),
+        }
     }
 }
 
@@ -41,11 +58,36 @@ impl CommandLineHandler for CreateSubnet {
     type Arguments = CreateSubnetArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("create subnet with args: {:?}", arguments);
+        tracing::debug!("create subnet with args: {:?}", arguments);
+
+        if arguments.preview {
+            let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+            let client = IpcAgentClient::default_from_url(url);
+            let preview = client
+                .preview_create_subnet(CreateSubnet::params(arguments))
+                .await?;
+            let estimated = |amount: Option<Amount>| {
+                amount.map_or(String::from("cannot be estimated"), |a| {
+                    format!("{} {}", a.fil, a.unit)
+                })
+            };
+            tracing::info!(
+                "creating subnet {} from {} costs a registry fee {}, a gas fee of up to {}, and {} {} of collateral staked by its validators to activate it, {} {} in total",
+                arguments.name,
+                preview.from,
+                estimated(preview.registry_fee),
+                estimated(preview.estimated_gas_fee),
+                preview.initial_collateral.fil,
+                preview.initial_collateral.unit,
+                preview.total.fil,
+                preview.total.unit
+            );
+            return Ok(());
+        }
 
         let address = CreateSubnet::create(global, arguments).await?;
 
-        log::info!(
+        tracing::info!(
             "created subnet actor with id: {}/{}",
             arguments.parent,
             address
@@ -55,6 +97,18 @@ impl CommandLineHandler for CreateSubnet {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CreateSubnetConsensus {
+    Mir,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CreateSubnetPermissionMode {
+    Collateral,
+    Federated,
+    Static,
+}
+
 #[derive(Debug, Args)]
 #[command(name = "create", about = "Create a new subnet actor")]
 pub struct CreateSubnetArgs {
@@ -74,4 +128,27 @@ pub struct CreateSubnetArgs {
     pub bottomup_check_period: ChainEpoch,
     #[arg(long, help = "The top down checkpoint period in number of blocks")]
     pub topdown_check_period: ChainEpoch,
+    #[arg(long, value_enum, default_value_t = CreateSubnetConsensus::Mir, help = "The consensus of the subnet")]
+    pub consensus: CreateSubnetConsensus,
+    #[arg(
+        long,
+        help = "The maximum number of active validators, unlimited if not set"
+    )]
+    pub active_validators_limit: Option<u64>,
+    #[arg(
+        long,
+        value_enum,
+        help = "How validators are admitted in the subnet, with collateral if not set"
+    )]
+    pub permission_mode: Option<CreateSubnetPermissionMode>,
+    #[arg(
+        long,
+        help = "The ERC20 token of the parent funding the subnet, its native token if not set"
+    )]
+    pub supply_source_token: Option<String>,
+    #[arg(
+        long,
+        help = "Only estimate what creating the subnet costs, without creating it"
+    )]
+    pub preview: bool,
 }
@@ -0,0 +1,58 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Decommission a killed subnet cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::decommission::DecommissionSubnetParams;
+
+/// The command to clean up after a killed subnet.
+pub(crate) struct DecommissionSubnet;
+
+#[async_trait]
+impl CommandLineHandler for DecommissionSubnet {
+    type Arguments = DecommissionSubnetArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("decommission subnet with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let params = DecommissionSubnetParams {
+            subnet: arguments.subnet.clone(),
+            dry_run: arguments.dry_run,
+        };
+        let r = client.decommission_subnet(params).await?;
+
+        if r.steps.is_empty() {
+            tracing::info!("nothing left to clean up for subnet {}", r.subnet);
+        }
+        for step in r.steps {
+            match step.error {
+                Some(e) => tracing::info!("{:?}: {} ({e:})", step.status, step.description),
+                None => tracing::info!("{:?}: {}", step.status, step.description),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "decommission",
+    about = "Release the funds left in a killed subnet and remove it from the config"
+)]
+pub(crate) struct DecommissionSubnetArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The killed subnet")]
+    pub subnet: String,
+    #[arg(long, help = "Only report the clean up, without running it")]
+    pub dry_run: bool,
+}
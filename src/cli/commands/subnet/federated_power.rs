@@ -0,0 +1,83 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The command to set the power of the validators of a federated subnet
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::federated_power::{FederatedPower, SetFederatedPowerParams};
+
+/// Setting the power of validators in a federated subnet
+pub(crate) struct SetFederatedPower;
+
+impl SetFederatedPower {
+    /// Parses a validator in the `<public-key>=<power>` format.
+    fn parse_validator(s: &str) -> anyhow::Result<FederatedPower> {
+        let (public_key, power) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("validator {s:} not in the <public-key>=<power> format"))?;
+        Ok(FederatedPower {
+            public_key: public_key.to_string(),
+            power: power.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl CommandLineHandler for SetFederatedPower {
+    type Arguments = SetFederatedPowerArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("set federated power with args: {:?}", arguments);
+
+        let validators = arguments
+            .validator
+            .iter()
+            .map(|v| SetFederatedPower::parse_validator(v))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let r = client
+            .set_federated_power(SetFederatedPowerParams {
+                subnet: arguments.subnet.clone(),
+                from: arguments.from.clone(),
+                validators,
+            })
+            .await?;
+
+        tracing::info!(
+            "set the power of validators {:?} in subnet: {:}",
+            r.addresses,
+            arguments.subnet
+        );
+        if let Some(set) = r.validator_set {
+            tracing::info!("validator set: {:#?}", set.validator_set);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Set the public keys and power of the validators of a federated subnet")]
+pub(crate) struct SetFederatedPowerArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "Owner address of the subnet")]
+    pub from: Option<String>,
+    #[arg(long, short, help = "The federated subnet to set the validators of")]
+    pub subnet: String,
+    #[arg(
+        long,
+        short,
+        required = true,
+        help = "A validator as <public-key>=<power>, with the hex encoded uncompressed secp256k1 public key, can be repeated"
+    )]
+    pub validator: Vec<String>,
+}
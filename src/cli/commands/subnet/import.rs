@@ -0,0 +1,115 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Import child subnets into the config cli command
+
+use std::fmt::Debug;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::import::{ImportSubnet, ImportSubnetsParams};
+
+/// The command to discover the children of a configured subnet and import one into the config.
+pub(crate) struct ImportSubnets;
+
+#[async_trait]
+impl CommandLineHandler for ImportSubnets {
+    type Arguments = ImportSubnetsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("import subnets with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+
+        let (subnet, endpoint) = match (&arguments.subnet, &arguments.endpoint) {
+            (Some(s), Some(e)) => (s.clone(), e.clone()),
+            (Some(_), None) => return Err(anyhow!("--endpoint is required to import")),
+            _ => {
+                let discovered = client.discover_subnets(arguments.parent.clone()).await?;
+                if discovered.is_empty() {
+                    tracing::info!("no child subnet found in {}", arguments.parent);
+                }
+                for s in discovered {
+                    let configured = if s.configured { " (configured)" } else { "" };
+                    tracing::info!(
+                        "{}{configured:} - status: {}, collateral: {}, circ.supply: {}, network type: {}, gateway: {}",
+                        s.id,
+                        s.status,
+                        s.stake,
+                        s.circ_supply,
+                        s.network_type,
+                        s.gateway_addr
+                    );
+                }
+                tracing::info!("import one of them with --subnet <id> --endpoint <url>");
+                return Ok(());
+            }
+        };
+
+        let params = ImportSubnetsParams {
+            parent: arguments.parent.clone(),
+            subnets: vec![ImportSubnet {
+                id: subnet,
+                network_name: arguments.network_name.clone(),
+                jsonrpc_api_http: endpoint,
+                auth_token: arguments.auth_token.clone(),
+                accounts: arguments.account.clone(),
+                gateway_addr: arguments.gateway_addr.clone(),
+            }],
+        };
+        for id in client.import_subnets(params).await?.imported {
+            tracing::info!("imported subnet {id:} into the config");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "import",
+    about = "Discover the child subnets of a configured subnet, and import one into the config"
+)]
+pub(crate) struct ImportSubnetsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The configured subnet to discover the children of"
+    )]
+    pub parent: String,
+    #[arg(
+        long,
+        short,
+        help = "The child subnet to import, the children are listed if not set"
+    )]
+    pub subnet: Option<String>,
+    #[arg(long, short, help = "The JSON RPC endpoint of the node of the subnet")]
+    pub endpoint: Option<String>,
+    #[arg(long, help = "The auth token of the node of the subnet")]
+    pub auth_token: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "An account of the agent in the subnet, can be repeated"
+    )]
+    pub account: Vec<String>,
+    #[arg(
+        long,
+        short,
+        help = "The name of the network, the subnet actor by default"
+    )]
+    pub network_name: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "Overrides the inferred gateway address of the subnet"
+    )]
+    pub gateway_addr: Option<String>,
+}
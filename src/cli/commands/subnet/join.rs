@@ -19,7 +19,7 @@ impl CommandLineHandler for JoinSubnet {
     type Arguments = JoinSubnetArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("join subnet with args: {:?}", arguments);
+        tracing::debug!("join subnet with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
 
@@ -36,7 +36,7 @@ impl CommandLineHandler for JoinSubnet {
         let client = IpcAgentClient::default_from_url(url);
         client.join_subnet(params).await?;
 
-        log::info!("joined subnet: {:}", arguments.subnet);
+        tracing::info!("joined subnet: {:}", arguments.subnet);
 
         Ok(())
     }
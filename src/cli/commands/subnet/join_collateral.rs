@@ -0,0 +1,73 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Estimate join collateral cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::join_collateral::EstimateJoinCollateralParams;
+
+/// The command to estimate what is needed to join a subnet
+pub struct EstimateJoinCollateral;
+
+#[async_trait]
+impl CommandLineHandler for EstimateJoinCollateral {
+    type Arguments = EstimateJoinCollateralArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("estimate join collateral with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let params = EstimateJoinCollateralParams {
+            subnet: arguments.subnet.clone(),
+            from: arguments.from.clone(),
+            validator_net_addr: arguments.validator_net_addr.clone(),
+        };
+
+        let client = IpcAgentClient::default_from_url(url);
+        let r = client.estimate_join_collateral(params).await?;
+
+        tracing::info!("minimum collateral: {}", r.min_collateral);
+        match r.estimated_gas_fee {
+            Some(fee) => tracing::info!("estimated gas fee: {fee:}"),
+            None => tracing::info!("estimated gas fee: unknown"),
+        }
+        tracing::info!("total needed: {}", r.total);
+        match r.max_validators {
+            Some(max) => tracing::info!(
+                "validators: {} (minimum {}, maximum {max:})",
+                r.validators,
+                r.min_validators
+            ),
+            None => tracing::info!(
+                "validators: {} (minimum {})",
+                r.validators,
+                r.min_validators
+            ),
+        }
+        tracing::info!("permission mode: {}", r.permission_mode);
+        tracing::info!("accepting validators: {}", r.accepting_validators);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "estimate-join",
+    about = "Estimate the collateral and gas needed to join a subnet"
+)]
+pub struct EstimateJoinCollateralArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The address that would join the subnet")]
+    pub from: Option<String>,
+    #[arg(long, short, help = "The subnet to join")]
+    pub subnet: String,
+    #[arg(long, short, help = "The validator net address")]
+    pub validator_net_addr: Option<String>,
+}
@@ -19,7 +19,7 @@ impl CommandLineHandler for KillSubnet {
     type Arguments = KillSubnetArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("kill subnet with args: {:?}", arguments);
+        tracing::debug!("kill subnet with args: {:?}", arguments);
 
         let params = KillSubnetParams {
             subnet: arguments.subnet.clone(),
@@ -30,7 +30,7 @@ impl CommandLineHandler for KillSubnet {
         let client = IpcAgentClient::default_from_url(url);
         client.kill_subnet(params).await?;
 
-        log::info!("killed subnet: {:}", arguments.subnet);
+        tracing::info!("killed subnet: {:}", arguments.subnet);
 
         Ok(())
     }
@@ -19,7 +19,7 @@ impl CommandLineHandler for LeaveSubnet {
     type Arguments = LeaveSubnetArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("leave subnet with args: {:?}", arguments);
+        tracing::debug!("leave subnet with args: {:?}", arguments);
 
         let params = LeaveSubnetParams {
             subnet: arguments.subnet.clone(),
@@ -31,7 +31,7 @@ impl CommandLineHandler for LeaveSubnet {
         let client = IpcAgentClient::default_from_url(url);
         client.leave_subnet(params).await?;
 
-        log::info!("left subnet: {:}", arguments.subnet);
+        tracing::info!("left subnet: {:}", arguments.subnet);
 
         Ok(())
     }
@@ -0,0 +1,65 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List the child subnets the accounts of the agent take part in cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the children of a subnet that the accounts of the agent own, validate or
+/// have funds in.
+pub(crate) struct ListOwnSubnets;
+
+#[async_trait]
+impl CommandLineHandler for ListOwnSubnets {
+    type Arguments = ListOwnSubnetsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list own subnets with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let subnets = client.list_own_subnets(arguments.parent.clone()).await?;
+
+        if subnets.is_empty() {
+            tracing::info!(
+                "no child subnet of {} with any of the accounts of the agent",
+                arguments.parent
+            );
+        }
+        for s in subnets {
+            let balances = s
+                .balances
+                .iter()
+                .map(|(account, balance)| format!("{account:}: {balance:}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tracing::info!(
+                "{} - status: {}, owners: {:?}, validators: {:?}, balances: [{balances:}]",
+                s.id,
+                s.status,
+                s.owners,
+                s.validators
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "list-own",
+    about = "List the child subnets the accounts of the agent own, validate or have funds in"
+)]
+pub(crate) struct ListOwnSubnetsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The configured subnet to list the children of")]
+    pub parent: String,
+}
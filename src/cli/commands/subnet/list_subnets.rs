@@ -14,7 +14,8 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
-use crate::server::list_subnets::ListSubnetsParams;
+use crate::sdk::token_from_env;
+use crate::server::list_subnets::{ListSubnetsParams, SubnetDetails};
 use serde::Deserialize;
 
 /// The command to create a new subnet actor.
@@ -25,14 +26,16 @@ impl CommandLineHandler for ListSubnets {
     type Arguments = ListSubnetsArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("list subnets with args: {:?}", arguments);
+        tracing::debug!("list subnets with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let params = ListSubnetsParams {
             gateway_address: arguments.gateway_address.clone(),
             subnet_id: arguments.subnet.clone(),
+            details: arguments.details,
+            account: arguments.account.clone(),
         };
 
         let subnets = json_rpc_client
@@ -47,13 +50,28 @@ impl CommandLineHandler for ListSubnets {
             let stake = TokenAmount::from_atto(u);
             let u = BigInt::from_str(&s.circ_supply).unwrap();
             let supply = TokenAmount::from_atto(u);
-            log::info!(
+            tracing::info!(
                 "{} - status: {}, collateral: {} FIL, circ.supply: {} FIL",
                 s.id,
                 s.status,
                 stake,
                 supply,
             );
+            if let Some(d) = &s.details {
+                tracing::info!(
+                    "  locked: {}, balance of {}: {}",
+                    d.locked
+                        .as_ref()
+                        .map_or("unknown".to_string(), |a| a.to_string()),
+                    d.account.as_deref().unwrap_or("no account"),
+                    d.balance
+                        .as_ref()
+                        .map_or("unknown".to_string(), |a| a.to_string()),
+                );
+                if let Some(e) = &d.error {
+                    tracing::warn!("  cannot read the details of {}: {e:}", s.id);
+                }
+            }
         }
 
         Ok(())
@@ -72,6 +90,16 @@ pub(crate) struct ListSubnetsArgs {
     pub gateway_address: String,
     #[arg(long, short, help = "The subnet id to query child subnets")]
     pub subnet: String,
+    #[arg(
+        long,
+        help = "Include the funds locked in the gateway of the child subnets in the config and the balance of the account in them"
+    )]
+    pub details: bool,
+    #[arg(
+        long,
+        help = "The account whose balance is included, the first account of each child subnet in the config by default"
+    )]
+    pub account: Option<String>,
 }
 
 /// A simplified wrapper for Subnet Info response. The SubnetInfo struct is deserialized differently
@@ -88,4 +116,5 @@ struct SubnetInfoWrapper {
     circ_supply: String,
     #[allow(dead_code)]
     status: i32,
+    details: Option<SubnetDetails>,
 }
@@ -11,6 +11,7 @@ use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
 use crate::lotus::message::ipc::QueryValidatorSetResponse;
+use crate::sdk::token_from_env;
 use crate::server::query_validators::QueryValidatorSetParams;
 
 /// The command to create a new subnet actor.
@@ -21,10 +22,10 @@ impl CommandLineHandler for ListValidators {
     type Arguments = ListValidatorsArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("list validators with args: {:?}", arguments);
+        tracing::debug!("list validators with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let params = QueryValidatorSetParams {
             subnet: arguments.subnet.clone(),
@@ -37,8 +38,8 @@ impl CommandLineHandler for ListValidators {
             )
             .await?;
 
-        log::info!("validators number: {}", valset.min_validators);
-        log::info!("validator set: {:?}", valset.validator_set);
+        tracing::info!("validators number: {}", valset.min_validators);
+        tracing::info!("validator set: {:?}", valset.validator_set);
 
         Ok(())
     }
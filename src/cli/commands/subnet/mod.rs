@@ -1,14 +1,29 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 
-pub use crate::cli::commands::subnet::create::{CreateSubnet, CreateSubnetArgs};
+use crate::cli::commands::subnet::circulating_supply::{CirculatingSupply, CirculatingSupplyArgs};
+use crate::cli::commands::subnet::compare_validators::{
+    CompareValidatorSets, CompareValidatorSetsArgs,
+};
+pub use crate::cli::commands::subnet::create::{
+    CreateSubnet, CreateSubnetArgs, CreateSubnetConsensus, CreateSubnetPermissionMode,
+};
+use crate::cli::commands::subnet::decommission::{DecommissionSubnet, DecommissionSubnetArgs};
+use crate::cli::commands::subnet::federated_power::{SetFederatedPower, SetFederatedPowerArgs};
+use crate::cli::commands::subnet::import::{ImportSubnets, ImportSubnetsArgs};
 pub use crate::cli::commands::subnet::join::{JoinSubnet, JoinSubnetArgs};
+use crate::cli::commands::subnet::join_collateral::{
+    EstimateJoinCollateral, EstimateJoinCollateralArgs,
+};
 pub use crate::cli::commands::subnet::kill::{KillSubnet, KillSubnetArgs};
 pub use crate::cli::commands::subnet::leave::{LeaveSubnet, LeaveSubnetArgs};
+use crate::cli::commands::subnet::list_own::{ListOwnSubnets, ListOwnSubnetsArgs};
 use crate::cli::commands::subnet::list_subnets::{ListSubnets, ListSubnetsArgs};
 use crate::cli::commands::subnet::list_validators::{ListValidators, ListValidatorsArgs};
 use crate::cli::commands::subnet::net_addr::{SetValidatorNetAddr, SetValidatorNetAddrArgs};
+use crate::cli::commands::subnet::onboarding::{OnboardingDryRun, OnboardingDryRunArgs};
 use crate::cli::commands::subnet::send_value::{SendValue, SendValueArgs};
+use crate::cli::commands::subnet::validator_subnets::{ValidatorSubnets, ValidatorSubnetsArgs};
 use crate::cli::commands::subnet::worker_addr::{
     SetValidatorWorkerAddr, SetValidatorWorkerAddrArgs,
 };
@@ -17,15 +32,24 @@ use clap::{Args, Subcommand};
 
 use self::rpc::{RPCSubnet, RPCSubnetArgs};
 
+pub mod circulating_supply;
+pub mod compare_validators;
 pub mod create;
+pub mod decommission;
+pub mod federated_power;
+pub mod import;
 pub mod join;
+pub mod join_collateral;
 pub mod kill;
 pub mod leave;
+pub mod list_own;
 pub mod list_subnets;
 pub mod list_validators;
 pub mod net_addr;
+pub mod onboarding;
 pub mod rpc;
 pub mod send_value;
+pub mod validator_subnets;
 pub mod worker_addr;
 
 #[derive(Debug, Args)]
@@ -44,16 +68,25 @@ impl SubnetCommandsArgs {
         match &self.command {
             Commands::Create(args) => CreateSubnet::handle(global, args).await,
             Commands::List(args) => ListSubnets::handle(global, args).await,
+            Commands::Import(args) => ImportSubnets::handle(global, args).await,
+            Commands::ListOwn(args) => ListOwnSubnets::handle(global, args).await,
             Commands::ListValidators(args) => ListValidators::handle(global, args).await,
+            Commands::CompareValidators(args) => CompareValidatorSets::handle(global, args).await,
             Commands::Join(args) => JoinSubnet::handle(global, args).await,
+            Commands::EstimateJoin(args) => EstimateJoinCollateral::handle(global, args).await,
+            Commands::OnboardingDryRun(args) => OnboardingDryRun::handle(global, args).await,
             Commands::Rpc(args) => RPCSubnet::handle(global, args).await,
             Commands::Leave(args) => LeaveSubnet::handle(global, args).await,
             Commands::Kill(args) => KillSubnet::handle(global, args).await,
+            Commands::Decommission(args) => DecommissionSubnet::handle(global, args).await,
             Commands::SendValue(args) => SendValue::handle(global, args).await,
             Commands::SetValidatorNetAddr(args) => SetValidatorNetAddr::handle(global, args).await,
             Commands::SetValidatorWorkerAddr(args) => {
                 SetValidatorWorkerAddr::handle(global, args).await
             }
+            Commands::SetFederatedPower(args) => SetFederatedPower::handle(global, args).await,
+            Commands::CirculatingSupply(args) => CirculatingSupply::handle(global, args).await,
+            Commands::ValidatorSubnets(args) => ValidatorSubnets::handle(global, args).await,
         }
     }
 }
@@ -62,12 +95,21 @@ impl SubnetCommandsArgs {
 pub(crate) enum Commands {
     Create(CreateSubnetArgs),
     List(ListSubnetsArgs),
+    Import(ImportSubnetsArgs),
+    ListOwn(ListOwnSubnetsArgs),
     ListValidators(ListValidatorsArgs),
+    CompareValidators(CompareValidatorSetsArgs),
     Join(JoinSubnetArgs),
+    EstimateJoin(EstimateJoinCollateralArgs),
+    OnboardingDryRun(OnboardingDryRunArgs),
     Rpc(RPCSubnetArgs),
     Leave(LeaveSubnetArgs),
     Kill(KillSubnetArgs),
+    Decommission(DecommissionSubnetArgs),
     SendValue(SendValueArgs),
     SetValidatorNetAddr(SetValidatorNetAddrArgs),
     SetValidatorWorkerAddr(SetValidatorWorkerAddrArgs),
+    SetFederatedPower(SetFederatedPowerArgs),
+    CirculatingSupply(CirculatingSupplyArgs),
+    ValidatorSubnets(ValidatorSubnetsArgs),
 }
@@ -6,6 +6,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::net_addr::SetValidatorNetAddrParams;
 use async_trait::async_trait;
 use clap::Args;
@@ -18,10 +19,10 @@ impl CommandLineHandler for SetValidatorNetAddr {
     type Arguments = SetValidatorNetAddrArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("set the validator net addr args: {:?}", arguments);
+        tracing::debug!("set the validator net addr args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let params = SetValidatorNetAddrParams {
             subnet: arguments.subnet.clone(),
@@ -36,7 +37,7 @@ impl CommandLineHandler for SetValidatorNetAddr {
             )
             .await?;
 
-        log::info!(
+        tracing::info!(
             "set the validator net addr to: {:} in subnet: {:}",
             arguments.validator_net_addr,
             arguments.subnet
@@ -0,0 +1,82 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Onboarding dry run cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::onboarding::{OnboardingDryRunParams, ReadinessStatus};
+
+/// The command to check what a prospective validator is missing to join a subnet
+pub struct OnboardingDryRun;
+
+#[async_trait]
+impl CommandLineHandler for OnboardingDryRun {
+    type Arguments = OnboardingDryRunArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("onboarding dry run with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let params = OnboardingDryRunParams {
+            subnet: arguments.subnet.clone(),
+            validator: arguments.validator.clone(),
+            collateral: arguments.collateral,
+            validator_net_addr: arguments.validator_net_addr.clone(),
+            worker_addr: arguments.worker_addr.clone(),
+        };
+
+        let client = IpcAgentClient::default_from_url(url);
+        let r = client.onboarding_dry_run(params).await?;
+
+        for step in r.steps {
+            match step.status {
+                ReadinessStatus::Ready => tracing::info!("[ready] {}: {}", step.check, step.detail),
+                ReadinessStatus::Warning => {
+                    tracing::warn!("[warning] {}: {}", step.check, step.detail)
+                }
+                ReadinessStatus::Blocked => {
+                    tracing::error!("[blocked] {}: {}", step.check, step.detail)
+                }
+            }
+        }
+        if r.ready {
+            tracing::info!("{} is ready to join subnet {}", r.validator, r.subnet);
+        } else {
+            tracing::error!("{} cannot join subnet {} yet", r.validator, r.subnet);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "onboarding-dry-run",
+    about = "Check what a prospective validator is missing to join a subnet, without sending anything"
+)]
+pub struct OnboardingDryRunArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet to join")]
+    pub subnet: String,
+    #[arg(long, help = "The address of the prospective validator")]
+    pub validator: String,
+    #[arg(
+        long,
+        short,
+        help = "The collateral to join with, in whole FIL, the minimum collateral if not set"
+    )]
+    pub collateral: Option<f64>,
+    #[arg(long, short, help = "The validator net address")]
+    pub validator_net_addr: Option<String>,
+    #[arg(
+        long,
+        help = "The worker address of the validator, the validator itself if not set"
+    )]
+    pub worker_addr: Option<String>,
+}
@@ -12,6 +12,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::rpc::RPCSubnetParams;
 
 /// The command to get the RPC endpoint for a subnet
@@ -22,10 +23,10 @@ impl CommandLineHandler for RPCSubnet {
     type Arguments = RPCSubnetArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("get rpc for subnet with args: {:?}", arguments);
+        tracing::debug!("get rpc for subnet with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let params = RPCSubnetParams {
             subnet: arguments.subnet.clone(),
@@ -37,10 +38,10 @@ impl CommandLineHandler for RPCSubnet {
 
         let id = SubnetID::from_str(&arguments.subnet)?;
 
-        log::info!("rpc endpoint for subnet {:}: {:}", arguments.subnet, rpc);
+        tracing::info!("rpc endpoint for subnet {:}: {:}", arguments.subnet, rpc);
         // todo: We currently have the same ChainID for all subnet. This will be changed
         // once https://github.com/consensus-shipyard/lotus/issues/178 is implemented
-        log::info!(
+        tracing::info!(
             "chainID for subnet {:}: {:}",
             arguments.subnet,
             id.chain_id(),
@@ -10,6 +10,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::send_value::SendValueParams;
 
 pub(crate) struct SendValue;
@@ -19,10 +20,10 @@ impl CommandLineHandler for SendValue {
     type Arguments = SendValueArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("send value in subnet with args: {:?}", arguments);
+        tracing::debug!("send value in subnet with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         // The json rpc server will handle directing the request to
         // the correct parent.
@@ -37,7 +38,7 @@ impl CommandLineHandler for SendValue {
             .request::<()>(json_rpc_methods::SEND_VALUE, serde_json::to_value(params)?)
             .await?;
 
-        log::info!("sending value in subnet: {:}", arguments.subnet);
+        tracing::info!("sending value in subnet: {:}", arguments.subnet);
 
         Ok(())
     }
@@ -0,0 +1,71 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List the subnets of a validator cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the configured subnets a validator is part of.
+pub(crate) struct ValidatorSubnets;
+
+#[async_trait]
+impl CommandLineHandler for ValidatorSubnets {
+    type Arguments = ValidatorSubnetsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list validator subnets with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = client
+            .list_validators_across_subnets(arguments.address.clone())
+            .await?;
+
+        if response.subnets.is_empty() {
+            tracing::info!(
+                "{} is not a validator of any configured subnet",
+                arguments.address
+            );
+        }
+        for s in response.subnets {
+            let votes = s
+                .votes
+                .iter()
+                .map(|v| {
+                    let status = if v.voted { "voted" } else { "not voted" };
+                    format!("{} epoch {} {status:}", v.direction, v.epoch)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let rewards = s
+                .pending_rewards
+                .map_or_else(|| String::from("paid out"), |r| r.to_string());
+            tracing::info!(
+                "subnet: {}, weight: {}, net addr: {}, votes: [{votes:}], pending rewards: {rewards:}",
+                s.subnet,
+                s.weight,
+                s.net_addr
+            );
+        }
+        for (subnet, error) in response.errors {
+            tracing::warn!("cannot query subnet: {subnet:}: {error:}");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the configured subnets a validator is part of, with its stake and votes")]
+pub(crate) struct ValidatorSubnetsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The fvm or evm address of the validator")]
+    pub address: String,
+}
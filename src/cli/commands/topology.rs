@@ -0,0 +1,171 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The topology command line handler that exports the subnet hierarchy as a diagram.
+
+use std::fmt::Debug;
+use std::io::Write;
+
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::topology::{NodeStatus, TopologyNode};
+
+/// The command to export the hierarchy of the subnets of the agent.
+pub(crate) struct Topology;
+
+#[async_trait]
+impl CommandLineHandler for Topology {
+    type Arguments = TopologyArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("export topology with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let nodes = client.topology().await?.nodes;
+
+        let rendered = match arguments.format {
+            TopologyFormat::Dot => render_dot(&nodes),
+            TopologyFormat::Mermaid => render_mermaid(&nodes),
+            TopologyFormat::Json => serde_json::to_string_pretty(&nodes)?,
+        };
+        match &arguments.output {
+            Some(p) => {
+                let mut file = std::fs::File::create(p)?;
+                file.write_all(rendered.as_bytes())?;
+                tracing::info!(
+                    "topology of {} subnets written to file {:?}",
+                    nodes.len(),
+                    p
+                );
+            }
+            None => tracing::info!("subnet topology:\n{rendered:}"),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum TopologyFormat {
+    /// Graphviz, render it with `dot -Tsvg`
+    Dot,
+    Mermaid,
+    Json,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Export the hierarchy of the configured and discovered subnets as a diagram")]
+pub(crate) struct TopologyArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, value_enum, default_value_t = TopologyFormat::Dot, help = "The format of the diagram")]
+    pub format: TopologyFormat,
+    #[arg(
+        long,
+        short,
+        help = "Optional parameter that outputs the diagram into the file specified"
+    )]
+    pub output: Option<String>,
+}
+
+fn status_color(status: NodeStatus) -> &'static str {
+    match status {
+        NodeStatus::Healthy => "#2e7d32",
+        NodeStatus::Lagging => "#f9a825",
+        NodeStatus::Inactive => "#757575",
+        NodeStatus::Unreachable => "#c62828",
+        NodeStatus::Unknown => "#90a4ae",
+    }
+}
+
+fn status_name(status: NodeStatus) -> &'static str {
+    match status {
+        NodeStatus::Healthy => "healthy",
+        NodeStatus::Lagging => "lagging",
+        NodeStatus::Inactive => "inactive",
+        NodeStatus::Unreachable => "unreachable",
+        NodeStatus::Unknown => "unknown",
+    }
+}
+
+/// The label of a node, with its network type and status.
+fn label(node: &TopologyNode, separator: &str) -> String {
+    let mut label = node.id.clone();
+    if let Some(t) = &node.network_type {
+        label.push_str(&format!("{separator:}{t:}"));
+    }
+    label.push_str(&format!("{separator:}{}", status_name(node.status)));
+    label
+}
+
+fn render_dot(nodes: &[TopologyNode]) -> String {
+    let mut lines = vec![
+        String::from("digraph subnets {"),
+        String::from("  rankdir=TB;"),
+        String::from("  node [shape=box, style=\"rounded,filled\", fontcolor=white];"),
+    ];
+    for node in nodes {
+        let style = if node.configured {
+            "rounded,filled"
+        } else {
+            "rounded,filled,dashed"
+        };
+        let tooltip = node.detail.as_deref().unwrap_or_default().replace('"', "'");
+        lines.push(format!(
+            "  \"{}\" [label=\"{}\", fillcolor=\"{}\", style=\"{style:}\", tooltip=\"{tooltip:}\"];",
+            node.id,
+            label(node, "\\n"),
+            status_color(node.status)
+        ));
+    }
+    for node in nodes {
+        if let Some(parent) = &node.parent {
+            lines.push(format!("  \"{parent:}\" -> \"{}\";", node.id));
+        }
+    }
+    lines.push(String::from("}"));
+    lines.join("\n")
+}
+
+/// Mermaid ids cannot contain slashes, so nodes are identified by their index.
+fn render_mermaid(nodes: &[TopologyNode]) -> String {
+    let id = |subnet: &str| {
+        nodes
+            .iter()
+            .position(|n| n.id == subnet)
+            .map(|i| format!("s{i:}"))
+    };
+
+    let mut lines = vec![String::from("graph TD")];
+    for (i, node) in nodes.iter().enumerate() {
+        lines.push(format!(
+            "  s{i:}[\"{}\"]:::{}",
+            label(node, "<br/>"),
+            status_name(node.status)
+        ));
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if let Some(parent) = node.parent.as_deref().and_then(id) {
+            let arrow = if node.configured { "-->" } else { "-.->" };
+            lines.push(format!("  {parent:} {arrow:} s{i:}"));
+        }
+    }
+    for status in [
+        NodeStatus::Healthy,
+        NodeStatus::Lagging,
+        NodeStatus::Inactive,
+        NodeStatus::Unreachable,
+        NodeStatus::Unknown,
+    ] {
+        lines.push(format!(
+            "  classDef {} fill:{},color:#fff",
+            status_name(status),
+            status_color(status)
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,94 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Build offline transaction cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::manager::offline::TxEncoding;
+use crate::sdk::IpcAgentClient;
+use crate::server::offline_tx::BuildTxParams;
+
+/// The command to build an unsigned transaction to be signed offline.
+pub(crate) struct BuildTx;
+
+#[async_trait]
+impl CommandLineHandler for BuildTx {
+    type Arguments = BuildTxArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("build offline tx with args: {:?}", arguments);
+
+        let encoding = TxEncoding::from_str(&arguments.encoding)?;
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let tx = client
+            .build_offline_tx(BuildTxParams {
+                op: arguments.op.clone(),
+                subnet: arguments.subnet.clone(),
+                from: arguments.from.clone(),
+                to: arguments.to.clone(),
+                amount: arguments.amount,
+                validator_net_addr: arguments.validator_net_addr.clone(),
+                worker_addr: arguments.worker_addr.clone(),
+                epoch: arguments.epoch,
+            })
+            .await?;
+
+        tx.write_to_file(&arguments.output, encoding)?;
+        tracing::info!(
+            "unsigned transaction to {} written to {:?}",
+            tx.description,
+            arguments.output
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Build an unsigned transaction to be signed offline")]
+pub(crate) struct BuildTxArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        help = "The operation of the transaction, i.e. fund, release, join, leave, send-value, checkpoint-vote"
+    )]
+    pub op: String,
+    #[arg(long, short, help = "The subnet the operation refers to")]
+    pub subnet: String,
+    #[arg(long, help = "The address that signs the transaction")]
+    pub from: Option<String>,
+    #[arg(
+        long,
+        help = "The recipient of fund, release and send-value, defaults to the sender"
+    )]
+    pub to: Option<String>,
+    #[arg(
+        long,
+        help = "The amount to send in FIL, or the collateral to join with"
+    )]
+    pub amount: Option<f64>,
+    #[arg(long, help = "The validator net address when joining")]
+    pub validator_net_addr: Option<String>,
+    #[arg(long, help = "The worker address when joining, defaults to the sender")]
+    pub worker_addr: Option<String>,
+    #[arg(long, help = "The epoch of the bottom-up checkpoint to vote")]
+    pub epoch: Option<ChainEpoch>,
+    #[arg(long, short, help = "The file the unsigned transaction is written to")]
+    pub output: String,
+    #[arg(
+        long,
+        short,
+        default_value = "json",
+        help = "The encoding of the transaction file, i.e. json, cbor"
+    )]
+    pub encoding: String,
+}
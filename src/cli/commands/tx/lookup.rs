@@ -0,0 +1,73 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Lookup message cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to look up a message of a subnet, with its receipt.
+pub(crate) struct LookupMessage;
+
+#[async_trait]
+impl CommandLineHandler for LookupMessage {
+    type Arguments = LookupMessageArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("lookup message with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let m = client.get_message(&arguments.subnet, &arguments.id).await?;
+
+        tracing::info!(
+            "message {} from {} to {} of {} with method {} and nonce {}",
+            m.id,
+            m.from,
+            m.to,
+            m.value,
+            m.method,
+            m.nonce
+        );
+        if m.ipc {
+            tracing::info!(
+                "ipc operation: {}{}",
+                m.ipc_method.as_deref().unwrap_or("unknown method"),
+                m.agent_operation
+                    .map(|o| format!(", signed by the agent for {o:?}"))
+                    .unwrap_or_default()
+            );
+        }
+        match &m.receipt {
+            Some(r) => tracing::info!(
+                "executed at epoch {}, success: {}, exit code: {:?}, gas used: {}, fee: {}",
+                r.epoch,
+                r.success,
+                r.exit_code,
+                r.gas_used,
+                r.gas_fee
+            ),
+            None => tracing::info!("not executed yet"),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Look up a message of a subnet by its cid, or transaction hash, with its receipt"
+)]
+pub(crate) struct LookupMessageArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet of the message")]
+    pub subnet: String,
+    #[arg(help = "The cid of the message, or the hash of the transaction in evm subnets")]
+    pub id: String,
+}
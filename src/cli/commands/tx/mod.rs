@@ -0,0 +1,44 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Commands to build transactions online, sign them offline and broadcast them later, and to
+//! look up the messages of a subnet.
+
+use crate::cli::commands::tx::build::{BuildTx, BuildTxArgs};
+use crate::cli::commands::tx::lookup::{LookupMessage, LookupMessageArgs};
+use crate::cli::commands::tx::send::{SendTx, SendTxArgs};
+use crate::cli::commands::tx::sign::{SignTx, SignTxArgs};
+use crate::cli::{CommandLineHandler, GlobalArguments};
+
+use clap::{Args, Subcommand};
+
+mod build;
+mod lookup;
+mod send;
+mod sign;
+
+#[derive(Debug, Args)]
+#[command(name = "tx", about = "offline transaction and message lookup commands")]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct TxCommandsArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl TxCommandsArgs {
+    pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
+        match &self.command {
+            Commands::Build(args) => BuildTx::handle(global, args).await,
+            Commands::Sign(args) => SignTx::handle(global, args).await,
+            Commands::Send(args) => SendTx::handle(global, args).await,
+            Commands::Lookup(args) => LookupMessage::handle(global, args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    Build(BuildTxArgs),
+    Sign(SignTxArgs),
+    Send(SendTxArgs),
+    Lookup(LookupMessageArgs),
+}
@@ -0,0 +1,44 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Send signed offline transaction cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::manager::offline::OfflineTx;
+use crate::sdk::IpcAgentClient;
+
+/// The command to broadcast a transaction signed offline.
+pub(crate) struct SendTx;
+
+#[async_trait]
+impl CommandLineHandler for SendTx {
+    type Arguments = SendTxArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("send signed tx with args: {:?}", arguments);
+
+        let tx = OfflineTx::read_from_file(&arguments.input)?;
+        let description = tx.description.clone();
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let id = client.send_signed_tx(tx).await?;
+
+        tracing::info!("sent transaction to {description:} with id: {id:}");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Broadcast a transaction signed offline")]
+pub(crate) struct SendTxArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short = 'f', help = "The signed transaction file")]
+    pub input: String,
+}
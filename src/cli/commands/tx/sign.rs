@@ -0,0 +1,70 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Sign offline transaction cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_identity::Wallet;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::cli::commands::{get_evm_keystore, get_fvm_store};
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::manager::offline::{sign_evm, sign_fvm, OfflineTx, TxEncoding, TxNetwork};
+
+/// The command to sign a transaction with the local keystores, without connecting to any node
+/// or agent so that it can run in an air-gapped machine.
+pub(crate) struct SignTx;
+
+#[async_trait]
+impl CommandLineHandler for SignTx {
+    type Arguments = SignTxArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("sign offline tx with args: {:?}", arguments);
+
+        let encoding = TxEncoding::from_str(&arguments.encoding)?;
+        let mut tx = OfflineTx::read_from_file(&arguments.input)?;
+
+        tracing::info!("signing transaction to {} in {}", tx.description, tx.subnet);
+        tracing::info!("decoded transaction: {}", tx.summary()?);
+
+        match tx.network {
+            TxNetwork::Fvm => {
+                let mut wallet = Wallet::new(get_fvm_store(global, arguments.keystore.clone())?);
+                sign_fvm(&mut tx, &mut wallet)?;
+            }
+            TxNetwork::Fevm => {
+                let keystore = get_evm_keystore(global, &arguments.keystore)?;
+                sign_evm(&mut tx, &keystore)?;
+            }
+        }
+
+        tx.write_to_file(&arguments.output, encoding)?;
+        tracing::info!("signed transaction written to {:?}", arguments.output);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Sign a transaction offline with the key of its sender")]
+pub(crate) struct SignTxArgs {
+    #[arg(long, short, help = "The unsigned transaction file")]
+    pub input: String,
+    #[arg(long, short, help = "The file the signed transaction is written to")]
+    pub output: String,
+    #[arg(
+        long,
+        short,
+        help = "Keystore path (the keystore of the data dir is used if not specified)"
+    )]
+    pub keystore: Option<String>,
+    #[arg(
+        long,
+        short,
+        default_value = "json",
+        help = "The encoding of the signed transaction file, i.e. json, cbor"
+    )]
+    pub encoding: String,
+}
@@ -0,0 +1,71 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Debug tap cli command
+
+use std::fmt::Debug;
+use std::io::Write;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to dump the requests to the nodes recorded by the debug tap of the agent.
+pub(crate) struct DebugTap;
+
+#[async_trait]
+impl CommandLineHandler for DebugTap {
+    type Arguments = DebugTapArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("debug tap with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let tap = client.debug_tap(arguments.clear).await?;
+
+        if tap.max_bytes.is_none() {
+            tracing::info!("debug tap disabled, set [server.debug_tap] in the config to enable it");
+            return Ok(());
+        }
+        match &arguments.output {
+            Some(p) => {
+                let mut file = std::fs::File::create(p)?;
+                file.write_all(serde_json::to_string_pretty(&tap.entries)?.as_bytes())?;
+                tracing::info!("{} requests written to file {:?}", tap.entries.len(), p);
+            }
+            None => {
+                for e in tap.entries.iter() {
+                    tracing::info!(
+                        "{} {} {} ({}ms)\n  request: {}\n  response: {}",
+                        e.timestamp,
+                        e.url,
+                        e.method,
+                        e.duration_ms,
+                        e.request,
+                        e.response
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Dump the requests to the nodes recorded by the debug tap of the agent")]
+pub(crate) struct DebugTapArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, help = "Drop the requests dumped from the tap")]
+    pub clear: bool,
+    #[arg(
+        long,
+        short,
+        help = "Optional parameter that outputs the requests as json into the file specified"
+    )]
+    pub output: Option<String>,
+}
@@ -0,0 +1,46 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List json rpc methods cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the json rpc methods served by the agent.
+pub(crate) struct ListMethods;
+
+#[async_trait]
+impl CommandLineHandler for ListMethods {
+    type Arguments = ListMethodsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("list methods with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+
+        for m in client.list_methods().await? {
+            let fields = m
+                .params
+                .fields
+                .map(|f| format!(" {{{}}}", f.join(", ")))
+                .unwrap_or_default();
+            let disabled = if m.enabled { "" } else { " (disabled)" };
+            tracing::info!("{}{disabled:}: {}{fields:}", m.method, m.params.type_name);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the JSON-RPC methods served by the agent")]
+pub(crate) struct ListMethodsArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+}
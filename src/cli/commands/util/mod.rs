@@ -4,9 +4,15 @@ use crate::cli::{CommandLineHandler, GlobalArguments};
 
 use clap::{Args, Subcommand};
 
+use self::debug_tap::{DebugTap, DebugTapArgs};
 use self::f4::{EthToF4Addr, EthToF4AddrArgs};
+use self::list_methods::{ListMethods, ListMethodsArgs};
+use self::read_state::{ReadState, ReadStateArgs};
 
+mod debug_tap;
 mod f4;
+mod list_methods;
+mod read_state;
 
 #[derive(Debug, Args)]
 #[command(name = "util", about = "util commands")]
@@ -20,6 +26,9 @@ impl UtilCommandsArgs {
     pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
         match &self.command {
             Commands::EthToF4Addr(args) => EthToF4Addr::handle(global, args).await,
+            Commands::ListMethods(args) => ListMethods::handle(global, args).await,
+            Commands::DebugTap(args) => DebugTap::handle(global, args).await,
+            Commands::ReadState(args) => ReadState::handle(global, args).await,
         }
     }
 }
@@ -27,4 +36,7 @@ impl UtilCommandsArgs {
 #[derive(Debug, Subcommand)]
 pub(crate) enum Commands {
     EthToF4Addr(EthToF4AddrArgs),
+    ListMethods(ListMethodsArgs),
+    DebugTap(DebugTapArgs),
+    ReadState(ReadStateArgs),
 }
@@ -0,0 +1,69 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Read actor state cli command
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use clap::Args;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::read_state::ReadStateParams;
+
+/// The command to read the state of an actor of a subnet.
+pub(crate) struct ReadState;
+
+#[async_trait]
+impl CommandLineHandler for ReadState {
+    type Arguments = ReadStateArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("read state with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = client
+            .read_state(ReadStateParams {
+                subnet: arguments.subnet.clone(),
+                address: arguments.address.clone(),
+                raw: arguments.raw,
+            })
+            .await?;
+
+        tracing::info!(
+            "actor {} with code {} and balance {}",
+            response.address,
+            response.code,
+            response.balance
+        );
+        match (response.schema, response.state, response.raw) {
+            (Some(schema), Some(state), _) => {
+                tracing::info!("{schema:} state: {}", serde_json::to_string_pretty(&state)?)
+            }
+            (_, _, Some(raw)) => tracing::info!("raw state: {raw:}"),
+            _ => tracing::info!("no state returned"),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Read the state of an actor, decoded for the gateway, subnet, init, account and multisig actors"
+)]
+pub(crate) struct ReadStateArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet of the actor")]
+    pub subnet: String,
+    #[arg(long, short, help = "The fvm or evm address of the actor")]
+    pub address: String,
+    #[arg(
+        long,
+        help = "Return the raw cbor of the state, hex encoded, even if its schema is known"
+    )]
+    pub raw: bool,
+}
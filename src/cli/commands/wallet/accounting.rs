@@ -0,0 +1,106 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Accounting export cli handler
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::manager::activity::{accounting_rows, ACCOUNTING_CSV_HEADER};
+use crate::sdk::token_from_env;
+use crate::server::wallet::activity::{WalletActivityParams, WalletActivityResponse};
+
+pub(crate) struct WalletAccountingExport;
+
+#[async_trait]
+impl CommandLineHandler for WalletAccountingExport {
+    type Arguments = WalletAccountingExportArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("accounting export with args: {:?}", arguments);
+
+        if arguments.period <= 0 {
+            return Err(anyhow!("the period must be a positive number of epochs"));
+        }
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
+
+        let mut lines = vec![ACCOUNTING_CSV_HEADER.to_string()];
+        for subnet in &arguments.subnet {
+            let params = WalletActivityParams {
+                subnet: subnet.clone(),
+                account: arguments.account.clone(),
+                from_epoch: arguments.from_epoch,
+            };
+            let response = json_rpc_client
+                .request::<WalletActivityResponse>(
+                    json_rpc_methods::WALLET_ACTIVITY,
+                    serde_json::to_value(params)?,
+                )
+                .await?;
+
+            for account in response.accounts {
+                if !account.listed_on_chain {
+                    tracing::warn!(
+                        "only the messages signed by the agent are exported for {} in subnet {}",
+                        account.account,
+                        response.subnet
+                    );
+                }
+                lines.extend(
+                    accounting_rows(
+                        &response.subnet,
+                        &account.account,
+                        &account.activity,
+                        response.from_epoch,
+                        arguments.period,
+                    )
+                    .iter()
+                    .map(|r| r.to_csv()),
+                );
+            }
+        }
+
+        std::fs::write(&arguments.output, lines.join("\n") + "\n")?;
+        tracing::info!("exported {} rows to {}", lines.len() - 1, arguments.output);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Export the gas spent and the value sent, funded and released by the accounts of subnets, by period, as CSV"
+)]
+pub(crate) struct WalletAccountingExportArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, required = true, help = "The subnets to export, repeated")]
+    pub subnet: Vec<String>,
+    #[arg(
+        long,
+        short,
+        help = "The account to export, all the accounts of the subnets if not set"
+    )]
+    pub account: Option<String>,
+    #[arg(
+        long,
+        help = "The epoch to export from, 2880 epochs before the chain head of each subnet if not set"
+    )]
+    pub from_epoch: Option<ChainEpoch>,
+    #[arg(
+        long,
+        default_value = "2880",
+        help = "The number of epochs of a period, a row per period with activity"
+    )]
+    pub period: ChainEpoch,
+    #[arg(long, short, help = "The CSV file to write the report to")]
+    pub output: String,
+}
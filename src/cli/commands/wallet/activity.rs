@@ -0,0 +1,97 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet activity cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
+use crate::server::wallet::activity::{WalletActivityParams, WalletActivityResponse};
+
+pub(crate) struct WalletActivity;
+
+#[async_trait]
+impl CommandLineHandler for WalletActivity {
+    type Arguments = WalletActivityArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("wallet activity with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
+
+        let params = WalletActivityParams {
+            subnet: arguments.subnet.clone(),
+            account: arguments.account.clone(),
+            from_epoch: arguments.from_epoch,
+        };
+
+        let response = json_rpc_client
+            .request::<WalletActivityResponse>(
+                json_rpc_methods::WALLET_ACTIVITY,
+                serde_json::to_value(params)?,
+            )
+            .await?;
+
+        tracing::info!(
+            "activity in subnet {} since epoch {}:",
+            response.subnet,
+            response.from_epoch
+        );
+        for account in response.accounts {
+            let summary = &account.summary;
+            tracing::info!(
+                "{}: sent {}, received {}, gas spent {} ({} on checkpoints){}",
+                account.account,
+                summary.sent,
+                summary.received,
+                summary.gas_spent,
+                summary.checkpoint_gas_spent,
+                if account.listed_on_chain {
+                    ""
+                } else {
+                    ", only the messages signed by the agent are listed"
+                }
+            );
+            for a in account.activity {
+                tracing::info!(
+                    "    {} {:?} {} {}{}{}",
+                    a.epoch,
+                    a.kind,
+                    a.counterparty,
+                    a.value,
+                    a.operation.map(|o| format!(" ({o:?})")).unwrap_or_default(),
+                    if a.success { "" } else { " failed" }
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List what the accounts of a subnet sent, received and spent in gas")]
+pub(crate) struct WalletActivityArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet to list the activity in")]
+    pub subnet: String,
+    #[arg(
+        long,
+        short,
+        help = "The account to list the activity of, all the accounts of the subnet if not set"
+    )]
+    pub account: Option<String>,
+    #[arg(
+        long,
+        help = "The epoch to list the activity from, 2880 epochs before the chain head if not set"
+    )]
+    pub from_epoch: Option<ChainEpoch>,
+}
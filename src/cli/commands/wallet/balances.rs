@@ -10,6 +10,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::wallet::balances::{WalletBalancesParams, WalletBalancesResponse};
 
 pub(crate) struct WalletBalances;
@@ -19,10 +20,10 @@ impl CommandLineHandler for WalletBalances {
     type Arguments = WalletBalancesArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("list wallets with args: {:?}", arguments);
+        tracing::debug!("list wallets with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let params = WalletBalancesParams {
             subnet: arguments.subnet.clone(),
@@ -35,11 +36,11 @@ impl CommandLineHandler for WalletBalances {
             )
             .await?;
 
-        log::info!("wallets in subnet {:} are:", arguments.subnet);
+        tracing::info!("wallets in subnet {:} are:", arguments.subnet);
 
         // iterate through addresses and pretty print balances
         for (addr, balance) in addrs {
-            log::info!("{}: {}", addr, balance);
+            tracing::info!("{}: {}", addr, balance);
         }
 
         Ok(())
@@ -0,0 +1,124 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet HD derivation cli handler
+
+use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::Args;
+use fvm_shared::crypto::signature::SignatureType;
+use ipc_identity::hd::{
+    derivation_path, derive_evm_key, derive_fvm_key, AccountRole, ETH_COIN_TYPE, FIL_COIN_TYPE,
+};
+use std::fmt::Debug;
+use std::str::FromStr;
+use zeroize::Zeroize;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::commands::wallet::secret::read_secret_from_stdin;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::wallet::import::{FvmImportParams, WalletImportParams};
+use crate::server::wallet::WalletType;
+
+/// The environment variable the BIP39 passphrase of the mnemonic is read from, if set.
+const PASSPHRASE_ENV: &str = "IPC_MNEMONIC_PASSPHRASE";
+
+pub(crate) struct WalletDerive;
+
+#[async_trait]
+impl CommandLineHandler for WalletDerive {
+    type Arguments = WalletDeriveArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("derive wallet with args: {:?}", arguments);
+
+        let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
+        let role = AccountRole::from_str(&arguments.role)?;
+
+        let mut phrase = std::fs::read_to_string(&arguments.mnemonic_path)?;
+        let mut passphrase = if arguments.passphrase_stdin {
+            Some(read_secret_from_stdin("BIP39 passphrase")?)
+        } else {
+            std::env::var(PASSPHRASE_ENV).ok()
+        };
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+
+        let r: anyhow::Result<_> = try {
+            match wallet_type {
+                WalletType::Fvm => {
+                    let key = derive_fvm_key(
+                        phrase.trim(),
+                        passphrase.as_deref(),
+                        role,
+                        arguments.index,
+                    )?;
+                    let params = WalletImportParams::Fvm(FvmImportParams {
+                        key_type: SignatureType::Secp256k1 as u8,
+                        private_key: BASE64_STANDARD.encode(key.private_key()),
+                    });
+                    let addr = client.import(params).await?;
+                    (addr, derivation_path(FIL_COIN_TYPE, role, arguments.index))
+                }
+                WalletType::Evm => {
+                    let key = derive_evm_key(
+                        phrase.trim(),
+                        passphrase.as_deref(),
+                        role,
+                        arguments.index,
+                    )?;
+                    let addr = client
+                        .import_evm_from_private_key(hex::encode(key.private_key()))
+                        .await?;
+                    (addr, derivation_path(ETH_COIN_TYPE, role, arguments.index))
+                }
+            }
+        };
+        phrase.zeroize();
+        passphrase.zeroize();
+        let (addr, path) = r?;
+
+        tracing::info!(
+            "imported wallet with address {:?} derived at {}",
+            addr,
+            path
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Derive an account from a BIP39 mnemonic and import it into the agent's wallet")]
+pub(crate) struct WalletDeriveArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The type of the wallet, i.e. fvm, evm")]
+    pub wallet_type: String,
+    #[arg(
+        long,
+        short = 'p',
+        help = "Path of the file with the mnemonic to derive the account from"
+    )]
+    pub mnemonic_path: String,
+    #[arg(
+        long,
+        help = "Read the BIP39 passphrase of the mnemonic from stdin, otherwise it is read from IPC_MNEMONIC_PASSPHRASE if set"
+    )]
+    pub passphrase_stdin: bool,
+    #[arg(
+        long,
+        short,
+        default_value = "default",
+        help = "The role of the account in the subnet, i.e. default, validator, worker, relayer"
+    )]
+    pub role: String,
+    #[arg(
+        long,
+        short = 'n',
+        default_value_t = 0,
+        help = "The index of the account within its role"
+    )]
+    pub index: u32,
+}
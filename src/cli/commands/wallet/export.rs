@@ -5,12 +5,18 @@ use anyhow::anyhow;
 use async_trait::async_trait;
 use base64::{prelude::BASE64_STANDARD, Engine};
 use clap::Args;
+use ethers::core::rand::thread_rng;
+use ethers::signers::LocalWallet;
 use fvm_shared::address::Address;
-use ipc_identity::{EvmKeyStore, PersistentKeyInfo, Wallet};
+use fvm_shared::crypto::signature::SignatureType;
+use ipc_identity::{EvmKeyInfo, EvmKeyStore, KeyInfo, PersistentKeyInfo, Wallet};
 use std::fmt::Debug;
 use std::io::Write;
+use std::path::Path;
 use std::str::FromStr;
+use zeroize::Zeroize;
 
+use crate::cli::commands::wallet::secret::{read_secret_from_file, read_secret_from_stdin};
 use crate::cli::get_evm_keystore;
 use crate::sdk::LotusJsonKeyType;
 use crate::server::wallet::WalletType;
@@ -21,14 +27,37 @@ use crate::{
 
 pub(crate) struct WalletExport;
 
+/// The formats a key can be exported in.
+#[derive(Debug, PartialEq, Eq)]
+enum ExportFormat {
+    /// Lotus json for fvm keys and the agent json for evm keys.
+    Json,
+    /// The hex encoded lotus json accepted by `lotus wallet import`.
+    HexLotus,
+    /// An encrypted eth keystore, i.e. web3 secret storage, file.
+    EthKeystore,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "json" => Self::Json,
+            "hex-lotus" => Self::HexLotus,
+            "eth-keystore" => Self::EthKeystore,
+            _ => return Err(anyhow!("invalid export format")),
+        })
+    }
+}
+
 impl WalletExport {
-    fn export_evm(arguments: &WalletExportArgs) -> anyhow::Result<String> {
-        let keystore = get_evm_keystore(&arguments.keystore)?;
+    fn export_evm(
+        global: &GlobalArguments,
+        arguments: &WalletExportArgs,
+    ) -> anyhow::Result<String> {
         let address = ethers::types::Address::from_str(&arguments.address)?;
-
-        let key_info = keystore
-            .get(&address)?
-            .ok_or_else(|| anyhow!("key does not exists"))?;
+        let key_info = WalletExport::evm_key_info(global, arguments)?;
 
         let info = PersistentKeyInfo::new(
             format!("{:?}", address),
@@ -37,44 +66,145 @@ impl WalletExport {
         Ok(serde_json::to_string(&info)?)
     }
 
-    fn export_fvm(arguments: &WalletExportArgs) -> anyhow::Result<String> {
-        let mut wallet = Wallet::new(get_fvm_store(arguments.keystore.clone())?);
-
-        let addr = Address::from_str(&arguments.address)?;
-        let key_info = wallet.export(&addr)?;
+    fn export_fvm(
+        global: &GlobalArguments,
+        arguments: &WalletExportArgs,
+    ) -> anyhow::Result<String> {
+        let key_info = WalletExport::key_info(global, arguments)?;
         Ok(serde_json::to_string(&LotusJsonKeyType {
             r#type: WalletKeyType::try_from(*key_info.key_type())?.to_string(),
             private_key: BASE64_STANDARD.encode(key_info.private_key()),
         })?)
     }
+
+    fn evm_key_info(
+        global: &GlobalArguments,
+        arguments: &WalletExportArgs,
+    ) -> anyhow::Result<EvmKeyInfo> {
+        let keystore = get_evm_keystore(global, &arguments.keystore)?;
+        let address = ethers::types::Address::from_str(&arguments.address)?;
+
+        keystore
+            .get(&address)?
+            .ok_or_else(|| anyhow!("key does not exists"))
+    }
+
+    /// Returns the key of the address in the fvm representation, regardless of the wallet it is
+    /// stored in. Keys in the evm keystore are always secp256k1 keys.
+    fn key_info(global: &GlobalArguments, arguments: &WalletExportArgs) -> anyhow::Result<KeyInfo> {
+        match WalletType::from_str(&arguments.wallet_type)? {
+            WalletType::Evm => {
+                let info = WalletExport::evm_key_info(global, arguments)?;
+                Ok(KeyInfo::new(
+                    SignatureType::Secp256k1,
+                    info.private_key().to_vec(),
+                ))
+            }
+            WalletType::Fvm => {
+                let mut wallet = Wallet::new(get_fvm_store(global, arguments.keystore.clone())?);
+                let addr = Address::from_str(&arguments.address)?;
+                Ok(wallet.export(&addr)?)
+            }
+        }
+    }
+
+    fn export_hex_lotus(
+        global: &GlobalArguments,
+        arguments: &WalletExportArgs,
+    ) -> anyhow::Result<String> {
+        let key_info = WalletExport::key_info(global, arguments)?;
+        LotusJsonKeyType {
+            r#type: WalletKeyType::try_from(*key_info.key_type())?.to_string(),
+            private_key: BASE64_STANDARD.encode(key_info.private_key()),
+        }
+        .to_hex_lotus()
+    }
+
+    /// Encrypts the key in an eth keystore file written to `output`.
+    fn export_eth_keystore(
+        global: &GlobalArguments,
+        arguments: &WalletExportArgs,
+    ) -> anyhow::Result<()> {
+        let output = arguments
+            .output
+            .as_ref()
+            .ok_or_else(|| anyhow!("output file required for the eth-keystore format"))?;
+        let mut password = match &arguments.password_file {
+            Some(path) => read_secret_from_file(path)?,
+            None => read_secret_from_stdin("password to encrypt the key with")?,
+        };
+        if password.is_empty() {
+            return Err(anyhow!("password required for the eth-keystore format"));
+        }
+
+        let key_info = WalletExport::key_info(global, arguments)?;
+        if *key_info.key_type() != SignatureType::Secp256k1 {
+            return Err(anyhow!(
+                "only secp256k1 keys can be exported as eth keystore"
+            ));
+        }
+
+        let output = Path::new(output);
+        let dir = match output.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let name = output
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow!("invalid output file"))?;
+        let encrypted = LocalWallet::encrypt_keystore(
+            dir,
+            &mut thread_rng(),
+            key_info.private_key(),
+            &password,
+            Some(name),
+        );
+        password.zeroize();
+        encrypted?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl CommandLineHandler for WalletExport {
     type Arguments = WalletExportArgs;
 
-    async fn handle(_global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("export wallet with args: {:?}", arguments);
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("export wallet with args: {:?}", arguments);
 
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
-        let v = match wallet_type {
-            WalletType::Evm => WalletExport::export_evm(arguments),
-            WalletType::Fvm => WalletExport::export_fvm(arguments),
+        let v = match ExportFormat::from_str(&arguments.format)? {
+            ExportFormat::Json => match wallet_type {
+                WalletType::Evm => WalletExport::export_evm(global, arguments),
+                WalletType::Fvm => WalletExport::export_fvm(global, arguments),
+            },
+            ExportFormat::HexLotus => WalletExport::export_hex_lotus(global, arguments),
+            ExportFormat::EthKeystore => {
+                WalletExport::export_eth_keystore(global, arguments)?;
+                tracing::info!(
+                    "exported wallet with address {:?} as eth keystore in file {:?}",
+                    arguments.address,
+                    arguments.output.as_ref().unwrap()
+                );
+                return Ok(());
+            }
         }?;
 
         match &arguments.output {
             Some(p) => {
                 let mut file = std::fs::File::create(p)?;
                 file.write_all(v.as_bytes())?;
-                log::info!(
+                tracing::info!(
                     "exported new wallet with address {:?} in file {:?}",
                     arguments.address,
                     p
                 );
             }
             None => {
-                log::info!("exported new wallet with address {:?}", arguments.address);
-                log::info!("Key: {:?}", v);
+                tracing::info!("exported new wallet with address {:?}", arguments.address);
+                tracing::info!("Key: {:?}", v);
             }
         }
 
@@ -90,7 +220,7 @@ pub(crate) struct WalletExportArgs {
     #[arg(
         long,
         short,
-        help = "Keystore path (the default repo keystore is used if not specified)"
+        help = "Keystore path (the keystore of the data dir is used if not specified)"
     )]
     pub keystore: Option<String>,
     #[arg(
@@ -101,4 +231,16 @@ pub(crate) struct WalletExportArgs {
     pub output: Option<String>,
     #[arg(long, short, help = "The type of the wallet, i.e. fvm, evm")]
     pub wallet_type: String,
+    #[arg(
+        long,
+        short,
+        default_value = "json",
+        help = "The format of the exported key, i.e. json, hex-lotus, eth-keystore"
+    )]
+    pub format: String,
+    #[arg(
+        long,
+        help = "The file with the password to encrypt the key with in the eth-keystore format, read from stdin if not specified"
+    )]
+    pub password_file: Option<String>,
 }
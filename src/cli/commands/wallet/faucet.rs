@@ -0,0 +1,63 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet faucet cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::wallet::faucet::FaucetRequestParams;
+
+pub(crate) struct WalletFaucet;
+
+#[async_trait]
+impl CommandLineHandler for WalletFaucet {
+    type Arguments = WalletFaucetArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("faucet request with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = client
+            .faucet_request(FaucetRequestParams {
+                subnet: arguments.subnet.clone(),
+                address: arguments.address.clone(),
+                from: arguments.from.clone(),
+                timeout_secs: arguments.timeout_secs,
+            })
+            .await?;
+
+        tracing::info!(
+            "received {} in {} after {}s, balance: {}",
+            response.received,
+            response.address,
+            response.waited_secs,
+            response.balance
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Request test funds for an address from the faucet of a subnet")]
+pub(crate) struct WalletFaucetArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet whose faucet to request funds from")]
+    pub subnet: String,
+    #[arg(
+        long,
+        short,
+        help = "The address calling the faucet contract, if the faucet is a contract"
+    )]
+    pub from: Option<String>,
+    #[arg(long, help = "The seconds to wait for the funds to land (default 180)")]
+    pub timeout_secs: Option<u64>,
+    #[arg(help = "The address to fund")]
+    pub address: String,
+}
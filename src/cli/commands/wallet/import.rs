@@ -19,7 +19,7 @@ impl CommandLineHandler for WalletImport {
     type Arguments = WalletImportArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("import wallet with args: {:?}", arguments);
+        tracing::debug!("import wallet with args: {:?}", arguments);
 
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
 
@@ -47,7 +47,7 @@ impl CommandLineHandler for WalletImport {
             }
         };
 
-        log::info!("imported wallet with address {:?}", addr);
+        tracing::info!("imported wallet with address {:?}", addr);
 
         Ok(())
     }
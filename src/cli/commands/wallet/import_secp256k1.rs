@@ -0,0 +1,97 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet secp256k1 key import cli handler
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::Args;
+use ethers::signers::LocalWallet;
+use ipc_identity::PersistentKeyInfo;
+use std::fmt::Debug;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::lotus::message::wallet::WalletKeyType;
+use crate::sdk::{IpcAgentClient, LotusJsonKeyType};
+
+pub(crate) struct WalletImportSecp256k1;
+
+impl WalletImportSecp256k1 {
+    /// Reads the hex encoded private key from a key file in any of the formats supported: lotus
+    /// json, hex-lotus, the json exported by the agent for evm keys, or an encrypted eth keystore
+    /// if a password is provided.
+    fn read_private_key(path: &str, password: &Option<String>) -> anyhow::Result<String> {
+        if let Some(password) = password {
+            let wallet = LocalWallet::decrypt_keystore(path, password)?;
+            let private_key = Zeroizing::new(wallet.signer().to_bytes().to_vec());
+            return Ok(hex::encode(&*private_key));
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let content = content.trim();
+        if let Ok(key) = LotusJsonKeyType::from_str(content)
+            .or_else(|_| LotusJsonKeyType::from_hex_lotus(content))
+        {
+            if WalletKeyType::from_str(&key.r#type)? != WalletKeyType::Secp256k1 {
+                return Err(anyhow!("only secp256k1 keys can be used in evm subnets"));
+            }
+            let private_key = Zeroizing::new(BASE64_STANDARD.decode(&key.private_key)?);
+            return Ok(hex::encode(&*private_key));
+        }
+
+        let persisted: PersistentKeyInfo =
+            serde_json::from_str(content).map_err(|_| anyhow!("unknown key format in {path:}"))?;
+        Ok(String::from(persisted.private_key()))
+    }
+}
+
+#[async_trait]
+impl CommandLineHandler for WalletImportSecp256k1 {
+    type Arguments = WalletImportSecp256k1Args;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("import secp256k1 key with args: {:?}", arguments);
+
+        let private_key = match (&arguments.private_key, &arguments.path) {
+            (Some(key), None) => key.clone(),
+            (None, Some(path)) => {
+                WalletImportSecp256k1::read_private_key(path, &arguments.password)?
+            }
+            _ => return Err(anyhow!("either private key or path must be specified")),
+        };
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let addrs = client.import_secp256k1(private_key).await?;
+
+        tracing::info!("imported key with addresses:");
+        tracing::info!("fvm: {}", addrs.fvm_address);
+        tracing::info!("evm: {} (f410: {})", addrs.evm_address, addrs.fevm_address);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Import a secp256k1 key to be used both in fvm and fevm subnets")]
+pub(crate) struct WalletImportSecp256k1Args {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "Path of the key file, in lotus json, hex-lotus, evm json or eth keystore format"
+    )]
+    pub path: Option<String>,
+    #[arg(long, help = "The password of the eth keystore file in path")]
+    pub password: Option<String>,
+    #[arg(
+        long,
+        short = 'k',
+        help = "The hex encoded private key to import if path is not specified"
+    )]
+    pub private_key: Option<String>,
+}
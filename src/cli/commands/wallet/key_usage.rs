@@ -0,0 +1,92 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Key usage cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
+use crate::server::wallet::key_usage::{KeyUsageParams, KeyUsageResponse};
+
+pub(crate) struct WalletKeyUsage;
+
+#[async_trait]
+impl CommandLineHandler for WalletKeyUsage {
+    type Arguments = WalletKeyUsageArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("key usage with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
+
+        let params = KeyUsageParams {
+            address: arguments.address.clone(),
+        };
+
+        let entries = json_rpc_client
+            .request::<KeyUsageResponse>(json_rpc_methods::KEY_USAGE, serde_json::to_value(params)?)
+            .await?;
+
+        if entries.is_empty() {
+            tracing::info!("no keys used since the agent started");
+        }
+
+        for entry in entries {
+            let usage = entry.usage;
+            tracing::info!(
+                "{}: {} signatures, last used at {:?}{}",
+                entry.address,
+                usage.signatures,
+                usage.last_used,
+                if usage.validator {
+                    ", validator key"
+                } else {
+                    ""
+                }
+            );
+            if usage.validator_transfers > 0 {
+                tracing::warn!(
+                    "{}: used for {} value transfers while acting as a validator",
+                    entry.address,
+                    usage.validator_transfers
+                );
+            }
+            for u in usage.recent.iter().rev().take(arguments.limit) {
+                tracing::info!(
+                    "    {} {:?} in {}: {}",
+                    u.timestamp,
+                    u.operation,
+                    u.subnet,
+                    u.message
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Review the signatures produced with the keys of the agent since it started")]
+pub(crate) struct WalletKeyUsageArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(
+        long,
+        short,
+        help = "The fvm or evm address of the key, all keys if not set"
+    )]
+    pub address: Option<String>,
+    #[arg(
+        long,
+        default_value = "10",
+        help = "The number of most recent usages to print for every key"
+    )]
+    pub limit: usize,
+}
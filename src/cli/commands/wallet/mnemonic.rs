@@ -0,0 +1,54 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet mnemonic generation cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_identity::hd::{generate_mnemonic, DEFAULT_WORD_COUNT};
+use std::fmt::Debug;
+use std::io::Write;
+
+use crate::cli::{CommandLineHandler, GlobalArguments};
+
+pub(crate) struct WalletMnemonic;
+
+#[async_trait]
+impl CommandLineHandler for WalletMnemonic {
+    type Arguments = WalletMnemonicArgs;
+
+    async fn handle(_global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("generate mnemonic with args: {:?}", arguments);
+
+        let phrase = generate_mnemonic(arguments.words)?;
+        match &arguments.output {
+            Some(p) => {
+                let mut file = std::fs::File::create(p)?;
+                // only the user can read the mnemonic, as anyone can derive the keys from it
+                #[cfg(unix)]
+                ipc_identity::set_user_perm(&file)?;
+                file.write_all(phrase.as_bytes())?;
+                tracing::info!("new mnemonic written to file {:?}", p);
+            }
+            // printed rather than logged, so that it does not end up in the logs
+            None => println!("{phrase:}"),
+        }
+        tracing::info!(
+            "back up the mnemonic, it is the only way to recover the accounts derived from it"
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Generate a new BIP39 mnemonic to derive accounts from")]
+pub(crate) struct WalletMnemonicArgs {
+    #[arg(long, short, default_value_t = DEFAULT_WORD_COUNT, help = "The number of words of the mnemonic")]
+    pub words: usize,
+    #[arg(
+        long,
+        short,
+        help = "Optional parameter that outputs the mnemonic into the file specified"
+    )]
+    pub output: Option<String>,
+}
@@ -2,19 +2,40 @@
 // SPDX-License-Identifier: MIT
 use crate::cli::{CommandLineHandler, GlobalArguments};
 
+use crate::cli::commands::wallet::accounting::{
+    WalletAccountingExport, WalletAccountingExportArgs,
+};
+use crate::cli::commands::wallet::activity::{WalletActivity, WalletActivityArgs};
 use crate::cli::commands::wallet::balances::{WalletBalances, WalletBalancesArgs};
 use crate::cli::commands::wallet::new::{WalletNew, WalletNewArgs};
 use clap::{Args, Subcommand};
 
+use self::derive::{WalletDerive, WalletDeriveArgs};
 use self::export::{WalletExport, WalletExportArgs};
+use self::faucet::{WalletFaucet, WalletFaucetArgs};
 use self::import::{WalletImport, WalletImportArgs};
+use self::import_secp256k1::{WalletImportSecp256k1, WalletImportSecp256k1Args};
+use self::key_usage::{WalletKeyUsage, WalletKeyUsageArgs};
+use self::mnemonic::{WalletMnemonic, WalletMnemonicArgs};
+use self::pending::{WalletPending, WalletPendingArgs};
 use self::remove::{WalletRemove, WalletRemoveArgs};
+use self::replace::{WalletReplace, WalletReplaceArgs};
 
+mod accounting;
+mod activity;
 mod balances;
+mod derive;
 mod export;
+mod faucet;
 mod import;
+mod import_secp256k1;
+mod key_usage;
+mod mnemonic;
 mod new;
+mod pending;
 mod remove;
+mod replace;
+mod secret;
 
 #[derive(Debug, Args)]
 #[command(name = "wallet", about = "wallet related commands")]
@@ -30,8 +51,17 @@ impl WalletCommandsArgs {
             Commands::New(args) => WalletNew::handle(global, args).await,
             Commands::Balances(args) => WalletBalances::handle(global, args).await,
             Commands::Import(args) => WalletImport::handle(global, args).await,
+            Commands::ImportSecp256k1(args) => WalletImportSecp256k1::handle(global, args).await,
             Commands::Export(args) => WalletExport::handle(global, args).await,
             Commands::Remove(args) => WalletRemove::handle(global, args).await,
+            Commands::Mnemonic(args) => WalletMnemonic::handle(global, args).await,
+            Commands::Derive(args) => WalletDerive::handle(global, args).await,
+            Commands::KeyUsage(args) => WalletKeyUsage::handle(global, args).await,
+            Commands::Activity(args) => WalletActivity::handle(global, args).await,
+            Commands::AccountingExport(args) => WalletAccountingExport::handle(global, args).await,
+            Commands::Pending(args) => WalletPending::handle(global, args).await,
+            Commands::Replace(args) => WalletReplace::handle(global, args).await,
+            Commands::Faucet(args) => WalletFaucet::handle(global, args).await,
         }
     }
 }
@@ -41,6 +71,15 @@ pub(crate) enum Commands {
     New(WalletNewArgs),
     Balances(WalletBalancesArgs),
     Import(WalletImportArgs),
+    ImportSecp256k1(WalletImportSecp256k1Args),
     Export(WalletExportArgs),
     Remove(WalletRemoveArgs),
+    Mnemonic(WalletMnemonicArgs),
+    Derive(WalletDeriveArgs),
+    KeyUsage(WalletKeyUsageArgs),
+    Activity(WalletActivityArgs),
+    AccountingExport(WalletAccountingExportArgs),
+    Pending(WalletPendingArgs),
+    Replace(WalletReplaceArgs),
+    Faucet(WalletFaucetArgs),
 }
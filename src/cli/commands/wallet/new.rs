@@ -11,6 +11,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::wallet::new::{NewFvmWallet, WalletNewParams, WalletNewResponse};
 use crate::server::wallet::WalletType;
 
@@ -21,10 +22,10 @@ impl CommandLineHandler for WalletNew {
     type Arguments = WalletNewArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("create new wallet with args: {:?}", arguments);
+        tracing::debug!("create new wallet with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
         let params = match wallet_type {
@@ -41,7 +42,7 @@ impl CommandLineHandler for WalletNew {
             )
             .await?;
 
-        log::info!("created new wallet with address {:?}", addr,);
+        tracing::info!("created new wallet with address {:?}", addr,);
 
         Ok(())
     }
@@ -0,0 +1,62 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet pending messages cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+
+/// The command to list the messages of the accounts of a subnet pending in its memory pool.
+pub(crate) struct WalletPending;
+
+#[async_trait]
+impl CommandLineHandler for WalletPending {
+    type Arguments = WalletPendingArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("wallet pending with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let response = client
+            .list_pending_messages(&arguments.subnet, arguments.from.clone())
+            .await?;
+
+        if response.messages.is_empty() {
+            tracing::info!("no message pending in subnet {}", arguments.subnet);
+        }
+        for m in response.messages {
+            tracing::info!(
+                "{} nonce {}: {} to {} method {} (fee cap {}, premium {}){}",
+                m.from,
+                m.nonce,
+                m.value,
+                m.to,
+                m.method,
+                m.gas_fee_cap,
+                m.gas_premium,
+                if m.stuck { " stuck" } else { "" }
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the messages of the accounts of a subnet pending in its memory pool")]
+pub(crate) struct WalletPendingArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet to list the pending messages of")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The sender of the messages, the accounts of the subnet in the config if not set"
+    )]
+    pub from: Option<String>,
+}
@@ -11,6 +11,7 @@ use crate::cli::commands::get_ipc_agent_url;
 use crate::cli::{CommandLineHandler, GlobalArguments};
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::sdk::token_from_env;
 use crate::server::wallet::remove::WalletRemoveParams;
 use crate::server::wallet::WalletType;
 
@@ -21,10 +22,10 @@ impl CommandLineHandler for WalletRemove {
     type Arguments = WalletRemoveArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        log::debug!("remove wallet with args: {:?}", arguments);
+        tracing::debug!("remove wallet with args: {:?}", arguments);
 
         let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
-        let json_rpc_client = JsonRpcClientImpl::new(url, None);
+        let json_rpc_client = JsonRpcClientImpl::new(url, token_from_env().as_deref());
 
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
         let params = WalletRemoveParams {
@@ -39,7 +40,7 @@ impl CommandLineHandler for WalletRemove {
             )
             .await?;
 
-        log::info!("remove wallet keys for address {:?}", arguments.address);
+        tracing::info!("remove wallet keys for address {:?}", arguments.address);
 
         Ok(())
     }
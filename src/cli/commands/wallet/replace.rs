@@ -0,0 +1,83 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet replace message cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use std::fmt::Debug;
+
+use crate::cli::commands::get_ipc_agent_url;
+use crate::cli::{CommandLineHandler, GlobalArguments};
+use crate::sdk::IpcAgentClient;
+use crate::server::replace_message::ReplaceMessageParams;
+
+/// The command to replace or cancel a message of an account of the agent pending in a subnet.
+pub(crate) struct WalletReplace;
+
+#[async_trait]
+impl CommandLineHandler for WalletReplace {
+    type Arguments = WalletReplaceArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        tracing::debug!("wallet replace with args: {:?}", arguments);
+
+        let url = get_ipc_agent_url(&arguments.ipc_agent_url, global)?;
+        let client = IpcAgentClient::default_from_url(url);
+        let id = client
+            .replace_message(ReplaceMessageParams {
+                subnet: arguments.subnet.clone(),
+                from: arguments.from.clone(),
+                nonce: arguments.nonce,
+                gas_fee_cap: arguments.gas_fee_cap.clone(),
+                gas_premium: arguments.gas_premium.clone(),
+                gas_limit: arguments.gas_limit,
+                cancel: arguments.cancel,
+            })
+            .await?;
+
+        tracing::info!(
+            "message with nonce {} {} by {id:}",
+            arguments.nonce,
+            if arguments.cancel {
+                "cancelled"
+            } else {
+                "replaced"
+            }
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Replace or cancel a message of an account of the agent pending in a subnet")]
+pub(crate) struct WalletReplaceArgs {
+    #[arg(long, short, help = "The JSON RPC server url for ipc agent")]
+    pub ipc_agent_url: Option<String>,
+    #[arg(long, short, help = "The subnet the message is pending in")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The sender of the message, the first account of the subnet in the config if not set"
+    )]
+    pub from: Option<String>,
+    #[arg(long, help = "The nonce of the message to replace")]
+    pub nonce: u64,
+    #[arg(long, help = "The new gas fee cap, in attoFIL per unit of gas")]
+    pub gas_fee_cap: String,
+    #[arg(
+        long,
+        help = "The new gas premium, in attoFIL per unit of gas, at least 25% above the one of the pending message"
+    )]
+    pub gas_premium: String,
+    #[arg(
+        long,
+        help = "The new gas limit, the one of the pending message if not set"
+    )]
+    pub gas_limit: Option<u64>,
+    #[arg(
+        long,
+        help = "Replace the message with an empty send to the sender, also fills a gap in its nonces"
+    )]
+    pub cancel: bool,
+}
@@ -0,0 +1,34 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Reading of the passwords and passphrases of the wallet commands, which are never taken as
+//! arguments so that they do not end up in the process list or the shell history.
+
+use std::io::{BufRead, Write};
+
+use anyhow::anyhow;
+
+/// Reads a secret from the first line of stdin, after printing `prompt` to stderr so that it
+/// does not mix with the output of the command.
+pub(crate) fn read_secret_from_stdin(prompt: &str) -> anyhow::Result<String> {
+    eprint!("{prompt:}: ");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    if line.is_empty() {
+        return Err(anyhow!("no input for the {prompt:}"));
+    }
+    Ok(trim_newline(line))
+}
+
+/// Reads a secret from the file in `path`, without its trailing newline.
+pub(crate) fn read_secret_from_file(path: &str) -> anyhow::Result<String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| anyhow!("cannot read {path:}: {e:}"))?;
+    Ok(trim_newline(content))
+}
+
+fn trim_newline(mut s: String) -> String {
+    let len = s.trim_end_matches(&['\n', '\r'][..]).len();
+    s.truncate(len);
+    s
+}
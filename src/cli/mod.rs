@@ -1,12 +1,15 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
-use anyhow::Result;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use clap::Args;
 
 mod commands;
 
 use crate::config::Config;
+use crate::persistence::datadir::DataDir;
 pub use commands::*;
 
 const DEFAULT_REPO_PATH: &str = ".ipc-agent";
@@ -50,6 +53,19 @@ impl GlobalArguments {
         let config_path = self.config_path();
         Config::from_file(config_path)
     }
+
+    /// The data directory of the agent, where the daemon reads its keystores from: the one set in
+    /// the config, or the directory of the config if it is not set or there is no config yet.
+    pub fn data_dir(&self) -> Result<DataDir> {
+        let config_path = self.config_path();
+        let repo = Path::new(&config_path)
+            .parent()
+            .ok_or_else(|| anyhow!("no repo found for the config {config_path:}"))?;
+        if !Path::new(&config_path).exists() {
+            return Ok(DataDir::new(repo));
+        }
+        Ok(DataDir::in_repo(repo, &self.config()?))
+    }
 }
 
 pub fn default_repo_path() -> String {
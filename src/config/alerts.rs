@@ -0,0 +1,71 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// A threshold on a metric of the agent, evaluated by the agent itself, that fires a
+/// notification when the metric crosses it and another one when it is back within it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AlertRule {
+    /// The name the alert is notified with.
+    pub name: String,
+    pub metric: AlertMetric,
+    /// The subnet the metric is evaluated in, all the subnets if not set. Not used by the
+    /// metrics of the agent as a whole.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+    /// The account whose balance is evaluated, all the accounts of the subnet if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    /// Fires when the metric is above this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f64>,
+    /// Fires when the metric is below this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<f64>,
+}
+
+impl AlertRule {
+    /// Whether `value` of the metric crosses the thresholds of the rule.
+    pub fn breached(&self, value: f64) -> bool {
+        self.above.map_or(false, |a| value > a) || self.below.map_or(false, |b| value < b)
+    }
+
+    /// The thresholds of the rule, to show in its notifications.
+    pub fn thresholds(&self) -> String {
+        match (self.above, self.below) {
+            (Some(a), Some(b)) => format!("above {a:} or below {b:}"),
+            (Some(a), None) => format!("above {a:}"),
+            (None, Some(b)) => format!("below {b:}"),
+            (None, None) => String::from("no threshold"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// The checkpoints not executed yet, in checkpoint periods, of each direction checkpointed
+    /// by the agent.
+    CheckpointLag,
+    /// The balance of the accounts, in whole FIL.
+    Balance,
+    /// The checkpoint rounds that failed per minute, across the agent.
+    ErrorRate,
+    /// The epochs the oldest checkpoint not executed yet of each direction has been short of the
+    /// votes of its quorum, among all the validators of the subnet. Evaluated in the subnets
+    /// whose votes are counted, in the modes that run the checkpoints.
+    QuorumStall,
+}
+
+impl Display for AlertMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertMetric::CheckpointLag => write!(f, "checkpoint lag"),
+            AlertMetric::Balance => write!(f, "balance"),
+            AlertMetric::ErrorRate => write!(f, "error rate"),
+            AlertMetric::QuorumStall => write!(f, "quorum stall"),
+        }
+    }
+}
@@ -2,13 +2,15 @@
 // SPDX-License-Identifier: MIT
 //! Deserialization utils for config mod.
 
+use crate::config::subnet::{SubmissionDelayConfig, MAX_SUBMISSION_DELAY_SECS};
 use crate::config::Subnet;
 use fvm_shared::address::Address;
 use ipc_sdk::subnet_id::SubnetID;
 use primitives::EthAddress;
+use reqwest::header::{HeaderName, HeaderValue};
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Formatter;
 use std::str::FromStr;
 
@@ -29,6 +31,48 @@ where
     Ok(hashmap)
 }
 
+/// A serde deserialization method to deserialize the http headers of a subnet, failing on the
+/// names and values that cannot be sent in a request.
+pub(crate) fn deserialize_http_headers<'de, D>(
+    deserializer: D,
+) -> anyhow::Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let headers = <BTreeMap<String, String>>::deserialize(deserializer)?;
+    for (name, value) in &headers {
+        HeaderName::from_str(name)
+            .map_err(|e| D::Error::custom(format!("invalid http header name {name:}: {e:}")))?;
+        HeaderValue::from_str(value)
+            .map_err(|e| D::Error::custom(format!("invalid value of http header {name:}: {e:}")))?;
+    }
+    Ok(headers)
+}
+
+/// Deserializes the submission delay of a subnet, checking that its window is not inverted and
+/// does not exceed [`MAX_SUBMISSION_DELAY_SECS`].
+pub(crate) fn deserialize_submission_delay<'de, D>(
+    deserializer: D,
+) -> anyhow::Result<Option<SubmissionDelayConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let delay = <SubmissionDelayConfig>::deserialize(deserializer)?;
+    if delay.max_secs > MAX_SUBMISSION_DELAY_SECS {
+        return Err(D::Error::custom(format!(
+            "submission delay of {} seconds above the maximum of {MAX_SUBMISSION_DELAY_SECS:}",
+            delay.max_secs
+        )));
+    }
+    if delay.min_secs > delay.max_secs {
+        return Err(D::Error::custom(format!(
+            "submission delay min_secs {} above max_secs {}",
+            delay.min_secs, delay.max_secs
+        )));
+    }
+    Ok(Some(delay))
+}
+
 /// A serde deserialization method to deserialize an address from i64
 pub(crate) fn deserialize_address_from_str<'de, D>(
     deserializer: D,
@@ -0,0 +1,91 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// How the daemon logs: the format and levels of its logs, and the file it writes them to, in
+/// addition to stderr, and how it is rotated.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct LogConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// The level of the modules without a level of their own in `levels`.
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// The path of the log file, relative to the working directory of the agent if not absolute.
+    /// Rotated files are kept next to it, suffixed with the unix timestamp they were rotated at.
+    /// Only logged to stderr if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The size, in megabytes, past which the file is rotated. Not rotated on its size if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
+    /// How often the file is rotated, regardless of its size.
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// The number of rotated files that are kept, the oldest ones are deleted.
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// The levels of modules, by module path, e.g. `ipc_agent::checkpoint = "debug"`, including
+    /// the modules of the dependencies of the agent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub levels: BTreeMap<String, String>,
+}
+
+fn default_level() -> String {
+    String::from("info")
+}
+
+fn default_max_files() -> usize {
+    7
+}
+
+impl LogConfig {
+    /// The directives of the log filter: the default level, followed by the levels of the
+    /// modules.
+    pub fn filter_directives(&self) -> String {
+        let mut directives = vec![self.level.clone()];
+        for (module, level) in self.levels.iter() {
+            directives.push(format!("{module:}={level:}"));
+        }
+        directives.join(",")
+    }
+
+    /// The size, in bytes, past which the file is rotated.
+    pub fn max_size(&self) -> Option<u64> {
+        self.max_size_mb.map(|mb| mb * 1024 * 1024)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// A line of text per record.
+    #[default]
+    Text,
+    /// A JSON object per line, with the fields of the record and of the spans it was logged in,
+    /// e.g. the method and id of a JSON-RPC request or the subnet of a checkpoint.
+    Json,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    /// Only rotated on its size.
+    Never,
+}
+
+impl LogRotation {
+    /// The length, in seconds, of the periods a file covers.
+    pub fn period_secs(&self) -> Option<u64> {
+        match self {
+            LogRotation::Hourly => Some(60 * 60),
+            LogRotation::Daily => Some(24 * 60 * 60),
+            LogRotation::Never => None,
+        }
+    }
+}
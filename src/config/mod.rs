@@ -5,10 +5,16 @@
 //! Reads a TOML config file for the IPC Agent and deserializes it in a type-safe way into a
 //! [`Config`] struct.
 
+mod alerts;
 mod deserialize;
+mod logging;
+mod mode;
 mod reload;
+mod schemas;
 mod server;
 pub mod subnet;
+mod validate;
+mod watch;
 
 mod serialize;
 #[cfg(test)]
@@ -16,17 +22,28 @@ mod tests;
 
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+pub use alerts::{AlertMetric, AlertRule};
 use anyhow::Result;
 use deserialize::deserialize_subnets_from_vec;
 use ipc_sdk::subnet_id::SubnetID;
-pub use reload::ReloadableConfig;
+pub use logging::{LogConfig, LogFormat, LogRotation};
+pub use mode::RunMode;
+pub use reload::{ReloadStatus, ReloadableConfig};
+pub use schemas::StateSchema;
 use serde::{Deserialize, Serialize};
 use serialize::serialize_subnets_to_str;
-pub use server::JSON_RPC_ENDPOINT;
-pub use server::{json_rpc_methods, Server};
+pub use server::{
+    json_rpc_methods, ApprovalConfig, AuthConfig, AuthToken, DebugTapConfig, MetricsConfig,
+    NotificationsConfig, Permission, Server,
+};
+pub use server::{
+    HEALTHZ_ENDPOINT, JSON_RPC_ENDPOINT, METRICS_ENDPOINT, READYZ_ENDPOINT, WS_ENDPOINT,
+};
 pub use subnet::Subnet;
+pub use validate::{ConfigIssue, ConfigValidation, SubnetValidation};
+pub use watch::ConfigWatcher;
 
 pub const JSON_RPC_VERSION: &str = "2.0";
 
@@ -62,12 +79,27 @@ registry_addr = "0xb505eD453138A782b5c51f45952E067798F4777d"
 
 /// The top-level struct representing the config. Calls to [`Config::from_file`] deserialize into
 /// this struct.
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub struct Config {
+    /// The directory the agent keeps its state in, see [`crate::persistence::DataDir`]. Relative
+    /// to the directory of the config if not absolute, the directory of the config if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
     pub server: Server,
     #[serde(deserialize_with = "deserialize_subnets_from_vec", default)]
     #[serde(serialize_with = "serialize_subnets_to_str")]
     pub subnets: HashMap<SubnetID, Subnet>,
+    /// How the daemon logs, see [`LogConfig`]. Logged as text to stderr, at the levels of
+    /// `RUST_LOG`, if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<LogConfig>,
+    /// The thresholds on the metrics of the agent that notify the operator, see [`AlertRule`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRule>,
+    /// The schemas of the actors not known by the agent, by code cid, to decode their state
+    /// with in `ipc_readState`, e.g. a gateway deployed from another bundle.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub state_schemas: HashMap<String, StateSchema>,
 }
 
 impl Config {
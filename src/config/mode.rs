@@ -0,0 +1,113 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The workloads the daemon runs.
+//!
+//! Large operators can run the cross-net relaying and the checkpointing of their subnets in
+//! separate daemons, scaled and isolated independently, each checking at startup that the config
+//! has what its workload needs.
+
+use anyhow::{anyhow, Result};
+use strum::{Display, EnumString};
+
+use crate::config::subnet::SubnetMode;
+use crate::config::{json_rpc_methods, Config, Subnet};
+
+/// The methods of the JSON-RPC API that only make sense along the checkpointing subsystem.
+const CHECKPOINTING_METHODS: [&str; 7] = [
+    json_rpc_methods::CO_SIGN_VOTE,
+    json_rpc_methods::NOTIFY_TOP_DOWN_CHECKPOINT,
+    json_rpc_methods::LIST_VOTE_APPROVALS,
+    json_rpc_methods::APPROVE_VOTE,
+    json_rpc_methods::RESEND_TOPDOWN_MSGS,
+    json_rpc_methods::ESTIMATE_CHECKPOINT_SUBMISSION_TIME,
+    json_rpc_methods::CHECKPOINT_QUORUM,
+];
+
+/// The methods of the JSON-RPC API that move funds and messages across subnets.
+const CROSS_MSG_METHODS: [&str; 5] = [
+    json_rpc_methods::FUND,
+    json_rpc_methods::RELEASE,
+    json_rpc_methods::PROPAGATE,
+    json_rpc_methods::SEND_CROSS_MSG,
+    json_rpc_methods::DIAGNOSE_CROSS_MSG,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum RunMode {
+    /// Runs all the subsystems.
+    #[default]
+    Full,
+    /// Only serves the JSON-RPC API, to fund, release and propagate cross-net messages, without
+    /// the checkpointing subsystem and the methods that go with it.
+    Relayer,
+    /// Only runs the checkpointing subsystem, the JSON-RPC API serving everything but the
+    /// cross-net methods.
+    Checkpointer,
+}
+
+impl RunMode {
+    /// Whether the checkpointing subsystem runs in this mode.
+    pub fn runs_checkpointing(&self) -> bool {
+        *self != RunMode::Relayer
+    }
+
+    /// Whether `method` is served by the JSON-RPC API in this mode.
+    pub fn serves(&self, method: &str) -> bool {
+        match self {
+            RunMode::Full => true,
+            RunMode::Relayer => !CHECKPOINTING_METHODS.contains(&method),
+            RunMode::Checkpointer => !CROSS_MSG_METHODS.contains(&method),
+        }
+    }
+
+    /// Checks that `config` has the subnets the workload of the mode needs.
+    pub fn validate(&self, config: &Config) -> Result<()> {
+        match self {
+            RunMode::Full => Ok(()),
+            RunMode::Relayer => {
+                if !config
+                    .subnets
+                    .values()
+                    .any(|s| s.features.propagate && can_submit(s))
+                {
+                    return Err(anyhow!(
+                        "relayer mode needs a subnet with propagation enabled, in validate mode and with accounts to sign with"
+                    ));
+                }
+                Ok(())
+            }
+            RunMode::Checkpointer => {
+                let checkpointed = config
+                    .subnets
+                    .values()
+                    .filter(|s| s.features.bottom_up || s.features.top_down)
+                    .filter_map(|child| {
+                        let parent = config.subnets.get(&child.id.parent()?)?;
+                        Some((child, parent))
+                    })
+                    .collect::<Vec<_>>();
+                if checkpointed.is_empty() {
+                    return Err(anyhow!(
+                        "checkpointer mode needs a subnet with checkpointing enabled and its parent in the config"
+                    ));
+                }
+                // bottom-up checkpoints are voted in the parent, top-down ones in the child
+                if !checkpointed.iter().any(|(child, parent)| {
+                    (child.features.bottom_up && can_submit(parent))
+                        || (child.features.top_down && can_submit(child))
+                }) {
+                    return Err(anyhow!(
+                        "checkpointer mode needs a checkpointed subnet, or its parent for bottom-up checkpoints, in validate mode and with accounts to vote with"
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Whether the agent can sign and submit messages to `subnet`.
+fn can_submit(subnet: &Subnet) -> bool {
+    subnet.mode == SubnetMode::Validate && !subnet.accounts().is_empty()
+}
@@ -4,11 +4,22 @@
 
 use crate::config::Config;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::ops::DerefMut;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::broadcast;
 
+/// The outcome of the last reload of the config.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReloadStatus {
+    /// The unix timestamp, in seconds, of the reload.
+    pub at: u64,
+    /// The error the reload failed with, the previous config is still in use if set.
+    pub error: Option<String>,
+}
+
 /// Reloadable configuration exposes the latest config through `get_config` method. Use this you
 /// will always the latest config. At the same time, it also exposes `new_subscriber`. If caller
 /// needs to be notified when config has updated, just make a new subscription. Once received a
@@ -16,6 +27,7 @@ use tokio::sync::broadcast;
 pub struct ReloadableConfig {
     path: RwLock<Arc<String>>,
     config: RwLock<Arc<Config>>,
+    last_reload: RwLock<Option<ReloadStatus>>,
     broadcast_tx: broadcast::Sender<()>,
     /// We keep at least one channel active, so that we dont encounter a `SendError`. We might need to use it later.
     #[allow(dead_code)]
@@ -32,6 +44,7 @@ impl ReloadableConfig {
         Ok(Self {
             path: RwLock::new(Arc::new(path)),
             config,
+            last_reload: RwLock::new(None),
             broadcast_tx,
             broadcast_rx,
         })
@@ -49,6 +62,11 @@ impl ReloadableConfig {
         *p = Arc::new(path);
     }
 
+    /// Returns the path of the config file.
+    pub fn get_path(&self) -> String {
+        self.path.read().unwrap().to_string()
+    }
+
     /// Returns the current path where the config is stored.
     pub fn get_config_repo(&self) -> Option<String> {
         let p = self.path.read().unwrap().clone();
@@ -56,11 +74,55 @@ impl ReloadableConfig {
         Some(parent.to_str()?.to_string())
     }
 
+    /// The outcome of the last reload, none if the config was not reloaded since it was loaded.
+    pub fn last_reload(&self) -> Option<ReloadStatus> {
+        self.last_reload.read().unwrap().clone()
+    }
+
     /// Triggers a reload of the config.
     pub async fn reload(&self) -> Result<()> {
+        self.record(self.load(|_| Ok(()), true).await).map(|_| ())
+    }
+
+    /// Reloads the config if its file changed, e.g. when the file is watched, and only if the
+    /// new config passes `validate`. Returns whether the config changed, a file without changes
+    /// not counting as a reload.
+    pub async fn reload_changed(
+        &self,
+        validate: impl FnOnce(&Config) -> Result<()>,
+    ) -> Result<bool> {
+        match self.load(validate, false).await {
+            Ok(false) => Ok(false),
+            result => self.record(result),
+        }
+    }
+
+    /// Records the outcome of a reload as the last one.
+    fn record(&self, result: Result<bool>) -> Result<bool> {
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        *self.last_reload.write().unwrap() = Some(ReloadStatus {
+            at,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+    }
+
+    /// Loads the config from its file, unless it is the one in use and `force` is not set.
+    async fn load(
+        &self,
+        validate: impl FnOnce(&Config) -> Result<()>,
+        force: bool,
+    ) -> Result<bool> {
         let path = self.path.read().unwrap().to_string();
         let new_config = Config::from_file_async(path).await?;
-        log::info!("new config loaded: {new_config:?}");
+        if !force && new_config == *self.get_config() {
+            return Ok(false);
+        }
+        validate(&new_config)?;
+        tracing::info!("new config loaded: {new_config:?}");
 
         let mut config = self.config.write().unwrap();
         let r = config.deref_mut();
@@ -68,7 +130,7 @@ impl ReloadableConfig {
 
         self.broadcast_tx.send(()).unwrap_or_default();
 
-        Ok(())
+        Ok(true)
     }
 
     pub fn new_subscriber(&self) -> broadcast::Receiver<()> {
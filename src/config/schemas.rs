@@ -0,0 +1,28 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// The typed schema the state of an actor is decoded with by `ipc_readState`.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum StateSchema {
+    Gateway,
+    SubnetActor,
+    Init,
+    Account,
+    Multisig,
+}
+
+impl Display for StateSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateSchema::Gateway => write!(f, "gateway"),
+            StateSchema::SubnetActor => write!(f, "subnet_actor"),
+            StateSchema::Init => write!(f, "init"),
+            StateSchema::Account => write!(f, "account"),
+            StateSchema::Multisig => write!(f, "multisig"),
+        }
+    }
+}
@@ -135,21 +135,52 @@ mod tests {
         let mut config = Config {
             server: Server {
                 json_rpc_address: "127.0.0.1:3030".parse().unwrap(),
+                approvals: None,
+                disabled_methods: vec![],
+                debug_tap: None,
+                notifications: None,
+                auth: None,
+                metrics: None,
+                shutdown_timeout_secs: 60,
+                watch_config: false,
             },
             subnets: Default::default(),
+            log: None,
+            alerts: vec![],
+            state_schemas: Default::default(),
+            data_dir: None,
         };
 
         let subnet1 = Subnet {
             id: SubnetID::new_root(123),
             network_name: "test".to_string(),
+            block_time_secs: None,
+            checkpoint_period_poll_secs: None,
+            gas: Default::default(),
+            mode: Default::default(),
+            features: Default::default(),
+            vote_policy: Default::default(),
+            checkpoint_signing: Default::default(),
+            co_signing: None,
+            submission_delay: None,
+            federation: None,
+            faucet: None,
+            headers: Default::default(),
             config: SubnetConfig::Fvm(FVMSubnet {
                 gateway_addr: Address::from_str("f01").unwrap(),
                 jsonrpc_api_http: "http://127.0.0.1:3030/rpc/v1".parse().unwrap(),
+                jsonrpc_api_ws: None,
+                expected_network_name: None,
+                retry: Default::default(),
+                connections: Default::default(),
+                message_wait: Default::default(),
                 auth_token: None,
                 accounts: vec![
                     Address::from_str("f01").unwrap(),
                     Address::from_str("f01").unwrap(),
                 ],
+                signer: Default::default(),
+                decoding: Default::default(),
             }),
         };
 
@@ -157,9 +188,22 @@ mod tests {
         let subnet2 = Subnet {
             id: SubnetID::new_root(1234),
             network_name: "test2".to_string(),
+            block_time_secs: None,
+            checkpoint_period_poll_secs: None,
+            gas: Default::default(),
+            mode: Default::default(),
+            features: Default::default(),
+            vote_policy: Default::default(),
+            checkpoint_signing: Default::default(),
+            co_signing: None,
+            submission_delay: None,
+            federation: None,
+            faucet: None,
+            headers: Default::default(),
             config: SubnetConfig::Fevm(EVMSubnet {
                 gateway_addr: Address::from(eth_addr1),
                 provider_http: "http://127.0.0.1:3030/rpc/v1".parse().unwrap(),
+                chain_id: None,
                 auth_token: None,
                 accounts: vec![Address::from(eth_addr1), Address::from(eth_addr1)],
                 registry_addr: Address::from(eth_addr1),
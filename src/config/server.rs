@@ -1,18 +1,176 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::time::Duration;
+use url::Url;
 
 pub const JSON_RPC_ENDPOINT: &str = "json_rpc";
+pub const WS_ENDPOINT: &str = "ws";
+pub const METRICS_ENDPOINT: &str = "metrics";
+pub const HEALTHZ_ENDPOINT: &str = "healthz";
+pub const READYZ_ENDPOINT: &str = "readyz";
 
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Server {
     pub json_rpc_address: SocketAddr,
+    /// The operations that are parked until an operator approves them, see [`ApprovalConfig`].
+    /// All operations are executed right away if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approvals: Option<ApprovalConfig>,
+    /// The json rpc methods that are not served, e.g. to run an agent that only reads state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_methods: Vec<String>,
+    /// Records the raw json rpc traffic with the nodes of the subnets, see [`DebugTapConfig`].
+    /// Nothing is recorded if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_tap: Option<DebugTapConfig>,
+    /// Where the notifications of the agent are sent, see [`NotificationsConfig`]. They are only
+    /// logged if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationsConfig>,
+    /// The bearer tokens the clients of the json rpc api authenticate with, see [`AuthConfig`].
+    /// The api is open to every client if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+    /// The prometheus endpoint of the agent, see [`MetricsConfig`]. The metrics are not exported
+    /// if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsConfig>,
+    /// The number of seconds the daemon waits, when it is stopped, for the requests being served
+    /// and the checkpoint submissions in flight to finish before it exits.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Whether the daemon reloads the config when its file changes, see
+    /// [`crate::config::ConfigWatcher`]. Only read when the daemon starts.
+    #[serde(default)]
+    pub watch_config: bool,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    60
+}
+
+impl Server {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+}
+
+/// The thresholds above which the operations requested to the agent are parked in the approval
+/// queue instead of being executed.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ApprovalConfig {
+    /// The value, in whole FIL, above which funds, releases, transfers and cross-net messages
+    /// need approval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_threshold: Option<f64>,
+    /// The collateral, in whole FIL, above which joining a subnet needs approval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collateral_threshold: Option<f64>,
+    /// Whether killing a subnet needs approval.
+    #[serde(default)]
+    pub kill: bool,
+    /// The number of seconds a parked operation can be approved for.
+    #[serde(default = "default_approval_expiry_secs")]
+    pub expiry_secs: u64,
+}
+
+fn default_approval_expiry_secs() -> u64 {
+    60 * 60
+}
+
+impl ApprovalConfig {
+    pub fn expiry(&self) -> Duration {
+        Duration::from_secs(self.expiry_secs)
+    }
+}
+
+/// The size of the ring buffer the requests to the nodes, and their responses, are recorded in.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct DebugTapConfig {
+    /// The maximum size, in bytes, of the requests and responses recorded. The oldest ones are
+    /// dropped first.
+    #[serde(default = "default_debug_tap_max_bytes")]
+    pub max_bytes: usize,
+}
+
+fn default_debug_tap_max_bytes() -> usize {
+    1024 * 1024
+}
+
+/// The webhook the notifications of the agent, e.g. a validator running out of funds to vote
+/// checkpoints, are posted to as json.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct NotificationsConfig {
+    pub webhook_url: Url,
+}
+
+/// The static bearer tokens accepted by the json rpc api, each granting a [`Permission`], and
+/// the permission needed by the methods.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<AuthToken>,
+    /// The permission needed by the methods listed, overriding the built-in one: `read` for the
+    /// methods that only query state, `admin` for those that kill subnets, move the funds of the
+    /// wallets or manage the agent itself, and `write` for all the others.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub methods: HashMap<String, Permission>,
+    /// Whether the methods needing the `read` permission are served to the requests without a
+    /// token.
+    #[serde(default = "default_anonymous_read")]
+    pub anonymous_read: bool,
+}
+
+fn default_anonymous_read() -> bool {
+    true
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AuthToken {
+    pub token: String,
+    pub permission: Permission,
+}
+
+/// The address the metrics of the agent are served at, in the prometheus text format.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MetricsConfig {
+    /// The address of the `/metrics` endpoint, on a port of its own so that it can be exposed to
+    /// the scrapers only.
+    pub listen_address: SocketAddr,
+    /// The number of seconds between two refreshes of the balances of the accounts of the
+    /// subnets.
+    #[serde(default = "default_balance_poll_secs")]
+    pub balance_poll_secs: u64,
+}
+
+fn default_balance_poll_secs() -> u64 {
+    60
+}
+
+impl MetricsConfig {
+    pub fn balance_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.balance_poll_secs)
+    }
+}
+
+/// The permission levels of the json rpc api, each granting the methods of the previous ones.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Read,
+    Write,
+    Admin,
 }
 
 pub mod json_rpc_methods {
     pub const CREATE_SUBNET: &str = "ipc_createSubnet";
+    pub const PREVIEW_CREATE_SUBNET: &str = "ipc_previewCreateSubnet";
     pub const JOIN_SUBNET: &str = "ipc_joinSubnet";
+    pub const ESTIMATE_JOIN_COLLATERAL: &str = "ipc_estimateJoinCollateral";
+    pub const ONBOARDING_DRY_RUN: &str = "ipc_onboardingDryRun";
     pub const RPC_SUBNET: &str = "ipc_rpcSubnet";
     pub const LEAVE_SUBNET: &str = "ipc_leaveSubnet";
     pub const KILL_SUBNET: &str = "ipc_killSubnet";
@@ -20,17 +178,72 @@ pub mod json_rpc_methods {
     pub const RELEASE: &str = "ipc_release";
     pub const PROPAGATE: &str = "ipc_propagate";
     pub const SEND_CROSS_MSG: &str = "ipc_sendCrossMessage";
+    pub const DIAGNOSE_CROSS_MSG: &str = "ipc_diagnoseCrossMessage";
     pub const LIST_CHILD_SUBNETS: &str = "ipc_listChildSubnets";
     pub const RELOAD_CONFIG: &str = "ipc_reloadConfig";
+    pub const VALIDATE_CONFIG: &str = "ipc_validateConfig";
     pub const QUERY_VALIDATOR_SET: &str = "ipc_queryValidatorSet";
+    pub const COMPARE_VALIDATOR_SETS: &str = "ipc_compareValidatorSets";
     pub const SET_VALIDATOR_NET_ADDR: &str = "ipc_setValidatorNetAddr";
     pub const SET_VALIDATOR_WORKER_ADDR: &str = "ipc_setValidatorWorkerAddr";
+    pub const SET_FEDERATED_POWER: &str = "ipc_setFederatedPower";
     pub const SEND_VALUE: &str = "ipc_sendValue";
+    pub const BUILD_OFFLINE_TX: &str = "ipc_buildOfflineTx";
+    pub const SEND_SIGNED_TX: &str = "ipc_sendSignedTx";
     pub const WALLET_NEW: &str = "ipc_walletNew";
     pub const WALLET_REMOVE: &str = "ipc_walletRemove";
     pub const WALLET_IMPORT: &str = "ipc_walletImport";
+    pub const WALLET_IMPORT_SECP256K1: &str = "ipc_walletImportSecp256k1";
     pub const WALLET_EXPORT: &str = "ipc_walletExport";
     pub const WALLET_BALANCES: &str = "ipc_walletBalances";
+    pub const KEY_USAGE: &str = "ipc_keyUsage";
+    pub const WALLET_ACTIVITY: &str = "ipc_walletActivity";
+    pub const FAUCET_REQUEST: &str = "ipc_faucetRequest";
+    pub const LIST_PENDING_MESSAGES: &str = "ipc_listPendingMessages";
+    pub const REPLACE_MESSAGE: &str = "ipc_replaceMessage";
+    pub const GET_MESSAGE: &str = "ipc_getMessage";
+    pub const GET_RECEIPT: &str = "ipc_getReceipt";
     pub const LIST_BOTTOMUP_CHECKPOINTS: &str = "ipc_listBottomUpCheckpoints";
     pub const LAST_TOPDOWN_EXECUTED: &str = "ipc_lastTopDownCheckpointExecuted";
+    pub const PENDING_BOTTOMUP_MSGS: &str = "ipc_pendingBottomUpMsgs";
+    pub const PENDING_RELEASES: &str = "ipc_pendingReleases";
+    pub const NONCES: &str = "ipc_nonces";
+    pub const RESEND_TOPDOWN_MSGS: &str = "ipc_resendTopdownMsgs";
+    pub const NOTIFY_TOP_DOWN_CHECKPOINT: &str = "ipc_notifyTopDownCheckpoint";
+    pub const BOTTOMUP_CHECKPOINT_DIFF: &str = "ipc_bottomUpCheckpointDiff";
+    pub const GET_CIRCULATING_SUPPLY: &str = "ipc_getCirculatingSupply";
+    pub const HEALTH: &str = "ipc_health";
+    pub const SIGNED_STATUS: &str = "ipc_signedStatus";
+    pub const LIST_VALIDATORS_ACROSS_SUBNETS: &str = "ipc_listValidatorsAcrossSubnets";
+    pub const LIST_VOTE_APPROVALS: &str = "ipc_listVoteApprovals";
+    pub const APPROVE_VOTE: &str = "ipc_approveVote";
+    pub const CHECKPOINT_QUORUM: &str = "ipc_checkpointQuorum";
+    pub const LIST_OPERATION_APPROVALS: &str = "ipc_listOperationApprovals";
+    pub const APPROVE_OPERATION: &str = "ipc_approveOperation";
+    pub const ABORT_PENDING_OPERATION: &str = "ipc_abortPendingOperation";
+    pub const CO_SIGN_VOTE: &str = "ipc_coSignVote";
+    pub const LIST_METHODS: &str = "ipc_listMethods";
+    pub const TOPOLOGY: &str = "ipc_topology";
+    pub const DISCOVER_SUBNETS: &str = "ipc_discoverSubnets";
+    pub const LIST_OWN_SUBNETS: &str = "ipc_listOwnSubnets";
+    pub const IMPORT_SUBNETS: &str = "ipc_importSubnets";
+    pub const DECOMMISSION_SUBNET: &str = "ipc_decommissionSubnet";
+    pub const BACKFILL_HISTORY: &str = "ipc_backfillHistory";
+    pub const CHECKPOINT_HISTORY: &str = "ipc_checkpointHistory";
+    pub const LIST_CHECKPOINT_SUBMISSIONS: &str = "ipc_listCheckpointSubmissions";
+    pub const QUERY_VALIDATOR_VOTE_HISTORY: &str = "ipc_queryValidatorVoteHistory";
+    pub const ESTIMATE_CHECKPOINT_SUBMISSION_TIME: &str = "ipc_estimateCheckpointSubmissionTime";
+    pub const READ_STATE: &str = "ipc_readState";
+    pub const GET_CROSS_MSGS_BY_ACCOUNT: &str = "ipc_getCrossMsgsByAccount";
+    pub const LIST_JOBS: &str = "ipc_listJobs";
+    pub const CONTROL_JOB: &str = "ipc_controlJob";
+    pub const LIST_NOTIFICATIONS: &str = "ipc_listNotifications";
+    pub const REDELIVER_NOTIFICATIONS: &str = "ipc_redeliverNotifications";
+    pub const DEBUG_TAP: &str = "ipc_debugTap";
+    pub const MAINTENANCE: &str = "ipc_maintenance";
+    /// Only served over websocket, along with the notifications of the subscriptions.
+    pub const SUBSCRIBE_CHECKPOINTS: &str = "ipc_subscribeCheckpoints";
+    pub const SUBSCRIBE_CROSS_MSGS: &str = "ipc_subscribeCrossMsgs";
+    pub const UNSUBSCRIBE: &str = "ipc_unsubscribe";
+    pub const SUBSCRIPTION: &str = "ipc_subscription";
 }
@@ -1,13 +1,23 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use ethers::core::rand::{thread_rng, Rng};
 use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
 use ipc_sdk::subnet_id::SubnetID;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::config::deserialize::{
     deserialize_accounts, deserialize_address_from_str, deserialize_eth_accounts,
-    deserialize_eth_address_from_str, deserialize_subnet_id,
+    deserialize_eth_address_from_str, deserialize_http_headers, deserialize_submission_delay,
+    deserialize_subnet_id,
 };
 use crate::config::serialize::{
     serialize_accounts, serialize_address_to_str, serialize_eth_accounts,
@@ -15,16 +25,85 @@ use crate::config::serialize::{
 };
 
 /// Represents a subnet declaration in the config.
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Subnet {
     #[serde(deserialize_with = "deserialize_subnet_id")]
     #[serde(serialize_with = "serialize_subnet_id_to_str")]
     pub id: SubnetID,
     pub network_name: String,
+    /// The expected block time of the subnet in seconds, used to estimate when an epoch will
+    /// be reached. The block time is measured from the chain if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_time_secs: Option<u64>,
+    /// The interval in seconds between the polls of the checkpoints of the subnet, both bottom-up
+    /// and top-down, instead of about once per block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_period_poll_secs: Option<u64>,
+    /// The adjustments applied to the estimated gas limit of the messages sent to the subnet.
+    #[serde(default)]
+    pub gas: GasConfig,
+    /// Whether the agent acts on the subnet or only observes it, see [`SubnetMode`].
+    #[serde(default)]
+    pub mode: SubnetMode,
+    /// The checkpointing and cross-net features run for the subnet, see [`SubnetFeatures`].
+    #[serde(default)]
+    pub features: SubnetFeatures,
+    /// How the agent decides to vote the checkpoints of the subnet, see [`VotePolicyConfig`].
+    #[serde(default)]
+    pub vote_policy: VotePolicyConfig,
+    /// How the bottom-up checkpoints of the subnet are signed, see [`CheckpointSigning`].
+    #[serde(default)]
+    pub checkpoint_signing: CheckpointSigning,
+    /// The second agent that co-signs the checkpoint votes of the subnet, see
+    /// [`CoSigningConfig`]. Votes are submitted without co-signing if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub co_signing: Option<CoSigningConfig>,
+    /// The random delay before the checkpoint votes of each epoch of the subnet are submitted,
+    /// see [`SubmissionDelayConfig`]. Votes are submitted as soon as they are decided if not set.
+    #[serde(deserialize_with = "deserialize_submission_delay", default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submission_delay: Option<SubmissionDelayConfig>,
+    /// The peer agents notified of the top-down checkpoints of the subnet reached by its parent,
+    /// or the one trusted to notify them, see [`FederationConfig`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub federation: Option<FederationConfig>,
+    /// The faucet test funds are requested from for the accounts of the subnet, on devnets and
+    /// testnets, see [`FaucetConfig`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faucet: Option<FaucetConfig>,
+    /// The extra http headers sent with every request to the node of the subnet, e.g. the
+    /// credentials required by hosted rpc providers.
+    #[serde(deserialize_with = "deserialize_http_headers", default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
     pub config: SubnetConfig,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+/// How the agent interacts with a subnet.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubnetMode {
+    /// The agent signs and submits messages to the subnet, including checkpoints.
+    #[default]
+    Validate,
+    /// The agent only reads from the subnet, e.g. for explorers or parents where the operator
+    /// is not a validator. Signing and submitting messages is disabled.
+    Observe,
+}
+
+impl SubnetMode {
+    /// Fails if messages cannot be signed and submitted to `subnet` in this mode.
+    pub fn ensure_can_submit(&self, subnet: &SubnetID) -> anyhow::Result<()> {
+        if *self == SubnetMode::Observe {
+            return Err(anyhow!(
+                "subnet {subnet:} is in observe mode, signing and submitting messages is disabled"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(tag = "network_type")]
 pub enum SubnetConfig {
     #[serde(rename = "fvm")]
@@ -62,6 +141,59 @@ impl Subnet {
         }
     }
 
+    /// The network the node of an fvm subnet is pinned to in the config, if any.
+    pub fn expected_network_name(&self) -> Option<&String> {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.expected_network_name.as_ref(),
+            SubnetConfig::Fevm(_) => None,
+        }
+    }
+
+    /// How the json rpc requests to the node are retried, the default policy for fevm subnets,
+    /// whose config does not set it.
+    pub fn retry(&self) -> RetryConfig {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.retry.clone(),
+            SubnetConfig::Fevm(_) => RetryConfig::default(),
+        }
+    }
+
+    /// How the connections to the node are pooled, the default for fevm subnets, whose config
+    /// does not set it.
+    pub fn connections(&self) -> ConnectionConfig {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.connections.clone(),
+            SubnetConfig::Fevm(_) => ConnectionConfig::default(),
+        }
+    }
+
+    /// How the messages sent to the node are waited for, the default for fevm subnets, whose
+    /// config does not set it.
+    pub fn message_wait(&self) -> MessageWaitConfig {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.message_wait.clone(),
+            SubnetConfig::Fevm(_) => MessageWaitConfig::default(),
+        }
+    }
+
+    /// The websocket endpoint of the node set in the config, if any.
+    pub fn rpc_ws(&self) -> Option<&Url> {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.jsonrpc_api_ws.as_ref(),
+            SubnetConfig::Fevm(_) => None,
+        }
+    }
+
+    /// Returns the same subnet reached through another endpoint, e.g. an archival node.
+    pub fn with_rpc_http(&self, url: Url) -> Subnet {
+        let mut subnet = self.clone();
+        match &mut subnet.config {
+            SubnetConfig::Fvm(s) => s.jsonrpc_api_http = url,
+            SubnetConfig::Fevm(s) => s.provider_http = url,
+        }
+        subnet
+    }
+
     pub fn gateway_addr(&self) -> Address {
         match &self.config {
             SubnetConfig::Fvm(s) => s.gateway_addr,
@@ -75,25 +207,477 @@ impl Subnet {
             SubnetConfig::Fevm(s) => s.accounts.clone(),
         }
     }
+
+    /// Who signs the messages sent to the subnet. Only fvm subnets can delegate signing to the
+    /// node, evm transactions are always signed by the agent.
+    pub fn fvm_signer(&self) -> FvmSigner {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.signer,
+            SubnetConfig::Fevm(_) => FvmSigner::Agent,
+        }
+    }
+
+    /// How strictly the responses of the node of the subnet are decoded. Only the responses of
+    /// fvm nodes are decoded by the agent, evm ones are decoded by the provider.
+    pub fn decoding(&self) -> Decoding {
+        match &self.config {
+            SubnetConfig::Fvm(s) => s.decoding,
+            SubnetConfig::Fevm(_) => Decoding::Lenient,
+        }
+    }
+
+    pub fn block_time(&self) -> Option<Duration> {
+        self.block_time_secs.map(Duration::from_secs)
+    }
+
+    pub fn checkpoint_poll_interval(&self) -> Option<Duration> {
+        self.checkpoint_period_poll_secs.map(Duration::from_secs)
+    }
+
+    /// The extra headers of the requests to the node of the subnet. The headers are validated
+    /// when the config is read, the invalid ones set otherwise are left out.
+    pub fn http_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            match (HeaderName::from_str(name), HeaderValue::from_str(value)) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => tracing::warn!("invalid http header {name:} of subnet {}, ignored", self.id),
+            }
+        }
+        headers
+    }
 }
 
-/// The FVM subnet config parameters
+/// The gas limit policies of the messages sent to a subnet, by type of operation. Operators can
+/// tune them to trade off messages failing for running out of gas against overpaying for it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct GasConfig {
+    /// Submission of checkpoints.
+    #[serde(default)]
+    pub checkpoint: GasLimitPolicy,
+    /// Funds, releases, propagations and any other cross-net message.
+    #[serde(default)]
+    pub cross_msg: GasLimitPolicy,
+    /// Transfers of value within the subnet.
+    #[serde(default)]
+    pub send: GasLimitPolicy,
+    /// Any other message, like creating, joining or leaving subnets.
+    #[serde(default)]
+    pub other: GasLimitPolicy,
+    /// The factor the estimated fee cap of all the messages is multiplied by, e.g. to get them
+    /// included when the estimation of the node is too low during congestion.
+    #[serde(default = "default_gas_multiplier")]
+    pub fee_cap_multiplier: f64,
+    /// The factor the estimated gas premium of all the messages is multiplied by.
+    #[serde(default = "default_gas_multiplier")]
+    pub premium_multiplier: f64,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            checkpoint: GasLimitPolicy::default(),
+            cross_msg: GasLimitPolicy::default(),
+            send: GasLimitPolicy::default(),
+            other: GasLimitPolicy::default(),
+            fee_cap_multiplier: default_gas_multiplier(),
+            premium_multiplier: default_gas_multiplier(),
+        }
+    }
+}
+
+/// How the estimated gas limit of a message is adjusted before sending it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct GasLimitPolicy {
+    /// The factor the estimated gas limit is multiplied by.
+    #[serde(default = "default_gas_multiplier")]
+    pub multiplier: f64,
+    /// The maximum gas limit of the messages, the adjusted estimation is capped to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_limit: Option<u64>,
+}
+
+fn default_gas_multiplier() -> f64 {
+    1.0
+}
+
+impl Default for GasLimitPolicy {
+    fn default() -> Self {
+        Self {
+            multiplier: default_gas_multiplier(),
+            max_limit: None,
+        }
+    }
+}
+
+impl GasLimitPolicy {
+    /// Returns the gas limit to use for a message whose estimated gas limit is `estimated`.
+    pub fn apply(&self, estimated: u64) -> u64 {
+        let limit = (estimated as f64 * self.multiplier).ceil() as u64;
+        match self.max_limit {
+            Some(max) => limit.min(max),
+            None => limit,
+        }
+    }
+}
+
+/// How the requests to the node of a subnet are retried when they fail to reach it, e.g. when the
+/// node restarts or a proxy in front of it drops the connection. Requests the node answered with
+/// an error are not retried.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RetryConfig {
+    /// The number of attempts of a request, including the first one. `1` disables retries.
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled at every retry.
+    #[serde(default = "default_retry_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// The maximum delay between two attempts.
+    #[serde(default = "default_retry_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// The maximum deviation of a delay, as a fraction of it, so that the agents sharing a node
+    /// do not retry in lockstep.
+    #[serde(default = "default_retry_jitter")]
+    pub jitter: f64,
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_retry_max_backoff_ms() -> u64 {
+    10_000
+}
+
+fn default_retry_jitter() -> f64 {
+    0.2
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            initial_backoff_ms: default_retry_initial_backoff_ms(),
+            max_backoff_ms: default_retry_max_backoff_ms(),
+            jitter: default_retry_jitter(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// A policy that makes a single attempt.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+    /// The delay before the attempt following the failed attempt number `attempt`, 1-based,
+    /// before jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(32);
+        let backoff = self.initial_backoff_ms.saturating_mul(1u64 << exp);
+        Duration::from_millis(backoff.min(self.max_backoff_ms))
+    }
+
+    /// [`Self::backoff`] with a random deviation of up to `jitter`.
+    pub fn jittered_backoff(&self, attempt: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 + thread_rng().gen_range(-jitter..=jitter);
+        self.backoff(attempt).mul_f64(factor)
+    }
+}
+
+/// How the connections to the node of a subnet are pooled. All the clients of the node share its
+/// connections, which are kept alive between requests.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionConfig {
+    /// The maximum number of requests in flight to the node, and of idle connections kept open.
+    /// Unbounded if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+    /// How long an idle connection is kept open before it is closed.
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    90
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: None,
+            idle_timeout_secs: default_idle_timeout_secs(),
+        }
+    }
+}
+
+/// How long the agent waits for the messages it sends to a subnet to be executed, e.g. before the
+/// result of a fund or a join is returned to the caller.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct MessageWaitConfig {
+    /// The number of epochs a message must be buried under after its execution, against reorgs.
+    #[serde(default)]
+    pub confidence: u64,
+    /// How long a message is waited for before giving up with a timeout error.
+    #[serde(default = "default_message_wait_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_message_wait_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for MessageWaitConfig {
+    fn default() -> Self {
+        Self {
+            confidence: 0,
+            timeout_secs: default_message_wait_timeout_secs(),
+        }
+    }
+}
+
+impl MessageWaitConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+/// The checkpointing and cross-net features the agent runs for a subnet. All of them are enabled
+/// by default, but they can be disabled independently for hierarchies that delegate one of the
+/// directions to other infrastructure.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubnetFeatures {
+    /// Submission of the bottom-up checkpoints of the subnet to its parent.
+    #[serde(default = "default_feature_enabled")]
+    pub bottom_up: bool,
+    /// Votes in the subnet on the top-down checkpoints of its parent.
+    #[serde(default = "default_feature_enabled")]
+    pub top_down: bool,
+    /// Propagation of the cross-net messages in the postbox of the subnet.
+    #[serde(default = "default_feature_enabled")]
+    pub propagate: bool,
+}
+
+fn default_feature_enabled() -> bool {
+    true
+}
+
+impl Default for SubnetFeatures {
+    fn default() -> Self {
+        Self {
+            bottom_up: default_feature_enabled(),
+            top_down: default_feature_enabled(),
+            propagate: default_feature_enabled(),
+        }
+    }
+}
+
+impl Display for SubnetFeatures {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let status = |enabled: bool| if enabled { "enabled" } else { "disabled" };
+        write!(
+            f,
+            "bottom-up checkpoints {}, top-down checkpoints {}, propagation {}",
+            status(self.bottom_up),
+            status(self.top_down),
+            status(self.propagate)
+        )
+    }
+}
+
+/// How the bottom-up checkpoints of a subnet are signed by its validators, on top of the signature
+/// of the message submitting them.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckpointSigning {
+    /// The checkpoints are not signed, the signature of their message is enough.
+    #[default]
+    None,
+    /// Each checkpoint is signed with the key of the validator submitting it, either secp256k1
+    /// or BLS.
+    Validator,
+    /// Each checkpoint is signed by all the validators of the subnet managed by the agent, whose
+    /// keys must be BLS keys, and their signatures are aggregated into a single one.
+    BlsAggregate,
+}
+
+/// The policy deciding whether the validators of the agent vote a checkpoint of the subnet, in
+/// both directions, once they have not voted it yet.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VotePolicyConfig {
+    #[serde(default)]
+    pub kind: VotePolicyKind,
+    /// The number of epochs the head of the subnet a checkpoint comes from must be past its
+    /// epoch to vote it with the `strict-local` policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finality: Option<ChainEpoch>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VotePolicyKind {
+    /// Votes every checkpoint as soon as its window is open.
+    #[default]
+    Default,
+    /// Only votes once at least half of the other validators of the subnet have voted.
+    FollowMajority,
+    /// Only votes checkpoints that are final on the node of the subnet they come from.
+    StrictLocal,
+    /// Only votes once an operator approves the vote through the JSON-RPC API.
+    ManualApproval,
+}
+
+/// The peer agent the checkpoint votes of a subnet are co-signed with. Both agents configure each
+/// other as peer: the agent submitting the votes asks its peer to co-sign them, and the peer only
+/// co-signs the requests signed by the agent.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CoSigningConfig {
+    /// The JSON-RPC endpoint of the peer agent.
+    pub peer_url: Url,
+    /// The eth address of the key the peer agent signs its messages with.
+    pub peer_address: ethers::types::Address,
+    /// The eth address of the key in the evm keystore of this agent its messages are signed with.
+    pub signer: ethers::types::Address,
+    /// The bearer token sent to the json rpc api of the peer agent, when it requires auth. The
+    /// token needs the `write` permission to co-sign votes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// The maximum delay before the checkpoint votes of an epoch are submitted. A poll of a manager
+/// votes a few epochs, each delayed once, and must stay well within the time the watchdog allows
+/// it.
+pub const MAX_SUBMISSION_DELAY_SECS: u64 = 120;
+
+/// A window the submission of the checkpoint votes of each epoch of a subnet is delayed by a
+/// random time in. Agents running validators of the same subnet would otherwise all submit their
+/// votes in the same block once a checkpoint is reached, and the votes executed after the quorum
+/// was crossed are rejected by the actor, wasting their gas. The window is checked when the config
+/// is read, and votes are not delayed while a manager catches up.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubmissionDelayConfig {
+    #[serde(default)]
+    pub min_secs: u64,
+    /// At most [`MAX_SUBMISSION_DELAY_SECS`].
+    pub max_secs: u64,
+}
+
+impl SubmissionDelayConfig {
+    /// A random delay in the window, the window being empty if its bounds are inverted.
+    pub fn sample(&self) -> Duration {
+        let max = self.max_secs.min(MAX_SUBMISSION_DELAY_SECS);
+        let min = self.min_secs.min(max);
+        Duration::from_millis(thread_rng().gen_range(min * 1000..=max * 1000))
+    }
+}
+
+/// The agents federated to run the checkpointing of a subnet whose parent is only reached by
+/// one of them. The agent next to the parent notifies the agents next to the subnet whenever the
+/// parent reaches the epoch of a top-down checkpoint, and those only poll the parent for their
+/// top-down checkpoints when notified, instead of on every block.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FederationConfig {
+    /// The JSON-RPC endpoints of the agents to notify, set in the agent next to the parent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notify: Vec<Url>,
+    /// The address of the identity key of the agent whose notifications are accepted, set in the
+    /// agents next to the subnet. The notifications are signed with the identity key of the
+    /// notifying agent, see [`crate::server::status`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_peer: Option<ethers::types::Address>,
+}
+
+/// Where the test funds of a subnet come from.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum FaucetConfig {
+    /// An http faucet, like the one of calibration: the address to fund is posted to `url` as
+    /// `{"address": "<address>"}`.
+    Http { url: Url },
+    /// A faucet contract deployed in the subnet, whose `drip(address)` method sends test funds
+    /// to the address. It is called with an account of the subnet.
+    Contract { address: ethers::types::Address },
+}
+
+/// The FVM subnet config parameters
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct FVMSubnet {
     #[serde(deserialize_with = "deserialize_address_from_str")]
     #[serde(serialize_with = "serialize_address_to_str")]
     pub gateway_addr: Address,
     pub jsonrpc_api_http: Url,
+    /// The websocket endpoint of the node, to be notified of new heads, derived from
+    /// `jsonrpc_api_http` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonrpc_api_ws: Option<Url>,
+    /// The name of the network the node must serve, as returned by `StateNetworkName`. Messages
+    /// are not sent to a node serving another network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_network_name: Option<String>,
+    /// How the requests to the node are retried on transport errors, see [`RetryConfig`].
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// How the connections to the node are pooled, see [`ConnectionConfig`].
+    #[serde(default)]
+    pub connections: ConnectionConfig,
+    /// How the messages sent to the node are waited for, see [`MessageWaitConfig`].
+    #[serde(default)]
+    pub message_wait: MessageWaitConfig,
     pub auth_token: Option<String>,
     #[serde(deserialize_with = "deserialize_accounts", default)]
     #[serde(serialize_with = "serialize_accounts")]
     pub accounts: Vec<Address>,
+    /// Where the messages of the accounts are signed, see [`FvmSigner`].
+    #[serde(default)]
+    pub signer: FvmSigner,
+    /// How strictly the responses of the node are decoded, see [`Decoding`].
+    #[serde(default)]
+    pub decoding: Decoding,
+}
+
+/// How strictly the json rpc responses of a node are decoded.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Decoding {
+    /// Fields the agent does not know are ignored and missing optional fields are left empty, so
+    /// that the agent keeps working with nodes that add fields to their responses.
+    #[default]
+    Lenient,
+    /// Responses with fields the agent does not know fail to decode, to notice as soon as a node
+    /// upgrade changes the format of its responses.
+    Strict,
+}
+
+/// The signer of the messages sent to an fvm subnet.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FvmSigner {
+    /// Messages are signed with the keys in the agent wallet and pushed with `MpoolPush`, so
+    /// the keys never need to be imported into the node.
+    #[default]
+    Agent,
+    /// Messages are signed by the node with `MpoolPushMessage`, which requires the keys of the
+    /// accounts to be in the keystore of the node.
+    Node,
 }
 
 /// The EVM subnet config parameters
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct EVMSubnet {
     pub provider_http: Url,
+    /// The chain id the node must serve and the transactions are signed for, derived from the
+    /// id of the subnet if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
     pub auth_token: Option<String>,
     #[serde(deserialize_with = "deserialize_eth_address_from_str")]
     #[serde(serialize_with = "serialize_eth_address_to_str")]
@@ -2,17 +2,26 @@
 // SPDX-License-Identifier: MIT
 use std::io::Write;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 
 use fvm_shared::address::Address;
-use indoc::formatdoc;
+use indoc::{formatdoc, indoc};
 use ipc_sdk::subnet_id::SubnetID;
 use primitives::EthAddress;
 use tempfile::NamedTempFile;
 use url::Url;
 
-use crate::config::{Config, ReloadableConfig};
+use crate::config::subnet::{
+    CheckpointSigning, ConnectionConfig, FaucetConfig, FvmSigner, MessageWaitConfig, RetryConfig,
+    SubmissionDelayConfig, SubnetConfig, SubnetMode, VotePolicyKind, MAX_SUBMISSION_DELAY_SECS,
+};
+use crate::config::{
+    json_rpc_methods, AlertMetric, Config, LogFormat, LogRotation, Permission, ReloadableConfig,
+    RunMode, StateSchema, Subnet,
+};
 
 // Arguments for the config's fields
 const SERVER_JSON_RPC_ADDR: &str = "127.0.0.1:3030";
@@ -85,6 +94,62 @@ async fn reload_works() {
     );
 }
 
+#[tokio::test]
+async fn reload_status() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(config_str().as_bytes()).unwrap();
+    let path = file.path().to_str().unwrap().to_string();
+
+    let h = ReloadableConfig::new(path).unwrap();
+    assert!(h.last_reload().is_none());
+
+    h.reload().await.unwrap();
+    assert!(h.last_reload().unwrap().error.is_none());
+
+    // a failed reload is reported, and the previous config kept
+    std::fs::write(file.path(), "not a config").unwrap();
+    assert!(h.reload().await.is_err());
+    assert!(h.last_reload().unwrap().error.is_some());
+    assert_eq!(
+        h.get_config().server.json_rpc_address,
+        SocketAddr::from_str(SERVER_JSON_RPC_ADDR).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn reload_changed() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(config_str().as_bytes()).unwrap();
+    let path = file.path().to_str().unwrap().to_string();
+
+    let h = ReloadableConfig::new(path).unwrap();
+    let mut rx = h.new_subscriber();
+    // the file is written without changes
+    assert!(!h.reload_changed(|_| Ok(())).await.unwrap());
+    assert!(rx.try_recv().is_err());
+    assert!(h.last_reload().is_none());
+
+    // a config rejected by the validation is not applied
+    std::fs::write(file.path(), config_str_diff_addr()).unwrap();
+    assert!(h
+        .reload_changed(|_| Err(anyhow::anyhow!("invalid")))
+        .await
+        .is_err());
+    assert!(h.last_reload().unwrap().error.is_some());
+    assert_eq!(
+        h.get_config().server.json_rpc_address,
+        SocketAddr::from_str(SERVER_JSON_RPC_ADDR).unwrap()
+    );
+
+    assert!(h.reload_changed(|_| Ok(())).await.unwrap());
+    assert!(rx.try_recv().is_ok());
+    assert!(h.last_reload().unwrap().error.is_none());
+    assert_ne!(
+        h.get_config().server.json_rpc_address,
+        SocketAddr::from_str(SERVER_JSON_RPC_ADDR).unwrap()
+    );
+}
+
 #[test]
 fn check_server_config() {
     let config = read_config().server;
@@ -93,6 +158,129 @@ fn check_server_config() {
         SocketAddr::from_str(SERVER_JSON_RPC_ADDR).unwrap(),
         "invalid server rpc address"
     );
+    assert!(config.approvals.is_none());
+    assert!(config.debug_tap.is_none());
+    assert!(config.notifications.is_none());
+    assert!(config.auth.is_none());
+    assert_eq!(config.shutdown_timeout(), Duration::from_secs(60));
+    assert!(!config.watch_config);
+}
+
+#[test]
+fn check_approval_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [server.approvals]
+            value_threshold = 100.5
+            kill = true
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let approvals = config.server.approvals.unwrap();
+    assert_eq!(approvals.value_threshold, Some(100.5));
+    assert!(approvals.collateral_threshold.is_none());
+    assert!(approvals.kill);
+    assert_eq!(approvals.expiry_secs, 3600);
+}
+
+#[test]
+fn check_auth_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [[server.auth.tokens]]
+            token = "secret"
+            permission = "admin"
+
+            [server.auth.methods]
+            ipc_fund = "admin"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let auth = config.server.auth.unwrap();
+    assert_eq!(auth.tokens.len(), 1);
+    assert_eq!(auth.tokens[0].token, "secret");
+    assert_eq!(auth.tokens[0].permission, Permission::Admin);
+    assert_eq!(auth.methods[json_rpc_methods::FUND], Permission::Admin);
+    assert!(auth.anonymous_read);
+}
+
+#[test]
+fn check_metrics_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [server.metrics]
+            listen_address = "0.0.0.0:9184"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let metrics = config.server.metrics.unwrap();
+    assert_eq!(
+        metrics.listen_address,
+        SocketAddr::from_str("0.0.0.0:9184").unwrap()
+    );
+    assert_eq!(metrics.balance_poll_interval(), Duration::from_secs(60));
+    assert!(read_config().server.metrics.is_none());
+}
+
+#[test]
+fn check_debug_tap_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [server.debug_tap]
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    assert_eq!(config.server.debug_tap.unwrap().max_bytes, 1024 * 1024);
+}
+
+#[test]
+fn check_notifications_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [server.notifications]
+            webhook_url = "https://alerts.example.com/ipc"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.server.notifications.unwrap().webhook_url.as_str(),
+        "https://alerts.example.com/ipc"
+    );
 }
 
 #[test]
@@ -109,6 +297,7 @@ fn check_subnets_config() {
     );
     assert_eq!(*root.rpc_http(), Url::from_str(JSONRPC_API_HTTP).unwrap());
     assert_eq!(root.auth_token().as_ref().unwrap(), ROOT_AUTH_TOKEN);
+    assert_eq!(root.fvm_signer(), FvmSigner::Agent);
 
     let child_id = SubnetID::from_str(CHILD_ID).unwrap();
     let child = &config[&child_id];
@@ -129,6 +318,335 @@ fn check_subnets_config() {
     );
 }
 
+/// Parses a config with a single fvm subnet, `ROOT_ID`, whose entry has the fields and tables of
+/// `entry` and whose `[subnets.config]` table has the extra fields and tables of `config`.
+fn parse_subnet(entry: &str, config: &str) -> anyhow::Result<Subnet> {
+    let mut parsed = Config::from_toml_str(&formatdoc!(
+        r#"
+        [server]
+        json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+        [[subnets]]
+        id = "{ROOT_ID}"
+        network_name = "root"
+        {entry}
+
+        [subnets.config]
+        network_type = "fvm"
+        gateway_addr = "{GATEWAY_ADDR}"
+        jsonrpc_api_http = "{JSONRPC_API_HTTP}"
+        {config}
+        "#
+    ))?;
+    Ok(parsed
+        .subnets
+        .remove(&SubnetID::from_str(ROOT_ID).unwrap())
+        .unwrap())
+}
+
+#[test]
+fn check_subnet_fields() {
+    let cases: &[(&str, &str, fn(&Subnet))] = &[
+        ("", "", |s| {
+            assert_eq!(s.mode, SubnetMode::Validate);
+            assert_eq!(s.checkpoint_poll_interval(), None);
+            assert!(s.features.bottom_up && s.features.top_down && s.features.propagate);
+            assert_eq!(s.checkpoint_signing, CheckpointSigning::None);
+            assert!(s.co_signing.is_none());
+            assert!(s.submission_delay.is_none());
+            assert!(s.federation.is_none());
+            assert!(s.faucet.is_none());
+            assert!(s.headers.is_empty());
+            assert!(s.expected_network_name().is_none());
+            assert_eq!(s.retry(), RetryConfig::default());
+            assert_eq!(s.message_wait(), MessageWaitConfig::default());
+            assert_eq!(s.connections(), ConnectionConfig::default());
+        }),
+        (
+            indoc!(
+                r#"
+                [subnets.gas]
+                fee_cap_multiplier = 1.2
+                [subnets.gas.checkpoint]
+                multiplier = 1.5
+                max_limit = 1000
+                [subnets.gas.send]
+                max_limit = 500
+                "#
+            ),
+            "",
+            |s| {
+                assert_eq!(s.gas.checkpoint.apply(600), 900);
+                assert_eq!(s.gas.checkpoint.apply(800), 1000);
+                assert_eq!(s.gas.send.apply(800), 500);
+                assert_eq!(s.gas.other.apply(800), 800);
+                assert_eq!(s.gas.fee_cap_multiplier, 1.2);
+                assert_eq!(s.gas.premium_multiplier, 1.0);
+            },
+        ),
+        (r#"mode = "observe""#, "", |s| {
+            assert_eq!(s.mode, SubnetMode::Observe);
+            assert!(s.mode.ensure_can_submit(&s.id).is_err());
+            assert!(SubnetMode::default().ensure_can_submit(&s.id).is_ok());
+        }),
+        ("checkpoint_period_poll_secs = 30", "", |s| {
+            assert_eq!(s.checkpoint_poll_interval(), Some(Duration::from_secs(30)))
+        }),
+        ("[subnets.features]\ntop_down = false", "", |s| {
+            assert!(s.features.bottom_up && !s.features.top_down && s.features.propagate)
+        }),
+        (
+            "[subnets.vote_policy]\nkind = \"strict-local\"\nfinality = 30",
+            "",
+            |s| {
+                assert_eq!(s.vote_policy.kind, VotePolicyKind::StrictLocal);
+                assert_eq!(s.vote_policy.finality, Some(30));
+            },
+        ),
+        (r#"checkpoint_signing = "bls-aggregate""#, "", |s| {
+            assert_eq!(s.checkpoint_signing, CheckpointSigning::BlsAggregate)
+        }),
+        (
+            "[subnets.faucet]\nkind = \"contract\"\naddress = \"0x6be1ccf648c74800380d0520d797a170c808b624\"",
+            "",
+            |s| {
+                assert_eq!(
+                    s.faucet,
+                    Some(FaucetConfig::Contract {
+                        address: ethers::types::Address::from_str(ETH_ADDRESS).unwrap()
+                    })
+                )
+            },
+        ),
+        (
+            "[subnets.faucet]\nkind = \"http\"\nurl = \"http://127.0.0.1:8080/fund\"",
+            "",
+            |s| {
+                assert_eq!(
+                    s.faucet,
+                    Some(FaucetConfig::Http {
+                        url: Url::parse("http://127.0.0.1:8080/fund").unwrap()
+                    })
+                )
+            },
+        ),
+        (
+            indoc!(
+                r#"
+                [subnets.co_signing]
+                peer_url = "http://10.0.0.2:3030/json_rpc"
+                peer_address = "0x6be1ccf648c74800380d0520d797a170c808b624"
+                signer = "0x2a9f5d2e2c9c2c1b0a3e4f8b1f04e1b0f4c5d6e7"
+                token = "peer"
+                "#
+            ),
+            "",
+            |s| {
+                let co_signing = s.co_signing.as_ref().unwrap();
+                assert_eq!(co_signing.token.as_deref(), Some("peer"));
+                assert_eq!(
+                    co_signing.peer_url.as_str(),
+                    "http://10.0.0.2:3030/json_rpc"
+                );
+                assert_eq!(
+                    co_signing.peer_address,
+                    ethers::types::Address::from_str(ETH_ADDRESS).unwrap()
+                );
+            },
+        ),
+        ("[subnets.submission_delay]\nmax_secs = 20", "", |s| {
+            let delay = s.submission_delay.as_ref().unwrap();
+            assert_eq!(delay.min_secs, 0);
+            assert!(delay.sample() <= Duration::from_secs(20));
+        }),
+        (
+            "[subnets.federation]\ntrusted_peer = \"0x6be1ccf648c74800380d0520d797a170c808b624\"",
+            "",
+            |s| {
+                let federation = s.federation.as_ref().unwrap();
+                assert!(federation.notify.is_empty());
+                assert_eq!(
+                    federation.trusted_peer,
+                    Some(ethers::types::Address::from_str(ETH_ADDRESS).unwrap())
+                );
+            },
+        ),
+        (
+            "[subnets.headers]\nCF-Access-Client-Id = \"client.access\"\nx-api-key = \"secret\"",
+            "",
+            |s| {
+                let headers = s.http_headers();
+                assert_eq!(headers.len(), 2);
+                assert_eq!(headers["cf-access-client-id"], "client.access");
+                assert_eq!(headers["x-api-key"], "secret");
+            },
+        ),
+        ("", r#"expected_network_name = "/r123""#, |s| {
+            assert_eq!(s.expected_network_name(), Some(&ROOT_ID.to_string()))
+        }),
+        (
+            "",
+            "[subnets.config.retry]\nmax_attempts = 5\ninitial_backoff_ms = 100\nmax_backoff_ms = 1000",
+            |s| {
+                let retry = s.retry();
+                assert_eq!(retry.max_attempts, 5);
+                assert_eq!(retry.jitter, 0.2);
+                assert_eq!(retry.backoff(1), Duration::from_millis(100));
+                assert_eq!(retry.backoff(3), Duration::from_millis(400));
+                assert_eq!(retry.backoff(5), Duration::from_millis(1000));
+                assert_eq!(retry.backoff(100), Duration::from_millis(1000));
+                let jittered = retry.jittered_backoff(2);
+                assert!(
+                    jittered >= Duration::from_millis(160) && jittered <= Duration::from_millis(240)
+                );
+            },
+        ),
+        ("", "[subnets.config.message_wait]\nconfidence = 5", |s| {
+            assert_eq!(s.message_wait().confidence, 5);
+            assert_eq!(s.message_wait().timeout(), Duration::from_secs(300));
+        }),
+        ("", "[subnets.config.connections]\nmax_connections = 16", |s| {
+            assert_eq!(s.connections().max_connections, Some(16));
+            assert_eq!(s.connections().idle_timeout_secs, 90);
+        }),
+    ];
+
+    for (entry, config, check) in cases {
+        check(&parse_subnet(entry, config).unwrap());
+    }
+}
+
+#[test]
+fn check_invalid_subnet_fields() {
+    let cases = [
+        ("[subnets.headers]\n\"bad header\" = \"value\"", ""),
+        ("[subnets.headers]\nx-api-key = \"line\\nbreak\"", ""),
+        (r#"mode = "relay""#, ""),
+        (r#"checkpoint_signing = "schnorr""#, ""),
+        ("[subnets.submission_delay]\nmax_secs = 600", ""),
+        (
+            "[subnets.submission_delay]\nmin_secs = 30\nmax_secs = 20",
+            "",
+        ),
+    ];
+
+    for (entry, config) in cases {
+        assert!(
+            parse_subnet(entry, config).is_err(),
+            "parsed {entry:?} {config:?}"
+        );
+    }
+}
+
+#[test]
+fn check_submission_delay_sample() {
+    let delay = SubmissionDelayConfig {
+        min_secs: 5,
+        max_secs: 5,
+    };
+    assert_eq!(delay.sample(), Duration::from_secs(5));
+    let delay = SubmissionDelayConfig {
+        min_secs: 10,
+        max_secs: 2,
+    };
+    assert_eq!(delay.sample(), Duration::from_secs(2));
+    let delay = SubmissionDelayConfig {
+        min_secs: MAX_SUBMISSION_DELAY_SECS + 10,
+        max_secs: MAX_SUBMISSION_DELAY_SECS * 2,
+    };
+    assert_eq!(
+        delay.sample(),
+        Duration::from_secs(MAX_SUBMISSION_DELAY_SECS)
+    );
+}
+#[test]
+fn check_run_mode() {
+    let mut config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [[subnets]]
+            id = "{ROOT_ID}"
+            network_name = "root"
+
+            [subnets.features]
+            propagate = false
+
+            [subnets.config]
+            network_type = "fvm"
+            gateway_addr = "{GATEWAY_ADDR}"
+            jsonrpc_api_http = "{JSONRPC_API_HTTP}"
+            accounts = ["{ACCOUNT_ADDRESS}"]
+
+            [[subnets]]
+            id = "{CHILD_ID}"
+            network_name = "child"
+
+            [subnets.features]
+            top_down = false
+
+            [subnets.config]
+            network_type = "fvm"
+            gateway_addr = "{GATEWAY_ADDR}"
+            jsonrpc_api_http = "{JSONRPC_API_HTTP}"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+    let root = SubnetID::from_str(ROOT_ID).unwrap();
+
+    assert_eq!(RunMode::from_str("relayer").unwrap(), RunMode::Relayer);
+    assert!(RunMode::Full.validate(&config).is_ok());
+    // the bottom-up checkpoints of the child are voted with the accounts of the root
+    assert!(RunMode::Checkpointer.validate(&config).is_ok());
+    // the only subnet with accounts does not propagate
+    assert!(RunMode::Relayer.validate(&config).is_err());
+
+    config.subnets.get_mut(&root).unwrap().features.propagate = true;
+    assert!(RunMode::Relayer.validate(&config).is_ok());
+
+    config.remove_subnet(&root);
+    assert!(RunMode::Checkpointer.validate(&config).is_err());
+
+    assert!(!RunMode::Relayer.serves(json_rpc_methods::CO_SIGN_VOTE));
+    assert!(RunMode::Relayer.serves(json_rpc_methods::PROPAGATE));
+    assert!(!RunMode::Checkpointer.serves(json_rpc_methods::PROPAGATE));
+    assert!(RunMode::Full.serves(json_rpc_methods::PROPAGATE));
+}
+
+#[test]
+fn check_pinned_chain_id() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [[subnets]]
+            id = "{CHILD_ID}"
+            network_name = "child"
+
+            [subnets.config]
+            network_type = "fevm"
+            provider_http = "{PROVIDER_HTTP}"
+            chain_id = 314159
+            registry_addr = "{ETH_ADDRESS}"
+            gateway_addr = "{ETH_ADDRESS}"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    match &config.subnets[&SubnetID::from_str(CHILD_ID).unwrap()].config {
+        SubnetConfig::Fevm(s) => assert_eq!(s.chain_id, Some(314159)),
+        _ => panic!("not a fevm subnet"),
+    }
+}
+
 fn config_str() -> String {
     formatdoc!(
         r#"
@@ -196,3 +714,198 @@ fn config_str_diff_addr() -> String {
 fn read_config() -> Config {
     Config::from_toml_str(config_str().as_str()).unwrap()
 }
+
+#[test]
+fn check_log_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [log]
+            file = "/var/log/ipc-agent/agent.log"
+            max_size_mb = 100
+            format = "json"
+
+            [log.levels]
+            "ipc_agent::checkpoint" = "debug"
+            hyper = "warn"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let log = config.log.unwrap();
+    assert_eq!(log.format, LogFormat::Json);
+    assert_eq!(
+        log.filter_directives(),
+        "info,hyper=warn,ipc_agent::checkpoint=debug"
+    );
+    assert_eq!(log.max_size(), Some(100 * 1024 * 1024));
+    assert_eq!(log.rotation, LogRotation::Daily);
+    assert_eq!(log.max_files, 7);
+    assert!(read_config().log.is_none());
+}
+
+#[test]
+fn check_alert_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [[alerts]]
+            name = "low-balance"
+            metric = "balance"
+            subnet = "/r1234"
+            below = 10.5
+
+            [[alerts]]
+            name = "checkpoint-errors"
+            metric = "error_rate"
+            above = 2
+
+            [[alerts]]
+            name = "validators-down"
+            metric = "quorum_stall"
+            above = 50
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    assert_eq!(config.alerts.len(), 3);
+    let balance = &config.alerts[0];
+    assert_eq!(balance.metric, AlertMetric::Balance);
+    assert_eq!(balance.subnet, Some(String::from("/r1234")));
+    assert!(balance.account.is_none());
+    assert!(balance.breached(10.0) && !balance.breached(10.5));
+    let errors = &config.alerts[1];
+    assert_eq!(errors.metric, AlertMetric::ErrorRate);
+    assert!(errors.breached(2.5) && !errors.breached(1.0));
+    assert_eq!(config.alerts[2].metric, AlertMetric::QuorumStall);
+    assert!(read_config().alerts.is_empty());
+}
+
+#[test]
+fn check_state_schemas_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [state_schemas]
+            bafk2bzacecgbd2ztifn4ppptyo2l4y4hmmxnwbokdvzy4y2xmdg7z5ffdmk3k = "gateway"
+            bafk2bzaceaxmmpfw4oytsupcsmr6mgtvbr5gdxb5ejz7exmi3ydqpdaxmowdg = "multisig"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    assert_eq!(config.state_schemas.len(), 2);
+    assert_eq!(
+        config.state_schemas["bafk2bzacecgbd2ztifn4ppptyo2l4y4hmmxnwbokdvzy4y2xmdg7z5ffdmk3k"],
+        StateSchema::Gateway
+    );
+    assert!(read_config().state_schemas.is_empty());
+
+    // unknown schemas are rejected
+    assert!(Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [state_schemas]
+            bafk2bzacecgbd2ztifn4ppptyo2l4y4hmmxnwbokdvzy4y2xmdg7z5ffdmk3k = "miner"
+            "#
+        )
+        .as_str(),
+    )
+    .is_err());
+}
+
+#[test]
+fn check_data_dir_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            data_dir = "/var/lib/ipc-agent"
+
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    assert_eq!(config.data_dir, Some(PathBuf::from("/var/lib/ipc-agent")));
+    assert!(read_config().data_dir.is_none());
+}
+
+#[test]
+fn check_validate_config() {
+    let validation = read_config().validate();
+    assert!(!validation.valid);
+    assert_eq!(validation.subnets.len(), 2);
+
+    let root = &validation.subnets[0];
+    assert_eq!(root.subnet, ROOT_ID);
+    assert!(root.is_valid());
+    assert!(root.warnings.is_empty());
+    assert!(root.reachable.is_none());
+
+    // the child uses the same eth address for its registry, its gateway and both its accounts
+    let child = &validation.subnets[1];
+    assert_eq!(child.subnet, CHILD_ID);
+    assert_eq!(child.errors.len(), 1);
+    assert_eq!(child.errors[0].field, "config.registry_addr");
+    assert_eq!(child.warnings.len(), 1);
+    assert_eq!(child.warnings[0].field, "config.accounts");
+
+    let config_str = |mode: &str| {
+        formatdoc!(
+            r#"
+            [server]
+            json_rpc_address = "{SERVER_JSON_RPC_ADDR}"
+
+            [[subnets]]
+            id = "/r0/f0100"
+            network_name = "orphan"
+            mode = "{mode}"
+
+            [subnets.config]
+            network_type = "fvm"
+            gateway_addr = "{ACCOUNT_ADDRESS}"
+            jsonrpc_api_http = "{JSONRPC_API_HTTP}"
+            "#
+        )
+    };
+    let validation = Config::from_toml_str(&config_str("validate"))
+        .unwrap()
+        .validate();
+    assert!(!validation.valid);
+    let subnet = &validation.subnets[0];
+    let fields = subnet
+        .errors
+        .iter()
+        .map(|i| i.field.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(fields, vec!["id", "config.gateway_addr", "config.accounts"]);
+    // its parent is not in the config
+    assert_eq!(subnet.warnings.len(), 1);
+    assert_eq!(subnet.warnings[0].field, "id");
+
+    // observed subnets do not need accounts
+    let validation = Config::from_toml_str(&config_str("observe"))
+        .unwrap()
+        .validate();
+    assert_eq!(validation.subnets[0].errors.len(), 2);
+}
@@ -0,0 +1,241 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Validation of the subnets of a config.
+//!
+//! Deserializing a config only checks that its values parse, a config with an account that
+//! cannot sign or a gateway set to an account still loads, and the mistake only shows when a
+//! request reaches that subnet. [`Config::validate`] checks every subnet entry up front and
+//! reports all the issues found at once, the nodes being probed separately, see
+//! [`crate::server::subnet::SubnetManagerPool::validate_config`].
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use fvm_shared::address::{Address, Payload, Protocol};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::subnet::{SubnetConfig, SubnetMode};
+use crate::config::{Config, Subnet};
+
+/// An issue found in a field of a subnet entry.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// The field of the subnet entry, e.g. `config.gateway_addr`.
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// The validation of a subnet entry of the config.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubnetValidation {
+    pub subnet: String,
+    pub endpoint: String,
+    /// Whether the node of the subnet answered and serves the network of the subnet, `None` if it
+    /// was not probed.
+    pub reachable: Option<bool>,
+    /// The issues that make the subnet fail, a subnet without errors is valid.
+    pub errors: Vec<ConfigIssue>,
+    /// The issues that limit what the agent does with the subnet without making it fail.
+    pub warnings: Vec<ConfigIssue>,
+}
+
+impl SubnetValidation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Records the node of `subnet` failing its probe with `error`.
+    pub fn unreachable(&mut self, subnet: &Subnet, error: impl Display) {
+        self.reachable = Some(false);
+        self.errors
+            .push(ConfigIssue::new(endpoint_field(subnet), error.to_string()));
+    }
+}
+
+/// The validation of all the subnets of a config.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigValidation {
+    /// Whether the config was read and none of its subnets has errors.
+    pub valid: bool,
+    /// The errors of the config as a whole, e.g. the file failing to parse.
+    pub errors: Vec<String>,
+    /// The validation of each subnet, sorted by subnet.
+    pub subnets: Vec<SubnetValidation>,
+}
+
+impl ConfigValidation {
+    /// The validation of a config that could not be read.
+    pub fn unreadable(error: impl ToString) -> Self {
+        Self {
+            valid: false,
+            errors: vec![error.to_string()],
+            subnets: vec![],
+        }
+    }
+
+    /// Updates [`Self::valid`] after the subnets were changed, e.g. with the results of probes.
+    pub fn refresh(&mut self) {
+        self.valid = self.errors.is_empty() && self.subnets.iter().all(|s| s.is_valid());
+    }
+}
+
+impl Config {
+    /// Checks the ids, addresses, endpoints and accounts of every subnet of the config, without
+    /// reaching their nodes.
+    pub fn validate(&self) -> ConfigValidation {
+        let mut subnets = self
+            .subnets
+            .values()
+            .map(|s| validate_subnet(&self.subnets, s))
+            .collect::<Vec<_>>();
+        subnets.sort_by(|a, b| a.subnet.cmp(&b.subnet));
+
+        let mut validation = ConfigValidation {
+            valid: false,
+            errors: vec![],
+            subnets,
+        };
+        validation.refresh();
+        validation
+    }
+}
+
+fn validate_subnet(subnets: &HashMap<SubnetID, Subnet>, subnet: &Subnet) -> SubnetValidation {
+    let mut errors = vec![];
+    let mut warnings = vec![];
+
+    if subnet.id.root_id() == 0 {
+        errors.push(ConfigIssue::new(
+            "id",
+            "the root of the subnet id is the chain id of the root network, it cannot be 0",
+        ));
+    }
+    for child in subnet.id.children() {
+        if !is_actor(&child) {
+            errors.push(ConfigIssue::new(
+                "id",
+                format!("{child:} in the route of the subnet id is an account, not a subnet actor"),
+            ));
+        }
+    }
+    if let Some(parent) = subnet.id.parent() && !subnets.contains_key(&parent) {
+        warnings.push(ConfigIssue::new(
+            "id",
+            format!(
+                "the parent {parent:} is not in the config, the subnet is not checkpointed and its cross-net messages are not propagated"
+            ),
+        ));
+    }
+    if subnet.network_name.is_empty() {
+        errors.push(ConfigIssue::new("network_name", "cannot be empty"));
+    }
+
+    let scheme = subnet.rpc_http().scheme();
+    if scheme != "http" && scheme != "https" {
+        errors.push(ConfigIssue::new(
+            endpoint_field(subnet),
+            format!("{} is not an http endpoint", subnet.rpc_http()),
+        ));
+    }
+
+    match &subnet.config {
+        SubnetConfig::Fvm(_) => {
+            if !is_actor(&subnet.gateway_addr()) {
+                errors.push(ConfigIssue::new(
+                    "config.gateway_addr",
+                    format!(
+                        "{} is an account, not the gateway actor",
+                        subnet.gateway_addr()
+                    ),
+                ));
+            }
+            for account in subnet.accounts() {
+                if account.protocol() == Protocol::Actor {
+                    errors.push(ConfigIssue::new(
+                        "config.accounts",
+                        format!("{account:} is an actor, it cannot sign messages"),
+                    ));
+                }
+            }
+        }
+        SubnetConfig::Fevm(s) => {
+            if !is_contract(&s.gateway_addr) {
+                errors.push(ConfigIssue::new(
+                    "config.gateway_addr",
+                    "must be the non-zero eth address of the gateway contract",
+                ));
+            }
+            if !is_contract(&s.registry_addr) {
+                errors.push(ConfigIssue::new(
+                    "config.registry_addr",
+                    "must be the non-zero eth address of the registry contract",
+                ));
+            } else if s.registry_addr == s.gateway_addr {
+                errors.push(ConfigIssue::new(
+                    "config.registry_addr",
+                    "is the address of the gateway",
+                ));
+            }
+        }
+    }
+
+    let accounts = subnet.accounts();
+    if accounts.is_empty() && subnet.mode == SubnetMode::Validate {
+        errors.push(ConfigIssue::new(
+            "config.accounts",
+            "the subnet is in validate mode but has no account to sign with, add one or set the subnet in observe mode",
+        ));
+    }
+    for (i, account) in accounts.iter().enumerate() {
+        if accounts[..i].contains(account) {
+            warnings.push(ConfigIssue::new(
+                "config.accounts",
+                format!("{account:} is listed more than once"),
+            ));
+        }
+    }
+
+    SubnetValidation {
+        subnet: subnet.id.to_string(),
+        endpoint: subnet.rpc_http().to_string(),
+        reachable: None,
+        errors,
+        warnings,
+    }
+}
+
+/// The field of the endpoint of the node of `subnet`.
+fn endpoint_field(subnet: &Subnet) -> &'static str {
+    match subnet.config {
+        SubnetConfig::Fvm(_) => "config.jsonrpc_api_http",
+        SubnetConfig::Fevm(_) => "config.provider_http",
+    }
+}
+
+/// Whether `addr` can be the address of an actor, as opposed to a key.
+fn is_actor(addr: &Address) -> bool {
+    matches!(
+        addr.protocol(),
+        Protocol::ID | Protocol::Actor | Protocol::Delegated
+    )
+}
+
+/// Whether `addr` can be the address of a contract: an eth address other than zero, or the id
+/// of the actor.
+fn is_contract(addr: &Address) -> bool {
+    match addr.payload() {
+        Payload::ID(_) => true,
+        Payload::Delegated(d) => d.subaddress().iter().any(|b| *b != 0),
+        _ => false,
+    }
+}
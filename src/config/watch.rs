@@ -0,0 +1,97 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Reload of the config when its file changes.
+//!
+//! With `watch_config` set in the `[server]` section, the daemon watches the directory of its
+//! config, with inotify on linux and fsevents on macos, and reloads the config when its file is
+//! written, or replaced as editors do when they save a copy and rename it over the original. The
+//! new config is only applied if it parses and the daemon can run with it in its mode, otherwise
+//! the previous one stays active and the error is reported as the outcome of the last reload.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ::notify::{Event, RecursiveMode, Watcher};
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::time::sleep;
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+
+use crate::config::{ReloadableConfig, RunMode};
+
+/// The time to wait after a change of the file for the writes that follow, so that a file saved
+/// in several steps is only reloaded once it is complete.
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Reloads the config of the daemon when its file changes, until the daemon shuts down.
+pub struct ConfigWatcher {
+    config: Arc<ReloadableConfig>,
+    mode: RunMode,
+}
+
+impl ConfigWatcher {
+    pub fn new(config: Arc<ReloadableConfig>, mode: RunMode) -> Self {
+        Self { config, mode }
+    }
+
+    async fn reload(&self) {
+        let mode = self.mode;
+        match self.config.reload_changed(|c| mode.validate(c)).await {
+            Ok(true) => tracing::info!("config reloaded after a change of its file"),
+            Ok(false) => tracing::debug!("config file written without changes"),
+            Err(e) => tracing::error!(
+                "rejected the changes of the config file, keeping the previous config: {e:}"
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl IntoSubsystem<anyhow::Error> for ConfigWatcher {
+    async fn run(self, subsys: SubsystemHandle) -> Result<()> {
+        let path = PathBuf::from(self.config.get_path());
+        // the directory is watched as the file is replaced, not modified, by most editors
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = path.file_name().map(|n| n.to_os_string());
+
+        let (sender, mut changes) = unbounded_channel();
+        let mut watcher =
+            ::notify::recommended_watcher(move |event: ::notify::Result<Event>| match event {
+                Ok(event) => {
+                    let config_changed = !event.kind.is_access()
+                        && event
+                            .paths
+                            .iter()
+                            .any(|p| p.file_name() == file_name.as_deref());
+                    if config_changed {
+                        sender.send(()).unwrap_or_default();
+                    }
+                }
+                Err(e) => tracing::warn!("cannot watch the config file: {e:}"),
+            })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        tracing::info!("watching {path:?} to reload the config when it changes");
+
+        loop {
+            tokio::select! {
+                change = changes.recv() => {
+                    if change.is_none() {
+                        return Ok(());
+                    }
+                    sleep(SETTLE_DELAY).await;
+                    while changes.try_recv().is_ok() {}
+                    self.reload().await;
+                }
+                _ = subsys.on_shutdown_requested() => {
+                    tracing::info!("Shutting down config watcher");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
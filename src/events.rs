@@ -0,0 +1,227 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The events of the checkpoints and cross messages the agent submits or observes, pushed to the
+//! subscribers of the websocket json rpc api.
+//!
+//! Events are only kept while they are being delivered: a subscriber that falls behind by more
+//! than [`EVENT_BUFFER`] events misses the oldest ones, and nothing is replayed to a subscriber
+//! that connects later.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::amount::Amount;
+use crate::checkpoint::DiffCrossMsg;
+
+/// The number of events buffered for a subscriber that is slower than the agent.
+pub const EVENT_BUFFER: usize = 1024;
+
+/// The kinds of events a client subscribes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Checkpoints,
+    CrossMsgs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// The vote of a validator managed by the agent for a checkpoint was executed.
+    CheckpointSubmitted {
+        /// The child subnet of the checkpoint.
+        subnet: String,
+        /// The subnet the checkpoint was submitted to.
+        target: String,
+        /// `bottom-up` or `top-down`
+        direction: String,
+        epoch: ChainEpoch,
+        validator: String,
+        /// The cid of the message, or the hash of the transaction in evm subnets, of the vote.
+        message: String,
+        /// The epoch the vote was executed at.
+        executed_epoch: ChainEpoch,
+    },
+    /// A checkpoint was committed in the subnet it was submitted to.
+    CheckpointCommitted {
+        subnet: String,
+        target: String,
+        direction: String,
+        epoch: ChainEpoch,
+    },
+    /// A cross message was sent by the agent through the json rpc api.
+    CrossMsgSent {
+        /// The subnet the message was sent in.
+        subnet: String,
+        /// The method of the api that sent it, e.g. `ipc_fund`.
+        method: String,
+        from: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        to: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<Amount>,
+        /// The epoch the message was executed at, if known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        epoch: Option<ChainEpoch>,
+    },
+    /// A cross message was carried by a checkpoint submitted by the agent.
+    CrossMsgRelayed {
+        /// The child subnet of the checkpoint.
+        subnet: String,
+        /// The subnet the message was relayed to.
+        target: String,
+        direction: String,
+        /// The epoch of the checkpoint.
+        epoch: ChainEpoch,
+        msg: DiffCrossMsg,
+    },
+}
+
+impl AgentEvent {
+    pub fn topic(&self) -> Topic {
+        match self {
+            AgentEvent::CheckpointSubmitted { .. } | AgentEvent::CheckpointCommitted { .. } => {
+                Topic::Checkpoints
+            }
+            AgentEvent::CrossMsgSent { .. } | AgentEvent::CrossMsgRelayed { .. } => {
+                Topic::CrossMsgs
+            }
+        }
+    }
+
+    /// The subnet the event is about, the one subscriptions are filtered on.
+    pub fn subnet(&self) -> &str {
+        match self {
+            AgentEvent::CheckpointSubmitted { subnet, .. }
+            | AgentEvent::CheckpointCommitted { subnet, .. }
+            | AgentEvent::CrossMsgSent { subnet, .. }
+            | AgentEvent::CrossMsgRelayed { subnet, .. } => subnet,
+        }
+    }
+
+    /// Whether the event is about `subnet`, or one of the subnets it is sent to.
+    pub fn concerns(&self, subnet: &SubnetID) -> bool {
+        let subnet = subnet.to_string();
+        match self {
+            AgentEvent::CheckpointSubmitted { target, .. }
+            | AgentEvent::CheckpointCommitted { target, .. }
+            | AgentEvent::CrossMsgRelayed { target, .. } => {
+                self.subnet() == subnet || *target == subnet
+            }
+            AgentEvent::CrossMsgSent { .. } => self.subnet() == subnet,
+        }
+    }
+}
+
+pub struct EventBus {
+    sender: Mutex<Option<broadcast::Sender<AgentEvent>>>,
+    /// The last checkpoint epoch published, by subnet and direction, as the same checkpoint is
+    /// seen in several polls and by several validators.
+    published: Mutex<Option<HashMap<(String, String, Topic), ChainEpoch>>>,
+}
+
+impl EventBus {
+    pub const fn new() -> Self {
+        Self {
+            sender: Mutex::new(None),
+            published: Mutex::new(None),
+        }
+    }
+
+    /// Publishes `event` to the current subscribers, dropped if there is none.
+    pub fn publish(&self, event: AgentEvent) {
+        if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+            // an error only means that there is no subscriber
+            sender.send(event).ok();
+        }
+    }
+
+    /// Publishes the `topic` events of the checkpoint of `subnet` in `direction` at `epoch`, if
+    /// none were published yet for it or a later one.
+    pub fn publish_once(
+        &self,
+        topic: Topic,
+        subnet: &SubnetID,
+        direction: &str,
+        epoch: ChainEpoch,
+        events: impl FnOnce() -> Vec<AgentEvent>,
+    ) {
+        {
+            let mut published = self.published.lock().unwrap();
+            let last = published
+                .get_or_insert_with(HashMap::new)
+                .entry((subnet.to_string(), direction.to_string(), topic))
+                .or_insert(0);
+            if *last >= epoch {
+                return;
+            }
+            *last = epoch;
+        }
+        for event in events() {
+            self.publish(event);
+        }
+    }
+
+    /// Subscribes to the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.sender
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| broadcast::channel(EVENT_BUFFER).0)
+            .subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::events::{AgentEvent, EventBus, Topic};
+
+    fn committed(epoch: i64) -> AgentEvent {
+        AgentEvent::CheckpointCommitted {
+            subnet: String::from("/r123/f0100"),
+            target: String::from("/r123"),
+            direction: String::from("bottom-up"),
+            epoch,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_bus() {
+        let bus = EventBus::new();
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+
+        // nothing is buffered before the first subscriber
+        bus.publish(committed(5));
+        let mut events = bus.subscribe();
+
+        for epoch in [10, 10, 5, 20] {
+            bus.publish_once(Topic::Checkpoints, &subnet, "bottom-up", epoch, || {
+                vec![committed(epoch)]
+            });
+        }
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event, committed(10));
+        assert_eq!(event.topic(), Topic::Checkpoints);
+        assert!(event.concerns(&subnet));
+        assert!(event.concerns(&subnet.parent().unwrap()));
+        assert!(!event.concerns(&SubnetID::from_str("/r123/f0200").unwrap()));
+        assert_eq!(events.recv().await.unwrap(), committed(20));
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,399 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Backfill of the checkpoint history of a subnet.
+//!
+//! The checkpoints committed in the parent are listed in batches of epochs, with a bounded number
+//! of requests per second, from the node of the parent in the config or from another endpoint,
+//! e.g. an archival node or a node bootstrapped from a chain snapshot, so that production nodes
+//! are not loaded with historical scans. The progress is saved after every batch, and a backfill
+//! of a subnet started again resumes where the previous one stopped. Backfills run as [`Job`]s.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::history::{CheckpointStore, IndexedCheckpoint};
+use crate::jobs::Job;
+use crate::server::check_subnet;
+use crate::server::subnet::{Connection, SubnetManagerPool};
+use crate::state::AgentState;
+
+/// The number of epochs listed in every request if not set.
+pub const DEFAULT_BATCH_EPOCHS: ChainEpoch = 500;
+/// The rate of the requests to the node if not set.
+pub const DEFAULT_MAX_REQUESTS_PER_SEC: f64 = 1.0;
+
+/// The kind of the backfill jobs.
+pub const BACKFILL_JOB: &str = "backfill";
+
+/// The subnets whose backfill is running, so that a subnet is only backfilled once at a time.
+#[derive(Default)]
+pub struct RunningBackfills {
+    subnets: Mutex<HashSet<SubnetID>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillParams {
+    /// The subnet whose checkpoints are indexed.
+    pub subnet: String,
+    /// Defaults to the epoch after the last checkpoint indexed, or the genesis.
+    pub from_epoch: Option<ChainEpoch>,
+    /// Defaults to the head of the parent.
+    pub to_epoch: Option<ChainEpoch>,
+    /// The endpoint of the node of the parent to backfill from, instead of the one in the config.
+    pub endpoint: Option<String>,
+    pub batch_epochs: Option<ChainEpoch>,
+    pub max_requests_per_sec: Option<f64>,
+}
+
+/// The progress of a backfill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillCursor {
+    pub subnet: String,
+    pub from_epoch: ChainEpoch,
+    pub to_epoch: ChainEpoch,
+    /// The first epoch of the next batch.
+    pub next_epoch: ChainEpoch,
+    pub endpoint: Option<String>,
+    pub batch_epochs: ChainEpoch,
+    pub max_requests_per_sec: f64,
+    /// The number of checkpoints indexed so far.
+    pub indexed: u64,
+}
+
+impl BackfillCursor {
+    pub fn done(&self) -> bool {
+        self.next_epoch > self.to_epoch
+    }
+
+    /// The percentage of the epochs of the range already backfilled.
+    pub fn progress(&self) -> f64 {
+        let total = self.to_epoch - self.from_epoch + 1;
+        if total <= 0 {
+            return 100.0;
+        }
+        let done = (self.next_epoch - self.from_epoch).clamp(0, total);
+        done as f64 * 100.0 / total as f64
+    }
+
+    /// The epochs of the next batch, both included.
+    fn next_batch(&self) -> (ChainEpoch, ChainEpoch) {
+        let to = (self.next_epoch + self.batch_epochs - 1).min(self.to_epoch);
+        (self.next_epoch, to)
+    }
+
+    /// The time to wait between two requests.
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.max_requests_per_sec)
+    }
+}
+
+/// Marks the backfill of a subnet as running in the backfills of `state` while alive.
+struct RunningGuard {
+    subnet: SubnetID,
+    state: Arc<AgentState>,
+}
+
+impl RunningGuard {
+    fn new(state: &Arc<AgentState>, subnet: &SubnetID) -> Result<Self> {
+        if !state
+            .backfills
+            .subnets
+            .lock()
+            .unwrap()
+            .insert(subnet.clone())
+        {
+            return Err(anyhow!("backfill of {subnet:} already running"));
+        }
+        Ok(Self {
+            subnet: subnet.clone(),
+            state: state.clone(),
+        })
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.state
+            .backfills
+            .subnets
+            .lock()
+            .unwrap()
+            .remove(&self.subnet);
+    }
+}
+
+/// The backfill of the checkpoint history of a subnet.
+pub struct Backfill {
+    subnet: SubnetID,
+    cursor: BackfillCursor,
+    parent: Connection,
+    store: CheckpointStore,
+    _running: RunningGuard,
+}
+
+impl Backfill {
+    /// Resumes the unfinished backfill of the subnet of `params`, if any, or starts a new one.
+    pub async fn start(
+        pool: &SubnetManagerPool,
+        store: CheckpointStore,
+        params: BackfillParams,
+    ) -> Result<Self> {
+        let subnet = SubnetID::from_str(&params.subnet)?;
+        let running = RunningGuard::new(pool.state(), &subnet)?;
+        let parent_id = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("subnet id does not have a parent"))?;
+
+        let cursor = match load_cursor(&cursor_path(&store, &subnet)).await? {
+            Some(c) if !c.done() => {
+                tracing::info!(
+                    "resuming backfill of {subnet:} at epoch {} ({:.1}%)",
+                    c.next_epoch,
+                    c.progress()
+                );
+                c
+            }
+            _ => new_cursor(pool, &store, &subnet, &parent_id, params).await?,
+        };
+        Self::open(pool, store, subnet, cursor, running)
+    }
+
+    /// Resumes the unfinished backfill of `subnet`, if any.
+    pub async fn resume(
+        pool: &SubnetManagerPool,
+        store: CheckpointStore,
+        subnet: &SubnetID,
+    ) -> Result<Option<Self>> {
+        let running = RunningGuard::new(pool.state(), subnet)?;
+        match load_cursor(&cursor_path(&store, subnet)).await? {
+            Some(c) if !c.done() => Ok(Some(Self::open(pool, store, subnet.clone(), c, running)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Connects to the parent of `subnet`, through the endpoint of `cursor` if set.
+    fn open(
+        pool: &SubnetManagerPool,
+        store: CheckpointStore,
+        subnet: SubnetID,
+        cursor: BackfillCursor,
+        running: RunningGuard,
+    ) -> Result<Self> {
+        let parent_id = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("subnet id does not have a parent"))?;
+        let parent_subnet = pool
+            .subnets()
+            .remove(&parent_id)
+            .ok_or_else(|| anyhow!("target parent subnet not found"))?;
+        check_subnet(&parent_subnet)?;
+
+        let parent_subnet = match &cursor.endpoint {
+            Some(endpoint) => parent_subnet.with_rpc_http(endpoint.parse()?),
+            None => parent_subnet,
+        };
+        let parent = pool
+            .connect(&parent_subnet)
+            .ok_or_else(|| anyhow!("cannot connect to the parent of {subnet:}"))?;
+
+        Ok(Self {
+            subnet,
+            cursor,
+            parent,
+            store,
+            _running: running,
+        })
+    }
+
+    pub fn cursor(&self) -> &BackfillCursor {
+        &self.cursor
+    }
+
+    /// Indexes the checkpoints of the next batch and saves the progress.
+    pub async fn index_batch(&mut self) -> Result<()> {
+        if self.cursor.done() {
+            return Ok(());
+        }
+
+        let (from, to) = self.cursor.next_batch();
+        let checkpoints = self
+            .parent
+            .manager()
+            .list_checkpoints(self.subnet.clone(), from, to)
+            .await?
+            .iter()
+            .map(IndexedCheckpoint::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        self.store.append(&self.subnet, &checkpoints).await?;
+
+        self.cursor.next_epoch = to + 1;
+        self.cursor.indexed += checkpoints.len() as u64;
+        save_cursor(&cursor_path(&self.store, &self.subnet), &self.cursor).await
+    }
+}
+
+#[async_trait]
+impl Job for Backfill {
+    fn kind(&self) -> &'static str {
+        BACKFILL_JOB
+    }
+
+    async fn step(&mut self) -> Result<()> {
+        self.index_batch().await?;
+        tracing::info!(
+            "backfill of {} at epoch {} ({:.1}%), {} checkpoints indexed",
+            self.subnet,
+            self.cursor.next_epoch,
+            self.cursor.progress(),
+            self.cursor.indexed
+        );
+        Ok(())
+    }
+
+    fn done(&self) -> bool {
+        self.cursor.done()
+    }
+
+    fn progress(&self) -> f64 {
+        self.cursor.progress()
+    }
+
+    fn interval(&self) -> Duration {
+        self.cursor.interval()
+    }
+}
+
+/// The cursor of a new backfill of `subnet` with `params`.
+async fn new_cursor(
+    pool: &SubnetManagerPool,
+    store: &CheckpointStore,
+    subnet: &SubnetID,
+    parent: &SubnetID,
+    params: BackfillParams,
+) -> Result<BackfillCursor> {
+    let from_epoch = match params.from_epoch {
+        Some(e) => e,
+        None => store.last_epoch(subnet).await?.map_or(0, |e| e + 1),
+    };
+    let to_epoch = match params.to_epoch {
+        Some(e) => e,
+        None => {
+            let conn = pool
+                .get(parent)
+                .ok_or_else(|| anyhow!("target parent subnet not found"))?;
+            conn.manager().chain_head_epoch().await?
+        }
+    };
+    let batch_epochs = params.batch_epochs.unwrap_or(DEFAULT_BATCH_EPOCHS);
+    let max_requests_per_sec = params
+        .max_requests_per_sec
+        .unwrap_or(DEFAULT_MAX_REQUESTS_PER_SEC);
+    if batch_epochs <= 0 || max_requests_per_sec <= 0.0 {
+        return Err(anyhow!(
+            "the batch size and request rate of a backfill must be positive"
+        ));
+    }
+    if params.endpoint.is_none() {
+        tracing::warn!(
+            "backfilling {subnet:} from the node of {parent:} in the config, set an archival endpoint to keep the load off it"
+        );
+    }
+
+    Ok(BackfillCursor {
+        subnet: subnet.to_string(),
+        from_epoch,
+        to_epoch,
+        next_epoch: from_epoch,
+        endpoint: params.endpoint,
+        batch_epochs,
+        max_requests_per_sec,
+        indexed: 0,
+    })
+}
+
+fn cursor_path(store: &CheckpointStore, subnet: &SubnetID) -> PathBuf {
+    store.path(subnet, "backfill.json")
+}
+
+/// Returns the cursor of the last backfill of a subnet saved in `path`, if any.
+pub(crate) async fn load_cursor(path: &Path) -> Result<Option<BackfillCursor>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = tokio::fs::read_to_string(path).await?;
+    Ok(Some(serde_json::from_str(&content)?))
+}
+
+/// Saves `cursor` to `path`, replacing the file atomically so that a crash while saving never
+/// loses the progress.
+async fn save_cursor(path: &Path, cursor: &BackfillCursor) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec(cursor)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Returns the progress of the last backfill of `subnet`, if any.
+pub async fn backfill_cursor(
+    store: &CheckpointStore,
+    subnet: &SubnetID,
+) -> Result<Option<BackfillCursor>> {
+    load_cursor(&cursor_path(store, subnet)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::backfill::{load_cursor, save_cursor, BackfillCursor};
+
+    fn cursor() -> BackfillCursor {
+        BackfillCursor {
+            subnet: String::from("/r123/f0100"),
+            from_epoch: 100,
+            to_epoch: 1099,
+            next_epoch: 100,
+            endpoint: None,
+            batch_epochs: 300,
+            max_requests_per_sec: 2.0,
+            indexed: 0,
+        }
+    }
+
+    #[test]
+    fn test_cursor_batches() {
+        let mut cursor = cursor();
+        assert_eq!(cursor.progress(), 0.0);
+        assert_eq!(cursor.next_batch(), (100, 399));
+
+        cursor.next_epoch = 1000;
+        assert_eq!(cursor.progress(), 90.0);
+        // the last batch stops at the end of the range
+        assert_eq!(cursor.next_batch(), (1000, 1099));
+
+        cursor.next_epoch = 1100;
+        assert!(cursor.done());
+        assert_eq!(cursor.progress(), 100.0);
+        assert_eq!(cursor.interval().as_millis(), 500);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("r123_f0100.backfill.json");
+        assert!(load_cursor(&path).await.unwrap().is_none());
+
+        let mut c = cursor();
+        c.next_epoch = 400;
+        c.indexed = 30;
+        save_cursor(&path, &c).await.unwrap();
+        assert_eq!(load_cursor(&path).await.unwrap(), Some(c));
+    }
+}
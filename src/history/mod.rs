@@ -0,0 +1,397 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! History of the bottom-up checkpoints committed in the parent of the subnets, with their
+//! cross-net messages, indexed in the data dir of the agent. The cross-net messages are also indexed by
+//! sender and recipient, for the history of an account.
+//!
+//! The history of a subnet is filled by a [`backfill`] job that walks the checkpoints committed
+//! in a range of epochs, in batches and at a bounded rate, so that bootstrapping an empty history
+//! does not scan the whole chain of a production node at once.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Result;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::checkpoint::{DiffCrossMsg, NativeBottomUpCheckpoint};
+use crate::persistence::DataDir;
+
+pub mod backfill;
+pub mod votes;
+
+/// The name of the directory in the history where the files of decommissioned subnets are moved.
+pub const ARCHIVE_DIR_NAME: &str = "archive";
+
+/// A bottom-up checkpoint committed in the parent of a subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedCheckpoint {
+    pub epoch: ChainEpoch,
+    pub cross_msgs: Vec<DiffCrossMsg>,
+    /// In whole FIL
+    pub fee: String,
+    /// The child subnets whose checks are included in the checkpoint.
+    pub children: Vec<String>,
+}
+
+impl TryFrom<&NativeBottomUpCheckpoint> for IndexedCheckpoint {
+    type Error = anyhow::Error;
+
+    fn try_from(c: &NativeBottomUpCheckpoint) -> Result<Self> {
+        Ok(IndexedCheckpoint {
+            epoch: c.epoch,
+            cross_msgs: c
+                .cross_msgs
+                .cross_msgs
+                .iter()
+                .flatten()
+                .map(DiffCrossMsg::try_from)
+                .collect::<Result<_>>()?,
+            fee: c.cross_msgs.fee.to_string(),
+            children: c.children.iter().map(|c| c.source.to_string()).collect(),
+        })
+    }
+}
+
+/// A cross-net message sent or received by an account, in the index of the accounts of a subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountEntry {
+    account: String,
+    epoch: ChainEpoch,
+    nonce: u64,
+    sent: bool,
+}
+
+/// A cross-net message of the history that involves an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountCrossMsg {
+    /// The subnet whose checkpoint carried the message.
+    pub subnet: String,
+    /// The epoch of the checkpoint.
+    pub epoch: ChainEpoch,
+    /// Whether the account sent the message, or received it.
+    pub sent: bool,
+    pub msg: DiffCrossMsg,
+}
+
+/// Returns the raw address of the ipc address `addr`, `subnet:address`.
+fn raw_addr(addr: &str) -> Option<Address> {
+    let (_, raw) = addr.rsplit_once(':')?;
+    Address::from_str(raw).ok()
+}
+
+/// Returns the entries of the accounts involved in the messages of `checkpoints`.
+fn account_entries(checkpoints: &[IndexedCheckpoint]) -> Vec<AccountEntry> {
+    let mut entries = vec![];
+    for c in checkpoints {
+        for msg in c.cross_msgs.iter() {
+            for (addr, sent) in [(&msg.from, true), (&msg.to, false)] {
+                if let Some(account) = raw_addr(addr) {
+                    entries.push(AccountEntry {
+                        account: account.to_string(),
+                        epoch: c.epoch,
+                        nonce: msg.nonce,
+                        sent,
+                    });
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// The checkpoints indexed for each subnet, stored as json lines in one file per subnet.
+#[derive(Clone)]
+pub struct CheckpointStore {
+    dir: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn new(data_dir: &DataDir) -> Self {
+        Self {
+            dir: data_dir.history(),
+        }
+    }
+
+    /// The path of a file of `subnet` in the history directory.
+    pub(crate) fn path(&self, subnet: &SubnetID, extension: &str) -> PathBuf {
+        let name = subnet.to_string().trim_start_matches('/').replace('/', "_");
+        self.dir.join(format!("{name:}.{extension:}"))
+    }
+
+    fn checkpoints_path(&self, subnet: &SubnetID) -> PathBuf {
+        self.path(subnet, "jsonl")
+    }
+
+    fn accounts_path(&self, subnet: &SubnetID) -> PathBuf {
+        self.path(subnet, "accounts.jsonl")
+    }
+
+    async fn append_lines<T: Serialize>(&self, path: PathBuf, items: &[T]) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let mut content = vec![];
+        for item in items {
+            serde_json::to_writer(&mut content, item)?;
+            content.push(b'\n');
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(&content).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    /// Appends `checkpoints` to the history of `subnet`, and their messages to the index of the
+    /// accounts.
+    pub async fn append(&self, subnet: &SubnetID, checkpoints: &[IndexedCheckpoint]) -> Result<()> {
+        // the histories indexed before the accounts are indexed in full on their first query
+        self.ensure_accounts_index(subnet).await?;
+        self.append_lines(self.checkpoints_path(subnet), checkpoints)
+            .await?;
+        self.append_lines(self.accounts_path(subnet), &account_entries(checkpoints))
+            .await
+    }
+
+    /// Indexes the accounts of the whole history of `subnet` if it has no index yet.
+    async fn ensure_accounts_index(&self, subnet: &SubnetID) -> Result<()> {
+        if self.accounts_path(subnet).exists() || !self.checkpoints_path(subnet).exists() {
+            return Ok(());
+        }
+        let checkpoints = self.read(subnet, 0, ChainEpoch::MAX).await?;
+        tracing::info!("indexing the accounts of the history of subnet {subnet:}");
+        self.append_lines(self.accounts_path(subnet), &account_entries(&checkpoints))
+            .await
+    }
+
+    /// Returns the cross-net messages of the history of `subnet` sent or received by `account`,
+    /// the latest first.
+    pub async fn cross_msgs_by_account(
+        &self,
+        subnet: &SubnetID,
+        account: &Address,
+    ) -> Result<Vec<AccountCrossMsg>> {
+        self.ensure_accounts_index(subnet).await?;
+        let path = self.accounts_path(subnet);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+
+        let account = account.to_string();
+        // entries are appended again with their batch, the set drops the copies
+        let mut entries = BTreeSet::new();
+        let content = tokio::fs::read_to_string(path).await?;
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            let e: AccountEntry = serde_json::from_str(line)?;
+            if e.account == account {
+                entries.insert((e.epoch, e.nonce, e.sent));
+            }
+        }
+        let (from, to) = match (entries.iter().next(), entries.iter().next_back()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => return Ok(vec![]),
+        };
+
+        let checkpoints = self.read(subnet, from, to).await?;
+        let mut msgs = vec![];
+        for (epoch, nonce, sent) in entries.into_iter().rev() {
+            let msg = checkpoints
+                .iter()
+                .find(|c| c.epoch == epoch)
+                .and_then(|c| c.cross_msgs.iter().find(|m| m.nonce == nonce));
+            if let Some(msg) = msg {
+                msgs.push(AccountCrossMsg {
+                    subnet: subnet.to_string(),
+                    epoch,
+                    sent,
+                    msg: msg.clone(),
+                });
+            }
+        }
+        Ok(msgs)
+    }
+
+    /// Returns the checkpoints of `subnet` indexed between `from` and `to`, both included,
+    /// sorted by epoch.
+    pub async fn read(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<IndexedCheckpoint>> {
+        let path = self.checkpoints_path(subnet);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+
+        // a batch can be appended twice if the agent stops before saving the progress of its
+        // backfill, the last copy wins
+        let content = tokio::fs::read_to_string(path).await?;
+        let mut checkpoints = BTreeMap::new();
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            let c: IndexedCheckpoint = serde_json::from_str(line)?;
+            if c.epoch >= from && c.epoch <= to {
+                checkpoints.insert(c.epoch, c);
+            }
+        }
+        Ok(checkpoints.into_values().collect())
+    }
+
+    /// Returns the files of `subnet` in the history, the indexed checkpoints, the index of their
+    /// accounts, the progress of their backfill and the cached votes of the validators.
+    pub fn files(&self, subnet: &SubnetID) -> Vec<PathBuf> {
+        [
+            self.checkpoints_path(subnet),
+            self.accounts_path(subnet),
+            self.path(subnet, "backfill.json"),
+            self.path(subnet, "votes.jsonl"),
+        ]
+        .into_iter()
+        .filter(|p| p.exists())
+        .collect()
+    }
+
+    /// Moves the files of `subnet` to the archive of the history, so that they are kept aside
+    /// once the subnet is gone. Returns the paths of the archived files.
+    pub async fn archive(&self, subnet: &SubnetID) -> Result<Vec<PathBuf>> {
+        let archive = self.dir.join(ARCHIVE_DIR_NAME);
+        tokio::fs::create_dir_all(&archive).await?;
+
+        let mut archived = vec![];
+        for file in self.files(subnet) {
+            // the file names are unique per subnet
+            let to = archive.join(file.file_name().unwrap_or_default());
+            tokio::fs::rename(&file, &to).await?;
+            archived.push(to);
+        }
+        Ok(archived)
+    }
+
+    /// Returns the epoch of the last checkpoint indexed for `subnet`, if any.
+    pub async fn last_epoch(&self, subnet: &SubnetID) -> Result<Option<ChainEpoch>> {
+        Ok(self
+            .read(subnet, 0, ChainEpoch::MAX)
+            .await?
+            .last()
+            .map(|c| c.epoch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::address::Address;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::checkpoint::DiffCrossMsg;
+    use crate::history::{CheckpointStore, IndexedCheckpoint};
+    use crate::persistence::DataDir;
+
+    fn checkpoint(epoch: i64, fee: &str) -> IndexedCheckpoint {
+        IndexedCheckpoint {
+            epoch,
+            cross_msgs: vec![],
+            fee: fee.to_string(),
+            children: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::new(&DataDir::new(dir.path()));
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+
+        assert_eq!(store.last_epoch(&subnet).await.unwrap(), None);
+
+        store
+            .append(&subnet, &[checkpoint(10, "0"), checkpoint(20, "0")])
+            .await
+            .unwrap();
+        // the same batch appended again after a restart
+        store
+            .append(&subnet, &[checkpoint(20, "1"), checkpoint(30, "0")])
+            .await
+            .unwrap();
+
+        let checkpoints = store.read(&subnet, 15, 30).await.unwrap();
+        assert_eq!(checkpoints, vec![checkpoint(20, "1"), checkpoint(30, "0")]);
+        assert_eq!(store.last_epoch(&subnet).await.unwrap(), Some(30));
+
+        let other = SubnetID::from_str("/r123/f0200").unwrap();
+        assert!(store.read(&other, 0, 30).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cross_msgs_by_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::new(&DataDir::new(dir.path()));
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+
+        let msg = |from: u64, to: u64, nonce: u64| DiffCrossMsg {
+            from: format!("/r123/f0100:f0{from:}"),
+            to: format!("/r123:f0{to:}"),
+            method: 0,
+            value: String::from("1"),
+            nonce,
+        };
+        let mut first = checkpoint(10, "0");
+        first.cross_msgs = vec![msg(1001, 1002, 0), msg(1003, 1003, 1)];
+        let mut second = checkpoint(20, "0");
+        second.cross_msgs = vec![msg(1002, 1001, 2)];
+        store.append(&subnet, &[first.clone()]).await.unwrap();
+        // the same batch appended again after a restart
+        store.append(&subnet, &[first, second]).await.unwrap();
+
+        let msgs = store
+            .cross_msgs_by_account(&subnet, &Address::new_id(1001))
+            .await
+            .unwrap();
+        assert_eq!(
+            msgs.iter().map(|m| (m.epoch, m.sent)).collect::<Vec<_>>(),
+            vec![(20, false), (10, true)]
+        );
+        // sent to itself
+        let msgs = store
+            .cross_msgs_by_account(&subnet, &Address::new_id(1003))
+            .await
+            .unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert!(store
+            .cross_msgs_by_account(&subnet, &Address::new_id(1004))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::new(&DataDir::new(dir.path()));
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let other = SubnetID::from_str("/r123/f0200").unwrap();
+
+        store.append(&subnet, &[checkpoint(10, "0")]).await.unwrap();
+        store.append(&other, &[checkpoint(10, "0")]).await.unwrap();
+        // the checkpoints and the index of their accounts
+        assert_eq!(store.files(&subnet).len(), 2);
+
+        let archived = store.archive(&subnet).await.unwrap();
+        assert_eq!(archived.len(), 2);
+        assert!(archived.iter().all(|f| f.exists()));
+        assert!(store.files(&subnet).is_empty());
+        assert!(store.read(&subnet, 0, 30).await.unwrap().is_empty());
+        // the history of the other subnets is left as is
+        assert_eq!(store.last_epoch(&other).await.unwrap(), Some(10));
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Cache of the checkpoint votes of the validators, in the history of their subnet.
+//!
+//! Whether a validator voted a checkpoint is read from the chain, one request per checkpoint, and
+//! does not change once the checkpoint is executed, so the votes of the executed checkpoints are
+//! cached to serve the long ranges of uptime dashboards without querying the node again. The
+//! cache also keeps the votes of validators that left the validator set, which the chain cannot
+//! tell anymore.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::history::CheckpointStore;
+
+/// The vote of a validator on a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteStatus {
+    Voted,
+    /// The checkpoint was executed without the vote of the validator.
+    Missed,
+    /// The validator is not in the validator set of the subnet.
+    NotRequired,
+}
+
+/// A vote in the cache of a subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteRecord {
+    pub validator: String,
+    /// Either `bottom-up` or `top-down`
+    pub direction: String,
+    pub epoch: ChainEpoch,
+    pub status: VoteStatus,
+}
+
+impl CheckpointStore {
+    fn votes_path(&self, subnet: &SubnetID) -> PathBuf {
+        self.path(subnet, "votes.jsonl")
+    }
+
+    /// Caches the votes of `records` for `subnet`.
+    pub async fn append_votes(&self, subnet: &SubnetID, records: &[VoteRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.append_lines(self.votes_path(subnet), records).await
+    }
+
+    /// Returns the cached votes of `validator` on the checkpoints of `subnet` in `direction`, by
+    /// epoch.
+    pub async fn read_votes(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+        direction: &str,
+    ) -> Result<BTreeMap<ChainEpoch, VoteStatus>> {
+        let path = self.votes_path(subnet);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let validator = validator.to_string();
+        let content = tokio::fs::read_to_string(path).await?;
+        let mut votes = BTreeMap::new();
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            let r: VoteRecord = serde_json::from_str(line)?;
+            if r.validator == validator && r.direction == direction {
+                votes.insert(r.epoch, r.status);
+            }
+        }
+        Ok(votes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::address::Address;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::history::votes::{VoteRecord, VoteStatus};
+    use crate::history::CheckpointStore;
+    use crate::persistence::DataDir;
+
+    #[tokio::test]
+    async fn test_vote_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::new(&DataDir::new(dir.path()));
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let validator = Address::new_id(1001);
+
+        let record = |validator: u64, direction: &str, epoch, status| VoteRecord {
+            validator: Address::new_id(validator).to_string(),
+            direction: direction.to_string(),
+            epoch,
+            status,
+        };
+        store
+            .append_votes(
+                &subnet,
+                &[
+                    record(1001, "bottom-up", 10, VoteStatus::Voted),
+                    record(1001, "bottom-up", 20, VoteStatus::Missed),
+                    record(1001, "top-down", 10, VoteStatus::Voted),
+                    record(1002, "bottom-up", 10, VoteStatus::Missed),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let votes = store
+            .read_votes(&subnet, &validator, "bottom-up")
+            .await
+            .unwrap();
+        assert_eq!(
+            votes.into_iter().collect::<Vec<_>>(),
+            vec![(10, VoteStatus::Voted), (20, VoteStatus::Missed)]
+        );
+        assert_eq!(store.files(&subnet).len(), 1);
+        assert!(store
+            .read_votes(&subnet, &Address::new_id(1003), "bottom-up")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,480 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Long-running jobs of the agent, like the backfill of the checkpoint history.
+//!
+//! Jobs run step by step in the background of the daemon, and can be paused, resumed and
+//! cancelled between two steps. The jobs are persisted in the data dir of the agent, and the ones
+//! that were running or paused when the agent stopped are restored, in the same state, at startup.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::sleep;
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+use tracing::{Instrument, Span};
+
+use crate::config::ReloadableConfig;
+use crate::history::backfill::{Backfill, BackfillParams, BACKFILL_JOB};
+use crate::history::CheckpointStore;
+use crate::manager::maintenance::MaintenanceMode;
+use crate::manager::multihop::MULTI_HOP_FUND_JOB;
+use crate::persistence::DataDir;
+use crate::server::subnet::SubnetManagerPool;
+use crate::state::AgentState;
+
+/// How often a paused job checks whether it has been resumed.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[async_trait]
+pub trait Job: Send {
+    /// The kind of the job, used to restore it at startup.
+    fn kind(&self) -> &'static str;
+
+    /// Runs the next step of the job.
+    async fn step(&mut self) -> Result<()>;
+
+    fn done(&self) -> bool;
+
+    /// The percentage of the job done.
+    fn progress(&self) -> f64;
+
+    /// The time to wait between two steps.
+    fn interval(&self) -> Duration;
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Paused,
+    Cancelled,
+    Failed,
+    Done,
+}
+
+impl JobStatus {
+    /// Whether the job can still make progress.
+    fn is_active(&self) -> bool {
+        matches!(self, JobStatus::Running | JobStatus::Paused)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobAction {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: String,
+    /// The params the job was started with.
+    pub params: Value,
+    pub status: JobStatus,
+    /// The percentage of the job done.
+    pub progress: f64,
+    /// Why the job failed.
+    pub error: Option<String>,
+    /// The unix timestamp, in seconds, the job was started at.
+    pub started_at: u64,
+    /// The unix timestamp, in seconds, of the last change of status of the job.
+    pub updated_at: u64,
+}
+
+#[derive(Default)]
+struct Jobs {
+    next_id: u64,
+    jobs: BTreeMap<u64, JobInfo>,
+    /// The file the jobs are persisted to, not persisted if not set.
+    path: Option<PathBuf>,
+}
+
+impl Jobs {
+    /// Writes the jobs to their file. The file is replaced atomically so that a crash while
+    /// writing never leaves a truncated file behind.
+    fn persist(&self) {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return,
+        };
+        let r: Result<()> = try {
+            let tmp = path.with_extension("json.tmp");
+            let jobs = self.jobs.values().collect::<Vec<_>>();
+            std::fs::write(&tmp, serde_json::to_vec(&jobs)?)?;
+            std::fs::rename(&tmp, path)?;
+        };
+        if let Err(e) = r {
+            tracing::warn!("cannot persist jobs in {path:?}: {e:}");
+        }
+    }
+}
+
+pub struct JobRegistry {
+    jobs: Mutex<Option<Jobs>>,
+}
+
+impl JobRegistry {
+    pub const fn new() -> Self {
+        Self {
+            jobs: Mutex::new(None),
+        }
+    }
+
+    /// Loads the jobs persisted in `path`, that the jobs are persisted to from now on, and
+    /// returns the ones that were running or paused.
+    pub fn restore(&self, path: &Path) -> Result<Vec<JobInfo>> {
+        let persisted: Vec<JobInfo> = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            vec![]
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        let jobs = jobs.get_or_insert_with(Jobs::default);
+        jobs.path = Some(path.to_path_buf());
+        for job in persisted.iter() {
+            jobs.next_id = jobs.next_id.max(job.id);
+            jobs.jobs.insert(job.id, job.clone());
+        }
+        jobs.persist();
+
+        Ok(persisted
+            .into_iter()
+            .filter(|j| j.status.is_active())
+            .collect())
+    }
+
+    fn register(&self, kind: &str, params: Value, progress: f64) -> JobInfo {
+        let mut jobs = self.jobs.lock().unwrap();
+        let jobs = jobs.get_or_insert_with(Jobs::default);
+
+        let now = now();
+        jobs.next_id += 1;
+        let job = JobInfo {
+            id: jobs.next_id,
+            kind: kind.to_string(),
+            params,
+            status: JobStatus::Running,
+            progress,
+            error: None,
+            started_at: now,
+            updated_at: now,
+        };
+        tracing::info!("job {} ({kind:}) started", job.id);
+        jobs.jobs.insert(job.id, job.clone());
+        jobs.persist();
+        job
+    }
+
+    /// Pauses, resumes or cancels the job `id`.
+    pub fn control(&self, id: u64, action: JobAction) -> Result<JobInfo> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let jobs = jobs
+            .as_mut()
+            .ok_or_else(|| anyhow!("no job with id {id:}"))?;
+        let job = jobs
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("no job with id {id:}"))?;
+
+        job.status = match (action, job.status) {
+            (JobAction::Pause, JobStatus::Running) => JobStatus::Paused,
+            (JobAction::Resume, JobStatus::Paused) => JobStatus::Running,
+            (JobAction::Cancel, s) if s.is_active() => JobStatus::Cancelled,
+            (_, s) => return Err(anyhow!("cannot {action:?} job {id:}, it is {s:?}")),
+        };
+        job.updated_at = now();
+        tracing::info!("job {id:} ({}) is now {:?}", job.kind, job.status);
+
+        let job = job.clone();
+        jobs.persist();
+        Ok(job)
+    }
+
+    /// Returns all the jobs, sorted by id.
+    pub fn all(&self) -> Vec<JobInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.as_ref()
+            .map(|j| j.jobs.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Persists the jobs, with their latest progress.
+    pub fn flush(&self) {
+        if let Some(jobs) = self.jobs.lock().unwrap().as_ref() {
+            jobs.persist();
+        }
+    }
+
+    fn status(&self, id: u64) -> Option<JobStatus> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.as_ref()?.jobs.get(&id).map(|j| j.status)
+    }
+
+    fn set_progress(&self, id: u64, progress: f64) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.as_mut().and_then(|j| j.jobs.get_mut(&id)) {
+            job.progress = progress;
+        }
+    }
+
+    /// Marks the job `id` as finished, with the error it failed with if any.
+    fn finish(&self, id: u64, error: Option<String>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let jobs = match jobs.as_mut() {
+            Some(j) => j,
+            None => return,
+        };
+        if let Some(job) = jobs.jobs.get_mut(&id) {
+            job.status = match error {
+                Some(_) => JobStatus::Failed,
+                None => JobStatus::Done,
+            };
+            if job.status == JobStatus::Done {
+                job.progress = 100.0;
+            }
+            job.error = error;
+            job.updated_at = now();
+            tracing::info!("job {id:} ({}) {:?}", job.kind, job.status);
+        }
+        jobs.persist();
+    }
+
+    /// Runs the job `id` until it is done, fails or is cancelled, pausing it while the agent is in
+    /// `maintenance`.
+    async fn run(&self, id: u64, mut job: Box<dyn Job>, maintenance: &MaintenanceMode) {
+        loop {
+            match self.status(id) {
+                Some(JobStatus::Running) => {}
+                Some(JobStatus::Paused) => {
+                    sleep(PAUSE_POLL_INTERVAL).await;
+                    continue;
+                }
+                _ => return,
+            }
+            if job.done() {
+                return self.finish(id, None);
+            }
+            // jobs do not make progress while the agent is in maintenance
+            let guard = match maintenance.start(format!("job {id:}")) {
+                Some(g) => g,
+                None => {
+                    sleep(PAUSE_POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+            if let Err(e) = job.step().await {
+                tracing::error!("job {id:} failed: {e:}");
+                return self.finish(id, Some(e.to_string()));
+            }
+            self.set_progress(id, job.progress());
+            drop(guard);
+            sleep(job.interval()).await;
+        }
+    }
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs `job`, started with `params`, in the background among the jobs of `state`. The job is
+/// logged in the span it is spawned in, that of the request that started it.
+pub fn spawn(state: Arc<AgentState>, job: Box<dyn Job>, params: Value) -> JobInfo {
+    let info = state.jobs.register(job.kind(), params, job.progress());
+    let id = info.id;
+    tokio::spawn(
+        async move { state.jobs.run(id, job, &state.maintenance).await }
+            .instrument(Span::current()),
+    );
+    info
+}
+
+/// Recreates the job of `info` after a restart, `None` if it has nothing left to do.
+async fn restore_job(
+    info: &JobInfo,
+    pool: &SubnetManagerPool,
+    store: &CheckpointStore,
+) -> Result<Option<Box<dyn Job>>> {
+    match info.kind.as_str() {
+        BACKFILL_JOB => {
+            let params: BackfillParams = serde_json::from_value(info.params.clone())?;
+            let subnet = params.subnet.parse::<SubnetID>()?;
+            Ok(Backfill::resume(pool, store.clone(), &subnet)
+                .await?
+                .map(|b| Box::new(b) as Box<dyn Job>))
+        }
+        // the hops already sent are not known, sending the fund again could fund it twice
+        MULTI_HOP_FUND_JOB => Err(anyhow!(
+            "multi-hop funds cannot be resumed, check the balances in the subnets of the route"
+        )),
+        kind => Err(anyhow!("unknown job kind {kind:}")),
+    }
+}
+
+/// Restores the jobs persisted in the data dir at startup, and persists them until the agent
+/// shuts down.
+pub struct JobsSubsystem {
+    path: PathBuf,
+    store: CheckpointStore,
+    pool: SubnetManagerPool,
+    config: Arc<ReloadableConfig>,
+}
+
+impl JobsSubsystem {
+    pub fn new(
+        data_dir: &DataDir,
+        config: Arc<ReloadableConfig>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
+    ) -> Self {
+        Self {
+            path: data_dir.jobs(),
+            store: CheckpointStore::new(data_dir),
+            pool: SubnetManagerPool::new(config.clone(), fvm_wallet, evm_keystore, state),
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl IntoSubsystem<anyhow::Error> for JobsSubsystem {
+    async fn run(self, subsys: SubsystemHandle) -> Result<()> {
+        let state = self.pool.state().clone();
+        for info in state.jobs.restore(&self.path)? {
+            match restore_job(&info, &self.pool, &self.store).await {
+                Ok(Some(job)) => {
+                    tracing::info!(
+                        "job {} ({}) restored as {:?}",
+                        info.id,
+                        info.kind,
+                        info.status
+                    );
+                    let state = state.clone();
+                    tokio::spawn(
+                        async move { state.jobs.run(info.id, job, &state.maintenance).await },
+                    );
+                }
+                Ok(None) => state.jobs.finish(info.id, None),
+                Err(e) => state
+                    .jobs
+                    .finish(info.id, Some(format!("cannot restore job: {e:}"))),
+            }
+        }
+
+        subsys.on_shutdown_requested().await;
+        tracing::info!("Shutting down jobs subsystem");
+        // the jobs stop before their next step, and are persisted once they all did
+        state
+            .shutdown
+            .drain(self.config.get_config().server.shutdown_timeout())
+            .await;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    use crate::jobs::{Job, JobAction, JobRegistry, JobStatus};
+    use crate::manager::maintenance::MaintenanceMode;
+
+    struct CountingJob {
+        steps: u64,
+        total: u64,
+    }
+
+    #[async_trait]
+    impl Job for CountingJob {
+        fn kind(&self) -> &'static str {
+            "counting"
+        }
+
+        async fn step(&mut self) -> anyhow::Result<()> {
+            self.steps += 1;
+            Ok(())
+        }
+
+        fn done(&self) -> bool {
+            self.steps >= self.total
+        }
+
+        fn progress(&self) -> f64 {
+            self.steps as f64 * 100.0 / self.total as f64
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::ZERO
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        let registry = JobRegistry::new();
+        assert!(registry.restore(&path).unwrap().is_empty());
+
+        let job = registry.register("counting", json!({"total": 4}), 0.0);
+        registry.control(job.id, JobAction::Pause).unwrap();
+        assert!(registry.control(job.id, JobAction::Pause).is_err());
+
+        // the paused job is restored as paused after a restart
+        let restarted = JobRegistry::new();
+        let restored = restarted.restore(&path).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].status, JobStatus::Paused);
+        assert_eq!(restored[0].params, json!({"total": 4}));
+
+        restarted.control(job.id, JobAction::Resume).unwrap();
+        restarted
+            .run(
+                job.id,
+                Box::new(CountingJob { steps: 0, total: 4 }),
+                &MaintenanceMode::default(),
+            )
+            .await;
+        let done = restarted.all().pop().unwrap();
+        assert_eq!(done.status, JobStatus::Done);
+        assert_eq!(done.progress, 100.0);
+        assert!(restarted.control(job.id, JobAction::Cancel).is_err());
+
+        // cancelled jobs stop before their next step
+        let cancelled = restarted.register("counting", json!({}), 0.0);
+        restarted.control(cancelled.id, JobAction::Cancel).unwrap();
+        restarted
+            .run(
+                cancelled.id,
+                Box::new(CountingJob { steps: 0, total: 4 }),
+                &MaintenanceMode::default(),
+            )
+            .await;
+        assert_eq!(restarted.all()[1].status, JobStatus::Cancelled);
+        assert!(restarted.restore(&path).unwrap().is_empty());
+    }
+}
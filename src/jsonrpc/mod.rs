@@ -1,4 +1,5 @@
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
@@ -6,20 +7,29 @@ use anyhow::{anyhow, Result};
 use async_channel::{Receiver, Sender};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
-use reqwest::header::HeaderValue;
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 use tokio::net::TcpStream;
 use tokio::spawn;
+use tokio::time::sleep;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::MaybeTlsStream;
 use tokio_tungstenite::{connect_async, WebSocketStream};
+use tracing::Instrument;
 use url::Url;
 
+use self::pool::PooledClient;
+use self::tap::DebugTap;
+use crate::config::subnet::{Decoding, RetryConfig};
+use crate::metrics::Metrics;
+
+pub mod pool;
+pub mod tap;
 #[cfg(test)]
 mod tests;
 
@@ -29,6 +39,14 @@ const DEFAULT_JSON_RPC_ID: u8 = 1;
 /// the time required to validate transaction on-chain.
 const DEFAULT_REQ_TIMEOUT: Duration = Duration::from_secs(250);
 
+/// The statuses of the responses of nodes, or of the proxies in front of them, that are retried.
+const RETRIED_STATUSES: [StatusCode; 4] = [
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
 /// A convenience constant that represents empty params in a JSON-RPC request.
 pub const NO_PARAMS: Value = json!([]);
 
@@ -41,8 +59,8 @@ pub trait JsonRpcClient {
     async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T>;
 
     /// Subscribes to notifications via a Websocket. This returns a [`Receiver`]
-    /// channel that is used to receive the messages sent by the server.
-    /// TODO: https://github.com/consensus-shipyard/ipc-agent/issues/7.
+    /// channel that is used to receive the messages sent by the server, starting with the
+    /// response to the subscription. The websocket is closed once the receiver is dropped.
     async fn subscribe(&self, method: &str) -> Result<Receiver<Value>>;
 }
 
@@ -50,7 +68,22 @@ pub trait JsonRpcClient {
 pub struct JsonRpcClientImpl {
     http_client: Client,
     url: Url,
+    /// The websocket endpoint of the subscriptions, derived from `url` if not set.
+    ws_url: Option<Url>,
     bearer_token: Option<String>,
+    /// The extra headers sent with every request.
+    headers: HeaderMap,
+    decoding: Decoding,
+    retry: RetryConfig,
+    /// The methods that are only retried when the request did not reach the node, as the node
+    /// may have processed it otherwise.
+    non_idempotent: Vec<String>,
+    /// The shared client of the node, if the connections to the node are pooled.
+    pooled: Option<PooledClient>,
+    /// The tap the requests are recorded in, if any.
+    tap: Option<Arc<DebugTap>>,
+    /// The metrics the latency of the requests is observed in, if any.
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl JsonRpcClientImpl {
@@ -59,31 +92,165 @@ impl JsonRpcClientImpl {
         Self {
             http_client: Client::default(),
             url,
+            ws_url: None,
             bearer_token: bearer_token.map(String::from),
+            headers: HeaderMap::new(),
+            decoding: Decoding::default(),
+            // not retried unless asked to, the client does not know which methods are safe to retry
+            retry: RetryConfig::none(),
+            non_idempotent: vec![],
+            pooled: None,
+            tap: None,
+            metrics: None,
         }
     }
-}
 
-#[async_trait]
-impl JsonRpcClient for JsonRpcClientImpl {
-    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
-        let request_body = build_jsonrpc_request(method, params)?;
-        let mut builder = self.http_client.post(self.url.as_str()).json(&request_body);
-        builder = builder.timeout(DEFAULT_REQ_TIMEOUT);
+    /// Sends the requests with the shared client of the node, reusing the connections other
+    /// clients of the node keep alive and bounding the requests in flight to the node.
+    pub fn with_pooled_client(mut self, pooled: PooledClient) -> Self {
+        self.http_client = pooled.client.clone();
+        self.pooled = Some(pooled);
+        self
+    }
+
+    /// Records the requests, and the responses of the node, in `tap`.
+    pub fn with_tap(mut self, tap: Arc<DebugTap>) -> Self {
+        self.tap = Some(tap);
+        self
+    }
+
+    /// Observes the latency of the requests in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sets the extra headers sent with every request, e.g. the credentials of a hosted node.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets how strictly the results of the requests are decoded.
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// Sets how the requests are retried on transport errors. Requests to `non_idempotent`
+    /// methods are only retried when they did not reach the node.
+    pub fn with_retry(mut self, retry: RetryConfig, non_idempotent: &[&str]) -> Self {
+        self.retry = retry;
+        self.non_idempotent = non_idempotent.iter().map(|m| m.to_string()).collect();
+        self
+    }
+
+    /// Sends the request over HTTP once, returning the body of the response.
+    async fn send_request(
+        &self,
+        request_body: &Value,
+    ) -> std::result::Result<String, AttemptError> {
+        // held until the body of the response is read
+        let _guard = match &self.pooled {
+            Some(pooled) => Some(pooled.acquire().await.map_err(|e| AttemptError {
+                error: e,
+                transient: false,
+                maybe_processed: false,
+            })?),
+            None => None,
+        };
+
+        let mut builder = self.http_client.post(self.url.as_str()).json(request_body);
+        builder = builder
+            .timeout(DEFAULT_REQ_TIMEOUT)
+            .headers(self.headers.clone());
 
         // Add the authorization bearer token if present
         if self.bearer_token.is_some() {
             builder = builder.bearer_auth(self.bearer_token.as_ref().unwrap());
         }
 
-        let response = builder.send().await?;
+        let response = builder.send().await.map_err(|e| AttemptError {
+            transient: e.is_connect() || e.is_timeout() || e.is_request(),
+            maybe_processed: !e.is_connect(),
+            error: e.into(),
+        })?;
 
-        let response_body = response.text().await?;
-        log::debug!("received raw response body: {:?}", response_body);
+        let status = response.status();
+        if RETRIED_STATUSES.contains(&status) {
+            return Err(AttemptError {
+                error: anyhow!("node answered with status {status:}"),
+                transient: true,
+                // the node or the proxy in front of it turned the request away
+                maybe_processed: status != StatusCode::TOO_MANY_REQUESTS
+                    && status != StatusCode::SERVICE_UNAVAILABLE,
+            });
+        }
 
-        let value =
-            serde_json::from_str::<JsonRpcResponse<T>>(response_body.as_ref()).map_err(|e| {
-                log::error!("cannot parse json rpc client response: {:?}", response_body);
+        response.text().await.map_err(|e| AttemptError {
+            error: e.into(),
+            transient: true,
+            maybe_processed: true,
+        })
+    }
+
+    /// Sets the websocket endpoint of the subscriptions, for nodes serving them behind another
+    /// url than the requests.
+    pub fn with_ws_url(mut self, ws_url: Option<Url>) -> Self {
+        self.ws_url = ws_url;
+        self
+    }
+
+    /// The websocket endpoint of the subscriptions.
+    pub fn ws_url(&self) -> Result<Url> {
+        match &self.ws_url {
+            Some(url) => Ok(url.clone()),
+            None => ws_url_from_http(&self.url),
+        }
+    }
+
+    /// Sends the request, retrying it as set in the config, and decodes its result.
+    async fn send_and_decode<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let request_body = build_jsonrpc_request(method, params)?;
+        let idempotent = !self.non_idempotent.iter().any(|m| m == method);
+
+        let started = Instant::now();
+        let mut attempt = 1;
+        let response_body = loop {
+            match self.send_request(&request_body).await {
+                Ok(body) => break Ok(body),
+                Err(e) if attempt < self.retry.max_attempts && e.retriable(idempotent) => {
+                    let backoff = self.retry.jittered_backoff(attempt);
+                    tracing::warn!(
+                        "request {method:} to {} failed, attempt {attempt:} of {}, retrying in {backoff:?}: {}",
+                        self.url,
+                        self.retry.max_attempts,
+                        e.error
+                    );
+                    sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(e) => break Err(e.error),
+            }
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_node_request(method, started.elapsed(), response_body.is_ok());
+        }
+        let response_body = response_body?;
+        tracing::debug!("received raw response body: {:?}", response_body);
+        if let Some(tap) = &self.tap {
+            tap.record(
+                &self.url,
+                method,
+                &request_body,
+                &response_body,
+                started.elapsed(),
+            );
+        }
+
+        let value = serde_json::from_str::<JsonRpcResponse<Value>>(response_body.as_ref())
+            .map_err(|e| {
+                tracing::error!("cannot parse json rpc client response: {:?}", response_body);
                 anyhow!(
                     "cannot parse json rpc response: {:} due to {:}",
                     response_body,
@@ -95,11 +262,41 @@ impl JsonRpcClient for JsonRpcClientImpl {
             return Err(anyhow!("json_rpc id or version not matching."));
         }
 
-        Result::from(value)
+        let result = Result::from(value)?;
+        decode_result(method, result, self.decoding)
+    }
+}
+
+/// Derives the websocket endpoint of a node from its http one, e.g. `ws://127.0.0.1:1234/rpc/v1`
+/// for `http://127.0.0.1:1234/rpc/v1`, as lotus serves both on the same path.
+pub(crate) fn ws_url_from_http(url: &Url) -> Result<Url> {
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        s => {
+            return Err(anyhow!(
+                "cannot derive a websocket url from a {s:} url: {url:}"
+            ))
+        }
+    };
+    let mut ws_url = url.clone();
+    ws_url
+        .set_scheme(scheme)
+        .map_err(|_| anyhow!("cannot derive a websocket url from {url:}"))?;
+    Ok(ws_url)
+}
+
+#[async_trait]
+impl JsonRpcClient for JsonRpcClientImpl {
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        // logged within the span of the request served by the agent it is made for, if any
+        let span = tracing::debug_span!("node_request", method, node = %self.url);
+        self.send_and_decode(method, params).instrument(span).await
     }
 
     async fn subscribe(&self, method: &str) -> Result<Receiver<Value>> {
-        let mut request = self.url.as_str().into_client_request()?;
+        let mut request = self.ws_url()?.as_str().into_client_request()?;
+        request.headers_mut().extend(self.headers.clone());
 
         // Add the authorization bearer token if present
         if self.bearer_token.is_some() {
@@ -121,6 +318,21 @@ impl JsonRpcClient for JsonRpcClientImpl {
     }
 }
 
+/// A failed attempt at sending a request.
+struct AttemptError {
+    error: anyhow::Error,
+    /// Whether another attempt may succeed.
+    transient: bool,
+    /// Whether the node may have received and processed the request.
+    maybe_processed: bool,
+}
+
+impl AttemptError {
+    fn retriable(&self, idempotent: bool) -> bool {
+        self.transient && (idempotent || !self.maybe_processed)
+    }
+}
+
 /// JsonRpcResponse wraps the json rpc response.
 /// We could have encountered success or error, this struct handles the error and result and convert
 /// them into Result.
@@ -150,7 +362,7 @@ impl<T: DeserializeOwned> From<JsonRpcResponse<T>> for Result<T> {
 }
 
 // Processes a websocket stream by reading messages from the stream `ws_stream` and sending
-// them to an output channel `chan`.
+// them to an output channel `chan`, until either the stream or the channel is closed.
 async fn handle_stream(
     mut ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
     chan: Sender<Value>,
@@ -158,18 +370,32 @@ async fn handle_stream(
     loop {
         match ws_stream.next().await {
             None => {
-                log::trace!("No message in websocket stream. The stream was closed.");
+                tracing::trace!("No message in websocket stream. The stream was closed.");
                 break;
             }
             Some(result) => match result {
-                Ok(msg) => {
-                    println!("{}", msg);
-                    log::trace!("Read message from websocket stream: {}", msg);
-                    let value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
-                    chan.send(value).await.unwrap();
+                Ok(Message::Text(msg)) => {
+                    tracing::trace!("Read message from websocket stream: {}", msg);
+                    let value = match serde_json::from_str::<Value>(&msg) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            tracing::warn!("Skipping websocket message that is not json: {e:}");
+                            continue;
+                        }
+                    };
+                    if chan.send(value).await.is_err() {
+                        tracing::trace!("The receiver of the websocket stream was dropped.");
+                        break;
+                    }
                 }
+                Ok(Message::Close(frame)) => {
+                    tracing::debug!("Websocket stream closed by the server: {frame:?}");
+                    break;
+                }
+                // pings are answered by the stream itself
+                Ok(msg) => tracing::trace!("Ignoring websocket message: {}", msg),
                 Err(err) => {
-                    log::error!("Error reading message from websocket stream: {:?}", err);
+                    tracing::error!("Error reading message from websocket stream: {:?}", err);
                     break;
                 }
             },
@@ -178,6 +404,41 @@ async fn handle_stream(
     chan.close();
 }
 
+/// Decodes the `result` of a request to `method`, pointing at the field that cannot be decoded,
+/// e.g. `Blocks[0].Height`, so that responses changed by a node upgrade are easy to diagnose.
+pub(crate) fn decode_result<T: DeserializeOwned>(
+    method: &str,
+    result: Value,
+    decoding: Decoding,
+) -> Result<T> {
+    let mut unknown = vec![];
+    let deserializer =
+        serde_ignored::Deserializer::new(result, |path| unknown.push(path.to_string()));
+    let decoded = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        anyhow!(
+            "cannot decode the response of {method:} at `{}`: {}",
+            e.path(),
+            e.inner()
+        )
+    })?;
+
+    if !unknown.is_empty() {
+        match decoding {
+            Decoding::Strict => {
+                return Err(anyhow!(
+                    "unknown fields in the response of {method:}: {}",
+                    unknown.join(", ")
+                ))
+            }
+            Decoding::Lenient => tracing::debug!(
+                "ignored unknown fields in the response of {method:}: {}",
+                unknown.join(", ")
+            ),
+        }
+    }
+    Ok(decoded)
+}
+
 // A convenience function to build a JSON-RPC request.
 fn build_jsonrpc_request(method: &str, params: Value) -> Result<Value> {
     let has_params = if params.is_array() {
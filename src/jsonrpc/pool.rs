@@ -0,0 +1,171 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The HTTP clients shared by the json rpc clients of a node.
+//!
+//! Clients are created for every subnet manager, which is created for every request to the
+//! JSON-RPC API of the agent. They share the HTTP client of the endpoint of the node, so that
+//! their requests reuse the connections kept alive in its pool instead of opening new ones.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+use crate::config::subnet::ConnectionConfig;
+
+/// The interval of the TCP keep-alive probes of the pooled connections.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// The HTTP client of an endpoint, with the connections it keeps alive.
+#[derive(Clone)]
+pub struct PooledClient {
+    pub(crate) client: Client,
+    config: ConnectionConfig,
+    /// Bounds the requests in flight to `max_connections`, if set.
+    permits: Option<Arc<Semaphore>>,
+    in_flight: Arc<AtomicUsize>,
+    requests: Arc<AtomicU64>,
+}
+
+impl PooledClient {
+    fn new(config: &ConnectionConfig) -> Result<Self> {
+        let mut builder = Client::builder()
+            .pool_idle_timeout(Duration::from_secs(config.idle_timeout_secs))
+            .tcp_keepalive(TCP_KEEPALIVE);
+        if let Some(max) = config.max_connections {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        Ok(Self {
+            client: builder.build()?,
+            config: config.clone(),
+            permits: config.max_connections.map(|m| Arc::new(Semaphore::new(m))),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            requests: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Waits for a slot to send a request, held until the returned guard is dropped.
+    pub(crate) async fn acquire(&self) -> Result<RequestGuard> {
+        let permit = match &self.permits {
+            Some(p) => Some(p.clone().acquire_owned().await?),
+            None => None,
+        };
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        Ok(RequestGuard {
+            _permit: permit,
+            in_flight: self.in_flight.clone(),
+        })
+    }
+}
+
+/// A request in flight to a node.
+pub(crate) struct RequestGuard {
+    _permit: Option<OwnedSemaphorePermit>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The use of the connections to an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    pub endpoint: String,
+    pub max_connections: Option<usize>,
+    pub in_flight: usize,
+    /// The requests sent since the client was created.
+    pub requests: u64,
+}
+
+pub struct HttpClientPool {
+    clients: Mutex<Option<HashMap<String, PooledClient>>>,
+}
+
+impl HttpClientPool {
+    pub const fn new() -> Self {
+        Self {
+            clients: Mutex::new(None),
+        }
+    }
+
+    /// Returns the client of `endpoint`, created with `config` if there is none yet, or if the
+    /// config changed since it was created.
+    pub fn get(&self, endpoint: &Url, config: &ConnectionConfig) -> Result<PooledClient> {
+        let mut clients = self.clients.lock().unwrap();
+        let clients = clients.get_or_insert_with(HashMap::new);
+        match clients.get(endpoint.as_str()) {
+            Some(c) if &c.config == config => Ok(c.clone()),
+            _ => {
+                let client = PooledClient::new(config)?;
+                clients.insert(endpoint.to_string(), client.clone());
+                Ok(client)
+            }
+        }
+    }
+
+    /// The use of the connections to `endpoint`, if a client was created for it.
+    pub fn stats(&self, endpoint: &Url) -> Option<ConnectionStats> {
+        let clients = self.clients.lock().unwrap();
+        let client = clients.as_ref()?.get(endpoint.as_str())?;
+        Some(ConnectionStats {
+            endpoint: endpoint.to_string(),
+            max_connections: client.config.max_connections,
+            in_flight: client.in_flight.load(Ordering::Relaxed),
+            requests: client.requests.load(Ordering::Relaxed),
+        })
+    }
+}
+
+impl Default for HttpClientPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::config::subnet::ConnectionConfig;
+    use crate::jsonrpc::pool::HttpClientPool;
+
+    #[tokio::test]
+    async fn test_pooled_clients() {
+        let pool = HttpClientPool::new();
+        let endpoint = Url::parse("http://127.0.0.1:1234/rpc/v1").unwrap();
+        let config = ConnectionConfig {
+            max_connections: Some(1),
+            idle_timeout_secs: 30,
+        };
+        assert!(pool.stats(&endpoint).is_none());
+
+        let client = pool.get(&endpoint, &config).unwrap();
+        let guard = client.acquire().await.unwrap();
+        // the second client of the endpoint shares the slots of the first one
+        let shared = pool.get(&endpoint, &config).unwrap();
+        assert!(shared.permits.as_ref().unwrap().try_acquire().is_err());
+        let stats = pool.stats(&endpoint).unwrap();
+        assert_eq!((stats.in_flight, stats.requests), (1, 1));
+
+        drop(guard);
+        assert_eq!(pool.stats(&endpoint).unwrap().in_flight, 0);
+
+        // a new config replaces the client
+        let config = ConnectionConfig {
+            max_connections: None,
+            idle_timeout_secs: 30,
+        };
+        pool.get(&endpoint, &config).unwrap();
+        assert_eq!(pool.stats(&endpoint).unwrap().requests, 0);
+    }
+}
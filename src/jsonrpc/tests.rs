@@ -1,10 +1,16 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use std::time::{Duration, Instant};
+
 use futures_util::StreamExt;
+use serde::Deserialize;
 use serde_json::json;
 use url::Url;
 
-use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl, NO_PARAMS};
+use crate::config::subnet::{Decoding, RetryConfig};
+use crate::jsonrpc::{
+    decode_result, ws_url_from_http, JsonRpcClient, JsonRpcClientImpl, NO_PARAMS,
+};
 
 /// The default endpoints for public lotus node. If the urls fail in running tests, need to
 /// check these endpoints again.
@@ -58,6 +64,36 @@ async fn test_request_with_params_error() {
     assert!(response.is_err());
 }
 
+#[tokio::test]
+async fn test_request_retry() {
+    // nothing listens on the port, the requests never reach a node
+    let url = Url::parse("http://127.0.0.1:1/rpc/v1").unwrap();
+    let retry = RetryConfig {
+        max_attempts: 3,
+        initial_backoff_ms: 50,
+        max_backoff_ms: 1000,
+        jitter: 0.0,
+    };
+
+    let client = JsonRpcClientImpl::new(url.clone(), None).with_retry(retry.clone(), &[]);
+    let started = Instant::now();
+    assert!(client
+        .request::<serde_json::Value>("Filecoin.ChainHead", NO_PARAMS)
+        .await
+        .is_err());
+    // retried after 50ms and 100ms
+    assert!(started.elapsed() >= Duration::from_millis(150));
+
+    // requests that did not reach the node are retried for non idempotent methods too
+    let client = JsonRpcClientImpl::new(url, None).with_retry(retry, &["Filecoin.WalletNew"]);
+    let started = Instant::now();
+    assert!(client
+        .request::<serde_json::Value>("Filecoin.WalletNew", NO_PARAMS)
+        .await
+        .is_err());
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
+
 #[tokio::test]
 #[ignore]
 async fn test_subscribe() {
@@ -68,3 +104,57 @@ async fn test_subscribe() {
         chan.next().await.unwrap();
     }
 }
+
+#[test]
+fn test_ws_url_from_http() {
+    let ws = |url: &str| ws_url_from_http(&Url::parse(url).unwrap()).map(|u| u.to_string());
+    assert_eq!(
+        ws("http://127.0.0.1:1234/rpc/v1").unwrap(),
+        "ws://127.0.0.1:1234/rpc/v1"
+    );
+    assert_eq!(ws(HTTP_ENDPOINT).unwrap(), "wss://api.node.glif.io/rpc/v0");
+    assert_eq!(ws(WS_ENDPOINT).unwrap(), WS_ENDPOINT);
+    assert!(ws("file:///tmp/lotus.sock").is_err());
+
+    let client = JsonRpcClientImpl::new(Url::parse(HTTP_ENDPOINT).unwrap(), None)
+        .with_ws_url(Some(Url::parse(WS_ENDPOINT).unwrap()));
+    assert_eq!(client.ws_url().unwrap().as_str(), WS_ENDPOINT);
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+struct Head {
+    height: u64,
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+struct Block {
+    miner: String,
+    timestamp: Option<u64>,
+}
+
+#[test]
+fn test_decode_result() {
+    let result = json!({"Height": 10, "Blocks": [{"Miner": "t01000", "WinCount": 1}]});
+    let head =
+        decode_result::<Head>("Filecoin.ChainHead", result.clone(), Decoding::Lenient).unwrap();
+    assert_eq!(
+        head,
+        Head {
+            height: 10,
+            blocks: vec![Block {
+                miner: "t01000".to_string(),
+                timestamp: None
+            }]
+        }
+    );
+
+    let e = decode_result::<Head>("Filecoin.ChainHead", result, Decoding::Strict).unwrap_err();
+    assert!(e.to_string().contains("WinCount"));
+
+    let result = json!({"Height": 10, "Blocks": [{"Miner": 1000}]});
+    let e = decode_result::<Head>("Filecoin.ChainHead", result, Decoding::Lenient).unwrap_err();
+    assert!(e.to_string().contains("at `Blocks[0].Miner`"));
+}
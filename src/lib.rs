@@ -4,11 +4,21 @@
 #![feature(let_chains)]
 #![feature(drain_filter)]
 
+pub mod alerts;
+pub mod amount;
 pub mod checkpoint;
 pub mod cli;
 pub mod config;
+pub mod events;
+pub mod history;
+pub mod jobs;
 pub mod jsonrpc;
+pub mod logging;
 pub mod lotus;
 pub mod manager;
+pub mod metrics;
+pub mod notify;
+pub mod persistence;
 pub mod sdk;
 pub mod server;
+pub mod state;
@@ -0,0 +1,352 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Logging of the agent, to stderr and to the rotated log file of the `[log]` section of the
+//! config.
+//!
+//! The logger is set up before the config is read, writing text to stderr only, and the daemon
+//! applies the format, the levels and the log file of its config once it has loaded it. The logs
+//! of the dependencies that still use the `log` crate are forwarded to the same logger. The file
+//! is rotated when it grows past its maximum size or when the period it covers ends, by renaming
+//! it with the unix timestamp of the rotation as suffix, and the oldest rotated files past the
+//! retention are deleted.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, Result};
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+use crate::config::{LogConfig, LogFormat};
+use crate::manager::clock::now;
+
+/// The level of the logs when neither `RUST_LOG` nor the config set one.
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type FormatLayer = Box<dyn Layer<Filtered> + Send + Sync>;
+
+/// The logger of the agent, with the handles to change the levels, the format and the file of the
+/// logs once the config is loaded.
+pub struct Logger {
+    filter: reload::Handle<EnvFilter, Registry>,
+    format: reload::Handle<FormatLayer, Filtered>,
+    file: Arc<LogFile>,
+}
+
+/// Sets up the logger, as text with the levels of `RUST_LOG` and `info` by default.
+pub fn init() -> Logger {
+    let filter = env_filter(None).unwrap_or_else(|e| {
+        eprintln!("ignoring RUST_LOG: {e:}");
+        EnvFilter::new(DEFAULT_LOG_LEVEL)
+    });
+    let file = Arc::new(LogFile::new());
+    let (filter, filter_handle) = reload::Layer::new(filter);
+    let (format, format_handle) = reload::Layer::new(format_layer(LogFormat::Text, &file));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(format)
+        .init();
+
+    Logger {
+        filter: filter_handle,
+        format: format_handle,
+        file,
+    }
+}
+
+impl Logger {
+    /// Applies the `[log]` section of the config: the format and the levels of the logs, and the
+    /// file they are written to. Called before any span is opened, as the spans already opened
+    /// are formatted for the previous format.
+    pub fn configure(&self, config: Option<&LogConfig>) -> Result<()> {
+        let filter = env_filter(config)?;
+        self.filter.reload(filter)?;
+        let format = config.map(|c| c.format).unwrap_or_default();
+        self.format.reload(format_layer(format, &self.file))?;
+        self.file.set(config)
+    }
+}
+
+/// The filter of the logs: the directives of `RUST_LOG` if set, which take precedence over the
+/// levels of the config, or `info` if neither is set.
+fn env_filter(config: Option<&LogConfig>) -> Result<EnvFilter> {
+    let directives = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) if !directives.is_empty() => directives,
+        _ => config.map_or_else(
+            || String::from(DEFAULT_LOG_LEVEL),
+            LogConfig::filter_directives,
+        ),
+    };
+    EnvFilter::try_new(&directives).map_err(|e| anyhow!("invalid log levels {directives:}: {e:}"))
+}
+
+fn format_layer(format: LogFormat, file: &Arc<LogFile>) -> FormatLayer {
+    let file = file.clone();
+    let sink = move || LogSink(file.clone());
+    match format {
+        LogFormat::Text => fmt::layer().with_ansi(false).with_writer(sink).boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_writer(sink)
+            .boxed(),
+    }
+}
+
+pub struct LogFile {
+    file: Mutex<Option<RotatingFile>>,
+}
+
+impl LogFile {
+    pub const fn new() -> Self {
+        Self {
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Writes the logs to the file of `config` from now on, or stops writing them to a file if
+    /// not set.
+    pub fn set(&self, config: Option<&LogConfig>) -> Result<()> {
+        let path = config.and_then(|c| c.file.as_ref());
+        let file = match (config, path) {
+            (Some(config), Some(path)) => {
+                let file = RotatingFile::open(path, config)?;
+                tracing::info!("writing the logs to {path:?}");
+                Some(file)
+            }
+            _ => None,
+        };
+        *self.file.lock().unwrap() = file;
+        Ok(())
+    }
+
+    fn write(&self, buf: &[u8]) {
+        let mut file = self.file.lock().unwrap();
+        if let Some(f) = file.as_mut() {
+            // the logs are still written to stderr, report there that the file is failing
+            if let Err(e) = f.write_all(buf) {
+                eprintln!("cannot write to the log file {:?}: {e:}", f.path);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(f) = self.file.lock().unwrap().as_mut() {
+            let _ = f.flush();
+        }
+    }
+}
+
+impl Default for LogFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The target of the logger, writing every record to stderr and to the log file.
+struct LogSink(Arc<LogFile>);
+
+impl Write for LogSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf);
+        io::stderr().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush();
+        io::stderr().flush()
+    }
+}
+
+/// A file rotated on its size and on the periods of its config.
+struct RotatingFile {
+    path: PathBuf,
+    config: LogConfig,
+    file: File,
+    size: u64,
+    /// The period the file covers, since the unix epoch.
+    period: Option<u64>,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, config: &LogConfig) -> Result<Self> {
+        if let Some(dir) = path.parent() && !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("cannot open the log file {path:?}: {e:}"))?;
+        let metadata = file.metadata()?;
+        // a file left by a previous run covers the period it was last written in
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(now, |d| d.as_secs());
+        Ok(Self {
+            period: period(config, modified),
+            size: metadata.len(),
+            file,
+            path: path.to_path_buf(),
+            config: config.clone(),
+        })
+    }
+
+    fn should_rotate(&self, len: u64, now: u64) -> bool {
+        let too_big = self
+            .config
+            .max_size()
+            .map_or(false, |max| self.size > 0 && self.size + len > max);
+        too_big || period(&self.config, now) != self.period
+    }
+
+    fn rotate(&mut self, now: u64) -> Result<()> {
+        self.file.flush()?;
+        let mut rotated = suffixed(&self.path, now);
+        // several rotations can happen within a second when the maximum size is small
+        let mut n = 1;
+        while rotated.exists() {
+            rotated = suffixed(&self.path, &format!("{now:}-{n:}"));
+            n += 1;
+        }
+        std::fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.period = period(&self.config, now);
+        self.prune()
+    }
+
+    /// Deletes the oldest rotated files past the retention.
+    fn prune(&self) -> Result<()> {
+        let mut rotated = rotated_files(&self.path)?;
+        if rotated.len() <= self.config.max_files {
+            return Ok(());
+        }
+        rotated.sort();
+        let excess = rotated.len() - self.config.max_files;
+        for (_, path) in rotated.into_iter().take(excess) {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = now();
+        if self.should_rotate(buf.len() as u64, now) {
+            self.rotate(now)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn period(config: &LogConfig, timestamp: u64) -> Option<u64> {
+    config.rotation.period_secs().map(|secs| timestamp / secs)
+}
+
+fn suffixed(path: &Path, suffix: impl std::fmt::Display) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix:}"));
+    path.with_file_name(name)
+}
+
+/// Returns the files rotated from `path`, with the timestamp and the index they were rotated at.
+fn rotated_files(path: &Path) -> Result<Vec<((u64, u64), PathBuf)>> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}.",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let mut rotated = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let suffix = match name.strip_prefix(&prefix) {
+            Some(s) => s,
+            None => continue,
+        };
+        let (timestamp, index) = suffix.split_once('-').unwrap_or((suffix, "0"));
+        if let (Ok(timestamp), Ok(index)) = (timestamp.parse(), index.parse()) {
+            rotated.push(((timestamp, index), entry.path()));
+        }
+    }
+    Ok(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use std::collections::BTreeMap;
+
+    use crate::config::{LogConfig, LogFormat, LogRotation};
+    use crate::logging::{rotated_files, RotatingFile};
+
+    #[test]
+    fn test_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.log");
+        let config = LogConfig {
+            format: LogFormat::Text,
+            level: String::from("info"),
+            file: Some(path.clone()),
+            max_size_mb: None,
+            rotation: LogRotation::Hourly,
+            max_files: 2,
+            levels: BTreeMap::new(),
+        };
+        let mut file = RotatingFile::open(&path, &config).unwrap();
+        file.write_all(b"first\n").unwrap();
+        let hour = 60 * 60;
+        let current = file.period.unwrap() * hour;
+
+        // rotated on its size
+        assert!(!file.should_rotate(1024 * 1024, current));
+        file.config.max_size_mb = Some(1);
+        assert!(!file.should_rotate(1024, current));
+        assert!(file.should_rotate(1024 * 1024, current));
+
+        // rotated when the hour changes
+        let next = current + hour;
+        assert!(!file.should_rotate(6, next - 1));
+        assert!(file.should_rotate(6, next));
+        file.rotate(next).unwrap();
+        assert_eq!(rotated_files(&path).unwrap().len(), 1);
+        assert!(!file.should_rotate(6, next));
+
+        // only the latest rotated files are kept
+        file.rotate(next).unwrap();
+        file.rotate(next).unwrap();
+        let mut rotated = rotated_files(&path).unwrap();
+        rotated.sort();
+        assert_eq!(
+            rotated.into_iter().map(|(t, _)| t).collect::<Vec<_>>(),
+            vec![(next, 1), (next, 2)]
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+}
@@ -4,14 +4,15 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
+use async_channel::Receiver;
 use async_trait::async_trait;
 use base64::Engine;
-use cid::multihash::MultihashDigest;
 use cid::Cid;
 use fil_actors_runtime::cbor;
-use fvm_ipld_encoding::{to_vec, RawBytes};
+use fvm_ipld_encoding::RawBytes;
 use fvm_shared::address::Address;
 use fvm_shared::bigint::BigInt;
 use fvm_shared::clock::ChainEpoch;
@@ -25,19 +26,30 @@ use ipc_sdk::subnet_id::SubnetID;
 use num_traits::cast::ToPrimitive;
 use serde::de::DeserializeOwned;
 use serde_json::json;
+use tokio::time::sleep;
 
+use crate::config::subnet::{FvmSigner, GasConfig, MessageWaitConfig, SubnetMode};
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl, NO_PARAMS};
+use crate::lotus::gas::{fill_gas, raise_premium, GasEstimator};
 use crate::lotus::json::ToJson;
-use crate::lotus::message::chain::{ChainHeadResponse, GetTipSetByHeightResponse};
+use crate::lotus::message::chain::{ChainHeadResponse, GetTipSetByHeightResponse, HeadChange};
 use crate::lotus::message::ipc::{IPCReadGatewayStateResponse, IPCReadSubnetActorStateResponse};
 use crate::lotus::message::mpool::{
-    EstimateGasResponse, MpoolPushMessage, MpoolPushMessageResponse, MpoolPushMessageResponseInner,
+    message_cid, EstimateGasResponse, MpoolPushMessage, MpoolPushMessageResponse,
+    MpoolPushMessageResponseInner,
+};
+use crate::lotus::message::state::{
+    ActorResponse, ReadStateResponse, StateReplayResponse, StateWaitMsgResponse,
 };
-use crate::lotus::message::state::{ReadStateResponse, StateWaitMsgResponse};
 use crate::lotus::message::wallet::{WalletKeyType, WalletListResponse};
 use crate::lotus::message::CIDMap;
-use crate::lotus::{LotusClient, NetworkVersion};
+use crate::lotus::version::{NodeApi, VersionResponse};
+use crate::lotus::{LotusClient, MessageWaitTimeout, NetworkVersion};
+use crate::manager::activity::min_replacement_premium;
+use crate::manager::funds::ensure_funds;
+use crate::manager::key_usage::KeyOperation;
 use crate::manager::SubnetInfo;
+use crate::state::AgentState;
 
 pub type DefaultLotusJsonRPCClient = LotusJsonRPCClient<JsonRpcClientImpl>;
 
@@ -46,16 +58,29 @@ mod methods {
     pub const MPOOL_PUSH_MESSAGE: &str = "Filecoin.MpoolPushMessage";
     pub const MPOOL_PUSH: &str = "Filecoin.MpoolPush";
     pub const MPOOL_GET_NONCE: &str = "Filecoin.MpoolGetNonce";
+    pub const MPOOL_PENDING: &str = "Filecoin.MpoolPending";
     pub const STATE_WAIT_MSG: &str = "Filecoin.StateWaitMsg";
+    pub const STATE_SEARCH_MSG: &str = "Filecoin.StateSearchMsg";
+    pub const STATE_LIST_MESSAGES: &str = "Filecoin.StateListMessages";
+    pub const CHAIN_GET_MESSAGE: &str = "Filecoin.ChainGetMessage";
+    pub const STATE_REPLAY: &str = "Filecoin.StateReplay";
     pub const STATE_NETWORK_NAME: &str = "Filecoin.StateNetworkName";
     pub const STATE_NETWORK_VERSION: &str = "Filecoin.StateNetworkVersion";
     pub const STATE_ACTOR_CODE_CIDS: &str = "Filecoin.StateActorCodeCIDs";
+    pub const AUTH_VERIFY: &str = "Filecoin.AuthVerify";
     pub const WALLET_NEW: &str = "Filecoin.WalletNew";
     pub const WALLET_LIST: &str = "Filecoin.WalletList";
     pub const WALLET_BALANCE: &str = "Filecoin.WalletBalance";
     pub const WALLET_DEFAULT_ADDRESS: &str = "Filecoin.WalletDefaultAddress";
     pub const STATE_READ_STATE: &str = "Filecoin.StateReadState";
+    pub const STATE_GET_ACTOR: &str = "Filecoin.StateGetActor";
+    pub const CHAIN_READ_OBJ: &str = "Filecoin.ChainReadObj";
     pub const CHAIN_HEAD: &str = "Filecoin.ChainHead";
+    pub const CHAIN_NOTIFY: &str = "Filecoin.ChainNotify";
+    /// The notifications of the values and of the closing of a subscription channel.
+    pub const CHANNEL_VALUE: &str = "xrpc.ch.val";
+    pub const CHANNEL_CLOSE: &str = "xrpc.ch.close";
+    pub const VERSION: &str = "Filecoin.Version";
     pub const GET_TIPSET_BY_HEIGHT: &str = "Filecoin.ChainGetTipSetByHeight";
     pub const ESTIMATE_MESSAGE_GAS: &str = "Filecoin.GasEstimateMessageGas";
     pub const IPC_GET_PREV_CHECKPOINT_FOR_CHILD: &str = "Filecoin.IPCGetPrevCheckpointForChild";
@@ -69,6 +94,10 @@ mod methods {
     pub const IPC_LIST_BOTTOMUP_CHECKPOINTS: &str = "Filecoin.IPCListCheckpointsSerialized";
     pub const IPC_GET_TOPDOWN_MESSAGES: &str = "Filecoin.IPCGetTopDownMsgsSerialized";
     pub const IPC_GENESIS_EPOCH_FOR_SUBNET: &str = "Filecoin.IPCGetGenesisEpochForSubnet";
+
+    /// The methods the node would process twice if retried, e.g. signing a message with the
+    /// next nonce of the sender.
+    pub const NON_IDEMPOTENT: [&str; 2] = [MPOOL_PUSH_MESSAGE, WALLET_NEW];
 }
 
 /// The default state wait confidence value
@@ -82,6 +111,8 @@ const STATE_WAIT_LOOK_BACK_NO_LIMIT: i8 = -1;
 /// TODO: when set to false, lotus raises `found message with equal nonce as the one we are looking`
 /// TODO: error. Should check this again.
 const STATE_WAIT_ALLOW_REPLACE: bool = true;
+/// The interval between the searches of a message waited for with a confidence.
+const STATE_SEARCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 /// The struct implementation for Lotus Client API. It allows for multiple different trait
 /// extension.
@@ -103,15 +134,36 @@ const STATE_WAIT_ALLOW_REPLACE: bool = true;
 pub struct LotusJsonRPCClient<T: JsonRpcClient> {
     client: T,
     subnet: SubnetID,
+    /// The key of the node in the cache of the API versions.
+    endpoint: String,
     wallet_store: Option<Arc<RwLock<Wallet>>>,
+    signer: FvmSigner,
+    gas: GasConfig,
+    mode: SubnetMode,
+    /// The network the node must serve for messages to be sent to it.
+    expected_network_name: Option<String>,
+    /// Estimates the gas of the messages instead of the node, if set.
+    gas_estimator: Option<Arc<dyn GasEstimator>>,
+    /// How the messages sent are waited for, see [`Self::wait_message`].
+    message_wait: MessageWaitConfig,
+    /// The state of the agent the nonces, the API of the node and the keys used are kept in.
+    state: Arc<AgentState>,
 }
 
 impl<T: JsonRpcClient> LotusJsonRPCClient<T> {
     pub fn new(client: T, subnet: SubnetID) -> Self {
         Self {
+            endpoint: subnet.to_string(),
             client,
             subnet,
             wallet_store: None,
+            signer: FvmSigner::Agent,
+            gas: GasConfig::default(),
+            mode: SubnetMode::default(),
+            expected_network_name: None,
+            gas_estimator: None,
+            message_wait: MessageWaitConfig::default(),
+            state: Arc::default(),
         }
     }
 
@@ -121,11 +173,94 @@ impl<T: JsonRpcClient> LotusJsonRPCClient<T> {
         wallet_store: Arc<RwLock<Wallet>>,
     ) -> Self {
         Self {
+            endpoint: subnet.to_string(),
             client,
             subnet,
             wallet_store: Some(wallet_store),
+            signer: FvmSigner::Agent,
+            gas: GasConfig::default(),
+            mode: SubnetMode::default(),
+            expected_network_name: None,
+            gas_estimator: None,
+            message_wait: MessageWaitConfig::default(),
+            state: Arc::default(),
         }
     }
+
+    /// The subnet the client is connected to.
+    pub fn subnet(&self) -> &SubnetID {
+        &self.subnet
+    }
+
+    /// Sets who signs the messages pushed to the mpool.
+    pub fn with_signer(mut self, signer: FvmSigner) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    /// Sets the gas limit policies applied to the messages pushed with [`Self::push_operation`].
+    pub fn with_gas_config(mut self, gas: GasConfig) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    /// Sets the estimator of the gas parameters of the messages that do not override them, in
+    /// place of the estimation of the node.
+    pub fn with_gas_estimator(mut self, estimator: Arc<dyn GasEstimator>) -> Self {
+        self.gas_estimator = Some(estimator);
+        self
+    }
+
+    /// Sets the confidence and the timeout of the messages waited for with
+    /// [`Self::wait_message`].
+    pub fn with_message_wait(mut self, message_wait: MessageWaitConfig) -> Self {
+        self.message_wait = message_wait;
+        self
+    }
+
+    /// Shares the state of the agent, e.g. the nonces reserved by its other clients.
+    pub fn with_state(mut self, state: Arc<AgentState>) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Sets the mode of the subnet, messages are not pushed to observed subnets.
+    pub fn with_mode(mut self, mode: SubnetMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the network the node must serve, messages are not sent to nodes serving another one.
+    pub fn with_expected_network_name(mut self, network_name: Option<String>) -> Self {
+        self.expected_network_name = network_name;
+        self
+    }
+
+    /// Checks that the node serves the network set with [`Self::with_expected_network_name`],
+    /// if any, so that a subnet pointed at the endpoint of another chain does not get messages
+    /// meant for it.
+    pub async fn ensure_network(&self) -> Result<()> {
+        let expected = match &self.expected_network_name {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+        let network_name = self.state_network_name().await?;
+        if &network_name != expected {
+            return Err(anyhow!(
+                "node at {} serves network {network_name:}, expected {expected:} for subnet {}",
+                self.endpoint,
+                self.subnet
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sets the endpoint of the node, so that clients of the same subnet connected to different
+    /// nodes negotiate their API separately.
+    fn with_endpoint(mut self, endpoint: &url::Url) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
 }
 
 #[async_trait]
@@ -134,6 +269,9 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
         &self,
         msg: MpoolPushMessage,
     ) -> Result<MpoolPushMessageResponseInner> {
+        self.mode.ensure_can_submit(&self.subnet)?;
+        self.ensure_network().await?;
+
         let nonce = msg
             .nonce
             .map(|n| serde_json::Value::Number(n.into()))
@@ -143,10 +281,14 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             t.map(|n| serde_json::Value::Number(n.atto().to_u64().unwrap().into()))
                 .unwrap_or(serde_json::Value::Null)
         };
+        let to_value_str = |t: Option<TokenAmount>| {
+            t.map(|n| serde_json::Value::String(n.atto().to_string()))
+                .unwrap_or(serde_json::Value::Null)
+        };
         let gas_limit = to_value(msg.gas_limit);
-        let gas_premium = to_value(msg.gas_premium);
-        let gas_fee_cap = to_value(msg.gas_fee_cap);
-        let max_fee = to_value(msg.max_fee);
+        let gas_premium = to_value_str(msg.gas_premium);
+        let gas_fee_cap = to_value_str(msg.gas_fee_cap);
+        let max_fee = to_value_str(msg.max_fee);
 
         // refer to: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolpushmessage
         let params = json!([
@@ -159,14 +301,14 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
 
                 // THESE ALL WILL AUTO POPULATE if null
                 "nonce": nonce,
-                "gas_limit": gas_limit,
-                "gas_fee_cap": gas_fee_cap,
-                "gas_premium": gas_premium,
+                "GasLimit": gas_limit,
+                "GasFeeCap": gas_fee_cap,
+                "GasPremium": gas_premium,
                 "cid": CIDMap::from(msg.cid),
                 "version": serde_json::Value::Null,
             },
             {
-                "max_fee": max_fee
+                "MaxFee": max_fee
             }
         ]);
 
@@ -174,15 +316,37 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             .client
             .request::<MpoolPushMessageResponse>(methods::MPOOL_PUSH_MESSAGE, params)
             .await?;
-        log::debug!("received mpool_push_message response: {r:?}");
+        tracing::debug!("received mpool_push_message response: {r:?}");
+        self.state.counters.messages_sent.inc();
 
         Ok(r.message)
     }
 
     async fn mpool_push(&self, mut msg: MpoolPushMessage) -> Result<Cid> {
-        if msg.nonce.is_none() {
+        self.mode.ensure_can_submit(&self.subnet)?;
+        if self.signer == FvmSigner::Node {
+            return self.mpool_push_message(msg).await?.cid();
+        }
+
+        let from = msg.from;
+        let reserved = self.reserve_nonce(&mut msg).await?;
+        let pushed: Result<Cid> = try {
+            let mut msg = self.mpool_prepare(msg).await?;
+            if let Some(replaced) = reserved.as_ref().and_then(|r| r.replaces.as_ref()) {
+                raise_premium(&mut msg, &min_replacement_premium(replaced));
+            }
+            let signature = self.sign_mpool_message(&msg)?;
+            self.mpool_push_signed(msg, signature).await?
+        };
+        self.release_nonce(&from, reserved, &pushed);
+        pushed
+    }
+
+    async fn mpool_prepare(&self, mut msg: MpoolPushMessage) -> Result<MpoolPushMessage> {
+        // `MpoolPushMessage` rejects messages with a nonce, the node assigns it when signing
+        if msg.nonce.is_none() && self.signer != FvmSigner::Node {
             let nonce = self.mpool_nonce(&msg.from).await?;
-            log::info!(
+            tracing::info!(
                 "sender: {:} with nonce: {nonce:} in subnet: {:}",
                 msg.from,
                 self.subnet
@@ -194,13 +358,28 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             msg.version = Some(0);
         }
 
-        self.estimate_message_gas(&mut msg).await?;
-        log::debug!("estimated gas for message: {msg:?}");
+        if msg.gas_limit.is_none() || msg.gas_fee_cap.is_none() || msg.gas_premium.is_none() {
+            let estimate = match &self.gas_estimator {
+                Some(estimator) => estimator.estimate_gas(&msg).await?,
+                None => self.estimate_gas(&msg).await?,
+            };
+            fill_gas(
+                &mut msg,
+                estimate,
+                self.gas.fee_cap_multiplier,
+                self.gas.premium_multiplier,
+            );
+            tracing::debug!("estimated gas for message: {msg:?}");
+        }
 
-        let signature = self.sign_mpool_message(&msg)?;
+        Ok(msg)
+    }
 
+    async fn mpool_push_signed(&self, msg: MpoolPushMessage, signature: Signature) -> Result<Cid> {
+        self.mode.ensure_can_submit(&self.subnet)?;
+        self.ensure_network().await?;
         let params = create_signed_message_params(msg, signature);
-        log::debug!(
+        tracing::debug!(
             "message to push to mpool: {params:?} in subnet: {:?}",
             self.subnet
         );
@@ -209,35 +388,206 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             .client
             .request::<CIDMap>(methods::MPOOL_PUSH, params)
             .await?;
-        log::debug!("received mpool_push_message response: {r:?}");
+        tracing::debug!("received mpool_push_message response: {r:?}");
+        self.state.counters.messages_sent.inc();
 
         Cid::try_from(r)
     }
 
+    async fn mpool_pending(&self, from: Option<Address>) -> Result<Vec<MpoolPushMessageResponse>> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolpending
+        // the messages pending at the chain head
+        let params = json!([serde_json::Value::Null]);
+
+        let r = self
+            .client
+            .request::<Vec<MpoolPushMessageResponse>>(methods::MPOOL_PENDING, params)
+            .await?;
+        tracing::debug!("received {} pending messages from mpool", r.len());
+
+        // the node does not filter the pending messages by sender
+        let from = match from {
+            Some(from) => from,
+            None => return Ok(r),
+        };
+        let mut pending = vec![];
+        for signed in r {
+            if signed.message.from()? == from {
+                pending.push(signed);
+            }
+        }
+        Ok(pending)
+    }
+
+    async fn mpool_nonce(&self, address: &Address) -> Result<u64> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolgetnonce
+        let params = json!([address.to_string()]);
+        let r = self
+            .client
+            .request::<u64>(methods::MPOOL_GET_NONCE, params)
+            .await;
+        if r.is_err() {
+            let e = r.unwrap_err();
+            if e.to_string().contains("resolution lookup failed") {
+                return Ok(0);
+            }
+            return Err(e);
+        }
+        Ok(r.unwrap())
+    }
+
     async fn state_wait_msg(&self, cid: Cid) -> Result<StateWaitMsgResponse> {
         // refer to: https://lotus.filecoin.io/reference/lotus/state/#statewaitmsg
-        let params = json!([
-            CIDMap::from(cid),
+        let params = self.node_api().await?.state_wait_msg_params(
+            json!(CIDMap::from(cid)),
             STATE_WAIT_CONFIDENCE,
             STATE_WAIT_LOOK_BACK_NO_LIMIT,
             STATE_WAIT_ALLOW_REPLACE,
-        ]);
+        );
 
         let r = self
             .client
             .request::<StateWaitMsgResponse>(methods::STATE_WAIT_MSG, params)
             .await?;
-        log::debug!("received state_wait_msg response: {r:?}");
+        tracing::debug!("received state_wait_msg response: {r:?}");
         Ok(r)
     }
 
+    async fn state_search_msg(&self, cid: Cid) -> Result<Option<StateWaitMsgResponse>> {
+        let params = self.node_api().await?.state_search_msg_params(
+            json!(CIDMap::from(cid)),
+            STATE_WAIT_LOOK_BACK_NO_LIMIT,
+            STATE_WAIT_ALLOW_REPLACE,
+        );
+
+        let r = self
+            .client
+            .request::<Option<StateWaitMsgResponse>>(methods::STATE_SEARCH_MSG, params)
+            .await?;
+        tracing::debug!("received state_search_msg response: {r:?}");
+        Ok(r)
+    }
+
+    async fn state_wait_msg_with_confidence(
+        &self,
+        cid: Cid,
+        confidence: u64,
+        timeout: Duration,
+    ) -> Result<StateWaitMsgResponse> {
+        // searched rather than waited for, a long request to the node would outlive the timeout
+        let started = Instant::now();
+        let mut executed_at = None;
+        loop {
+            if let Some(r) = self.state_search_msg(cid).await? {
+                let head = self.chain_head().await?.height;
+                if head >= r.height + confidence {
+                    return Ok(r);
+                }
+                executed_at = Some(r.height);
+            }
+
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return Err(MessageWaitTimeout {
+                    cid,
+                    confidence,
+                    timeout,
+                    executed_at,
+                }
+                .into());
+            }
+            sleep(STATE_SEARCH_POLL_INTERVAL.min(timeout - elapsed)).await;
+        }
+    }
+
+    async fn state_list_messages(
+        &self,
+        from: Option<Address>,
+        to: Option<Address>,
+        to_height: ChainEpoch,
+    ) -> Result<Vec<Cid>> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/state/#statelistmessages
+        // the messages are listed from the chain head
+        let params = json!([
+            {
+                "From": from.map(|a| a.to_string()),
+                "To": to.map(|a| a.to_string()),
+            },
+            serde_json::Value::Null,
+            to_height
+        ]);
+
+        let r = self
+            .client
+            .request::<Option<Vec<CIDMap>>>(methods::STATE_LIST_MESSAGES, params)
+            .await?;
+        tracing::debug!("received state_list_messages response: {r:?}");
+        r.unwrap_or_default()
+            .into_iter()
+            .map(Cid::try_from)
+            .collect()
+    }
+
+    async fn chain_get_message(&self, cid: Cid) -> Result<MpoolPushMessageResponseInner> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/chain/#chaingetmessage
+        let r = self
+            .client
+            .request::<MpoolPushMessageResponseInner>(
+                methods::CHAIN_GET_MESSAGE,
+                json!([CIDMap::from(cid)]),
+            )
+            .await?;
+        tracing::debug!("received chain_get_message response: {r:?}");
+        Ok(r)
+    }
+
+    async fn state_replay_gas_cost(&self, cid: Cid) -> Result<TokenAmount> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/state/#statereplay
+        // the message is looked up from the chain head
+        let r = self
+            .client
+            .request::<StateReplayResponse>(
+                methods::STATE_REPLAY,
+                json!([serde_json::Value::Null, CIDMap::from(cid)]),
+            )
+            .await?;
+        tracing::debug!("received state_replay response: {r:?}");
+        Ok(TokenAmount::from_atto(BigInt::from_str(
+            &r.gas_cost.total_cost,
+        )?))
+    }
+
+    async fn node_api(&self) -> Result<NodeApi> {
+        if let Some(api) = self.state.node_apis.get(&self.endpoint) {
+            return Ok(api);
+        }
+
+        // refer to: https://lotus.filecoin.io/reference/lotus/common/#version
+        let r = self
+            .client
+            .request::<VersionResponse>(methods::VERSION, NO_PARAMS)
+            .await?;
+        tracing::debug!("received version response: {r:?}");
+
+        let api = NodeApi::try_from(r)
+            .map_err(|e| anyhow!("node of subnet {} not supported: {e:}", self.subnet))?;
+        tracing::info!(
+            "node of subnet {} runs {} with API {}",
+            self.subnet,
+            api.version,
+            api.api_version
+        );
+        self.state.node_apis.insert(&self.endpoint, api.clone());
+        Ok(api)
+    }
+
     async fn state_network_name(&self) -> Result<String> {
         // refer to: https://lotus.filecoin.io/reference/lotus/state/#statenetworkname
         let r = self
             .client
             .request::<String>(methods::STATE_NETWORK_NAME, serde_json::Value::Null)
             .await?;
-        log::debug!("received state_network_name response: {r:?}");
+        tracing::debug!("received state_network_name response: {r:?}");
         Ok(r)
     }
 
@@ -250,7 +600,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             .request::<NetworkVersion>(methods::STATE_NETWORK_VERSION, params)
             .await?;
 
-        log::debug!("received state_network_version response: {r:?}");
+        tracing::debug!("received state_network_version response: {r:?}");
         Ok(r)
     }
 
@@ -271,17 +621,27 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             cids.insert(key, Cid::try_from(cid_map)?);
         }
 
-        log::debug!("received state_actor_manifest_cid response: {cids:?}");
+        tracing::debug!("received state_actor_manifest_cid response: {cids:?}");
         Ok(cids)
     }
 
+    async fn auth_verify(&self, token: &str) -> Result<Vec<String>> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/auth/#authverify
+        let r = self
+            .client
+            .request::<Vec<String>>(methods::AUTH_VERIFY, json!([token]))
+            .await?;
+        tracing::debug!("received auth_verify response: {r:?}");
+        Ok(r)
+    }
+
     async fn wallet_default(&self) -> Result<Address> {
         // refer to: https://lotus.filecoin.io/reference/lotus/wallet/#walletdefaultaddress
         let r = self
             .client
             .request::<String>(methods::WALLET_DEFAULT_ADDRESS, json!({}))
             .await?;
-        log::debug!("received wallet_default response: {r:?}");
+        tracing::debug!("received wallet_default response: {r:?}");
 
         let addr = Address::from_str(&r)?;
         Ok(addr)
@@ -293,7 +653,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             .client
             .request::<WalletListResponse>(methods::WALLET_LIST, json!({}))
             .await?;
-        log::debug!("received wallet_list response: {r:?}");
+        tracing::debug!("received wallet_list response: {r:?}");
         Ok(r)
     }
 
@@ -304,7 +664,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             .client
             .request::<String>(methods::WALLET_NEW, json!([key_type_str]))
             .await?;
-        log::debug!("received wallet_new response: {r:?}");
+        tracing::debug!("received wallet_new response: {r:?}");
         Ok(r)
     }
 
@@ -314,7 +674,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             .client
             .request::<String>(methods::WALLET_BALANCE, json!([address.to_string()]))
             .await?;
-        log::debug!("received wallet_balance response: {r:?}");
+        tracing::debug!("received wallet_balance response: {r:?}");
 
         let v = BigInt::from_str(&r)?;
         Ok(TokenAmount::from_atto(v))
@@ -333,19 +693,79 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
                 json!([address.to_string(), [CIDMap::from(tipset)]]),
             )
             .await?;
-        log::debug!("received read_state response: {r:?}");
+        tracing::debug!("received read_state response: {r:?}");
         Ok(r)
     }
 
+    async fn state_get_actor(&self, address: &Address) -> Result<ActorResponse> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/state/#stategetactor
+        let r = self
+            .client
+            .request::<ActorResponse>(
+                methods::STATE_GET_ACTOR,
+                json!([address.to_string(), serde_json::Value::Null]),
+            )
+            .await?;
+        tracing::debug!("received state_get_actor response: {r:?}");
+        Ok(r)
+    }
+
+    async fn chain_read_obj(&self, cid: Cid) -> Result<Vec<u8>> {
+        // refer to: https://lotus.filecoin.io/reference/lotus/chain/#chainreadobj
+        let r = self
+            .client
+            .request::<String>(methods::CHAIN_READ_OBJ, json!([CIDMap::from(cid)]))
+            .await?;
+        Ok(base64::engine::general_purpose::STANDARD.decode(r)?)
+    }
+
     async fn chain_head(&self) -> Result<ChainHeadResponse> {
         let r = self
             .client
             .request::<ChainHeadResponse>(methods::CHAIN_HEAD, NO_PARAMS)
             .await?;
-        log::debug!("received chain_head response: {r:?}");
+        tracing::debug!("received chain_head response: {r:?}");
         Ok(r)
     }
 
+    async fn chain_notify(&self) -> Result<Receiver<Vec<HeadChange>>> {
+        let notifications = self.client.subscribe(methods::CHAIN_NOTIFY).await?;
+        let (send, recv) = async_channel::unbounded();
+        let subnet = self.subnet.clone();
+
+        tokio::spawn(async move {
+            while let Ok(msg) = notifications.recv().await {
+                match msg.get("method").and_then(serde_json::Value::as_str) {
+                    Some(methods::CHANNEL_VALUE) => match decode_head_changes(msg) {
+                        Ok(changes) => {
+                            if send.send(changes).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!("cannot decode head change of subnet {subnet:}: {e:}")
+                        }
+                    },
+                    Some(methods::CHANNEL_CLOSE) => {
+                        tracing::debug!("node closed the head subscription of subnet {subnet:}");
+                        break;
+                    }
+                    // the response to the subscription, with the id of the channel
+                    _ => {
+                        if let Some(e) = msg.get("error") {
+                            tracing::warn!(
+                                "cannot subscribe to the heads of subnet {subnet:}: {e:}"
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(recv)
+    }
+
     async fn current_epoch(&self) -> Result<ChainEpoch> {
         Ok(self.chain_head().await?.height as ChainEpoch)
     }
@@ -362,7 +782,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
                 json!([epoch, [CIDMap::from(tip_set)]]),
             )
             .await?;
-        log::debug!("received get_tipset_by_height response: {r:?}");
+        tracing::debug!("received get_tipset_by_height response: {r:?}");
         Ok(r)
     }
 
@@ -380,12 +800,15 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             ipc_gateway::Method::SubmitTopDownCheckpoint as MethodNum,
             cbor::serialize(&checkpoint, "topdown_checkpoint")?.to_vec(),
         );
-        let message_cid = self.mpool_push(message).await.map_err(|e| {
-            log::error!("error submitting top down checkpoint at epoch {epoch:} at gateway: {gateway_addr:}");
-            e
-        })?;
+        let message_cid = self
+            .push_operation(KeyOperation::Checkpoint, message)
+            .await
+            .map_err(|e| {
+                tracing::error!("error submitting top down checkpoint at epoch {epoch:} at gateway: {gateway_addr:}");
+                e
+            })?;
 
-        self.state_wait_msg(message_cid)
+        self.wait_message(message_cid)
             .await
             .map(|r| r.height as ChainEpoch)
     }
@@ -401,8 +824,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
         let params = json!([gateway_addr.to_string(), child_subnet_id.to_json()]);
 
         let r = self
-            .client
-            .request::<Option<CIDMap>>(methods::IPC_GET_PREV_CHECKPOINT_FOR_CHILD, params)
+            .ipc_request::<Option<CIDMap>>(methods::IPC_GET_PREV_CHECKPOINT_FOR_CHILD, params)
             .await?;
         Ok(r)
     }
@@ -413,8 +835,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
         epoch: ChainEpoch,
     ) -> Result<BottomUpCheckpoint> {
         let r = self
-            .client
-            .request::<String>(
+            .ipc_request::<String>(
                 methods::IPC_GET_CHECKPOINT_TEMPLATE,
                 json!([gateway_addr.to_string(), epoch]),
             )
@@ -433,11 +854,10 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     ) -> Result<BottomUpCheckpoint> {
         let params = json!([subnet_id.to_json(), epoch]);
         let r = self
-            .client
-            .request::<String>(methods::IPC_GET_CHECKPOINT, params)
+            .ipc_request::<String>(methods::IPC_GET_CHECKPOINT, params)
             .await
             .map_err(|e| {
-                log::debug!(
+                tracing::debug!(
                     "error getting checkpoint for epoch {epoch:} in subnet {:?}: {}",
                     subnet_id,
                     e.to_string()
@@ -457,8 +877,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     ) -> Result<IPCReadGatewayStateResponse> {
         let params = json!([gateway_addr.to_string(), [CIDMap::from(tip_set)]]);
         let r = self
-            .client
-            .request::<IPCReadGatewayStateResponse>(methods::IPC_READ_GATEWAY_STATE, params)
+            .ipc_request::<IPCReadGatewayStateResponse>(methods::IPC_READ_GATEWAY_STATE, params)
             .await?;
         Ok(r)
     }
@@ -469,11 +888,10 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
         tip_set: Cid,
     ) -> Result<IPCReadSubnetActorStateResponse> {
         let params = json!([subnet_id.to_json(), [CIDMap::from(tip_set)]]);
-        log::debug!("sending {params:?}");
+        tracing::debug!("sending {params:?}");
 
         let r = self
-            .client
-            .request::<IPCReadSubnetActorStateResponse>(
+            .ipc_request::<IPCReadSubnetActorStateResponse>(
                 methods::IPC_READ_SUBNET_ACTOR_STATE,
                 params,
             )
@@ -484,8 +902,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     async fn ipc_list_child_subnets(&self, gateway_addr: Address) -> Result<Vec<SubnetInfo>> {
         let params = json!([gateway_addr.to_string()]);
         let r = self
-            .client
-            .request::<Option<Vec<SubnetInfo>>>(methods::IPC_LIST_CHILD_SUBNETS, params)
+            .ipc_request::<Option<Vec<SubnetInfo>>>(methods::IPC_LIST_CHILD_SUBNETS, params)
             .await?;
         Ok(r.unwrap_or_default())
     }
@@ -498,8 +915,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     ) -> Result<bool> {
         let params = json!([subnet_id.to_json(), epoch, validator.to_string()]);
         let r = self
-            .client
-            .request::<bool>(methods::IPC_VALIDATOR_HAS_VOTED_BOTTOMUP, params)
+            .ipc_request::<bool>(methods::IPC_VALIDATOR_HAS_VOTED_BOTTOMUP, params)
             .await?;
         Ok(r)
     }
@@ -512,8 +928,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     ) -> Result<bool> {
         let params = json!([gateway_addr.to_string(), epoch, validator.to_string()]);
         let r = self
-            .client
-            .request::<bool>(methods::IPC_VALIDATOR_HAS_VOTED_TOPDOWN, params)
+            .ipc_request::<bool>(methods::IPC_VALIDATOR_HAS_VOTED_TOPDOWN, params)
             .await?;
         Ok(r)
     }
@@ -532,8 +947,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
             nonce
         ]);
         let r = self
-            .client
-            .request::<Vec<String>>(methods::IPC_GET_TOPDOWN_MESSAGES, params)
+            .ipc_request::<Vec<String>>(methods::IPC_GET_TOPDOWN_MESSAGES, params)
             .await?;
 
         let msgs = r
@@ -558,8 +972,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     ) -> Result<ChainEpoch> {
         let params = json!([gateway_addr.to_string(), subnet_id.to_json()]);
         let r = self
-            .client
-            .request::<ChainEpoch>(methods::IPC_GENESIS_EPOCH_FOR_SUBNET, params)
+            .ipc_request::<ChainEpoch>(methods::IPC_GENESIS_EPOCH_FOR_SUBNET, params)
             .await?;
         Ok(r)
     }
@@ -572,8 +985,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     ) -> Result<Vec<BottomUpCheckpoint>> {
         let params = json!([subnet_id.to_json(), from_epoch, to_epoch]);
         let r = self
-            .client
-            .request::<Vec<String>>(methods::IPC_LIST_BOTTOMUP_CHECKPOINTS, params)
+            .ipc_request::<Vec<String>>(methods::IPC_LIST_BOTTOMUP_CHECKPOINTS, params)
             .await?;
 
         let checkpoints = r
@@ -592,51 +1004,180 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     }
 }
 
+/// A nonce reserved for a message of the agent.
+struct ReservedNonce {
+    nonce: u64,
+    /// The gas premium of the abandoned message the nonce was reclaimed from, if any.
+    replaces: Option<TokenAmount>,
+}
+
 impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
+    /// Pushes the message performing `operation` to the mpool. The estimated gas limit of the
+    /// message is adjusted with the policy of the operation, the message is only pushed if the
+    /// balance of the sender covers its maximum fee and value, and the signature is recorded in
+    /// the key usage log.
+    pub async fn push_operation(
+        &self,
+        operation: KeyOperation,
+        mut msg: MpoolPushMessage,
+    ) -> anyhow::Result<Cid> {
+        let from = msg.from;
+        // an overridden gas limit is used as it is
+        let estimated_limit = msg.gas_limit.is_none();
+        let reserved = self.reserve_nonce(&mut msg).await?;
+        let pushed: anyhow::Result<Cid> = try {
+            let mut msg = self.mpool_prepare(msg).await?;
+            if let Some(replaced) = reserved.as_ref().and_then(|r| r.replaces.as_ref()) {
+                raise_premium(&mut msg, &min_replacement_premium(replaced));
+            }
+            if let Some(estimated) = msg
+                .gas_limit
+                .as_ref()
+                .filter(|_| estimated_limit)
+                .and_then(|l| l.atto().to_u64())
+            {
+                let limit = operation.gas_policy(&self.gas).apply(estimated);
+                tracing::debug!(
+                    "gas limit of {operation:?} message adjusted from {estimated:} to {limit:}"
+                );
+                msg.gas_limit = Some(TokenAmount::from_atto(limit));
+            }
+            if let (Some(limit), Some(fee_cap)) = (&msg.gas_limit, &msg.gas_fee_cap) {
+                let required =
+                    TokenAmount::from_atto(fee_cap.atto() * limit.atto() + msg.value.atto());
+                let balance = self.wallet_balance(&from).await?;
+                ensure_funds(from, balance, required)?;
+            }
+            self.mpool_push(msg).await?
+        };
+        self.release_nonce(&from, reserved, &pushed);
+
+        let message_cid = pushed?;
+        self.state
+            .key_usage
+            .record(&from, operation, &self.subnet, message_cid);
+        Ok(message_cid)
+    }
+
+    /// Reserves the nonce of `msg` in the nonces of the agent if it is not set and the agent signs
+    /// it, so that the messages sent concurrently from the same account do not collide, or
+    /// reclaims the nonce of an abandoned message of the account. Returns the nonce reserved, if
+    /// any.
+    async fn reserve_nonce(
+        &self,
+        msg: &mut MpoolPushMessage,
+    ) -> anyhow::Result<Option<ReservedNonce>> {
+        // the node assigns the nonces of the messages it signs, see `mpool_prepare`
+        if msg.nonce.is_some() || self.signer == FvmSigner::Node {
+            return Ok(None);
+        }
+        if let Some((nonce, premium)) = self.state.nonces.reclaim(&self.subnet, &msg.from) {
+            tracing::info!(
+                "reclaiming nonce {nonce:} of the abandoned message of {} in subnet {}",
+                msg.from,
+                self.subnet
+            );
+            msg.nonce = Some(nonce);
+            return Ok(Some(ReservedNonce {
+                nonce,
+                replaces: Some(premium),
+            }));
+        }
+        let node_next = self.mpool_nonce(&msg.from).await?;
+        let nonce = self
+            .state
+            .nonces
+            .reserve(&self.subnet, &msg.from, node_next);
+        tracing::debug!(
+            "reserved nonce {nonce:} of {} in subnet {}",
+            msg.from,
+            self.subnet
+        );
+        msg.nonce = Some(nonce);
+        Ok(Some(ReservedNonce {
+            nonce,
+            replaces: None,
+        }))
+    }
+
+    /// Gives back the nonce `reserved` for a message of `from` if it could not be pushed, a
+    /// reclaimed nonce being abandoned again.
+    fn release_nonce<R>(
+        &self,
+        from: &Address,
+        reserved: Option<ReservedNonce>,
+        pushed: &anyhow::Result<R>,
+    ) {
+        match (reserved, pushed) {
+            (
+                Some(ReservedNonce {
+                    nonce,
+                    replaces: Some(premium),
+                }),
+                Err(_),
+            ) => self
+                .state
+                .nonces
+                .abandon(&self.subnet, from, nonce, premium),
+            (Some(ReservedNonce { nonce, .. }), Err(_)) => {
+                self.state.nonces.release(&self.subnet, from, nonce)
+            }
+            _ => {}
+        }
+    }
+
+    /// Waits for a message sent by the agent with the confidence and the timeout of the subnet.
+    pub async fn wait_message(&self, cid: Cid) -> anyhow::Result<StateWaitMsgResponse> {
+        self.state_wait_msg_with_confidence(
+            cid,
+            self.message_wait.confidence,
+            self.message_wait.timeout(),
+        )
+        .await
+    }
+
+    /// Sends the request of an IPC method, only served by the eudico nodes with a recent API.
+    async fn ipc_request<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<R> {
+        let api = self.node_api().await?;
+        api.ensure_ipc(method)?;
+        self.client
+            .request::<R>(method, params)
+            .await
+            .map_err(|e| api.explain(method, e))
+    }
+
     fn sign_mpool_message(&self, msg: &MpoolPushMessage) -> anyhow::Result<Signature> {
         if self.wallet_store.is_none() {
             return Err(anyhow!("key store not set, function not supported"));
         }
 
-        let message = fvm_shared::message::Message {
-            version: msg
-                .version
-                .ok_or_else(|| anyhow!("version should not be empty"))? as u64,
-            from: msg.from,
-            to: msg.to,
-            sequence: msg
-                .nonce
-                .ok_or_else(|| anyhow!("nonce should not be empty"))?,
-            value: msg.value.clone(),
-            method_num: msg.method,
-            params: RawBytes::from(msg.params.clone()),
-            gas_limit: msg
-                .gas_limit
-                .as_ref()
-                .ok_or_else(|| anyhow!("gas_limit should not be empty"))?
-                .atto()
-                .to_u64()
-                .unwrap() as u64,
-            gas_fee_cap: msg
-                .gas_fee_cap
-                .as_ref()
-                .ok_or_else(|| anyhow!("gas_fee_cap should not be empty"))?
-                .clone(),
-            gas_premium: msg
-                .gas_premium
-                .as_ref()
-                .ok_or_else(|| anyhow!("gas_premium should not be empty"))?
-                .clone(),
-        };
-
-        let hash = cid::multihash::Code::Blake2b256.digest(&to_vec(&message)?);
-        let msg_cid = Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, hash).to_bytes();
+        let message = fvm_shared::message::Message::try_from(msg)?;
+        let msg_cid = message_cid(&message)?.to_bytes();
 
         let mut wallet_store = self.wallet_store.as_ref().unwrap().write().unwrap();
+        if !wallet_store.has_key(&msg.from) {
+            return Err(anyhow!(
+                "key of {} not found in the agent wallet, import it with `ipc-agent wallet import` or set `signer = \"node\"` in the config of subnet {} to sign with the node keystore",
+                msg.from,
+                self.subnet
+            ));
+        }
         Ok(wallet_store.sign(&msg.from, &msg_cid)?)
     }
 
-    async fn estimate_message_gas(&self, msg: &mut MpoolPushMessage) -> anyhow::Result<()> {
+    async fn estimate_message_gas(
+        &self,
+        msg: &MpoolPushMessage,
+    ) -> anyhow::Result<EstimateGasResponse> {
+        let gas_limit = msg
+            .gas_limit
+            .as_ref()
+            .and_then(|l| l.atto().to_u64())
+            .unwrap_or(0);
         let params = json!([
             {
                 "Version": msg.version.unwrap_or(0),
@@ -647,7 +1188,8 @@ impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
                 "Params": msg.params,
                 "Nonce": msg.nonce,
 
-                "GasLimit": 0,
+                // the node only estimates the gas limit if it is not set
+                "GasLimit": gas_limit,
                 "GasFeeCap": "0",
                 "GasPremium": "0",
 
@@ -657,56 +1199,87 @@ impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
             []
         ]);
 
-        let gas = self
-            .client
+        self.client
             .request::<EstimateGasResponse>(methods::ESTIMATE_MESSAGE_GAS, params)
-            .await?;
-
-        msg.gas_fee_cap = gas.gas_fee_cap;
-        msg.gas_limit = gas.gas_limit;
-        msg.gas_premium = gas.gas_premium;
-
-        Ok(())
+            .await
     }
+}
 
-    async fn mpool_nonce(&self, address: &Address) -> anyhow::Result<u64> {
-        let params = json!([address.to_string()]);
-        let r = self
-            .client
-            .request::<u64>(methods::MPOOL_GET_NONCE, params)
-            .await;
-        if r.is_err() {
-            let e = r.unwrap_err();
-            if e.to_string().contains("resolution lookup failed") {
-                return Ok(0);
-            }
-            return Err(e);
-        }
-        Ok(r.unwrap())
+/// The estimation of the node, used unless another estimator is set.
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> GasEstimator for LotusJsonRPCClient<T> {
+    async fn estimate_gas(&self, msg: &MpoolPushMessage) -> Result<EstimateGasResponse> {
+        self.estimate_message_gas(msg).await
     }
 }
 
 impl LotusJsonRPCClient<JsonRpcClientImpl> {
     /// A constructor that returns a `LotusJsonRPCClient` from a `Subnet`. The returned
-    /// `LotusJsonRPCClient` makes requests to the URL defined in the `Subnet`.
-    pub fn from_subnet(subnet: &crate::config::Subnet) -> Self {
+    /// `LotusJsonRPCClient` makes requests to the URL defined in the `Subnet`, sharing `state`
+    /// with the other clients of the agent.
+    pub fn from_subnet(subnet: &crate::config::Subnet, state: Arc<AgentState>) -> Self {
         let url = subnet.rpc_http().clone();
-        let auth_token = subnet.auth_token();
-        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref());
+        let jsonrpc_client = subnet_jsonrpc_client(subnet, &state);
         LotusJsonRPCClient::new(jsonrpc_client, subnet.id.clone())
+            .with_state(state)
+            .with_endpoint(&url)
+            .with_signer(subnet.fvm_signer())
+            .with_gas_config(subnet.gas.clone())
+            .with_mode(subnet.mode)
+            .with_expected_network_name(subnet.expected_network_name().cloned())
+            .with_message_wait(subnet.message_wait())
     }
 
     pub fn from_subnet_with_wallet_store(
         subnet: &crate::config::Subnet,
         wallet_store: Arc<RwLock<Wallet>>,
+        state: Arc<AgentState>,
     ) -> Self {
         let url = subnet.rpc_http().clone();
-        let auth_token = subnet.auth_token();
-        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref());
+        let jsonrpc_client = subnet_jsonrpc_client(subnet, &state);
         LotusJsonRPCClient::new_with_wallet_store(jsonrpc_client, subnet.id.clone(), wallet_store)
+            .with_state(state)
+            .with_endpoint(&url)
+            .with_signer(subnet.fvm_signer())
+            .with_gas_config(subnet.gas.clone())
+            .with_mode(subnet.mode)
+            .with_expected_network_name(subnet.expected_network_name().cloned())
+            .with_message_wait(subnet.message_wait())
     }
 }
 
+/// The json rpc client of the node of `subnet`, sharing the connections to the node with the
+/// other clients of the subnet in `state`.
+fn subnet_jsonrpc_client(subnet: &crate::config::Subnet, state: &AgentState) -> JsonRpcClientImpl {
+    let url = subnet.rpc_http();
+    let auth_token = subnet.auth_token();
+    let client = JsonRpcClientImpl::new(url.clone(), auth_token.as_deref())
+        .with_headers(subnet.http_headers())
+        .with_decoding(subnet.decoding())
+        .with_ws_url(subnet.rpc_ws().cloned())
+        .with_retry(subnet.retry(), &methods::NON_IDEMPOTENT)
+        .with_tap(state.debug_tap.clone())
+        .with_metrics(state.metrics.clone());
+    match state.http_clients.get(url, &subnet.connections()) {
+        Ok(pooled) => client.with_pooled_client(pooled),
+        Err(e) => {
+            tracing::warn!("cannot pool the connections to {url:}, opening them per client: {e:}");
+            client
+        }
+    }
+}
+
+/// Decodes the head changes in a channel notification, whose params are the id of the channel and
+/// the changes.
+fn decode_head_changes(mut msg: serde_json::Value) -> Result<Vec<HeadChange>> {
+    let changes = msg
+        .get_mut("params")
+        .and_then(|p| p.get_mut(1))
+        .map(serde_json::Value::take)
+        .ok_or_else(|| anyhow!("no head changes in notification"))?;
+    Ok(serde_json::from_value(changes)?)
+}
+
 fn create_signed_message_params(msg: MpoolPushMessage, signature: Signature) -> serde_json::Value {
     let nonce = msg
         .nonce
@@ -0,0 +1,133 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Estimation of the gas parameters of the messages pushed to a node.
+//!
+//! The gas parameters set on a [`MpoolPushMessage`] are overrides that are sent as they are. The
+//! others are estimated, by the node unless the client is given another [`GasEstimator`], and
+//! adjusted with the gas policies of the subnet.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use fvm_shared::econ::TokenAmount;
+
+use crate::lotus::message::mpool::{EstimateGasResponse, MpoolPushMessage};
+
+/// The precision of the multipliers applied to fees.
+const MULTIPLIER_PRECISION: u64 = 1_000;
+
+/// Estimates the gas parameters of the messages.
+#[async_trait]
+pub trait GasEstimator: Send + Sync {
+    /// Estimates the gas parameters of `msg`. The gas limit of the message is set if it is
+    /// overridden.
+    async fn estimate_gas(&self, msg: &MpoolPushMessage) -> Result<EstimateGasResponse>;
+}
+
+/// An estimator that forces the gas parameters of all the messages, e.g. when the estimation of
+/// the node cannot be relied on.
+#[derive(Debug, Clone)]
+pub struct FixedGasEstimator {
+    pub gas_limit: u64,
+    pub gas_fee_cap: TokenAmount,
+    pub gas_premium: TokenAmount,
+}
+
+#[async_trait]
+impl GasEstimator for FixedGasEstimator {
+    async fn estimate_gas(&self, _msg: &MpoolPushMessage) -> Result<EstimateGasResponse> {
+        Ok(EstimateGasResponse {
+            gas_limit: Some(TokenAmount::from_atto(self.gas_limit)),
+            gas_fee_cap: Some(self.gas_fee_cap.clone()),
+            gas_premium: Some(self.gas_premium.clone()),
+        })
+    }
+}
+
+/// Multiplies `amount` by `factor`, to a precision of a thousandth.
+pub(crate) fn scale(amount: &TokenAmount, factor: f64) -> TokenAmount {
+    let factor = (factor.max(0.0) * MULTIPLIER_PRECISION as f64).round() as u64;
+    TokenAmount::from_atto(amount.atto() * factor / MULTIPLIER_PRECISION)
+}
+
+/// Sets the gas parameters of `msg` that are not overridden to their estimation, with the fee cap
+/// and premium multiplied by `fee_cap_multiplier` and `premium_multiplier`. The fee cap is raised
+/// to the premium if it falls below it, as the node would reject the message.
+pub(crate) fn fill_gas(
+    msg: &mut MpoolPushMessage,
+    estimate: EstimateGasResponse,
+    fee_cap_multiplier: f64,
+    premium_multiplier: f64,
+) {
+    if msg.gas_limit.is_none() {
+        msg.gas_limit = estimate.gas_limit;
+    }
+    if msg.gas_premium.is_none() {
+        msg.gas_premium = estimate.gas_premium.map(|p| scale(&p, premium_multiplier));
+    }
+    if msg.gas_fee_cap.is_none() {
+        let fee_cap = estimate.gas_fee_cap.map(|c| scale(&c, fee_cap_multiplier));
+        msg.gas_fee_cap = match (fee_cap, &msg.gas_premium) {
+            (Some(c), Some(p)) if &c < p => Some(p.clone()),
+            (c, _) => c,
+        };
+    }
+}
+
+/// Raises the gas premium of `msg` to at least `min`, e.g. for it to replace a message in the
+/// memory pool, and its fee cap to cover it.
+pub(crate) fn raise_premium(msg: &mut MpoolPushMessage, min: &TokenAmount) {
+    if msg.gas_premium.as_ref().map_or(true, |p| p < min) {
+        msg.gas_premium = Some(min.clone());
+    }
+    if msg.gas_fee_cap.as_ref().map_or(true, |c| c < min) {
+        msg.gas_fee_cap = Some(min.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+
+    use crate::lotus::gas::{fill_gas, raise_premium, scale, FixedGasEstimator, GasEstimator};
+    use crate::lotus::message::mpool::MpoolPushMessage;
+
+    #[tokio::test]
+    async fn test_fill_gas() {
+        assert_eq!(
+            scale(&TokenAmount::from_atto(1000), 1.25),
+            TokenAmount::from_atto(1250)
+        );
+
+        let estimator = FixedGasEstimator {
+            gas_limit: 1_000_000,
+            gas_fee_cap: TokenAmount::from_atto(200),
+            gas_premium: TokenAmount::from_atto(100),
+        };
+        let msg = MpoolPushMessage::new(Address::new_id(1), Address::new_id(2), 0, vec![]);
+
+        let mut estimated = msg.clone();
+        let estimate = estimator.estimate_gas(&estimated).await.unwrap();
+        fill_gas(&mut estimated, estimate, 1.5, 1.0);
+        assert_eq!(estimated.gas_limit, Some(TokenAmount::from_atto(1_000_000)));
+        assert_eq!(estimated.gas_fee_cap, Some(TokenAmount::from_atto(300)));
+        assert_eq!(estimated.gas_premium, Some(TokenAmount::from_atto(100)));
+
+        // overrides are kept, and the fee cap is raised to the premium
+        let mut overridden = msg;
+        overridden.gas_limit = Some(TokenAmount::from_atto(5_000));
+        overridden.gas_premium = Some(TokenAmount::from_atto(400));
+        let estimate = estimator.estimate_gas(&overridden).await.unwrap();
+        fill_gas(&mut overridden, estimate, 1.5, 1.0);
+        assert_eq!(overridden.gas_limit, Some(TokenAmount::from_atto(5_000)));
+        assert_eq!(overridden.gas_fee_cap, Some(TokenAmount::from_atto(400)));
+        assert_eq!(overridden.gas_premium, Some(TokenAmount::from_atto(400)));
+
+        // a replacement pays at least the premium required, and a fee cap covering it
+        raise_premium(&mut overridden, &TokenAmount::from_atto(500));
+        assert_eq!(overridden.gas_premium, Some(TokenAmount::from_atto(500)));
+        assert_eq!(overridden.gas_fee_cap, Some(TokenAmount::from_atto(500)));
+        raise_premium(&mut overridden, &TokenAmount::from_atto(100));
+        assert_eq!(overridden.gas_premium, Some(TokenAmount::from_atto(500)));
+    }
+}
@@ -11,6 +11,8 @@ use crate::lotus::message::CIDMap;
 #[serde(rename_all = "PascalCase")]
 pub struct Block {
     parent_state_root: CIDMap,
+    #[serde(default)]
+    timestamp: u64,
 }
 
 /// A simplified struct representing a `ChainGetTipSetByHeight` response that does not fully
@@ -20,6 +22,8 @@ pub struct Block {
 pub struct GetTipSetByHeightResponse {
     pub cids: Vec<CIDMap>,
     blocks: Vec<Block>,
+    #[serde(default)]
+    pub height: u64,
 }
 
 impl GetTipSetByHeightResponse {
@@ -41,6 +45,12 @@ impl GetTipSetByHeightResponse {
             .map(|b| Cid::try_from(&b.parent_state_root))
             .collect()
     }
+
+    /// The unix timestamp, in seconds, of the tipset. All the blocks in a tipset share the
+    /// same timestamp.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.blocks.first().map(|b| b.timestamp)
+    }
 }
 
 /// A simplified struct representing a `ChainHead` response that does not decode the `blocks` field.
@@ -54,3 +64,42 @@ pub struct ChainHeadResponse {
     #[allow(dead_code)]
     pub height: u64,
 }
+
+impl ChainHeadResponse {
+    /// The unix timestamp, in seconds, of the head tipset.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.blocks.first()?.get("Timestamp")?.as_u64()
+    }
+}
+
+/// The kind of a [`HeadChange`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadChangeType {
+    /// The head of the chain when subscribing.
+    Current,
+    /// A tipset added to the chain.
+    Apply,
+    /// A tipset removed from the chain by a reorg.
+    Revert,
+}
+
+/// A change of the head of the chain notified by `ChainNotify`, see:
+/// https://lotus.filecoin.io/reference/lotus/chain/#chainnotify
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HeadChange {
+    #[serde(rename = "Type")]
+    pub change: HeadChangeType,
+    pub val: ChainHeadResponse,
+}
+
+/// Returns the height of the chain after a batch of head changes, `None` if the batch only
+/// reverts tipsets.
+pub fn head_height(changes: &[HeadChange]) -> Option<u64> {
+    changes
+        .iter()
+        .filter(|c| c.change != HeadChangeType::Revert)
+        .map(|c| c.val.height)
+        .max()
+}
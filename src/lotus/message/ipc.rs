@@ -1,8 +1,12 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 
+use std::fmt::{Display, Formatter};
+
+use anyhow::anyhow;
 use cid::Cid;
 use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::MethodNum;
@@ -38,8 +42,14 @@ pub struct IPCReadGatewayStateResponse {
     pub bottom_up_check_period: ChainEpoch,
     pub top_down_check_period: ChainEpoch,
     pub applied_topdown_nonce: u64,
+    /// Not served by the older nodes.
+    #[serde(default)]
+    pub applied_bottomup_nonce: u64,
     pub top_down_checkpoint_voting: Voting,
     pub initialized: bool,
+    /// Not served by the older nodes.
+    #[serde(default, deserialize_with = "deserialize_token_amount_from_str")]
+    pub cross_msg_fee: TokenAmount,
 }
 
 /// The state of a subnet actor. The struct omits all fields that are not relevant for the
@@ -50,6 +60,8 @@ pub struct IPCReadSubnetActorStateResponse {
     pub bottom_up_check_period: ChainEpoch,
     pub validator_set: ValidatorSet,
     pub min_validators: u64,
+    #[serde(deserialize_with = "deserialize_token_amount_from_str")]
+    pub min_validator_stake: TokenAmount,
     pub bottom_up_checkpoint_voting: Voting,
 }
 
@@ -109,6 +121,74 @@ pub struct QueryValidatorSetResponse {
     pub genesis_epoch: i64,
 }
 
+/// How validators are admitted in a subnet, as set in its subnet actor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionMode {
+    /// Validators join and leave on their own by staking collateral
+    Collateral,
+    /// The subnet owner sets the validators and their power
+    Federated,
+    /// The validator set is fixed at genesis
+    Static,
+}
+
+impl TryFrom<u8> for PermissionMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => PermissionMode::Collateral,
+            1 => PermissionMode::Federated,
+            2 => PermissionMode::Static,
+            _ => return Err(anyhow!("invalid permission mode: {value:}")),
+        })
+    }
+}
+
+impl Display for PermissionMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PermissionMode::Collateral => "collateral",
+            PermissionMode::Federated => "federated",
+            PermissionMode::Static => "static",
+        };
+        write!(f, "{s:}")
+    }
+}
+
+/// A validator of a federated subnet and the power set for it by the subnet owner.
+#[derive(Debug, Clone)]
+pub struct FederatedValidator {
+    pub address: Address,
+    /// The uncompressed secp256k1 public key of the validator
+    pub public_key: Vec<u8>,
+    pub power: u64,
+}
+
+/// What a validator needs to join a subnet, as set in its subnet actor.
+#[derive(Debug)]
+pub struct JoinRequirements {
+    /// Minimum collateral of a validator
+    pub min_validator_stake: TokenAmount,
+    /// Minimum number of validators for the subnet to be active
+    pub min_validators: u64,
+    /// Number of validators already in the subnet
+    pub validators: u64,
+    /// Maximum number of validators, if the subnet actor caps its validator set. Neither the fvm
+    /// subnet actor nor the fevm contracts cap it yet.
+    pub max_validators: Option<u64>,
+}
+
+/// What creating a subnet actor costs its creator, besides the collateral of its validators.
+#[derive(Debug)]
+pub struct SubnetCreationCost {
+    /// The fee charged by the registry deploying the subnet actor.
+    pub registry_fee: TokenAmount,
+    /// The maximum fee paid for the gas of the creation.
+    pub gas_fee: TokenAmount,
+}
+
 /// The validator struct. See `ValidatorSet` comment on why we need this duplicated definition.
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Validator {
@@ -221,7 +301,7 @@ impl TryFrom<BottomUpCheckpointWrapper> for BottomUpCheckpoint {
         } else {
             TCid::default()
         };
-        log::debug!("previous checkpoint: {prev_check:?}");
+        tracing::debug!("previous checkpoint: {prev_check:?}");
 
         let children = if let Some(children) = checkpoint_response.data.children {
             children
@@ -242,14 +322,14 @@ impl TryFrom<BottomUpCheckpointWrapper> for BottomUpCheckpoint {
         } else {
             vec![]
         };
-        log::debug!("children: {children:?}");
+        tracing::debug!("children: {children:?}");
 
         let cross_msgs = if let Some(cross_msgs) = checkpoint_response.data.cross_msgs {
             BatchCrossMsgs::from(cross_msgs)
         } else {
             BatchCrossMsgs::default()
         };
-        log::debug!("cross_msgs: {cross_msgs:?}");
+        tracing::debug!("cross_msgs: {cross_msgs:?}");
 
         let data = ipc_gateway::checkpoint::CheckData {
             source: checkpoint_response.data.source,
@@ -268,7 +348,21 @@ impl TryFrom<BottomUpCheckpointWrapper> for BottomUpCheckpoint {
 
 #[cfg(test)]
 mod tests {
-    use crate::lotus::message::ipc::IPCReadSubnetActorStateResponse;
+    use crate::lotus::message::ipc::{IPCReadSubnetActorStateResponse, PermissionMode};
+
+    #[test]
+    fn permission_mode_from_contract() {
+        assert_eq!(
+            PermissionMode::try_from(0).unwrap(),
+            PermissionMode::Collateral
+        );
+        assert_eq!(
+            PermissionMode::try_from(1).unwrap(),
+            PermissionMode::Federated
+        );
+        assert_eq!(PermissionMode::try_from(2).unwrap(), PermissionMode::Static);
+        assert!(PermissionMode::try_from(3).is_err());
+    }
 
     #[test]
     fn deserialize_ipc_subnet_state() {
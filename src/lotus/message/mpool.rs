@@ -5,10 +5,16 @@ use crate::lotus::message::deserialize::{
     deserialize_some_token_amount_from_str, deserialize_token_amount_from_str,
 };
 use crate::lotus::message::CIDMap;
+use anyhow::anyhow;
+use base64::Engine;
+use cid::multihash::{Code, MultihashDigest};
 use cid::Cid;
+use fvm_ipld_encoding::{to_vec, RawBytes};
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
 use fvm_shared::MethodNum;
+use num_traits::ToPrimitive;
 use serde::Deserialize;
 use std::str::FromStr;
 
@@ -28,7 +34,8 @@ pub struct MpoolPushMessageResponseInner {
     from: String,
     pub value: String,
     pub method: MethodNum,
-    pub params: String,
+    /// The base64 encoded params, `null` for messages without params.
+    pub params: Option<String>,
 
     pub nonce: u64,
     #[serde(rename = "GasLimit")]
@@ -55,9 +62,18 @@ impl MpoolPushMessageResponseInner {
     pub fn from(&self) -> anyhow::Result<Address> {
         Ok(Address::from_str(&self.from)?)
     }
+
+    /// The decoded params of the message.
+    pub fn params(&self) -> anyhow::Result<RawBytes> {
+        let bytes = match &self.params {
+            Some(p) => base64::engine::general_purpose::STANDARD.decode(p)?,
+            None => vec![],
+        };
+        Ok(RawBytes::new(bytes))
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct MpoolPushMessage {
     #[serde(deserialize_with = "deserialize_address_from_str")]
@@ -70,6 +86,8 @@ pub struct MpoolPushMessage {
     pub params: Vec<u8>,
 
     pub nonce: Option<u64>,
+    /// The gas parameters, estimated when pushed if not set. Setting them overrides the
+    /// estimation, see [`crate::lotus::gas`].
     #[serde(deserialize_with = "deserialize_some_token_amount_from_num")]
     pub gas_limit: Option<TokenAmount>,
     #[serde(deserialize_with = "deserialize_some_token_amount_from_str")]
@@ -100,6 +118,70 @@ impl MpoolPushMessage {
     }
 }
 
+/// Converts a message with all its fields populated, i.e. ready to be signed, into the fvm
+/// message that is signed.
+impl TryFrom<&MpoolPushMessage> for Message {
+    type Error = anyhow::Error;
+
+    fn try_from(msg: &MpoolPushMessage) -> Result<Self, Self::Error> {
+        Ok(Message {
+            version: msg
+                .version
+                .ok_or_else(|| anyhow!("version should not be empty"))? as u64,
+            from: msg.from,
+            to: msg.to,
+            sequence: msg
+                .nonce
+                .ok_or_else(|| anyhow!("nonce should not be empty"))?,
+            value: msg.value.clone(),
+            method_num: msg.method,
+            params: RawBytes::from(msg.params.clone()),
+            gas_limit: msg
+                .gas_limit
+                .as_ref()
+                .ok_or_else(|| anyhow!("gas_limit should not be empty"))?
+                .atto()
+                .to_u64()
+                .ok_or_else(|| anyhow!("invalid gas_limit"))?,
+            gas_fee_cap: msg
+                .gas_fee_cap
+                .as_ref()
+                .ok_or_else(|| anyhow!("gas_fee_cap should not be empty"))?
+                .clone(),
+            gas_premium: msg
+                .gas_premium
+                .as_ref()
+                .ok_or_else(|| anyhow!("gas_premium should not be empty"))?
+                .clone(),
+        })
+    }
+}
+
+impl From<Message> for MpoolPushMessage {
+    fn from(msg: Message) -> Self {
+        MpoolPushMessage {
+            to: msg.to,
+            from: msg.from,
+            value: msg.value,
+            method: msg.method_num,
+            params: msg.params.to_vec(),
+            nonce: Some(msg.sequence),
+            gas_limit: Some(TokenAmount::from_atto(msg.gas_limit)),
+            gas_fee_cap: Some(msg.gas_fee_cap),
+            gas_premium: Some(msg.gas_premium),
+            cid: None,
+            version: Some(msg.version as u16),
+            max_fee: None,
+        }
+    }
+}
+
+/// Returns the cid of the message, whose bytes are what the sender signs.
+pub fn message_cid(message: &Message) -> anyhow::Result<Cid> {
+    let hash = Code::Blake2b256.digest(&to_vec(message)?);
+    Ok(Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, hash))
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct EstimateGasResponse {
@@ -4,6 +4,7 @@ use anyhow::anyhow;
 use base64::Engine;
 use fil_actors_runtime::cbor;
 use fvm_ipld_encoding::RawBytes;
+use fvm_shared::clock::ChainEpoch;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
@@ -32,18 +33,88 @@ pub struct ReadStateResponse<State> {
     pub state: State,
 }
 
+/// An actor of the state tree.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorResponse {
+    pub code: CIDMap,
+    /// The root of the state of the actor.
+    pub head: CIDMap,
+    pub nonce: u64,
+    pub balance: String,
+}
+
+/// The state of the init actor.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct InitState {
+    pub address_map: CIDMap,
+    #[serde(rename = "NextID")]
+    pub next_id: u64,
+    pub network_name: String,
+}
+
+/// The state of an account actor.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AccountState {
+    pub address: String,
+}
+
+/// The state of a multisig actor.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MultisigState {
+    pub signers: Vec<String>,
+    pub num_approvals_threshold: u64,
+    #[serde(rename = "NextTxnID")]
+    pub next_txn_id: i64,
+    pub initial_balance: String,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+    pub pending_txns: CIDMap,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Receipt {
-    #[allow(dead_code)]
     exit_code: u32,
     #[serde(rename = "Return")]
     pub result: Option<String>,
-    #[allow(dead_code)]
     gas_used: u64,
 }
 
+/// The result of the replay of a message, of which only the gas cost is used.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StateReplayResponse {
+    pub gas_cost: GasCost,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GasCost {
+    /// The total fee paid by the sender of the message, in attoFIL.
+    pub total_cost: String,
+}
+
 impl Receipt {
+    pub fn exit_code(&self) -> u32 {
+        self.exit_code
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    /// The decoded return of the message, empty if it has none.
+    pub fn return_data(&self) -> anyhow::Result<Vec<u8>> {
+        match &self.result {
+            Some(r) => Ok(base64::engine::general_purpose::STANDARD.decode(r)?),
+            None => Ok(vec![]),
+        }
+    }
+
     pub fn parse_result_into<T: Default + DeserializeOwned>(self) -> anyhow::Result<T> {
         if self.result.is_none() {
             return Ok(Default::default());
@@ -52,7 +123,7 @@ impl Receipt {
         let r = base64::engine::general_purpose::STANDARD
             .decode(self.result.unwrap())
             .map_err(|e| {
-                log::error!("cannot base64 decode due to {e:?}");
+                tracing::error!("cannot base64 decode due to {e:?}");
                 anyhow!("cannot decode return string")
             })?;
 
@@ -61,7 +132,7 @@ impl Receipt {
             "deserialize create subnet return response",
         )
         .map_err(|e| {
-            log::error!("cannot decode bytes due to {e:?}");
+            tracing::error!("cannot decode bytes due to {e:?}");
             anyhow!("cannot cbor deserialize return data")
         })
     }
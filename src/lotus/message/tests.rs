@@ -182,3 +182,21 @@ fn test_checkpoint_template_from_str() {
     let w: BottomUpCheckpointWrapper = serde_json::from_str(raw_str).unwrap();
     assert_eq!(w.data.source, SubnetID::from_str("/r123/f01002").unwrap());
 }
+
+#[test]
+fn test_head_changes_from_str() {
+    use crate::lotus::message::chain::{head_height, HeadChange, HeadChangeType};
+
+    let raw_str = r#"
+    [
+        {"Type": "revert", "Val": {"Cids": [], "Blocks": [], "Height": 101}},
+        {"Type": "apply", "Val": {"Cids": [], "Blocks": [], "Height": 101}},
+        {"Type": "apply", "Val": {"Cids": [], "Blocks": [], "Height": 102}}
+    ]
+    "#;
+
+    let changes: Vec<HeadChange> = serde_json::from_str(raw_str).unwrap();
+    assert_eq!(changes[0].change, HeadChangeType::Revert);
+    assert_eq!(head_height(&changes), Some(102));
+    assert_eq!(head_height(&changes[..1]), None);
+}
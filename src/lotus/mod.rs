@@ -3,12 +3,14 @@
 ///! The lotus api to interact with lotus node
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use cid::Cid;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::crypto::signature::Signature;
 use fvm_shared::econ::TokenAmount;
 use ipc_gateway::{BottomUpCheckpoint, TopDownCheckpoint};
 use ipc_sdk::cross::CrossMsg;
@@ -16,19 +18,25 @@ use ipc_sdk::subnet_id::SubnetID;
 use serde::de::DeserializeOwned;
 
 use crate::lotus::message::chain::GetTipSetByHeightResponse;
-use message::chain::ChainHeadResponse;
-use message::mpool::{MpoolPushMessage, MpoolPushMessageResponseInner};
-use message::state::{ReadStateResponse, StateWaitMsgResponse};
+use async_channel::Receiver;
+use message::chain::{ChainHeadResponse, HeadChange};
+use message::mpool::{MpoolPushMessage, MpoolPushMessageResponse, MpoolPushMessageResponseInner};
+use message::state::{ActorResponse, ReadStateResponse, StateWaitMsgResponse};
 use message::wallet::{WalletKeyType, WalletListResponse};
 
 use crate::lotus::message::ipc::{IPCReadGatewayStateResponse, IPCReadSubnetActorStateResponse};
+use crate::lotus::version::NodeApi;
 use crate::manager::SubnetInfo;
 
 use self::message::CIDMap;
 
 pub mod client;
+pub mod gas;
 mod json;
 pub mod message;
+pub mod schema;
+pub mod version;
+
 #[cfg(test)]
 mod tests;
 
@@ -36,6 +44,33 @@ mod tests;
 /// see https://github.com/filecoin-project/go-state-types/blob/f6fd668a32b4b4a0bc39fd69d8a5f8fb11f49461/network/version.go#L7
 pub type NetworkVersion = u32;
 
+/// A message was not executed, or not buried under enough epochs, in the time given to it. The
+/// message may still be executed later.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{}", self.describe())]
+pub struct MessageWaitTimeout {
+    pub cid: Cid,
+    pub confidence: u64,
+    pub timeout: Duration,
+    /// The epoch the message was executed at, if it was.
+    pub executed_at: Option<u64>,
+}
+
+impl MessageWaitTimeout {
+    fn describe(&self) -> String {
+        match self.executed_at {
+            Some(epoch) => format!(
+                "message {} executed at epoch {epoch:} but not confirmed by {} epochs after {:?}",
+                self.cid, self.confidence, self.timeout
+            ),
+            None => format!(
+                "message {} not executed after {:?}, it may still be executed later",
+                self.cid, self.timeout
+            ),
+        }
+    }
+}
+
 /// The Lotus client api to interact with the Lotus node.
 #[async_trait]
 pub trait LotusClient {
@@ -50,9 +85,56 @@ pub trait LotusClient {
     /// See: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolpush
     async fn mpool_push(&self, mut msg: MpoolPushMessage) -> Result<Cid>;
 
+    /// Fills in the nonce, version and gas of the message, so that it is ready to be signed. The
+    /// nonce is left unset if the node signs the message, as it assigns the nonce itself.
+    async fn mpool_prepare(&self, msg: MpoolPushMessage) -> Result<MpoolPushMessage>;
+
+    /// Push a message signed by the caller to memory pool, see: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolpush
+    async fn mpool_push_signed(&self, msg: MpoolPushMessage, signature: Signature) -> Result<Cid>;
+
+    /// Returns the signed messages pending in the memory pool, only those sent by `from` if set,
+    /// see: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolpending
+    async fn mpool_pending(&self, from: Option<Address>) -> Result<Vec<MpoolPushMessageResponse>>;
+
+    /// Returns the next nonce of `address`, following the messages of `address` pending in the
+    /// memory pool without a gap, see: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolgetnonce
+    async fn mpool_nonce(&self, address: &Address) -> Result<u64>;
+
     /// Wait for the message cid of a particular nonce, see: https://lotus.filecoin.io/reference/lotus/state/#statewaitmsg
     async fn state_wait_msg(&self, cid: Cid) -> Result<StateWaitMsgResponse>;
 
+    /// Returns the receipt of the message `cid` if it was executed, without waiting for it, see:
+    /// https://lotus.filecoin.io/reference/lotus/state/#statesearchmsg
+    async fn state_search_msg(&self, cid: Cid) -> Result<Option<StateWaitMsgResponse>>;
+
+    /// Waits for the message `cid` to be executed and buried under `confidence` epochs, failing
+    /// with [`MessageWaitTimeout`] if it is not after `timeout`.
+    async fn state_wait_msg_with_confidence(
+        &self,
+        cid: Cid,
+        confidence: u64,
+        timeout: Duration,
+    ) -> Result<StateWaitMsgResponse>;
+
+    /// Lists the cids of the messages executed since `to_height` that match the sender `from`
+    /// and the recipient `to`, see: https://lotus.filecoin.io/reference/lotus/state/#statelistmessages
+    async fn state_list_messages(
+        &self,
+        from: Option<Address>,
+        to: Option<Address>,
+        to_height: ChainEpoch,
+    ) -> Result<Vec<Cid>>;
+
+    /// Returns the message of a cid, see: https://lotus.filecoin.io/reference/lotus/chain/#chaingetmessage
+    async fn chain_get_message(&self, cid: Cid) -> Result<MpoolPushMessageResponseInner>;
+
+    /// Returns the total fee paid by the sender of an executed message, replaying it, see:
+    /// https://lotus.filecoin.io/reference/lotus/state/#statereplay
+    async fn state_replay_gas_cost(&self, cid: Cid) -> Result<TokenAmount>;
+
+    /// Returns the API served by the node, probed once and cached, see https://lotus.filecoin.io/reference/lotus/common/#version
+    async fn node_api(&self) -> Result<NodeApi>;
+
     /// Returns the name of the network the node is synced to, see https://lotus.filecoin.io/reference/lotus/state/#statenetworkname
     async fn state_network_name(&self) -> Result<String>;
 
@@ -65,6 +147,9 @@ pub trait LotusClient {
         network_version: NetworkVersion,
     ) -> Result<HashMap<String, Cid>>;
 
+    /// Returns the permissions granted by the auth `token`, see https://lotus.filecoin.io/reference/lotus/auth/#authverify
+    async fn auth_verify(&self, token: &str) -> Result<Vec<String>>;
+
     /// Get the default wallet of the node, see: https://lotus.filecoin.io/reference/lotus/wallet/#walletdefaultaddress
     async fn wallet_default(&self) -> Result<Address>;
 
@@ -84,10 +169,24 @@ pub trait LotusClient {
         tipset: Cid,
     ) -> Result<ReadStateResponse<State>>;
 
+    /// Returns the actor of `address` at the chain head, with the cid of its code and of its
+    /// state, see: https://lotus.filecoin.io/reference/lotus/state/#stategetactor
+    async fn state_get_actor(&self, address: &Address) -> Result<ActorResponse>;
+
+    /// Returns the raw bytes of the object of `cid` in the blockstore of the node, see:
+    /// https://lotus.filecoin.io/reference/lotus/chain/#chainreadobj
+    async fn chain_read_obj(&self, cid: Cid) -> Result<Vec<u8>>;
+
     /// Returns the current head of the chain.
     /// See: https://lotus.filecoin.io/reference/lotus/chain/#chainhead
     async fn chain_head(&self) -> Result<ChainHeadResponse>;
 
+    /// Subscribes to the changes of the head of the chain over the websocket endpoint of the
+    /// node, starting with the current head. The channel is closed when the node closes the
+    /// subscription or the connection drops.
+    /// See: https://lotus.filecoin.io/reference/lotus/chain/#chainnotify
+    async fn chain_notify(&self) -> Result<Receiver<Vec<HeadChange>>>;
+
     /// Returns the heaviest epoch for the chain
     async fn current_epoch(&self) -> Result<ChainEpoch>;
 
@@ -0,0 +1,124 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Registry of the typed schemas of the state of the actors, by code cid.
+//!
+//! The code cids of the builtin and IPC actors change with every bundle, so the registry of a
+//! subnet is built from the manifest of the network version its node runs, by actor name, and
+//! extended with the code cids of the `state_schemas` of the config. The state of an actor with a
+//! schema is decoded by the node and checked against the typed struct of its schema.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use ipc_subnet_actor::types::MANIFEST_ID;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::config::StateSchema;
+use crate::lotus::message::ipc::{IPCReadGatewayStateResponse, IPCReadSubnetActorStateResponse};
+use crate::lotus::message::state::{AccountState, InitState, MultisigState};
+
+/// The name of the gateway actor in the manifest of the IPC bundles.
+const GATEWAY_MANIFEST_ID: &str = "ipc_gateway";
+
+#[derive(Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: HashMap<Cid, StateSchema>,
+}
+
+impl SchemaRegistry {
+    /// Builds the registry from the code cids of the actors of the network, by name, and the
+    /// schemas of the config, by code cid, which take precedence.
+    pub fn new(
+        manifest: &HashMap<String, Cid>,
+        extensions: &HashMap<String, StateSchema>,
+    ) -> Result<Self> {
+        let mut schemas = manifest
+            .iter()
+            .filter_map(|(name, code)| Some((*code, manifest_schema(name)?)))
+            .collect::<HashMap<_, _>>();
+        for (code, schema) in extensions {
+            let code = Cid::from_str(code)
+                .map_err(|e| anyhow!("invalid code cid {code:} in state_schemas: {e:}"))?;
+            schemas.insert(code, *schema);
+        }
+        Ok(Self { schemas })
+    }
+
+    pub fn schema(&self, code: &Cid) -> Option<StateSchema> {
+        self.schemas.get(code).copied()
+    }
+}
+
+fn manifest_schema(name: &str) -> Option<StateSchema> {
+    match name {
+        MANIFEST_ID => Some(StateSchema::SubnetActor),
+        GATEWAY_MANIFEST_ID => Some(StateSchema::Gateway),
+        "init" => Some(StateSchema::Init),
+        "account" => Some(StateSchema::Account),
+        "multisig" => Some(StateSchema::Multisig),
+        _ => None,
+    }
+}
+
+/// Checks that `state`, as decoded by the node, has the fields of `schema`.
+pub fn check_state(schema: StateSchema, state: &Value) -> Result<()> {
+    match schema {
+        StateSchema::Gateway => check::<IPCReadGatewayStateResponse>(schema, state),
+        StateSchema::SubnetActor => check::<IPCReadSubnetActorStateResponse>(schema, state),
+        StateSchema::Init => check::<InitState>(schema, state),
+        StateSchema::Account => check::<AccountState>(schema, state),
+        StateSchema::Multisig => check::<MultisigState>(schema, state),
+    }
+}
+
+fn check<T: DeserializeOwned>(schema: StateSchema, state: &Value) -> Result<()> {
+    serde_json::from_value::<T>(state.clone())
+        .map(|_| ())
+        .map_err(|e| anyhow!("state does not match the {schema:} schema: {e:}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use cid::Cid;
+    use serde_json::json;
+
+    use crate::config::StateSchema;
+    use crate::lotus::schema::{check_state, SchemaRegistry};
+
+    const INIT_CODE: &str = "bafk2bzaceaipvjhoxmtofsnv3aj6gj5ida4afdrxa4ewku2hfipdlxpaektlw";
+    const CUSTOM_CODE: &str = "bafk2bzacecbtbyfdzyhzwlkk4lvnndfxsoa4qwolmxpnnn4xwqvmgsl4cxbno";
+
+    #[test]
+    fn test_schema_registry() {
+        let init = Cid::from_str(INIT_CODE).unwrap();
+        let custom = Cid::from_str(CUSTOM_CODE).unwrap();
+        let manifest = HashMap::from([
+            (String::from("init"), init),
+            (String::from("reward"), custom),
+        ]);
+
+        let registry = SchemaRegistry::new(&manifest, &HashMap::new()).unwrap();
+        assert_eq!(registry.schema(&init), Some(StateSchema::Init));
+        assert_eq!(registry.schema(&custom), None);
+
+        let extensions = HashMap::from([(String::from(CUSTOM_CODE), StateSchema::Gateway)]);
+        let registry = SchemaRegistry::new(&manifest, &extensions).unwrap();
+        assert_eq!(registry.schema(&custom), Some(StateSchema::Gateway));
+
+        let invalid = HashMap::from([(String::from("not a cid"), StateSchema::Gateway)]);
+        assert!(SchemaRegistry::new(&manifest, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_check_state() {
+        let account = json!({ "Address": "f3vvmn62lofvhjd2ugzca6sof2j2ubwok6cj4xxbfzz4yuxfkgobpihhd2thlanmsh3w2ptld2gqkn2jvlss4a" });
+        assert!(check_state(StateSchema::Account, &account).is_ok());
+        assert!(check_state(StateSchema::Multisig, &account).is_err());
+    }
+}
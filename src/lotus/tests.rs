@@ -1,10 +1,24 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_channel::Receiver;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
 use url::Url;
 
-use crate::jsonrpc::JsonRpcClientImpl;
+use crate::config::subnet::FvmSigner;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
 use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::message::mpool::MpoolPushMessage;
 use crate::lotus::LotusClient;
+use crate::manager::key_usage::KeyOperation;
 
 const HTTP_ENDPOINT: &str = "https://api.node.glif.io/rpc/v0";
 
@@ -49,3 +63,106 @@ async fn chain_head() {
     assert!(!head.blocks.is_empty());
     assert_eq!(head.cids.len(), head.blocks.len());
 }
+
+#[tokio::test]
+#[ignore]
+async fn chain_notify() {
+    let client = get_lotus_client();
+    let heads = client.chain_notify().await.unwrap();
+    // the first notification is the current head
+    let changes = heads.recv().await.unwrap();
+    assert!(!changes.is_empty());
+}
+
+#[tokio::test]
+#[ignore]
+async fn mpool_pending() {
+    let client = get_lotus_client();
+    let pending = client.mpool_pending(None).await.unwrap();
+    if let Some(first) = pending.first() {
+        let from = first.message.from().unwrap();
+        let pending = client.mpool_pending(Some(from)).await.unwrap();
+        assert!(pending.iter().all(|m| m.message.from().unwrap() == from));
+        assert!(client.mpool_nonce(&from).await.unwrap() > 0);
+    }
+}
+
+const MESSAGE_CID: &str = "bafy2bzacecwgnejfzcq7a4zvvownmb4oae6xzyu323z5wuuufesbtikortt6k";
+
+/// A node that records the requests it receives and answers the few methods used to push a
+/// message.
+#[derive(Default)]
+struct RecordingNode {
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+#[async_trait]
+impl JsonRpcClient for RecordingNode {
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> anyhow::Result<T> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((method.to_string(), params.clone()));
+        let response = match method {
+            "Filecoin.WalletBalance" => json!("1000000000000000000"),
+            "Filecoin.MpoolGetNonce" => json!(7),
+            "Filecoin.MpoolPushMessage" => {
+                let msg = &params[0];
+                json!({
+                    "Message": {
+                        "To": msg["to"],
+                        "From": msg["from"],
+                        "Value": msg["value"],
+                        "Method": msg["method"],
+                        "Params": null,
+                        "Nonce": 7,
+                        "GasLimit": msg["GasLimit"],
+                        "GasFeeCap": msg["GasFeeCap"],
+                        "GasPremium": msg["GasPremium"],
+                        "Version": 0,
+                        "CID": {"/": MESSAGE_CID},
+                    },
+                    "CID": {"/": MESSAGE_CID},
+                })
+            }
+            _ => return Err(anyhow!("unexpected request: {method:}")),
+        };
+        Ok(serde_json::from_value(response)?)
+    }
+
+    async fn subscribe(&self, _method: &str) -> anyhow::Result<Receiver<Value>> {
+        Err(anyhow!("subscriptions not supported"))
+    }
+}
+
+#[tokio::test]
+async fn push_operation_signed_by_node() {
+    let node = RecordingNode::default();
+    let requests = node.requests.clone();
+    let client = LotusJsonRPCClient::new(node, SubnetID::default()).with_signer(FvmSigner::Node);
+
+    let mut msg = MpoolPushMessage::new(
+        Address::from_str("t01").unwrap(),
+        Address::from_str("t0100").unwrap(),
+        0,
+        vec![],
+    );
+    msg.gas_limit = Some(TokenAmount::from_atto(1_000_000));
+    msg.gas_fee_cap = Some(TokenAmount::from_atto(100));
+    msg.gas_premium = Some(TokenAmount::from_atto(100));
+
+    let cid = client
+        .push_operation(KeyOperation::SendValue, msg)
+        .await
+        .unwrap();
+    assert_eq!(cid.to_string(), MESSAGE_CID);
+
+    // the node assigns the nonce of the messages it signs
+    let requests = requests.lock().unwrap();
+    assert!(requests.iter().all(|(m, _)| m != "Filecoin.MpoolGetNonce"));
+    let (_, params) = requests
+        .iter()
+        .find(|(m, _)| m == "Filecoin.MpoolPushMessage")
+        .unwrap();
+    assert_eq!(params[0]["nonce"], Value::Null);
+}
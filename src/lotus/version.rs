@@ -0,0 +1,249 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Negotiation of the API version of the Lotus nodes.
+//!
+//! The version of the API of a node is probed the first time a client talks to it, and selects
+//! the variants of the methods whose signature differs between versions, e.g. `StateWaitMsg`
+//! only takes the look back limit and the replace flag in the v1 API. Nodes whose API the agent
+//! does not support are rejected with an error that lists the versions required, instead of
+//! failing later with a `method not found` deep in the checkpointing flows.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// How long the API of a node is cached before being probed again, so that upgraded nodes are
+/// picked up without restarting the agent.
+const NODE_API_TTL: Duration = Duration::from_secs(600);
+
+/// The oldest versions of the v0 and v1 full node APIs supported by the agent.
+pub const MIN_API_V0: ApiVersion = ApiVersion::new(1, 5, 0);
+pub const MIN_API_V1: ApiVersion = ApiVersion::new(2, 3, 0);
+
+/// The error message of the node when a method is not served.
+const METHOD_NOT_FOUND: &str = "not found";
+
+/// The response of `Filecoin.Version`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct VersionResponse {
+    pub version: String,
+    #[serde(rename = "APIVersion")]
+    pub api_version: u32,
+}
+
+/// The version of the API served by a node, encoded by Lotus as `major << 16 | minor << 8 | patch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ApiVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl From<u32> for ApiVersion {
+    fn from(v: u32) -> Self {
+        Self::new(v >> 16, (v >> 8) & 0xff, v & 0xff)
+    }
+}
+
+impl Display for ApiVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The API of a node, that selects the variants of the methods it serves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeApi {
+    /// The version of the node, e.g. `1.20.0+eudico`.
+    pub version: String,
+    pub api_version: ApiVersion,
+}
+
+impl NodeApi {
+    /// Whether the node serves the v1 API, the one the IPC methods of eudico are served in.
+    pub fn is_v1(&self) -> bool {
+        self.api_version.major >= MIN_API_V1.major
+    }
+
+    /// The params of `StateWaitMsg` for the version of the node.
+    pub fn state_wait_msg_params(
+        &self,
+        cid: Value,
+        confidence: u8,
+        look_back: i8,
+        allow_replace: bool,
+    ) -> Value {
+        if self.is_v1() {
+            json!([cid, confidence, look_back, allow_replace])
+        } else {
+            // the v0 API neither limits the look back nor lets the message be replaced
+            json!([cid, confidence])
+        }
+    }
+
+    /// The params of `StateSearchMsg` for the version of the node, searching from the head.
+    pub fn state_search_msg_params(&self, cid: Value, look_back: i8, allow_replace: bool) -> Value {
+        if self.is_v1() {
+            // the empty tipset key is the head of the chain
+            json!([[], cid, look_back, allow_replace])
+        } else {
+            json!([cid])
+        }
+    }
+
+    /// Fails if `method`, an IPC method, is not served by the node.
+    pub fn ensure_ipc(&self, method: &str) -> Result<()> {
+        if self.is_v1() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "{method:} is not served by the v0 API {} of the node ({}), the IPC methods require the v1 API {MIN_API_V1:} or later of an eudico node, point the subnet to its /rpc/v1 endpoint",
+            self.api_version,
+            self.version
+        ))
+    }
+
+    /// Turns the `method not found` error of `method` into one that explains which node is
+    /// required.
+    pub fn explain(&self, method: &str, e: anyhow::Error) -> anyhow::Error {
+        if !e.to_string().contains(METHOD_NOT_FOUND) {
+            return e;
+        }
+        anyhow!(
+            "{method:} is not served by the node ({}, API {}), the agent requires an eudico node serving the v1 API {MIN_API_V1:} or later: {e:}",
+            self.version,
+            self.api_version
+        )
+    }
+}
+
+impl TryFrom<VersionResponse> for NodeApi {
+    type Error = anyhow::Error;
+
+    fn try_from(r: VersionResponse) -> Result<Self> {
+        let api_version = ApiVersion::from(r.api_version);
+        let supported = match api_version.major {
+            1 => api_version >= MIN_API_V0,
+            2 => api_version >= MIN_API_V1,
+            _ => false,
+        };
+        if !supported {
+            return Err(anyhow!(
+                "the node ({}) serves the API {api_version:}, the agent requires the v1 API {MIN_API_V1:} or later, or the v0 API {MIN_API_V0:} or later",
+                r.version
+            ));
+        }
+        Ok(Self {
+            version: r.version,
+            api_version,
+        })
+    }
+}
+
+pub(crate) struct NodeApis {
+    apis: Mutex<Option<HashMap<String, (NodeApi, Instant)>>>,
+}
+
+impl NodeApis {
+    pub const fn new() -> Self {
+        Self {
+            apis: Mutex::new(None),
+        }
+    }
+
+    /// Returns the API of the node at `endpoint`, if probed recently.
+    pub fn get(&self, endpoint: &str) -> Option<NodeApi> {
+        let apis = self.apis.lock().unwrap();
+        match apis.as_ref()?.get(endpoint) {
+            Some((api, at)) if at.elapsed() < NODE_API_TTL => Some(api.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&self, endpoint: &str, api: NodeApi) {
+        let mut apis = self.apis.lock().unwrap();
+        apis.get_or_insert_with(HashMap::new)
+            .insert(endpoint.to_string(), (api, Instant::now()));
+    }
+}
+
+impl Default for NodeApis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use serde_json::json;
+
+    use crate::lotus::version::{ApiVersion, NodeApi, VersionResponse};
+
+    fn node_api(api_version: u32) -> anyhow::Result<NodeApi> {
+        NodeApi::try_from(VersionResponse {
+            version: "1.20.0+eudico".to_string(),
+            api_version,
+        })
+    }
+
+    #[test]
+    fn test_api_version() {
+        assert_eq!(ApiVersion::from(0x020300), ApiVersion::new(2, 3, 0));
+        assert_eq!(ApiVersion::from(0x010500).to_string(), "1.5.0");
+
+        assert!(node_api(0x020300).unwrap().is_v1());
+        assert!(!node_api(0x010500).unwrap().is_v1());
+        assert!(node_api(0x020200).is_err());
+        assert!(node_api(0x030000).is_err());
+    }
+
+    #[test]
+    fn test_method_variants() {
+        let v1 = node_api(0x020300).unwrap();
+        let v0 = node_api(0x010500).unwrap();
+
+        let cid = json!({"/": "bafy"});
+        assert_eq!(
+            v1.state_wait_msg_params(cid.clone(), 0, -1, true),
+            json!([{"/": "bafy"}, 0, -1, true])
+        );
+        assert_eq!(
+            v0.state_wait_msg_params(cid.clone(), 0, -1, true),
+            json!([{"/": "bafy"}, 0])
+        );
+        assert_eq!(
+            v1.state_search_msg_params(cid.clone(), -1, true),
+            json!([[], {"/": "bafy"}, -1, true])
+        );
+        assert_eq!(
+            v0.state_search_msg_params(cid, -1, true),
+            json!([{"/": "bafy"}])
+        );
+
+        assert!(v1.ensure_ipc("Filecoin.IPCReadGatewayState").is_ok());
+        assert!(v0.ensure_ipc("Filecoin.IPCReadGatewayState").is_err());
+
+        let e = v1.explain(
+            "Filecoin.IPCReadGatewayState",
+            anyhow!("json_rpc error: method 'Filecoin.IPCReadGatewayState' not found"),
+        );
+        assert!(e.to_string().contains("requires an eudico node"));
+    }
+}
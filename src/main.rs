@@ -2,12 +2,12 @@
 // SPDX-License-Identifier: MIT
 #![feature(try_blocks)]
 use fvm_shared::address::{set_current_network, Network};
-use ipc_agent::cli;
+use ipc_agent::{cli, logging};
 use num_traits::FromPrimitive;
 
 #[tokio::main]
 async fn main() {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    let logger = logging::init();
 
     let network_raw: u8 = std::env::var("LOTUS_NETWORK")
         // default to testnet
@@ -15,11 +15,11 @@ async fn main() {
         .parse()
         .unwrap();
     let network = Network::from_u8(network_raw).unwrap();
-    log::debug!("using network: {network:?}");
+    tracing::debug!("using network: {network:?}");
     set_current_network(network);
 
-    if let Err(e) = cli::cli().await {
-        log::error!("main process failed: {e:#}");
+    if let Err(e) = cli::cli(logger).await {
+        tracing::error!("main process failed: {e:#}");
         std::process::exit(1);
     }
 }
@@ -0,0 +1,480 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Activity feed of the accounts of the agent.
+//!
+//! The feed of an account in a subnet combines the messages it sent or received on chain, the
+//! operations the agent signed with it, from the key usage log, and the cross-net messages of the
+//! checkpoint history that involve it, so that the sends, receives and gas spent by the account
+//! can be accounted for.
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::history::AccountCrossMsg;
+use crate::manager::key_usage::KeyOperation;
+
+/// A message executed on chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedMessage {
+    /// The cid of the fvm message or the hash of the evm transaction.
+    pub id: String,
+    pub epoch: ChainEpoch,
+    pub from: Address,
+    pub to: Address,
+    pub value: TokenAmount,
+    /// The method called, always 0 for evm transactions.
+    pub method: u64,
+    /// The fee paid by the sender.
+    pub gas_fee: TokenAmount,
+    /// Whether the message was executed successfully.
+    pub success: bool,
+}
+
+/// A message looked up by its id, whether it is executed yet or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageLookup {
+    /// The cid of the fvm message or the hash of the evm transaction.
+    pub id: String,
+    pub from: Address,
+    pub to: Address,
+    pub value: TokenAmount,
+    /// The method called, always 0 for evm transactions.
+    pub method: u64,
+    pub nonce: u64,
+    /// The cbor params of the fvm message or the calldata of the evm transaction.
+    pub params: Vec<u8>,
+    /// The method of the gateway or of a subnet actor called by the message, if it calls one.
+    pub ipc_method: Option<String>,
+    /// Not set while the message is not executed.
+    pub receipt: Option<MessageReceipt>,
+}
+
+/// The receipt of an executed message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageReceipt {
+    pub epoch: ChainEpoch,
+    pub success: bool,
+    /// The exit code of the fvm message, not set for evm transactions.
+    pub exit_code: Option<u32>,
+    /// The cbor return of the fvm message, evm receipts do not include the return of the call.
+    pub return_data: Option<Vec<u8>>,
+    pub gas_used: u64,
+    /// The fee paid by the sender.
+    pub gas_fee: TokenAmount,
+}
+
+/// A message waiting in the memory pool of the node to be executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMessage {
+    /// The cid of the signed message.
+    pub id: String,
+    pub from: Address,
+    pub to: Address,
+    pub value: TokenAmount,
+    pub method: u64,
+    pub nonce: u64,
+    pub gas_fee_cap: TokenAmount,
+    pub gas_premium: TokenAmount,
+    /// Whether the message cannot be executed until a message with a lower nonce is sent, i.e.
+    /// its nonce is past a gap in the nonces of its sender.
+    pub stuck: bool,
+}
+
+/// The gas a pending message is replaced with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplacementGas {
+    pub gas_fee_cap: TokenAmount,
+    pub gas_premium: TokenAmount,
+    /// The gas limit of the replaced message is kept if not set.
+    pub gas_limit: Option<u64>,
+}
+
+impl ReplacementGas {
+    /// Checks that the gas replaces a message paying `premium`, see [`min_replacement_premium`].
+    pub fn ensure_replaces(&self, premium: &TokenAmount) -> anyhow::Result<()> {
+        if self.gas_fee_cap < self.gas_premium {
+            return Err(anyhow!("gas fee cap is below the gas premium"));
+        }
+        let min = min_replacement_premium(premium);
+        if self.gas_premium < min {
+            return Err(anyhow!(
+                "gas premium of the replacement must be at least {} attoFIL, 25% above the one of the pending message",
+                min.atto()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The lowest premium of a message replacing one paying `premium`. The memory pool of the node
+/// only accepts a replacement whose premium is at least 25% above the one of the message, the
+/// ratio Lotus replaces messages with.
+pub fn min_replacement_premium(premium: &TokenAmount) -> TokenAmount {
+    TokenAmount::from_atto((premium.atto() * 125 + 99) / 100)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// A message sent by the account.
+    Send,
+    /// A message received by the account.
+    Receive,
+    /// A cross-net message sent by the account, committed in a checkpoint of its subnet.
+    CrossNetSend,
+    /// A cross-net message to the account, committed in a checkpoint of its subnet.
+    CrossNetReceive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    pub account: String,
+    pub kind: ActivityKind,
+    /// The epoch the message was executed, or its checkpoint committed, at.
+    pub epoch: ChainEpoch,
+    /// The other end of the message.
+    pub counterparty: String,
+    pub value: Amount,
+    /// The fee paid by the account, for the messages it sent.
+    pub gas_fee: Option<Amount>,
+    /// The operation the agent signed the message for, if it did.
+    pub operation: Option<KeyOperation>,
+    /// The cid or transaction hash of the message, not set for cross-net messages.
+    pub message: Option<String>,
+    pub success: bool,
+}
+
+/// The totals of the activity of an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivitySummary {
+    pub sent: Amount,
+    pub received: Amount,
+    pub gas_spent: Amount,
+    /// The part of `gas_spent` spent on checkpoint votes.
+    pub checkpoint_gas_spent: Amount,
+    /// The part of `sent` funded to child subnets.
+    pub funded: Amount,
+    /// The part of `sent` released to the parent.
+    pub released: Amount,
+}
+
+/// The totals of the activity of an account in a subnet over a period of epochs, a row of the
+/// accounting export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountingRow {
+    pub subnet: String,
+    pub account: String,
+    pub from_epoch: ChainEpoch,
+    /// Exclusive.
+    pub to_epoch: ChainEpoch,
+    pub summary: ActivitySummary,
+}
+
+/// The header of the accounting export, the amounts in FIL.
+pub const ACCOUNTING_CSV_HEADER: &str =
+    "subnet,account,from_epoch,to_epoch,sent,received,gas_spent,checkpoint_gas_spent,funded,released";
+
+impl AccountingRow {
+    pub fn to_csv(&self) -> String {
+        let s = &self.summary;
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.subnet,
+            self.account,
+            self.from_epoch,
+            self.to_epoch,
+            s.sent.fil,
+            s.received.fil,
+            s.gas_spent.fil,
+            s.checkpoint_gas_spent.fil,
+            s.funded.fil,
+            s.released.fil
+        )
+    }
+}
+
+/// Builds the feed of `account` from the messages executed on chain, the operations the agent
+/// signed, by message id, and the cross-net messages of the history, the latest first.
+pub fn activity_feed(
+    account: &Address,
+    messages: &[ExecutedMessage],
+    operations: &HashMap<String, KeyOperation>,
+    cross_msgs: &[AccountCrossMsg],
+) -> Vec<Activity> {
+    let mut feed = vec![];
+    for m in messages {
+        let operation = operations.get(&m.id).copied();
+        // a message to itself is both sent and received
+        if &m.from == account {
+            feed.push(Activity {
+                account: account.to_string(),
+                kind: ActivityKind::Send,
+                epoch: m.epoch,
+                counterparty: m.to.to_string(),
+                value: Amount::from(&m.value),
+                gas_fee: Some(Amount::from(&m.gas_fee)),
+                operation,
+                message: Some(m.id.clone()),
+                success: m.success,
+            });
+        }
+        if &m.to == account {
+            feed.push(Activity {
+                account: account.to_string(),
+                kind: ActivityKind::Receive,
+                epoch: m.epoch,
+                counterparty: m.from.to_string(),
+                value: Amount::from(&m.value),
+                gas_fee: None,
+                operation: None,
+                message: Some(m.id.clone()),
+                success: m.success,
+            });
+        }
+    }
+    for c in cross_msgs {
+        let (kind, counterparty) = if c.sent {
+            (ActivityKind::CrossNetSend, &c.msg.to)
+        } else {
+            (ActivityKind::CrossNetReceive, &c.msg.from)
+        };
+        feed.push(Activity {
+            account: account.to_string(),
+            kind,
+            epoch: c.epoch,
+            counterparty: counterparty.clone(),
+            value: Amount::from(&parse_whole_fil(&c.msg.value).unwrap_or_default()),
+            gas_fee: None,
+            operation: None,
+            message: None,
+            success: true,
+        });
+    }
+    feed.sort_by(|a, b| b.epoch.cmp(&a.epoch));
+    feed
+}
+
+/// Sums the value sent and received, and the gas spent, over `feed`. Failed messages only count
+/// for their gas.
+pub fn summarize(feed: &[Activity]) -> ActivitySummary {
+    let mut funded = TokenAmount::from_atto(0);
+    let mut released = TokenAmount::from_atto(0);
+    let mut sent = TokenAmount::from_atto(0);
+    let mut received = TokenAmount::from_atto(0);
+    let mut gas_spent = TokenAmount::from_atto(0);
+    let mut checkpoint_gas_spent = TokenAmount::from_atto(0);
+    for a in feed {
+        let value = TokenAmount::try_from(&a.value).unwrap_or_default();
+        if let Some(fee) = &a.gas_fee {
+            let fee = TokenAmount::try_from(fee).unwrap_or_default();
+            if a.operation == Some(KeyOperation::Checkpoint) {
+                checkpoint_gas_spent += fee.clone();
+            }
+            gas_spent += fee;
+        }
+        if !a.success {
+            continue;
+        }
+        match a.operation {
+            Some(KeyOperation::Fund) if a.kind == ActivityKind::Send => funded += value.clone(),
+            Some(KeyOperation::Release) if a.kind == ActivityKind::Send => {
+                released += value.clone()
+            }
+            _ => {}
+        }
+        match a.kind {
+            ActivityKind::Send | ActivityKind::CrossNetSend => sent += value,
+            ActivityKind::Receive | ActivityKind::CrossNetReceive => received += value,
+        }
+    }
+    ActivitySummary {
+        sent: Amount::from(sent),
+        received: Amount::from(received),
+        gas_spent: Amount::from(gas_spent),
+        checkpoint_gas_spent: Amount::from(checkpoint_gas_spent),
+        funded: Amount::from(funded),
+        released: Amount::from(released),
+    }
+}
+
+/// Splits the `feed` of `account` in `subnet` into periods of `period` epochs from `from_epoch`,
+/// and sums each of them. Only the periods with activity have a row, the oldest first.
+pub fn accounting_rows(
+    subnet: &str,
+    account: &str,
+    feed: &[Activity],
+    from_epoch: ChainEpoch,
+    period: ChainEpoch,
+) -> Vec<AccountingRow> {
+    let mut periods = BTreeMap::<ChainEpoch, Vec<Activity>>::new();
+    for a in feed.iter().filter(|a| a.epoch >= from_epoch) {
+        let start = from_epoch + (a.epoch - from_epoch) / period * period;
+        periods.entry(start).or_default().push(a.clone());
+    }
+    periods
+        .into_iter()
+        .map(|(start, activity)| AccountingRow {
+            subnet: subnet.to_string(),
+            account: account.to_string(),
+            from_epoch: start,
+            to_epoch: start + period,
+            summary: summarize(&activity),
+        })
+        .collect()
+}
+
+/// Parses an amount in whole FIL, as the cross-net messages of the history store them, e.g.
+/// `1.5`.
+fn parse_whole_fil(s: &str) -> Option<TokenAmount> {
+    let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+    if frac.len() > 18 {
+        return None;
+    }
+    let atto = format!("{whole:}{frac:0<18}");
+    Some(TokenAmount::from_atto(BigInt::from_str(&atto).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+
+    use crate::checkpoint::DiffCrossMsg;
+    use crate::history::AccountCrossMsg;
+    use crate::manager::activity::{
+        accounting_rows, activity_feed, parse_whole_fil, summarize, ActivityKind, ExecutedMessage,
+        ReplacementGas,
+    };
+    use crate::manager::key_usage::KeyOperation;
+
+    #[test]
+    fn test_activity_feed() {
+        let account = Address::new_id(100);
+        let other = Address::new_id(200);
+        let message = |id: &str, epoch, from, to, value, success| ExecutedMessage {
+            id: id.to_string(),
+            epoch,
+            from,
+            to,
+            value: TokenAmount::from_whole(value),
+            method: 0,
+            gas_fee: TokenAmount::from_nano(10),
+            success,
+        };
+        let messages = vec![
+            message("send", 10, account, other, 2, true),
+            message("receive", 20, other, account, 5, true),
+            message("checkpoint", 30, account, other, 0, true),
+            message("failed", 40, account, other, 1, false),
+        ];
+        let operations = HashMap::from([(String::from("checkpoint"), KeyOperation::Checkpoint)]);
+        let cross_msgs = vec![AccountCrossMsg {
+            subnet: String::from("/r123/f0100"),
+            epoch: 25,
+            sent: true,
+            msg: DiffCrossMsg {
+                from: format!("/r123/f0100:{account:}"),
+                to: format!("/r123:{other:}"),
+                method: 0,
+                value: String::from("1.5"),
+                nonce: 0,
+            },
+        }];
+
+        let feed = activity_feed(&account, &messages, &operations, &cross_msgs);
+        assert_eq!(
+            feed.iter().map(|a| a.epoch).collect::<Vec<_>>(),
+            vec![40, 30, 25, 20, 10]
+        );
+        assert_eq!(feed[1].operation, Some(KeyOperation::Checkpoint));
+        assert_eq!(feed[2].kind, ActivityKind::CrossNetSend);
+        assert_eq!(feed[3].kind, ActivityKind::Receive);
+        assert_eq!(feed[3].gas_fee, None);
+
+        let summary = summarize(&feed);
+        assert_eq!(summary.sent.fil, "3.5");
+        assert_eq!(summary.received.fil, "5");
+        assert_eq!(summary.gas_spent.fil, "0.00000003");
+        assert_eq!(summary.checkpoint_gas_spent.fil, "0.00000001");
+    }
+
+    #[test]
+    fn test_accounting_rows() {
+        let account = Address::new_id(100);
+        let gateway = Address::new_id(64);
+        let messages = (0..4)
+            .map(|i| ExecutedMessage {
+                id: format!("cid{i:}"),
+                epoch: 100 + i * 10,
+                from: account,
+                to: gateway,
+                value: TokenAmount::from_whole(1),
+                method: 0,
+                gas_fee: TokenAmount::from_nano(10),
+                success: true,
+            })
+            .collect::<Vec<_>>();
+        let operations = HashMap::from([
+            (String::from("cid0"), KeyOperation::Fund),
+            (String::from("cid1"), KeyOperation::Release),
+            (String::from("cid3"), KeyOperation::Fund),
+        ]);
+        let feed = activity_feed(&account, &messages, &operations, &[]);
+
+        let rows = accounting_rows("/r123", "f0100", &feed, 100, 20);
+        assert_eq!(
+            rows.iter()
+                .map(|r| (r.from_epoch, r.to_epoch))
+                .collect::<Vec<_>>(),
+            vec![(100, 120), (120, 140)]
+        );
+        assert_eq!(rows[0].to_csv(), "/r123,f0100,100,120,2,0,0.00000002,0,1,1");
+        assert_eq!(rows[1].summary.funded.fil, "1");
+        assert_eq!(rows[1].summary.released.fil, "0");
+    }
+
+    #[test]
+    fn test_parse_whole_fil() {
+        assert_eq!(
+            parse_whole_fil("1.5"),
+            Some(TokenAmount::from_nano(1_500_000_000u64))
+        );
+        assert_eq!(parse_whole_fil("2"), Some(TokenAmount::from_whole(2)));
+        assert_eq!(
+            parse_whole_fil("0.000000000000000001"),
+            Some(TokenAmount::from_atto(1))
+        );
+        assert_eq!(parse_whole_fil("0.0000000000000000001"), None);
+        assert_eq!(parse_whole_fil("one"), None);
+    }
+
+    #[test]
+    fn test_replacement_gas() {
+        let gas = |fee_cap: u64, premium: u64| ReplacementGas {
+            gas_fee_cap: TokenAmount::from_atto(fee_cap),
+            gas_premium: TokenAmount::from_atto(premium),
+            gas_limit: None,
+        };
+        let premium = TokenAmount::from_atto(100);
+        assert!(gas(200, 125).ensure_replaces(&premium).is_ok());
+        assert!(gas(200, 124).ensure_replaces(&premium).is_err());
+        assert!(gas(120, 130).ensure_replaces(&premium).is_err());
+        // rounded up
+        assert!(gas(200, 2)
+            .ensure_replaces(&TokenAmount::from_atto(1))
+            .is_ok());
+        assert!(gas(200, 1)
+            .ensure_replaces(&TokenAmount::from_atto(1))
+            .is_err());
+    }
+}
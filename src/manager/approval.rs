@@ -0,0 +1,253 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Queue of the operations waiting for the approval of an operator.
+//!
+//! Operations above the thresholds of the `approvals` section of the server config are not
+//! executed when requested. They are parked here with the request that triggered them, and only
+//! executed once an operator approves them, before they expire.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The maximum number of decided or expired operations kept for review.
+pub const MAX_DECIDED_OPERATIONS: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Expired,
+    /// Withdrawn by an operator before it was decided.
+    Aborted,
+}
+
+/// An operation parked until an operator approves it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ParkedOperation {
+    pub id: u64,
+    /// The json rpc method of the operation.
+    pub method: String,
+    /// The params the method was called with.
+    pub params: Value,
+    /// The threshold the operation exceeds.
+    pub reason: String,
+    pub status: OperationStatus,
+    /// The unix timestamp, in seconds, the operation was requested at.
+    pub requested_at: u64,
+    /// The unix timestamp, in seconds, after which the operation can no longer be approved.
+    pub expires_at: u64,
+}
+
+#[derive(Default)]
+struct Operations {
+    next_id: u64,
+    operations: VecDeque<ParkedOperation>,
+}
+
+impl Operations {
+    /// Marks the pending operations past their expiry as expired, and drops the oldest decided
+    /// operations above [`MAX_DECIDED_OPERATIONS`].
+    fn refresh(&mut self, now: u64) {
+        for op in self.operations.iter_mut() {
+            if op.status == OperationStatus::Pending && now > op.expires_at {
+                tracing::info!(
+                    "operation {} ({}) expired without approval",
+                    op.id,
+                    op.method
+                );
+                op.status = OperationStatus::Expired;
+            }
+        }
+
+        let mut decided = self
+            .operations
+            .iter()
+            .filter(|op| op.status != OperationStatus::Pending)
+            .count();
+        self.operations.retain(|op| {
+            if decided > MAX_DECIDED_OPERATIONS && op.status != OperationStatus::Pending {
+                decided -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
+
+pub struct OperationApprovals {
+    operations: Mutex<Option<Operations>>,
+}
+
+impl OperationApprovals {
+    pub const fn new() -> Self {
+        Self {
+            operations: Mutex::new(None),
+        }
+    }
+
+    /// Parks the call of `method` with `params` until it is approved, for at most `expiry`.
+    pub fn park(
+        &self,
+        method: &str,
+        params: Value,
+        reason: String,
+        expiry: Duration,
+    ) -> ParkedOperation {
+        let mut operations = self.operations.lock().unwrap();
+        let operations = operations.get_or_insert_with(Operations::default);
+
+        let now = now();
+        operations.refresh(now);
+        operations.next_id += 1;
+        let op = ParkedOperation {
+            id: operations.next_id,
+            method: method.to_string(),
+            params,
+            reason,
+            status: OperationStatus::Pending,
+            requested_at: now,
+            expires_at: now + expiry.as_secs(),
+        };
+        tracing::info!(
+            "operation {} ({}) parked for approval: {}",
+            op.id,
+            op.method,
+            op.reason
+        );
+        operations.operations.push_back(op.clone());
+        op
+    }
+
+    /// Approves or rejects the pending operation `id`, returning it so that the caller executes
+    /// it if approved.
+    pub fn decide(&self, id: u64, approve: bool) -> Result<ParkedOperation> {
+        self.decide_at(id, approve, now())
+    }
+
+    fn decide_at(&self, id: u64, approve: bool, now: u64) -> Result<ParkedOperation> {
+        let status = if approve {
+            OperationStatus::Approved
+        } else {
+            OperationStatus::Rejected
+        };
+        self.close_at(id, status, now)
+    }
+
+    /// Aborts the pending operation `id`, which can then no longer be approved.
+    pub fn abort(&self, id: u64) -> Result<ParkedOperation> {
+        let op = self.close_at(id, OperationStatus::Aborted, now())?;
+        tracing::info!("operation {} ({}) aborted", op.id, op.method);
+        Ok(op)
+    }
+
+    /// Moves the pending operation `id` to `status`.
+    fn close_at(&self, id: u64, status: OperationStatus, now: u64) -> Result<ParkedOperation> {
+        let mut operations = self.operations.lock().unwrap();
+        let operations = operations
+            .as_mut()
+            .ok_or_else(|| anyhow!("no operation parked with id {id:}"))?;
+        operations.refresh(now);
+
+        let op = operations
+            .operations
+            .iter_mut()
+            .find(|op| op.id == id)
+            .ok_or_else(|| anyhow!("no operation parked with id {id:}"))?;
+        if op.status != OperationStatus::Pending {
+            return Err(anyhow!("operation {id:} already {:?}", op.status));
+        }
+
+        op.status = status;
+        Ok(op.clone())
+    }
+
+    /// Returns all the operations parked, oldest first.
+    pub fn all(&self) -> Vec<ParkedOperation> {
+        let mut operations = self.operations.lock().unwrap();
+        match operations.as_mut() {
+            Some(o) => {
+                o.refresh(now());
+                o.operations.iter().cloned().collect()
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Default for OperationApprovals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::manager::approval::{now, OperationApprovals, OperationStatus};
+
+    #[test]
+    fn test_operation_approvals() {
+        let approvals = OperationApprovals::new();
+        let expiry = Duration::from_secs(60);
+
+        let fund = approvals.park("ipc_fund", json!({"amount": 10}), "fund".into(), expiry);
+        let kill = approvals.park("ipc_killSubnet", json!({}), "kill".into(), expiry);
+        let join = approvals.park("ipc_joinSubnet", json!({}), "join".into(), expiry);
+        let release = approvals.park("ipc_release", json!({}), "release".into(), expiry);
+        assert_eq!(fund.status, OperationStatus::Pending);
+
+        let approved = approvals.decide(fund.id, true).unwrap();
+        assert_eq!(approved.status, OperationStatus::Approved);
+        assert_eq!(approved.params, json!({"amount": 10}));
+        // operations are only executed once
+        assert!(approvals.decide(fund.id, true).is_err());
+
+        approvals.decide(kill.id, false).unwrap();
+        assert!(approvals.decide(kill.id, true).is_err());
+        assert!(approvals.abort(kill.id).is_err());
+
+        // aborted operations can no longer be approved
+        assert_eq!(
+            approvals.abort(release.id).unwrap().status,
+            OperationStatus::Aborted
+        );
+        assert!(approvals.decide(release.id, true).is_err());
+
+        // operations past their expiry can no longer be approved
+        assert!(approvals
+            .decide_at(join.id, true, now() + expiry.as_secs() + 1)
+            .is_err());
+        let statuses = approvals
+            .all()
+            .into_iter()
+            .map(|op| op.status)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                OperationStatus::Approved,
+                OperationStatus::Rejected,
+                OperationStatus::Expired,
+                OperationStatus::Aborted
+            ]
+        );
+        assert!(approvals.decide(100, true).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wall-clock estimates for subnet epochs.
+//!
+//! The mapping between the epochs of a subnet and the wall clock is calibrated from its chain:
+//! the timestamp of its genesis, the average block time since then, and the block time over the
+//! last epochs, whose difference with the average is the drift of the block time. Calibrations
+//! are kept for a while so that the scheduler, the ETAs and the catch-up logic share them
+//! instead of measuring the chain on every use.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Subnet;
+use crate::manager::SubnetManager;
+
+/// The block time assumed when it is neither configured nor can be measured from the chain.
+pub const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(30);
+/// The number of epochs sampled to measure the block time of a subnet.
+const BLOCK_TIME_SAMPLE_EPOCHS: ChainEpoch = 100;
+/// How long a calibration is used before the subnet is measured again.
+const CALIBRATION_TTL: Duration = Duration::from_secs(600);
+
+/// The mapping between the epochs of a subnet and the wall clock, measured from its chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochCalibration {
+    /// The unix timestamp, in seconds, of the genesis of the subnet.
+    pub genesis_timestamp: u64,
+    /// The epoch and unix timestamp of the chain head when calibrated.
+    pub head_epoch: ChainEpoch,
+    pub head_timestamp: u64,
+    /// The average block time since genesis.
+    pub average_block_time: Duration,
+    /// The block time to extrapolate from the head: the one in the config of the subnet, if
+    /// set, or the one measured over the last epochs.
+    pub block_time: Duration,
+    /// The unix timestamp, in seconds, of the calibration.
+    pub calibrated_at: u64,
+}
+
+impl EpochCalibration {
+    /// How much slower, in seconds per epoch, the recent blocks are than the average since
+    /// genesis, negative if they are faster.
+    pub fn drift(&self) -> f64 {
+        self.block_time.as_secs_f64() - self.average_block_time.as_secs_f64()
+    }
+
+    /// Estimates the unix timestamp of `epoch`, interpolated between the genesis and the head
+    /// for past epochs, and extrapolated from the head with the block time for the others.
+    pub fn timestamp_of(&self, epoch: ChainEpoch) -> u64 {
+        let secs = if epoch <= self.head_epoch {
+            self.genesis_timestamp as f64 + self.average_block_time.as_secs_f64() * epoch as f64
+        } else {
+            self.head_timestamp as f64
+                + self.block_time.as_secs_f64() * (epoch - self.head_epoch) as f64
+        };
+        secs.max(0.0) as u64
+    }
+
+    /// Estimates the epoch the subnet was, or will be, at the unix timestamp `timestamp`.
+    pub fn epoch_at(&self, timestamp: u64) -> ChainEpoch {
+        let per_epoch = |block_time: Duration| block_time.as_secs_f64().max(f64::EPSILON);
+        let epoch = if timestamp <= self.head_timestamp {
+            timestamp.saturating_sub(self.genesis_timestamp) as f64
+                / per_epoch(self.average_block_time)
+        } else {
+            self.head_epoch as f64
+                + (timestamp - self.head_timestamp) as f64 / per_epoch(self.block_time)
+        };
+        epoch.floor() as ChainEpoch
+    }
+
+    fn is_fresh(&self) -> bool {
+        now().saturating_sub(self.calibrated_at) < CALIBRATION_TTL.as_secs()
+    }
+}
+
+/// Measures the calibration of `subnet` from its chain.
+async fn measure(subnet: &Subnet, manager: &dyn SubnetManager) -> Result<EpochCalibration> {
+    let head_epoch = manager.chain_head_epoch().await?;
+    if head_epoch <= 0 {
+        return Err(anyhow!("subnet {} has no blocks yet", subnet.id));
+    }
+    let head_timestamp = manager.epoch_timestamp(head_epoch).await?;
+    let genesis_timestamp = manager.epoch_timestamp(0).await?;
+    let average_block_time = Duration::from_secs_f64(
+        head_timestamp.saturating_sub(genesis_timestamp) as f64 / head_epoch as f64,
+    );
+
+    let block_time = match subnet.block_time() {
+        Some(block_time) => block_time,
+        None => manager
+            .block_time(BLOCK_TIME_SAMPLE_EPOCHS)
+            .await
+            .unwrap_or(average_block_time),
+    };
+
+    Ok(EpochCalibration {
+        genesis_timestamp,
+        head_epoch,
+        head_timestamp,
+        average_block_time,
+        block_time,
+        calibrated_at: now(),
+    })
+}
+
+/// Returns the calibration of `subnet` in `calibrations`, measuring it if there is none recent
+/// enough.
+pub async fn calibration(
+    calibrations: &CalibrationLog,
+    subnet: &Subnet,
+    manager: &dyn SubnetManager,
+) -> Result<EpochCalibration> {
+    if let Some(c) = calibrations.get(&subnet.id) {
+        return Ok(c);
+    }
+    let c = measure(subnet, manager).await?;
+    tracing::debug!(
+        "calibrated subnet {}: genesis at {}, block time {:?}, drift {:.3}s per epoch",
+        subnet.id,
+        c.genesis_timestamp,
+        c.block_time,
+        c.drift()
+    );
+    calibrations.insert(&subnet.id, c.clone());
+    Ok(c)
+}
+
+pub struct CalibrationLog {
+    subnets: Mutex<Option<HashMap<SubnetID, EpochCalibration>>>,
+}
+
+impl CalibrationLog {
+    pub const fn new() -> Self {
+        Self {
+            subnets: Mutex::new(None),
+        }
+    }
+
+    /// Returns the calibration of `subnet`, if recent enough.
+    pub fn get(&self, subnet: &SubnetID) -> Option<EpochCalibration> {
+        let subnets = self.subnets.lock().unwrap();
+        subnets
+            .as_ref()?
+            .get(subnet)
+            .filter(|c| c.is_fresh())
+            .cloned()
+    }
+
+    pub fn insert(&self, subnet: &SubnetID, calibration: EpochCalibration) {
+        let mut subnets = self.subnets.lock().unwrap();
+        subnets
+            .get_or_insert_with(HashMap::new)
+            .insert(subnet.clone(), calibration);
+    }
+}
+
+impl Default for CalibrationLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Estimated time at which an epoch will be reached in a subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochEta {
+    pub epoch: ChainEpoch,
+    /// Seconds left until the epoch is reached, zero if it is already in the past.
+    pub eta_secs: u64,
+    /// The estimated unix timestamp, in seconds, of the epoch.
+    pub timestamp: u64,
+}
+
+/// Converts epochs of a subnet into wall-clock estimates, anchored at the current chain head.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochClock {
+    current_epoch: ChainEpoch,
+    block_time: Duration,
+    /// Unix timestamp, in seconds, at which `current_epoch` was observed.
+    observed_at: u64,
+}
+
+impl EpochClock {
+    pub fn new(current_epoch: ChainEpoch, block_time: Duration) -> Self {
+        Self::new_at(current_epoch, block_time, now())
+    }
+
+    pub fn new_at(current_epoch: ChainEpoch, block_time: Duration, observed_at: u64) -> Self {
+        Self {
+            current_epoch,
+            block_time,
+            observed_at,
+        }
+    }
+
+    /// Creates the clock of a subnet. The block time in the subnet config takes precedence over
+    /// the one calibrated from the chain, kept in `calibrations`, and [`DEFAULT_BLOCK_TIME`] is
+    /// used if neither is known.
+    pub async fn from_subnet(
+        calibrations: &CalibrationLog,
+        subnet: &Subnet,
+        manager: &dyn SubnetManager,
+    ) -> Result<Self> {
+        let current_epoch = manager.chain_head_epoch().await?;
+
+        let block_time = match subnet.block_time() {
+            Some(block_time) => block_time,
+            None => match calibration(calibrations, subnet, manager).await {
+                Ok(c) => c.block_time,
+                Err(e) => {
+                    tracing::warn!(
+                        "cannot calibrate the clock of subnet {}, using default block time: {e:}",
+                        subnet.id
+                    );
+                    DEFAULT_BLOCK_TIME
+                }
+            },
+        };
+
+        Ok(Self::new(current_epoch, block_time))
+    }
+
+    pub fn current_epoch(&self) -> ChainEpoch {
+        self.current_epoch
+    }
+
+    pub fn block_time(&self) -> Duration {
+        self.block_time
+    }
+
+    /// The time left until `epoch` is reached, zero if it is already in the past, saturating at
+    /// [`Duration::MAX`] for epochs too far ahead to be represented.
+    pub fn time_until(&self, epoch: ChainEpoch) -> Duration {
+        let epochs =
+            u32::try_from(epoch.saturating_sub(self.current_epoch).max(0)).unwrap_or(u32::MAX);
+        self.block_time.checked_mul(epochs).unwrap_or(Duration::MAX)
+    }
+
+    /// Estimates when `epoch` is reached.
+    pub fn eta(&self, epoch: ChainEpoch) -> EpochEta {
+        let secs = self.block_time.as_secs_f64() * (epoch - self.current_epoch) as f64;
+        let timestamp = (self.observed_at as f64 + secs).max(0.0) as u64;
+
+        EpochEta {
+            epoch,
+            eta_secs: self.time_until(epoch).as_secs(),
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use fvm_shared::clock::ChainEpoch;
+
+    use crate::manager::clock::{EpochCalibration, EpochClock, EpochEta};
+
+    #[test]
+    fn test_eta() {
+        let clock = EpochClock::new_at(100, Duration::from_secs(30), 1_000_000);
+
+        assert_eq!(
+            clock.eta(110),
+            EpochEta {
+                epoch: 110,
+                eta_secs: 300,
+                timestamp: 1_000_300,
+            }
+        );
+        // epochs in the past are already reached
+        assert_eq!(
+            clock.eta(90),
+            EpochEta {
+                epoch: 90,
+                eta_secs: 0,
+                timestamp: 999_700,
+            }
+        );
+    }
+
+    #[test]
+    fn test_time_until_saturates() {
+        let clock = EpochClock::new_at(0, Duration::from_secs(30), 1_000_000);
+
+        assert_eq!(
+            clock.time_until(u32::MAX as i64 + 1),
+            Duration::from_secs(30) * u32::MAX
+        );
+        assert_eq!(
+            EpochClock::new_at(0, Duration::MAX, 1_000_000).time_until(2),
+            Duration::MAX
+        );
+        assert_eq!(clock.time_until(ChainEpoch::MIN), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_calibration() {
+        // 30s blocks on average, 10s blocks lately
+        let calibration = EpochCalibration {
+            genesis_timestamp: 1_000_000,
+            head_epoch: 1000,
+            head_timestamp: 1_030_000,
+            average_block_time: Duration::from_secs(30),
+            block_time: Duration::from_secs(10),
+            calibrated_at: 1_030_000,
+        };
+        assert_eq!(calibration.drift(), -20.0);
+
+        assert_eq!(calibration.timestamp_of(0), 1_000_000);
+        assert_eq!(calibration.timestamp_of(500), 1_015_000);
+        assert_eq!(calibration.timestamp_of(1100), 1_031_000);
+
+        assert_eq!(calibration.epoch_at(999_000), 0);
+        assert_eq!(calibration.epoch_at(1_015_000), 500);
+        assert_eq!(calibration.epoch_at(1_031_005), 1100);
+    }
+}
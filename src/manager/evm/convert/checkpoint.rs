@@ -37,7 +37,9 @@ macro_rules! bottom_up_checkpoint {
             fn try_from(value: NativeChildCheck) -> Result<Self, Self::Error> {
                 let vec_to_array = |v: Vec<u8>| {
                     let bytes = if v.len() > 32 {
-                        log::warn!("child check more than 32 bytes, taking only first 32 bytes");
+                        tracing::warn!(
+                            "child check more than 32 bytes, taking only first 32 bytes"
+                        );
                         &v[0..32]
                     } else {
                         &v
@@ -107,7 +107,7 @@ macro_rules! cross_msg_types {
             fn try_from(value: StorableMsg) -> Result<Self, Self::Error> {
                 let msg_value = fil_to_eth_amount(&value.value)?;
 
-                log::info!(
+                tracing::info!(
                     "storable message token amount: {:}, converted: {:?}",
                     value.value.atto().to_string(),
                     msg_value
@@ -284,7 +284,7 @@ pub fn eth_to_fil_amount(amount: &U256) -> anyhow::Result<TokenAmount> {
 
 pub fn ethers_address_to_fil_address(addr: &ethers::types::Address) -> anyhow::Result<Address> {
     let raw_addr = format!("{addr:?}");
-    log::debug!("raw evm subnet addr: {raw_addr:}");
+    tracing::debug!("raw evm subnet addr: {raw_addr:}");
 
     let eth_addr = EthAddress::from_str(&raw_addr)?;
     Ok(Address::from(eth_addr))
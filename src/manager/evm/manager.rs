@@ -1,32 +1,47 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
-use crate::checkpoint::NativeBottomUpCheckpoint;
+use crate::checkpoint::{next_checkpoint_epoch, NativeBottomUpCheckpoint, SubmittedCheckpoint};
+use crate::manager::evm::eth_to_fil_amount;
 pub use crate::manager::evm::{ethers_address_to_fil_address, fil_to_eth_amount};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use ethers::abi::Tokenizable;
+use ethers::abi::{AbiDecode, Tokenizable};
 use ethers::prelude::k256::ecdsa::SigningKey;
 use ethers::prelude::{abigen, Signer, SignerMiddleware};
-use ethers::providers::{Authorization, Http, Middleware, Provider};
+use ethers::providers::{Authorization, Http, Middleware, PendingTransaction, Provider};
 use ethers::signers::{LocalWallet, Wallet};
-use ethers::types::{Eip1559TransactionRequest, I256, U256};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, BlockNumber, Eip1559TransactionRequest, TxHash, I256, U256};
 use fvm_shared::address::Payload;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
+use ipc_gateway::checkpoint::BatchCrossMsgs;
 use ipc_gateway::TopDownCheckpoint;
 use ipc_identity::{EvmKeyStore, PersistentKeyStore};
 use ipc_sdk::subnet_id::SubnetID;
 use ipc_subnet_actor::ConstructParams;
 use num_traits::ToPrimitive;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 
-use crate::config::subnet::SubnetConfig;
+use crate::config::subnet::{GasConfig, GasLimitPolicy, SubnetConfig, SubnetMode};
 use crate::config::Subnet;
-use crate::lotus::message::ipc::{QueryValidatorSetResponse, SubnetInfo, Validator, ValidatorSet};
+use crate::lotus::message::ipc::{
+    FederatedValidator, JoinRequirements, PermissionMode, QueryValidatorSetResponse,
+    SubnetCreationCost, SubnetInfo, Validator, ValidatorSet,
+};
+use crate::manager::activity::{
+    ExecutedMessage, MessageLookup, MessageReceipt, PendingMessage, ReplacementGas,
+};
+use crate::manager::funds::ensure_funds;
+use crate::manager::key_usage::KeyOperation;
+use crate::manager::offline::{self, OfflineCall, OfflineTx, TxNetwork};
 use crate::manager::{EthManager, SubnetManager};
+use crate::state::AgentState;
 
 pub type DefaultSignerMiddleware = SignerMiddleware<Provider<Http>, Wallet<SigningKey>>;
 
@@ -64,16 +79,48 @@ abigen!(
     "contracts/GatewayMessengerFacet.json"
 );
 abigen!(SubnetRegistry, "contracts/SubnetRegistry.json");
+// Permission modes are not part of the ABI of the contracts bundled with the agent, so their
+// methods are declared here to support subnet actors deployed with newer contracts.
+abigen!(
+    SubnetActorPermissionFacet,
+    r#"[
+        function permissionMode() external view returns (uint8)
+        function setFederatedPower(address[] calldata validators, bytes[] calldata publicKeys, uint256[] calldata powers) external
+    ]"#
+);
+// Registries charging a fee to deploy subnet actors expose it with a getter that is not part of
+// the ABI of the bundled registry either, which charges none.
+abigen!(
+    SubnetRegistryFeeFacet,
+    r#"[
+        function creationFee() external view returns (uint256)
+    ]"#
+);
+// The membership of the subnet consensus is set in the gateway of the subnet by its nodes, the
+// getter is not part of the ABI of the contracts bundled with the agent either.
+abigen!(
+    GatewayMembershipFacet,
+    r#"[
+        struct Validator { uint256 weight; address addr; }
+        struct Membership { Validator[] validators; uint64 configurationNumber; }
+        function getCurrentMembership() external view returns (Membership memory)
+    ]"#
+);
 
 pub struct EthSubnetManager {
     keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
     ipc_contract_info: IPCContractInfo,
+    gas: GasConfig,
+    mode: SubnetMode,
+    /// The state of the agent the nonces and the keys used are kept in.
+    state: Arc<AgentState>,
 }
 
 /// Keep track of the on chain information for the subnet manager
 struct IPCContractInfo {
     gateway_addr: ethers::types::Address,
     registry_addr: ethers::types::Address,
+    subnet: SubnetID,
     chain_id: u64,
     provider: Provider<Http>,
 }
@@ -81,53 +128,30 @@ struct IPCContractInfo {
 #[async_trait]
 impl SubnetManager for EthSubnetManager {
     async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address> {
-        self.ensure_same_gateway(&params.ipc_gateway_addr)?;
-
-        let name_len = params.name.as_bytes().len();
-        if name_len > SUBNET_NAME_MAX_LEN {
-            return Err(anyhow!("subnet name too long"));
-        }
-        let mut name = [0u8; SUBNET_NAME_MAX_LEN];
-        name[0..name_len].copy_from_slice(params.name.as_bytes());
-
-        let min_validator_stake = params
-            .min_validator_stake
-            .atto()
-            .to_u128()
-            .ok_or_else(|| anyhow!("invalid min validator stake"))?;
-
-        log::debug!("calling create subnet for EVM manager");
-
-        let route = agent_subnet_to_evm_addresses(&params.parent)?;
-        log::debug!("root SubnetID as Ethereum type: {route:?}");
-
-        let params = subnet_registry::ConstructParams {
-            parent_id: subnet_registry::SubnetID {
-                root: params.parent.root_id(),
-                route,
-            },
-            name,
-            ipc_gateway_addr: self.ipc_contract_info.gateway_addr,
-            consensus: params.consensus as u64 as u8,
-            min_activation_collateral: ethers::types::U256::from(min_validator_stake),
-            min_validators: params.min_validators,
-            bottom_up_check_period: params.bottomup_check_period as u64,
-            top_down_check_period: params.topdown_check_period as u64,
-            majority_percentage: SUBNET_MAJORITY_PERCENTAGE,
-            genesis: ethers::types::Bytes::default(),
-        };
-
-        log::info!("creating subnet on evm with params: {params:?}");
+        let params = self.registry_construct_params(params)?;
+        tracing::info!("creating subnet on evm with params: {params:?}");
 
+        let fee = self.registry_creation_fee().await?;
         let signer = self.get_signer(&from)?;
         let signer = Arc::new(signer);
         let registry_contract =
             SubnetRegistry::new(self.ipc_contract_info.registry_addr, signer.clone());
 
-        let call = call_with_premium_estimation(signer, registry_contract.new_subnet_actor(params))
-            .await?;
+        let mut call = registry_contract.new_subnet_actor(params);
+        if !fee.is_zero() {
+            tracing::info!("paying a fee of {fee:} to the registry to create the subnet");
+            call = call.value(fee);
+        }
+        let call = call_with_premium_estimation(
+            signer.clone(),
+            call,
+            self.gas_policy(KeyOperation::CreateSubnet),
+        )
+        .await?;
         // TODO: Edit call to get estimate premium
-        let pending_tx = call.send().await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::CreateSubnet, &signer, call.tx)
+            .await?;
         // We need the retry to parse the deployment event. At the time of this writing, it's a bug
         // in current FEVM that without the retries, events are not picked up.
         // See https://github.com/filecoin-project/community/discussions/638 for more info and updates.
@@ -135,18 +159,18 @@ impl SubnetManager for EthSubnetManager {
         match receipt {
             Some(r) => {
                 for log in r.logs {
-                    log::debug!("log: {log:?}");
+                    tracing::debug!("log: {log:?}");
 
                     match ethers_contract::parse_log::<subnet_registry::SubnetDeployedFilter>(log) {
                         Ok(subnet_deploy) => {
                             let subnet_registry::SubnetDeployedFilter { subnet_addr } =
                                 subnet_deploy;
 
-                            log::debug!("subnet deployed at {subnet_addr:?}");
+                            tracing::debug!("subnet deployed at {subnet_addr:?}");
                             return ethers_address_to_fil_address(&subnet_addr);
                         }
                         Err(_) => {
-                            log::debug!("no event for subnet actor published yet, continue");
+                            tracing::debug!("no event for subnet actor published yet, continue");
                             continue;
                         }
                     }
@@ -157,6 +181,31 @@ impl SubnetManager for EthSubnetManager {
         }
     }
 
+    async fn estimate_create_subnet(
+        &self,
+        from: Address,
+        params: ConstructParams,
+    ) -> Result<SubnetCreationCost> {
+        let params = self.registry_construct_params(params)?;
+        let fee = self.registry_creation_fee().await?;
+
+        let provider = Arc::new(self.ipc_contract_info.provider.clone());
+        let registry_contract =
+            SubnetRegistry::new(self.ipc_contract_info.registry_addr, provider.clone());
+        let gas = registry_contract
+            .new_subnet_actor(params)
+            .from(payload_to_evm_address(from.payload())?)
+            .value(fee)
+            .estimate_gas()
+            .await?;
+        let (_, max_fee_per_gas) = premium_estimation(provider).await?;
+
+        Ok(SubnetCreationCost {
+            registry_fee: eth_to_fil_amount(&fee)?,
+            gas_fee: eth_to_fil_amount(&(gas * max_fee_per_gas))?,
+        })
+    }
+
     async fn join_subnet(
         &self,
         subnet: SubnetID,
@@ -171,7 +220,7 @@ impl SubnetManager for EthSubnetManager {
             .ok_or_else(|| anyhow!("invalid min validator stake"))?;
 
         let address = contract_address_from_subnet(&subnet)?;
-        log::info!(
+        tracing::info!(
             "interacting with evm subnet contract: {address:} with collateral: {collateral:}"
         );
 
@@ -183,41 +232,59 @@ impl SubnetManager for EthSubnetManager {
             subnet_actor_manager_facet::FvmAddress::from(worker_addr),
         );
         txn.tx.set_value(collateral);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::JoinSubnet),
+        )
+        .await?;
 
-        txn.send().await?.await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::JoinSubnet, &signer, txn.tx)
+            .await?;
+        pending_tx.await?;
 
         Ok(())
     }
 
     async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
         let address = contract_address_from_subnet(&subnet)?;
-        log::info!("leaving evm subnet: {subnet:} at contract: {address:}");
+        tracing::info!("leaving evm subnet: {subnet:} at contract: {address:}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let contract = SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.leave())
-            .await?
-            .send()
-            .await?
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            contract.leave(),
+            self.gas_policy(KeyOperation::LeaveSubnet),
+        )
+        .await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::LeaveSubnet, &signer, txn.tx)
             .await?;
+        pending_tx.await?;
 
         Ok(())
     }
 
     async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
         let address = contract_address_from_subnet(&subnet)?;
-        log::info!("kill evm subnet: {subnet:} at contract: {address:}");
+        tracing::info!("kill evm subnet: {subnet:} at contract: {address:}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let contract = SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.kill())
-            .await?
-            .send()
-            .await?
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            contract.kill(),
+            self.gas_policy(KeyOperation::KillSubnet),
+        )
+        .await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::KillSubnet, &signer, txn.tx)
             .await?;
+        pending_tx.await?;
 
         Ok(())
     }
@@ -236,7 +303,7 @@ impl SubnetManager for EthSubnetManager {
         let mut s = HashMap::new();
 
         let evm_subnets = gateway_contract.list_subnets().call().await?;
-        log::debug!("raw subnet: {evm_subnets:?}");
+        tracing::debug!("raw subnet: {evm_subnets:?}");
 
         for subnet in evm_subnets {
             let info = SubnetInfo::try_from(subnet)?;
@@ -261,10 +328,10 @@ impl SubnetManager for EthSubnetManager {
             .to_u128()
             .ok_or_else(|| anyhow!("invalid value to fund"))?;
 
-        log::info!("fund with evm gateway contract: {gateway_addr:} with value: {value:}, original: {amount:?}");
+        tracing::info!("fund with evm gateway contract: {gateway_addr:} with value: {value:}, original: {amount:?}");
 
         let evm_subnet_id = gateway_manager_facet::SubnetID::try_from(&subnet)?;
-        log::debug!("evm subnet id to fund: {evm_subnet_id:?}");
+        tracing::debug!("evm subnet id to fund: {evm_subnet_id:?}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let gateway_contract =
@@ -275,9 +342,13 @@ impl SubnetManager for EthSubnetManager {
             gateway_manager_facet::FvmAddress::try_from(to)?,
         );
         txn.tx.set_value(value);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn =
+            call_with_premium_estimation(signer.clone(), txn, self.gas_policy(KeyOperation::Fund))
+                .await?;
 
-        let pending_tx = txn.send().await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::Fund, &signer, txn.tx)
+            .await?;
         let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
         block_number_from_receipt(receipt)
     }
@@ -297,16 +368,23 @@ impl SubnetManager for EthSubnetManager {
             .to_u128()
             .ok_or_else(|| anyhow!("invalid value to fund"))?;
 
-        log::info!("release with evm gateway contract: {gateway_addr:} with value: {value:}");
+        tracing::info!("release with evm gateway contract: {gateway_addr:} with value: {value:}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let gateway_contract =
             GatewayManagerFacet::new(self.ipc_contract_info.gateway_addr, signer.clone());
         let mut txn = gateway_contract.release(gateway_manager_facet::FvmAddress::try_from(to)?);
         txn.tx.set_value(value);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::Release),
+        )
+        .await?;
 
-        let pending_tx = txn.send().await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::Release, &signer, txn.tx)
+            .await?;
         let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
         block_number_from_receipt(receipt)
     }
@@ -318,6 +396,7 @@ impl SubnetManager for EthSubnetManager {
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
     ) -> Result<()> {
         if postbox_msg_key.len() != 32 {
             return Err(anyhow!(
@@ -328,7 +407,12 @@ impl SubnetManager for EthSubnetManager {
 
         self.ensure_same_gateway(&gateway_addr)?;
 
-        log::info!("propagate postbox evm gateway contract: {gateway_addr:} with message key: {postbox_msg_key:?}");
+        let value = fee
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid propagation fee"))?;
+
+        tracing::info!("propagate postbox evm gateway contract: {gateway_addr:} with message key: {postbox_msg_key:?}, extra fee: {fee:}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let gateway_contract =
@@ -337,9 +421,15 @@ impl SubnetManager for EthSubnetManager {
         let mut key = [0u8; 32];
         key.copy_from_slice(&postbox_msg_key);
 
-        call_with_premium_estimation(signer, gateway_contract.propagate(key))
-            .await?
-            .send()
+        let mut txn = gateway_contract.propagate(key);
+        txn.tx.set_value(value);
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::Propagate),
+        )
+        .await?;
+        self.send_transaction(&from, KeyOperation::Propagate, &signer, txn.tx)
             .await?;
 
         Ok(())
@@ -353,16 +443,20 @@ impl SubnetManager for EthSubnetManager {
     ) -> Result<()> {
         self.ensure_same_gateway(&gateway_addr)?;
 
-        log::info!("send evm cross messages to gateway contract: {gateway_addr:} with message: {cross_msg:?}");
+        tracing::info!("send evm cross messages to gateway contract: {gateway_addr:} with message: {cross_msg:?}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let gateway_contract =
             GatewayMessengerFacet::new(self.ipc_contract_info.gateway_addr, signer.clone());
 
         let evm_cross_msg = gateway_messenger_facet::CrossMsg::try_from(cross_msg)?;
-        call_with_premium_estimation(signer, gateway_contract.send_cross_message(evm_cross_msg))
-            .await?
-            .send()
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            gateway_contract.send_cross_message(evm_cross_msg),
+            self.gas_policy(KeyOperation::SendCrossMessage),
+        )
+        .await?;
+        self.send_transaction(&from, KeyOperation::SendCrossMessage, &signer, txn.tx)
             .await?;
 
         Ok(())
@@ -375,7 +469,7 @@ impl SubnetManager for EthSubnetManager {
         net_addr: String,
     ) -> Result<()> {
         let address = contract_address_from_subnet(&subnet)?;
-        log::info!(
+        tracing::info!(
             "set validator net addr: {net_addr:} on evm subnet: {subnet:} at contract: {address:}"
         );
 
@@ -384,9 +478,17 @@ impl SubnetManager for EthSubnetManager {
 
         let txn = contract.set_validator_net_addr(net_addr);
 
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::SetValidatorNetAddr),
+        )
+        .await?;
 
-        txn.send().await?.await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::SetValidatorNetAddr, &signer, txn.tx)
+            .await?;
+        pending_tx.await?;
 
         Ok(())
     }
@@ -398,7 +500,7 @@ impl SubnetManager for EthSubnetManager {
         worker_addr: Address,
     ) -> Result<()> {
         let address = contract_address_from_subnet(&subnet)?;
-        log::info!("set validator worker addr: {worker_addr:} on evm subnet: {subnet:} at contract: {address:}");
+        tracing::info!("set validator worker addr: {worker_addr:} on evm subnet: {subnet:} at contract: {address:}");
 
         let signer = Arc::new(self.get_signer(&from)?);
         let contract = SubnetActorManagerFacet::new(address, signer.clone());
@@ -406,9 +508,17 @@ impl SubnetManager for EthSubnetManager {
         let txn = contract
             .set_validator_worker_addr(subnet_actor_manager_facet::FvmAddress::from(worker_addr));
 
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::SetValidatorWorkerAddr),
+        )
+        .await?;
 
-        txn.send().await?.await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::SetValidatorWorkerAddr, &signer, txn.tx)
+            .await?;
+        pending_tx.await?;
 
         Ok(())
     }
@@ -417,15 +527,25 @@ impl SubnetManager for EthSubnetManager {
     async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()> {
         let signer = Arc::new(self.get_signer(&from)?);
         let (fee, fee_cap) = premium_estimation(signer.clone()).await?;
-        let tx = Eip1559TransactionRequest::new()
+        let mut tx = Eip1559TransactionRequest::new()
+            .from(signer.address())
             .to(payload_to_evm_address(to.payload())?)
             .value(fil_to_eth_amount(&amount)?)
             .max_priority_fee_per_gas(fee)
             .max_fee_per_gas(fee_cap);
+        let estimated = signer
+            .estimate_gas(&TypedTransaction::Eip1559(tx.clone()), None)
+            .await?;
+        tx = tx.gas(
+            self.gas_policy(KeyOperation::SendValue)
+                .apply(estimated.as_u64()),
+        );
 
-        let tx_pending = signer.send_transaction(tx, None).await?;
+        let tx_pending = self
+            .send_transaction(&from, KeyOperation::SendValue, &signer, tx.into())
+            .await?;
 
-        log::info!(
+        tracing::info!(
             "sending FIL from {from:} to {to:} in tx {:?}",
             tx_pending.tx_hash()
         );
@@ -433,6 +553,40 @@ impl SubnetManager for EthSubnetManager {
         Ok(())
     }
 
+    async fn faucet_drip(
+        &self,
+        faucet: ethers::types::Address,
+        from: Address,
+        to: Address,
+    ) -> Result<()> {
+        let signer = Arc::new(self.get_signer(&from)?);
+        let (fee, fee_cap) = premium_estimation(signer.clone()).await?;
+        let mut tx = Eip1559TransactionRequest::new()
+            .from(signer.address())
+            .to(faucet)
+            .data(drip_calldata(payload_to_evm_address(to.payload())?))
+            .max_priority_fee_per_gas(fee)
+            .max_fee_per_gas(fee_cap);
+        let estimated = signer
+            .estimate_gas(&TypedTransaction::Eip1559(tx.clone()), None)
+            .await?;
+        tx = tx.gas(
+            self.gas_policy(KeyOperation::FaucetDrip)
+                .apply(estimated.as_u64()),
+        );
+
+        let tx_pending = self
+            .send_transaction(&from, KeyOperation::FaucetDrip, &signer, tx.into())
+            .await?;
+
+        tracing::info!(
+            "requesting test funds for {to:} from faucet {faucet:?} in tx {:?}",
+            tx_pending.tx_hash()
+        );
+        tx_pending.await?;
+        Ok(())
+    }
+
     async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
         let balance = self
             .ipc_contract_info
@@ -462,7 +616,7 @@ impl SubnetManager for EthSubnetManager {
         to_epoch: ChainEpoch,
     ) -> Result<Vec<NativeBottomUpCheckpoint>> {
         let address = contract_address_from_subnet(&subnet_id)?;
-        log::info!("listing checkpoints in evm subnet: {subnet_id} at contract: {address}");
+        tracing::info!("listing checkpoints in evm subnet: {subnet_id} at contract: {address}");
 
         let contract =
             SubnetActorGetterFacet::new(address, Arc::new(self.ipc_contract_info.provider.clone()));
@@ -470,75 +624,649 @@ impl SubnetManager for EthSubnetManager {
             .list_bottom_up_checkpoints(from_epoch as u64, to_epoch as u64)
             .call()
             .await?;
-        log::debug!("list of bottom up checkpoints from evm: {checkpoints:?}");
+        tracing::debug!("list of bottom up checkpoints from evm: {checkpoints:?}");
 
         let checkpoints = checkpoints
             .into_iter()
             .map(NativeBottomUpCheckpoint::try_from)
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(checkpoints)
+        Ok(checkpoints)
+    }
+
+    async fn get_validator_set(
+        &self,
+        subnet_id: &SubnetID,
+        gateway: Option<Address>,
+    ) -> Result<QueryValidatorSetResponse> {
+        // we do optionally check as gateway addr is already part of the struct
+        if let Some(addr) = gateway {
+            self.ensure_same_gateway(&addr)?;
+        }
+
+        // get genesis epoch from gateway
+        let evm_subnet_id = gateway_getter_facet::SubnetID::try_from(subnet_id)?;
+        tracing::debug!("evm subnet id: {evm_subnet_id:?}");
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let (exists, evm_subnet) = gateway_contract.get_subnet(evm_subnet_id).call().await?;
+        if !exists {
+            return Err(anyhow!("subnet: {subnet_id:?} does not exists"));
+        }
+        let genesis_epoch = evm_subnet.genesis_epoch.as_u64() as i64;
+
+        let min_validators = self.min_validators(subnet_id).await?;
+
+        // get validator set
+        let address = contract_address_from_subnet(subnet_id)?;
+        tracing::debug!("get validator info for subnet: {subnet_id:} at contract: {address:}");
+
+        let contract =
+            SubnetActorGetterFacet::new(address, Arc::new(self.ipc_contract_info.provider.clone()));
+        let evm_validator_set = contract.get_validator_set().call().await?;
+
+        let mut validators = vec![];
+        for v in evm_validator_set.validators.into_iter() {
+            validators.push(Validator {
+                // we are using worker address here so that the fvm validator node can pick up
+                // the correct
+                addr: Address::try_from(v.worker_addr.clone())?.to_string(),
+                net_addr: v.net_addresses,
+                worker_addr: Some(Address::try_from(v.worker_addr)?.to_string()),
+                weight: v.weight.to_string(),
+            });
+        }
+        let mut validator_set = ValidatorSet {
+            validators: None,
+            configuration_number: evm_validator_set.configuration_number,
+        };
+        if !validators.is_empty() {
+            validator_set.validators = Some(validators);
+        }
+
+        Ok(QueryValidatorSetResponse {
+            validator_set,
+            min_validators,
+            genesis_epoch,
+        })
+    }
+
+    async fn bottom_up_check_period(&self, gateway_addr: &Address) -> Result<ChainEpoch> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        Ok(gateway_contract.bottom_up_check_period().call().await? as ChainEpoch)
+    }
+
+    async fn top_down_check_period(&self, gateway_addr: &Address) -> Result<ChainEpoch> {
+        self.ensure_same_gateway(gateway_addr)?;
+        self.gateway_top_down_check_period().await
+    }
+
+    async fn cross_msg_fee(&self, gateway_addr: &Address) -> Result<TokenAmount> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        eth_to_fil_amount(&gateway_contract.cross_msg_fee().call().await?)
+    }
+
+    async fn chain_head_epoch(&self) -> Result<ChainEpoch> {
+        self.current_epoch().await
+    }
+
+    async fn chain_head_timestamp(&self) -> Result<u64> {
+        let head = self
+            .ipc_contract_info
+            .provider
+            .get_block(ethers::types::BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("latest block not found"))?;
+        Ok(head.timestamp.as_u64())
+    }
+
+    async fn epoch_timestamp(&self, epoch: ChainEpoch) -> Result<u64> {
+        let block = self
+            .ipc_contract_info
+            .provider
+            .get_block(epoch as u64)
+            .await?
+            .ok_or_else(|| anyhow!("block {epoch:} not found"))?;
+        Ok(block.timestamp.as_u64())
+    }
+
+    async fn block_time(&self, sample: ChainEpoch) -> Result<Duration> {
+        let provider = &self.ipc_contract_info.provider;
+        let head = provider.get_block_number().await?.as_u64();
+        let from = head.saturating_sub(sample as u64);
+        if head == from {
+            return Err(anyhow!("not enough blocks to measure the block time"));
+        }
+
+        let head_block = provider
+            .get_block(head)
+            .await?
+            .ok_or_else(|| anyhow!("block {head:} not found"))?;
+        let from_block = provider
+            .get_block(from)
+            .await?
+            .ok_or_else(|| anyhow!("block {from:} not found"))?;
+        let elapsed = head_block
+            .timestamp
+            .saturating_sub(from_block.timestamp)
+            .as_u64();
+
+        Ok(Duration::from_secs_f64(
+            elapsed as f64 / (head - from) as f64,
+        ))
+    }
+
+    async fn pending_bottom_up_msgs(
+        &self,
+        gateway_addr: &Address,
+    ) -> Result<(ChainEpoch, BatchCrossMsgs)> {
+        let period = self.bottom_up_check_period(gateway_addr).await?;
+        let epoch = next_checkpoint_epoch(self.current_epoch().await?, period);
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        let (exists, checkpoint) = gateway_contract
+            .bottom_up_checkpoint_at_epoch(epoch as u64)
+            .call()
+            .await?;
+        if !exists {
+            // no cross message has been committed for the window yet
+            return Ok((epoch, BatchCrossMsgs::default()));
+        }
+
+        tracing::debug!("raw pending bottom up checkpoint from gateway: {checkpoint:?}");
+        let token = checkpoint.into_token();
+        let checkpoint = subnet_actor_manager_facet::BottomUpCheckpoint::from_token(token)?;
+        let checkpoint = NativeBottomUpCheckpoint::try_from(checkpoint)?;
+
+        Ok((epoch, checkpoint.cross_msgs))
+    }
+
+    async fn bottom_up_checkpoint_template(
+        &self,
+        gateway_addr: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<NativeBottomUpCheckpoint> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let (exists, checkpoint) = gateway_contract
+            .bottom_up_checkpoint_at_epoch(epoch as u64)
+            .call()
+            .await?;
+        if !exists {
+            return Err(anyhow!(
+                "no bottom-up checkpoint cut in the gateway for epoch: {epoch:}"
+            ));
+        }
+
+        let token = checkpoint.into_token();
+        let checkpoint = subnet_actor_manager_facet::BottomUpCheckpoint::from_token(token)?;
+        NativeBottomUpCheckpoint::try_from(checkpoint)
+    }
+
+    async fn build_unsigned_tx(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<Vec<u8>> {
+        let tx = self.prepare_call(gateway_addr, from, call).await?;
+        tracing::debug!("unsigned transaction built: {tx:?}");
+
+        Ok(serde_json::to_vec(&tx)?)
+    }
+
+    async fn send_signed_tx(&self, tx: &OfflineTx) -> Result<String> {
+        if tx.network != TxNetwork::Fevm {
+            return Err(anyhow!("not a fevm transaction"));
+        }
+        self.mode
+            .ensure_can_submit(&self.ipc_contract_info.subnet)?;
+        self.ensure_chain_id().await?;
+        let raw = offline::evm_signed_transaction(tx)?;
+
+        let pending_tx = self
+            .ipc_contract_info
+            .provider
+            .send_raw_transaction(raw)
+            .await?;
+        self.state.counters.messages_sent.inc();
+
+        let tx_hash = pending_tx.tx_hash();
+        tracing::info!("signed transaction sent with hash: {tx_hash:?}");
+        Ok(format!("{tx_hash:?}"))
+    }
+
+    async fn estimate_fee(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<TokenAmount> {
+        let tx = self.prepare_call(gateway_addr, from, call).await?;
+        let gas = tx.gas().cloned().unwrap_or_default();
+        let max_fee_per_gas = tx.gas_price().unwrap_or_default();
+        eth_to_fil_amount(&(gas * max_fee_per_gas))
+    }
+
+    async fn join_requirements(&self, subnet: &SubnetID) -> Result<JoinRequirements> {
+        let address = contract_address_from_subnet(subnet)?;
+        let contract =
+            SubnetActorGetterFacet::new(address, Arc::new(self.ipc_contract_info.provider.clone()));
+
+        Ok(JoinRequirements {
+            min_validator_stake: eth_to_fil_amount(
+                &contract.min_activation_collateral().call().await?,
+            )?,
+            min_validators: contract.min_validators().call().await?,
+            validators: contract.validator_count().call().await?.as_u64(),
+            max_validators: None,
+        })
+    }
+
+    async fn permission_mode(&self, subnet: &SubnetID) -> Result<PermissionMode> {
+        let address = contract_address_from_subnet(subnet)?;
+        let contract = SubnetActorPermissionFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        match contract.permission_mode().call().await {
+            Ok(mode) => PermissionMode::try_from(mode),
+            // subnet actors deployed before permission modes were introduced do not implement
+            // the getter, and only support collateral based validator sets
+            Err(e) if e.is_revert() => Ok(PermissionMode::Collateral),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn set_federated_power(
+        &self,
+        subnet: &SubnetID,
+        from: Address,
+        validators: Vec<FederatedValidator>,
+    ) -> Result<()> {
+        if self.permission_mode(subnet).await? != PermissionMode::Federated {
+            return Err(anyhow!("subnet {subnet:} is not federated"));
+        }
+
+        let mut addresses = vec![];
+        let mut public_keys = vec![];
+        let mut powers = vec![];
+        for v in validators {
+            addresses.push(payload_to_evm_address(v.address.payload())?);
+            public_keys.push(ethers::types::Bytes::from(v.public_key));
+            powers.push(U256::from(v.power));
+        }
+
+        let address = contract_address_from_subnet(subnet)?;
+        tracing::info!("setting federated power of {} validators in evm subnet: {subnet:} at contract: {address:}", addresses.len());
+
+        let signer = Arc::new(self.get_signer(&from)?);
+        let contract = SubnetActorPermissionFacet::new(address, signer.clone());
+
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            contract.set_federated_power(addresses, public_keys, powers),
+            self.gas_policy(KeyOperation::SetFederatedPower),
+        )
+        .await?;
+        let pending_tx = self
+            .send_transaction(&from, KeyOperation::SetFederatedPower, &signer, txn.tx)
+            .await?;
+        pending_tx.await?;
+
+        Ok(())
+    }
+
+    async fn circulating_supply(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        epoch: Option<ChainEpoch>,
+    ) -> Result<TokenAmount> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let mut call = gateway_contract.list_subnets();
+        if let Some(epoch) = epoch {
+            call = call.block(epoch as u64);
+        }
+
+        for s in call.call().await? {
+            let info = SubnetInfo::try_from(s)?;
+            if info.id == *subnet {
+                return Ok(info.circ_supply);
+            }
+        }
+        Err(anyhow!(
+            "subnet {subnet:} not registered in gateway {gateway_addr:}"
+        ))
+    }
+
+    async fn balance_at(
+        &self,
+        address: &Address,
+        epoch: Option<ChainEpoch>,
+    ) -> Result<TokenAmount> {
+        let balance = self
+            .ipc_contract_info
+            .provider
+            .get_balance(
+                payload_to_evm_address(address.payload())?,
+                epoch.map(|e| BlockId::from(e as u64)),
+            )
+            .await?;
+        Ok(TokenAmount::from_atto(balance.as_u128()))
+    }
+
+    async fn applied_top_down_nonce(&self, gateway_addr: &Address) -> Result<u64> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        Ok(gateway_contract.applied_top_down_nonce().call().await?)
+    }
+
+    async fn applied_bottom_up_nonce(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+    ) -> Result<u64> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let evm_subnet_id = gateway_getter_facet::SubnetID::try_from(subnet)?;
+        let gateway_contract = GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let (exists, evm_subnet) = gateway_contract.get_subnet(evm_subnet_id).call().await?;
+        if !exists {
+            return Err(anyhow!("subnet: {subnet:?} does not exists"));
+        }
+        Ok(evm_subnet.applied_bottom_up_nonce)
+    }
+
+    async fn top_down_msgs_from(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        nonce: u64,
+    ) -> Result<Vec<ipc_sdk::cross::CrossMsg>> {
+        self.ensure_same_gateway(gateway_addr)?;
+
+        let epoch = self.current_epoch().await?;
+        self.top_down_msgs(subnet, epoch, nonce).await
+    }
+
+    async fn top_down_msgs_at(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        nonce: u64,
+        epoch: ChainEpoch,
+    ) -> Result<Vec<ipc_sdk::cross::CrossMsg>> {
+        self.ensure_same_gateway(gateway_addr)?;
+        self.top_down_msgs(subnet, epoch, nonce).await
+    }
+
+    async fn subnets_created_by(&self, owner: &Address) -> Result<Vec<Address>> {
+        let owner = payload_to_evm_address(owner.payload())?;
+        let registry_contract = SubnetRegistry::new(
+            self.ipc_contract_info.registry_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        // the subnets deployed by an account are indexed by its nonce in the registry
+        let nonces = registry_contract.user_nonces(owner).call().await?;
+        let mut subnets = vec![];
+        for nonce in 0..nonces {
+            let address = registry_contract
+                .get_subnet_deployed_by_nonce(owner, nonce)
+                .call()
+                .await?;
+            subnets.push(ethers_address_to_fil_address(&address)?);
+        }
+        Ok(subnets)
+    }
+
+    async fn list_account_messages(
+        &self,
+        _account: &Address,
+        _from_epoch: ChainEpoch,
+    ) -> Result<Vec<String>> {
+        Err(anyhow!(
+            "evm nodes do not index the transactions of an account, only the ones signed by the agent are listed"
+        ))
+    }
+
+    async fn get_executed_message(&self, id: &str) -> Result<ExecutedMessage> {
+        let hash = TxHash::from_str(id)?;
+        let provider = &self.ipc_contract_info.provider;
+        let tx = provider
+            .get_transaction(hash)
+            .await?
+            .ok_or_else(|| anyhow!("transaction {id:} not found"))?;
+        let receipt = provider
+            .get_transaction_receipt(hash)
+            .await?
+            .ok_or_else(|| anyhow!("transaction {id:} not executed yet"))?;
+        let epoch = receipt
+            .block_number
+            .ok_or_else(|| anyhow!("transaction {id:} not executed yet"))?
+            .as_u64() as ChainEpoch;
+        let gas_fee =
+            receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+        let to = tx
+            .to
+            .ok_or_else(|| anyhow!("transaction {id:} deploys a contract"))?;
+        Ok(ExecutedMessage {
+            id: id.to_string(),
+            epoch,
+            from: ethers_address_to_fil_address(&tx.from)?,
+            to: ethers_address_to_fil_address(&to)?,
+            value: eth_to_fil_amount(&tx.value)?,
+            method: 0,
+            gas_fee: eth_to_fil_amount(&gas_fee)?,
+            success: receipt.status.map_or(false, |s| s.as_u64() == 1),
+        })
+    }
+
+    async fn lookup_message(&self, id: &str) -> Result<MessageLookup> {
+        let hash = TxHash::from_str(id)?;
+        let provider = &self.ipc_contract_info.provider;
+        let tx = provider
+            .get_transaction(hash)
+            .await?
+            .ok_or_else(|| anyhow!("transaction {id:} not found"))?;
+        let to = tx
+            .to
+            .ok_or_else(|| anyhow!("transaction {id:} deploys a contract"))?;
+        let receipt = match provider.get_transaction_receipt(hash).await? {
+            Some(r) => {
+                let gas_used = r.gas_used.unwrap_or_default();
+                let gas_fee = gas_used * r.effective_gas_price.unwrap_or_default();
+                Some(MessageReceipt {
+                    epoch: r
+                        .block_number
+                        .ok_or_else(|| anyhow!("transaction {id:} not executed yet"))?
+                        .as_u64() as ChainEpoch,
+                    success: r.status.map_or(false, |s| s.as_u64() == 1),
+                    exit_code: None,
+                    return_data: None,
+                    gas_used: gas_used.as_u64(),
+                    gas_fee: eth_to_fil_amount(&gas_fee)?,
+                })
+            }
+            None => None,
+        };
+        Ok(MessageLookup {
+            id: id.to_string(),
+            from: ethers_address_to_fil_address(&tx.from)?,
+            to: ethers_address_to_fil_address(&to)?,
+            value: eth_to_fil_amount(&tx.value)?,
+            method: 0,
+            nonce: tx.nonce.as_u64(),
+            ipc_method: ipc_function_name(&to, &self.ipc_contract_info.gateway_addr, &tx.input),
+            params: tx.input.to_vec(),
+            receipt,
+        })
     }
 
-    async fn get_validator_set(
+    async fn pending_messages(&self, _from: Option<Address>) -> Result<Vec<PendingMessage>> {
+        Err(anyhow!(
+            "evm nodes do not expose the transactions pending in their mempool"
+        ))
+    }
+
+    async fn replace_message(
         &self,
-        subnet_id: &SubnetID,
-        gateway: Option<Address>,
-    ) -> Result<QueryValidatorSetResponse> {
-        // we do optionally check as gateway addr is already part of the struct
-        if let Some(addr) = gateway {
-            self.ensure_same_gateway(&addr)?;
+        from: Address,
+        nonce: u64,
+        gas: ReplacementGas,
+        cancel: bool,
+    ) -> Result<String> {
+        // the pending transaction cannot be read from the mempool to be sent again
+        if !cancel {
+            return Err(anyhow!(
+                "evm nodes do not expose the transactions pending in their mempool, only cancelling them is supported"
+            ));
+        }
+        if gas.gas_fee_cap < gas.gas_premium {
+            return Err(anyhow!("gas fee cap is below the gas premium"));
         }
 
-        // get genesis epoch from gateway
-        let evm_subnet_id = gateway_getter_facet::SubnetID::try_from(subnet_id)?;
-        log::debug!("evm subnet id: {evm_subnet_id:?}");
+        let signer = Arc::new(self.get_signer(&from)?);
+        let mut tx = Eip1559TransactionRequest::new()
+            .from(signer.address())
+            .to(signer.address())
+            .value(U256::zero())
+            .nonce(nonce)
+            .max_priority_fee_per_gas(fil_to_eth_amount(&gas.gas_premium)?)
+            .max_fee_per_gas(fil_to_eth_amount(&gas.gas_fee_cap)?);
+        let limit = match gas.gas_limit {
+            Some(limit) => U256::from(limit),
+            None => {
+                signer
+                    .estimate_gas(&TypedTransaction::Eip1559(tx.clone()), None)
+                    .await?
+            }
+        };
+        tx = tx.gas(limit);
 
-        let gateway_contract = GatewayGetterFacet::new(
+        let tx_pending = self
+            .send_transaction(&from, KeyOperation::ReplaceMessage, &signer, tx.into())
+            .await?;
+        tracing::info!(
+            "transaction of {from:} with nonce {nonce:} cancelled by {:?}",
+            tx_pending.tx_hash()
+        );
+        Ok(format!("{:?}", tx_pending.tx_hash()))
+    }
+
+    async fn consensus_membership(&self, gateway_addr: &Address) -> Result<ValidatorSet> {
+        self.ensure_same_gateway(gateway_addr)?;
+        let contract = GatewayMembershipFacet::new(
             self.ipc_contract_info.gateway_addr,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        let (exists, evm_subnet) = gateway_contract.get_subnet(evm_subnet_id).call().await?;
-        if !exists {
-            return Err(anyhow!("subnet: {subnet_id:?} does not exists"));
-        }
-        let genesis_epoch = evm_subnet.genesis_epoch.as_u64() as i64;
-
-        let min_validators = self.min_validators(subnet_id).await?;
-
-        // get validator set
-        let address = contract_address_from_subnet(subnet_id)?;
-        log::debug!("get validator info for subnet: {subnet_id:} at contract: {address:}");
-
-        let contract =
-            SubnetActorGetterFacet::new(address, Arc::new(self.ipc_contract_info.provider.clone()));
-        let evm_validator_set = contract.get_validator_set().call().await?;
+        let membership = contract.get_current_membership().call().await?;
 
         let mut validators = vec![];
-        for v in evm_validator_set.validators.into_iter() {
+        for v in membership.validators {
+            let addr = ethers_address_to_fil_address(&v.addr)?.to_string();
             validators.push(Validator {
-                // we are using worker address here so that the fvm validator node can pick up
-                // the correct
-                addr: Address::try_from(v.worker_addr.clone())?.to_string(),
-                net_addr: v.net_addresses,
-                worker_addr: Some(Address::try_from(v.worker_addr)?.to_string()),
+                addr: addr.clone(),
+                net_addr: String::new(),
+                worker_addr: Some(addr),
                 weight: v.weight.to_string(),
             });
         }
-        let mut validator_set = ValidatorSet {
-            validators: None,
-            configuration_number: evm_validator_set.configuration_number,
-        };
-        if !validators.is_empty() {
-            validator_set.validators = Some(validators);
-        }
-
-        Ok(QueryValidatorSetResponse {
-            validator_set,
-            min_validators,
-            genesis_epoch,
+        Ok(ValidatorSet {
+            validators: (!validators.is_empty()).then_some(validators),
+            configuration_number: membership.configuration_number,
         })
     }
+
+    async fn ensure_network(&self) -> Result<()> {
+        self.ensure_chain_id().await
+    }
+
+    async fn vote_top_down_checkpoint(
+        &self,
+        gateway_addr: &Address,
+        from: Address,
+        checkpoint: TopDownCheckpoint,
+    ) -> Result<ChainEpoch> {
+        self.ensure_same_gateway(gateway_addr)?;
+        Ok(self
+            .submit_top_down_checkpoint(&from, checkpoint)
+            .await?
+            .epoch)
+    }
+
+    async fn bottom_up_vote(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<(ChainEpoch, bool)> {
+        let epoch = self.subnet_last_voting_executed_epoch(subnet).await?
+            + self.subnet_bottom_up_checkpoint_period(subnet).await?;
+        let voted = self.has_voted_in_subnet(subnet, epoch, validator).await?;
+        Ok((epoch, voted))
+    }
+
+    async fn top_down_vote(
+        &self,
+        gateway_addr: &Address,
+        validator: &Address,
+    ) -> Result<(ChainEpoch, bool)> {
+        let epoch = self.last_topdown_executed(gateway_addr).await?
+            + self.gateway_top_down_check_period().await?;
+        let voted = self.has_voted_in_gateway(epoch, validator).await?;
+        Ok((epoch, voted))
+    }
+
+    async fn pending_rewards(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<Option<TokenAmount>> {
+        let contract = SubnetActorGetterFacet::new(
+            contract_address_from_subnet(subnet)?,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let rewards = contract
+            .accumulated_rewards(payload_to_evm_address(validator.payload())?)
+            .call()
+            .await?;
+        Ok(Some(eth_to_fil_amount(&rewards)?))
+    }
 }
 
 #[async_trait]
@@ -574,31 +1302,39 @@ impl EthManager for EthSubnetManager {
         &self,
         from: &Address,
         checkpoint: TopDownCheckpoint,
-    ) -> Result<ChainEpoch> {
+    ) -> Result<SubmittedCheckpoint> {
         let checkpoint = gateway_router_facet::TopDownCheckpoint::try_from(checkpoint)?;
-        log::debug!("submit top down checkpoint: {:?}", checkpoint);
+        tracing::debug!("submit top down checkpoint: {:?}", checkpoint);
 
         let signer = Arc::new(self.get_signer(from)?);
         let gateway_contract =
             GatewayRouterFacet::new(self.ipc_contract_info.gateway_addr, signer.clone());
 
         let txn = gateway_contract.submit_top_down_checkpoint(checkpoint);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::Checkpoint),
+        )
+        .await?;
+        let receipt = self
+            .send_transaction(from, KeyOperation::Checkpoint, &signer, txn.tx)
+            .await?
+            .retries(TRANSACTION_RECEIPT_RETRIES)
+            .await?;
+        submitted_from_receipt(receipt)
     }
 
     async fn submit_bottom_up_checkpoint(
         &self,
         from: &Address,
         checkpoint: NativeBottomUpCheckpoint,
-    ) -> Result<ChainEpoch> {
+    ) -> Result<SubmittedCheckpoint> {
         let checkpoint = subnet_actor_manager_facet::BottomUpCheckpoint::try_from(checkpoint)?;
 
         let route = &checkpoint.source.route;
 
-        log::debug!(
+        tracing::debug!(
             "submit bottom up checkpoint: {:?} to address: {:?}",
             checkpoint,
             route[route.len() - 1]
@@ -608,10 +1344,18 @@ impl EthManager for EthSubnetManager {
         let contract = SubnetActorManagerFacet::new(route[route.len() - 1], signer.clone());
 
         let txn = contract.submit_checkpoint(checkpoint);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        let txn = call_with_premium_estimation(
+            signer.clone(),
+            txn,
+            self.gas_policy(KeyOperation::Checkpoint),
+        )
+        .await?;
+        let receipt = self
+            .send_transaction(from, KeyOperation::Checkpoint, &signer, txn.tx)
+            .await?
+            .retries(TRANSACTION_RECEIPT_RETRIES)
+            .await?;
+        submitted_from_receipt(receipt)
     }
 
     async fn has_voted_in_subnet(
@@ -666,7 +1410,7 @@ impl EthManager for EthSubnetManager {
                 "bottom up checkpoint not exists at epoch: {epoch:}"
             ))
         } else {
-            log::debug!("raw bottom up checkpoint from gateway: {checkpoint:?}");
+            tracing::debug!("raw bottom up checkpoint from gateway: {checkpoint:?}");
             let token = checkpoint.into_token();
             let c = subnet_actor_manager_facet::BottomUpCheckpoint::from_token(token)?;
             Ok(c)
@@ -675,7 +1419,7 @@ impl EthManager for EthSubnetManager {
 
     async fn get_applied_top_down_nonce(&self, subnet_id: &SubnetID) -> Result<u64> {
         let route = agent_subnet_to_evm_addresses(subnet_id)?;
-        log::debug!("getting applied top down nonce for route: {route:?}");
+        tracing::debug!("getting applied top down nonce for route: {route:?}");
 
         let evm_subnet_id = gateway_getter_facet::SubnetID {
             root: subnet_id.root_id(),
@@ -707,7 +1451,7 @@ impl EthManager for EthSubnetManager {
         nonce: u64,
     ) -> Result<Vec<ipc_sdk::cross::CrossMsg>> {
         let route = agent_subnet_to_evm_addresses(subnet_id)?;
-        log::debug!("getting top down messages for route: {route:?}");
+        tracing::debug!("getting top down messages for route: {route:?}");
 
         let subnet_id = gateway_getter_facet::SubnetID {
             root: subnet_id.root_id(),
@@ -808,7 +1552,7 @@ impl EthSubnetManager {
     pub fn new(
         gateway_addr: ethers::types::Address,
         registry_addr: ethers::types::Address,
-        chain_id: u64,
+        subnet: SubnetID,
         provider: Provider<Http>,
         keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
     ) -> Self {
@@ -817,10 +1561,41 @@ impl EthSubnetManager {
             ipc_contract_info: IPCContractInfo {
                 gateway_addr,
                 registry_addr,
-                chain_id,
+                chain_id: subnet.chain_id(),
+                subnet,
                 provider,
             },
+            gas: GasConfig::default(),
+            mode: SubnetMode::default(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Shares the state of the agent, e.g. the nonces reserved by its other managers.
+    pub fn with_state(mut self, state: Arc<AgentState>) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Sets the gas limit policies applied to the transactions sent by the manager.
+    pub fn with_gas_config(mut self, gas: GasConfig) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    /// Sets the mode of the subnet, no transactions are signed for observed subnets.
+    pub fn with_mode(mut self, mode: SubnetMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Overrides the chain id derived from the id of the subnet, e.g. for roots whose chain id
+    /// is not their id.
+    pub fn with_chain_id(mut self, chain_id: Option<u64>) -> Self {
+        if let Some(chain_id) = chain_id {
+            self.ipc_contract_info.chain_id = chain_id;
         }
+        self
     }
 
     pub fn ensure_same_gateway(&self, gateway: &Address) -> Result<()> {
@@ -832,10 +1607,269 @@ impl EthSubnetManager {
         }
     }
 
+    /// Builds the transaction of `call` with its nonce and gas filled in, ready to be signed.
+    async fn prepare_call(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<TypedTransaction> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let provider = Arc::new(self.ipc_contract_info.provider.clone());
+        let gateway_contract =
+            GatewayManagerFacet::new(self.ipc_contract_info.gateway_addr, provider.clone());
+
+        let (to, value, data) = match call {
+            OfflineCall::Fund { subnet, to, amount } => {
+                let call = gateway_contract.fund(
+                    gateway_manager_facet::SubnetID::try_from(&subnet)?,
+                    gateway_manager_facet::FvmAddress::try_from(to)?,
+                );
+                (
+                    self.ipc_contract_info.gateway_addr,
+                    fil_to_eth_amount(&amount)?,
+                    call.calldata(),
+                )
+            }
+            OfflineCall::Release { to, amount, .. } => {
+                let call =
+                    gateway_contract.release(gateway_manager_facet::FvmAddress::try_from(to)?);
+                (
+                    self.ipc_contract_info.gateway_addr,
+                    fil_to_eth_amount(&amount)?,
+                    call.calldata(),
+                )
+            }
+            OfflineCall::Join {
+                subnet,
+                collateral,
+                validator_net_addr,
+                worker_addr,
+            } => {
+                let address = contract_address_from_subnet(&subnet)?;
+                let contract = SubnetActorManagerFacet::new(address, provider.clone());
+                let call = contract.join(
+                    validator_net_addr,
+                    subnet_actor_manager_facet::FvmAddress::from(worker_addr),
+                );
+                (address, fil_to_eth_amount(&collateral)?, call.calldata())
+            }
+            OfflineCall::Leave { subnet } => {
+                let address = contract_address_from_subnet(&subnet)?;
+                let contract = SubnetActorManagerFacet::new(address, provider.clone());
+                (address, U256::zero(), contract.leave().calldata())
+            }
+            OfflineCall::SendValue { to, amount, .. } => (
+                payload_to_evm_address(to.payload())?,
+                fil_to_eth_amount(&amount)?,
+                None,
+            ),
+            OfflineCall::CheckpointVote { checkpoint } => {
+                let address = contract_address_from_subnet(&checkpoint.source)?;
+                let contract = SubnetActorManagerFacet::new(address, provider.clone());
+                let call = contract.submit_checkpoint(
+                    subnet_actor_manager_facet::BottomUpCheckpoint::try_from(checkpoint)?,
+                );
+                (address, U256::zero(), call.calldata())
+            }
+        };
+
+        let sender = payload_to_evm_address(from.payload())?;
+        let nonce = provider
+            .get_transaction_count(sender, Some(BlockNumber::Pending.into()))
+            .await?;
+        let (fee, fee_cap) = premium_estimation(provider.clone()).await?;
+
+        let mut tx = Eip1559TransactionRequest::new()
+            .from(sender)
+            .to(to)
+            .value(value)
+            .nonce(nonce)
+            .chain_id(self.ipc_contract_info.chain_id)
+            .max_priority_fee_per_gas(fee)
+            .max_fee_per_gas(fee_cap);
+        if let Some(data) = data {
+            tx = tx.data(data);
+        }
+        let mut tx = TypedTransaction::Eip1559(tx);
+        let gas = provider.estimate_gas(&tx, None).await?;
+        tx.set_gas(gas);
+
+        Ok(tx)
+    }
+
+    /// The params of the registry deploying a subnet actor with `params`.
+    fn registry_construct_params(
+        &self,
+        params: ConstructParams,
+    ) -> Result<subnet_registry::ConstructParams> {
+        self.ensure_same_gateway(&params.ipc_gateway_addr)?;
+
+        let name_len = params.name.as_bytes().len();
+        if name_len > SUBNET_NAME_MAX_LEN {
+            return Err(anyhow!("subnet name too long"));
+        }
+        let mut name = [0u8; SUBNET_NAME_MAX_LEN];
+        name[0..name_len].copy_from_slice(params.name.as_bytes());
+
+        let min_validator_stake = params
+            .min_validator_stake
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid min validator stake"))?;
+
+        let route = agent_subnet_to_evm_addresses(&params.parent)?;
+        tracing::debug!("root SubnetID as Ethereum type: {route:?}");
+
+        Ok(subnet_registry::ConstructParams {
+            parent_id: subnet_registry::SubnetID {
+                root: params.parent.root_id(),
+                route,
+            },
+            name,
+            ipc_gateway_addr: self.ipc_contract_info.gateway_addr,
+            consensus: params.consensus as u64 as u8,
+            min_activation_collateral: ethers::types::U256::from(min_validator_stake),
+            min_validators: params.min_validators,
+            bottom_up_check_period: params.bottomup_check_period as u64,
+            top_down_check_period: params.topdown_check_period as u64,
+            majority_percentage: SUBNET_MAJORITY_PERCENTAGE,
+            genesis: ethers::types::Bytes::default(),
+        })
+    }
+
+    /// The fee the registry charges to deploy a subnet actor, zero for the registries that do
+    /// not charge one.
+    async fn registry_creation_fee(&self) -> Result<U256> {
+        let registry = SubnetRegistryFeeFacet::new(
+            self.ipc_contract_info.registry_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        match registry.creation_fee().call().await {
+            Ok(fee) => Ok(fee),
+            // the registry bundled with the agent does not implement the getter
+            Err(e) if e.is_revert() => Ok(U256::zero()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Checks that the node serves the chain the transactions are signed for, so that a
+    /// misconfigured endpoint cannot get them replayed in another chain.
+    async fn ensure_chain_id(&self) -> Result<()> {
+        let chain_id = self.ipc_contract_info.provider.get_chainid().await?;
+        if chain_id != U256::from(self.ipc_contract_info.chain_id) {
+            return Err(anyhow!(
+                "endpoint serves chain id {chain_id:}, expected {}",
+                self.ipc_contract_info.chain_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// Signs the transaction locally and broadcasts it with `eth_sendRawTransaction`, so that it
+    /// works with nodes that do not manage any account. Every transaction of the manager is sent
+    /// this way: its nonce is reserved in the nonces of the agent instead of relying on the node,
+    /// and released if the transaction is not sent, so that the transactions sent at the same
+    /// time from the same account do not collide. A nonce already set, e.g. to replace a pending
+    /// transaction, is kept.
+    async fn send_transaction(
+        &self,
+        key: &Address,
+        operation: KeyOperation,
+        signer: &DefaultSignerMiddleware,
+        mut tx: TypedTransaction,
+    ) -> Result<PendingTransaction<'_, Http>> {
+        self.ensure_chain_id().await?;
+
+        let from = signer.address();
+        tx.set_from(from);
+        tx.set_chain_id(self.ipc_contract_info.chain_id);
+        let subnet = &self.ipc_contract_info.subnet;
+        let reserved = match tx.nonce() {
+            Some(_) => None,
+            None => {
+                let pending = self
+                    .ipc_contract_info
+                    .provider
+                    .get_transaction_count(from, Some(BlockNumber::Pending.into()))
+                    .await?;
+                let nonce = self.state.nonces.reserve(subnet, key, pending.as_u64());
+                tx.set_nonce(nonce);
+                Some(nonce)
+            }
+        };
+
+        let pending_tx = match self.sign_and_send(key, signer, tx).await {
+            Ok(p) => p,
+            Err(e) => {
+                if let Some(nonce) = reserved {
+                    self.state.nonces.release(subnet, key, nonce);
+                }
+                return Err(e);
+            }
+        };
+        self.state.counters.messages_sent.inc();
+        tracing::debug!("transaction sent with hash: {:?}", pending_tx.tx_hash());
+        self.record_key_usage(key, operation, pending_tx.tx_hash());
+
+        Ok(pending_tx)
+    }
+
+    /// Fills in the gas of the transaction, checks that `key` can pay for it and broadcasts it
+    /// signed by `signer`.
+    async fn sign_and_send<'a>(
+        &'a self,
+        key: &Address,
+        signer: &DefaultSignerMiddleware,
+        mut tx: TypedTransaction,
+    ) -> Result<PendingTransaction<'a, Http>> {
+        let from = signer.address();
+        signer.fill_transaction(&mut tx, None).await?;
+        if let (Some(gas), Some(price)) = (tx.gas(), tx.gas_price()) {
+            let required = gas * price + tx.value().cloned().unwrap_or_default();
+            let balance = self
+                .ipc_contract_info
+                .provider
+                .get_balance(from, None)
+                .await?;
+            ensure_funds(
+                *key,
+                eth_to_fil_amount(&balance)?,
+                eth_to_fil_amount(&required)?,
+            )?;
+        }
+
+        let signature = signer.signer().sign_transaction(&tx).await?;
+        Ok(self
+            .ipc_contract_info
+            .provider
+            .send_raw_transaction(tx.rlp_signed(&signature))
+            .await?)
+    }
+
+    /// The policy applied to the estimated gas limit of the transactions performing `operation`.
+    fn gas_policy(&self, operation: KeyOperation) -> &GasLimitPolicy {
+        operation.gas_policy(&self.gas)
+    }
+
+    /// Records the transaction signed with the key of `from` in the key usage log.
+    fn record_key_usage(&self, from: &Address, operation: KeyOperation, tx_hash: TxHash) {
+        self.state.key_usage.record(
+            from,
+            operation,
+            &self.ipc_contract_info.subnet,
+            format!("{tx_hash:?}"),
+        );
+    }
+
     /// Get the ethers singer instance.
     /// We use filecoin addresses throughout our whole code-base
     /// and translate them to evm addresses when relevant.
     fn get_signer(&self, addr: &Address) -> Result<DefaultSignerMiddleware> {
+        self.mode
+            .ensure_can_submit(&self.ipc_contract_info.subnet)?;
+
         // convert to its underlying eth address
         let addr = payload_to_evm_address(addr.payload())?;
         let keystore = self.keystore.read().unwrap();
@@ -856,6 +1890,7 @@ impl EthSubnetManager {
     pub fn from_subnet_with_wallet_store(
         subnet: &Subnet,
         keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
     ) -> Result<Self> {
         let url = subnet.rpc_http().clone();
         let auth_token = subnet.auth_token();
@@ -866,7 +1901,13 @@ impl EthSubnetManager {
             return Err(anyhow!("not evm config"));
         };
 
-        let provider = if auth_token.is_some() {
+        let headers = subnet.http_headers();
+        let provider = if !headers.is_empty() {
+            let client = reqwest::Client::builder()
+                .default_headers(bearer_headers(headers, auth_token)?)
+                .build()?;
+            Http::new_with_client(url, client)
+        } else if auth_token.is_some() {
             Http::new_with_auth(url, Authorization::Bearer(auth_token.unwrap()))?
         } else {
             Http::new(url)
@@ -883,25 +1924,49 @@ impl EthSubnetManager {
         Ok(Self::new(
             gateway_address,
             registry_address,
-            subnet.id.chain_id(),
+            subnet.id.clone(),
             provider,
             keystore,
-        ))
+        )
+        .with_gas_config(subnet.gas.clone())
+        .with_mode(subnet.mode)
+        .with_chain_id(config.chain_id)
+        .with_state(state))
+    }
+}
+
+/// Adds the authorization of `auth_token`, if any, to the custom `headers` of a subnet, as the
+/// provider does not authenticate the requests of a client it is given.
+fn bearer_headers(mut headers: HeaderMap, auth_token: Option<String>) -> Result<HeaderMap> {
+    if let Some(token) = auth_token {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token:}"))?,
+        );
     }
+    Ok(headers)
 }
 
 /// Receives an input `FunctionCall` and returns a new instance
-/// after estimating an optimal `gas_premium` for the transaction
+/// after estimating an optimal `gas_premium` for the transaction, with its estimated gas limit
+/// adjusted with `policy`
 async fn call_with_premium_estimation<B, D, M>(
     signer: Arc<DefaultSignerMiddleware>,
     call: ethers_contract::FunctionCall<B, D, M>,
+    policy: &GasLimitPolicy,
 ) -> Result<ethers_contract::FunctionCall<B, D, M>>
 where
     B: std::borrow::Borrow<D>,
+    D: Middleware + 'static,
     M: ethers::abi::Detokenize,
 {
     let (max_priority_fee_per_gas, _) = premium_estimation(signer).await?;
-    Ok(call.gas_price(max_priority_fee_per_gas))
+    let call = call.gas_price(max_priority_fee_per_gas);
+
+    let estimated = call.estimate_gas().await?;
+    let limit = policy.apply(estimated.as_u64());
+    tracing::debug!("gas limit of call adjusted from {estimated:} to {limit:}");
+    Ok(call.gas(limit))
 }
 
 /// Returns an estimation of an optimal `gas_premium` and `gas_fee_cap`
@@ -909,8 +1974,8 @@ where
 /// past blocks
 /// This is adaptation of ethers' `eip1559_default_estimator`:
 /// https://github.com/gakonst/ethers-rs/blob/5dcd3b7e754174448f9a8cbfc0523896609629f9/ethers-core/src/utils/mod.rs#L476
-async fn premium_estimation(
-    signer: Arc<DefaultSignerMiddleware>,
+async fn premium_estimation<M: Middleware + 'static>(
+    signer: Arc<M>,
 ) -> Result<(ethers::types::U256, ethers::types::U256)> {
     let base_fee_per_gas = signer
         .get_block(ethers::types::BlockNumber::Latest)
@@ -1009,6 +2074,43 @@ fn estimate_priority_fee(rewards: Vec<Vec<U256>>) -> U256 {
 }
 
 /// Get the block number from the transaction receipt
+/// The name of the function of the gateway at `gateway`, or of a subnet actor, called with
+/// `calldata` by a transaction to `to`.
+fn ipc_function_name(
+    to: &ethers::types::Address,
+    gateway: &ethers::types::Address,
+    calldata: &[u8],
+) -> Option<String> {
+    let selector: [u8; 4] = calldata.get(..4)?.try_into().ok()?;
+    let abis = if to == gateway {
+        vec![
+            &*gateway_manager_facet::GATEWAYMANAGERFACET_ABI,
+            &*gateway_router_facet::GATEWAYROUTERFACET_ABI,
+            &*gateway_messenger_facet::GATEWAYMESSENGERFACET_ABI,
+        ]
+    } else {
+        vec![&*subnet_actor_manager_facet::SUBNETACTORMANAGERFACET_ABI]
+    };
+    abis.into_iter()
+        .flat_map(|abi| abi.functions())
+        .find(|f| f.short_signature() == selector)
+        .map(|f| f.name.clone())
+}
+
+/// The checkpoint vote executed with the transaction of `receipt`.
+fn submitted_from_receipt(
+    receipt: Option<ethers::types::TransactionReceipt>,
+) -> Result<SubmittedCheckpoint> {
+    let message = receipt
+        .as_ref()
+        .map(|r| format!("{:?}", r.transaction_hash))
+        .unwrap_or_default();
+    Ok(SubmittedCheckpoint {
+        epoch: block_number_from_receipt(receipt)?,
+        message,
+    })
+}
+
 fn block_number_from_receipt(
     receipt: Option<ethers::types::TransactionReceipt>,
 ) -> Result<ChainEpoch> {
@@ -1049,6 +2151,29 @@ pub(crate) fn agent_subnet_to_evm_addresses(
 }
 
 /// Util function to convert Fil address payload to evm address. Only delegated address is supported.
+/// The calldata of `drip(address)` on a faucet contract, sending test funds to `to`.
+fn drip_calldata(to: ethers::types::Address) -> ethers::types::Bytes {
+    let mut data = ethers::utils::id("drip(address)").to_vec();
+    data.extend(ethers::abi::encode(&[ethers::abi::Token::Address(to)]));
+    data.into()
+}
+
+/// The epoch of the bottom-up checkpoint submitted by a transaction with `calldata`, `None` if the
+/// transaction does not submit one.
+pub(crate) fn bottom_up_checkpoint_epoch(calldata: &[u8]) -> Option<ChainEpoch> {
+    subnet_actor_manager_facet::SubmitCheckpointCall::decode(calldata)
+        .ok()
+        .map(|call| call.checkpoint.epoch as ChainEpoch)
+}
+
+/// The epoch of the top-down checkpoint submitted by a transaction with `calldata`, `None` if the
+/// transaction does not submit one.
+pub(crate) fn top_down_checkpoint_epoch(calldata: &[u8]) -> Option<ChainEpoch> {
+    gateway_router_facet::SubmitTopDownCheckpointCall::decode(calldata)
+        .ok()
+        .map(|call| call.checkpoint.epoch as ChainEpoch)
+}
+
 pub(crate) fn payload_to_evm_address(payload: &Payload) -> Result<ethers::types::Address> {
     match payload {
         Payload::Delegated(delegated) => {
@@ -1062,12 +2187,28 @@ pub(crate) fn payload_to_evm_address(payload: &Payload) -> Result<ethers::types:
 #[cfg(test)]
 mod tests {
     use crate::manager::evm::manager::{
-        agent_subnet_to_evm_addresses, contract_address_from_subnet,
+        agent_subnet_to_evm_addresses, bottom_up_checkpoint_epoch, contract_address_from_subnet,
+        drip_calldata, gateway_manager_facet, gateway_router_facet, ipc_function_name,
+        subnet_actor_manager_facet, top_down_checkpoint_epoch, EthSubnetManager,
     };
+    use crate::manager::key_usage::KeyOperation;
+    use ethers::abi::AbiEncode;
+    use ethers::core::rand::thread_rng;
+    use ethers::providers::{Http, Provider};
+    use ethers::signers::LocalWallet;
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Eip1559TransactionRequest, U256};
+    use ethers::utils::keccak256;
+    use ethers::utils::rlp::Rlp;
     use fvm_shared::address::Address;
+    use ipc_identity::{EvmKeyInfo, EvmKeyStore, PersistentKeyStore};
     use ipc_sdk::subnet_id::SubnetID;
     use primitives::EthAddress;
+    use serde_json::{json, Value};
+    use std::net::SocketAddr;
     use std::str::FromStr;
+    use std::sync::{Arc, Mutex, RwLock};
+    use warp::Filter;
 
     #[test]
     fn test_agent_subnet_to_evm_address() {
@@ -1098,4 +2239,153 @@ mod tests {
 
         assert_eq!(addrs, vec![a, b]);
     }
+
+    #[test]
+    fn test_ipc_function_name() {
+        let gateway = ethers::types::Address::from_low_u64_be(1);
+        let subnet_actor = ethers::types::Address::from_low_u64_be(2);
+        let release = gateway_manager_facet::GATEWAYMANAGERFACET_ABI
+            .function("release")
+            .unwrap()
+            .short_signature();
+
+        assert_eq!(
+            ipc_function_name(&gateway, &gateway, &release),
+            Some(String::from("release"))
+        );
+        // the functions of the gateway are not looked up in the subnet actors
+        assert_eq!(ipc_function_name(&subnet_actor, &gateway, &release), None);
+        assert_eq!(ipc_function_name(&gateway, &gateway, &release[..2]), None);
+    }
+
+    #[test]
+    fn test_drip_calldata() {
+        let to = ethers::types::Address::from_low_u64_be(0xabcd);
+        let data = drip_calldata(to);
+
+        assert_eq!(data.len(), 4 + 32);
+        assert_eq!(hex::encode(&data[..4]), "67a5cd06");
+        assert_eq!(&data[16..], to.as_bytes());
+    }
+
+    #[test]
+    fn test_checkpoint_epoch_from_calldata() {
+        let bottom_up = subnet_actor_manager_facet::SubmitCheckpointCall {
+            checkpoint: subnet_actor_manager_facet::BottomUpCheckpoint {
+                epoch: 20,
+                ..Default::default()
+            },
+        }
+        .encode();
+        let top_down = gateway_router_facet::SubmitTopDownCheckpointCall {
+            checkpoint: gateway_router_facet::TopDownCheckpoint {
+                epoch: 30,
+                ..Default::default()
+            },
+        }
+        .encode();
+        let other = drip_calldata(ethers::types::Address::from_low_u64_be(0xabcd));
+
+        assert_eq!(bottom_up_checkpoint_epoch(&bottom_up), Some(20));
+        assert_eq!(bottom_up_checkpoint_epoch(&top_down), None);
+        assert_eq!(bottom_up_checkpoint_epoch(&other), None);
+        assert_eq!(top_down_checkpoint_epoch(&top_down), Some(30));
+        assert_eq!(top_down_checkpoint_epoch(&bottom_up), None);
+        assert_eq!(top_down_checkpoint_epoch(&[]), None);
+    }
+
+    /// Serves the requests of a node needed to send transactions, recording the raw transactions
+    /// sent. The node reports the same pending nonce for every account, as if the transactions
+    /// just sent had not reached its mempool yet.
+    fn mock_node(chain_id: u64, sent: Arc<Mutex<Vec<Vec<u8>>>>) -> SocketAddr {
+        let api = warp::post()
+            .and(warp::body::json::<Value>())
+            .map(move |body: Value| {
+                let result = match body["method"].as_str().unwrap_or_default() {
+                    "eth_chainId" => json!(format!("{chain_id:#x}")),
+                    "eth_getTransactionCount" => json!("0x5"),
+                    "eth_getBalance" => json!("0xffffffffffffffffffff"),
+                    "eth_sendRawTransaction" => {
+                        let raw = body["params"][0].as_str().unwrap_or_default();
+                        let raw = hex::decode(raw.trim_start_matches("0x")).unwrap();
+                        let hash = keccak256(&raw);
+                        sent.lock().unwrap().push(raw);
+                        json!(format!("0x{}", hex::encode(hash)))
+                    }
+                    method => {
+                        return warp::reply::json(&json!({
+                            "jsonrpc": "2.0",
+                            "id": body["id"],
+                            "error": {"code": -32601, "message": format!("{method:} not mocked")},
+                        }))
+                    }
+                };
+                warp::reply::json(&json!({"jsonrpc": "2.0", "id": body["id"], "result": result}))
+            });
+        let (addr, server) = warp::serve(api).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_transactions_from_one_account() {
+        let subnet = SubnetID::from_str("/r31415926").unwrap();
+        let sent = Arc::new(Mutex::new(vec![]));
+        let node = mock_node(subnet.chain_id(), sent.clone());
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut keystore = PersistentKeyStore::new(dir.path().join("keystore")).unwrap();
+        let wallet = LocalWallet::new(&mut thread_rng());
+        let eth = keystore
+            .put(EvmKeyInfo::new(wallet.signer().to_bytes().to_vec()))
+            .unwrap();
+        let key = Address::new_delegated(10, eth.as_bytes()).unwrap();
+        let manager = EthSubnetManager::new(
+            ethers::types::Address::from_low_u64_be(1),
+            ethers::types::Address::from_low_u64_be(2),
+            subnet,
+            Provider::<Http>::try_from(format!("http://{node:}").as_str()).unwrap(),
+            Arc::new(RwLock::new(keystore)),
+        );
+        let signer = manager.get_signer(&key).unwrap();
+        let tx = |value: U256| -> TypedTransaction {
+            Eip1559TransactionRequest::new()
+                .to(ethers::types::Address::from_low_u64_be(3))
+                .value(value)
+                .gas(21000)
+                .max_fee_per_gas(100)
+                .max_priority_fee_per_gas(1)
+                .into()
+        };
+
+        // both transactions are sent before the node sees any of them
+        let (first, second) = tokio::join!(
+            manager.send_transaction(&key, KeyOperation::SendValue, &signer, tx(U256::one())),
+            manager.send_transaction(&key, KeyOperation::SendValue, &signer, tx(U256::one())),
+        );
+        first.unwrap();
+        second.unwrap();
+
+        // a transaction that is not sent gives its nonce back
+        assert!(manager
+            .send_transaction(&key, KeyOperation::SendValue, &signer, tx(U256::exp10(30)))
+            .await
+            .is_err());
+        manager
+            .send_transaction(&key, KeyOperation::SendValue, &signer, tx(U256::one()))
+            .await
+            .unwrap();
+
+        let mut nonces = sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|raw| {
+                let (tx, _) = TypedTransaction::decode_signed(&Rlp::new(raw)).unwrap();
+                tx.nonce().unwrap().as_u64()
+            })
+            .collect::<Vec<_>>();
+        nonces.sort();
+        assert_eq!(nonces, vec![5, 6, 7]);
+    }
 }
@@ -15,8 +15,9 @@ use ipc_sdk::subnet_id::SubnetID;
 
 use super::subnet::SubnetManager;
 pub use manager::EthSubnetManager;
+pub(crate) use manager::{bottom_up_checkpoint_epoch, top_down_checkpoint_epoch};
 
-use crate::checkpoint::NativeBottomUpCheckpoint;
+use crate::checkpoint::{NativeBottomUpCheckpoint, SubmittedCheckpoint};
 use crate::manager::evm::manager::subnet_actor_manager_facet;
 pub use convert::{eth_to_fil_amount, ethers_address_to_fil_address, fil_to_eth_amount};
 
@@ -39,14 +40,14 @@ pub trait EthManager: SubnetManager {
         &self,
         from: &Address,
         checkpoint: TopDownCheckpoint,
-    ) -> anyhow::Result<ChainEpoch>;
+    ) -> anyhow::Result<SubmittedCheckpoint>;
 
     /// Submit bottom up checkpoint to the subnet actor.
     async fn submit_bottom_up_checkpoint(
         &self,
         from: &Address,
         checkpoint: NativeBottomUpCheckpoint,
-    ) -> anyhow::Result<ChainEpoch>;
+    ) -> anyhow::Result<SubmittedCheckpoint>;
 
     /// Has the validator voted in subnet contract at epoch
     async fn has_voted_in_subnet(
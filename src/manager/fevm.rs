@@ -1,22 +1,31 @@
+use std::collections::HashSet;
 use std::str::FromStr;
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 use crate::checkpoint::{
-    create_proof, BottomUpHandler, CheckpointQuery, NativeBottomUpCheckpoint, TopDownHandler,
-    VoteQuery,
+    create_proof, BottomUpHandler, CheckpointQuery, NativeBottomUpCheckpoint, PendingVotes,
+    SubmittedCheckpoint, TopDownHandler, VoteQuery,
 };
 use crate::jsonrpc::JsonRpcClientImpl;
 use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::LotusClient;
+use crate::manager::evm::{bottom_up_checkpoint_epoch, top_down_checkpoint_epoch};
 use crate::manager::{EthManager, EthSubnetManager, SubnetManager};
 use anyhow::anyhow;
 use async_trait::async_trait;
 use fil_actors_runtime::cbor;
+use fvm_ipld_encoding::BytesDe;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::MethodNum;
 use ipc_gateway::TopDownCheckpoint;
 use ipc_sdk::cross::CrossMsg;
 use ipc_sdk::subnet_id::SubnetID;
 
+/// The method of the evm actor invoking a contract, the method of the messages of eth
+/// transactions.
+const INVOKE_CONTRACT: MethodNum = 3844450837;
+
 pub struct FevmSubnetManager {
     evm_subnet_manager: EthSubnetManager,
     lotus_client: LotusJsonRPCClient<JsonRpcClientImpl>,
@@ -32,6 +41,45 @@ impl FevmSubnetManager {
             lotus_client,
         }
     }
+
+    /// Returns the epochs of the checkpoints submitted by `validator` that are still pending in
+    /// the mempool. The eth api does not expose the content of the mempool, so the transactions
+    /// are read from the messages pending in the mempool of the Lotus node, the checkpoints being
+    /// those sent to `contract`, if set, whose calldata `epoch` decodes an epoch from.
+    async fn pending_transactions(
+        &self,
+        validator: &Address,
+        contract: Option<&Address>,
+        epoch: fn(&[u8]) -> Option<ChainEpoch>,
+    ) -> anyhow::Result<PendingVotes> {
+        let mut epochs = HashSet::new();
+        for signed in self.lotus_client.mpool_pending(Some(*validator)).await? {
+            let message = signed.message;
+            if message.method != INVOKE_CONTRACT {
+                continue;
+            }
+            if let Some(contract) = contract && message.to().ok().as_ref() != Some(contract) {
+                continue;
+            }
+            // the calldata of eth transactions is cbor encoded in the params of their message
+            let calldata = match message
+                .params()
+                .and_then(|p| Ok(cbor::deserialize::<BytesDe>(&p, "calldata")?))
+            {
+                Ok(calldata) => calldata,
+                Err(e) => {
+                    tracing::warn!(
+                        "skipping pending message of {validator:} with undecodable calldata: {e:}"
+                    );
+                    continue;
+                }
+            };
+            if let Some(epoch) = epoch(&calldata.0) {
+                epochs.insert(epoch);
+            }
+        }
+        Ok(PendingVotes::Epochs(epochs))
+    }
 }
 
 #[async_trait]
@@ -56,6 +104,19 @@ impl VoteQuery<NativeBottomUpCheckpoint> for FevmSubnetManager {
             .has_voted_in_subnet(subnet_id, epoch, validator)
             .await
     }
+
+    async fn pending_votes(
+        &self,
+        subnet_id: &SubnetID,
+        validator: &Address,
+    ) -> anyhow::Result<PendingVotes> {
+        self.pending_transactions(
+            validator,
+            Some(&subnet_id.subnet_actor()),
+            bottom_up_checkpoint_epoch,
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -78,7 +139,7 @@ impl BottomUpHandler for FevmSubnetManager {
         epoch: ChainEpoch,
     ) -> anyhow::Result<NativeBottomUpCheckpoint> {
         let checkpoint = self.evm_subnet_manager.bottom_up_checkpoint(epoch).await?;
-        log::debug!("raw bottom up templated: {checkpoint:?}");
+        tracing::debug!("raw bottom up templated: {checkpoint:?}");
 
         NativeBottomUpCheckpoint::try_from(checkpoint)
     }
@@ -110,7 +171,7 @@ impl BottomUpHandler for FevmSubnetManager {
         &self,
         validator: &Address,
         checkpoint: NativeBottomUpCheckpoint,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<SubmittedCheckpoint> {
         self.evm_subnet_manager
             .submit_bottom_up_checkpoint(validator, checkpoint)
             .await
@@ -143,6 +204,16 @@ impl VoteQuery<TopDownCheckpoint> for FevmSubnetManager {
 
         Ok(has_voted)
     }
+
+    async fn pending_votes(
+        &self,
+        _subnet_id: &SubnetID,
+        validator: &Address,
+    ) -> anyhow::Result<PendingVotes> {
+        // the gateway is the only contract top-down checkpoints are submitted to
+        self.pending_transactions(validator, None, top_down_checkpoint_epoch)
+            .await
+    }
 }
 
 #[async_trait]
@@ -163,7 +234,7 @@ impl CheckpointQuery<TopDownCheckpoint> for FevmSubnetManager {
                 .into_iter()
                 .map(|v| Address::from_str(&v.worker_addr.unwrap()))
                 .collect::<Result<Vec<_>, _>>()?;
-            log::debug!("top down validators: {v:?}");
+            tracing::debug!("top down validators: {v:?}");
             Ok(v)
         } else {
             Ok(vec![])
@@ -198,7 +269,7 @@ impl TopDownHandler for FevmSubnetManager {
         &self,
         _validator: &Address,
         _checkpoint: TopDownCheckpoint,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<SubmittedCheckpoint> {
         todo!()
     }
 }
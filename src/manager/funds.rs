@@ -0,0 +1,166 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Checks of the balance of the signers before their messages are pushed.
+//!
+//! The fee of a message is estimated before it is sent, and the message is not sent if the
+//! balance of its signer does not cover it, with an [`InsufficientFunds`] error instead of the
+//! opaque rejection of the mpool. The checkpoint managers track the validators that could not vote
+//! for this reason until their balance is topped up.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+
+/// The balance of the signer of a message does not cover its maximum fee and value.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "insufficient funds: {account:} has {balance:} FIL, the message needs up to {required:} FIL"
+)]
+pub struct InsufficientFunds {
+    pub account: Address,
+    pub balance: TokenAmount,
+    pub required: TokenAmount,
+}
+
+/// Fails with [`InsufficientFunds`] if `balance` does not cover the `required` amount.
+pub fn ensure_funds(
+    account: Address,
+    balance: TokenAmount,
+    required: TokenAmount,
+) -> anyhow::Result<()> {
+    if balance < required {
+        return Err(InsufficientFunds {
+            account,
+            balance,
+            required,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// A validator whose votes are skipped in a subnet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Underfunded {
+    pub subnet: String,
+    pub account: String,
+    /// The balance of the validator at the last attempt.
+    pub balance: Amount,
+    /// The amount the last vote needed.
+    pub required: Amount,
+    /// The unix timestamp, in seconds, since which the votes are skipped.
+    pub since: u64,
+}
+
+pub struct UnderfundedSigners {
+    signers: Mutex<Option<HashMap<(SubnetID, Address), Underfunded>>>,
+}
+
+impl UnderfundedSigners {
+    pub const fn new() -> Self {
+        Self {
+            signers: Mutex::new(None),
+        }
+    }
+
+    /// Records that a vote in `subnet` was skipped. Returns `true` if the signer was funded until
+    /// now.
+    pub fn mark_underfunded(&self, subnet: &SubnetID, e: &InsufficientFunds) -> bool {
+        let mut signers = self.signers.lock().unwrap();
+        let signers = signers.get_or_insert_with(HashMap::new);
+        let since = signers.get(&(subnet.clone(), e.account)).map(|u| u.since);
+        signers.insert(
+            (subnet.clone(), e.account),
+            Underfunded {
+                subnet: subnet.to_string(),
+                account: e.account.to_string(),
+                balance: Amount::from(&e.balance),
+                required: Amount::from(&e.required),
+                since: since.unwrap_or_else(now),
+            },
+        );
+        since.is_none()
+    }
+
+    /// Records that a vote of `account` in `subnet` went through. Returns `true` if the signer was
+    /// underfunded until now.
+    pub fn mark_funded(&self, subnet: &SubnetID, account: &Address) -> bool {
+        let mut signers = self.signers.lock().unwrap();
+        signers
+            .as_mut()
+            .and_then(|s| s.remove(&(subnet.clone(), *account)))
+            .is_some()
+    }
+
+    /// Returns the validators whose votes are currently skipped.
+    pub fn all(&self) -> Vec<Underfunded> {
+        let signers = self.signers.lock().unwrap();
+        let mut all = signers
+            .as_ref()
+            .map(|s| s.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        all.sort_by(|a, b| (&a.subnet, &a.account).cmp(&(&b.subnet, &b.account)));
+        all
+    }
+}
+
+impl Default for UnderfundedSigners {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::manager::funds::{ensure_funds, InsufficientFunds, UnderfundedSigners};
+
+    #[test]
+    fn test_underfunded_signers() {
+        let subnet = SubnetID::new_root(123);
+        let account = Address::new_id(100);
+
+        assert!(ensure_funds(
+            account,
+            TokenAmount::from_whole(1),
+            TokenAmount::from_whole(1)
+        )
+        .is_ok());
+        let e = ensure_funds(
+            account,
+            TokenAmount::from_nano(1),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap_err();
+        let e = e.downcast_ref::<InsufficientFunds>().unwrap();
+        assert!(e.to_string().starts_with("insufficient funds"));
+
+        let signers = UnderfundedSigners::new();
+        assert!(!signers.mark_funded(&subnet, &account));
+        assert!(signers.mark_underfunded(&subnet, e));
+        // only reported once
+        assert!(!signers.mark_underfunded(&subnet, e));
+        assert_eq!(signers.all().len(), 1);
+        assert_eq!(signers.all()[0].required.fil, "1");
+
+        assert!(signers.mark_funded(&subnet, &account));
+        assert!(signers.all().is_empty());
+    }
+}
@@ -3,22 +3,27 @@
 
 mod conversion;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use crate::checkpoint::{
-    create_proof, BottomUpHandler, CheckpointQuery, NativeBottomUpCheckpoint, TopDownHandler,
-    VoteQuery,
+    create_proof, next_checkpoint_epoch, BottomUpHandler, CheckpointQuery,
+    NativeBottomUpCheckpoint, PendingVotes, SubmittedCheckpoint, TopDownHandler, VoteQuery,
 };
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use cid::Cid;
 use fil_actors_runtime::types::{InitExecParams, InitExecReturn, INIT_EXEC_METHOD_NUM};
 use fil_actors_runtime::{builtin::singletons::INIT_ACTOR_ADDR, cbor};
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::bigint::BigInt;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::message::Message;
 use fvm_shared::METHOD_SEND;
 use fvm_shared::{address::Address, econ::TokenAmount, MethodNum};
+use ipc_gateway::checkpoint::BatchCrossMsgs;
 use ipc_gateway::{
     BottomUpCheckpoint, FundParams, PropagateParams, ReleaseParams, TopDownCheckpoint,
 };
@@ -31,12 +36,19 @@ use crate::config::Subnet;
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
 use crate::lotus::client::LotusJsonRPCClient;
 use crate::lotus::message::ipc::{
-    IPCReadGatewayStateResponse, IPCReadSubnetActorStateResponse, QueryValidatorSetResponse,
-    SubnetInfo,
+    FederatedValidator, IPCReadGatewayStateResponse, IPCReadSubnetActorStateResponse,
+    JoinRequirements, PermissionMode, QueryValidatorSetResponse, SubnetCreationCost, SubnetInfo,
+    ValidatorSet,
 };
 use crate::lotus::message::mpool::MpoolPushMessage;
 use crate::lotus::message::state::StateWaitMsgResponse;
 use crate::lotus::LotusClient;
+use crate::manager::activity::{
+    ExecutedMessage, MessageLookup, MessageReceipt, PendingMessage, ReplacementGas,
+};
+use crate::manager::key_usage::KeyOperation;
+use crate::manager::offline::{self, OfflineCall, OfflineTx, TxNetwork};
+use crate::state::AgentState;
 
 use super::subnet::SubnetManager;
 
@@ -48,34 +60,36 @@ pub struct LotusSubnetManager<T: JsonRpcClient> {
 #[async_trait]
 impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
     async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address> {
-        if !self.is_network_match(&params.parent).await? {
-            return Err(anyhow!("subnet actor being deployed in the wrong parent network, parent network names do not match"));
-        }
-
-        let exec_params = InitExecParams {
-            code_cid: self.get_subnet_actor_code_cid().await?,
-            constructor_params: cbor::serialize(&params, "create subnet actor")?,
-        };
-        log::debug!("create subnet for init actor with params: {exec_params:?}");
-        let init_params = cbor::serialize(&exec_params, "init subnet actor params")?;
-        let message = MpoolPushMessage::new(
-            INIT_ACTOR_ADDR,
-            from,
-            INIT_EXEC_METHOD_NUM,
-            init_params.to_vec(),
-        );
+        let message = self.create_subnet_message(from, &params).await?;
 
-        let state_wait_response = self.mpool_push_and_wait(message).await?;
+        let state_wait_response = self
+            .mpool_push_and_wait(KeyOperation::CreateSubnet, message)
+            .await?;
         let result = state_wait_response
             .receipt
             .parse_result_into::<InitExecReturn>()?;
         let addr = result.robust_address;
         let id = result.id_address;
-        log::info!("created subnet result - robust address: {addr:}, robust address: {id:}");
+        tracing::info!("created subnet result - robust address: {addr:}, robust address: {id:}");
 
         Ok(addr)
     }
 
+    async fn estimate_create_subnet(
+        &self,
+        from: Address,
+        params: ConstructParams,
+    ) -> Result<SubnetCreationCost> {
+        let message = self.create_subnet_message(from, &params).await?;
+        let message = self.lotus_client.mpool_prepare(message).await?;
+        let message = Message::try_from(&message)?;
+        Ok(SubnetCreationCost {
+            // subnet actors are deployed by the init actor, which charges no fee
+            registry_fee: TokenAmount::from_atto(0),
+            gas_fee: TokenAmount::from_atto(message.gas_fee_cap.atto() * message.gas_limit),
+        })
+    }
+
     async fn join_subnet(
         &self,
         subnet: SubnetID,
@@ -101,8 +115,9 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
         );
         message.value = collateral;
 
-        self.mpool_push_and_wait(message).await?;
-        log::info!("joined subnet: {subnet:}");
+        self.mpool_push_and_wait(KeyOperation::JoinSubnet, message)
+            .await?;
+        tracing::info!("joined subnet: {subnet:}");
 
         Ok(())
     }
@@ -115,14 +130,17 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
             return Err(anyhow!("subnet actor being deployed in the wrong parent network, parent network names do not match"));
         }
 
-        self.mpool_push_and_wait(MpoolPushMessage::new(
-            subnet.subnet_actor(),
-            from,
-            ipc_subnet_actor::Method::Leave as MethodNum,
-            vec![],
-        ))
+        self.mpool_push_and_wait(
+            KeyOperation::LeaveSubnet,
+            MpoolPushMessage::new(
+                subnet.subnet_actor(),
+                from,
+                ipc_subnet_actor::Method::Leave as MethodNum,
+                vec![],
+            ),
+        )
         .await?;
-        log::info!("left subnet: {subnet:}");
+        tracing::info!("left subnet: {subnet:}");
 
         Ok(())
     }
@@ -133,14 +151,17 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
             return Err(anyhow!("subnet actor being deployed in the wrong parent network, parent network names do not match"));
         }
 
-        self.mpool_push_and_wait(MpoolPushMessage::new(
-            subnet.subnet_actor(),
-            from,
-            ipc_subnet_actor::Method::Kill as MethodNum,
-            vec![],
-        ))
+        self.mpool_push_and_wait(
+            KeyOperation::KillSubnet,
+            MpoolPushMessage::new(
+                subnet.subnet_actor(),
+                from,
+                ipc_subnet_actor::Method::Kill as MethodNum,
+                vec![],
+            ),
+        )
         .await?;
-        log::info!("left subnet: {subnet:}");
+        tracing::info!("left subnet: {subnet:}");
 
         Ok(())
     }
@@ -154,14 +175,14 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
             .ipc_list_child_subnets(gateway_addr)
             .await?;
 
-        log::debug!("received subnets: {subnets:?}");
+        tracing::debug!("received subnets: {subnets:?}");
 
         let mut map = HashMap::new();
         for s in subnets {
             map.insert(s.id.clone(), s);
         }
 
-        log::debug!("converted to subnets: {map:?}");
+        tracing::debug!("converted to subnets: {map:?}");
 
         Ok(map)
     }
@@ -190,7 +211,9 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
             fund_params.to_vec(),
         );
         message.value = amount;
-        let r = self.mpool_push_and_wait(message).await?;
+        let r = self
+            .mpool_push_and_wait(KeyOperation::Fund, message)
+            .await?;
         Ok(r.height as ChainEpoch)
     }
 
@@ -218,7 +241,9 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
         );
         message.value = amount;
 
-        let r = self.mpool_push_and_wait(message).await?;
+        let r = self
+            .mpool_push_and_wait(KeyOperation::Release, message)
+            .await?;
         Ok(r.height as ChainEpoch)
     }
 
@@ -229,6 +254,7 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
     ) -> Result<()> {
         let postbox_cid = Cid::try_from(postbox_msg_key.as_slice())?;
 
@@ -238,14 +264,16 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
 
         let params = cbor::serialize(&PropagateParams { postbox_cid }, "propagate params")?;
 
-        let message = MpoolPushMessage::new(
+        let mut message = MpoolPushMessage::new(
             gateway_addr,
             from,
             ipc_gateway::Method::Propagate as MethodNum,
             params.to_vec(),
         );
+        message.value = fee;
 
-        self.mpool_push_and_wait(message).await?;
+        self.mpool_push_and_wait(KeyOperation::Propagate, message)
+            .await?;
         Ok(())
     }
 
@@ -284,7 +312,8 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
             params.to_vec(),
         );
 
-        self.mpool_push_and_wait(message).await?;
+        self.mpool_push_and_wait(KeyOperation::SetValidatorNetAddr, message)
+            .await?;
         Ok(())
     }
 
@@ -301,14 +330,26 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
     async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()> {
         let mut message = MpoolPushMessage::new(to, from, METHOD_SEND, Vec::new());
         message.value = amount;
-        self.mpool_push_and_wait(message).await?;
-        log::info!("sending FIL from {from:} to {to:}");
+        self.mpool_push_and_wait(KeyOperation::SendValue, message)
+            .await?;
+        tracing::info!("sending FIL from {from:} to {to:}");
 
         Ok(())
     }
 
+    async fn faucet_drip(
+        &self,
+        _faucet: ethers::types::Address,
+        _from: Address,
+        _to: Address,
+    ) -> Result<()> {
+        Err(anyhow!(
+            "faucet contracts are only supported in fevm subnets"
+        ))
+    }
+
     async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
-        log::info!("get the balance of an address");
+        tracing::info!("get the balance of an address");
         self.lotus_client.wallet_balance(address).await
     }
 
@@ -373,6 +414,503 @@ impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
             genesis_epoch,
         })
     }
+
+    async fn bottom_up_check_period(&self, gateway_addr: &Address) -> Result<ChainEpoch> {
+        let gw_state = gateway_state(&self.lotus_client, gateway_addr).await?;
+        Ok(gw_state.bottom_up_check_period)
+    }
+
+    async fn top_down_check_period(&self, gateway_addr: &Address) -> Result<ChainEpoch> {
+        let gw_state = gateway_state(&self.lotus_client, gateway_addr).await?;
+        Ok(gw_state.top_down_check_period)
+    }
+
+    async fn cross_msg_fee(&self, gateway_addr: &Address) -> Result<TokenAmount> {
+        let gw_state = gateway_state(&self.lotus_client, gateway_addr).await?;
+        Ok(gw_state.cross_msg_fee)
+    }
+
+    async fn chain_head_epoch(&self) -> Result<ChainEpoch> {
+        self.lotus_client.current_epoch().await
+    }
+
+    async fn chain_head_timestamp(&self) -> Result<u64> {
+        let head = self.lotus_client.chain_head().await?;
+        head.timestamp()
+            .ok_or_else(|| anyhow!("chain head has no timestamp"))
+    }
+
+    async fn epoch_timestamp(&self, epoch: ChainEpoch) -> Result<u64> {
+        let head = self.lotus_client.chain_head().await?;
+        let cid_map = head.cids.first().unwrap().clone();
+        let tip_set = Cid::try_from(cid_map)?;
+
+        self.lotus_client
+            .get_tipset_by_height(epoch, tip_set)
+            .await?
+            .timestamp()
+            .ok_or_else(|| anyhow!("tipset at epoch {epoch:} has no timestamp"))
+    }
+
+    async fn block_time(&self, sample: ChainEpoch) -> Result<Duration> {
+        let head = self.lotus_client.chain_head().await?;
+        let cid_map = head
+            .cids
+            .first()
+            .ok_or_else(|| anyhow!("chain head has no blocks"))?
+            .clone();
+        let tip_set = Cid::try_from(cid_map)?;
+
+        let from_epoch = (head.height as ChainEpoch - sample).max(0);
+        let past = self
+            .lotus_client
+            .get_tipset_by_height(from_epoch, tip_set)
+            .await?;
+
+        let epochs = head.height.saturating_sub(past.height);
+        if epochs == 0 {
+            return Err(anyhow!("not enough epochs to measure the block time"));
+        }
+        let head_ts = head
+            .timestamp()
+            .ok_or_else(|| anyhow!("chain head has no timestamp"))?;
+        let past_ts = past
+            .timestamp()
+            .ok_or_else(|| anyhow!("tipset at epoch {from_epoch:} has no timestamp"))?;
+
+        Ok(Duration::from_secs_f64(
+            head_ts.saturating_sub(past_ts) as f64 / epochs as f64,
+        ))
+    }
+
+    async fn pending_bottom_up_msgs(
+        &self,
+        gateway_addr: &Address,
+    ) -> Result<(ChainEpoch, BatchCrossMsgs)> {
+        let period = self.bottom_up_check_period(gateway_addr).await?;
+        let current_epoch = self.lotus_client.current_epoch().await?;
+        let epoch = next_checkpoint_epoch(current_epoch, period);
+
+        let template = self
+            .lotus_client
+            .ipc_get_checkpoint_template(gateway_addr, epoch)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "error getting bottom-up checkpoint template for epoch:{epoch:} due to {e:}"
+                )
+            })?;
+        tracing::debug!("pending bottom up template: {template:?}");
+
+        Ok((epoch, template.data.cross_msgs))
+    }
+
+    async fn bottom_up_checkpoint_template(
+        &self,
+        gateway_addr: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<NativeBottomUpCheckpoint> {
+        let template = self
+            .lotus_client
+            .ipc_get_checkpoint_template(gateway_addr, epoch)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "error getting bottom-up checkpoint template for epoch:{epoch:} due to {e:}"
+                )
+            })?;
+
+        let mut checkpoint = BottomUpCheckpoint::new(template.source().clone(), epoch);
+        checkpoint.data.children = template.data.children;
+        checkpoint.data.cross_msgs = template.data.cross_msgs;
+        tracing::debug!("raw bottom up templated: {checkpoint:?}");
+
+        NativeBottomUpCheckpoint::try_from(checkpoint)
+    }
+
+    async fn build_unsigned_tx(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<Vec<u8>> {
+        let message = self.prepare_call(gateway_addr, from, call).await?;
+        let message = Message::try_from(&message)?;
+        Ok(fvm_ipld_encoding::to_vec(&message)?)
+    }
+
+    async fn estimate_fee(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<TokenAmount> {
+        let message = self.prepare_call(gateway_addr, from, call).await?;
+        let message = Message::try_from(&message)?;
+        Ok(TokenAmount::from_atto(
+            message.gas_fee_cap.atto() * message.gas_limit,
+        ))
+    }
+
+    async fn join_requirements(&self, subnet: &SubnetID) -> Result<JoinRequirements> {
+        let state = self.get_subnet_state(subnet).await?;
+        Ok(JoinRequirements {
+            min_validator_stake: state.min_validator_stake,
+            min_validators: state.min_validators,
+            validators: state
+                .validator_set
+                .validators
+                .map(|v| v.len() as u64)
+                .unwrap_or_default(),
+            max_validators: None,
+        })
+    }
+
+    async fn permission_mode(&self, _subnet: &SubnetID) -> Result<PermissionMode> {
+        // the fvm subnet actor only supports collateral based validator sets
+        Ok(PermissionMode::Collateral)
+    }
+
+    async fn set_federated_power(
+        &self,
+        subnet: &SubnetID,
+        _from: Address,
+        _validators: Vec<FederatedValidator>,
+    ) -> Result<()> {
+        Err(anyhow!(
+            "subnet {subnet:} is not federated, fvm subnets only support collateral"
+        ))
+    }
+
+    async fn send_signed_tx(&self, tx: &OfflineTx) -> Result<String> {
+        if tx.network != TxNetwork::Fvm {
+            return Err(anyhow!("not a fvm transaction"));
+        }
+        let (message, signature) = offline::fvm_signed_message(tx)?;
+        let cid = self
+            .lotus_client
+            .mpool_push_signed(MpoolPushMessage::from(message), signature)
+            .await?;
+        tracing::info!("signed message published with cid: {cid:}");
+        Ok(cid.to_string())
+    }
+
+    async fn circulating_supply(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        epoch: Option<ChainEpoch>,
+    ) -> Result<TokenAmount> {
+        // the lotus api only lists the child subnets at the chain head
+        if epoch.is_some() {
+            return Err(anyhow!(
+                "fvm gateways only report the circulating supply at the chain head"
+            ));
+        }
+
+        let subnets = self.list_child_subnets(*gateway_addr).await?;
+        let info = subnets
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet {subnet:} not registered in gateway {gateway_addr:}"))?;
+        Ok(info.circ_supply.clone())
+    }
+
+    async fn balance_at(
+        &self,
+        address: &Address,
+        epoch: Option<ChainEpoch>,
+    ) -> Result<TokenAmount> {
+        let epoch = match epoch {
+            Some(epoch) => epoch,
+            None => return self.lotus_client.wallet_balance(address).await,
+        };
+
+        let head = chain_head_cid(&self.lotus_client).await?;
+        let tip_set = self
+            .lotus_client
+            .get_tipset_by_height(epoch, head)
+            .await?
+            .tip_set_cids()?;
+        let tip_set = tip_set
+            .first()
+            .ok_or_else(|| anyhow!("no tipset found at epoch {epoch:}"))?;
+        let state = self
+            .lotus_client
+            .read_state::<serde_json::Value>(*address, *tip_set)
+            .await?;
+        Ok(TokenAmount::from_atto(BigInt::from_str(&state.balance)?))
+    }
+
+    async fn applied_top_down_nonce(&self, gateway_addr: &Address) -> Result<u64> {
+        let state = gateway_state(&self.lotus_client, gateway_addr).await?;
+        Ok(state.applied_topdown_nonce)
+    }
+
+    async fn applied_bottom_up_nonce(
+        &self,
+        gateway_addr: &Address,
+        _subnet: &SubnetID,
+    ) -> Result<u64> {
+        // fvm gateways apply the bottom-up messages of all their children in a single sequence
+        let state = gateway_state(&self.lotus_client, gateway_addr).await?;
+        Ok(state.applied_bottomup_nonce)
+    }
+
+    async fn top_down_msgs_from(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        nonce: u64,
+    ) -> Result<Vec<CrossMsg>> {
+        let head = chain_head_cid(&self.lotus_client).await?;
+        self.lotus_client
+            .ipc_get_topdown_msgs(subnet, gateway_addr, head, nonce)
+            .await
+    }
+
+    async fn top_down_msgs_at(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        nonce: u64,
+        epoch: ChainEpoch,
+    ) -> Result<Vec<CrossMsg>> {
+        let head = chain_head_cid(&self.lotus_client).await?;
+        let tip_set = self.lotus_client.get_tipset_by_height(epoch, head).await?;
+        let tip_set = Cid::try_from(tip_set.cids.first().unwrap().clone())?;
+        self.lotus_client
+            .ipc_get_topdown_msgs(subnet, gateway_addr, tip_set, nonce)
+            .await
+    }
+
+    async fn subnets_created_by(&self, _owner: &Address) -> Result<Vec<Address>> {
+        Err(anyhow!("fvm subnet actors do not record who created them"))
+    }
+
+    async fn list_account_messages(
+        &self,
+        account: &Address,
+        from_epoch: ChainEpoch,
+    ) -> Result<Vec<String>> {
+        // the node only matches the sender or the recipient in a single call
+        let mut cids = self
+            .lotus_client
+            .state_list_messages(Some(*account), None, from_epoch)
+            .await?;
+        for cid in self
+            .lotus_client
+            .state_list_messages(None, Some(*account), from_epoch)
+            .await?
+        {
+            if !cids.contains(&cid) {
+                cids.push(cid);
+            }
+        }
+        Ok(cids.iter().map(Cid::to_string).collect())
+    }
+
+    async fn get_executed_message(&self, id: &str) -> Result<ExecutedMessage> {
+        let cid = Cid::from_str(id)?;
+        let state = self
+            .lotus_client
+            .state_search_msg(cid)
+            .await?
+            .ok_or_else(|| anyhow!("message {cid:} not executed"))?;
+        let msg = self.lotus_client.chain_get_message(cid).await?;
+        let gas_fee = self.lotus_client.state_replay_gas_cost(cid).await?;
+        Ok(ExecutedMessage {
+            id: id.to_string(),
+            epoch: state.height as ChainEpoch,
+            from: msg.from()?,
+            to: msg.to()?,
+            value: TokenAmount::from_atto(BigInt::from_str(&msg.value)?),
+            method: msg.method,
+            gas_fee,
+            success: state.receipt.exit_code() == 0,
+        })
+    }
+
+    async fn lookup_message(&self, id: &str) -> Result<MessageLookup> {
+        let cid = Cid::from_str(id)?;
+        let msg = self.lotus_client.chain_get_message(cid).await?;
+        let receipt = match self.lotus_client.state_search_msg(cid).await? {
+            Some(state) => Some(MessageReceipt {
+                epoch: state.height as ChainEpoch,
+                success: state.receipt.exit_code() == 0,
+                exit_code: Some(state.receipt.exit_code()),
+                return_data: Some(state.receipt.return_data()?),
+                gas_used: state.receipt.gas_used(),
+                gas_fee: self.lotus_client.state_replay_gas_cost(cid).await?,
+            }),
+            None => None,
+        };
+        let to = msg.to()?;
+        Ok(MessageLookup {
+            id: id.to_string(),
+            from: msg.from()?,
+            ipc_method: ipc_method_name(&to, &self.gateway_addr, msg.method).map(String::from),
+            to,
+            value: TokenAmount::from_atto(BigInt::from_str(&msg.value)?),
+            method: msg.method,
+            nonce: msg.nonce,
+            params: msg.params()?.to_vec(),
+            receipt,
+        })
+    }
+
+    async fn pending_messages(&self, from: Option<Address>) -> Result<Vec<PendingMessage>> {
+        let mut next_nonces = HashMap::new();
+        let mut pending = vec![];
+        for signed in self.lotus_client.mpool_pending(from).await? {
+            let msg = signed.message;
+            let sender = msg.from()?;
+            let next_nonce = match next_nonces.get(&sender) {
+                Some(n) => *n,
+                None => {
+                    let n = self.lotus_client.mpool_nonce(&sender).await?;
+                    next_nonces.insert(sender, n);
+                    n
+                }
+            };
+            pending.push(PendingMessage {
+                id: Cid::try_from(signed.cid)?.to_string(),
+                from: sender,
+                to: msg.to()?,
+                value: TokenAmount::from_atto(BigInt::from_str(&msg.value)?),
+                method: msg.method,
+                nonce: msg.nonce,
+                gas_fee_cap: TokenAmount::from_atto(BigInt::from_str(&msg.gas_fee_cap)?),
+                gas_premium: TokenAmount::from_atto(BigInt::from_str(&msg.gas_premium)?),
+                // the nonce of the node only follows the pending messages up to the first gap
+                stuck: msg.nonce >= next_nonce,
+            });
+        }
+        Ok(pending)
+    }
+
+    async fn replace_message(
+        &self,
+        from: Address,
+        nonce: u64,
+        gas: ReplacementGas,
+        cancel: bool,
+    ) -> Result<String> {
+        let pending = self
+            .lotus_client
+            .mpool_pending(Some(from))
+            .await?
+            .into_iter()
+            .map(|signed| signed.message)
+            .find(|m| m.nonce == nonce);
+
+        let mut message = match (pending, cancel) {
+            (Some(pending), _) => {
+                gas.ensure_replaces(&TokenAmount::from_atto(BigInt::from_str(
+                    &pending.gas_premium,
+                )?))?;
+                let mut message = MpoolPushMessage::new(
+                    pending.to()?,
+                    from,
+                    pending.method,
+                    pending.params()?.to_vec(),
+                );
+                message.value = TokenAmount::from_atto(BigInt::from_str(&pending.value)?);
+                message.gas_limit = Some(TokenAmount::from_atto(pending.gas_limit));
+                message
+            }
+            (None, true) => {
+                // the nonce is a gap in the nonces of the sender, filled with an empty send
+                tracing::info!(
+                    "no message of {from:} with nonce {nonce:} pending, filling the gap"
+                );
+                MpoolPushMessage::new(from, from, METHOD_SEND, Vec::new())
+            }
+            (None, false) => {
+                return Err(anyhow!(
+                    "no message of {from:} with nonce {nonce:} pending in the memory pool"
+                ))
+            }
+        };
+        if cancel {
+            message.to = from;
+            message.value = TokenAmount::from_atto(0);
+            message.method = METHOD_SEND;
+            message.params = Vec::new();
+        }
+        message.nonce = Some(nonce);
+        if let Some(limit) = gas.gas_limit {
+            message.gas_limit = Some(TokenAmount::from_atto(limit));
+        }
+        message.gas_fee_cap = Some(gas.gas_fee_cap);
+        message.gas_premium = Some(gas.gas_premium);
+
+        let cid = self
+            .lotus_client
+            .push_operation(KeyOperation::ReplaceMessage, message)
+            .await?;
+        tracing::info!("message of {from:} with nonce {nonce:} replaced by {cid:}");
+        Ok(cid.to_string())
+    }
+
+    async fn consensus_membership(&self, _gateway_addr: &Address) -> Result<ValidatorSet> {
+        Err(anyhow!(
+            "fvm gateways do not expose the membership of the subnet consensus"
+        ))
+    }
+
+    async fn ensure_network(&self) -> Result<()> {
+        self.lotus_client.ensure_network().await
+    }
+
+    async fn vote_top_down_checkpoint(
+        &self,
+        gateway_addr: &Address,
+        from: Address,
+        checkpoint: TopDownCheckpoint,
+    ) -> Result<ChainEpoch> {
+        self.lotus_client
+            .ipc_submit_top_down_checkpoint(*gateway_addr, &from, checkpoint)
+            .await
+    }
+
+    async fn bottom_up_vote(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<(ChainEpoch, bool)> {
+        let state = self.get_subnet_state(subnet).await?;
+        let epoch =
+            state.bottom_up_checkpoint_voting.last_voting_executed + state.bottom_up_check_period;
+        let voted = self
+            .lotus_client
+            .ipc_validator_has_voted_bottomup(subnet, epoch, validator)
+            .await?;
+        Ok((epoch, voted))
+    }
+
+    async fn top_down_vote(
+        &self,
+        gateway_addr: &Address,
+        validator: &Address,
+    ) -> Result<(ChainEpoch, bool)> {
+        let state = gateway_state(&self.lotus_client, gateway_addr).await?;
+        let epoch =
+            state.top_down_checkpoint_voting.last_voting_executed + state.top_down_check_period;
+        let voted = self
+            .lotus_client
+            .ipc_validator_has_voted_topdown(gateway_addr, epoch, validator)
+            .await?;
+        Ok((epoch, voted))
+    }
+
+    async fn pending_rewards(
+        &self,
+        _subnet: &SubnetID,
+        _validator: &Address,
+    ) -> Result<Option<TokenAmount>> {
+        // the fvm subnet actor sends the rewards to the validators as they are distributed
+        Ok(None)
+    }
 }
 
 impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
@@ -402,18 +940,99 @@ impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
             .await
     }
 
+    /// Builds the message of `call` with its nonce and gas filled in, ready to be signed.
+    async fn prepare_call(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<MpoolPushMessage> {
+        if !self.is_network_match(&call.execution_subnet()?).await? {
+            return Err(anyhow!("call not executed in the current network"));
+        }
+
+        let message = match call {
+            OfflineCall::Fund { subnet, to, amount } => {
+                let fund_params =
+                    cbor::serialize(&FundParams { subnet, to }, "fund subnet actor params")?;
+                let mut message = MpoolPushMessage::new(
+                    gateway_addr,
+                    from,
+                    ipc_gateway::Method::Fund as MethodNum,
+                    fund_params.to_vec(),
+                );
+                message.value = amount;
+                message
+            }
+            OfflineCall::Release { to, amount, .. } => {
+                let release_params = cbor::serialize(&ReleaseParams { to }, "release params")?;
+                let mut message = MpoolPushMessage::new(
+                    gateway_addr,
+                    from,
+                    ipc_gateway::Method::Release as MethodNum,
+                    release_params.to_vec(),
+                );
+                message.value = amount;
+                message
+            }
+            OfflineCall::Join {
+                subnet,
+                collateral,
+                validator_net_addr,
+                worker_addr,
+            } => {
+                if from != worker_addr {
+                    return Err(anyhow!("worker address should equal sender"));
+                }
+                let mut message = MpoolPushMessage::new(
+                    subnet.subnet_actor(),
+                    from,
+                    ipc_subnet_actor::Method::Join as MethodNum,
+                    cbor::serialize(&JoinParams { validator_net_addr }, "join subnet params")?
+                        .to_vec(),
+                );
+                message.value = collateral;
+                message
+            }
+            OfflineCall::Leave { subnet } => MpoolPushMessage::new(
+                subnet.subnet_actor(),
+                from,
+                ipc_subnet_actor::Method::Leave as MethodNum,
+                vec![],
+            ),
+            OfflineCall::SendValue { to, amount, .. } => {
+                let mut message = MpoolPushMessage::new(to, from, METHOD_SEND, Vec::new());
+                message.value = amount;
+                message
+            }
+            OfflineCall::CheckpointVote { checkpoint } => MpoolPushMessage::new(
+                checkpoint.source.subnet_actor(),
+                from,
+                ipc_subnet_actor::Method::SubmitCheckpoint as MethodNum,
+                cbor::serialize(&BottomUpCheckpoint::try_from(&checkpoint)?, "checkpoint")?
+                    .to_vec(),
+            ),
+        };
+
+        self.lotus_client.mpool_prepare(message).await
+    }
+
     /// Publish the message to memory pool and wait for the response
-    async fn mpool_push_and_wait(&self, message: MpoolPushMessage) -> Result<StateWaitMsgResponse> {
-        let message_cid = self.lotus_client.mpool_push(message).await?;
-        log::debug!("message published with cid: {message_cid:?}");
+    async fn mpool_push_and_wait(
+        &self,
+        operation: KeyOperation,
+        message: MpoolPushMessage,
+    ) -> Result<StateWaitMsgResponse> {
+        let message_cid = self.lotus_client.push_operation(operation, message).await?;
+        tracing::debug!("message published with cid: {message_cid:?}");
 
-        self.lotus_client.state_wait_msg(message_cid).await
+        self.lotus_client.wait_message(message_cid).await
     }
 
     /// Checks the `network` is the one we are currently talking to.
     async fn is_network_match(&self, network: &SubnetID) -> Result<bool> {
         let network_name = self.lotus_client.state_network_name().await?;
-        log::debug!(
+        tracing::debug!(
             "current network name: {network_name:?}, to check network: {:?}",
             network.to_string()
         );
@@ -421,11 +1040,35 @@ impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
         Ok(network.to_string() == network_name)
     }
 
+    /// The message deploying a subnet actor with `params` through the init actor.
+    async fn create_subnet_message(
+        &self,
+        from: Address,
+        params: &ConstructParams,
+    ) -> Result<MpoolPushMessage> {
+        if !self.is_network_match(&params.parent).await? {
+            return Err(anyhow!("subnet actor being deployed in the wrong parent network, parent network names do not match"));
+        }
+
+        let exec_params = InitExecParams {
+            code_cid: self.get_subnet_actor_code_cid().await?,
+            constructor_params: cbor::serialize(params, "create subnet actor")?,
+        };
+        tracing::debug!("create subnet for init actor with params: {exec_params:?}");
+        let init_params = cbor::serialize(&exec_params, "init subnet actor params")?;
+        Ok(MpoolPushMessage::new(
+            INIT_ACTOR_ADDR,
+            from,
+            INIT_EXEC_METHOD_NUM,
+            init_params.to_vec(),
+        ))
+    }
+
     /// Obtain the actor code cid of `ipc_subnet_actor` only, since this is the
     /// code cid we are interested in.
     async fn get_subnet_actor_code_cid(&self) -> Result<Cid> {
         let network_version = self.lotus_client.state_network_version(vec![]).await?;
-        log::debug!("received network version: {network_version:?}");
+        tracing::debug!("received network version: {network_version:?}");
 
         let mut cid_map = self
             .lotus_client
@@ -439,13 +1082,17 @@ impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
 }
 
 impl LotusSubnetManager<JsonRpcClientImpl> {
-    pub fn from_subnet(subnet: &Subnet) -> Self {
-        let client = LotusJsonRPCClient::from_subnet(subnet);
+    pub fn from_subnet(subnet: &Subnet, state: Arc<AgentState>) -> Self {
+        let client = LotusJsonRPCClient::from_subnet(subnet, state);
         LotusSubnetManager::new(client, subnet.gateway_addr())
     }
 
-    pub fn from_subnet_with_wallet_store(subnet: &Subnet, wallet: Arc<RwLock<Wallet>>) -> Self {
-        let client = LotusJsonRPCClient::from_subnet_with_wallet_store(subnet, wallet);
+    pub fn from_subnet_with_wallet_store(
+        subnet: &Subnet,
+        wallet: Arc<RwLock<Wallet>>,
+        state: Arc<AgentState>,
+    ) -> Self {
+        let client = LotusJsonRPCClient::from_subnet_with_wallet_store(subnet, wallet, state);
         LotusSubnetManager::new(client, subnet.gateway_addr())
     }
 }
@@ -482,6 +1129,35 @@ impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
             .map(|f| Address::from_str(&f.addr).map_err(|e| anyhow!("cannot create address: {e:}")))
             .collect::<Result<_>>()
     }
+
+    /// Returns the epochs of the checkpoints sent by `validator` to `to` with `method` that are
+    /// still pending in the mempool. `epoch` decodes the epoch from the params of a message.
+    async fn pending_checkpoint_epochs(
+        &self,
+        validator: &Address,
+        to: &Address,
+        method: MethodNum,
+        epoch: impl Fn(&RawBytes) -> Result<ChainEpoch> + Send,
+    ) -> Result<PendingVotes> {
+        let mut epochs = HashSet::new();
+        for signed in self.lotus_client.mpool_pending(Some(*validator)).await? {
+            let message = signed.message;
+            if message.method != method || message.to().ok().as_ref() != Some(to) {
+                continue;
+            }
+            // a message that cannot be decoded is not a vote of the agent, it does not prevent
+            // the others from being reconciled
+            match message.params().and_then(|p| epoch(&p)) {
+                Ok(e) => {
+                    epochs.insert(e);
+                }
+                Err(e) => tracing::warn!(
+                    "skipping pending message of {validator:} to {to:} with undecodable params: {e:}"
+                ),
+            }
+        }
+        Ok(PendingVotes::Epochs(epochs))
+    }
 }
 
 #[async_trait]
@@ -504,7 +1180,7 @@ impl<T: JsonRpcClient + Send + Sync> VoteQuery<NativeBottomUpCheckpoint> for Lot
         epoch: ChainEpoch,
         validator: &Address,
     ) -> Result<bool> {
-        log::debug!(
+        tracing::debug!(
             "attempt to obtain the next submission epoch in bottom up checkpoint for subnet: {:?}",
             subnet_id
         );
@@ -517,6 +1193,23 @@ impl<T: JsonRpcClient + Send + Sync> VoteQuery<NativeBottomUpCheckpoint> for Lot
         // we should vote only when the validator has not voted
         Ok(!has_voted)
     }
+
+    async fn pending_votes(
+        &self,
+        subnet_id: &SubnetID,
+        validator: &Address,
+    ) -> Result<PendingVotes> {
+        self.pending_checkpoint_epochs(
+            validator,
+            &subnet_id.subnet_actor(),
+            ipc_subnet_actor::Method::SubmitCheckpoint as MethodNum,
+            |params| {
+                let checkpoint = cbor::deserialize::<BottomUpCheckpoint>(params, "checkpoint")?;
+                Ok(checkpoint.epoch())
+            },
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -530,7 +1223,7 @@ impl<T: JsonRpcClient + Send + Sync> CheckpointQuery<NativeBottomUpCheckpoint>
             .ipc_read_subnet_actor_state(subnet_id, tip_set)
             .await
             .map_err(|e| {
-                log::error!("error getting subnet actor state for {:?}", subnet_id);
+                tracing::error!("error getting subnet actor state for {:?}", subnet_id);
                 e
             })?;
 
@@ -545,22 +1238,8 @@ impl<T: JsonRpcClient + Send + Sync> CheckpointQuery<NativeBottomUpCheckpoint>
 #[async_trait]
 impl<T: JsonRpcClient + Send + Sync> BottomUpHandler for LotusSubnetManager<T> {
     async fn checkpoint_template(&self, epoch: ChainEpoch) -> Result<NativeBottomUpCheckpoint> {
-        let template = self
-            .lotus_client
-            .ipc_get_checkpoint_template(&self.gateway_addr, epoch)
+        self.bottom_up_checkpoint_template(&self.gateway_addr, epoch)
             .await
-            .map_err(|e| {
-                anyhow!(
-                    "error getting bottom-up checkpoint template for epoch:{epoch:} due to {e:}"
-                )
-            })?;
-
-        let mut checkpoint = BottomUpCheckpoint::new(template.source().clone(), epoch);
-        checkpoint.data.children = template.data.children;
-        checkpoint.data.cross_msgs = template.data.cross_msgs;
-        log::debug!("raw bottom up templated: {checkpoint:?}");
-
-        NativeBottomUpCheckpoint::try_from(checkpoint)
     }
 
     async fn populate_prev_hash(
@@ -603,7 +1282,7 @@ impl<T: JsonRpcClient + Send + Sync> BottomUpHandler for LotusSubnetManager<T> {
         &self,
         validator: &Address,
         checkpoint: NativeBottomUpCheckpoint,
-    ) -> Result<ChainEpoch> {
+    ) -> Result<SubmittedCheckpoint> {
         let to = checkpoint.source.subnet_actor();
         let message = MpoolPushMessage::new(
             to,
@@ -611,16 +1290,23 @@ impl<T: JsonRpcClient + Send + Sync> BottomUpHandler for LotusSubnetManager<T> {
             ipc_subnet_actor::Method::SubmitCheckpoint as MethodNum,
             cbor::serialize(&BottomUpCheckpoint::try_from(&checkpoint)?, "checkpoint")?.to_vec(),
         );
-        let message_cid = self.lotus_client.mpool_push(message).await.map_err(|e| {
-            anyhow!(
-                "error submitting checkpoint for epoch {:} in subnet: {:?} with reason {e:}",
-                checkpoint.epoch,
-                checkpoint.source
-            )
-        })?;
-        log::debug!("checkpoint message published with cid: {message_cid:?}");
+        let message_cid = self
+            .lotus_client
+            .push_operation(KeyOperation::Checkpoint, message)
+            .await
+            .map_err(|e| {
+                e.context(format!(
+                    "error submitting checkpoint for epoch {:} in subnet: {:?}",
+                    checkpoint.epoch, checkpoint.source
+                ))
+            })?;
+        tracing::debug!("checkpoint message published with cid: {message_cid:?}");
 
-        Ok(self.lotus_client.state_wait_msg(message_cid).await?.height as ChainEpoch)
+        let receipt = self.lotus_client.wait_message(message_cid).await?;
+        Ok(SubmittedCheckpoint {
+            message: message_cid.to_string(),
+            epoch: receipt.height as ChainEpoch,
+        })
     }
 }
 
@@ -654,6 +1340,24 @@ impl<T: JsonRpcClient + Send + Sync> VoteQuery<TopDownCheckpoint> for LotusSubne
             })?;
         Ok(has_voted)
     }
+
+    async fn pending_votes(
+        &self,
+        _subnet_id: &SubnetID,
+        validator: &Address,
+    ) -> Result<PendingVotes> {
+        self.pending_checkpoint_epochs(
+            validator,
+            &self.gateway_addr,
+            ipc_gateway::Method::SubmitTopDownCheckpoint as MethodNum,
+            |params| {
+                let checkpoint =
+                    cbor::deserialize::<TopDownCheckpoint>(params, "topdown_checkpoint")?;
+                Ok(checkpoint.epoch)
+            },
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -699,7 +1403,7 @@ impl<T: JsonRpcClient + Send + Sync> TopDownHandler for LotusSubnetManager<T> {
             .ipc_get_topdown_msgs(subnet_id, &self.gateway_addr, submission_tip_set, nonce)
             .await?;
 
-        log::debug!(
+        tracing::debug!(
             "nonce: {:} for submission tip set: {:} at epoch {:} of subnet: {:}, size of top down messages: {:}",
             nonce, submission_tip_set, epoch, subnet_id, top_down_msgs.len()
         );
@@ -711,16 +1415,69 @@ impl<T: JsonRpcClient + Send + Sync> TopDownHandler for LotusSubnetManager<T> {
         &self,
         validator: &Address,
         checkpoint: TopDownCheckpoint,
-    ) -> Result<ChainEpoch> {
-        let submitted_epoch = self
+    ) -> Result<SubmittedCheckpoint> {
+        let epoch = checkpoint.epoch;
+        let message = MpoolPushMessage::new(
+            self.gateway_addr,
+            *validator,
+            ipc_gateway::Method::SubmitTopDownCheckpoint as MethodNum,
+            cbor::serialize(&checkpoint, "topdown_checkpoint")?.to_vec(),
+        );
+        let message_cid = self
             .lotus_client
-            .ipc_submit_top_down_checkpoint(self.gateway_addr, validator, checkpoint)
-            .await?;
+            .push_operation(KeyOperation::Checkpoint, message)
+            .await
+            .map_err(|e| {
+                e.context(format!(
+                    "error submitting top down checkpoint at epoch {epoch:} at gateway: {}",
+                    self.gateway_addr
+                ))
+            })?;
+        tracing::debug!("top down checkpoint message published with cid: {message_cid:?}");
 
-        Ok(submitted_epoch)
+        let receipt = self.lotus_client.wait_message(message_cid).await?;
+        Ok(SubmittedCheckpoint {
+            message: message_cid.to_string(),
+            epoch: receipt.height as ChainEpoch,
+        })
     }
 }
 
+/// The name of the method of the gateway at `gateway`, or of a subnet actor, called by a message
+/// to `to` with method number `method`. The methods of the actors are hashes of their names, so
+/// that those of other actors are not mistaken for them.
+fn ipc_method_name(to: &Address, gateway: &Address, method: MethodNum) -> Option<&'static str> {
+    let methods = if to == gateway {
+        vec![
+            (ipc_gateway::Method::Fund as MethodNum, "Fund"),
+            (ipc_gateway::Method::Release as MethodNum, "Release"),
+            (ipc_gateway::Method::Propagate as MethodNum, "Propagate"),
+            (
+                ipc_gateway::Method::SubmitTopDownCheckpoint as MethodNum,
+                "SubmitTopDownCheckpoint",
+            ),
+        ]
+    } else {
+        vec![
+            (ipc_subnet_actor::Method::Join as MethodNum, "Join"),
+            (ipc_subnet_actor::Method::Leave as MethodNum, "Leave"),
+            (ipc_subnet_actor::Method::Kill as MethodNum, "Kill"),
+            (
+                ipc_subnet_actor::Method::SetValidatorNetAddr as MethodNum,
+                "SetValidatorNetAddr",
+            ),
+            (
+                ipc_subnet_actor::Method::SubmitCheckpoint as MethodNum,
+                "SubmitCheckpoint",
+            ),
+        ]
+    };
+    methods
+        .into_iter()
+        .find(|(m, _)| *m == method)
+        .map(|(_, name)| name)
+}
+
 pub async fn gateway_state(
     client: &(impl LotusClient + Sync),
     gateway_addr: &Address,
@@ -0,0 +1,268 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Health of the subnets the agent is configured with.
+//!
+//! The node of a subnet being unreachable does not prevent the agent from starting. The subnet is
+//! marked as unhealthy, the agent keeps serving all the other subnets, and the unhealthy ones are
+//! checked again in the background until their node is back.
+//!
+//! The last checkpoint successfully submitted in each subnet is recorded as well, for the
+//! operators to tell a subnet that is reachable but where checkpointing stalled.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+/// The result of the last checks of a subnet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubnetHealth {
+    pub healthy: bool,
+    /// The error of the last check, if the subnet is unhealthy.
+    pub error: Option<String>,
+    /// The unix timestamp, in seconds, since which the subnet is in its current state.
+    pub since: u64,
+    /// The number of consecutive failed checks.
+    pub failed_checks: u64,
+}
+
+pub struct SubnetHealthLog {
+    subnets: Mutex<Option<HashMap<SubnetID, SubnetHealth>>>,
+}
+
+impl SubnetHealthLog {
+    pub const fn new() -> Self {
+        Self {
+            subnets: Mutex::new(None),
+        }
+    }
+
+    /// Records a successful check of `subnet`.
+    pub fn mark_healthy(&self, subnet: &SubnetID) {
+        let mut subnets = self.subnets.lock().unwrap();
+        let subnets = subnets.get_or_insert_with(HashMap::new);
+        match subnets.get(subnet).map(|h| h.healthy) {
+            Some(true) => {}
+            previous => {
+                if previous.is_some() {
+                    tracing::info!("subnet {subnet:} is healthy again");
+                }
+                subnets.insert(
+                    subnet.clone(),
+                    SubnetHealth {
+                        healthy: true,
+                        error: None,
+                        since: now(),
+                        failed_checks: 0,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Records a failed check of `subnet`.
+    pub fn mark_unhealthy(&self, subnet: &SubnetID, error: impl Display) {
+        let mut subnets = self.subnets.lock().unwrap();
+        let subnets = subnets.get_or_insert_with(HashMap::new);
+        match subnets.get_mut(subnet) {
+            Some(h) if !h.healthy => {
+                h.error = Some(error.to_string());
+                h.failed_checks += 1;
+            }
+            _ => {
+                subnets.insert(
+                    subnet.clone(),
+                    SubnetHealth {
+                        healthy: false,
+                        error: Some(error.to_string()),
+                        since: now(),
+                        failed_checks: 1,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Whether `subnet` is healthy. Subnets that have not been checked yet are considered healthy.
+    pub fn is_healthy(&self, subnet: &SubnetID) -> bool {
+        let subnets = self.subnets.lock().unwrap();
+        subnets
+            .as_ref()
+            .and_then(|s| s.get(subnet))
+            .map_or(true, |h| h.healthy)
+    }
+
+    /// Returns the subnets that are currently unhealthy.
+    pub fn unhealthy(&self) -> Vec<SubnetID> {
+        self.all()
+            .into_iter()
+            .filter(|(_, h)| !h.healthy)
+            .map(|(s, _)| s)
+            .collect()
+    }
+
+    /// Returns the health of all the subnets checked so far.
+    pub fn all(&self) -> HashMap<SubnetID, SubnetHealth> {
+        let subnets = self.subnets.lock().unwrap();
+        subnets.clone().unwrap_or_default()
+    }
+
+    /// Forgets the subnets that are no longer in the config.
+    pub fn retain<'a>(&self, configured: impl IntoIterator<Item = &'a SubnetID>) {
+        let configured = configured.into_iter().collect::<Vec<_>>();
+        let mut subnets = self.subnets.lock().unwrap();
+        if let Some(s) = subnets.as_mut() {
+            s.retain(|id, _| configured.contains(&id));
+        }
+    }
+
+    /// Returns a summary of the unhealthy subnets, if any.
+    pub fn summary(&self) -> Option<String> {
+        let all = self.all();
+        let mut unhealthy = all.iter().filter(|(_, h)| !h.healthy).collect::<Vec<_>>();
+        if unhealthy.is_empty() {
+            return None;
+        }
+        unhealthy.sort_by_key(|(s, _)| s.to_string());
+
+        let lines = unhealthy
+            .iter()
+            .map(|(s, h)| format!("  {s:}: {}", h.error.as_deref().unwrap_or("unknown error")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(format!(
+            "running in degraded mode, {} of {} subnets unhealthy:\n{lines:}",
+            unhealthy.len(),
+            all.len()
+        ))
+    }
+}
+
+impl Default for SubnetHealthLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The last checkpoint submitted in a direction of a child subnet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LastSubmission {
+    pub subnet: String,
+    /// `bottom-up` or `top-down`
+    pub direction: String,
+    pub epoch: ChainEpoch,
+    /// The unix timestamp, in seconds, of the submission.
+    pub at: u64,
+}
+
+pub struct SubmissionLog {
+    submissions: Mutex<Option<HashMap<(SubnetID, String), LastSubmission>>>,
+}
+
+impl SubmissionLog {
+    pub const fn new() -> Self {
+        Self {
+            submissions: Mutex::new(None),
+        }
+    }
+
+    /// Records a checkpoint of the child `subnet` at `epoch` submitted successfully in
+    /// `direction`. The checkpoints of earlier epochs, submitted by other validators of the
+    /// agent, are not recorded.
+    pub fn record(&self, subnet: &SubnetID, direction: &str, epoch: ChainEpoch) {
+        let mut submissions = self.submissions.lock().unwrap();
+        let submissions = submissions.get_or_insert_with(HashMap::new);
+        let key = (subnet.clone(), direction.to_string());
+        if submissions.get(&key).map_or(true, |s| s.epoch <= epoch) {
+            submissions.insert(
+                key,
+                LastSubmission {
+                    subnet: subnet.to_string(),
+                    direction: direction.to_string(),
+                    epoch,
+                    at: now(),
+                },
+            );
+        }
+    }
+
+    /// Returns the last submission of every subnet and direction, sorted by subnet.
+    pub fn all(&self) -> Vec<LastSubmission> {
+        let submissions = self.submissions.lock().unwrap();
+        let mut all = submissions
+            .as_ref()
+            .map(|s| s.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        all.sort_by(|a, b| (&a.subnet, &a.direction).cmp(&(&b.subnet, &b.direction)));
+        all
+    }
+}
+
+impl Default for SubmissionLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::manager::health::{SubmissionLog, SubnetHealthLog};
+
+    #[test]
+    fn test_subnet_health() {
+        let log = SubnetHealthLog::new();
+        let root = SubnetID::new_root(123);
+        let child = SubnetID::new_root(456);
+
+        // Unknown subnets are not reported as unhealthy.
+        assert!(log.is_healthy(&root));
+        assert!(log.summary().is_none());
+
+        log.mark_healthy(&root);
+        log.mark_unhealthy(&child, "connection refused");
+        log.mark_unhealthy(&child, "connection timed out");
+        assert!(log.is_healthy(&root));
+        assert!(!log.is_healthy(&child));
+        assert_eq!(log.unhealthy(), vec![child.clone()]);
+
+        let health = log.all().remove(&child).unwrap();
+        assert_eq!(health.failed_checks, 2);
+        assert_eq!(health.error.as_deref(), Some("connection timed out"));
+        assert!(log.summary().unwrap().contains("1 of 2 subnets unhealthy"));
+
+        log.mark_healthy(&child);
+        assert!(log.is_healthy(&child));
+        assert!(log.summary().is_none());
+
+        log.retain([&root]);
+        assert_eq!(log.all().len(), 1);
+    }
+
+    #[test]
+    fn test_submission_log() {
+        let log = SubmissionLog::new();
+        let subnet = SubnetID::new_root(123);
+
+        log.record(&subnet, "bottom-up", 20);
+        log.record(&subnet, "bottom-up", 10);
+        log.record(&subnet, "top-down", 5);
+
+        let all = log.all();
+        assert_eq!(all.len(), 2);
+        assert_eq!((all[0].direction.as_str(), all[0].epoch), ("bottom-up", 20));
+        assert_eq!((all[1].direction.as_str(), all[1].epoch), ("top-down", 5));
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Audit log of the signatures produced with the keys of the agent.
+//!
+//! Every message or transaction signed by the agent is recorded with the operation it performs,
+//! so that operators can review what each key is used for. Keys that act as validators, i.e. that
+//! have been used to join a subnet or to submit checkpoints, should not move funds around; using
+//! them for value transfers is logged as a warning.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fvm_shared::address::Address;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::subnet::{GasConfig, GasLimitPolicy};
+
+/// The number of most recent usages kept for every key.
+pub const MAX_RECENT_USAGES: usize = 100;
+
+/// The operation performed by a signed message.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyOperation {
+    CreateSubnet,
+    JoinSubnet,
+    LeaveSubnet,
+    KillSubnet,
+    SetValidatorNetAddr,
+    SetValidatorWorkerAddr,
+    SetFederatedPower,
+    Fund,
+    Release,
+    Propagate,
+    SendCrossMessage,
+    SendValue,
+    /// A call to a faucet contract requesting test funds.
+    FaucetDrip,
+    Checkpoint,
+    /// A message replacing a message pending in the memory pool.
+    ReplaceMessage,
+}
+
+impl KeyOperation {
+    /// Whether the operation moves funds out of the key's account.
+    pub fn is_value_transfer(&self) -> bool {
+        matches!(
+            self,
+            KeyOperation::Fund
+                | KeyOperation::Release
+                | KeyOperation::SendCrossMessage
+                | KeyOperation::SendValue
+        )
+    }
+
+    /// The policy applied to the estimated gas limit of the messages performing the operation.
+    pub fn gas_policy<'a>(&self, gas: &'a GasConfig) -> &'a GasLimitPolicy {
+        match self {
+            KeyOperation::Checkpoint => &gas.checkpoint,
+            KeyOperation::Fund
+            | KeyOperation::Release
+            | KeyOperation::Propagate
+            | KeyOperation::SendCrossMessage => &gas.cross_msg,
+            KeyOperation::SendValue => &gas.send,
+            _ => &gas.other,
+        }
+    }
+
+    /// Whether the operation can only be performed by a validator.
+    pub fn is_validator_operation(&self) -> bool {
+        matches!(
+            self,
+            KeyOperation::JoinSubnet
+                | KeyOperation::SetValidatorNetAddr
+                | KeyOperation::SetValidatorWorkerAddr
+                | KeyOperation::Checkpoint
+        )
+    }
+}
+
+/// A single signature produced with a key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyUsage {
+    pub operation: KeyOperation,
+    pub subnet: String,
+    /// The unix timestamp, in seconds, of the signature.
+    pub timestamp: u64,
+    /// The cid of the fvm message or the hash of the evm transaction signed.
+    pub message: String,
+}
+
+/// The usage of a key since the agent started.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyUsageSummary {
+    /// The total number of signatures produced with the key.
+    pub signatures: u64,
+    /// The unix timestamp, in seconds, of the last signature.
+    pub last_used: Option<u64>,
+    /// Whether the key has been used for validator operations.
+    pub validator: bool,
+    /// The number of value transfers signed with the key while acting as a validator.
+    pub validator_transfers: u64,
+    /// The most recent usages of the key, oldest first.
+    pub recent: VecDeque<KeyUsage>,
+}
+
+pub struct KeyUsageLog {
+    keys: Mutex<Option<HashMap<Address, KeyUsageSummary>>>,
+}
+
+impl KeyUsageLog {
+    pub const fn new() -> Self {
+        Self {
+            keys: Mutex::new(None),
+        }
+    }
+
+    /// Records a signature produced with `key` for `operation` in `subnet`.
+    pub fn record(
+        &self,
+        key: &Address,
+        operation: KeyOperation,
+        subnet: &SubnetID,
+        message: impl ToString,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let mut keys = self.keys.lock().unwrap();
+        let summary = keys
+            .get_or_insert_with(HashMap::new)
+            .entry(*key)
+            .or_default();
+
+        if operation.is_value_transfer() && summary.validator {
+            tracing::warn!(
+                "validator key {key:} used for a value transfer ({operation:?}) in subnet {subnet:}, consider using a separate key for transfers"
+            );
+            summary.validator_transfers += 1;
+        }
+        summary.validator |= operation.is_validator_operation();
+        summary.signatures += 1;
+        summary.last_used = Some(timestamp);
+
+        if summary.recent.len() == MAX_RECENT_USAGES {
+            summary.recent.pop_front();
+        }
+        summary.recent.push_back(KeyUsage {
+            operation,
+            subnet: subnet.to_string(),
+            timestamp,
+            message: message.to_string(),
+        });
+    }
+
+    /// Returns the usage of `key`, if it has been used at all.
+    pub fn get(&self, key: &Address) -> Option<KeyUsageSummary> {
+        let keys = self.keys.lock().unwrap();
+        keys.as_ref().and_then(|k| k.get(key).cloned())
+    }
+
+    /// Returns the key that signed `message` in `subnet`, with the usage, if it is among the recent
+    /// usages of the keys.
+    pub fn find(&self, subnet: &SubnetID, message: &str) -> Option<(Address, KeyUsage)> {
+        let keys = self.keys.lock().unwrap();
+        let subnet = subnet.to_string();
+        keys.as_ref()?.iter().find_map(|(key, summary)| {
+            summary
+                .recent
+                .iter()
+                .find(|u| u.message == message && u.subnet == subnet)
+                .map(|u| (*key, u.clone()))
+        })
+    }
+
+    /// Returns the usage of all the keys used so far.
+    pub fn all(&self) -> HashMap<Address, KeyUsageSummary> {
+        let keys = self.keys.lock().unwrap();
+        keys.clone().unwrap_or_default()
+    }
+}
+
+impl Default for KeyUsageLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::manager::key_usage::{KeyOperation, KeyUsageLog, MAX_RECENT_USAGES};
+
+    #[test]
+    fn test_validator_transfers() {
+        let log = KeyUsageLog::new();
+        let key = Address::new_id(100);
+        let subnet = SubnetID::new_root(123);
+
+        // Transfers are fine until the key is used as a validator.
+        log.record(&key, KeyOperation::SendValue, &subnet, "cid0");
+        log.record(&key, KeyOperation::Checkpoint, &subnet, "cid1");
+        log.record(&key, KeyOperation::Fund, &subnet, "cid2");
+
+        let summary = log.get(&key).unwrap();
+        assert_eq!(summary.signatures, 3);
+        assert!(summary.validator);
+        assert_eq!(summary.validator_transfers, 1);
+        assert_eq!(summary.recent.back().unwrap().message, "cid2");
+        assert!(log.get(&Address::new_id(101)).is_none());
+
+        let (signer, usage) = log.find(&subnet, "cid1").unwrap();
+        assert_eq!(signer, key);
+        assert_eq!(usage.operation, KeyOperation::Checkpoint);
+        assert!(log.find(&SubnetID::new_root(124), "cid1").is_none());
+    }
+
+    #[test]
+    fn test_recent_usages_are_bounded() {
+        let log = KeyUsageLog::new();
+        let key = Address::new_id(100);
+        let subnet = SubnetID::new_root(123);
+
+        for i in 0..MAX_RECENT_USAGES + 1 {
+            log.record(&key, KeyOperation::Propagate, &subnet, i);
+        }
+
+        let summary = log.get(&key).unwrap();
+        assert_eq!(summary.signatures as usize, MAX_RECENT_USAGES + 1);
+        assert_eq!(summary.recent.len(), MAX_RECENT_USAGES);
+        assert_eq!(summary.recent.front().unwrap().message, "1");
+    }
+}
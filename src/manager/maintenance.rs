@@ -0,0 +1,211 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Maintenance mode of the agent, to upgrade or restart it without interrupting its submissions.
+//!
+//! Once in maintenance, the checkpoint managers finish the submissions of the epoch they are in
+//! and stop polling, and the background jobs stop before their next step. The agent is safe to
+//! upgrade when none of them is running anymore, at which point the state of the jobs is
+//! persisted. Leaving maintenance resumes them from where they stopped.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::jobs::JobRegistry;
+use crate::manager::clock::now;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceAction {
+    Enter,
+    Exit,
+    /// Only returns the status.
+    Status,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    /// The unix timestamp, in seconds, the agent entered maintenance at.
+    pub since: Option<u64>,
+    pub reason: Option<String>,
+    /// The activities still running, that the agent waits for before it is safe to upgrade.
+    pub in_flight: Vec<String>,
+    /// Whether the agent is in maintenance and nothing is running anymore.
+    pub safe_to_upgrade: bool,
+}
+
+#[derive(Default)]
+struct Maintenance {
+    /// The time the agent entered maintenance at, not in maintenance if not set.
+    since: Option<u64>,
+    reason: Option<String>,
+    /// The number of runs in flight of every activity.
+    in_flight: BTreeMap<String, usize>,
+}
+
+impl Maintenance {
+    fn status(&self) -> MaintenanceStatus {
+        MaintenanceStatus {
+            enabled: self.since.is_some(),
+            since: self.since,
+            reason: self.reason.clone(),
+            in_flight: self.in_flight.keys().cloned().collect(),
+            safe_to_upgrade: self.since.is_some() && self.in_flight.is_empty(),
+        }
+    }
+}
+
+pub struct MaintenanceMode {
+    state: Mutex<Option<Maintenance>>,
+    /// The jobs of the agent, persisted once they stopped in maintenance.
+    jobs: Arc<JobRegistry>,
+}
+
+impl MaintenanceMode {
+    pub fn new(jobs: Arc<JobRegistry>) -> Self {
+        Self {
+            state: Mutex::new(None),
+            jobs,
+        }
+    }
+
+    /// Puts the agent into maintenance, no new activity is started from now on.
+    pub fn enter(&self, reason: Option<String>) -> MaintenanceStatus {
+        let status = {
+            let mut state = self.state.lock().unwrap();
+            let state = state.get_or_insert_with(Maintenance::default);
+            if state.since.is_none() {
+                state.since = Some(now());
+                state.reason = reason;
+                tracing::info!(
+                    "entering maintenance, waiting for {} activities to finish",
+                    state.in_flight.len()
+                );
+            }
+            state.status()
+        };
+        if status.safe_to_upgrade {
+            self.drained();
+        }
+        status
+    }
+
+    /// Takes the agent out of maintenance, the activities resume from where they stopped.
+    pub fn exit(&self) -> MaintenanceStatus {
+        let mut state = self.state.lock().unwrap();
+        let state = state.get_or_insert_with(Maintenance::default);
+        if state.since.take().is_some() {
+            state.reason = None;
+            tracing::info!("leaving maintenance, resuming the activities of the agent");
+        }
+        state.status()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.as_ref().map_or(false, |s| s.since.is_some())
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        let state = self.state.lock().unwrap();
+        state
+            .as_ref()
+            .map(|s| s.status())
+            .unwrap_or_else(|| Maintenance::default().status())
+    }
+
+    /// Records a run of `activity`, until the returned guard is dropped. Returns `None` if the
+    /// agent is in maintenance, in which case the activity must not run.
+    pub fn start(&self, activity: impl Into<String>) -> Option<ActivityGuard> {
+        let mut state = self.state.lock().unwrap();
+        let state = state.get_or_insert_with(Maintenance::default);
+        if state.since.is_some() {
+            return None;
+        }
+        let activity = activity.into();
+        *state.in_flight.entry(activity.clone()).or_default() += 1;
+        Some(ActivityGuard {
+            mode: self,
+            activity,
+        })
+    }
+
+    fn finish(&self, activity: &str) {
+        let drained = {
+            let mut state = self.state.lock().unwrap();
+            let state = match state.as_mut() {
+                Some(s) => s,
+                None => return,
+            };
+            if let Some(count) = state.in_flight.get_mut(activity) {
+                *count -= 1;
+                if *count == 0 {
+                    state.in_flight.remove(activity);
+                }
+            }
+            state.since.is_some() && state.in_flight.is_empty()
+        };
+        if drained {
+            self.drained();
+        }
+    }
+
+    /// Persists the state of the agent once nothing runs anymore in maintenance.
+    fn drained(&self) {
+        self.jobs.flush();
+        tracing::info!("all activities stopped, the agent is safe to upgrade");
+    }
+}
+
+impl Default for MaintenanceMode {
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+/// A run of an activity of the agent.
+pub struct ActivityGuard<'a> {
+    mode: &'a MaintenanceMode,
+    activity: String,
+}
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        self.mode.finish(&self.activity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::maintenance::MaintenanceMode;
+
+    #[test]
+    fn test_maintenance() {
+        let mode = MaintenanceMode::default();
+        assert!(!mode.status().enabled);
+
+        let first = mode.start("checkpoints of manager a").unwrap();
+        let second = mode.start("checkpoints of manager a").unwrap();
+        let job = mode.start("job 1").unwrap();
+
+        let status = mode.enter(Some(String::from("upgrade")));
+        assert!(status.enabled);
+        assert_eq!(status.in_flight, vec!["checkpoints of manager a", "job 1"]);
+        assert!(!status.safe_to_upgrade);
+        // nothing new starts in maintenance
+        assert!(mode.start("job 2").is_none());
+
+        drop(job);
+        drop(first);
+        assert_eq!(mode.status().in_flight, vec!["checkpoints of manager a"]);
+        drop(second);
+        assert!(mode.status().safe_to_upgrade);
+
+        let status = mode.exit();
+        assert!(!status.enabled && !status.safe_to_upgrade);
+        assert!(status.reason.is_none());
+        assert!(mode.start("job 2").is_some());
+    }
+}
@@ -6,7 +6,19 @@ pub use subnet::SubnetManager;
 
 pub use crate::lotus::message::ipc::SubnetInfo;
 
+pub mod activity;
+pub mod approval;
+pub mod clock;
 pub mod evm;
 pub mod fevm;
+pub mod funds;
 pub mod fvm;
+pub mod health;
+pub mod key_usage;
+pub mod maintenance;
+pub mod multihop;
+pub mod nonce;
+pub mod offline;
+pub mod shutdown;
+pub mod signer;
 mod subnet;
@@ -0,0 +1,206 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Funds sent to a descendant of a subnet deeper than its direct children.
+//!
+//! A gateway only funds its direct children, so the funds are moved down the route one subnet at
+//! a time: they are funded from the source to the first subnet of the route, to the account
+//! sending them, and funded again to the next subnet once the top-down checkpoint carrying them
+//! is executed, until the destination is reached. The hops after the first run as a [`Job`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+
+use crate::checkpoint::next_checkpoint_epoch;
+use crate::jobs::Job;
+use crate::server::check_subnet;
+use crate::server::subnet::SubnetManagerPool;
+
+/// The kind of the multi-hop fund jobs.
+pub const MULTI_HOP_FUND_JOB: &str = "multi-hop-fund";
+/// How often the arrival of the funds in the next subnet of the route is checked.
+const ARRIVAL_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Returns the subnets the funds go through from `source` to `destination`, both included.
+pub fn fund_route(source: &SubnetID, destination: &SubnetID) -> Result<Vec<SubnetID>> {
+    let mut route = vec![destination.clone()];
+    let mut current = destination.clone();
+    while &current != source {
+        current = current
+            .parent()
+            .ok_or_else(|| anyhow!("subnet {destination:} is not a descendant of {source:}"))?;
+        route.push(current.clone());
+    }
+    if route.len() < 2 {
+        return Err(anyhow!("cannot fund subnet {source:} from itself"));
+    }
+    route.reverse();
+    Ok(route)
+}
+
+/// A fund moved down its route one subnet at a time.
+pub struct MultiHopFund {
+    pool: Arc<SubnetManagerPool>,
+    route: Vec<SubnetID>,
+    /// The account sending the funds in every subnet of the route but the destination.
+    from: Address,
+    /// The account credited in the destination.
+    to: Address,
+    amount: TokenAmount,
+    /// The index in the route of the subnet the funds are sent from next.
+    hop: usize,
+    /// The epoch the fund of the current hop was executed at, once sent.
+    sent: Option<ChainEpoch>,
+}
+
+impl MultiHopFund {
+    /// Checks that the agent can send the funds from every subnet of `route` but the destination.
+    pub fn new(
+        pool: Arc<SubnetManagerPool>,
+        route: Vec<SubnetID>,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<Self> {
+        for id in route[..route.len() - 1].iter() {
+            let conn = pool
+                .get(id)
+                .ok_or_else(|| anyhow!("subnet {id:} of the route not configured"))?;
+            check_subnet(conn.subnet())?;
+        }
+
+        Ok(Self {
+            pool,
+            route,
+            from,
+            to,
+            amount,
+            hop: 0,
+            sent: None,
+        })
+    }
+
+    /// The subnets the funds go through, from the source to the destination.
+    pub fn route(&self) -> &[SubnetID] {
+        &self.route
+    }
+
+    fn hops(&self) -> usize {
+        self.route.len() - 1
+    }
+
+    /// Funds the next subnet of the route, returning the epoch the fund was executed at.
+    pub async fn send_hop(&mut self) -> Result<ChainEpoch> {
+        let (source, target) = (&self.route[self.hop], &self.route[self.hop + 1]);
+        let conn = self
+            .pool
+            .get(source)
+            .ok_or_else(|| anyhow!("subnet {source:} not configured"))?;
+        let last = self.hop + 1 == self.hops();
+        let to = if last { self.to } else { self.from };
+
+        let epoch = conn
+            .manager()
+            .fund(
+                target.clone(),
+                conn.subnet().gateway_addr(),
+                self.from,
+                to,
+                self.amount.clone(),
+            )
+            .await?;
+        tracing::info!(
+            "funded {} to {to:} in subnet {target:} from subnet {source:} at epoch {epoch:}",
+            self.amount
+        );
+
+        // the destination does not need to be in the config, the funds are not tracked once sent
+        // to it then
+        if last && self.pool.get(target).is_none() {
+            self.hop += 1;
+        } else {
+            self.sent = Some(epoch);
+        }
+        Ok(epoch)
+    }
+
+    /// Moves to the next hop if the funds of the current one, sent at epoch `sent`, were credited
+    /// by a top-down checkpoint.
+    async fn check_arrival(&mut self, sent: ChainEpoch) -> Result<()> {
+        let target = &self.route[self.hop + 1];
+        let conn = self
+            .pool
+            .get(target)
+            .ok_or_else(|| anyhow!("subnet {target:} not configured"))?;
+        let gateway = conn.subnet().gateway_addr();
+        let period = conn.manager().top_down_check_period(&gateway).await?;
+        let executed = conn.manager().last_topdown_executed(&gateway).await?;
+        if executed >= next_checkpoint_epoch(sent, period) {
+            tracing::info!("funds credited in subnet {target:}");
+            self.hop += 1;
+            self.sent = None;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Job for MultiHopFund {
+    fn kind(&self) -> &'static str {
+        MULTI_HOP_FUND_JOB
+    }
+
+    async fn step(&mut self) -> Result<()> {
+        match self.sent {
+            Some(sent) => self.check_arrival(sent).await,
+            None => self.send_hop().await.map(|_| ()),
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.hop >= self.hops()
+    }
+
+    fn progress(&self) -> f64 {
+        self.hop as f64 * 100.0 / self.hops() as f64
+    }
+
+    fn interval(&self) -> Duration {
+        ARRIVAL_POLL_INTERVAL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::manager::multihop::fund_route;
+
+    #[test]
+    fn test_fund_route() {
+        let root = SubnetID::from_str("/r123").unwrap();
+        let child = SubnetID::from_str("/r123/f0100").unwrap();
+        let grandchild = SubnetID::from_str("/r123/f0100/f0200").unwrap();
+
+        assert_eq!(
+            fund_route(&child, &grandchild).unwrap(),
+            vec![child.clone(), grandchild.clone()]
+        );
+        assert_eq!(
+            fund_route(&root, &grandchild).unwrap(),
+            vec![root, child.clone(), grandchild.clone()]
+        );
+        assert!(fund_route(&grandchild, &child).is_err());
+        assert!(fund_route(&child, &child).is_err());
+        let sibling = SubnetID::from_str("/r123/f0101").unwrap();
+        assert!(fund_route(&sibling, &grandchild).is_err());
+    }
+}
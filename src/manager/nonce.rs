@@ -0,0 +1,222 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Nonces of the accounts sending messages from the agent.
+//!
+//! The next nonce reported by a node only follows the messages already in its memory pool, so two
+//! handlers sending from the same account at the same time, e.g. a checkpoint and a fund, are given
+//! the same nonce and one replaces the other. The agent instead reserves the nonces of its
+//! messages locally, per subnet and account, and only takes the one of the node when it is ahead,
+//! i.e. messages were sent from the account outside of the agent. Nothing is persisted: after a
+//! restart the nonces are taken from the node again, which has seen every message sent before.
+//!
+//! The nonce of a message pushed but not executed can be reclaimed by abandoning the message: the
+//! next message of its sender is then sent with its nonce, and replaces it in the memory pool.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+
+/// The time after which the nonces reserved for an account that the node has not seen are given
+/// up on, e.g. the agent reserved a nonce and failed before sending its message.
+const RESERVATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy)]
+struct Reservation {
+    /// The nonce the next message of the account is sent with.
+    next: u64,
+    /// When the last nonce was reserved.
+    at: Instant,
+}
+
+pub struct NonceManager {
+    accounts: Mutex<Option<HashMap<(SubnetID, Address), Reservation>>>,
+    /// The nonces of the abandoned messages of the accounts, with the gas premium they pay.
+    abandoned: Mutex<Option<HashMap<(SubnetID, Address), BTreeMap<u64, TokenAmount>>>>,
+}
+
+impl NonceManager {
+    pub const fn new() -> Self {
+        Self {
+            accounts: Mutex::new(None),
+            abandoned: Mutex::new(None),
+        }
+    }
+
+    /// Returns the nonce of the next message of `account` in `subnet`, and reserves it. `node_next`
+    /// is the next nonce of the account reported by the node.
+    pub fn reserve(&self, subnet: &SubnetID, account: &Address, node_next: u64) -> u64 {
+        self.reserve_at(subnet, account, node_next, Instant::now())
+    }
+
+    fn reserve_at(
+        &self,
+        subnet: &SubnetID,
+        account: &Address,
+        node_next: u64,
+        now: Instant,
+    ) -> u64 {
+        let mut accounts = self.accounts.lock().unwrap();
+        let accounts = accounts.get_or_insert_with(HashMap::new);
+        let key = (subnet.clone(), *account);
+        let nonce = match accounts.get(&key) {
+            Some(r) if r.next > node_next && now.duration_since(r.at) < RESERVATION_TIMEOUT => {
+                r.next
+            }
+            Some(r) if r.next > node_next => {
+                tracing::warn!(
+                    "nonces {node_next:}..{} of {account:} in subnet {subnet:} reserved but not seen by the node, reusing them",
+                    r.next
+                );
+                node_next
+            }
+            _ => node_next,
+        };
+        accounts.insert(
+            key,
+            Reservation {
+                next: nonce + 1,
+                at: now,
+            },
+        );
+        nonce
+    }
+
+    /// Gives back the `nonce` reserved for a message of `account` in `subnet` that could not be
+    /// sent. The nonce is reused if no other one was reserved since, otherwise the next nonce is
+    /// taken from the node.
+    pub fn release(&self, subnet: &SubnetID, account: &Address, nonce: u64) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let accounts = match accounts.as_mut() {
+            Some(a) => a,
+            None => return,
+        };
+        let key = (subnet.clone(), *account);
+        match accounts.get_mut(&key) {
+            Some(r) if r.next == nonce + 1 => r.next = nonce,
+            Some(_) => {
+                accounts.remove(&key);
+            }
+            None => {}
+        }
+    }
+
+    /// Abandons the message of `account` in `subnet` pending with `nonce` and paying
+    /// `gas_premium`, so that its nonce is reclaimed by the next message of the account.
+    pub fn abandon(
+        &self,
+        subnet: &SubnetID,
+        account: &Address,
+        nonce: u64,
+        gas_premium: TokenAmount,
+    ) {
+        let mut abandoned = self.abandoned.lock().unwrap();
+        abandoned
+            .get_or_insert_with(HashMap::new)
+            .entry((subnet.clone(), *account))
+            .or_default()
+            .insert(nonce, gas_premium);
+    }
+
+    /// Takes the lowest nonce abandoned by `account` in `subnet`, if any, with the gas premium of
+    /// the message it replaces. The nonce is abandoned again if the replacement is not sent.
+    pub fn reclaim(&self, subnet: &SubnetID, account: &Address) -> Option<(u64, TokenAmount)> {
+        let mut abandoned = self.abandoned.lock().unwrap();
+        let key = (subnet.clone(), *account);
+        let nonces = abandoned.as_mut()?.get_mut(&key)?;
+        let nonce = *nonces.keys().next()?;
+        let premium = nonces.remove(&nonce);
+        if nonces.is_empty() {
+            abandoned.as_mut()?.remove(&key);
+        }
+        premium.map(|p| (nonce, p))
+    }
+
+    /// The nonces abandoned by `account` in `subnet` and not reclaimed yet.
+    pub fn abandoned(&self, subnet: &SubnetID, account: &Address) -> Vec<u64> {
+        let abandoned = self.abandoned.lock().unwrap();
+        abandoned
+            .as_ref()
+            .and_then(|a| a.get(&(subnet.clone(), *account)))
+            .map(|nonces| nonces.keys().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for NonceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::{Duration, Instant};
+
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::manager::nonce::{NonceManager, RESERVATION_TIMEOUT};
+
+    #[test]
+    fn test_reserve_nonces() {
+        let nonces = NonceManager::new();
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let account = Address::new_id(1001);
+        let now = Instant::now();
+
+        // the node does not see the messages just sent yet
+        assert_eq!(nonces.reserve_at(&subnet, &account, 5, now), 5);
+        assert_eq!(nonces.reserve_at(&subnet, &account, 5, now), 6);
+        // messages sent from other wallets move the node ahead
+        assert_eq!(nonces.reserve_at(&subnet, &account, 10, now), 10);
+
+        // the last nonce is reused when its message is not sent
+        nonces.release(&subnet, &account, 10);
+        assert_eq!(nonces.reserve_at(&subnet, &account, 10, now), 10);
+        nonces.reserve_at(&subnet, &account, 10, now);
+        nonces.release(&subnet, &account, 10);
+        assert_eq!(nonces.reserve_at(&subnet, &account, 8, now), 8);
+
+        // reservations the node never sees are given up on
+        let later = now + RESERVATION_TIMEOUT + Duration::from_secs(1);
+        assert_eq!(nonces.reserve_at(&subnet, &account, 8, later), 8);
+
+        // accounts and subnets are tracked independently
+        assert_eq!(
+            nonces.reserve_at(&subnet, &Address::new_id(1002), 0, now),
+            0
+        );
+        let other = SubnetID::from_str("/r123/f0101").unwrap();
+        assert_eq!(nonces.reserve_at(&other, &account, 3, now), 3);
+    }
+
+    #[test]
+    fn test_reclaim_abandoned_nonces() {
+        let nonces = NonceManager::new();
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let account = Address::new_id(1001);
+        assert!(nonces.reclaim(&subnet, &account).is_none());
+
+        nonces.abandon(&subnet, &account, 7, TokenAmount::from_atto(100));
+        nonces.abandon(&subnet, &account, 5, TokenAmount::from_atto(200));
+        assert_eq!(nonces.abandoned(&subnet, &account), vec![5, 7]);
+        assert!(nonces.abandoned(&subnet, &Address::new_id(1002)).is_empty());
+
+        // the lowest nonce first, as the next ones cannot be executed before it
+        assert_eq!(
+            nonces.reclaim(&subnet, &account),
+            Some((5, TokenAmount::from_atto(200)))
+        );
+        assert_eq!(
+            nonces.reclaim(&subnet, &account),
+            Some((7, TokenAmount::from_atto(100)))
+        );
+        assert!(nonces.reclaim(&subnet, &account).is_none());
+    }
+}
@@ -0,0 +1,487 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Transactions built on an online machine, signed on an offline one and broadcast later.
+//!
+//! The transaction travels between the three stages in an [`OfflineTx`] file. Its payload is the
+//! network specific unsigned transaction, i.e. the cbor encoded message in fvm subnets and the
+//! json encoded transaction in fevm subnets, with the nonce and gas already filled in. The file
+//! carries a checksum over all its fields to detect corrupted files. The checksum is not keyed,
+//! so anyone modifying the file can recompute it: the signer relies on the summary decoded from
+//! the payload rather than on the description, and the signature is verified against the sender
+//! before broadcasting.
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::Bytes;
+use fvm_shared::address::{Address, Protocol};
+use fvm_shared::crypto::signature::Signature;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use ipc_identity::{blake2b_256, EvmKeyStore, PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::NativeBottomUpCheckpoint;
+use crate::config::subnet::NetworkType;
+use crate::lotus::message::mpool::message_cid;
+use crate::manager::evm::ethers_address_to_fil_address;
+
+/// The version of the offline transaction file format.
+pub const OFFLINE_TX_VERSION: u8 = 1;
+
+/// The calls that can be built into an offline transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OfflineCall {
+    /// Fund an address in the subnet, executed in its parent.
+    Fund {
+        subnet: SubnetID,
+        to: Address,
+        amount: TokenAmount,
+    },
+    /// Release funds to an address in the parent, executed in the subnet.
+    Release {
+        subnet: SubnetID,
+        to: Address,
+        amount: TokenAmount,
+    },
+    /// Join the subnet as a validator, executed in its parent.
+    Join {
+        subnet: SubnetID,
+        collateral: TokenAmount,
+        validator_net_addr: String,
+        worker_addr: Address,
+    },
+    /// Leave the subnet, executed in its parent.
+    Leave { subnet: SubnetID },
+    /// Send value to an address within the subnet.
+    SendValue {
+        subnet: SubnetID,
+        to: Address,
+        amount: TokenAmount,
+    },
+    /// Vote a bottom-up checkpoint of the subnet, executed in its parent. The checkpoint is
+    /// assembled online, with its proof and the hash of the previous checkpoint.
+    CheckpointVote {
+        checkpoint: NativeBottomUpCheckpoint,
+    },
+}
+
+impl OfflineCall {
+    /// Returns the subnet whose chain executes the call.
+    pub fn execution_subnet(&self) -> Result<SubnetID> {
+        match self {
+            OfflineCall::Fund { subnet, .. }
+            | OfflineCall::Join { subnet, .. }
+            | OfflineCall::Leave { subnet } => subnet
+                .parent()
+                .ok_or_else(|| anyhow!("subnet {subnet:} has no parent")),
+            OfflineCall::Release { subnet, .. } | OfflineCall::SendValue { subnet, .. } => {
+                Ok(subnet.clone())
+            }
+            OfflineCall::CheckpointVote { checkpoint } => checkpoint
+                .source
+                .parent()
+                .ok_or_else(|| anyhow!("subnet {} has no parent", checkpoint.source)),
+        }
+    }
+}
+
+impl Display for OfflineCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OfflineCall::Fund {
+                subnet, to, amount, ..
+            } => write!(f, "fund {to:} in {subnet:} with {amount:} FIL"),
+            OfflineCall::Release {
+                subnet, to, amount, ..
+            } => write!(f, "release {amount:} FIL from {subnet:} to {to:}"),
+            OfflineCall::Join {
+                subnet, collateral, ..
+            } => write!(f, "join {subnet:} with {collateral:} FIL of collateral"),
+            OfflineCall::Leave { subnet } => write!(f, "leave {subnet:}"),
+            OfflineCall::SendValue {
+                subnet, to, amount, ..
+            } => write!(f, "send {amount:} FIL to {to:} in {subnet:}"),
+            OfflineCall::CheckpointVote { checkpoint } => write!(
+                f,
+                "vote the bottom-up checkpoint of {} at epoch {}",
+                checkpoint.source, checkpoint.epoch
+            ),
+        }
+    }
+}
+
+/// The type of network the transaction is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxNetwork {
+    Fvm,
+    Fevm,
+}
+
+impl From<NetworkType> for TxNetwork {
+    fn from(network: NetworkType) -> Self {
+        match network {
+            NetworkType::Fvm => TxNetwork::Fvm,
+            NetworkType::Fevm => TxNetwork::Fevm,
+        }
+    }
+}
+
+/// The encoding of the offline transaction files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxEncoding {
+    Json,
+    Cbor,
+}
+
+impl FromStr for TxEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "json" => Self::Json,
+            "cbor" => Self::Cbor,
+            _ => return Err(anyhow!("invalid encoding")),
+        })
+    }
+}
+
+/// A transaction in any of its offline stages, unsigned or signed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfflineTx {
+    pub version: u8,
+    pub network: TxNetwork,
+    /// The subnet executing the transaction
+    pub subnet: String,
+    pub from: String,
+    /// Informative summary of the call, the signer should rely on [`OfflineTx::summary`] instead
+    pub description: String,
+    /// Hex encoded unsigned transaction
+    pub payload: String,
+    /// Hex encoded signature of the transaction, set once signed
+    pub signature: Option<String>,
+    /// Hex encoded blake2b-256 digest of all the other fields
+    pub checksum: String,
+}
+
+impl OfflineTx {
+    pub fn new(
+        network: TxNetwork,
+        subnet: &SubnetID,
+        from: &Address,
+        description: String,
+        payload: &[u8],
+    ) -> Result<Self> {
+        let mut tx = Self {
+            version: OFFLINE_TX_VERSION,
+            network,
+            subnet: subnet.to_string(),
+            from: from.to_string(),
+            description,
+            payload: hex::encode(payload),
+            signature: None,
+            checksum: String::new(),
+        };
+        tx.checksum = tx.compute_checksum()?;
+        Ok(tx)
+    }
+
+    fn compute_checksum(&self) -> Result<String> {
+        let fields = serde_json::to_vec(&(
+            self.version,
+            self.network,
+            &self.subnet,
+            &self.from,
+            &self.description,
+            &self.payload,
+            &self.signature,
+        ))?;
+        Ok(hex::encode(blake2b_256(&fields)))
+    }
+
+    /// Checks that the file format is supported and that the transaction was not corrupted since
+    /// its checksum was computed.
+    pub fn verify(&self) -> Result<()> {
+        if self.version != OFFLINE_TX_VERSION {
+            return Err(anyhow!(
+                "unsupported transaction file version: {}",
+                self.version
+            ));
+        }
+        if self.compute_checksum()? != self.checksum {
+            return Err(anyhow!(
+                "checksum mismatch, the transaction file is corrupted"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn payload(&self) -> Result<Vec<u8>> {
+        Ok(hex::decode(&self.payload)?)
+    }
+
+    pub fn signature(&self) -> Result<Vec<u8>> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| anyhow!("transaction is not signed"))?;
+        Ok(hex::decode(signature)?)
+    }
+
+    fn set_signature(&mut self, signature: &[u8]) -> Result<()> {
+        self.signature = Some(hex::encode(signature));
+        self.checksum = self.compute_checksum()?;
+        Ok(())
+    }
+
+    pub fn sender(&self) -> Result<Address> {
+        Ok(Address::from_str(&self.from)?)
+    }
+
+    /// Decodes the payload into a human readable summary of what is signed.
+    pub fn summary(&self) -> Result<String> {
+        let payload = self.payload()?;
+        Ok(match self.network {
+            TxNetwork::Fvm => {
+                let msg: Message = fvm_ipld_encoding::from_slice(&payload)?;
+                format!(
+                    "from={}, to={}, value={} FIL, method={}, nonce={}, gas_limit={}, gas_fee_cap={}",
+                    msg.from,
+                    msg.to,
+                    msg.value,
+                    msg.method_num,
+                    msg.sequence,
+                    msg.gas_limit,
+                    msg.gas_fee_cap.atto()
+                )
+            }
+            TxNetwork::Fevm => {
+                let tx: TypedTransaction = serde_json::from_slice(&payload)?;
+                format!(
+                    "from={:?}, to={:?}, value={} wei, nonce={}, gas={}, chain_id={}",
+                    tx.from()
+                        .ok_or_else(|| anyhow!("no sender in transaction"))?,
+                    tx.to_addr()
+                        .ok_or_else(|| anyhow!("no recipient in transaction"))?,
+                    tx.value().cloned().unwrap_or_default(),
+                    tx.nonce().cloned().unwrap_or_default(),
+                    tx.gas().cloned().unwrap_or_default(),
+                    tx.chain_id().unwrap_or_default()
+                )
+            }
+        })
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>, encoding: TxEncoding) -> Result<()> {
+        let bytes = match encoding {
+            TxEncoding::Json => serde_json::to_vec_pretty(self)?,
+            TxEncoding::Cbor => fvm_ipld_encoding::to_vec(self)?,
+        };
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Reads and verifies a transaction file in any of the supported encodings.
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let tx: Self = match serde_json::from_slice(&bytes) {
+            Ok(tx) => tx,
+            Err(_) => fvm_ipld_encoding::from_slice(&bytes)
+                .map_err(|_| anyhow!("unknown transaction file encoding"))?,
+        };
+        tx.verify()?;
+        Ok(tx)
+    }
+}
+
+/// Signs an fvm transaction with the key of its sender in the wallet.
+pub fn sign_fvm(tx: &mut OfflineTx, wallet: &mut Wallet) -> Result<()> {
+    tx.verify()?;
+
+    let message: Message = fvm_ipld_encoding::from_slice(&tx.payload()?)?;
+    if message.from != tx.sender()? {
+        return Err(anyhow!(
+            "sender of the message does not match the transaction"
+        ));
+    }
+
+    let signature = wallet.sign(&message.from, &message_cid(&message)?.to_bytes())?;
+    tx.set_signature(signature.bytes())
+}
+
+/// Signs a fevm transaction with the key of its sender in the keystore.
+pub fn sign_evm(
+    tx: &mut OfflineTx,
+    keystore: &PersistentKeyStore<ethers::types::Address>,
+) -> Result<()> {
+    tx.verify()?;
+
+    let transaction: TypedTransaction = serde_json::from_slice(&tx.payload()?)?;
+    let from = *transaction
+        .from()
+        .ok_or_else(|| anyhow!("no sender in transaction"))?;
+    if ethers_address_to_fil_address(&from)? != tx.sender()? {
+        return Err(anyhow!("sender of the transaction does not match"));
+    }
+    let chain_id = transaction
+        .chain_id()
+        .ok_or_else(|| anyhow!("no chain id in transaction"))?;
+
+    let key_info = keystore
+        .get(&from)?
+        .ok_or_else(|| anyhow!("address {from:?} does not have private key in key store"))?;
+    let wallet = LocalWallet::from_bytes(key_info.private_key())?.with_chain_id(chain_id.as_u64());
+    let signature = wallet.sign_transaction_sync(&transaction)?;
+
+    tx.set_signature(&signature.to_vec())
+}
+
+/// Returns the fvm message and its signature, after checking that it was signed by its sender.
+pub fn fvm_signed_message(tx: &OfflineTx) -> Result<(Message, Signature)> {
+    tx.verify()?;
+
+    let message: Message = fvm_ipld_encoding::from_slice(&tx.payload()?)?;
+    let signature = match message.from.protocol() {
+        Protocol::Secp256k1 => Signature::new_secp256k1(tx.signature()?),
+        Protocol::BLS => Signature::new_bls(tx.signature()?),
+        _ => return Err(anyhow!("sender cannot sign messages")),
+    };
+    signature
+        .verify(&message_cid(&message)?.to_bytes(), &message.from)
+        .map_err(|e| anyhow!("invalid signature: {e:}"))?;
+
+    Ok((message, signature))
+}
+
+/// Returns the raw signed fevm transaction, after checking that it was signed by its sender.
+pub fn evm_signed_transaction(tx: &OfflineTx) -> Result<Bytes> {
+    tx.verify()?;
+
+    let transaction: TypedTransaction = serde_json::from_slice(&tx.payload()?)?;
+    let signature = ethers::types::Signature::try_from(tx.signature()?.as_slice())?;
+    let signer = signature.recover(transaction.sighash())?;
+    if Some(&signer) != transaction.from() {
+        return Err(anyhow!("transaction not signed by its sender"));
+    }
+
+    Ok(transaction.rlp_signed(&signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::address::Address;
+    use fvm_shared::crypto::signature::SignatureType;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::message::Message;
+    use ipc_identity::{KeyStore, KeyStoreConfig, Wallet};
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::checkpoint::NativeBottomUpCheckpoint;
+    use crate::manager::offline::{
+        fvm_signed_message, sign_fvm, OfflineCall, OfflineTx, TxEncoding, TxNetwork,
+    };
+
+    fn fvm_tx(from: Address) -> OfflineTx {
+        let message = Message {
+            version: 0,
+            from,
+            to: Address::new_id(64),
+            sequence: 1,
+            value: TokenAmount::from_whole(1),
+            method_num: 0,
+            params: Default::default(),
+            gas_limit: 1000,
+            gas_fee_cap: TokenAmount::from_atto(100),
+            gas_premium: TokenAmount::from_atto(10),
+        };
+        OfflineTx::new(
+            TxNetwork::Fvm,
+            &SubnetID::from_str("/r31415926").unwrap(),
+            &from,
+            String::from("send"),
+            &fvm_ipld_encoding::to_vec(&message).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn checkpoint_vote(subnet: &str) -> OfflineCall {
+        OfflineCall::CheckpointVote {
+            checkpoint: NativeBottomUpCheckpoint {
+                source: SubnetID::from_str(subnet).unwrap(),
+                proof: None,
+                epoch: 20,
+                prev_check: None,
+                children: vec![],
+                cross_msgs: Default::default(),
+                sig: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_vote() {
+        let call = checkpoint_vote("/r31415926/t01002");
+
+        // the vote is executed in the parent of the subnet checkpointed
+        assert_eq!(
+            call.execution_subnet().unwrap(),
+            SubnetID::from_str("/r31415926").unwrap()
+        );
+        assert_eq!(
+            call.to_string(),
+            "vote the bottom-up checkpoint of /r31415926/t01002 at epoch 20"
+        );
+        assert!(checkpoint_vote("/r31415926").execution_subnet().is_err());
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let mut tx = fvm_tx(Address::new_id(100));
+        tx.verify().unwrap();
+
+        tx.description = String::from("something else");
+        assert!(tx.verify().is_err());
+    }
+
+    #[test]
+    fn test_file_encodings() {
+        let dir = tempfile::tempdir().unwrap();
+        let tx = fvm_tx(Address::new_id(100));
+
+        for encoding in [TxEncoding::Json, TxEncoding::Cbor] {
+            let path = dir.path().join("tx");
+            tx.write_to_file(&path, encoding).unwrap();
+            assert_eq!(OfflineTx::read_from_file(&path).unwrap(), tx);
+        }
+    }
+
+    #[test]
+    fn test_sign_fvm() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let from = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+
+        let mut tx = fvm_tx(from);
+        assert!(fvm_signed_message(&tx).is_err());
+
+        sign_fvm(&mut tx, &mut wallet).unwrap();
+        let (message, _) = fvm_signed_message(&tx).unwrap();
+        assert_eq!(message.from, from);
+
+        // a signature of another key is rejected
+        let mut other = fvm_tx(wallet.generate_addr(SignatureType::Secp256k1).unwrap());
+        sign_fvm(&mut other, &mut wallet).unwrap();
+        // the checksum does not catch a modified file whose checksum is recomputed, the
+        // verification of the signature does
+        tx.signature = other.signature;
+        tx.checksum = tx.compute_checksum().unwrap();
+        assert!(fvm_signed_message(&tx).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Graceful shutdown of the daemon, on SIGINT or SIGTERM.
+//!
+//! Once the shutdown starts, the json rpc requests are refused, and the agent is put in
+//! maintenance: the checkpoint managers finish the submissions of the epoch they are in and the
+//! jobs stop before their next step. The subsystems wait, up to the shutdown timeout of the
+//! config, for the requests being served and these activities to finish before they stop, so that
+//! the agent does not exit in the middle of a submission.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::time::sleep;
+
+use crate::manager::maintenance::MaintenanceMode;
+
+/// The interval between two checks of the work in flight while draining it.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+struct Draining {
+    /// The deadline of the draining, not shutting down if not set.
+    deadline: Option<Instant>,
+    /// The number of json rpc requests being served.
+    requests: usize,
+}
+
+pub struct Shutdown {
+    state: Mutex<Option<Draining>>,
+    /// The maintenance mode the agent is put in to stop its activities.
+    maintenance: Arc<MaintenanceMode>,
+}
+
+impl Shutdown {
+    pub fn new(maintenance: Arc<MaintenanceMode>) -> Self {
+        Self {
+            state: Mutex::new(None),
+            maintenance,
+        }
+    }
+
+    /// Records a json rpc request being served, until the returned guard is dropped. Returns
+    /// `None` if the agent is shutting down, in which case the request must be refused.
+    pub fn start_request(&self) -> Option<RequestGuard> {
+        let mut state = self.state.lock().unwrap();
+        let state = state.get_or_insert_with(Draining::default);
+        if state.deadline.is_some() {
+            return None;
+        }
+        state.requests += 1;
+        Some(RequestGuard { shutdown: self })
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.as_ref().map_or(false, |s| s.deadline.is_some())
+    }
+
+    /// Starts the shutdown, if not started yet, and waits for the work in flight to finish, up to
+    /// `timeout` after the shutdown started. Returns whether everything finished in time.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let deadline = self.begin(timeout);
+        loop {
+            let requests = self.requests();
+            let activities = self.maintenance.status().in_flight;
+            if requests == 0 && activities.is_empty() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                tracing::warn!(
+                    "shutdown timed out with {requests:} json rpc requests in flight and activities still running: {activities:?}"
+                );
+                return false;
+            }
+            sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
+    fn begin(&self, timeout: Duration) -> Instant {
+        let mut state = self.state.lock().unwrap();
+        let state = state.get_or_insert_with(Draining::default);
+        if let Some(deadline) = state.deadline {
+            return deadline;
+        }
+        let deadline = Instant::now() + timeout;
+        state.deadline = Some(deadline);
+        tracing::info!(
+            "shutting down, refusing new requests and waiting up to {timeout:?} for {} requests in flight",
+            state.requests
+        );
+        self.maintenance.enter(Some(String::from("shutting down")));
+        deadline
+    }
+
+    fn requests(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.as_ref().map_or(0, |s| s.requests)
+    }
+
+    fn finish_request(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(state) = state.as_mut() {
+            state.requests -= 1;
+        }
+    }
+}
+
+/// A json rpc request being served.
+pub struct RequestGuard<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for RequestGuard<'_> {
+    fn drop(&mut self) {
+        self.shutdown.finish_request();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::manager::maintenance::MaintenanceMode;
+    use crate::manager::shutdown::Shutdown;
+
+    #[tokio::test]
+    async fn test_drain() {
+        let maintenance = Arc::new(MaintenanceMode::default());
+        let shutdown = Shutdown::new(maintenance.clone());
+        assert!(!shutdown.is_shutting_down());
+
+        let request = shutdown.start_request().unwrap();
+        let activity = maintenance.start("checkpoints of manager a").unwrap();
+        // the request in flight is not finished in time
+        assert!(!shutdown.drain(Duration::from_millis(300)).await);
+        assert!(shutdown.is_shutting_down());
+        assert!(maintenance.is_enabled());
+        assert!(shutdown.start_request().is_none());
+
+        drop(request);
+        drop(activity);
+        assert!(shutdown.drain(Duration::from_secs(10)).await);
+    }
+}
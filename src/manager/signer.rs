@@ -0,0 +1,197 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Signatures of the bottom-up checkpoints by the validators of a subnet.
+//!
+//! Besides the signature of the message submitting it, a bottom-up checkpoint can carry in its
+//! `sig` field the signature of its data by the validators, see
+//! [`CheckpointSigning`](crate::config::subnet::CheckpointSigning). A [`CheckpointSigner`] signs
+//! with the key of a validator, secp256k1 or BLS depending on its address, without going through
+//! the node. The signatures of several validators with BLS keys are aggregated into a single one,
+//! verified against all their public keys at once. As BLS aggregates are only secure over distinct
+//! messages, every validator signs the data of the checkpoint followed by its own address.
+
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Result};
+use fvm_shared::address::{Address, Payload};
+use fvm_shared::crypto::signature::ops::verify_bls_aggregate;
+use fvm_shared::crypto::signature::{Signature, SignatureType};
+use ipc_gateway::BottomUpCheckpoint;
+use ipc_identity::Wallet;
+use serde_tuple::{Deserialize_tuple, Serialize_tuple};
+
+use crate::checkpoint::NativeBottomUpCheckpoint;
+
+/// Signs the checkpoints with the keys of the validators.
+pub trait CheckpointSigner: Send + Sync {
+    /// Signs `message` with the key of `validator`.
+    fn sign(&self, validator: &Address, message: &[u8]) -> Result<Signature>;
+}
+
+/// Signs with the keys of the fvm wallet of the agent.
+pub struct WalletSigner {
+    wallet: Arc<RwLock<Wallet>>,
+}
+
+impl WalletSigner {
+    pub fn new(wallet: Arc<RwLock<Wallet>>) -> Self {
+        Self { wallet }
+    }
+}
+
+impl CheckpointSigner for WalletSigner {
+    fn sign(&self, validator: &Address, message: &[u8]) -> Result<Signature> {
+        self.wallet
+            .write()
+            .unwrap()
+            .sign(validator, message)
+            .map_err(|e| anyhow!("cannot sign checkpoint with the key of {validator:}: {e:}"))
+    }
+}
+
+/// The signature of a checkpoint, serialized as cbor in its `sig` field: the signature of a
+/// single validator, or the aggregation of the BLS signatures of several ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct CheckpointSignature {
+    /// The validators that signed the checkpoint.
+    pub signers: Vec<Address>,
+    pub signature: Signature,
+}
+
+impl CheckpointSignature {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(fvm_ipld_encoding::to_vec(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(fvm_ipld_encoding::from_slice(bytes)?)
+    }
+
+    /// Checks the signature of the checkpoint with data `payload` by all the signers.
+    pub fn verify(&self, payload: &[u8]) -> Result<()> {
+        match self.signers.as_slice() {
+            [] => Err(anyhow!("checkpoint signature without signers")),
+            [signer] => self
+                .signature
+                .verify(&signed_message(payload, signer), signer)
+                .map_err(|e| anyhow!("invalid checkpoint signature of {signer:}: {e:}")),
+            signers => {
+                let messages = signers
+                    .iter()
+                    .map(|s| signed_message(payload, s))
+                    .collect::<Vec<_>>();
+                let keys = signers
+                    .iter()
+                    .map(|s| match s.payload() {
+                        Payload::BLS(key) => Ok(key.as_slice()),
+                        _ => Err(anyhow!("signer {s:} of an aggregate is not a BLS address")),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let messages = messages.iter().map(Vec::as_slice).collect::<Vec<_>>();
+                if !verify_bls_aggregate(&messages, &keys, &self.signature) {
+                    return Err(anyhow!("invalid aggregated checkpoint signature"));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the data of `checkpoint` signed by the validators, i.e. the checkpoint without its
+/// signature.
+pub fn checkpoint_payload(checkpoint: &NativeBottomUpCheckpoint) -> Result<Vec<u8>> {
+    let checkpoint = BottomUpCheckpoint::try_from(checkpoint)?;
+    Ok(fvm_ipld_encoding::to_vec(&checkpoint.data)?)
+}
+
+/// Signs the checkpoint with data `payload` with the keys of `signers`, aggregating their
+/// signatures if there are several.
+pub fn sign_checkpoint(
+    signer: &dyn CheckpointSigner,
+    signers: &[Address],
+    payload: &[u8],
+) -> Result<CheckpointSignature> {
+    let signatures = signers
+        .iter()
+        .map(|s| signer.sign(s, &signed_message(payload, s)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let signature = match signatures.as_slice() {
+        [] => return Err(anyhow!("no signer for the checkpoint")),
+        [signature] => signature.clone(),
+        _ => {
+            if let Some(i) = signatures
+                .iter()
+                .position(|s| s.sig_type != SignatureType::BLS)
+            {
+                return Err(anyhow!(
+                    "cannot aggregate the checkpoint signature of {}, not a BLS key",
+                    signers[i]
+                ));
+            }
+            ipc_identity::aggregate_bls(&signatures)?
+        }
+    };
+    Ok(CheckpointSignature {
+        signers: signers.to_vec(),
+        signature,
+    })
+}
+
+/// The message signed by `signer` for the checkpoint with data `payload`.
+fn signed_message(payload: &[u8], signer: &Address) -> Vec<u8> {
+    let mut message = payload.to_vec();
+    message.extend(signer.to_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use fvm_shared::address::Address;
+    use fvm_shared::crypto::signature::SignatureType;
+    use ipc_identity::{generate, Key, KeyInfo, KeyStore, KeyStoreConfig, Wallet};
+
+    use crate::manager::signer::{sign_checkpoint, CheckpointSignature, WalletSigner};
+
+    fn new_key(key_type: SignatureType) -> Key {
+        Key::try_from(KeyInfo::new(key_type, generate(key_type).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_sign_checkpoint() {
+        let keys = vec![
+            new_key(SignatureType::BLS),
+            new_key(SignatureType::BLS),
+            new_key(SignatureType::Secp256k1),
+        ];
+        let addrs = keys.iter().map(|k| k.address).collect::<Vec<_>>();
+        let wallet = Wallet::new_from_keys(KeyStore::new(KeyStoreConfig::Memory).unwrap(), keys);
+        let signer = WalletSigner::new(Arc::new(RwLock::new(wallet)));
+        let payload = b"checkpoint";
+
+        // a single validator, with either key type
+        for addr in addrs.iter() {
+            let signature = sign_checkpoint(&signer, &[*addr], payload).unwrap();
+            signature.verify(payload).unwrap();
+            assert!(signature.verify(b"other checkpoint").is_err());
+        }
+
+        // the BLS validators, aggregated and serialized in the checkpoint
+        let signature = sign_checkpoint(&signer, &addrs[..2], payload).unwrap();
+        let bytes = signature.to_bytes().unwrap();
+        let decoded = CheckpointSignature::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, signature);
+        decoded.verify(payload).unwrap();
+        let forged = CheckpointSignature {
+            signers: vec![addrs[0], addrs[0]],
+            ..decoded
+        };
+        assert!(forged.verify(payload).is_err());
+
+        assert!(sign_checkpoint(&signer, &addrs, payload).is_err());
+        assert!(sign_checkpoint(&signer, &[Address::new_id(1001)], payload).is_err());
+        assert!(sign_checkpoint(&signer, &[], payload).is_err());
+    }
+}
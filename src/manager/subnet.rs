@@ -2,18 +2,26 @@
 // SPDX-License-Identifier: MIT
 ///! IPC node-specific traits.
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::checkpoint::NativeBottomUpCheckpoint;
 use anyhow::Result;
 use async_trait::async_trait;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
+use ipc_gateway::checkpoint::BatchCrossMsgs;
+use ipc_gateway::TopDownCheckpoint;
 use ipc_sdk::cross::CrossMsg;
 use ipc_sdk::subnet_id::SubnetID;
 use ipc_subnet_actor::ConstructParams;
 
-use crate::lotus::message::ipc::QueryValidatorSetResponse;
 use crate::lotus::message::ipc::SubnetInfo;
+use crate::lotus::message::ipc::{
+    FederatedValidator, JoinRequirements, PermissionMode, QueryValidatorSetResponse,
+    SubnetCreationCost, ValidatorSet,
+};
+use crate::manager::activity::{ExecutedMessage, PendingMessage, ReplacementGas};
+use crate::manager::offline::{OfflineCall, OfflineTx};
 
 /// Trait to interact with a subnet and handle its lifecycle.
 #[async_trait]
@@ -24,6 +32,14 @@ pub trait SubnetManager: Send + Sync {
     /// subet ID can be inferred.
     async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address>;
 
+    /// Estimates what creating a subnet actor with `params` from `from` costs, without creating
+    /// it. Fails if the gas cannot be estimated, e.g. when `from` has no funds.
+    async fn estimate_create_subnet(
+        &self,
+        from: Address,
+        params: ConstructParams,
+    ) -> Result<SubnetCreationCost>;
+
     /// Performs the call to join a subnet from a wallet address and staking an amount
     /// of collateral. This function, as well as all of the ones on this trait, can infer
     /// the specific subnet and actors on which to perform the relevant calls from the
@@ -73,13 +89,15 @@ pub trait SubnetManager: Send + Sync {
 
     /// Propagate a cross-net message forward. For `postbox_msg_key`, we are using bytes because different
     /// runtime have different representations. For FVM, it should be `CID` as bytes. For EVM, it is
-    /// `bytes32`.
+    /// `bytes32`. The `fee` is attached to the propagation on top of the fee the message carries,
+    /// to propagate messages whose fee is insufficient, zero if none.
     async fn propagate(
         &self,
         subnet: SubnetID,
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
     ) -> Result<()>;
 
     async fn send_cross_message(
@@ -108,6 +126,15 @@ pub trait SubnetManager: Send + Sync {
     /// Send value between two addresses in a subnet
     async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()>;
 
+    /// Calls `drip(address)` on the faucet contract at `faucet` with `from`, for the contract to
+    /// send test funds to `to`.
+    async fn faucet_drip(
+        &self,
+        faucet: ethers::types::Address,
+        from: Address,
+        to: Address,
+    ) -> Result<()>;
+
     /// Get the balance of an address
     async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount>;
 
@@ -122,10 +149,200 @@ pub trait SubnetManager: Send + Sync {
         to_epoch: ChainEpoch,
     ) -> Result<Vec<NativeBottomUpCheckpoint>>;
 
+    /// Returns the bottom-up checkpoint period configured in the gateway.
+    async fn bottom_up_check_period(&self, gateway_addr: &Address) -> Result<ChainEpoch>;
+
+    /// Returns the top-down checkpoint period configured in the gateway.
+    async fn top_down_check_period(&self, gateway_addr: &Address) -> Result<ChainEpoch>;
+
+    /// Returns the fee the gateway charges for every cross-net message it sends or propagates.
+    async fn cross_msg_fee(&self, gateway_addr: &Address) -> Result<TokenAmount>;
+
+    /// Returns the epoch of the current chain head of the subnet.
+    async fn chain_head_epoch(&self) -> Result<ChainEpoch>;
+
+    /// Returns the unix timestamp, in seconds, of the current chain head of the subnet.
+    async fn chain_head_timestamp(&self) -> Result<u64>;
+
+    /// Returns the unix timestamp, in seconds, of the block at `epoch`, or of the first one after
+    /// it if the epoch was a null round.
+    async fn epoch_timestamp(&self, epoch: ChainEpoch) -> Result<u64>;
+
+    /// Measures the average block time of the subnet over the last `sample` epochs.
+    async fn block_time(&self, sample: ChainEpoch) -> Result<Duration>;
+
+    /// Returns the bottom-up cross messages accumulated in the gateway for the next checkpoint
+    /// window that has not been cut yet, together with the epoch of that checkpoint.
+    async fn pending_bottom_up_msgs(
+        &self,
+        gateway_addr: &Address,
+    ) -> Result<(ChainEpoch, BatchCrossMsgs)>;
+
+    /// Returns the bottom-up checkpoint template cut by the gateway at `epoch`, without the proof
+    /// and the previous checkpoint that are filled in when submitting it.
+    async fn bottom_up_checkpoint_template(
+        &self,
+        gateway_addr: &Address,
+        epoch: ChainEpoch,
+    ) -> Result<NativeBottomUpCheckpoint>;
+
     /// Returns the validator set
     async fn get_validator_set(
         &self,
         subnet_id: &SubnetID,
         gateway: Option<Address>,
     ) -> Result<QueryValidatorSetResponse>;
+
+    /// Builds the unsigned transaction of `call` sent by `from`, with its nonce and gas already
+    /// filled in so that it can be signed offline. The encoding of the transaction is the payload
+    /// expected by [`OfflineTx`].
+    async fn build_unsigned_tx(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<Vec<u8>>;
+
+    /// Broadcasts a transaction signed offline and returns its id.
+    async fn send_signed_tx(&self, tx: &OfflineTx) -> Result<String>;
+
+    /// Estimates the maximum fee paid for the gas of `call` sent by `from`.
+    async fn estimate_fee(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        call: OfflineCall,
+    ) -> Result<TokenAmount>;
+
+    /// Returns the collateral and validator requirements to join the subnet, read from its
+    /// subnet actor in the parent.
+    async fn join_requirements(&self, subnet: &SubnetID) -> Result<JoinRequirements>;
+
+    /// Returns how validators are admitted in the subnet.
+    async fn permission_mode(&self, subnet: &SubnetID) -> Result<PermissionMode>;
+
+    /// Sets the public keys and power of validators in a federated subnet. Only the subnet owner
+    /// can set them, validators not included keep their current power.
+    async fn set_federated_power(
+        &self,
+        subnet: &SubnetID,
+        from: Address,
+        validators: Vec<FederatedValidator>,
+    ) -> Result<()>;
+
+    /// Returns the circulating supply of the child `subnet` as tracked by the gateway, at `epoch`
+    /// or at the chain head if not set.
+    async fn circulating_supply(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        epoch: Option<ChainEpoch>,
+    ) -> Result<TokenAmount>;
+
+    /// Returns the balance of `address` at `epoch`, or at the chain head if not set.
+    async fn balance_at(&self, address: &Address, epoch: Option<ChainEpoch>)
+        -> Result<TokenAmount>;
+
+    /// Returns the nonce of the top-down messages applied by the gateway of the subnet.
+    async fn applied_top_down_nonce(&self, gateway_addr: &Address) -> Result<u64>;
+
+    /// Returns the nonce of the bottom-up messages of the child `subnet` applied by the gateway of
+    /// the subnet.
+    async fn applied_bottom_up_nonce(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+    ) -> Result<u64>;
+
+    /// Returns the top-down messages to the child `subnet` from `nonce` on, at the chain head.
+    async fn top_down_msgs_from(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        nonce: u64,
+    ) -> Result<Vec<CrossMsg>>;
+
+    /// Returns the top-down messages to the child `subnet` from `nonce` on, as of `epoch`.
+    async fn top_down_msgs_at(
+        &self,
+        gateway_addr: &Address,
+        subnet: &SubnetID,
+        nonce: u64,
+        epoch: ChainEpoch,
+    ) -> Result<Vec<CrossMsg>>;
+
+    /// Returns the addresses of the subnet actors created by `owner` in the subnet.
+    async fn subnets_created_by(&self, owner: &Address) -> Result<Vec<Address>>;
+
+    /// Returns the ids of the messages sent or received by `account` in the subnet since
+    /// `from_epoch`, i.e. the cids of the fvm messages or the hashes of the evm transactions.
+    async fn list_account_messages(
+        &self,
+        account: &Address,
+        from_epoch: ChainEpoch,
+    ) -> Result<Vec<String>>;
+
+    /// Returns the executed message of id `id`, as returned by `list_account_messages` or
+    /// recorded in the key usage log.
+    async fn get_executed_message(&self, id: &str) -> Result<ExecutedMessage>;
+
+    /// Returns the message of id `id`, with its receipt if it is executed, and the IPC method it
+    /// calls if it is sent to the gateway or to a subnet actor.
+    async fn lookup_message(&self, id: &str) -> Result<MessageLookup>;
+
+    /// Returns the messages pending in the memory pool of the node, only those sent by `from` if
+    /// set.
+    async fn pending_messages(&self, from: Option<Address>) -> Result<Vec<PendingMessage>>;
+
+    /// Replaces the message of `from` with nonce `nonce` pending in the memory pool with the same
+    /// message paying `gas`, or with an empty send from `from` to itself if `cancel`, which also
+    /// fills a gap in the nonces of `from`. Returns the id of the replacement.
+    async fn replace_message(
+        &self,
+        from: Address,
+        nonce: u64,
+        gas: ReplacementGas,
+        cancel: bool,
+    ) -> Result<String>;
+
+    /// Returns the membership the consensus of the subnet currently runs with, as set in its
+    /// gateway from the top-down validator changes.
+    async fn consensus_membership(&self, gateway_addr: &Address) -> Result<ValidatorSet>;
+
+    /// Checks that the node serves the network the subnet is pinned to: the network name set in
+    /// the config for fvm subnets, the chain id for fevm ones.
+    async fn ensure_network(&self) -> Result<()>;
+
+    /// Votes the top-down checkpoint in the gateway of the subnet as the validator `from`.
+    /// Returns the epoch the vote was executed at.
+    async fn vote_top_down_checkpoint(
+        &self,
+        gateway_addr: &Address,
+        from: Address,
+        checkpoint: TopDownCheckpoint,
+    ) -> Result<ChainEpoch>;
+
+    /// Returns the epoch of the bottom-up checkpoint of `subnet` currently open in its subnet
+    /// actor, the one after the last executed, and whether `validator` voted it.
+    async fn bottom_up_vote(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<(ChainEpoch, bool)>;
+
+    /// Returns the epoch of the top-down checkpoint currently open in the gateway of the subnet,
+    /// the one after the last executed, and whether `validator` voted it.
+    async fn top_down_vote(
+        &self,
+        gateway_addr: &Address,
+        validator: &Address,
+    ) -> Result<(ChainEpoch, bool)>;
+
+    /// Returns the rewards of `validator` accumulated in the subnet actor of `subnet` and not
+    /// withdrawn yet, `None` if the subnet actor pays the rewards out as they are distributed.
+    async fn pending_rewards(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<Option<TokenAmount>>;
 }
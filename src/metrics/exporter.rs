@@ -0,0 +1,328 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The prometheus exporter of the metrics of the agent.
+//!
+//! When `[server.metrics]` is set in the config, the daemon serves at `/metrics`, on an address of
+//! its own, the persisted counters of the agent along with the metrics of the current process:
+//! the json rpc requests served by method, the latency of the requests to the nodes, the
+//! checkpoint submissions and the time the checkpoints take to reach their quorum by subnet, and
+//! the balances of the accounts of the subnets, refreshed in the background as querying them on
+//! every scrape would load the nodes.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+use tokio::sync::Notify;
+use tokio::time::sleep;
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+use warp::http::StatusCode;
+use warp::Filter;
+
+use crate::config::{MetricsConfig, ReloadableConfig, METRICS_ENDPOINT};
+use crate::metrics::Counters;
+use crate::server::subnet::SubnetManagerPool;
+use crate::state::AgentState;
+
+/// The prefix of the names of the metrics exported.
+const METRICS_PREFIX: &str = "ipc_agent";
+/// The buckets, in seconds, of the latency of the requests to the nodes, up to the time it takes
+/// a node to execute a message.
+const NODE_LATENCY_BUCKETS: [f64; 11] =
+    [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+/// The buckets, in epochs, of the time the checkpoints take to reach their quorum.
+const QUORUM_EPOCHS_BUCKETS: [f64; 9] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0];
+/// The label of the json rpc requests to a method that is not served, not to create a series for
+/// every name a client sends.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// The metrics of the current process, exported along with the persisted counters.
+pub struct Metrics {
+    registry: Registry,
+    json_rpc_requests: IntCounterVec,
+    node_request_duration: HistogramVec,
+    checkpoint_submissions: IntCounterVec,
+    time_to_quorum: HistogramVec,
+    wallet_balance: GaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some(String::from(METRICS_PREFIX)), None).unwrap();
+        Self {
+            json_rpc_requests: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new("json_rpc_requests_total", "JSON-RPC requests served"),
+                    &["method", "outcome"],
+                )
+                .unwrap(),
+            ),
+            node_request_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "node_request_duration_seconds",
+                        "Latency of the JSON-RPC requests to the nodes, retries included",
+                    )
+                    .buckets(NODE_LATENCY_BUCKETS.to_vec()),
+                    &["method", "outcome"],
+                )
+                .unwrap(),
+            ),
+            checkpoint_submissions: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "checkpoint_submissions_total",
+                        "Checkpoint votes submitted by the validators of the agent",
+                    ),
+                    &["subnet", "direction", "outcome"],
+                )
+                .unwrap(),
+            ),
+            time_to_quorum: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "checkpoint_time_to_quorum_epochs",
+                        "Epochs past a checkpoint when it was seen executed with the votes of its quorum",
+                    )
+                    .buckets(QUORUM_EPOCHS_BUCKETS.to_vec()),
+                    &["subnet", "direction"],
+                )
+                .unwrap(),
+            ),
+            wallet_balance: register(
+                &registry,
+                GaugeVec::new(
+                    Opts::new(
+                        "wallet_balance_fil",
+                        "Balance of the accounts of the subnets of the config, in FIL",
+                    ),
+                    &["subnet", "address"],
+                )
+                .unwrap(),
+            ),
+            registry,
+        }
+    }
+
+    /// Counts a json rpc request to `method`, `None` if the method is not served by the agent.
+    pub fn observe_json_rpc_request(&self, method: Option<&str>, ok: bool) {
+        self.json_rpc_requests
+            .with_label_values(&[method.unwrap_or(UNKNOWN_METHOD), outcome(ok)])
+            .inc();
+    }
+
+    pub fn observe_node_request(&self, method: &str, elapsed: Duration, ok: bool) {
+        self.node_request_duration
+            .with_label_values(&[method, outcome(ok)])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Counts a checkpoint vote of the child `subnet` submitted in `direction`.
+    pub fn observe_checkpoint_submission(&self, subnet: &SubnetID, direction: &str, ok: bool) {
+        self.checkpoint_submissions
+            .with_label_values(&[&subnet.to_string(), direction, outcome(ok)])
+            .inc();
+    }
+
+    /// Observes the time a checkpoint of the child `subnet` in `direction` took to reach its
+    /// quorum.
+    pub fn observe_time_to_quorum(&self, subnet: &SubnetID, direction: &str, epochs: ChainEpoch) {
+        self.time_to_quorum
+            .with_label_values(&[&subnet.to_string(), direction])
+            .observe(epochs as f64);
+    }
+
+    /// Renders all the metrics in the prometheus text format, along with `counters`.
+    pub fn render(&self, counters: &Counters) -> Result<String> {
+        let mut families = self.registry.gather();
+
+        // the persisted counters keep their own values, they are only copied at every scrape
+        let exported_counters = Registry::new_custom(Some(String::from(METRICS_PREFIX)), None)?;
+        for counter in counters.all() {
+            let name = counter.name();
+            let exported = IntCounter::new(
+                format!("{name:}_total"),
+                format!("{} since the agent was deployed", name.replace('_', " ")),
+            )?;
+            exported.inc_by(counter.get());
+            exported_counters.register(Box::new(exported))?;
+        }
+        families.extend(exported_counters.gather());
+
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&families, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn register<C: prometheus::core::Collector + Clone + 'static>(
+    registry: &Registry,
+    collector: C,
+) -> C {
+    registry.register(Box::new(collector.clone())).unwrap();
+    collector
+}
+
+fn outcome(ok: bool) -> &'static str {
+    if ok {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+/// Serves the metrics and refreshes the balances of the accounts of the subnets until the agent
+/// shuts down.
+pub struct MetricsExporter {
+    config: MetricsConfig,
+    pool: SubnetManagerPool,
+}
+
+impl MetricsExporter {
+    pub fn new(
+        config: MetricsConfig,
+        reload_config: Arc<ReloadableConfig>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
+    ) -> Self {
+        Self {
+            config,
+            pool: SubnetManagerPool::new(reload_config, fvm_wallet, evm_keystore, state),
+        }
+    }
+
+    /// Queries the balances of the accounts of the subnets, the series of the accounts that are
+    /// not in the config anymore, or whose subnet cannot be reached, are dropped.
+    async fn refresh_balances(&self) {
+        let mut balances = vec![];
+        for (id, subnet) in self.pool.subnets() {
+            let conn = match self.pool.connect(&subnet) {
+                Some(conn) => conn,
+                None => continue,
+            };
+            for account in subnet.accounts() {
+                let balance = match conn.manager().wallet_balance(&account).await {
+                    Ok(balance) => balance,
+                    Err(e) => {
+                        tracing::warn!("cannot get the balance of {account:} in {id:}: {e:}");
+                        continue;
+                    }
+                };
+                match balance.to_string().parse::<f64>() {
+                    Ok(value) => balances.push((id.to_string(), account.to_string(), value)),
+                    Err(e) => tracing::warn!("invalid balance {balance:} of {account:}: {e:}"),
+                }
+            }
+        }
+
+        let wallet_balance = &self.pool.state().metrics.wallet_balance;
+        wallet_balance.reset();
+        for (subnet, account, value) in balances {
+            wallet_balance
+                .with_label_values(&[&subnet, &account])
+                .set(value);
+        }
+    }
+}
+
+#[async_trait]
+impl IntoSubsystem<anyhow::Error> for MetricsExporter {
+    async fn run(self, subsys: SubsystemHandle) -> Result<()> {
+        tracing::info!(
+            "serving prometheus metrics at {}/{METRICS_ENDPOINT:}",
+            self.config.listen_address
+        );
+
+        let state = self.pool.state().clone();
+        let route = warp::get()
+            .and(warp::path(METRICS_ENDPOINT))
+            .and(warp::path::end())
+            .map(move || match state.metrics.render(&state.counters) {
+                Ok(body) => warp::reply::with_status(body, StatusCode::OK),
+                Err(e) => {
+                    tracing::error!("cannot render the metrics: {e:}");
+                    warp::reply::with_status(String::new(), StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            })
+            .map(|reply| {
+                warp::reply::with_header(reply, "content-type", TextEncoder::new().format_type())
+            });
+        let shutdown = Arc::new(Notify::new());
+        let (_, server) =
+            warp::serve(route).bind_with_graceful_shutdown(self.config.listen_address, {
+                let shutdown = shutdown.clone();
+                async move { shutdown.notified().await }
+            });
+        let server_handle = tokio::spawn(server);
+
+        loop {
+            self.refresh_balances().await;
+            tokio::select! {
+                _ = sleep(self.config.balance_poll_interval()) => {}
+                _ = subsys.on_shutdown_requested() => {
+                    tracing::info!("Shutting down metrics exporter");
+                    shutdown.notify_waiters();
+                    server_handle.await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::metrics::exporter::Metrics;
+    use crate::metrics::Counters;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.observe_json_rpc_request(Some("ipc_fund"), true);
+        metrics.observe_json_rpc_request(None, false);
+        metrics.observe_node_request("Test.Latency", Duration::from_millis(20), true);
+        metrics.observe_checkpoint_submission(&SubnetID::new_root(123), "bottom-up", false);
+        metrics.observe_time_to_quorum(&SubnetID::new_root(123), "top-down", 7);
+        let counters = Counters::new();
+        counters.checkpoints_skipped.inc();
+
+        let rendered = metrics.render(&counters).unwrap();
+        assert!(rendered
+            .contains(r#"ipc_agent_json_rpc_requests_total{method="ipc_fund",outcome="ok"}"#));
+        assert!(rendered
+            .contains(r#"ipc_agent_json_rpc_requests_total{method="unknown",outcome="error"}"#));
+        assert!(rendered.contains(
+            r#"ipc_agent_node_request_duration_seconds_bucket{method="Test.Latency",outcome="ok",le="0.05"} 1"#
+        ));
+        assert!(rendered.contains(
+            r#"ipc_agent_checkpoint_submissions_total{direction="bottom-up",outcome="error",subnet="/r123"}"#
+        ));
+        assert!(rendered.contains(
+            r#"ipc_agent_checkpoint_time_to_quorum_epochs_bucket{direction="top-down",subnet="/r123",le="10"} 1"#
+        ));
+        assert!(rendered.contains("# TYPE ipc_agent_checkpoints_skipped_total counter"));
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Agent metrics.
+//!
+//! Counters are monotonic for the whole lifetime of the agent deployment, not only of the current
+//! process: their values are periodically persisted in the data dir and restored at startup, so
+//! that rates computed over them don't see resets on every restart. All the metrics can be scraped
+//! by prometheus, see [`MetricsExporter`].
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub use exporter::{Metrics, MetricsExporter};
+pub use persistence::MetricsSubsystem;
+
+mod exporter;
+mod persistence;
+
+/// A monotonic counter.
+pub struct Counter {
+    name: &'static str,
+    value: AtomicU64,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, v: u64) {
+        self.value.fetch_add(v, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+/// The counters of the agent.
+pub struct Counters {
+    /// Number of checkpoints successfully submitted by the agent.
+    pub checkpoints_submitted: Counter,
+    /// Number of checkpoint submissions skipped because their epoch window was closed on chain.
+    pub checkpoints_skipped: Counter,
+    /// Number of checkpoint iterations cancelled by the watchdog for not completing in time.
+    pub checkpoint_task_restarts: Counter,
+    /// Number of messages pushed by the agent to the subnets.
+    pub messages_sent: Counter,
+    /// Number of checkpoint rounds of the managers that failed.
+    pub checkpoint_errors: Counter,
+}
+
+impl Counters {
+    pub const fn new() -> Self {
+        Self {
+            checkpoints_submitted: Counter::new("checkpoints_submitted"),
+            checkpoints_skipped: Counter::new("checkpoints_skipped"),
+            checkpoint_task_restarts: Counter::new("checkpoint_task_restarts"),
+            messages_sent: Counter::new("messages_sent"),
+            checkpoint_errors: Counter::new("checkpoint_errors"),
+        }
+    }
+
+    /// All the counters, in the order they are persisted and exported.
+    pub fn all(&self) -> [&Counter; 5] {
+        [
+            &self.checkpoints_submitted,
+            &self.checkpoints_skipped,
+            &self.checkpoint_task_restarts,
+            &self.messages_sent,
+            &self.checkpoint_errors,
+        ]
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Persistence of the counters across restarts.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::time::sleep;
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+
+use crate::metrics::Counter;
+use crate::persistence::DataDir;
+use crate::state::AgentState;
+
+/// How often the counters are flushed to disk.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Restores the counters persisted in `path` at startup and keeps flushing them to it until the
+/// agent shuts down.
+pub struct MetricsSubsystem {
+    path: PathBuf,
+    /// The state holding the counters.
+    state: Arc<AgentState>,
+}
+
+impl MetricsSubsystem {
+    pub fn new(data_dir: &DataDir, state: Arc<AgentState>) -> Self {
+        Self {
+            path: data_dir.metrics(),
+            state,
+        }
+    }
+}
+
+#[async_trait]
+impl IntoSubsystem<anyhow::Error> for MetricsSubsystem {
+    async fn run(self, subsys: SubsystemHandle) -> Result<()> {
+        let counters = self.state.counters.all();
+        // the metrics are not worth keeping the agent down for, they restart from zero and the
+        // unreadable file is replaced at the next flush
+        if let Err(e) = restore(&self.path, &counters).await {
+            tracing::warn!(
+                "cannot restore the metrics from {:?}, starting from zero: {e:}",
+                self.path
+            );
+        }
+
+        loop {
+            tokio::select! {
+                _ = sleep(FLUSH_INTERVAL) => {
+                    if let Err(e) = flush(&self.path, &counters).await {
+                        tracing::warn!("cannot persist metrics in {:?}: {e:}", self.path);
+                    }
+                }
+                _ = subsys.on_shutdown_requested() => {
+                    tracing::info!("Shutting down metrics subsystem");
+                    return flush(&self.path, &counters).await;
+                }
+            }
+        }
+    }
+}
+
+/// Adds the values persisted in `path` to the counters. Counters not present in the file, or a
+/// missing file, are left untouched.
+async fn restore(path: &Path, counters: &[&Counter]) -> Result<()> {
+    if !path.exists() {
+        tracing::info!("no persisted metrics found in {path:?}");
+        return Ok(());
+    }
+
+    let content = tokio::fs::read_to_string(path).await?;
+    let values: HashMap<String, u64> = serde_json::from_str(&content)?;
+    for counter in counters {
+        if let Some(v) = values.get(counter.name()) {
+            counter.inc_by(*v);
+        }
+    }
+    tracing::info!("restored metrics from {path:?}: {values:?}");
+
+    Ok(())
+}
+
+/// Writes the current value of the counters to `path`. The file is replaced atomically so that a
+/// crash while flushing never leaves a truncated file behind.
+async fn flush(path: &Path, counters: &[&Counter]) -> Result<()> {
+    let values = counters
+        .iter()
+        .map(|c| (c.name().to_string(), c.get()))
+        .collect::<HashMap<_, _>>();
+
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec(&values)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::persistence::{flush, restore};
+    use crate::metrics::Counter;
+
+    #[tokio::test]
+    async fn test_counters_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+
+        let counter = Counter::new("test");
+        counter.inc_by(10);
+        flush(&path, &[&counter]).await.unwrap();
+
+        let restarted = Counter::new("test");
+        restarted.inc();
+        restore(&path, &[&restarted]).await.unwrap();
+        assert_eq!(restarted.get(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_restore_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = Counter::new("test");
+
+        restore(&dir.path().join("metrics.json"), &[&counter])
+            .await
+            .unwrap();
+        assert_eq!(counter.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_restore_corrupted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        std::fs::write(&path, "{\"test\": ").unwrap();
+        let counter = Counter::new("test");
+
+        // the subsystem starts from zero
+        assert!(restore(&path, &[&counter]).await.is_err());
+        assert_eq!(counter.get(), 0);
+    }
+}
@@ -0,0 +1,449 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Notifications of the events an operator needs to act upon.
+//!
+//! Notifications are always logged, and posted as json to the webhook set in the config, if any,
+//! so that an operator can be alerted without watching the logs of the agent.
+//!
+//! The notifications of chain events, e.g. a committed checkpoint, are delivered once per event:
+//! their deliveries are keyed on the subnet, epoch, kind and cid of the event and persisted in the
+//! data dir, so that an event seen again, in a later poll or after a restart, is not notified
+//! twice. A delivery is only attempted again when an operator asks for it.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config::NotificationsConfig;
+
+/// The time allowed to the webhook to accept a notification.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// The number of deliveries kept, the oldest ones are dropped beyond it.
+const MAX_DELIVERIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A validator cannot vote checkpoints, its balance does not cover the fees.
+    InsufficientFunds,
+    /// A validator that could not vote for lack of funds voted again.
+    FundsRecovered,
+    /// A bottom-up checkpoint of the subnet was committed in its parent.
+    BottomUpCommitted,
+    /// A top-down checkpoint of the subnet was committed in it.
+    TopDownCommitted,
+    /// A metric of the agent crossed the threshold of an alert rule of the config.
+    AlertFiring,
+    /// A metric of a firing alert is back within its threshold.
+    AlertResolved,
+}
+
+impl Display for NotificationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationKind::InsufficientFunds => write!(f, "insufficient funds"),
+            NotificationKind::FundsRecovered => write!(f, "funds recovered"),
+            NotificationKind::BottomUpCommitted => write!(f, "bottom-up checkpoint committed"),
+            NotificationKind::TopDownCommitted => write!(f, "top-down checkpoint committed"),
+            NotificationKind::AlertFiring => write!(f, "alert firing"),
+            NotificationKind::AlertResolved => write!(f, "alert resolved"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    /// The subnet the event happened in, empty for the events of the agent as a whole.
+    pub subnet: String,
+    pub message: String,
+    /// The unix timestamp, in seconds, of the event.
+    pub timestamp: u64,
+    /// The epoch of the chain event notified, not set for the events of the agent itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<ChainEpoch>,
+    /// The cid of the chain object of the event, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<String>,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, subnet: &SubnetID, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            subnet: subnet.to_string(),
+            message: message.into(),
+            timestamp: now(),
+            epoch: None,
+            cid: None,
+        }
+    }
+
+    /// A notification of the agent as a whole, not of one of its subnets.
+    pub fn of_agent(kind: NotificationKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            subnet: String::new(),
+            message: message.into(),
+            timestamp: now(),
+            epoch: None,
+            cid: None,
+        }
+    }
+
+    /// Makes the notification the one of a chain event at `epoch`, delivered once.
+    pub fn chain_event(mut self, epoch: ChainEpoch, cid: Option<String>) -> Self {
+        self.epoch = Some(epoch);
+        self.cid = cid;
+        self
+    }
+
+    /// The key the notification is delivered once for, if it is the one of a chain event.
+    fn event_key(&self) -> Option<EventKey> {
+        self.epoch.map(|epoch| EventKey {
+            subnet: self.subnet.clone(),
+            epoch,
+            kind: self.kind,
+            cid: self.cid.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct EventKey {
+    subnet: String,
+    epoch: ChainEpoch,
+    kind: NotificationKind,
+    cid: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Being posted to the webhook, or the agent stopped while posting it.
+    Pending,
+    /// Accepted by the webhook, or logged if none is set.
+    Delivered,
+    Failed,
+}
+
+/// The delivery of the notification of a chain event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub notification: Notification,
+    pub status: DeliveryStatus,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub attempts: u32,
+}
+
+#[derive(Default)]
+struct Deliveries {
+    deliveries: BTreeMap<EventKey, Delivery>,
+    /// The file the deliveries are persisted to, not persisted if not set.
+    path: Option<PathBuf>,
+}
+
+impl Deliveries {
+    /// Writes the deliveries to their file, replaced atomically as the jobs are.
+    fn persist(&self) {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return,
+        };
+        let r: Result<()> = try {
+            let tmp = path.with_extension("json.tmp");
+            let deliveries = self.deliveries.values().collect::<Vec<_>>();
+            std::fs::write(&tmp, serde_json::to_vec(&deliveries)?)?;
+            std::fs::rename(&tmp, path)?;
+        };
+        if let Err(e) = r {
+            tracing::warn!("cannot persist notification deliveries in {path:?}: {e:}");
+        }
+    }
+
+    /// Drops the oldest deliveries beyond `MAX_DELIVERIES`. Their events are long past the ones
+    /// the agent still sees.
+    fn prune(&mut self) {
+        while self.deliveries.len() > MAX_DELIVERIES {
+            let oldest = self
+                .deliveries
+                .iter()
+                .min_by_key(|(_, d)| d.notification.timestamp)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(key) => self.deliveries.remove(&key),
+                None => break,
+            };
+        }
+    }
+
+    fn update(&mut self, key: &EventKey, result: &Result<()>) {
+        if let Some(delivery) = self.deliveries.get_mut(key) {
+            match result {
+                Ok(()) => {
+                    delivery.status = DeliveryStatus::Delivered;
+                    delivery.error = None;
+                }
+                Err(e) => {
+                    delivery.status = DeliveryStatus::Failed;
+                    delivery.error = Some(e.to_string());
+                }
+            }
+            self.persist();
+        }
+    }
+}
+
+pub struct Notifier {
+    webhook: Mutex<Option<Url>>,
+    /// The deliveries, shared with the posts to the webhook in flight.
+    deliveries: Arc<Mutex<Option<Deliveries>>>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self {
+            webhook: Mutex::new(None),
+            deliveries: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Restores the deliveries persisted in `path`, and persists them there from now on.
+    pub fn restore(&self, path: &Path) -> Result<()> {
+        let persisted: Vec<Delivery> = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            vec![]
+        };
+
+        let mut deliveries = self.deliveries.lock().unwrap();
+        let deliveries = deliveries.get_or_insert_with(Deliveries::default);
+        deliveries.path = Some(path.to_path_buf());
+        for delivery in persisted {
+            if let Some(key) = delivery.notification.event_key() {
+                deliveries.deliveries.insert(key, delivery);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the deliveries of the chain events, the latest first.
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        let deliveries = self.deliveries.lock().unwrap();
+        let mut all = deliveries
+            .as_ref()
+            .map(|d| d.deliveries.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        all.sort_by(|a, b| b.notification.timestamp.cmp(&a.notification.timestamp));
+        all
+    }
+
+    /// Posts the notifications to the webhook in `config`, or only logs them if not set.
+    pub fn configure(&self, config: Option<&NotificationsConfig>) {
+        *self.webhook.lock().unwrap() = config.map(|c| c.webhook_url.clone());
+    }
+
+    /// Logs the notification and posts it to the webhook in the background, so that the caller
+    /// is not held up by a slow webhook. The notification of a chain event already delivered is
+    /// dropped.
+    pub fn notify(&self, notification: Notification) {
+        let key = notification.event_key();
+        if let Some(key) = &key {
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let deliveries = deliveries.get_or_insert_with(Deliveries::default);
+            if deliveries.deliveries.contains_key(key) {
+                tracing::debug!(
+                    "{} notification of subnet {} at epoch {} already delivered",
+                    notification.kind,
+                    notification.subnet,
+                    key.epoch
+                );
+                return;
+            }
+            deliveries.deliveries.insert(
+                key.clone(),
+                Delivery {
+                    notification: notification.clone(),
+                    status: DeliveryStatus::Pending,
+                    error: None,
+                    attempts: 0,
+                },
+            );
+            deliveries.prune();
+        }
+
+        match notification.kind {
+            NotificationKind::InsufficientFunds | NotificationKind::AlertFiring => tracing::warn!(
+                "notification in subnet {}: {}",
+                notification.subnet,
+                notification.message
+            ),
+            _ => tracing::info!(
+                "notification in subnet {}: {}",
+                notification.subnet,
+                notification.message
+            ),
+        }
+
+        self.deliver(key, notification);
+    }
+
+    /// Posts the notifications of the chain events of `subnet` between `from` and `to` again,
+    /// whatever the status of their last delivery. Returns the deliveries attempted.
+    pub fn redeliver(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<Delivery>> {
+        if self.webhook.lock().unwrap().is_none() {
+            return Err(anyhow!("no webhook set to redeliver the notifications to"));
+        }
+
+        let subnet = subnet.to_string();
+        let redelivered = {
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let deliveries = deliveries.get_or_insert_with(Deliveries::default);
+            let mut redelivered = vec![];
+            for (key, delivery) in deliveries.deliveries.iter_mut() {
+                if key.subnet == subnet && key.epoch >= from && key.epoch <= to {
+                    delivery.status = DeliveryStatus::Pending;
+                    redelivered.push((key.clone(), delivery.clone()));
+                }
+            }
+            redelivered
+        };
+
+        for (key, delivery) in redelivered.iter() {
+            tracing::info!(
+                "redelivering {} notification of subnet {} at epoch {}",
+                delivery.notification.kind,
+                subnet,
+                key.epoch
+            );
+            self.deliver(Some(key.clone()), delivery.notification.clone());
+        }
+        Ok(redelivered.into_iter().map(|(_, d)| d).collect())
+    }
+
+    /// Posts the notification to the webhook, recording the outcome in the delivery of `key`.
+    fn deliver(&self, key: Option<EventKey>, notification: Notification) {
+        let webhook = self.webhook.lock().unwrap().clone();
+        {
+            let mut deliveries = self.deliveries.lock().unwrap();
+            if let (Some(key), Some(deliveries)) = (&key, deliveries.as_mut()) {
+                if let Some(delivery) = deliveries.deliveries.get_mut(key) {
+                    delivery.attempts += 1;
+                }
+                if webhook.is_none() {
+                    deliveries.update(key, &Ok(()));
+                } else {
+                    deliveries.persist();
+                }
+            }
+        }
+
+        let webhook = match webhook {
+            Some(url) => url,
+            None => return,
+        };
+        let deliveries = self.deliveries.clone();
+        tokio::spawn(async move {
+            let r = post(webhook, &notification).await;
+            if let Err(e) = &r {
+                tracing::warn!(
+                    "cannot post {} notification of subnet {} to the webhook: {e:}",
+                    notification.kind,
+                    notification.subnet
+                );
+            }
+            if let Some(key) = key {
+                if let Some(deliveries) = deliveries.lock().unwrap().as_mut() {
+                    deliveries.update(&key, &r);
+                }
+            }
+        });
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+async fn post(webhook: Url, notification: &Notification) -> anyhow::Result<()> {
+    reqwest::Client::new()
+        .post(webhook)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(notification)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::notify::{DeliveryStatus, Notification, NotificationKind, Notifier};
+
+    fn committed(subnet: &SubnetID, epoch: i64) -> Notification {
+        Notification::new(NotificationKind::BottomUpCommitted, subnet, "committed")
+            .chain_event(epoch, None)
+    }
+
+    #[test]
+    fn test_chain_events_delivered_once() {
+        let notifier = Notifier::new();
+        let restarted = Notifier::new();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notifications.json");
+        let subnet = SubnetID::new_root(123);
+
+        notifier.restore(&path).unwrap();
+        notifier.notify(committed(&subnet, 10));
+        notifier.notify(committed(&subnet, 10));
+        notifier.notify(committed(&subnet, 20));
+        // the events of the agent itself are not tracked
+        notifier.notify(Notification::new(
+            NotificationKind::FundsRecovered,
+            &subnet,
+            "recovered",
+        ));
+
+        let deliveries = notifier.deliveries();
+        assert_eq!(deliveries.len(), 2);
+        assert!(deliveries
+            .iter()
+            .all(|d| d.status == DeliveryStatus::Delivered && d.attempts == 1));
+
+        // the events delivered before a restart are not delivered again
+        restarted.restore(&path).unwrap();
+        restarted.notify(committed(&subnet, 20));
+        restarted.notify(committed(&subnet, 30));
+        assert_eq!(restarted.deliveries().len(), 3);
+        assert!(restarted.deliveries().iter().all(|d| d.attempts == 1));
+
+        // there is nothing to redeliver to without a webhook
+        assert!(restarted.redeliver(&subnet, 0, 30).is_err());
+    }
+}
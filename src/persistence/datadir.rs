@@ -0,0 +1,273 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The data directory of the agent, where all its state is kept on disk.
+//!
+//! Everything the agent persists lives under a single root, the directory of its config unless
+//! `data_dir` is set in the config: the keystores, the identity key, the journal of the checkpoint
+//! votes, the checkpoint history, the jobs, the deliveries of the notifications and the metrics.
+//! The layout of the directory is versioned in its `version` file. The daemon migrates a
+//! directory written by a previous release to the current layout when it starts, one version at
+//! a time, and refuses a directory written by a later release, whose layout it does not know.
+//! It also refuses to start if `data_dir` was set after the keys or state of the agent were
+//! written to the directory of the config, as it would start without them otherwise.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ipc_identity::{DEFAULT_KEYSTORE_NAME, KEYSTORE_NAME};
+
+use crate::config::{Config, ReloadableConfig};
+
+/// The version of the layout of the data directory written by this release.
+pub const DATA_DIR_VERSION: u32 = 1;
+/// The name of the file holding the version of the layout of the data directory.
+pub const VERSION_FILE_NAME: &str = "version";
+/// The name of the directory where the state of the agent is persisted.
+pub const PERSISTENCE_DIR_NAME: &str = "persistence";
+/// The name of the directory where the checkpoints of the subnets are indexed.
+pub const HISTORY_DIR_NAME: &str = "history";
+pub const JOURNAL_FILE_NAME: &str = "checkpoints.jsonl";
+pub const JOBS_FILE_NAME: &str = "jobs.json";
+pub const NOTIFICATIONS_FILE_NAME: &str = "notifications.json";
+pub const METRICS_FILE_NAME: &str = "metrics.json";
+pub const IDENTITY_KEY_FILE: &str = "agent_identity.key";
+
+/// The keys and state kept at the root of the directory, which are left behind in the directory
+/// of the config when `data_dir` is set.
+const RELOCATED_STATE: [&str; 5] = [
+    KEYSTORE_NAME,
+    DEFAULT_KEYSTORE_NAME,
+    IDENTITY_KEY_FILE,
+    PERSISTENCE_DIR_NAME,
+    HISTORY_DIR_NAME,
+];
+
+/// A change of the layout of the data directory between two releases.
+struct Migration {
+    /// The version of the layout once migrated.
+    to: u32,
+    description: &'static str,
+    apply: fn(&DataDir) -> Result<()>,
+}
+
+/// The migrations of the layout, in order.
+const MIGRATIONS: [Migration; 1] = [Migration {
+    to: 1,
+    description: "move the jobs, notifications and metrics to the persistence directory",
+    apply: move_state_to_persistence,
+}];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDir {
+    root: PathBuf,
+}
+
+impl DataDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The data directory set in the config, relative to the directory of the config if not
+    /// absolute, or the directory of the config if not set.
+    pub fn from_config(config: &ReloadableConfig) -> Result<Self> {
+        let repo = config
+            .get_config_repo()
+            .ok_or_else(|| anyhow!("no repo found for the config"))?;
+        Ok(Self::in_repo(Path::new(&repo), &config.get_config()))
+    }
+
+    /// The data directory set in `config`, whose file is in the directory `repo`.
+    pub fn in_repo(repo: &Path, config: &Config) -> Self {
+        match &config.data_dir {
+            Some(dir) => Self::new(repo.join(dir)),
+            None => Self::new(repo),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The root of the keystores, which are opened from a string path.
+    pub fn keystore_repo(&self) -> Result<String> {
+        self.root
+            .to_str()
+            .map(String::from)
+            .ok_or_else(|| anyhow!("invalid data dir {:?}", self.root))
+    }
+
+    pub fn identity_key(&self) -> PathBuf {
+        self.root.join(IDENTITY_KEY_FILE)
+    }
+
+    pub fn history(&self) -> PathBuf {
+        self.root.join(HISTORY_DIR_NAME)
+    }
+
+    pub fn journal(&self) -> PathBuf {
+        self.persistence().join(JOURNAL_FILE_NAME)
+    }
+
+    pub fn jobs(&self) -> PathBuf {
+        self.persistence().join(JOBS_FILE_NAME)
+    }
+
+    pub fn notifications(&self) -> PathBuf {
+        self.persistence().join(NOTIFICATIONS_FILE_NAME)
+    }
+
+    pub fn metrics(&self) -> PathBuf {
+        self.persistence().join(METRICS_FILE_NAME)
+    }
+
+    fn persistence(&self) -> PathBuf {
+        self.root.join(PERSISTENCE_DIR_NAME)
+    }
+
+    /// The version of the layout of the directory, 0 if it was written before the layout was
+    /// versioned or is new.
+    pub fn version(&self) -> Result<u32> {
+        let path = self.root.join(VERSION_FILE_NAME);
+        if !path.exists() {
+            return Ok(0);
+        }
+        let version = std::fs::read_to_string(&path)?;
+        version
+            .trim()
+            .parse()
+            .map_err(|e| anyhow!("invalid data dir version in {path:?}: {e:}"))
+    }
+
+    /// Migrates the directory to the layout of this release. Returns the version it was at.
+    pub fn migrate(&self) -> Result<u32> {
+        std::fs::create_dir_all(&self.root)?;
+        let version = self.version()?;
+        if version > DATA_DIR_VERSION {
+            return Err(anyhow!(
+                "data dir {:?} is at version {version:}, written by a later release of the agent that supports up to {DATA_DIR_VERSION:}",
+                self.root
+            ));
+        }
+
+        for migration in MIGRATIONS.iter().filter(|m| m.to > version) {
+            tracing::info!(
+                "migrating data dir {:?} to version {}: {}",
+                self.root,
+                migration.to,
+                migration.description
+            );
+            (migration.apply)(self).map_err(|e| {
+                anyhow!(
+                    "cannot migrate data dir {:?} to version {}: {e:}",
+                    self.root,
+                    migration.to
+                )
+            })?;
+            // written after every migration, so that an interrupted run resumes from the last one
+            self.write_version(migration.to)?;
+        }
+        Ok(version)
+    }
+
+    /// Fails if keys or state of the agent exist in `previous`, the directory they were kept in
+    /// before `data_dir` was set, but not in this directory, so that they do not silently
+    /// disappear. They are not moved, as the keystores are better moved by the operator.
+    pub fn check_relocated(&self, previous: &DataDir) -> Result<()> {
+        if self == previous {
+            return Ok(());
+        }
+        let left: Vec<&str> = RELOCATED_STATE
+            .into_iter()
+            .filter(|name| previous.root.join(name).exists() && !self.root.join(name).exists())
+            .collect();
+        if left.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "data dir is set to {:?}, but {} are still in {:?}: move them to the data dir, or unset `data_dir`",
+            self.root,
+            left.join(", "),
+            previous.root
+        ))
+    }
+
+    fn write_version(&self, version: u32) -> Result<()> {
+        let path = self.root.join(VERSION_FILE_NAME);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, format!("{version:}\n"))?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// The jobs, notifications and metrics were kept at the root of the directory before version 1.
+fn move_state_to_persistence(dir: &DataDir) -> Result<()> {
+    let moves = [
+        (JOBS_FILE_NAME, dir.jobs()),
+        (NOTIFICATIONS_FILE_NAME, dir.notifications()),
+        (METRICS_FILE_NAME, dir.metrics()),
+    ];
+    std::fs::create_dir_all(dir.persistence())?;
+    for (name, to) in moves {
+        let from = dir.root.join(name);
+        if from.exists() && !to.exists() {
+            std::fs::rename(&from, &to)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ipc_identity::KEYSTORE_NAME;
+
+    use crate::persistence::datadir::{
+        DataDir, DATA_DIR_VERSION, JOBS_FILE_NAME, METRICS_FILE_NAME, VERSION_FILE_NAME,
+    };
+
+    #[test]
+    fn test_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(dir.path());
+
+        // the state of a release before the layout was versioned
+        std::fs::write(dir.path().join(JOBS_FILE_NAME), "[]").unwrap();
+        std::fs::write(dir.path().join(METRICS_FILE_NAME), "{}").unwrap();
+        assert_eq!(data_dir.version().unwrap(), 0);
+
+        assert_eq!(data_dir.migrate().unwrap(), 0);
+        assert_eq!(data_dir.version().unwrap(), DATA_DIR_VERSION);
+        assert_eq!(std::fs::read_to_string(data_dir.jobs()).unwrap(), "[]");
+        assert_eq!(std::fs::read_to_string(data_dir.metrics()).unwrap(), "{}");
+        assert!(!dir.path().join(JOBS_FILE_NAME).exists());
+        assert!(!data_dir.notifications().exists());
+
+        // migrating again does nothing
+        assert_eq!(data_dir.migrate().unwrap(), DATA_DIR_VERSION);
+
+        std::fs::write(dir.path().join(VERSION_FILE_NAME), "99").unwrap();
+        assert!(data_dir.migrate().is_err());
+    }
+
+    #[test]
+    fn test_check_relocated() {
+        let repo = tempfile::tempdir().unwrap();
+        let previous = DataDir::new(repo.path());
+        let data_dir = DataDir::new(repo.path().join("data"));
+        assert!(data_dir.check_relocated(&previous).is_ok());
+
+        // the keystore written before the data dir was set
+        std::fs::write(repo.path().join(KEYSTORE_NAME), "{}").unwrap();
+        assert!(previous.check_relocated(&previous).is_ok());
+        let err = data_dir.check_relocated(&previous).unwrap_err();
+        assert!(err.to_string().contains(KEYSTORE_NAME));
+
+        std::fs::create_dir_all(data_dir.root()).unwrap();
+        std::fs::rename(
+            repo.path().join(KEYSTORE_NAME),
+            data_dir.root().join(KEYSTORE_NAME),
+        )
+        .unwrap();
+        assert!(data_dir.check_relocated(&previous).is_ok());
+    }
+}
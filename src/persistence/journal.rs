@@ -0,0 +1,360 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Journal of the checkpoint votes submitted by the agent.
+//!
+//! A vote is recorded as `attempted` right before its message is sent, and again as `executed`,
+//! with its message, or `failed` once the submission returns. A vote whose last record is still
+//! `attempted` when the agent starts was in flight when it stopped: it is recorded as
+//! `interrupted`, and its vote is checked again on chain and in the mempool before being
+//! submitted again, as for any other checkpoint.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::checkpoint::SubmittedCheckpoint;
+use crate::persistence::DataDir;
+
+/// How long the records are kept in the journal, they are dropped when the agent starts.
+const JOURNAL_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    /// The vote is about to be sent.
+    Attempted,
+    /// The message of the vote was executed.
+    Executed,
+    /// The vote could not be sent, or its message failed.
+    Failed,
+    /// The agent stopped while the vote was being submitted.
+    Interrupted,
+    /// The vote was found pending in the mempool without being tracked, e.g. its message was
+    /// sent before the agent restarted, and it is not submitted again while it is pending.
+    Adopted,
+}
+
+/// A record of the journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionRecord {
+    /// The child subnet of the checkpoint.
+    pub subnet: String,
+    /// Either `bottom-up` or `top-down`
+    pub direction: String,
+    pub epoch: ChainEpoch,
+    pub validator: String,
+    pub status: SubmissionStatus,
+    /// The cid of the message, or the hash of the transaction, of an executed vote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// The epoch the message of an executed vote was executed at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_epoch: Option<ChainEpoch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The unix timestamp, in seconds, of the record.
+    pub timestamp: u64,
+}
+
+impl SubmissionRecord {
+    pub fn new(
+        subnet: &SubnetID,
+        direction: &str,
+        epoch: ChainEpoch,
+        validator: &Address,
+        status: SubmissionStatus,
+    ) -> Self {
+        Self {
+            subnet: subnet.to_string(),
+            direction: String::from(direction),
+            epoch,
+            validator: validator.to_string(),
+            status,
+            message: None,
+            executed_epoch: None,
+            error: None,
+            timestamp: now(),
+        }
+    }
+
+    /// The record of the same vote with the outcome of its submission.
+    pub fn outcome(&self, result: &Result<SubmittedCheckpoint>) -> Self {
+        let mut record = Self {
+            timestamp: now(),
+            ..self.clone()
+        };
+        match result {
+            Ok(submitted) => {
+                record.status = SubmissionStatus::Executed;
+                record.message = Some(submitted.message.clone());
+                record.executed_epoch = Some(submitted.epoch);
+            }
+            Err(e) => {
+                record.status = SubmissionStatus::Failed;
+                record.error = Some(format!("{e:#}"));
+            }
+        }
+        record
+    }
+
+    /// The vote the record is about, the same for all its records.
+    fn key(&self) -> (&str, &str, ChainEpoch, &str) {
+        (&self.subnet, &self.direction, self.epoch, &self.validator)
+    }
+}
+
+/// The journal of the checkpoint votes, stored as json lines in the data dir of the agent.
+#[derive(Clone)]
+pub struct CheckpointJournal {
+    path: PathBuf,
+}
+
+impl CheckpointJournal {
+    pub fn new(data_dir: &DataDir) -> Self {
+        Self {
+            path: data_dir.journal(),
+        }
+    }
+
+    /// Appends `record` to the journal.
+    pub async fn record(&self, record: &SubmissionRecord) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        // a single write of the whole line, so that the records of the managers running at the
+        // same time are not interleaved
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    /// Syncs the journal to disk, so that the records written before the agent stops survive a
+    /// crash of the host.
+    pub async fn sync(&self) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    /// Returns all the records of the journal, the oldest first.
+    pub async fn read(&self) -> Result<Vec<SubmissionRecord>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        let mut records = vec![];
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(r) => records.push(r),
+                // the last line is cut if the agent stopped while writing it
+                Err(e) => tracing::warn!("skipping invalid record of the checkpoint journal: {e:}"),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns the last record of each vote of the journal in `subnet`, or in all the subnets, the
+    /// latest first.
+    pub async fn submissions(&self, subnet: Option<&SubnetID>) -> Result<Vec<SubmissionRecord>> {
+        let subnet = subnet.map(|s| s.to_string());
+        let mut submissions = latest(&self.read().await?)
+            .into_iter()
+            .filter(|r| subnet.as_ref().map_or(true, |s| &r.subnet == s))
+            .collect::<Vec<_>>();
+        submissions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        Ok(submissions)
+    }
+
+    /// Prepares the journal for a new run of the agent: the records older than the retention are
+    /// dropped, and the votes that were being submitted when the agent stopped are recorded as
+    /// interrupted. Returns the interrupted votes.
+    pub async fn recover(&self) -> Result<Vec<SubmissionRecord>> {
+        let records = self.read().await?;
+        if records.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let (mut kept, interrupted) = recover_records(records, now());
+        kept.extend(interrupted.iter().cloned());
+
+        let mut content = vec![];
+        for record in kept.iter() {
+            serde_json::to_writer(&mut content, record)?;
+            content.push(b'\n');
+        }
+        // the journal is replaced at once, not to lose it if the agent stops while rewriting it
+        let tmp = self.path.with_extension("jsonl.tmp");
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+
+        Ok(interrupted)
+    }
+}
+
+/// Returns the last record of each vote of `records`.
+fn latest(records: &[SubmissionRecord]) -> Vec<SubmissionRecord> {
+    let mut last = HashMap::new();
+    for r in records {
+        last.insert(r.key(), r);
+    }
+    last.into_values().cloned().collect()
+}
+
+/// Returns the `records` within the retention at `now`, and the records of the votes whose last
+/// record is an attempt, as interrupted.
+fn recover_records(
+    records: Vec<SubmissionRecord>,
+    now: u64,
+) -> (Vec<SubmissionRecord>, Vec<SubmissionRecord>) {
+    let mut interrupted = latest(&records)
+        .into_iter()
+        .filter(|r| r.status == SubmissionStatus::Attempted)
+        .map(|r| SubmissionRecord {
+            status: SubmissionStatus::Interrupted,
+            timestamp: now,
+            ..r
+        })
+        .collect::<Vec<_>>();
+    interrupted.sort_by(|a, b| a.key().cmp(&b.key()));
+
+    let cut_off = now.saturating_sub(JOURNAL_RETENTION.as_secs());
+    let kept = records
+        .into_iter()
+        .filter(|r| r.timestamp >= cut_off)
+        .collect();
+    (kept, interrupted)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::anyhow;
+    use fvm_shared::address::Address;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::checkpoint::SubmittedCheckpoint;
+    use crate::persistence::journal::{
+        recover_records, CheckpointJournal, SubmissionRecord, SubmissionStatus, JOURNAL_RETENTION,
+    };
+    use crate::persistence::DataDir;
+
+    #[tokio::test]
+    async fn test_checkpoint_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = CheckpointJournal::new(&DataDir::new(dir.path()));
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let other = SubnetID::from_str("/r123/f0101").unwrap();
+        let validator = Address::new_id(1001);
+
+        let executed = SubmissionRecord::new(
+            &subnet,
+            "bottom-up",
+            10,
+            &validator,
+            SubmissionStatus::Attempted,
+        );
+        let failed = SubmissionRecord::new(
+            &subnet,
+            "top-down",
+            10,
+            &validator,
+            SubmissionStatus::Attempted,
+        );
+        let in_flight = SubmissionRecord::new(
+            &other,
+            "bottom-up",
+            20,
+            &validator,
+            SubmissionStatus::Attempted,
+        );
+        for r in [
+            executed.clone(),
+            executed.outcome(&Ok(SubmittedCheckpoint {
+                message: String::from("bafy2bzace"),
+                epoch: 12,
+            })),
+            failed.clone(),
+            failed.outcome(&Err(anyhow!("out of gas"))),
+            in_flight.clone(),
+        ] {
+            journal.record(&r).await.unwrap();
+        }
+
+        let submissions = journal.submissions(Some(&subnet)).await.unwrap();
+        assert_eq!(submissions.len(), 2);
+        let bottom_up = submissions
+            .iter()
+            .find(|r| r.direction == "bottom-up")
+            .unwrap();
+        assert_eq!(bottom_up.status, SubmissionStatus::Executed);
+        assert_eq!(bottom_up.message.as_deref(), Some("bafy2bzace"));
+        assert_eq!(bottom_up.executed_epoch, Some(12));
+        let top_down = submissions
+            .iter()
+            .find(|r| r.direction == "top-down")
+            .unwrap();
+        assert_eq!(top_down.status, SubmissionStatus::Failed);
+        assert_eq!(top_down.error.as_deref(), Some("out of gas"));
+
+        // the vote in flight is found when the agent starts again, and only once
+        let interrupted = journal.recover().await.unwrap();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].subnet, other.to_string());
+        assert_eq!(interrupted[0].status, SubmissionStatus::Interrupted);
+        assert!(journal.recover().await.unwrap().is_empty());
+        assert_eq!(journal.read().await.unwrap().len(), 6);
+        assert_eq!(journal.submissions(None).await.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_journal_retention() {
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let record = |epoch, timestamp| SubmissionRecord {
+            timestamp,
+            ..SubmissionRecord::new(
+                &subnet,
+                "bottom-up",
+                epoch,
+                &Address::new_id(1001),
+                SubmissionStatus::Attempted,
+            )
+        };
+        let now = JOURNAL_RETENTION.as_secs() + 100;
+
+        let (kept, interrupted) = recover_records(vec![record(10, 99), record(20, 100)], now);
+        assert_eq!(kept, vec![record(20, 100)]);
+        // the attempts dropped with the retention are still reported
+        assert_eq!(interrupted.len(), 2);
+        assert!(interrupted.iter().all(|r| r.timestamp == now));
+    }
+}
@@ -0,0 +1,17 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! State of the agent persisted in its repo across restarts.
+//!
+//! The [`journal`] records every checkpoint vote the agent submits, before the message is sent
+//! and once its outcome is known, so that a restart finds the votes that were in flight when the
+//! agent stopped and operators can audit what was submitted and when. Like the history, the
+//! journal is made of json lines appended to a file of the repo, which is enough for the rate at
+//! which checkpoints are voted and does not need a database to be inspected.
+//!
+//! The paths of all the state on disk are owned by the [`DataDir`], which migrates them between
+//! releases.
+
+pub mod datadir;
+pub mod journal;
+
+pub use datadir::DataDir;
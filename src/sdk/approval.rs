@@ -0,0 +1,78 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Operation approval sdk functions
+
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::JsonRpcClient;
+use crate::manager::approval::ParkedOperation;
+use crate::sdk::IpcAgentClient;
+use crate::server::approval::{
+    AbortPendingOperationParams, AbortPendingOperationResponse, ApproveOperationParams,
+    ApproveOperationResponse, ListOperationApprovalsParams,
+};
+
+impl<T: JsonRpcClient> IpcAgentClient<T> {
+    /// Returns the operations parked for approval, and those decided recently.
+    pub async fn list_operation_approvals(&self) -> anyhow::Result<Vec<ParkedOperation>> {
+        self.json_rpc_client
+            .request::<Vec<ParkedOperation>>(
+                json_rpc_methods::LIST_OPERATION_APPROVALS,
+                serde_json::to_value(ListOperationApprovalsParams::default())?,
+            )
+            .await
+    }
+
+    /// Approves, and executes, or rejects the parked operation `id`.
+    pub async fn approve_operation(
+        &self,
+        id: u64,
+        approve: bool,
+    ) -> anyhow::Result<ApproveOperationResponse> {
+        let params = ApproveOperationParams { id, approve };
+
+        self.json_rpc_client
+            .request::<ApproveOperationResponse>(
+                json_rpc_methods::APPROVE_OPERATION,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Aborts the parked operation `id` before it is approved.
+    pub async fn abort_operation(&self, id: u64) -> anyhow::Result<AbortPendingOperationResponse> {
+        let params = AbortPendingOperationParams {
+            id: Some(id),
+            ..Default::default()
+        };
+        self.abort_pending_operation(params).await
+    }
+
+    /// Abandons the message of `from` pending with `nonce` in `subnet`, so that the next message
+    /// of `from` replaces it.
+    pub async fn abandon_pending_message(
+        &self,
+        subnet: &str,
+        from: Option<String>,
+        nonce: u64,
+    ) -> anyhow::Result<AbortPendingOperationResponse> {
+        let params = AbortPendingOperationParams {
+            id: None,
+            subnet: Some(subnet.to_string()),
+            from,
+            nonce: Some(nonce),
+        };
+        self.abort_pending_operation(params).await
+    }
+
+    async fn abort_pending_operation(
+        &self,
+        params: AbortPendingOperationParams,
+    ) -> anyhow::Result<AbortPendingOperationResponse> {
+        self.json_rpc_client
+            .request::<AbortPendingOperationResponse>(
+                json_rpc_methods::ABORT_PENDING_OPERATION,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+}
@@ -2,11 +2,26 @@
 // SPDX-License-Identifier: MIT
 //! Checkpoint related sdk functions
 
+use crate::checkpoint::{CheckpointDiff, VoteApproval};
 use crate::config::json_rpc_methods;
+use crate::history::backfill::BackfillParams;
+use crate::jobs::JobInfo;
 use crate::jsonrpc::JsonRpcClient;
 use crate::sdk::IpcAgentClient;
+use crate::server::checkpoint_diff::BottomUpCheckpointDiffParams;
+use crate::server::history::{CheckpointHistoryParams, CheckpointHistoryResponse};
+use crate::server::journal::{ListCheckpointSubmissionsParams, ListCheckpointSubmissionsResponse};
 use crate::server::list_checkpoints::ListBottomUpCheckpointsParams;
+use crate::server::pending_bottomup::{PendingBottomUpMsgsParams, PendingBottomUpMsgsResponse};
+use crate::server::quorum::{CheckpointQuorumEntry, CheckpointQuorumParams};
+use crate::server::submission_time::{
+    EstimateCheckpointSubmissionTimeParams, EstimateCheckpointSubmissionTimeResponse,
+};
 use crate::server::topdown_executed::LastTopDownExecParams;
+use crate::server::vote_approval::{ApproveVoteParams, ListVoteApprovalsParams};
+use crate::server::vote_history::{
+    QueryValidatorVoteHistoryParams, QueryValidatorVoteHistoryResponse,
+};
 use fvm_shared::clock::ChainEpoch;
 
 impl<T: JsonRpcClient> IpcAgentClient<T> {
@@ -42,4 +57,167 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
             )
             .await
     }
+
+    pub async fn pending_bottom_up_msgs(
+        &self,
+        subnet: &str,
+    ) -> anyhow::Result<PendingBottomUpMsgsResponse> {
+        let params = PendingBottomUpMsgsParams {
+            subnet_id: subnet.to_string(),
+        };
+
+        self.json_rpc_client
+            .request::<PendingBottomUpMsgsResponse>(
+                json_rpc_methods::PENDING_BOTTOMUP_MSGS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn bottom_up_checkpoint_diff(
+        &self,
+        subnet: &str,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<CheckpointDiff> {
+        let params = BottomUpCheckpointDiffParams {
+            subnet_id: subnet.to_string(),
+            epoch,
+        };
+
+        self.json_rpc_client
+            .request::<CheckpointDiff>(
+                json_rpc_methods::BOTTOMUP_CHECKPOINT_DIFF,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns the checkpoint votes waiting for, or decided by, an operator.
+    pub async fn list_vote_approvals(&self) -> anyhow::Result<Vec<VoteApproval>> {
+        self.json_rpc_client
+            .request::<Vec<VoteApproval>>(
+                json_rpc_methods::LIST_VOTE_APPROVALS,
+                serde_json::to_value(ListVoteApprovalsParams::default())?,
+            )
+            .await
+    }
+
+    /// Returns the votes of all the validators on the pending checkpoints of the subnets, or of
+    /// `subnet` only, and the time the checkpoints took to reach their quorum.
+    pub async fn checkpoint_quorum(
+        &self,
+        subnet: Option<&str>,
+    ) -> anyhow::Result<Vec<CheckpointQuorumEntry>> {
+        let params = CheckpointQuorumParams {
+            subnet: subnet.map(String::from),
+        };
+
+        self.json_rpc_client
+            .request::<Vec<CheckpointQuorumEntry>>(
+                json_rpc_methods::CHECKPOINT_QUORUM,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Approves, or rejects, the checkpoint vote of approval `id`.
+    pub async fn approve_vote(&self, id: u64, approve: bool) -> anyhow::Result<VoteApproval> {
+        let params = ApproveVoteParams { id, approve };
+
+        self.json_rpc_client
+            .request::<VoteApproval>(
+                json_rpc_methods::APPROVE_VOTE,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Starts, or resumes, the backfill of the checkpoint history of a subnet as a background job.
+    pub async fn backfill_history(&self, params: BackfillParams) -> anyhow::Result<JobInfo> {
+        self.json_rpc_client
+            .request::<JobInfo>(
+                json_rpc_methods::BACKFILL_HISTORY,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns the checkpoints of a subnet indexed by the agent between two epochs.
+    pub async fn checkpoint_history(
+        &self,
+        subnet: &str,
+        from_epoch: ChainEpoch,
+        to_epoch: ChainEpoch,
+    ) -> anyhow::Result<CheckpointHistoryResponse> {
+        let params = CheckpointHistoryParams {
+            subnet: subnet.to_string(),
+            from_epoch,
+            to_epoch,
+        };
+
+        self.json_rpc_client
+            .request::<CheckpointHistoryResponse>(
+                json_rpc_methods::CHECKPOINT_HISTORY,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns the checkpoint votes submitted by the agent, in a subnet or in all of them, the
+    /// latest first.
+    pub async fn list_checkpoint_submissions(
+        &self,
+        subnet: Option<String>,
+        limit: Option<usize>,
+    ) -> anyhow::Result<ListCheckpointSubmissionsResponse> {
+        let params = ListCheckpointSubmissionsParams { subnet, limit };
+
+        self.json_rpc_client
+            .request::<ListCheckpointSubmissionsResponse>(
+                json_rpc_methods::LIST_CHECKPOINT_SUBMISSIONS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns the votes of a validator on the checkpoints of a subnet executed between two
+    /// epochs.
+    pub async fn validator_vote_history(
+        &self,
+        subnet: &str,
+        validator: &str,
+        from_epoch: ChainEpoch,
+        to_epoch: ChainEpoch,
+    ) -> anyhow::Result<QueryValidatorVoteHistoryResponse> {
+        let params = QueryValidatorVoteHistoryParams {
+            subnet: subnet.to_string(),
+            validator: validator.to_string(),
+            from_epoch,
+            to_epoch,
+        };
+
+        self.json_rpc_client
+            .request::<QueryValidatorVoteHistoryResponse>(
+                json_rpc_methods::QUERY_VALIDATOR_VOTE_HISTORY,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns when the agent is expected to submit the next checkpoints of a subnet.
+    pub async fn estimate_checkpoint_submission_time(
+        &self,
+        subnet: &str,
+    ) -> anyhow::Result<EstimateCheckpointSubmissionTimeResponse> {
+        let params = EstimateCheckpointSubmissionTimeParams {
+            subnet: subnet.to_string(),
+        };
+
+        self.json_rpc_client
+            .request::<EstimateCheckpointSubmissionTimeResponse>(
+                json_rpc_methods::ESTIMATE_CHECKPOINT_SUBMISSION_TIME,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
 }
@@ -1,9 +1,11 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
-use crate::config::json_rpc_methods;
+use crate::config::{json_rpc_methods, ConfigValidation};
 use crate::jsonrpc::JsonRpcClient;
 use crate::sdk::IpcAgentClient;
-use crate::server::ReloadConfigParams;
+use crate::server::debug_tap::{DebugTapParams, DebugTapResponse};
+use crate::server::methods::{ListMethodsParams, MethodInfo};
+use crate::server::{ReloadConfigParams, ValidateConfigParams};
 
 impl<T: JsonRpcClient> IpcAgentClient<T> {
     pub async fn reload_config(&self, path: Option<String>) -> anyhow::Result<()> {
@@ -15,4 +17,41 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
             )
             .await
     }
+
+    /// Validates the subnets of the config loaded by the agent, or of the config at `path`,
+    /// probing their nodes unless `offline` is set.
+    pub async fn validate_config(
+        &self,
+        path: Option<String>,
+        offline: bool,
+    ) -> anyhow::Result<ConfigValidation> {
+        let params = ValidateConfigParams { path, offline };
+        self.json_rpc_client
+            .request::<ConfigValidation>(
+                json_rpc_methods::VALIDATE_CONFIG,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Lists the json rpc methods served by the agent, with the schema of their params.
+    pub async fn list_methods(&self) -> anyhow::Result<Vec<MethodInfo>> {
+        self.json_rpc_client
+            .request::<Vec<MethodInfo>>(
+                json_rpc_methods::LIST_METHODS,
+                serde_json::to_value(ListMethodsParams::default())?,
+            )
+            .await
+    }
+
+    /// Returns the requests to the nodes recorded by the debug tap of the agent, dropping them
+    /// from the tap if `clear` is set.
+    pub async fn debug_tap(&self, clear: bool) -> anyhow::Result<DebugTapResponse> {
+        self.json_rpc_client
+            .request::<DebugTapResponse>(
+                json_rpc_methods::DEBUG_TAP,
+                serde_json::to_value(DebugTapParams { clear })?,
+            )
+            .await
+    }
 }
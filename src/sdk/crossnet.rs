@@ -5,9 +5,13 @@
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::JsonRpcClient;
 use crate::sdk::IpcAgentClient;
-use crate::server::fund::FundParams;
-use crate::server::release::ReleaseParams;
-use fvm_shared::clock::ChainEpoch;
+use crate::server::diagnose_cross::{DiagnoseCrossMsgParams, DiagnoseCrossMsgResponse};
+use crate::server::fund::{FundParams, FundResponse};
+use crate::server::history::{CrossMsgsByAccountParams, CrossMsgsByAccountResponse};
+use crate::server::nonces::{NoncesParams, NoncesResponse};
+use crate::server::pending_releases::{PendingReleasesParams, PendingReleasesResponse};
+use crate::server::release::{ReleaseParams, ReleaseResponse};
+use crate::server::resend_topdown::{ResendTopDownMsgsParams, ResendTopDownMsgsResponse};
 
 impl<T: JsonRpcClient> IpcAgentClient<T> {
     pub async fn fund(
@@ -16,16 +20,18 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
         from: Option<String>,
         to: Option<String>,
         amount: f64,
-    ) -> anyhow::Result<ChainEpoch> {
+        source: Option<String>,
+    ) -> anyhow::Result<FundResponse> {
         let params = FundParams {
             subnet: subnet.to_string(),
             from,
             to,
             amount,
+            source,
         };
 
         self.json_rpc_client
-            .request::<ChainEpoch>(json_rpc_methods::FUND, serde_json::to_value(params)?)
+            .request::<FundResponse>(json_rpc_methods::FUND, serde_json::to_value(params)?)
             .await
     }
 
@@ -35,7 +41,7 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
         from: Option<String>,
         to: Option<String>,
         amount: f64,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<ReleaseResponse> {
         let params = ReleaseParams {
             subnet: subnet.to_string(),
             from,
@@ -43,7 +49,82 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
             amount,
         };
         self.json_rpc_client
-            .request::<ChainEpoch>(json_rpc_methods::RELEASE, serde_json::to_value(params)?)
+            .request::<ReleaseResponse>(json_rpc_methods::RELEASE, serde_json::to_value(params)?)
+            .await
+    }
+
+    /// Lists the releases from `subnet` waiting for its next bottom-up checkpoint, only those
+    /// initiated by `from` if set.
+    pub async fn pending_releases(
+        &self,
+        subnet: &str,
+        from: Option<String>,
+    ) -> anyhow::Result<PendingReleasesResponse> {
+        let params = PendingReleasesParams {
+            subnet: subnet.to_string(),
+            from,
+        };
+        self.json_rpc_client
+            .request::<PendingReleasesResponse>(
+                json_rpc_methods::PENDING_RELEASES,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn nonces(&self, subnet: &str) -> anyhow::Result<NoncesResponse> {
+        let params = NoncesParams {
+            subnet: subnet.to_string(),
+        };
+        self.json_rpc_client
+            .request::<NoncesResponse>(json_rpc_methods::NONCES, serde_json::to_value(params)?)
+            .await
+    }
+
+    pub async fn resend_top_down_msgs(
+        &self,
+        params: ResendTopDownMsgsParams,
+    ) -> anyhow::Result<ResendTopDownMsgsResponse> {
+        self.json_rpc_client
+            .request::<ResendTopDownMsgsResponse>(
+                json_rpc_methods::RESEND_TOPDOWN_MSGS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Finds what the cross message with `nonce`, sent or last propagated from `subnet`, waits on
+    /// and how to get it moving again.
+    pub async fn diagnose_cross_msg(
+        &self,
+        params: DiagnoseCrossMsgParams,
+    ) -> anyhow::Result<DiagnoseCrossMsgResponse> {
+        self.json_rpc_client
+            .request::<DiagnoseCrossMsgResponse>(
+                json_rpc_methods::DIAGNOSE_CROSS_MSG,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns a page of the cross messages indexed in the history of the configured subnets
+    /// that `account` sent or received.
+    pub async fn cross_msgs_by_account(
+        &self,
+        account: &str,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> anyhow::Result<CrossMsgsByAccountResponse> {
+        let params = CrossMsgsByAccountParams {
+            account: account.to_string(),
+            offset,
+            limit,
+        };
+        self.json_rpc_client
+            .request::<CrossMsgsByAccountResponse>(
+                json_rpc_methods::GET_CROSS_MSGS_BY_ACCOUNT,
+                serde_json::to_value(params)?,
+            )
             .await
     }
 }
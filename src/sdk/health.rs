@@ -0,0 +1,33 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::JsonRpcClient;
+use crate::sdk::IpcAgentClient;
+use crate::server::health::{HealthParams, HealthResponse};
+use crate::server::status::{SignedStatusParams, SignedStatusResponse};
+
+impl<T: JsonRpcClient> IpcAgentClient<T> {
+    /// Returns the health of the subnets of the agent.
+    pub async fn health(&self) -> anyhow::Result<HealthResponse> {
+        self.json_rpc_client
+            .request::<HealthResponse>(
+                json_rpc_methods::HEALTH,
+                serde_json::to_value(HealthParams::default())?,
+            )
+            .await
+    }
+
+    /// Returns the health of the subnets of the agent signed with its identity key, along with
+    /// `challenge`. The response is checked with [`SignedStatusResponse::verify`].
+    pub async fn signed_status(
+        &self,
+        challenge: Option<String>,
+    ) -> anyhow::Result<SignedStatusResponse> {
+        self.json_rpc_client
+            .request::<SignedStatusResponse>(
+                json_rpc_methods::SIGNED_STATUS,
+                serde_json::to_value(SignedStatusParams { challenge })?,
+            )
+            .await
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::config::json_rpc_methods;
+use crate::jobs::{JobAction, JobInfo};
+use crate::jsonrpc::JsonRpcClient;
+use crate::sdk::IpcAgentClient;
+use crate::server::jobs::{ControlJobParams, ListJobsParams};
+
+impl<T: JsonRpcClient> IpcAgentClient<T> {
+    /// Lists the background jobs of the agent.
+    pub async fn list_jobs(&self) -> anyhow::Result<Vec<JobInfo>> {
+        self.json_rpc_client
+            .request::<Vec<JobInfo>>(
+                json_rpc_methods::LIST_JOBS,
+                serde_json::to_value(ListJobsParams::default())?,
+            )
+            .await
+    }
+
+    /// Pauses, resumes or cancels a background job.
+    pub async fn control_job(&self, id: u64, action: JobAction) -> anyhow::Result<JobInfo> {
+        let params = ControlJobParams { id, action };
+        self.json_rpc_client
+            .request::<JobInfo>(json_rpc_methods::CONTROL_JOB, serde_json::to_value(params)?)
+            .await
+    }
+}
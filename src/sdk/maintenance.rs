@@ -0,0 +1,25 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::JsonRpcClient;
+use crate::manager::maintenance::{MaintenanceAction, MaintenanceStatus};
+use crate::sdk::IpcAgentClient;
+use crate::server::maintenance::MaintenanceParams;
+
+impl<T: JsonRpcClient> IpcAgentClient<T> {
+    /// Puts the agent into maintenance, takes it out of it, or only returns its maintenance
+    /// status, with `MaintenanceAction::Status`.
+    pub async fn maintenance(
+        &self,
+        action: MaintenanceAction,
+        reason: Option<String>,
+    ) -> anyhow::Result<MaintenanceStatus> {
+        let params = MaintenanceParams { action, reason };
+        self.json_rpc_client
+            .request::<MaintenanceStatus>(
+                json_rpc_methods::MAINTENANCE,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+}
@@ -3,10 +3,16 @@
 // Copyright 2022-2023 Protocol Labs
 //! Ipc agent sdk, contains the json rpc client to interact with the IPC agent rpc server.
 
+mod approval;
 mod checkpoint;
 mod config;
 mod crossnet;
+mod health;
+mod jobs;
+mod maintenance;
+mod notifications;
 mod subnet;
+mod tx;
 mod wallet;
 
 use crate::jsonrpc::JsonRpcClientImpl;
@@ -25,8 +31,21 @@ impl<T> IpcAgentClient<T> {
     }
 }
 
+/// The environment variable the bearer token sent to the agent is read from, for agents
+/// requiring authentication.
+pub const IPC_AGENT_TOKEN_ENV: &str = "IPC_AGENT_TOKEN";
+
+/// Returns the bearer token in the [`IPC_AGENT_TOKEN_ENV`] environment variable, if set.
+pub fn token_from_env() -> Option<String> {
+    std::env::var(IPC_AGENT_TOKEN_ENV)
+        .ok()
+        .filter(|t| !t.is_empty())
+}
+
 impl IpcAgentClient<JsonRpcClientImpl> {
+    /// Creates a client of the agent at `url`, authenticated with the token in the
+    /// [`IPC_AGENT_TOKEN_ENV`] environment variable if set.
     pub fn default_from_url(url: Url) -> Self {
-        IpcAgentClient::new(JsonRpcClientImpl::new(url, None))
+        IpcAgentClient::new(JsonRpcClientImpl::new(url, token_from_env().as_deref()))
     }
 }
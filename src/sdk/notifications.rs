@@ -0,0 +1,41 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use fvm_shared::clock::ChainEpoch;
+
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::JsonRpcClient;
+use crate::notify::Delivery;
+use crate::sdk::IpcAgentClient;
+use crate::server::notifications::{ListNotificationsParams, RedeliverNotificationsParams};
+
+impl<T: JsonRpcClient> IpcAgentClient<T> {
+    /// Lists the deliveries of the notifications of chain events.
+    pub async fn list_notifications(&self) -> anyhow::Result<Vec<Delivery>> {
+        self.json_rpc_client
+            .request::<Vec<Delivery>>(
+                json_rpc_methods::LIST_NOTIFICATIONS,
+                serde_json::to_value(ListNotificationsParams::default())?,
+            )
+            .await
+    }
+
+    /// Posts the notifications of the chain events of `subnet` between two epochs again.
+    pub async fn redeliver_notifications(
+        &self,
+        subnet: String,
+        from_epoch: ChainEpoch,
+        to_epoch: Option<ChainEpoch>,
+    ) -> anyhow::Result<Vec<Delivery>> {
+        let params = RedeliverNotificationsParams {
+            subnet,
+            from_epoch,
+            to_epoch,
+        };
+        self.json_rpc_client
+            .request::<Vec<Delivery>>(
+                json_rpc_methods::REDELIVER_NOTIFICATIONS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+}
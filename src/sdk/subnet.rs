@@ -3,10 +3,29 @@
 use crate::config::json_rpc_methods;
 use crate::jsonrpc::JsonRpcClient;
 use crate::sdk::IpcAgentClient;
-use crate::server::create::{CreateSubnetParams, CreateSubnetResponse};
+use crate::server::circulating_supply::{CirculatingSupplyParams, CirculatingSupplyResponse};
+use crate::server::compare_validators::{CompareValidatorSetsParams, CompareValidatorSetsResponse};
+use crate::server::create::{
+    CreateSubnetParams, CreateSubnetResponse, PreviewCreateSubnetResponse,
+};
+use crate::server::decommission::{DecommissionSubnetParams, DecommissionSubnetResponse};
+use crate::server::federated_power::{SetFederatedPowerParams, SetFederatedPowerResponse};
+use crate::server::import::{
+    DiscoverSubnetsParams, DiscoveredSubnet, ImportSubnetsParams, ImportSubnetsResponse,
+    ListOwnSubnetsParams, OwnSubnet,
+};
 use crate::server::join::JoinSubnetParams;
+use crate::server::join_collateral::{
+    EstimateJoinCollateralParams, EstimateJoinCollateralResponse,
+};
 use crate::server::kill::KillSubnetParams;
 use crate::server::leave::LeaveSubnetParams;
+use crate::server::onboarding::{OnboardingDryRunParams, OnboardingDryRunResponse};
+use crate::server::read_state::{ReadStateParams, ReadStateResponse};
+use crate::server::topology::{TopologyParams, TopologyResponse};
+use crate::server::validator_subnets::{
+    ListValidatorsAcrossSubnetsParams, ListValidatorsAcrossSubnetsResponse,
+};
 
 impl<T: JsonRpcClient> IpcAgentClient<T> {
     pub async fn create_subnet(&self, params: CreateSubnetParams) -> anyhow::Result<String> {
@@ -20,12 +39,50 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
             .address)
     }
 
+    /// Estimates what creating a subnet with `params` costs, without creating it.
+    pub async fn preview_create_subnet(
+        &self,
+        params: CreateSubnetParams,
+    ) -> anyhow::Result<PreviewCreateSubnetResponse> {
+        self.json_rpc_client
+            .request::<PreviewCreateSubnetResponse>(
+                json_rpc_methods::PREVIEW_CREATE_SUBNET,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
     pub async fn join_subnet(&self, params: JoinSubnetParams) -> anyhow::Result<()> {
         self.json_rpc_client
             .request::<()>(json_rpc_methods::JOIN_SUBNET, serde_json::to_value(params)?)
             .await
     }
 
+    pub async fn estimate_join_collateral(
+        &self,
+        params: EstimateJoinCollateralParams,
+    ) -> anyhow::Result<EstimateJoinCollateralResponse> {
+        self.json_rpc_client
+            .request::<EstimateJoinCollateralResponse>(
+                json_rpc_methods::ESTIMATE_JOIN_COLLATERAL,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Checks what a prospective validator is missing to join a subnet, without sending anything.
+    pub async fn onboarding_dry_run(
+        &self,
+        params: OnboardingDryRunParams,
+    ) -> anyhow::Result<OnboardingDryRunResponse> {
+        self.json_rpc_client
+            .request::<OnboardingDryRunResponse>(
+                json_rpc_methods::ONBOARDING_DRY_RUN,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
     pub async fn leave_subnet(&self, params: LeaveSubnetParams) -> anyhow::Result<()> {
         self.json_rpc_client
             .request::<()>(
@@ -35,9 +92,122 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
             .await
     }
 
+    pub async fn set_federated_power(
+        &self,
+        params: SetFederatedPowerParams,
+    ) -> anyhow::Result<SetFederatedPowerResponse> {
+        self.json_rpc_client
+            .request::<SetFederatedPowerResponse>(
+                json_rpc_methods::SET_FEDERATED_POWER,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
     pub async fn kill_subnet(&self, params: KillSubnetParams) -> anyhow::Result<()> {
         self.json_rpc_client
             .request::<()>(json_rpc_methods::KILL_SUBNET, serde_json::to_value(params)?)
             .await
     }
+
+    pub async fn decommission_subnet(
+        &self,
+        params: DecommissionSubnetParams,
+    ) -> anyhow::Result<DecommissionSubnetResponse> {
+        self.json_rpc_client
+            .request::<DecommissionSubnetResponse>(
+                json_rpc_methods::DECOMMISSION_SUBNET,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Compares the validator set of a subnet in its parent with the membership of its consensus.
+    pub async fn compare_validator_sets(
+        &self,
+        params: CompareValidatorSetsParams,
+    ) -> anyhow::Result<CompareValidatorSetsResponse> {
+        self.json_rpc_client
+            .request::<CompareValidatorSetsResponse>(
+                json_rpc_methods::COMPARE_VALIDATOR_SETS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn circulating_supply(
+        &self,
+        params: CirculatingSupplyParams,
+    ) -> anyhow::Result<CirculatingSupplyResponse> {
+        self.json_rpc_client
+            .request::<CirculatingSupplyResponse>(
+                json_rpc_methods::GET_CIRCULATING_SUPPLY,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn list_validators_across_subnets(
+        &self,
+        address: String,
+    ) -> anyhow::Result<ListValidatorsAcrossSubnetsResponse> {
+        let params = ListValidatorsAcrossSubnetsParams { address };
+        self.json_rpc_client
+            .request::<ListValidatorsAcrossSubnetsResponse>(
+                json_rpc_methods::LIST_VALIDATORS_ACROSS_SUBNETS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Reads the state of an actor of a subnet, decoded if its schema is known.
+    pub async fn read_state(&self, params: ReadStateParams) -> anyhow::Result<ReadStateResponse> {
+        self.json_rpc_client
+            .request::<ReadStateResponse>(
+                json_rpc_methods::READ_STATE,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn topology(&self) -> anyhow::Result<TopologyResponse> {
+        self.json_rpc_client
+            .request::<TopologyResponse>(
+                json_rpc_methods::TOPOLOGY,
+                serde_json::to_value(TopologyParams::default())?,
+            )
+            .await
+    }
+
+    pub async fn discover_subnets(&self, parent: String) -> anyhow::Result<Vec<DiscoveredSubnet>> {
+        let params = DiscoverSubnetsParams { parent };
+        self.json_rpc_client
+            .request::<Vec<DiscoveredSubnet>>(
+                json_rpc_methods::DISCOVER_SUBNETS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn list_own_subnets(&self, parent: String) -> anyhow::Result<Vec<OwnSubnet>> {
+        let params = ListOwnSubnetsParams { parent };
+        self.json_rpc_client
+            .request::<Vec<OwnSubnet>>(
+                json_rpc_methods::LIST_OWN_SUBNETS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    pub async fn import_subnets(
+        &self,
+        params: ImportSubnetsParams,
+    ) -> anyhow::Result<ImportSubnetsResponse> {
+        self.json_rpc_client
+            .request::<ImportSubnetsResponse>(
+                json_rpc_methods::IMPORT_SUBNETS,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
 }
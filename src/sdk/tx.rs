@@ -0,0 +1,64 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Offline transaction related sdk functions
+
+use crate::config::json_rpc_methods;
+use crate::jsonrpc::JsonRpcClient;
+use crate::manager::offline::OfflineTx;
+use crate::sdk::IpcAgentClient;
+use crate::server::lookup_message::{GetMessageResponse, LookupMessageParams, MessageReceiptEntry};
+use crate::server::offline_tx::{BuildTxParams, SendSignedTxParams, SendSignedTxResponse};
+
+impl<T: JsonRpcClient> IpcAgentClient<T> {
+    /// Builds an unsigned transaction to be signed offline.
+    pub async fn build_offline_tx(&self, params: BuildTxParams) -> anyhow::Result<OfflineTx> {
+        self.json_rpc_client
+            .request::<OfflineTx>(
+                json_rpc_methods::BUILD_OFFLINE_TX,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Broadcasts a transaction signed offline, returning its id.
+    pub async fn send_signed_tx(&self, tx: OfflineTx) -> anyhow::Result<String> {
+        let params = SendSignedTxParams { tx };
+        let r = self
+            .json_rpc_client
+            .request::<SendSignedTxResponse>(
+                json_rpc_methods::SEND_SIGNED_TX,
+                serde_json::to_value(params)?,
+            )
+            .await?;
+        Ok(r.id)
+    }
+
+    /// Returns a message of a subnet by its cid, or the hash of its transaction in evm subnets,
+    /// with its receipt if it is executed.
+    pub async fn get_message(&self, subnet: &str, id: &str) -> anyhow::Result<GetMessageResponse> {
+        let params = LookupMessageParams {
+            subnet: subnet.to_string(),
+            id: id.to_string(),
+        };
+        self.json_rpc_client
+            .request::<GetMessageResponse>(
+                json_rpc_methods::GET_MESSAGE,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Returns the receipt of an executed message of a subnet.
+    pub async fn get_receipt(&self, subnet: &str, id: &str) -> anyhow::Result<MessageReceiptEntry> {
+        let params = LookupMessageParams {
+            subnet: subnet.to_string(),
+            id: id.to_string(),
+        };
+        self.json_rpc_client
+            .request::<MessageReceiptEntry>(
+                json_rpc_methods::GET_RECEIPT,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+}
@@ -6,14 +6,19 @@ use crate::config::json_rpc_methods;
 use crate::jsonrpc::JsonRpcClient;
 use crate::lotus::message::wallet::WalletKeyType;
 use crate::sdk::IpcAgentClient;
+use crate::server::pending_messages::{ListPendingMessagesParams, ListPendingMessagesResponse};
+use crate::server::replace_message::{ReplaceMessageParams, ReplaceMessageResponse};
+use crate::server::wallet::activity::{WalletActivityParams, WalletActivityResponse};
+use crate::server::wallet::faucet::{FaucetRequestParams, FaucetRequestResponse};
 use crate::server::wallet::import::{
-    EvmImportParams, FvmImportParams, WalletImportParams, WalletImportResponse,
+    EvmImportParams, FvmImportParams, Secp256k1ImportParams, Secp256k1ImportResponse,
+    WalletImportParams, WalletImportResponse,
 };
 use fvm_shared::crypto::signature::SignatureType;
 use ipc_identity::PersistentKeyInfo;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 impl<T: JsonRpcClient> IpcAgentClient<T> {
     /// Import a wallet address in the form of lotus json
@@ -40,6 +45,80 @@ impl<T: JsonRpcClient> IpcAgentClient<T> {
         self.import(params).await
     }
 
+    /// Import a hex encoded secp256k1 private key in both the fvm and evm wallets of the agent.
+    /// Returns the addresses of the key in both representations.
+    pub async fn import_secp256k1(
+        &self,
+        private_key: String,
+    ) -> anyhow::Result<Secp256k1ImportResponse> {
+        let params = Secp256k1ImportParams { private_key };
+        self.json_rpc_client
+            .request::<Secp256k1ImportResponse>(
+                json_rpc_methods::WALLET_IMPORT_SECP256K1,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Lists what the accounts of a subnet sent, received and spent in gas.
+    pub async fn wallet_activity(
+        &self,
+        params: WalletActivityParams,
+    ) -> anyhow::Result<WalletActivityResponse> {
+        self.json_rpc_client
+            .request::<WalletActivityResponse>(
+                json_rpc_methods::WALLET_ACTIVITY,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Requests test funds for an account from the faucet of its subnet, returning once they
+    /// landed.
+    pub async fn faucet_request(
+        &self,
+        params: FaucetRequestParams,
+    ) -> anyhow::Result<FaucetRequestResponse> {
+        self.json_rpc_client
+            .request::<FaucetRequestResponse>(
+                json_rpc_methods::FAUCET_REQUEST,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Lists the messages of the accounts of a subnet pending in its memory pool, those of `from`
+    /// if set.
+    pub async fn list_pending_messages(
+        &self,
+        subnet: &str,
+        from: Option<String>,
+    ) -> anyhow::Result<ListPendingMessagesResponse> {
+        let params = ListPendingMessagesParams {
+            subnet: subnet.to_string(),
+            from,
+        };
+        self.json_rpc_client
+            .request::<ListPendingMessagesResponse>(
+                json_rpc_methods::LIST_PENDING_MESSAGES,
+                serde_json::to_value(params)?,
+            )
+            .await
+    }
+
+    /// Replaces a message of an account of the agent pending in the memory pool of a subnet, or
+    /// cancels it. Returns the id of the replacement.
+    pub async fn replace_message(&self, params: ReplaceMessageParams) -> anyhow::Result<String> {
+        Ok(self
+            .json_rpc_client
+            .request::<ReplaceMessageResponse>(
+                json_rpc_methods::REPLACE_MESSAGE,
+                serde_json::to_value(params)?,
+            )
+            .await?
+            .id)
+    }
+
     pub async fn import(&self, params: WalletImportParams) -> anyhow::Result<String> {
         Ok(self
             .json_rpc_client
@@ -69,8 +148,45 @@ impl FromStr for LotusJsonKeyType {
     }
 }
 
+impl LotusJsonKeyType {
+    /// Parses a key in the `hex-lotus` format, i.e. the hex encoded lotus json output by
+    /// `lotus wallet export`.
+    pub fn from_hex_lotus(s: &str) -> anyhow::Result<Self> {
+        let bytes = Zeroizing::new(hex::decode(s.trim())?);
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Encodes the key in the `hex-lotus` format accepted by `lotus wallet import`.
+    pub fn to_hex_lotus(&self) -> anyhow::Result<String> {
+        Ok(hex::encode(serde_json::to_vec(self)?))
+    }
+}
+
 impl Drop for LotusJsonKeyType {
     fn drop(&mut self) {
         self.private_key.zeroize();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::sdk::LotusJsonKeyType;
+
+    #[test]
+    fn test_hex_lotus_roundtrip() {
+        let key = LotusJsonKeyType {
+            r#type: String::from("secp256k1"),
+            private_key: String::from("8VcW07ADswS4BV2cxi5rnIadVsyTDDhY1NfDH19T8Uo="),
+        };
+
+        let encoded = key.to_hex_lotus().unwrap();
+        assert_eq!(
+            hex::decode(&encoded).unwrap(),
+            br#"{"Type":"secp256k1","PrivateKey":"8VcW07ADswS4BV2cxi5rnIadVsyTDDhY1NfDH19T8Uo="}"#
+        );
+
+        let decoded = LotusJsonKeyType::from_hex_lotus(&encoded).unwrap();
+        assert_eq!(decoded.r#type, key.r#type);
+        assert_eq!(decoded.private_key, key.private_key);
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Authentication of the json rpc requests with static bearer tokens.
+//!
+//! When the `[server.auth]` section of the config is set, every request carries a token in its
+//! `Authorization: Bearer <token>` header, and the method requested needs a permission no higher
+//! than the one of the token. Methods that only query state can be left open to the requests
+//! without a token.
+
+use warp::reject::Reject;
+
+use crate::config::{json_rpc_methods, AuthConfig, Permission};
+
+/// The methods that need the `admin` permission by default: they kill subnets, move the funds of
+/// the wallets of the agent, or manage the agent itself.
+const ADMIN_METHODS: [&str; 15] = [
+    json_rpc_methods::KILL_SUBNET,
+    json_rpc_methods::DECOMMISSION_SUBNET,
+    json_rpc_methods::SET_FEDERATED_POWER,
+    json_rpc_methods::SEND_VALUE,
+    json_rpc_methods::WALLET_REMOVE,
+    json_rpc_methods::WALLET_IMPORT,
+    json_rpc_methods::WALLET_IMPORT_SECP256K1,
+    json_rpc_methods::WALLET_EXPORT,
+    json_rpc_methods::RELOAD_CONFIG,
+    json_rpc_methods::VALIDATE_CONFIG,
+    json_rpc_methods::APPROVE_OPERATION,
+    json_rpc_methods::ABORT_PENDING_OPERATION,
+    json_rpc_methods::APPROVE_VOTE,
+    json_rpc_methods::DEBUG_TAP,
+    json_rpc_methods::MAINTENANCE,
+];
+
+/// The methods that only need the `read` permission by default, as they only query state.
+const READ_METHODS: [&str; 41] = [
+    json_rpc_methods::ESTIMATE_JOIN_COLLATERAL,
+    json_rpc_methods::PREVIEW_CREATE_SUBNET,
+    json_rpc_methods::ONBOARDING_DRY_RUN,
+    json_rpc_methods::LIST_CHILD_SUBNETS,
+    json_rpc_methods::QUERY_VALIDATOR_SET,
+    json_rpc_methods::COMPARE_VALIDATOR_SETS,
+    json_rpc_methods::BUILD_OFFLINE_TX,
+    json_rpc_methods::WALLET_BALANCES,
+    json_rpc_methods::KEY_USAGE,
+    json_rpc_methods::WALLET_ACTIVITY,
+    json_rpc_methods::LIST_PENDING_MESSAGES,
+    json_rpc_methods::GET_MESSAGE,
+    json_rpc_methods::GET_RECEIPT,
+    json_rpc_methods::LIST_BOTTOMUP_CHECKPOINTS,
+    json_rpc_methods::LAST_TOPDOWN_EXECUTED,
+    json_rpc_methods::PENDING_BOTTOMUP_MSGS,
+    json_rpc_methods::PENDING_RELEASES,
+    json_rpc_methods::NONCES,
+    json_rpc_methods::BOTTOMUP_CHECKPOINT_DIFF,
+    json_rpc_methods::GET_CIRCULATING_SUPPLY,
+    json_rpc_methods::HEALTH,
+    json_rpc_methods::SIGNED_STATUS,
+    json_rpc_methods::LIST_VALIDATORS_ACROSS_SUBNETS,
+    json_rpc_methods::LIST_VOTE_APPROVALS,
+    json_rpc_methods::CHECKPOINT_QUORUM,
+    json_rpc_methods::LIST_OPERATION_APPROVALS,
+    json_rpc_methods::LIST_METHODS,
+    json_rpc_methods::TOPOLOGY,
+    json_rpc_methods::DISCOVER_SUBNETS,
+    json_rpc_methods::LIST_OWN_SUBNETS,
+    json_rpc_methods::CHECKPOINT_HISTORY,
+    json_rpc_methods::LIST_CHECKPOINT_SUBMISSIONS,
+    json_rpc_methods::QUERY_VALIDATOR_VOTE_HISTORY,
+    json_rpc_methods::ESTIMATE_CHECKPOINT_SUBMISSION_TIME,
+    json_rpc_methods::READ_STATE,
+    json_rpc_methods::GET_CROSS_MSGS_BY_ACCOUNT,
+    json_rpc_methods::LIST_JOBS,
+    json_rpc_methods::LIST_NOTIFICATIONS,
+    json_rpc_methods::SUBSCRIBE_CHECKPOINTS,
+    json_rpc_methods::SUBSCRIBE_CROSS_MSGS,
+    json_rpc_methods::UNSUBSCRIBE,
+];
+
+/// Why a request was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AuthRejection {
+    /// The request has no token, or an unknown one.
+    Unauthenticated,
+    /// The token does not grant the permission the method needs.
+    Forbidden,
+}
+
+impl Reject for AuthRejection {}
+
+/// Returns the permission `method` needs.
+pub fn method_permission(auth: &AuthConfig, method: &str) -> Permission {
+    if let Some(permission) = auth.methods.get(method) {
+        return *permission;
+    }
+    if ADMIN_METHODS.contains(&method) {
+        Permission::Admin
+    } else if READ_METHODS.contains(&method) {
+        Permission::Read
+    } else {
+        Permission::Write
+    }
+}
+
+/// Checks that the `authorization` header of a request to `method` carries a token with the
+/// permission the method needs.
+pub(crate) fn authorize(
+    auth: &AuthConfig,
+    authorization: Option<&str>,
+    method: &str,
+) -> Result<(), AuthRejection> {
+    let needed = method_permission(auth, method);
+    let granted = match authorization {
+        Some(header) => token_permission(auth, header)?,
+        None if auth.anonymous_read && needed == Permission::Read => return Ok(()),
+        None => return Err(AuthRejection::Unauthenticated),
+    };
+    if granted < needed {
+        tracing::debug!("method {method:} needs the {needed:?} permission, token has {granted:?}");
+        return Err(AuthRejection::Forbidden);
+    }
+    Ok(())
+}
+
+/// Returns the permission of the bearer token in `header`.
+fn token_permission(auth: &AuthConfig, header: &str) -> Result<Permission, AuthRejection> {
+    let token = header
+        .strip_prefix("Bearer ")
+        .ok_or(AuthRejection::Unauthenticated)?
+        .trim();
+    auth.tokens
+        .iter()
+        .filter(|t| !t.token.is_empty())
+        .find(|t| tokens_match(t.token.as_bytes(), token.as_bytes()))
+        .map(|t| t.permission)
+        .ok_or(AuthRejection::Unauthenticated)
+}
+
+/// Compares the tokens in constant time, not to leak how much of a token was guessed right.
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::config::{json_rpc_methods, AuthConfig, AuthToken, Permission};
+    use crate::server::auth::{authorize, method_permission, AuthRejection};
+
+    #[test]
+    fn test_authorize() {
+        let mut auth = AuthConfig {
+            tokens: vec![
+                AuthToken {
+                    token: String::from("reader"),
+                    permission: Permission::Read,
+                },
+                AuthToken {
+                    token: String::from("operator"),
+                    permission: Permission::Admin,
+                },
+            ],
+            methods: HashMap::from([(String::from(json_rpc_methods::FUND), Permission::Admin)]),
+            anonymous_read: true,
+        };
+
+        assert_eq!(
+            method_permission(&auth, json_rpc_methods::KILL_SUBNET),
+            Permission::Admin
+        );
+        assert_eq!(
+            method_permission(&auth, json_rpc_methods::RELEASE),
+            Permission::Write
+        );
+        assert_eq!(
+            method_permission(&auth, json_rpc_methods::FUND),
+            Permission::Admin
+        );
+
+        // queries stay open, the rest needs a token with enough permission
+        assert!(authorize(&auth, None, json_rpc_methods::LIST_CHILD_SUBNETS).is_ok());
+        assert_eq!(
+            authorize(&auth, None, json_rpc_methods::SEND_VALUE),
+            Err(AuthRejection::Unauthenticated)
+        );
+        assert_eq!(
+            authorize(&auth, Some("Bearer reader"), json_rpc_methods::SEND_VALUE),
+            Err(AuthRejection::Forbidden)
+        );
+        assert!(authorize(&auth, Some("Bearer operator"), json_rpc_methods::SEND_VALUE).is_ok());
+        assert_eq!(
+            authorize(&auth, Some("Bearer unknown"), json_rpc_methods::HEALTH),
+            Err(AuthRejection::Unauthenticated)
+        );
+        assert_eq!(
+            authorize(&auth, Some("operator"), json_rpc_methods::HEALTH),
+            Err(AuthRejection::Unauthenticated)
+        );
+
+        auth.anonymous_read = false;
+        assert_eq!(
+            authorize(&auth, None, json_rpc_methods::LIST_CHILD_SUBNETS),
+            Err(AuthRejection::Unauthenticated)
+        );
+        assert!(authorize(&auth, Some("Bearer reader"), json_rpc_methods::HEALTH).is_ok());
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Approval of the operations exceeding the thresholds of the server config
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::amount::parse_fil;
+use crate::config::subnet::FvmSigner;
+use crate::config::{json_rpc_methods, ApprovalConfig};
+use crate::manager::approval::ParkedOperation;
+use crate::server::handlers::manager::decommission::DecommissionSubnetParams;
+use crate::server::handlers::manager::fund::FundParams;
+use crate::server::handlers::manager::join::JoinSubnetParams;
+use crate::server::handlers::manager::kill::KillSubnetParams;
+use crate::server::handlers::manager::pending_messages::PendingMessageEntry;
+use crate::server::handlers::manager::release::ReleaseParams;
+use crate::server::handlers::manager::send_cross::SendCrossMsgParams;
+use crate::server::handlers::manager::send_value::SendValueParams;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::handlers::manager::{check_subnet, parse_from};
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListOperationApprovalsParams {}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApproveOperationParams {
+    pub id: u64,
+    /// Whether to approve the operation, rejects it otherwise.
+    pub approve: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApproveOperationResponse {
+    pub operation: ParkedOperation,
+    /// The response of the method of the operation, if it was approved and executed.
+    pub result: Option<Value>,
+}
+
+/// The operation to abort: either the operation `id` parked for approval, or the message of
+/// `from` pending with `nonce` in `subnet`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AbortPendingOperationParams {
+    pub id: Option<u64>,
+    pub subnet: Option<String>,
+    /// The sender of the message, the first account of the subnet in the config if not set.
+    pub from: Option<String>,
+    pub nonce: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbortPendingOperationResponse {
+    /// The parked operation aborted.
+    pub operation: Option<ParkedOperation>,
+    /// The pending message abandoned, whose nonce is reclaimed by the next message of its sender.
+    pub abandoned: Option<PendingMessageEntry>,
+}
+
+/// Returns why the call of `method` with `params` needs approval, if it exceeds a threshold of
+/// `config`. Calls whose params cannot be parsed are left to the handler of the method to reject.
+pub(crate) fn approval_reason(
+    config: &ApprovalConfig,
+    method: &str,
+    params: &Value,
+) -> Result<Option<String>> {
+    let above = |amount: f64, threshold: Option<f64>| threshold.map_or(false, |t| amount > t);
+
+    let reason = match method {
+        json_rpc_methods::FUND => parse(params)
+            .filter(|p: &FundParams| above(p.amount, config.value_threshold))
+            .map(|p| format!("fund of {} FIL to subnet {}", p.amount, p.subnet)),
+        json_rpc_methods::RELEASE => parse(params)
+            .filter(|p: &ReleaseParams| above(p.amount, config.value_threshold))
+            .map(|p| format!("release of {} FIL from subnet {}", p.amount, p.subnet)),
+        json_rpc_methods::SEND_VALUE => parse(params)
+            .filter(|p: &SendValueParams| above(p.amount, config.value_threshold))
+            .map(|p| format!("transfer of {} FIL in subnet {}", p.amount, p.subnet)),
+        json_rpc_methods::SEND_CROSS_MSG => {
+            let threshold = config
+                .value_threshold
+                .map(|t| parse_fil("value_threshold", t))
+                .transpose()?;
+            parse(params)
+                .filter(|p: &SendCrossMsgParams| {
+                    threshold
+                        .as_ref()
+                        .map_or(false, |t| &p.cross_message.msg.value > t)
+                })
+                .map(|p| {
+                    format!(
+                        "cross-net message of {} FIL from subnet {}",
+                        p.cross_message.msg.value, p.subnet
+                    )
+                })
+        }
+        json_rpc_methods::JOIN_SUBNET => parse(params)
+            .filter(|p: &JoinSubnetParams| above(p.collateral, config.collateral_threshold))
+            .map(|p| {
+                format!(
+                    "join of subnet {} with {} FIL of collateral",
+                    p.subnet, p.collateral
+                )
+            }),
+        json_rpc_methods::KILL_SUBNET if config.kill => {
+            parse(params).map(|p: KillSubnetParams| format!("kill of subnet {}", p.subnet))
+        }
+        // the amounts released are only known once the clean up is planned, so any value
+        // threshold parks it, while dry runs only report the steps
+        json_rpc_methods::DECOMMISSION_SUBNET
+            if config.kill || config.value_threshold.is_some() =>
+        {
+            parse(params)
+                .filter(|p: &DecommissionSubnetParams| !p.dry_run)
+                .map(|p| format!("decommission of subnet {}", p.subnet))
+        }
+        _ => None,
+    };
+
+    Ok(reason)
+}
+
+fn parse<T: DeserializeOwned>(params: &Value) -> Option<T> {
+    serde_json::from_value(params.clone()).ok()
+}
+
+/// Lists the operations parked for approval, and those decided recently.
+pub(crate) struct ListOperationApprovalsHandler {
+    state: Arc<AgentState>,
+}
+
+impl ListOperationApprovalsHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListOperationApprovalsHandler {
+    type Request = ListOperationApprovalsParams;
+    type Response = Vec<ParkedOperation>;
+
+    async fn handle(&self, _request: Self::Request) -> Result<Self::Response> {
+        Ok(self.state.operation_approvals.all())
+    }
+}
+
+/// Aborts an operation parked for approval, or abandons a message of the agent pushed to the
+/// memory pool of a subnet but not executed yet. The message stays in the memory pool: its nonce
+/// is reclaimed by the next message of its sender, which replaces it.
+pub(crate) struct AbortPendingOperationHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl AbortPendingOperationHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+
+    async fn abandon(
+        &self,
+        subnet: &str,
+        from: Option<String>,
+        nonce: u64,
+    ) -> Result<PendingMessageEntry> {
+        let subnet = SubnetID::from_str(subnet)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("subnet {subnet:} not configured")),
+            Some(conn) => conn,
+        };
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+        // the node assigns its own nonce to the messages it signs
+        if subnet_config.fvm_signer() == FvmSigner::Node {
+            return Err(anyhow!(
+                "messages signed by the node of subnet {subnet:} cannot be abandoned by the agent, use `lotus mpool replace`"
+            ));
+        }
+
+        let from = parse_from(subnet_config, from)?;
+        let msg = conn
+            .manager()
+            .pending_messages(Some(from))
+            .await?
+            .into_iter()
+            .find(|m| m.nonce == nonce)
+            .ok_or_else(|| {
+                anyhow!("no message of {from:} pending with nonce {nonce:} in subnet {subnet:}")
+            })?;
+
+        tracing::info!(
+            "json rpc: abandoning the message {} of {from:} with nonce {nonce:} in subnet {subnet:}",
+            msg.id
+        );
+        self.pool
+            .state()
+            .nonces
+            .abandon(&subnet, &from, nonce, msg.gas_premium.clone());
+        Ok(PendingMessageEntry::from(msg))
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for AbortPendingOperationHandler {
+    type Request = AbortPendingOperationParams;
+    type Response = AbortPendingOperationResponse;
+
+    async fn handle(&self, request: Self::Request) -> Result<Self::Response> {
+        match request {
+            AbortPendingOperationParams {
+                id: Some(id),
+                subnet: None,
+                from: None,
+                nonce: None,
+            } => Ok(AbortPendingOperationResponse {
+                operation: Some(self.pool.state().operation_approvals.abort(id)?),
+                abandoned: None,
+            }),
+            AbortPendingOperationParams {
+                id: None,
+                subnet: Some(subnet),
+                from,
+                nonce: Some(nonce),
+            } => Ok(AbortPendingOperationResponse {
+                operation: None,
+                abandoned: Some(self.abandon(&subnet, from, nonce).await?),
+            }),
+            _ => Err(anyhow!(
+                "either the id of a parked operation, or the subnet and nonce of a pending message, must be set"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::config::{json_rpc_methods, ApprovalConfig};
+    use crate::server::handlers::approval::approval_reason;
+
+    #[test]
+    fn test_approval_reason() {
+        let config = ApprovalConfig {
+            value_threshold: Some(10.0),
+            collateral_threshold: None,
+            kill: true,
+            expiry_secs: 60,
+        };
+        let fund = |amount: f64| json!({"subnet": "/r123/f0100", "amount": amount});
+
+        assert!(
+            approval_reason(&config, json_rpc_methods::FUND, &fund(10.0))
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            approval_reason(&config, json_rpc_methods::FUND, &fund(10.5))
+                .unwrap()
+                .is_some()
+        );
+
+        let join = json!({"subnet": "/r123/f0100", "collateral": 1000.0, "validator_net_addr": ""});
+        assert!(
+            approval_reason(&config, json_rpc_methods::JOIN_SUBNET, &join)
+                .unwrap()
+                .is_none()
+        );
+
+        let kill = json!({"subnet": "/r123/f0100"});
+        assert!(
+            approval_reason(&config, json_rpc_methods::KILL_SUBNET, &kill)
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            approval_reason(&config, json_rpc_methods::LEAVE_SUBNET, &kill)
+                .unwrap()
+                .is_none()
+        );
+
+        let decommission = |dry_run: bool| json!({"subnet": "/r123/f0100", "dry_run": dry_run});
+        assert!(approval_reason(
+            &config,
+            json_rpc_methods::DECOMMISSION_SUBNET,
+            &decommission(false)
+        )
+        .unwrap()
+        .is_some());
+        assert!(approval_reason(
+            &config,
+            json_rpc_methods::DECOMMISSION_SUBNET,
+            &decommission(true)
+        )
+        .unwrap()
+        .is_none());
+    }
+}
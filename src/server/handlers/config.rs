@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: MIT
 //! Triggers a config reloading
 
-use crate::config::ReloadableConfig;
+use crate::config::{Config, ConfigValidation, ReloadableConfig};
+use crate::persistence::DataDir;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
 use crate::server::JsonRPCRequestHandler;
 use anyhow::anyhow;
 use async_trait::async_trait;
@@ -33,7 +35,7 @@ impl JsonRPCRequestHandler for ReloadConfigHandler {
     type Response = ();
 
     async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
-        log::info!("received request to reload config: {request:?}");
+        tracing::info!("received request to reload config: {request:?}");
 
         if request.path.is_some() {
             self.config.set_path(request.path.unwrap());
@@ -42,24 +44,57 @@ impl JsonRPCRequestHandler for ReloadConfigHandler {
     }
 }
 
-pub fn new_fvm_wallet_from_config(config: Arc<ReloadableConfig>) -> anyhow::Result<KeyStore> {
-    let repo_str = config.get_config_repo();
-    if let Some(repo_str) = repo_str {
-        new_keystore_from_path(&repo_str)
-    } else {
-        Err(anyhow!("No keystore repo found in config"))
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ValidateConfigParams {
+    /// Validates the config at this path instead of the one loaded by the agent, e.g. before
+    /// reloading it.
+    pub path: Option<String>,
+    /// Only checks the config itself, without probing the nodes of the subnets.
+    #[serde(default)]
+    pub offline: bool,
+}
+
+/// The json rpc method handler that validates every subnet of a config and reports the issues
+/// found, instead of them failing the requests that reach the subnets later on.
+pub(crate) struct ValidateConfigHandler {
+    pool: Arc<SubnetManagerPool>,
+    config: Arc<ReloadableConfig>,
+}
+
+impl ValidateConfigHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>, config: Arc<ReloadableConfig>) -> Self {
+        Self { pool, config }
     }
 }
 
+#[async_trait]
+impl JsonRPCRequestHandler for ValidateConfigHandler {
+    type Request = ValidateConfigParams;
+    type Response = ConfigValidation;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let config = match request.path {
+            Some(path) => match Config::from_file_async(&path).await {
+                Ok(config) => Arc::new(config),
+                // a config that does not parse is reported like any other invalid config
+                Err(e) => return Ok(ConfigValidation::unreadable(format!("{path:}: {e:}"))),
+            },
+            None => self.config.get_config(),
+        };
+        Ok(self.pool.validate_config(&config, request.offline).await)
+    }
+}
+
+pub fn new_fvm_wallet_from_config(config: Arc<ReloadableConfig>) -> anyhow::Result<KeyStore> {
+    let data_dir = DataDir::from_config(&config)?;
+    new_keystore_from_path(&data_dir.keystore_repo()?)
+}
+
 pub fn new_evm_keystore_from_config(
     config: Arc<ReloadableConfig>,
 ) -> anyhow::Result<PersistentKeyStore<ethers::types::Address>> {
-    let repo_str = config.get_config_repo();
-    if let Some(repo_str) = repo_str {
-        new_evm_keystore_from_path(&repo_str)
-    } else {
-        Err(anyhow!("No keystore repo found in config"))
-    }
+    let data_dir = DataDir::from_config(&config)?;
+    new_evm_keystore_from_path(&data_dir.keystore_repo()?)
 }
 
 pub fn new_evm_keystore_from_path(
@@ -0,0 +1,50 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Debug tap json rpc method handler
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::jsonrpc::tap::TapEntry;
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DebugTapParams {
+    /// Drops the entries returned from the tap.
+    #[serde(default)]
+    pub clear: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DebugTapResponse {
+    /// The maximum size of the entries recorded, none if the tap is disabled in the config.
+    pub max_bytes: Option<usize>,
+    pub entries: Vec<TapEntry>,
+}
+
+/// Returns the requests to the nodes, and their responses, recorded by the debug tap.
+pub(crate) struct DebugTapHandler {
+    state: Arc<AgentState>,
+}
+
+impl DebugTapHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for DebugTapHandler {
+    type Request = DebugTapParams;
+    type Response = DebugTapResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        Ok(DebugTapResponse {
+            max_bytes: self.state.debug_tap.max_bytes(),
+            entries: self.state.debug_tap.entries(request.clear),
+        })
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Health of the subnets of the agent
+//!
+//! The agent is ready when the nodes of all the subnets of its config are reachable and the last
+//! reload of its config, if any, succeeded. The subnets are checked when the agent starts to
+//! checkpoint them, and again while they are unhealthy; the ones no subsystem checked yet are
+//! checked on the first request.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ReloadStatus, ReloadableConfig};
+use crate::jsonrpc::pool::ConnectionStats;
+use crate::manager::funds::Underfunded;
+use crate::manager::health::{LastSubmission, SubnetHealth};
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HealthParams {
+    /// Checks the nodes of all the subnets again instead of reporting the last checks
+    #[serde(default)]
+    pub probe: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubnetHealthEntry {
+    pub subnet: String,
+    /// The endpoint of the node of the subnet checked
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(flatten)]
+    pub health: SubnetHealth,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubnetConnectionsEntry {
+    pub subnet: String,
+    #[serde(flatten)]
+    pub stats: ConnectionStats,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// Whether the agent is running in degraded mode, i.e. some subnets are unhealthy.
+    pub degraded: bool,
+    /// Whether all the subnets are healthy and the config was loaded, i.e. the agent can serve
+    /// all its workload.
+    #[serde(default)]
+    pub ready: bool,
+    pub subnets: Vec<SubnetHealthEntry>,
+    /// The last checkpoint submitted by the agent in each subnet and direction.
+    #[serde(default)]
+    pub checkpoints: Vec<LastSubmission>,
+    /// The outcome of the last reload of the config, if it was reloaded.
+    #[serde(default)]
+    pub config_reload: Option<ReloadStatus>,
+    /// The validators whose checkpoint votes are skipped for lack of funds.
+    #[serde(default)]
+    pub underfunded: Vec<Underfunded>,
+    /// The use of the pooled connections to the nodes of the subnets.
+    #[serde(default)]
+    pub connections: Vec<SubnetConnectionsEntry>,
+}
+
+/// Returns the health of the subnets checked by the agent.
+pub(crate) struct HealthHandler {
+    pool: Arc<SubnetManagerPool>,
+    config: Arc<ReloadableConfig>,
+}
+
+impl HealthHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>, config: Arc<ReloadableConfig>) -> Self {
+        Self { pool, config }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for HealthHandler {
+    type Request = HealthParams;
+    type Response = HealthResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let state = self.pool.state();
+        let configured = self.pool.subnets();
+        let checked = state.subnet_health.all();
+        let unchecked = configured
+            .values()
+            .filter(|s| request.probe || !checked.contains_key(&s.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unchecked.is_empty() {
+            self.pool.check_health(&unchecked).await;
+        }
+
+        // the subnets removed from the config are not reported anymore
+        let mut subnets = state
+            .subnet_health
+            .all()
+            .into_iter()
+            .filter_map(|(subnet, health)| {
+                Some(SubnetHealthEntry {
+                    endpoint: configured.get(&subnet)?.rpc_http().to_string(),
+                    subnet: subnet.to_string(),
+                    health,
+                })
+            })
+            .collect::<Vec<_>>();
+        subnets.sort_by(|a, b| a.subnet.cmp(&b.subnet));
+        let degraded = subnets.iter().any(|s| !s.health.healthy);
+        let config_reload = self.config.last_reload();
+        let ready = !degraded && config_reload.as_ref().map_or(true, |r| r.error.is_none());
+
+        let mut connections = self
+            .pool
+            .connection_stats()
+            .into_iter()
+            .map(|(subnet, stats)| SubnetConnectionsEntry {
+                subnet: subnet.to_string(),
+                stats,
+            })
+            .collect::<Vec<_>>();
+        connections.sort_by(|a, b| a.subnet.cmp(&b.subnet));
+
+        Ok(HealthResponse {
+            degraded,
+            ready,
+            subnets,
+            checkpoints: state.last_submissions.all(),
+            config_reload,
+            underfunded: state.underfunded_signers.all(),
+            connections,
+        })
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Backfill and query of the checkpoint history indexed by the agent
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ReloadableConfig;
+use crate::history::backfill::{backfill_cursor, Backfill, BackfillCursor, BackfillParams};
+use crate::history::{AccountCrossMsg, CheckpointStore, IndexedCheckpoint};
+use crate::jobs::{self, JobInfo};
+use crate::server::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+/// The backfill history json rpc method handler. The backfill runs in the background as a job,
+/// managed through the job methods.
+pub(crate) struct BackfillHistoryHandler {
+    pool: Arc<SubnetManagerPool>,
+    store: CheckpointStore,
+}
+
+impl BackfillHistoryHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>, store: CheckpointStore) -> Self {
+        Self { pool, store }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for BackfillHistoryHandler {
+    type Request = BackfillParams;
+    type Response = JobInfo;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let params = serde_json::to_value(&request)?;
+        let backfill = Backfill::start(&self.pool, self.store.clone(), request).await?;
+        Ok(jobs::spawn(
+            self.pool.state().clone(),
+            Box::new(backfill),
+            params,
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointHistoryParams {
+    pub subnet: String,
+    pub from_epoch: ChainEpoch,
+    pub to_epoch: ChainEpoch,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointHistoryResponse {
+    pub checkpoints: Vec<IndexedCheckpoint>,
+    /// The progress of the last backfill of the subnet, if any.
+    pub backfill: Option<BackfillCursor>,
+}
+
+/// The checkpoint history json rpc method handler.
+pub(crate) struct CheckpointHistoryHandler {
+    store: CheckpointStore,
+}
+
+impl CheckpointHistoryHandler {
+    pub(crate) fn new(store: CheckpointStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for CheckpointHistoryHandler {
+    type Request = CheckpointHistoryParams;
+    type Response = CheckpointHistoryResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        Ok(CheckpointHistoryResponse {
+            checkpoints: self
+                .store
+                .read(&subnet, request.from_epoch, request.to_epoch)
+                .await?,
+            backfill: backfill_cursor(&self.store, &subnet).await?,
+        })
+    }
+}
+
+/// The number of messages in a page of the history of an account, if not requested.
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrossMsgsByAccountParams {
+    pub account: String,
+    /// The number of messages to skip, from the previous page.
+    #[serde(default)]
+    pub offset: usize,
+    /// Defaults to 100, at most 1000.
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrossMsgsByAccountResponse {
+    pub msgs: Vec<AccountCrossMsg>,
+    /// The number of messages involving the account.
+    pub total: usize,
+    /// The offset of the next page, if any.
+    pub next_offset: Option<usize>,
+}
+
+/// The cross messages by account json rpc method handler, listing the cross-net messages of the
+/// history of the configured subnets sent or received by an account.
+pub(crate) struct CrossMsgsByAccountHandler {
+    config: Arc<ReloadableConfig>,
+    store: CheckpointStore,
+}
+
+impl CrossMsgsByAccountHandler {
+    pub(crate) fn new(config: Arc<ReloadableConfig>, store: CheckpointStore) -> Self {
+        Self { config, store }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for CrossMsgsByAccountHandler {
+    type Request = CrossMsgsByAccountParams;
+    type Response = CrossMsgsByAccountResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let account = Address::from_str(&request.account)?;
+        let limit = request.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return Err(anyhow!("limit must be between 1 and {MAX_PAGE_SIZE:}"));
+        }
+
+        let mut subnets = self
+            .config
+            .get_config()
+            .subnets
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        subnets.sort_by_key(|s| s.to_string());
+
+        // the messages of each subnet are sorted latest first, the subnets by id
+        let mut msgs = vec![];
+        for subnet in subnets {
+            msgs.extend(self.store.cross_msgs_by_account(&subnet, &account).await?);
+        }
+
+        let total = msgs.len();
+        let end = total.min(request.offset.saturating_add(limit));
+        Ok(CrossMsgsByAccountResponse {
+            msgs: msgs.into_iter().skip(request.offset).take(limit).collect(),
+            total,
+            next_offset: (end < total).then_some(end),
+        })
+    }
+}
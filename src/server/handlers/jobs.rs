@@ -0,0 +1,63 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List and control of the background jobs of the agent
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::jobs::{JobAction, JobInfo};
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListJobsParams {}
+
+/// The list jobs json rpc method handler.
+pub(crate) struct ListJobsHandler {
+    state: Arc<AgentState>,
+}
+
+impl ListJobsHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListJobsHandler {
+    type Request = ListJobsParams;
+    type Response = Vec<JobInfo>;
+
+    async fn handle(&self, _request: Self::Request) -> anyhow::Result<Self::Response> {
+        Ok(self.state.jobs.all())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ControlJobParams {
+    pub id: u64,
+    pub action: JobAction,
+}
+
+/// The control job json rpc method handler, to pause, resume or cancel a job.
+pub(crate) struct ControlJobHandler {
+    state: Arc<AgentState>,
+}
+
+impl ControlJobHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ControlJobHandler {
+    type Request = ControlJobParams;
+    type Response = JobInfo;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        self.state.jobs.control(request.id, request.action)
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Query of the journal of the checkpoint votes submitted by the agent
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::journal::{CheckpointJournal, SubmissionRecord};
+use crate::server::JsonRPCRequestHandler;
+
+/// The default number of submissions returned.
+const DEFAULT_SUBMISSIONS_LIMIT: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListCheckpointSubmissionsParams {
+    /// The child subnet of the checkpoints, all the subnets if not set.
+    pub subnet: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListCheckpointSubmissionsResponse {
+    /// The last record of each vote, the latest first.
+    pub submissions: Vec<SubmissionRecord>,
+}
+
+/// The handler listing the checkpoint votes submitted by the agent, from its journal.
+pub(crate) struct ListCheckpointSubmissionsHandler {
+    journal: CheckpointJournal,
+}
+
+impl ListCheckpointSubmissionsHandler {
+    pub(crate) fn new(journal: CheckpointJournal) -> Self {
+        Self { journal }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListCheckpointSubmissionsHandler {
+    type Request = ListCheckpointSubmissionsParams;
+    type Response = ListCheckpointSubmissionsResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = request
+            .subnet
+            .as_deref()
+            .map(SubnetID::from_str)
+            .transpose()?;
+        let mut submissions = self.journal.submissions(subnet.as_ref()).await?;
+        submissions.truncate(request.limit.unwrap_or(DEFAULT_SUBMISSIONS_LIMIT));
+        Ok(ListCheckpointSubmissionsResponse { submissions })
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Maintenance mode json rpc method handler.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::manager::maintenance::{MaintenanceAction, MaintenanceStatus};
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaintenanceParams {
+    pub action: MaintenanceAction,
+    /// Why the agent is put into maintenance, reported in the status.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Puts the agent into maintenance or takes it out of it, and reports whether it is safe to
+/// upgrade.
+pub(crate) struct MaintenanceHandler {
+    state: Arc<AgentState>,
+}
+
+impl MaintenanceHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for MaintenanceHandler {
+    type Request = MaintenanceParams;
+    type Response = MaintenanceStatus;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let maintenance = &self.state.maintenance;
+        Ok(match request.action {
+            MaintenanceAction::Enter => maintenance.enter(request.reason),
+            MaintenanceAction::Exit => maintenance.exit(),
+            MaintenanceAction::Status => maintenance.status(),
+        })
+    }
+}
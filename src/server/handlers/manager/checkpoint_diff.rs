@@ -0,0 +1,76 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Diff of a bottom-up checkpoint template against the committed checkpoint
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::CheckpointDiff;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BottomUpCheckpointDiffParams {
+    /// The child subnet whose checkpoint is compared
+    pub subnet_id: String,
+    pub epoch: ChainEpoch,
+}
+
+/// The bottom-up checkpoint diff json rpc method handler.
+pub(crate) struct BottomUpCheckpointDiffHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl BottomUpCheckpointDiffHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for BottomUpCheckpointDiffHandler {
+    type Request = BottomUpCheckpointDiffParams;
+    type Response = CheckpointDiff;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet_id)?;
+        let parent = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("root subnets have no bottom-up checkpoints"))?;
+
+        // the template is built by the gateway of the child
+        let child = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+        check_subnet(child.subnet())?;
+        let template = child
+            .manager()
+            .bottom_up_checkpoint_template(&child.subnet().gateway_addr(), request.epoch)
+            .await?;
+        tracing::debug!("bottom up template for diff: {template:?}");
+
+        // and committed in the subnet actor in the parent
+        let parent = match self.pool.get(&parent) {
+            None => return Err(anyhow!("parent subnet not found")),
+            Some(conn) => conn,
+        };
+        check_subnet(parent.subnet())?;
+        let committed = parent
+            .manager()
+            .list_checkpoints(subnet, request.epoch, request.epoch)
+            .await?
+            .into_iter()
+            .find(|c| c.epoch == request.epoch);
+        tracing::debug!("committed bottom up checkpoint for diff: {committed:?}");
+
+        CheckpointDiff::new(&template, committed.as_ref())
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Circulating supply of a child subnet
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CirculatingSupplyParams {
+    /// The child subnet whose supply is queried
+    pub subnet_id: String,
+    /// The epoch of the parent to read the circulating supply at, the chain head if not set
+    pub parent_epoch: Option<ChainEpoch>,
+    /// The epoch of the child to read the locked amount at, the chain head if not set
+    pub child_epoch: Option<ChainEpoch>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CirculatingSupplyResponse {
+    /// The circulating supply of the subnet tracked by the gateway of the parent
+    pub circulating_supply: Amount,
+    /// The funds held by the gateway of the subnet, i.e. released and not yet checkpointed. Not
+    /// set if the subnet is not in the agent config.
+    pub locked: Option<Amount>,
+}
+
+/// The circulating supply json rpc method handler.
+pub(crate) struct CirculatingSupplyHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl CirculatingSupplyHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for CirculatingSupplyHandler {
+    type Request = CirculatingSupplyParams;
+    type Response = CirculatingSupplyResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet_id)?;
+        let parent = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("root subnets have no circulating supply in a parent"))?;
+        let conn = match self.pool.get(&parent) {
+            None => return Err(anyhow!("parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let parent_config = conn.subnet();
+        check_subnet(parent_config)?;
+
+        let circulating_supply = conn
+            .manager()
+            .circulating_supply(&parent_config.gateway_addr(), &subnet, request.parent_epoch)
+            .await?;
+
+        let locked = match self.pool.get(&subnet) {
+            Some(child) => {
+                let locked = child
+                    .manager()
+                    .balance_at(&child.subnet().gateway_addr(), request.child_epoch)
+                    .await?;
+                Some(Amount::from(locked))
+            }
+            None => {
+                tracing::debug!("subnet {subnet:} not configured, locked amount unknown");
+                None
+            }
+        };
+
+        Ok(CirculatingSupplyResponse {
+            circulating_supply: Amount::from(circulating_supply),
+            locked,
+        })
+    }
+}
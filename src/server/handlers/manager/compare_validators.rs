@@ -0,0 +1,203 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Consistency check between the validator set of a subnet recorded in its parent and the
+//! membership its consensus runs with. The membership follows the validator set through the
+//! top-down checkpoints, so a divergence that persists means that validator changes were not
+//! propagated.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::lotus::message::ipc::ValidatorSet;
+use crate::server::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareValidatorSetsParams {
+    pub subnet: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightMismatch {
+    pub validator: String,
+    pub parent_weight: String,
+    pub child_weight: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareValidatorSetsResponse {
+    pub subnet: String,
+    /// Whether the membership of the subnet consensus matches the validator set in the parent.
+    pub consistent: bool,
+    pub parent_configuration_number: u64,
+    pub child_configuration_number: u64,
+    /// The validators in the parent the subnet consensus does not know of.
+    pub missing_in_child: Vec<String>,
+    /// The validators the subnet consensus runs with that left, or never joined, in the parent.
+    pub missing_in_parent: Vec<String>,
+    pub weight_mismatches: Vec<WeightMismatch>,
+}
+
+/// The compare validator sets json rpc method handler.
+pub(crate) struct CompareValidatorSetsHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl CompareValidatorSetsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for CompareValidatorSetsHandler {
+    type Request = CompareValidatorSetsParams;
+    type Response = CompareValidatorSetsResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let parent_id = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("root subnets have no parent to compare with"))?;
+        let parent = self
+            .pool
+            .get(&parent_id)
+            .ok_or_else(|| anyhow!("parent subnet {parent_id:} not configured"))?;
+        let child = self
+            .pool
+            .get(&subnet)
+            .ok_or_else(|| anyhow!("subnet {subnet:} not configured"))?;
+
+        let recorded = parent
+            .manager()
+            .get_validator_set(&subnet, Some(parent.subnet().gateway_addr()))
+            .await?
+            .validator_set;
+        let membership = child
+            .manager()
+            .consensus_membership(&child.subnet().gateway_addr())
+            .await?;
+
+        let response = compare(&subnet, &recorded, &membership);
+        if !response.consistent {
+            tracing::warn!(
+                "membership of subnet {subnet:} diverges from its validator set in the parent: {response:?}"
+            );
+        }
+        Ok(response)
+    }
+}
+
+/// The weight of each validator of `set`, by the address the subnet consensus knows it by.
+fn weights(set: &ValidatorSet) -> BTreeMap<String, String> {
+    set.validators
+        .iter()
+        .flatten()
+        .map(|v| {
+            let addr = v.worker_addr.clone().unwrap_or_else(|| v.addr.clone());
+            (addr, v.weight.clone())
+        })
+        .collect()
+}
+
+fn compare(
+    subnet: &SubnetID,
+    recorded: &ValidatorSet,
+    membership: &ValidatorSet,
+) -> CompareValidatorSetsResponse {
+    let parent = weights(recorded);
+    let child = weights(membership);
+
+    let missing_in_child = parent
+        .keys()
+        .filter(|v| !child.contains_key(*v))
+        .cloned()
+        .collect::<Vec<_>>();
+    let missing_in_parent = child
+        .keys()
+        .filter(|v| !parent.contains_key(*v))
+        .cloned()
+        .collect::<Vec<_>>();
+    let weight_mismatches = parent
+        .iter()
+        .filter_map(|(v, parent_weight)| {
+            let child_weight = child.get(v)?;
+            (child_weight != parent_weight).then(|| WeightMismatch {
+                validator: v.clone(),
+                parent_weight: parent_weight.clone(),
+                child_weight: child_weight.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    CompareValidatorSetsResponse {
+        subnet: subnet.to_string(),
+        consistent: missing_in_child.is_empty()
+            && missing_in_parent.is_empty()
+            && weight_mismatches.is_empty(),
+        parent_configuration_number: recorded.configuration_number,
+        child_configuration_number: membership.configuration_number,
+        missing_in_child,
+        missing_in_parent,
+        weight_mismatches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::lotus::message::ipc::{Validator, ValidatorSet};
+    use crate::server::handlers::manager::compare_validators::compare;
+
+    fn set(configuration_number: u64, validators: &[(&str, &str)]) -> ValidatorSet {
+        ValidatorSet {
+            validators: Some(
+                validators
+                    .iter()
+                    .map(|(addr, weight)| Validator {
+                        addr: addr.to_string(),
+                        net_addr: String::new(),
+                        worker_addr: Some(addr.to_string()),
+                        weight: weight.to_string(),
+                    })
+                    .collect(),
+            ),
+            configuration_number,
+        }
+    }
+
+    #[test]
+    fn test_compare_validator_sets() {
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let recorded = set(3, &[("f01001", "10"), ("f01002", "20")]);
+
+        let same = compare(
+            &subnet,
+            &recorded,
+            &set(3, &[("f01002", "20"), ("f01001", "10")]),
+        );
+        assert!(same.consistent);
+
+        // the last join and a stake change were not propagated
+        let diverged = compare(
+            &subnet,
+            &recorded,
+            &set(2, &[("f01001", "5"), ("f01003", "5")]),
+        );
+        assert!(!diverged.consistent);
+        assert_eq!(diverged.missing_in_child, vec!["f01002"]);
+        assert_eq!(diverged.missing_in_parent, vec!["f01003"]);
+        assert_eq!(diverged.weight_mismatches.len(), 1);
+        assert_eq!(diverged.weight_mismatches[0].child_weight, "5");
+        assert_eq!(diverged.child_configuration_number, 2);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Co-signing of the checkpoint votes of a peer agent
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+
+use crate::checkpoint::{
+    checkpoint_direction, setup_manager_from_subnet, vote_policy, CoSignRequest, CoSignResponse,
+    VoteDecision,
+};
+use crate::config::Subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+/// Co-signs the checkpoint votes of the peer agent of a subnet that this agent agrees with.
+pub(crate) struct CoSignVoteHandler {
+    pool: Arc<SubnetManagerPool>,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+}
+
+impl CoSignVoteHandler {
+    pub(crate) fn new(
+        pool: Arc<SubnetManagerPool>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    ) -> Self {
+        Self {
+            pool,
+            fvm_wallet,
+            evm_keystore,
+        }
+    }
+
+    /// Checks the vote of `request` against the node of this agent: the window of the checkpoint
+    /// must be open, its epoch reached, the checkpoint rebuilt from the nodes of this agent must
+    /// have the cid of the one voted, and the vote policy of the subnet must agree with it.
+    async fn check_vote(
+        &self,
+        subnets: &HashMap<SubnetID, Subnet>,
+        subnet: &Subnet,
+        request: &CoSignRequest,
+    ) -> anyhow::Result<VoteDecision> {
+        let validator = Address::from_str(&request.validator)?;
+        let managers = setup_manager_from_subnet(
+            subnets,
+            subnet,
+            self.fvm_wallet.clone(),
+            self.evm_keystore.clone(),
+            self.pool.state(),
+        )
+        .await?;
+        let manager = managers
+            .iter()
+            .find(|m| checkpoint_direction(m.as_ref()) == request.direction)
+            .ok_or_else(|| anyhow!("{} checkpoints not managed by the agent", request.direction))?;
+
+        let last_executed_epoch = manager.last_executed_epoch().await?;
+        if request.epoch <= last_executed_epoch {
+            return Ok(VoteDecision::Abstain(format!(
+                "window already closed, last executed epoch: {last_executed_epoch:}"
+            )));
+        }
+        let current_epoch = manager.current_epoch().await?;
+        if request.epoch > current_epoch {
+            return Ok(VoteDecision::Abstain(format!(
+                "epoch not reached by the node of the co-signing agent, at epoch {current_epoch:}"
+            )));
+        }
+        let checkpoint = manager.checkpoint_cid(request.epoch).await?.to_string();
+        if checkpoint != request.checkpoint {
+            return Ok(VoteDecision::Abstain(format!(
+                "checkpoint {} differs from the one built by the co-signing agent: {checkpoint:}",
+                request.checkpoint
+            )));
+        }
+
+        vote_policy(&subnet.vote_policy)
+            .decide(manager.as_ref(), &validator, request.epoch)
+            .await
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for CoSignVoteHandler {
+    type Request = CoSignRequest;
+    type Response = CoSignResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnets = self.pool.subnets();
+        let subnet = subnets
+            .get(&SubnetID::from_str(&request.subnet)?)
+            .ok_or_else(|| anyhow!("subnet {} not configured", request.subnet))?;
+        let config = subnet
+            .co_signing
+            .as_ref()
+            .ok_or_else(|| anyhow!("co-signing not configured for subnet {}", request.subnet))?;
+        request.verify(config)?;
+
+        let (approved, reason) = match self.check_vote(&subnets, subnet, &request).await {
+            Ok(VoteDecision::Vote) => (true, None),
+            Ok(VoteDecision::Abstain(reason)) => (false, Some(reason)),
+            Err(e) => (false, Some(format!("cannot check vote: {e:}"))),
+        };
+        tracing::info!(
+            "co-signing of {} checkpoint at epoch {} for validator {} in subnet {}: approved {approved:}",
+            request.direction,
+            request.epoch,
+            request.validator,
+            request.subnet
+        );
+
+        CoSignResponse::new(&request, approved, reason, config, &self.evm_keystore)
+    }
+}
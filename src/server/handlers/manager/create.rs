@@ -2,28 +2,140 @@
 // SPDX-License-Identifier: MIT
 //! Create subnet handler and parameters
 
+use crate::amount::{parse_fil, Amount};
+use crate::lotus::message::ipc::PermissionMode;
 use crate::server::handlers::manager::subnet::SubnetManagerPool;
 use crate::server::handlers::manager::{check_subnet, parse_from};
-use crate::server::{handlers, JsonRPCRequestHandler};
+use crate::server::JsonRPCRequestHandler;
 use anyhow::anyhow;
 use async_trait::async_trait;
+use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
 use ipc_sdk::subnet_id::SubnetID;
 use ipc_subnet_actor::{ConsensusType, ConstructParams};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::sync::Arc;
 
+/// The shortest checkpoint period, in epochs, of the subnet actors. Shorter periods are raised to
+/// it by the actors, so they are rejected instead of silently creating a different subnet.
+pub const MIN_CHECK_PERIOD: ChainEpoch = 10;
+
+/// The consensus a subnet runs, the subnet actors deployed by the registry only support Mir.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubnetConsensus {
+    #[default]
+    Mir,
+}
+
+impl From<SubnetConsensus> for ConsensusType {
+    fn from(consensus: SubnetConsensus) -> Self {
+        match consensus {
+            SubnetConsensus::Mir => ConsensusType::Mir,
+        }
+    }
+}
+
+/// Where the circulating supply of a subnet comes from.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SupplySource {
+    /// The native token of the parent, locked in the gateway when funding the subnet.
+    #[default]
+    Native,
+    /// An ERC20 token of the parent.
+    Erc20 { token: String },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateSubnetParams {
     pub from: Option<String>,
     pub parent: String,
     pub name: String,
+    /// Mir if not set.
+    #[serde(default)]
+    pub consensus: SubnetConsensus,
     /// In whole FIL
     pub min_validator_stake: f64,
     pub min_validators: u64,
     pub bottomup_check_period: ChainEpoch,
     pub topdown_check_period: ChainEpoch,
+    /// The maximum number of active validators, unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_validators_limit: Option<u64>,
+    /// Validators join with collateral if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<PermissionMode>,
+    /// The native token of the parent if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supply_source: Option<SupplySource>,
+}
+
+impl CreateSubnetParams {
+    /// Checks the params against the values the registry accepts, and returns the params of the
+    /// constructor of the subnet actor deployed in `parent` with the gateway `gateway_addr`.
+    pub fn construct_params(
+        &self,
+        parent: SubnetID,
+        gateway_addr: Address,
+    ) -> anyhow::Result<ConstructParams> {
+        if self.name.is_empty() {
+            return Err(anyhow!("the name of the subnet cannot be empty"));
+        }
+        let min_validator_stake = parse_fil("min_validator_stake", self.min_validator_stake)?;
+        if min_validator_stake.is_zero() {
+            return Err(anyhow!("min_validator_stake must be above 0"));
+        }
+        for (field, period) in [
+            ("bottomup_check_period", self.bottomup_check_period),
+            ("topdown_check_period", self.topdown_check_period),
+        ] {
+            if period < MIN_CHECK_PERIOD {
+                return Err(anyhow!(
+                    "{field:} must be at least {MIN_CHECK_PERIOD:} epochs, got {period:}"
+                ));
+            }
+        }
+
+        // the subnet actors deployed by the registry have no limit on their validators, admit
+        // them by collateral and are funded with the native token, other values are refused
+        // rather than ignored
+        if let Some(limit) = self.active_validators_limit {
+            return Err(anyhow!(
+                "active_validators_limit {limit:} is not supported by the subnet actors of the registry, their active validators are unlimited"
+            ));
+        }
+        match self.permission_mode {
+            None | Some(PermissionMode::Collateral) => {}
+            Some(mode) => {
+                return Err(anyhow!(
+                    "permission mode {mode:} is not supported by the subnet actors of the registry, only collateral"
+                ))
+            }
+        }
+        match &self.supply_source {
+            None | Some(SupplySource::Native) => {}
+            Some(SupplySource::Erc20 { token }) => {
+                return Err(anyhow!(
+                    "erc20 supply source {token:} is not supported by the subnet actors of the registry, only native"
+                ))
+            }
+        }
+
+        Ok(ConstructParams {
+            parent,
+            name: self.name.clone(),
+            ipc_gateway_addr: gateway_addr,
+            consensus: self.consensus.into(),
+            min_validator_stake,
+            min_validators: self.min_validators,
+            bottomup_check_period: self.bottomup_check_period,
+            topdown_check_period: self.topdown_check_period,
+            genesis: vec![],
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,6 +144,22 @@ pub struct CreateSubnetResponse {
     pub address: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewCreateSubnetResponse {
+    /// The address the subnet would be created from
+    pub from: String,
+    /// The fee charged by the registry to deploy the subnet actor, if the cost of the creation
+    /// could be estimated
+    pub registry_fee: Option<Amount>,
+    /// The estimated maximum fee of the creation, if it could be estimated
+    pub estimated_gas_fee: Option<Amount>,
+    /// The collateral the validators stake in total for the subnet to be active, i.e.
+    /// `min_validators` times `min_validator_stake`
+    pub initial_collateral: Amount,
+    /// The initial collateral plus the registry and gas fees, if they could be estimated
+    pub total: Amount,
+}
+
 /// The create subnet json rpc method handler.
 pub(crate) struct CreateSubnetHandler {
     pool: Arc<SubnetManagerPool>,
@@ -58,20 +186,10 @@ impl JsonRPCRequestHandler for CreateSubnetHandler {
         let subnet_config = conn.subnet();
         check_subnet(subnet_config)?;
 
-        let constructor_params = ConstructParams {
-            parent,
-            name: request.name,
-            ipc_gateway_addr: subnet_config.gateway_addr(),
-            consensus: ConsensusType::Mir,
-            min_validator_stake: handlers::f64_to_token_amount(request.min_validator_stake)?,
-            min_validators: request.min_validators,
-            bottomup_check_period: request.bottomup_check_period,
-            topdown_check_period: request.topdown_check_period,
-            genesis: vec![],
-        };
+        let constructor_params = request.construct_params(parent, subnet_config.gateway_addr())?;
 
         let from = parse_from(subnet_config, request.from)?;
-        log::debug!("conn: {:?}, from: {from:?}", conn.subnet());
+        tracing::debug!("conn: {:?}, from: {from:?}", conn.subnet());
 
         let created_subnet_addr = conn
             .manager()
@@ -83,3 +201,123 @@ impl JsonRPCRequestHandler for CreateSubnetHandler {
         })
     }
 }
+
+/// The json rpc method handler that estimates what creating a subnet costs, without creating
+/// it, so that deployments can be budgeted.
+pub(crate) struct PreviewCreateSubnetHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl PreviewCreateSubnetHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for PreviewCreateSubnetHandler {
+    type Request = CreateSubnetParams;
+    type Response = PreviewCreateSubnetResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let parent = SubnetID::from_str(&request.parent)?;
+        let conn = match self.pool.get(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        let constructor_params = request.construct_params(parent, subnet_config.gateway_addr())?;
+        let initial_collateral = TokenAmount::from_atto(
+            constructor_params.min_validator_stake.atto() * request.min_validators,
+        );
+
+        let from = parse_from(subnet_config, request.from)?;
+        let cost = match conn
+            .manager()
+            .estimate_create_subnet(from, constructor_params)
+            .await
+        {
+            Ok(cost) => Some(cost),
+            Err(e) => {
+                // estimation fails if the sender does not have the funds yet
+                tracing::warn!(
+                    "cannot estimate the cost to create subnet {} from {from:}: {e:}",
+                    request.name
+                );
+                None
+            }
+        };
+
+        let mut total = initial_collateral.clone();
+        if let Some(cost) = &cost {
+            total = total + cost.registry_fee.clone() + cost.gas_fee.clone();
+        }
+
+        Ok(PreviewCreateSubnetResponse {
+            from: from.to_string(),
+            registry_fee: cost.as_ref().map(|c| Amount::from(&c.registry_fee)),
+            estimated_gas_fee: cost.as_ref().map(|c| Amount::from(&c.gas_fee)),
+            initial_collateral: Amount::from(initial_collateral),
+            total: Amount::from(total),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::subnet_id::SubnetID;
+    use serde_json::json;
+
+    use crate::lotus::message::ipc::PermissionMode;
+    use crate::server::handlers::manager::create::{CreateSubnetParams, SupplySource};
+
+    #[test]
+    fn test_construct_params() {
+        let params = json!({
+            "parent": "/r123",
+            "name": "test",
+            "min_validator_stake": 1.5,
+            "min_validators": 2,
+            "bottomup_check_period": 30,
+            "topdown_check_period": 10,
+        });
+        let mut params: CreateSubnetParams = serde_json::from_value(params).unwrap();
+        let construct = |p: &CreateSubnetParams| {
+            p.construct_params(SubnetID::new_root(123), Address::new_id(64))
+        };
+        let constructed = construct(&params).unwrap();
+        assert_eq!(constructed.min_validators, 2);
+        assert_eq!(constructed.bottomup_check_period, 30);
+        assert_eq!(
+            constructed.min_validator_stake,
+            TokenAmount::from_nano(1_500_000_000)
+        );
+
+        params.topdown_check_period = 5;
+        assert!(construct(&params).is_err());
+        params.topdown_check_period = 10;
+        params.min_validator_stake = 0.0;
+        assert!(construct(&params).is_err());
+        params.min_validator_stake = 1.0;
+
+        // the options the subnet actors do not support are refused, not ignored
+        params.permission_mode = Some(PermissionMode::Collateral);
+        params.supply_source = Some(SupplySource::Native);
+        assert!(construct(&params).is_ok());
+        params.permission_mode = Some(PermissionMode::Federated);
+        assert!(construct(&params).is_err());
+        params.permission_mode = None;
+        params.supply_source = Some(SupplySource::Erc20 {
+            token: String::from("0x1234"),
+        });
+        assert!(construct(&params).is_err());
+        params.supply_source = None;
+        params.active_validators_limit = Some(100);
+        assert!(construct(&params).is_err());
+    }
+}
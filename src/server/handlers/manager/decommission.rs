@@ -0,0 +1,327 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Clean up of a killed subnet: the collateral of the validators of the agent is released, the
+//! balances of its accounts in the subnet are drained back to the parent, and the subnet is
+//! removed from the config with its history archived.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_gateway::Status;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, ReloadableConfig};
+use crate::history::CheckpointStore;
+use crate::manager::offline::OfflineCall;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::import::child_subnets;
+use crate::server::handlers::manager::subnet::{Connection, SubnetManagerPool};
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecommissionSubnetParams {
+    pub subnet: String,
+    /// Only report the steps of the clean up, without running them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecommissionStatus {
+    /// Reported by a dry run.
+    Planned,
+    Done,
+    Failed,
+    /// Not run, as an earlier step failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecommissionStep {
+    pub description: String,
+    pub status: DecommissionStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecommissionSubnetResponse {
+    pub subnet: String,
+    pub dry_run: bool,
+    pub steps: Vec<DecommissionStep>,
+}
+
+/// A step of the clean up, planned from the state of the subnet before any is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// A validator of the agent leaves the subnet to get its collateral back.
+    ReleaseCollateral {
+        validator: Address,
+    },
+    /// The balance of an account, minus the fee of the release, is released to the same
+    /// account in the parent.
+    DrainBalance {
+        account: Address,
+        amount: TokenAmount,
+    },
+    RemoveFromConfig,
+    ArchiveHistory {
+        files: usize,
+    },
+}
+
+impl Step {
+    fn description(&self, subnet: &SubnetID) -> String {
+        match self {
+            Step::ReleaseCollateral { validator } => {
+                format!("release the collateral of {validator:} in {subnet:}")
+            }
+            Step::DrainBalance { account, amount } => {
+                format!("release {amount:} FIL of {account:} from {subnet:} to the parent")
+            }
+            Step::RemoveFromConfig => format!("remove {subnet:} from the config"),
+            Step::ArchiveHistory { files } => {
+                format!("archive the {files:} history files of {subnet:}")
+            }
+        }
+    }
+
+    /// Whether the step moves funds. The subnet is only forgotten once all of these succeeded,
+    /// so that the failed ones can be retried.
+    fn moves_funds(&self) -> bool {
+        matches!(
+            self,
+            Step::ReleaseCollateral { .. } | Step::DrainBalance { .. }
+        )
+    }
+}
+
+/// The decommission subnet json rpc method handler.
+pub(crate) struct DecommissionSubnetHandler {
+    pool: Arc<SubnetManagerPool>,
+    config: Arc<ReloadableConfig>,
+    store: CheckpointStore,
+}
+
+impl DecommissionSubnetHandler {
+    pub(crate) fn new(
+        pool: Arc<SubnetManagerPool>,
+        config: Arc<ReloadableConfig>,
+        store: CheckpointStore,
+    ) -> Self {
+        Self {
+            pool,
+            config,
+            store,
+        }
+    }
+
+    /// Plans the clean up of `subnet`, failing if any of the state it depends on cannot be read.
+    async fn plan(&self, subnet: &SubnetID, parent: &Connection) -> anyhow::Result<Vec<Step>> {
+        let mut steps = vec![];
+
+        let validators = parent
+            .manager()
+            .get_validator_set(subnet, Some(parent.subnet().gateway_addr()))
+            .await?
+            .validator_set
+            .validators
+            .unwrap_or_default();
+        for account in parent.subnet().accounts() {
+            if validators.iter().any(|v| v.addr == account.to_string()) {
+                steps.push(Step::ReleaseCollateral { validator: account });
+            }
+        }
+
+        if let Some(child) = self.pool.get(subnet) {
+            for account in child.subnet().accounts() {
+                if let Some(amount) = drainable_balance(subnet, &child, account).await? {
+                    steps.push(Step::DrainBalance { account, amount });
+                }
+            }
+        }
+
+        let config = Config::from_file_async(&self.config.get_path()).await?;
+        if config.subnets.contains_key(subnet) {
+            steps.push(Step::RemoveFromConfig);
+        }
+
+        let files = self.store.files(subnet).len();
+        if files > 0 {
+            steps.push(Step::ArchiveHistory { files });
+        }
+
+        Ok(steps)
+    }
+
+    async fn run(&self, subnet: &SubnetID, parent: &Connection, step: &Step) -> anyhow::Result<()> {
+        match step {
+            Step::ReleaseCollateral { validator } => {
+                parent
+                    .manager()
+                    .leave_subnet(subnet.clone(), *validator)
+                    .await
+            }
+            Step::DrainBalance { account, amount } => {
+                let child = self
+                    .pool
+                    .get(subnet)
+                    .ok_or_else(|| anyhow!("subnet {subnet:} not configured"))?;
+                check_subnet(child.subnet())?;
+                child
+                    .manager()
+                    .release(
+                        subnet.clone(),
+                        child.subnet().gateway_addr(),
+                        *account,
+                        *account,
+                        amount.clone(),
+                    )
+                    .await?;
+                Ok(())
+            }
+            Step::RemoveFromConfig => {
+                // the config file is read again so that only the subnet is removed from it
+                let path = self.config.get_path();
+                let mut config = Config::from_file_async(&path).await?;
+                config.remove_subnet(subnet);
+                config.write_to_file_async(&path).await?;
+                tracing::info!("subnet {subnet:} removed from config {path:}");
+                self.config.reload().await
+            }
+            Step::ArchiveHistory { .. } => {
+                let archived = self.store.archive(subnet).await?;
+                tracing::info!("history of subnet {subnet:} archived to {archived:?}");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns what `account` can release from `subnet` once the fee of the release is paid, if
+/// anything.
+async fn drainable_balance(
+    subnet: &SubnetID,
+    child: &Connection,
+    account: Address,
+) -> anyhow::Result<Option<TokenAmount>> {
+    let balance = child.manager().wallet_balance(&account).await?;
+    if balance.is_zero() {
+        return Ok(None);
+    }
+
+    // the gas of a release does not depend on its amount, half of the balance is estimated so
+    // that the estimation itself is covered
+    let fee = child
+        .manager()
+        .estimate_fee(
+            child.subnet().gateway_addr(),
+            account,
+            OfflineCall::Release {
+                subnet: subnet.clone(),
+                to: account,
+                amount: balance.div_floor(2),
+            },
+        )
+        .await?;
+    if balance <= fee {
+        tracing::info!(
+            "balance of {account:} in subnet {subnet:} does not cover the fee of its release, left as is"
+        );
+        return Ok(None);
+    }
+    Ok(Some(balance - fee))
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for DecommissionSubnetHandler {
+    type Request = DecommissionSubnetParams;
+    type Response = DecommissionSubnetResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let parent_id = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("subnet {subnet:} has no parent"))?;
+        let children = child_subnets(&self.pool, &parent_id).await?;
+        match children.get(&subnet).map(|info| &info.status) {
+            None => return Err(anyhow!("subnet {subnet:} is not a child of {parent_id:}")),
+            Some(Status::Killed) => {}
+            Some(_) => return Err(anyhow!("subnet {subnet:} has not been killed")),
+        }
+        let parent = self
+            .pool
+            .get(&parent_id)
+            .ok_or_else(|| anyhow!("parent subnet {parent_id:} not configured"))?;
+
+        let plan = self.plan(&subnet, &parent).await?;
+        if plan.iter().any(|s| s.moves_funds()) {
+            check_subnet(parent.subnet())?;
+        }
+
+        let mut steps = vec![];
+        let mut funds_failed = false;
+        for step in plan {
+            let (status, error) = if request.dry_run {
+                (DecommissionStatus::Planned, None)
+            } else if funds_failed && !step.moves_funds() {
+                (DecommissionStatus::Skipped, None)
+            } else {
+                match self.run(&subnet, &parent, &step).await {
+                    Ok(()) => (DecommissionStatus::Done, None),
+                    Err(e) => {
+                        tracing::warn!(
+                            "cannot {} while decommissioning: {e:}",
+                            step.description(&subnet)
+                        );
+                        funds_failed |= step.moves_funds();
+                        (DecommissionStatus::Failed, Some(e.to_string()))
+                    }
+                }
+            };
+            steps.push(DecommissionStep {
+                description: step.description(&subnet),
+                status,
+                error,
+            });
+        }
+
+        Ok(DecommissionSubnetResponse {
+            subnet: subnet.to_string(),
+            dry_run: request.dry_run,
+            steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::server::handlers::manager::decommission::Step;
+
+    #[test]
+    fn test_step_description() {
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let drain = Step::DrainBalance {
+            account: Address::new_id(1001),
+            amount: TokenAmount::from_whole(2),
+        };
+        assert_eq!(
+            drain.description(&subnet),
+            "release 2 FIL of f01001 from /r123/f0100 to the parent"
+        );
+        assert!(drain.moves_funds());
+        assert!(!Step::RemoveFromConfig.moves_funds());
+        assert!(!Step::ArchiveHistory { files: 2 }.moves_funds());
+    }
+}
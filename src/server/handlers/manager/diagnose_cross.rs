@@ -0,0 +1,337 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Diagnosis of a cross message stuck on its route.
+//!
+//! A message is carried to the next subnet of its route by a checkpoint, and waits in the postbox
+//! of every intermediate subnet until it is propagated from there. Given the subnet the message
+//! was last sent or propagated from and its nonce in the gateway of that subnet, the handler
+//! finds which of these steps the message waits on and the action that gets it moving again. The
+//! agent propagates the message itself when asked to and it can.
+
+use crate::checkpoint::next_checkpoint_epoch;
+use crate::server::handlers::manager::send_cross::{check_leg, route_legs, Leg};
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::{check_subnet, parse_from, JsonRPCRequestHandler};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagnoseCrossMsgParams {
+    /// The subnet the message was sent, or last propagated, from
+    pub subnet: String,
+    /// The destination subnet of the message
+    pub destination: String,
+    /// The nonce of the message in the gateway of `subnet`
+    pub nonce: u64,
+    /// The key of the message in the postbox it waits in, needed to propagate it
+    pub postbox_msg_key: Option<Vec<u8>>,
+    /// The account propagating the message, the first account of its subnet if not set
+    pub from: Option<String>,
+    /// Execute the remediation if the agent can
+    #[serde(default)]
+    pub execute: bool,
+}
+
+/// What the message waits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StuckReason {
+    /// The message reached its destination subnet.
+    Delivered,
+    /// The checkpoint carrying the message is not committed yet.
+    AwaitingCheckpoint,
+    /// The checkpoints carrying the message stopped, or the subnet is not active.
+    StalledCheckpoint,
+    /// The message is in the postbox of an intermediate subnet.
+    UnpropagatedPostbox,
+    /// The message is in the postbox, and the account propagating it cannot pay the fee.
+    InsufficientFee,
+}
+
+/// What gets the message moving again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemediationAction {
+    None,
+    /// Wait for the next checkpoint.
+    Wait,
+    /// Propagate the message from the postbox.
+    Propagate,
+    /// Fund the account propagating the message.
+    TopUp,
+    /// Contact the operators of the subnet whose checkpoints stalled.
+    ContactOperator,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagnoseCrossMsgResponse {
+    /// The subnets of the route, from `subnet` to the destination
+    pub route: Vec<String>,
+    /// The subnet the message waits in, or the destination once delivered
+    pub waiting_in: String,
+    pub reason: StuckReason,
+    pub detail: String,
+    pub action: RemediationAction,
+    /// The remediation, for the operator
+    pub remediation: String,
+    /// The epochs until the next checkpoint carrying the message, when waiting for it
+    pub wait_epochs: Option<ChainEpoch>,
+    /// Whether the agent executed the remediation
+    pub executed: bool,
+}
+
+/// Returns what a message with `nonce` waits on in the first leg of its route, given the nonce
+/// `applied` next by the gateway at the end of the leg, whether the checkpoints of the leg are
+/// `stalled` and whether the leg is the `last` of the route.
+fn leg_reason(nonce: u64, applied: u64, stalled: bool, last: bool) -> StuckReason {
+    if nonce >= applied {
+        if stalled {
+            StuckReason::StalledCheckpoint
+        } else {
+            StuckReason::AwaitingCheckpoint
+        }
+    } else if last {
+        StuckReason::Delivered
+    } else {
+        StuckReason::UnpropagatedPostbox
+    }
+}
+
+/// The diagnose cross message json rpc method handler.
+pub(crate) struct DiagnoseCrossMsgHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl DiagnoseCrossMsgHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Returns the nonce of the next message of the leg applied by the gateway at its end, and
+    /// the epochs until the next checkpoint of the leg.
+    async fn leg_progress(&self, leg: &Leg) -> anyhow::Result<(u64, ChainEpoch)> {
+        let (child, parent) = match (self.pool.get(&leg.child), self.pool.get(&leg.parent)) {
+            (Some(c), Some(p)) => (c, p),
+            _ => return Err(anyhow!("subnet {} or its parent not configured", leg.child)),
+        };
+        let child_gateway = child.subnet().gateway_addr();
+
+        let (applied, period, head) = if leg.bottom_up {
+            let applied = parent
+                .manager()
+                .applied_bottom_up_nonce(&parent.subnet().gateway_addr(), &leg.child)
+                .await?;
+            let period = child
+                .manager()
+                .bottom_up_check_period(&child_gateway)
+                .await?;
+            let head = child.manager().chain_head_epoch().await?;
+            (applied, period, head)
+        } else {
+            let applied = child
+                .manager()
+                .applied_top_down_nonce(&child_gateway)
+                .await?;
+            let period = child
+                .manager()
+                .top_down_check_period(&child_gateway)
+                .await?;
+            let head = parent.manager().chain_head_epoch().await?;
+            (applied, period, head)
+        };
+
+        let wait = if period > 0 {
+            next_checkpoint_epoch(head, period) - head
+        } else {
+            0
+        };
+        Ok((applied, wait))
+    }
+
+    /// Diagnoses a message waiting in the postbox of `subnet`, propagating it if `execute` is set
+    /// and the agent can.
+    async fn diagnose_postbox(
+        &self,
+        subnet: &SubnetID,
+        request: &DiagnoseCrossMsgParams,
+        response: &mut DiagnoseCrossMsgResponse,
+    ) -> anyhow::Result<()> {
+        response.waiting_in = subnet.to_string();
+        response.action = RemediationAction::Propagate;
+        response.detail = format!(
+            "the message was delivered to the postbox of subnet {subnet:}, it may not have been propagated yet"
+        );
+
+        let conn = match self.pool.get(subnet) {
+            Some(conn) => conn,
+            None => {
+                response.remediation = format!(
+                    "propagate the message from the postbox of subnet {subnet:}, which is not in the config of the agent"
+                );
+                return Ok(());
+            }
+        };
+        let subnet_config = conn.subnet();
+        let from = parse_from(subnet_config, request.from.clone())?;
+
+        let fee = conn
+            .manager()
+            .cross_msg_fee(&subnet_config.gateway_addr())
+            .await?;
+        let balance = conn.manager().wallet_balance(&from).await?;
+        if balance < fee {
+            let missing: TokenAmount = fee.clone() - balance;
+            response.reason = StuckReason::InsufficientFee;
+            response.action = RemediationAction::TopUp;
+            response.detail = format!(
+                "the message is in the postbox of subnet {subnet:}, and {from:} cannot pay the cross message fee of {fee:} to propagate it"
+            );
+            response.remediation = format!(
+                "send at least {missing:} to {from:} in subnet {subnet:}, then propagate the message"
+            );
+            return Ok(());
+        }
+
+        response.remediation =
+            format!("propagate the message from the postbox of subnet {subnet:} with {from:}");
+        if !request.execute {
+            return Ok(());
+        }
+        let key = match &request.postbox_msg_key {
+            Some(key) => key.clone(),
+            None => {
+                response.remediation = format!(
+                    "{}, the key of the message in the postbox is needed to propagate it",
+                    response.remediation
+                );
+                return Ok(());
+            }
+        };
+        if !subnet_config.features.propagate {
+            return Err(anyhow!(
+                "cross-msg propagation is disabled for subnet {subnet:}"
+            ));
+        }
+        check_subnet(subnet_config)?;
+
+        conn.manager()
+            .propagate(
+                subnet.clone(),
+                subnet_config.gateway_addr(),
+                from,
+                key,
+                TokenAmount::from_atto(0),
+            )
+            .await?;
+        tracing::info!("propagated a stuck cross message in subnet {subnet:} with {from:}");
+        response.executed = true;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for DiagnoseCrossMsgHandler {
+    type Request = DiagnoseCrossMsgParams;
+    type Response = DiagnoseCrossMsgResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let source = SubnetID::from_str(&request.subnet)?;
+        let destination = SubnetID::from_str(&request.destination)?;
+        let legs = route_legs(&source, &destination)?;
+        let leg = legs
+            .first()
+            .ok_or_else(|| anyhow!("the message is sent within subnet {source:}"))?;
+        let next = if leg.bottom_up {
+            leg.parent.clone()
+        } else {
+            leg.child.clone()
+        };
+
+        let mut route = vec![source.to_string()];
+        for l in legs.iter() {
+            route.push(if l.bottom_up { &l.parent } else { &l.child }.to_string());
+        }
+
+        let (applied, wait) = self.leg_progress(leg).await?;
+        let issue = if request.nonce >= applied {
+            check_leg(&self.pool, leg).await?
+        } else {
+            None
+        };
+        let reason = leg_reason(request.nonce, applied, issue.is_some(), legs.len() == 1);
+
+        let direction = if leg.bottom_up {
+            "bottom-up"
+        } else {
+            "top-down"
+        };
+        let mut response = DiagnoseCrossMsgResponse {
+            route,
+            waiting_in: source.to_string(),
+            reason,
+            detail: String::new(),
+            action: RemediationAction::None,
+            remediation: String::new(),
+            wait_epochs: None,
+            executed: false,
+        };
+        match reason {
+            StuckReason::Delivered => {
+                response.waiting_in = destination.to_string();
+                response.detail = format!("the message was applied in subnet {destination:}");
+                response.remediation = String::from("nothing to do");
+            }
+            StuckReason::AwaitingCheckpoint => {
+                response.action = RemediationAction::Wait;
+                response.wait_epochs = Some(wait);
+                response.detail = format!(
+                    "the message waits for a {direction:} checkpoint of subnet {} to reach subnet {next:}, {applied:} messages applied so far",
+                    leg.child
+                );
+                response.remediation =
+                    format!("wait for the next checkpoint, due in {wait:} epochs");
+            }
+            StuckReason::StalledCheckpoint => {
+                response.action = RemediationAction::ContactOperator;
+                response.detail = issue.unwrap_or_default();
+                response.remediation = format!(
+                    "contact the operators of subnet {} to get its {direction:} checkpoints committed again",
+                    leg.child
+                );
+            }
+            StuckReason::UnpropagatedPostbox | StuckReason::InsufficientFee => {
+                self.diagnose_postbox(&next, &request, &mut response)
+                    .await?;
+            }
+        }
+
+        tracing::debug!("diagnosis of cross message: {response:?}");
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::handlers::manager::diagnose_cross::{leg_reason, StuckReason};
+
+    #[test]
+    fn test_leg_reason() {
+        assert_eq!(
+            leg_reason(5, 5, false, false),
+            StuckReason::AwaitingCheckpoint
+        );
+        assert_eq!(leg_reason(6, 5, true, true), StuckReason::StalledCheckpoint);
+        // once applied, stalled checkpoints do not hold the message anymore
+        assert_eq!(leg_reason(4, 5, true, true), StuckReason::Delivered);
+        assert_eq!(
+            leg_reason(4, 5, false, false),
+            StuckReason::UnpropagatedPostbox
+        );
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Set federated power handler and parameters
+
+use crate::lotus::message::ipc::{FederatedValidator, QueryValidatorSetResponse};
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::handlers::manager::{check_subnet, parse_from};
+use crate::server::JsonRPCRequestHandler;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethers::prelude::k256::ecdsa::VerifyingKey;
+use ethers::utils::public_key_to_address;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The length of an uncompressed secp256k1 public key.
+const PUBLIC_KEY_LEN: usize = 65;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FederatedPower {
+    /// The hex encoded uncompressed secp256k1 public key of the validator
+    pub public_key: String,
+    /// The power of the validator, zero removes it from the validator set
+    pub power: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetFederatedPowerParams {
+    pub subnet: String,
+    pub from: Option<String>,
+    pub validators: Vec<FederatedPower>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetFederatedPowerResponse {
+    /// The address of each validator whose power was set, derived from its public key
+    pub addresses: Vec<String>,
+    /// The validator set of the subnet after setting the power, if it could be queried
+    pub validator_set: Option<QueryValidatorSetResponse>,
+}
+
+/// Parses a hex encoded uncompressed secp256k1 public key, the only format accepted by the
+/// subnet actor, and returns it together with the address of the validator.
+fn parse_public_key(key: &str) -> anyhow::Result<FederatedValidator> {
+    let public_key = hex::decode(key.trim_start_matches("0x"))
+        .map_err(|_| anyhow!("public key {key:} is not hex encoded"))?;
+    if public_key.len() != PUBLIC_KEY_LEN || public_key[0] != 0x04 {
+        return Err(anyhow!(
+            "public key {key:} is not an uncompressed secp256k1 key"
+        ));
+    }
+    let verifying_key = VerifyingKey::from_sec1_bytes(&public_key)
+        .map_err(|_| anyhow!("public key {key:} is not a valid secp256k1 key"))?;
+
+    Ok(FederatedValidator {
+        address: ethers_address_to_fil_address(&public_key_to_address(&verifying_key))?,
+        public_key,
+        power: 0,
+    })
+}
+
+/// The json rpc method handler for the owner of a federated subnet to set its validators.
+pub(crate) struct SetFederatedPowerHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl SetFederatedPowerHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for SetFederatedPowerHandler {
+    type Request = SetFederatedPowerParams;
+    type Response = SetFederatedPowerResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.pool.get(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        if request.validators.is_empty() {
+            return Err(anyhow!("no validators to set the power of"));
+        }
+        let mut validators = vec![];
+        let mut seen = HashSet::new();
+        for v in request.validators {
+            let mut validator = parse_public_key(&v.public_key)?;
+            if !seen.insert(validator.address) {
+                return Err(anyhow!(
+                    "validator {} set more than once",
+                    validator.address
+                ));
+            }
+            validator.power = v.power;
+            validators.push(validator);
+        }
+        let addresses = validators.iter().map(|v| v.address.to_string()).collect();
+
+        let from = parse_from(subnet_config, request.from)?;
+        conn.manager()
+            .set_federated_power(&subnet, from, validators)
+            .await?;
+
+        let validator_set = match conn
+            .manager()
+            .get_validator_set(&subnet, Some(subnet_config.gateway_addr()))
+            .await
+        {
+            Ok(v) => Some(v),
+            Err(e) => {
+                tracing::warn!("cannot query the validator set of subnet {subnet:}: {e:}");
+                None
+            }
+        };
+
+        Ok(SetFederatedPowerResponse {
+            addresses,
+            validator_set,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manager::evm::ethers_address_to_fil_address;
+    use crate::server::handlers::manager::federated_power::parse_public_key;
+    use ethers::core::rand::thread_rng;
+    use ethers::prelude::k256::ecdsa::SigningKey;
+    use ethers::utils::secret_key_to_address;
+
+    #[test]
+    fn test_parse_public_key() {
+        let key = SigningKey::random(&mut thread_rng());
+        let public_key = key.verifying_key().to_encoded_point(false);
+
+        let validator = parse_public_key(&hex::encode(public_key.as_bytes())).unwrap();
+        assert_eq!(
+            validator.address,
+            ethers_address_to_fil_address(&secret_key_to_address(&key)).unwrap()
+        );
+        assert_eq!(validator.public_key, public_key.as_bytes());
+
+        // compressed keys are not accepted by the subnet actor
+        let compressed = key.verifying_key().to_encoded_point(true);
+        assert!(parse_public_key(&hex::encode(compressed.as_bytes())).is_err());
+        assert!(parse_public_key("not hex").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Notices of top-down checkpoints from the federated agent of a subnet
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ipc_sdk::subnet_id::SubnetID;
+
+use crate::checkpoint::TopDownNotice;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+/// Accepts the notices of the top-down checkpoints of a subnet reached by its parent, signed by
+/// the trusted peer of the subnet.
+pub(crate) struct NotifyTopDownCheckpointHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl NotifyTopDownCheckpointHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for NotifyTopDownCheckpointHandler {
+    type Request = TopDownNotice;
+    type Response = ();
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let subnets = self.pool.subnets();
+        let peer = subnets
+            .get(&subnet)
+            .ok_or_else(|| anyhow!("subnet {subnet:} not configured"))?
+            .federation
+            .as_ref()
+            .and_then(|f| f.trusted_peer)
+            .ok_or_else(|| anyhow!("no trusted peer configured for subnet {subnet:}"))?;
+        request.verify(peer)?;
+
+        if self
+            .pool
+            .state()
+            .top_down_notices
+            .record(&subnet, request.epoch)
+        {
+            tracing::info!(
+                "notified of the top-down checkpoint at epoch {} of subnet {subnet:}",
+                request.epoch
+            );
+        }
+        Ok(())
+    }
+}
@@ -2,8 +2,15 @@
 // SPDX-License-Identifier: MIT
 //! Fund operation in the gateway actor
 
-use crate::server::handlers::manager::subnet::SubnetManagerPool;
-use crate::server::{check_subnet, handlers, parse_from, JsonRPCRequestHandler};
+use crate::amount::{parse_fil, Amount};
+use crate::checkpoint::next_checkpoint_epoch;
+use crate::config::json_rpc_methods;
+use crate::events::AgentEvent;
+use crate::jobs::{self, Job, JobInfo};
+use crate::manager::clock::{EpochClock, EpochEta};
+use crate::manager::multihop::{fund_route, MultiHopFund};
+use crate::server::handlers::manager::subnet::{Connection, SubnetManagerPool};
+use crate::server::{check_subnet, parse_from, JsonRPCRequestHandler};
 use anyhow::anyhow;
 use async_trait::async_trait;
 use fvm_shared::address::Address;
@@ -20,6 +27,21 @@ pub struct FundParams {
     pub to: Option<String>,
     /// In whole FIL
     pub amount: f64,
+    /// The subnet the funds are sent from, an ancestor of `subnet`. Defaults to its parent.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FundResponse {
+    /// The epoch the fund was executed in the parent
+    pub epoch: ChainEpoch,
+    /// The amount funded
+    pub amount: Amount,
+    /// Estimate of the top-down checkpoint that credits the funds in the subnet, if it
+    /// could be determined. The first subnet of the route for multi-hop funds.
+    pub eta: Option<EpochEta>,
+    /// The job moving the funds down the rest of the route, for multi-hop funds.
+    pub job: Option<JobInfo>,
 }
 
 /// The fund json rpc method handler.
@@ -31,17 +53,50 @@ impl FundHandler {
     pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
         Self { pool }
     }
+
+    /// Funds are credited in the subnet once the top-down checkpoint including the fund epoch
+    /// is executed. Top-down checkpoints are cut at the period of the subnet's gateway.
+    async fn arrival_eta(
+        &self,
+        subnet: &SubnetID,
+        parent: &Connection,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<EpochEta> {
+        let child = self
+            .pool
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet not configured"))?;
+        let period = child
+            .manager()
+            .top_down_check_period(&child.subnet().gateway_addr())
+            .await?;
+
+        let clock = EpochClock::from_subnet(
+            &self.pool.state().epoch_calibrations,
+            parent.subnet(),
+            parent.manager(),
+        )
+        .await?;
+        Ok(clock.eta(next_checkpoint_epoch(epoch, period)))
+    }
 }
 
 #[async_trait]
 impl JsonRPCRequestHandler for FundHandler {
     type Request = FundParams;
-    type Response = ChainEpoch;
+    type Response = FundResponse;
 
     async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let params = serde_json::to_value(&request)?;
         let subnet = SubnetID::from_str(&request.subnet)?;
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.pool.get(&parent) {
+        let source = request
+            .source
+            .map(|s| SubnetID::from_str(&s))
+            .transpose()?
+            .unwrap_or(parent);
+        let route = fund_route(&source, &subnet)?;
+        let conn = match self.pool.get(&source) {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -55,10 +110,38 @@ impl JsonRPCRequestHandler for FundHandler {
             .map(|r| Address::from_str(&r))
             .transpose()?
             .unwrap_or(from);
-        let amount = handlers::f64_to_token_amount(request.amount)?;
+        let amount = parse_fil("amount", request.amount)?;
+
+        let mut fund = MultiHopFund::new(self.pool.clone(), route, from, to, amount.clone())?;
+        // the first hop is sent right away, so that its errors are returned to the caller
+        let epoch = fund.send_hop().await?;
+        self.pool.state().events.publish(AgentEvent::CrossMsgSent {
+            subnet: source.to_string(),
+            method: String::from(json_rpc_methods::FUND),
+            from: from.to_string(),
+            to: Some(to.to_string()),
+            value: Some(Amount::from(&amount)),
+            epoch: Some(epoch),
+        });
+
+        let next = fund.route()[1].clone();
+        let eta = match self.arrival_eta(&next, &conn, epoch).await {
+            Ok(eta) => Some(eta),
+            Err(e) => {
+                tracing::warn!("cannot estimate when funds arrive in subnet {next:}: {e:}");
+                None
+            }
+        };
+        // direct funds are not tracked by a job once sent
+        let state = self.pool.state().clone();
+        let job = (fund.route().len() > 2 && !fund.done())
+            .then(|| jobs::spawn(state, Box::new(fund), params));
 
-        conn.manager()
-            .fund(subnet, subnet_config.gateway_addr(), from, to, amount)
-            .await
+        Ok(FundResponse {
+            epoch,
+            amount: Amount::from(amount),
+            eta,
+            job,
+        })
     }
 }
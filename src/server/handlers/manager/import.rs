@@ -0,0 +1,437 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Discovery of the child subnets of a configured parent, and import of the ones selected into
+//! the config of the agent.
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use fvm_shared::address::Address;
+use ipc_gateway::Status;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::config::subnet::{FVMSubnet, SubnetConfig};
+use crate::config::{Config, ReloadableConfig, Subnet};
+use crate::lotus::message::ipc::SubnetInfo;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::{Connection, SubnetManagerPool};
+use crate::server::JsonRPCRequestHandler;
+
+/// The id of the gateway actor deployed at the genesis of the child subnets.
+pub const CHILD_GATEWAY_ACTOR_ID: u64 = 64;
+/// The maximum number of child subnets checked concurrently for the accounts of the agent.
+const OWN_SUBNETS_PARALLELISM: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiscoverSubnetsParams {
+    /// The configured subnet to discover the children of.
+    pub parent: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredSubnet {
+    pub id: String,
+    /// The status of the subnet in the gateway of the parent.
+    pub status: String,
+    pub stake: Amount,
+    pub circ_supply: Amount,
+    /// Whether the subnet is already in the config.
+    pub configured: bool,
+    /// The network type inferred for the subnet.
+    pub network_type: String,
+    /// The gateway address inferred for the subnet.
+    pub gateway_addr: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListOwnSubnetsParams {
+    /// The configured subnet to list the children of.
+    pub parent: String,
+}
+
+/// A child subnet the accounts of the agent take part in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OwnSubnet {
+    pub id: String,
+    /// The status of the subnet in the gateway of the parent.
+    pub status: String,
+    /// The accounts of the agent in the parent that created the subnet.
+    pub owners: Vec<String>,
+    /// The accounts of the agent in the parent that are validators of the subnet.
+    pub validators: Vec<String>,
+    /// The accounts of the agent in the subnet with a balance in it, only read for the subnets
+    /// in the config.
+    pub balances: BTreeMap<String, Amount>,
+}
+
+impl OwnSubnet {
+    fn is_own(&self) -> bool {
+        !self.owners.is_empty() || !self.validators.is_empty() || !self.balances.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportSubnetsParams {
+    pub parent: String,
+    pub subnets: Vec<ImportSubnet>,
+}
+
+/// A discovered subnet to write into the config, with what cannot be discovered on chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSubnet {
+    pub id: String,
+    /// Defaults to the address of the subnet actor in the parent.
+    pub network_name: Option<String>,
+    /// The json rpc endpoint of the node of the subnet.
+    pub jsonrpc_api_http: String,
+    pub auth_token: Option<String>,
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// Overrides the inferred gateway address.
+    pub gateway_addr: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportSubnetsResponse {
+    /// The subnets written into the config.
+    pub imported: Vec<String>,
+}
+
+/// Lists the children of `parent` in its gateway.
+pub(crate) async fn child_subnets(
+    pool: &SubnetManagerPool,
+    parent: &SubnetID,
+) -> anyhow::Result<HashMap<SubnetID, SubnetInfo>> {
+    let conn = match pool.get(parent) {
+        None => return Err(anyhow!("parent subnet {parent:} not configured")),
+        Some(conn) => conn,
+    };
+    check_subnet(conn.subnet())?;
+    conn.manager()
+        .list_child_subnets(conn.subnet().gateway_addr())
+        .await
+}
+
+/// The child subnets run an fvm node with the gateway at a well known actor id, regardless of
+/// the network type of their parent.
+fn inferred_gateway_addr() -> Address {
+    Address::new_id(CHILD_GATEWAY_ACTOR_ID)
+}
+
+/// Builds the config of a child subnet from the import request.
+fn subnet_config(request: &ImportSubnet, id: &SubnetID) -> anyhow::Result<Subnet> {
+    let gateway_addr = match &request.gateway_addr {
+        Some(addr) => Address::from_str(addr)?,
+        None => inferred_gateway_addr(),
+    };
+    let accounts = request
+        .accounts
+        .iter()
+        .map(|a| Address::from_str(a))
+        .collect::<Result<Vec<_>, _>>()?;
+    let network_name = match &request.network_name {
+        Some(name) => name.clone(),
+        None => id
+            .to_string()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    };
+
+    Ok(Subnet {
+        id: id.clone(),
+        network_name,
+        block_time_secs: None,
+        checkpoint_period_poll_secs: None,
+        gas: Default::default(),
+        mode: Default::default(),
+        features: Default::default(),
+        vote_policy: Default::default(),
+        checkpoint_signing: Default::default(),
+        co_signing: None,
+        submission_delay: None,
+        federation: None,
+        faucet: None,
+        headers: Default::default(),
+        config: SubnetConfig::Fvm(FVMSubnet {
+            gateway_addr,
+            jsonrpc_api_http: request.jsonrpc_api_http.parse()?,
+            jsonrpc_api_ws: None,
+            expected_network_name: None,
+            retry: Default::default(),
+            connections: Default::default(),
+            message_wait: Default::default(),
+            auth_token: request.auth_token.clone(),
+            accounts,
+            signer: Default::default(),
+            decoding: Default::default(),
+        }),
+    })
+}
+
+/// The discover child subnets json rpc method handler.
+pub(crate) struct DiscoverSubnetsHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl DiscoverSubnetsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for DiscoverSubnetsHandler {
+    type Request = DiscoverSubnetsParams;
+    type Response = Vec<DiscoveredSubnet>;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let parent = SubnetID::from_str(&request.parent)?;
+        let configured = self.pool.subnets();
+
+        let mut discovered = child_subnets(&self.pool, &parent)
+            .await?
+            .into_iter()
+            .map(|(id, info)| DiscoveredSubnet {
+                id: id.to_string(),
+                status: format!("{:?}", info.status),
+                stake: Amount::from(&info.stake),
+                circ_supply: Amount::from(&info.circ_supply),
+                configured: configured.contains_key(&id),
+                network_type: String::from("fvm"),
+                gateway_addr: inferred_gateway_addr().to_string(),
+            })
+            .collect::<Vec<_>>();
+        discovered.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(discovered)
+    }
+}
+
+/// The list own child subnets json rpc method handler, filtering the discovered children of a
+/// subnet down to those an account of the agent owns, validates or has funds in.
+pub(crate) struct ListOwnSubnetsHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl ListOwnSubnetsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Checks the accounts of the agent against the child subnet `id`. The checks that fail are
+    /// logged and left out, so that a single unreachable node does not fail the whole list.
+    async fn own_subnet(
+        &self,
+        parent: &Connection,
+        id: &SubnetID,
+        info: &SubnetInfo,
+        created: &HashMap<Address, Vec<Address>>,
+    ) -> OwnSubnet {
+        let mut own = OwnSubnet {
+            id: id.to_string(),
+            status: format!("{:?}", info.status),
+            ..Default::default()
+        };
+        let actor = id.subnet_actor();
+        own.owners = created
+            .iter()
+            .filter(|(_, subnets)| subnets.contains(&actor))
+            .map(|(owner, _)| owner.to_string())
+            .collect();
+
+        match parent
+            .manager()
+            .get_validator_set(id, Some(parent.subnet().gateway_addr()))
+            .await
+        {
+            Ok(r) => {
+                let validators = r.validator_set.validators.unwrap_or_default();
+                own.validators = parent
+                    .subnet()
+                    .accounts()
+                    .iter()
+                    .map(|a| a.to_string())
+                    .filter(|a| {
+                        validators
+                            .iter()
+                            .any(|v| &v.addr == a || v.worker_addr.as_ref() == Some(a))
+                    })
+                    .collect();
+            }
+            Err(e) => tracing::warn!("cannot get the validators of subnet {id:}: {e:}"),
+        }
+
+        if let Some(child) = self.pool.get(id) {
+            for account in child.subnet().accounts() {
+                match child.manager().wallet_balance(&account).await {
+                    Ok(balance) if !balance.is_zero() => {
+                        own.balances
+                            .insert(account.to_string(), Amount::from(balance));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("cannot get the balance of {account:} in subnet {id:}: {e:}")
+                    }
+                }
+            }
+        }
+
+        own
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListOwnSubnetsHandler {
+    type Request = ListOwnSubnetsParams;
+    type Response = Vec<OwnSubnet>;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let parent_id = SubnetID::from_str(&request.parent)?;
+        let children = child_subnets(&self.pool, &parent_id).await?;
+        let parent = self
+            .pool
+            .get(&parent_id)
+            .ok_or_else(|| anyhow!("parent subnet {parent_id:} not configured"))?;
+
+        // the subnets created by each account are read once for all the children
+        let mut created = HashMap::new();
+        for account in parent.subnet().accounts() {
+            match parent.manager().subnets_created_by(&account).await {
+                Ok(subnets) => {
+                    created.insert(account, subnets);
+                }
+                Err(e) => tracing::debug!("cannot get the subnets created by {account:}: {e:}"),
+            }
+        }
+
+        let mut own = stream::iter(children.iter())
+            .map(|(id, info)| self.own_subnet(&parent, id, info, &created))
+            .buffer_unordered(OWN_SUBNETS_PARALLELISM)
+            .filter(|s| futures::future::ready(s.is_own()))
+            .collect::<Vec<_>>()
+            .await;
+        own.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(own)
+    }
+}
+
+/// The import child subnets json rpc method handler.
+pub(crate) struct ImportSubnetsHandler {
+    pool: Arc<SubnetManagerPool>,
+    config: Arc<ReloadableConfig>,
+}
+
+impl ImportSubnetsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>, config: Arc<ReloadableConfig>) -> Self {
+        Self { pool, config }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ImportSubnetsHandler {
+    type Request = ImportSubnetsParams;
+    type Response = ImportSubnetsResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        if request.subnets.is_empty() {
+            return Err(anyhow!("no subnet to import"));
+        }
+
+        let parent = SubnetID::from_str(&request.parent)?;
+        let children = child_subnets(&self.pool, &parent).await?;
+
+        // the config file is read again so that only the new subnets are added to it
+        let path = self.config.get_path();
+        let mut config = Config::from_file_async(&path).await?;
+
+        let mut imported = vec![];
+        for s in request.subnets.iter() {
+            let id = SubnetID::from_str(&s.id)?;
+            match children.get(&id).map(|info| &info.status) {
+                None => return Err(anyhow!("subnet {id:} is not a child of {parent:}")),
+                Some(Status::Killed) => return Err(anyhow!("subnet {id:} has been killed")),
+                Some(_) => {}
+            }
+            if config.subnets.contains_key(&id) {
+                return Err(anyhow!("subnet {id:} already in the config"));
+            }
+
+            config.add_subnet(subnet_config(s, &id)?);
+            imported.push(id.to_string());
+        }
+
+        config.write_to_file_async(&path).await?;
+        tracing::info!("subnets {imported:?} imported into config {path:}");
+        self.config.reload().await?;
+
+        Ok(ImportSubnetsResponse { imported })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::amount::Amount;
+    use crate::config::subnet::SubnetConfig;
+    use crate::server::handlers::manager::import::{subnet_config, ImportSubnet, OwnSubnet};
+
+    #[test]
+    fn test_subnet_config() {
+        let id = SubnetID::from_str("/r123/f0100").unwrap();
+        let mut request = ImportSubnet {
+            id: id.to_string(),
+            network_name: None,
+            jsonrpc_api_http: String::from("http://127.0.0.1:1250/rpc/v1"),
+            auth_token: None,
+            accounts: vec![String::from("f0101")],
+            gateway_addr: None,
+        };
+
+        let subnet = subnet_config(&request, &id).unwrap();
+        assert_eq!(subnet.network_name, "f0100");
+        assert_eq!(subnet.gateway_addr(), Address::new_id(64));
+        assert_eq!(subnet.accounts(), vec![Address::new_id(101)]);
+        assert!(matches!(subnet.config, SubnetConfig::Fvm(_)));
+
+        request.gateway_addr = Some(String::from("f065"));
+        request.network_name = Some(String::from("child"));
+        let subnet = subnet_config(&request, &id).unwrap();
+        assert_eq!(subnet.network_name, "child");
+        assert_eq!(subnet.gateway_addr(), Address::new_id(65));
+
+        request.accounts = vec![String::from("not an address")];
+        assert!(subnet_config(&request, &id).is_err());
+    }
+
+    #[test]
+    fn test_own_subnet() {
+        let mut own = OwnSubnet {
+            id: String::from("/r123/f0100"),
+            status: String::from("Active"),
+            ..Default::default()
+        };
+        assert!(!own.is_own());
+
+        own.balances.insert(
+            String::from("f0101"),
+            Amount::from(TokenAmount::from_whole(1)),
+        );
+        assert!(own.is_own());
+
+        own.balances.clear();
+        own.validators.push(String::from("f0101"));
+        assert!(own.is_own());
+    }
+}
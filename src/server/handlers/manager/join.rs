@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: MIT
 //! Join subnet handler and parameters
 
+use crate::amount::parse_fil;
 use crate::server::handlers::manager::subnet::SubnetManagerPool;
-use crate::server::handlers::manager::{check_subnet, parse_from};
-use crate::server::{handlers, JsonRPCRequestHandler};
+use crate::server::handlers::manager::{check_collateral_mode, check_subnet, parse_from};
+use crate::server::JsonRPCRequestHandler;
 use anyhow::anyhow;
 use async_trait::async_trait;
 use fvm_shared::address::Address;
@@ -47,11 +48,13 @@ impl JsonRPCRequestHandler for JoinSubnetHandler {
             Some(conn) => conn,
         };
 
-        let collateral = handlers::f64_to_token_amount(request.collateral)?;
+        let collateral = parse_fil("collateral", request.collateral)?;
 
         let subnet_config = conn.subnet();
         check_subnet(subnet_config)?;
 
+        check_collateral_mode(conn.manager(), &subnet).await?;
+
         let from = parse_from(subnet_config, request.from)?;
         let worker = match request.worker_addr {
             None => from,
@@ -0,0 +1,254 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Estimate the collateral and gas needed to join a subnet
+
+use crate::amount::Amount;
+use crate::lotus::message::ipc::{JoinRequirements, PermissionMode};
+use crate::manager::offline::OfflineCall;
+use crate::server::handlers::manager::subnet::{Connection, SubnetManagerPool};
+use crate::server::handlers::manager::{check_subnet, parse_from};
+use crate::server::JsonRPCRequestHandler;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ipc_gateway::Status;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateJoinCollateralParams {
+    pub subnet: String,
+    /// The address that would join, used to estimate the gas of the join message
+    pub from: Option<String>,
+    pub validator_net_addr: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateJoinCollateralResponse {
+    /// The minimum collateral to join with
+    pub min_collateral: Amount,
+    /// The estimated maximum fee of the join message, if it could be estimated
+    pub estimated_gas_fee: Option<Amount>,
+    /// The collateral plus the estimated gas fee
+    pub total: Amount,
+    /// The minimum number of validators for the subnet to be active
+    pub min_validators: u64,
+    /// The number of validators already in the subnet
+    pub validators: u64,
+    /// The maximum number of validators, if the validator set is capped
+    pub max_validators: Option<u64>,
+    /// How validators are admitted in the subnet, only collateral based subnets can be joined
+    pub permission_mode: PermissionMode,
+    /// Whether the subnet currently accepts new validators
+    pub accepting_validators: bool,
+}
+
+/// The json rpc method handler that estimates what a validator needs to join a subnet.
+pub(crate) struct EstimateJoinCollateralHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl EstimateJoinCollateralHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+/// Whether a subnet admits new validators, and why not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Open,
+    /// The validators are set by the owner of the subnet or at genesis, they cannot join.
+    Permissioned(PermissionMode),
+    Killed,
+    Full {
+        max_validators: u64,
+    },
+}
+
+impl Admission {
+    /// The admission of a subnet with the permission `mode`, the `status` in the gateway of its
+    /// parent, `None` if not registered there yet, and the validator set of `requirements`.
+    fn of(mode: PermissionMode, status: Option<&Status>, requirements: &JoinRequirements) -> Self {
+        if mode != PermissionMode::Collateral {
+            return Admission::Permissioned(mode);
+        }
+        if matches!(status, Some(Status::Killed)) {
+            return Admission::Killed;
+        }
+        match requirements.max_validators {
+            Some(max) if requirements.validators >= max => Admission::Full {
+                max_validators: max,
+            },
+            _ => Admission::Open,
+        }
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        *self == Admission::Open
+    }
+}
+
+impl Display for Admission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Admission::Open => write!(f, "the subnet accepts validators"),
+            Admission::Permissioned(mode) => write!(
+                f,
+                "the subnet is in {mode:} permission mode, validators cannot join"
+            ),
+            Admission::Killed => write!(f, "the subnet has been killed"),
+            Admission::Full { max_validators } => write!(
+                f,
+                "the validator set is full, at its maximum of {max_validators:} validators"
+            ),
+        }
+    }
+}
+
+/// Whether `subnet`, whose validators are admitted with `mode` and whose validator set is the one
+/// of `requirements`, accepts new validators in its `parent`. Subnets are only registered in the
+/// gateway of the parent once they are activated, so a subnet not found there is not killed.
+pub(crate) async fn accepting_validators(
+    subnet: &SubnetID,
+    parent: &Connection,
+    mode: PermissionMode,
+    requirements: &JoinRequirements,
+) -> anyhow::Result<Admission> {
+    let subnets = parent
+        .manager()
+        .list_child_subnets(parent.subnet().gateway_addr())
+        .await?;
+    Ok(Admission::of(
+        mode,
+        subnets.get(subnet).map(|s| &s.status),
+        requirements,
+    ))
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for EstimateJoinCollateralHandler {
+    type Request = EstimateJoinCollateralParams;
+    type Response = EstimateJoinCollateralResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.pool.get(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        let from = parse_from(subnet_config, request.from)?;
+        let requirements = conn.manager().join_requirements(&subnet).await?;
+        let permission_mode = conn.manager().permission_mode(&subnet).await?;
+        let admission =
+            accepting_validators(&subnet, &conn, permission_mode, &requirements).await?;
+
+        let join = OfflineCall::Join {
+            subnet: subnet.clone(),
+            collateral: requirements.min_validator_stake.clone(),
+            validator_net_addr: request.validator_net_addr.unwrap_or_default(),
+            worker_addr: from,
+        };
+        let estimated_gas_fee = match conn
+            .manager()
+            .estimate_fee(subnet_config.gateway_addr(), from, join)
+            .await
+        {
+            Ok(fee) => Some(fee),
+            Err(e) => {
+                // estimation fails if the sender does not have the funds yet
+                tracing::warn!(
+                    "cannot estimate the gas to join subnet {subnet:} from {from:}: {e:}"
+                );
+                None
+            }
+        };
+
+        let total = requirements.min_validator_stake.clone()
+            + estimated_gas_fee.clone().unwrap_or_default();
+
+        Ok(EstimateJoinCollateralResponse {
+            min_collateral: Amount::from(&requirements.min_validator_stake),
+            estimated_gas_fee: estimated_gas_fee.map(Amount::from),
+            total: Amount::from(total),
+            min_validators: requirements.min_validators,
+            validators: requirements.validators,
+            max_validators: requirements.max_validators,
+            permission_mode,
+            accepting_validators: admission.is_open(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::econ::TokenAmount;
+    use ipc_gateway::Status;
+
+    use crate::lotus::message::ipc::{JoinRequirements, PermissionMode};
+    use crate::server::handlers::manager::join_collateral::Admission;
+
+    fn requirements(validators: u64, max_validators: Option<u64>) -> JoinRequirements {
+        JoinRequirements {
+            min_validator_stake: TokenAmount::from_whole(1),
+            min_validators: 1,
+            validators,
+            max_validators,
+        }
+    }
+
+    #[test]
+    fn test_admission_open() {
+        let admission = Admission::of(PermissionMode::Collateral, None, &requirements(3, None));
+        assert_eq!(admission, Admission::Open);
+        assert!(admission.is_open());
+
+        // active subnets with room left in their validator set
+        assert!(Admission::of(
+            PermissionMode::Collateral,
+            Some(&Status::Active),
+            &requirements(3, Some(4))
+        )
+        .is_open());
+    }
+
+    #[test]
+    fn test_admission_permissioned() {
+        for mode in [PermissionMode::Federated, PermissionMode::Static] {
+            assert_eq!(
+                Admission::of(mode, Some(&Status::Active), &requirements(0, None)),
+                Admission::Permissioned(mode)
+            );
+        }
+    }
+
+    #[test]
+    fn test_admission_killed() {
+        assert_eq!(
+            Admission::of(
+                PermissionMode::Collateral,
+                Some(&Status::Killed),
+                &requirements(3, None)
+            ),
+            Admission::Killed
+        );
+    }
+
+    #[test]
+    fn test_admission_full() {
+        let admission = Admission::of(
+            PermissionMode::Collateral,
+            Some(&Status::Active),
+            &requirements(4, Some(4)),
+        );
+        assert_eq!(admission, Admission::Full { max_validators: 4 });
+        assert!(!admission.is_open());
+    }
+}
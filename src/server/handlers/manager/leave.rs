@@ -3,7 +3,7 @@
 //! Leave subnet handler and parameters
 
 use crate::server::handlers::manager::subnet::SubnetManagerPool;
-use crate::server::handlers::manager::{check_subnet, parse_from};
+use crate::server::handlers::manager::{check_collateral_mode, check_subnet, parse_from};
 use crate::server::JsonRPCRequestHandler;
 use anyhow::anyhow;
 use async_trait::async_trait;
@@ -45,6 +45,8 @@ impl JsonRPCRequestHandler for LeaveSubnetHandler {
         let subnet_config = conn.subnet();
         check_subnet(subnet_config)?;
 
+        check_collateral_mode(conn.manager(), &subnet).await?;
+
         let from = parse_from(subnet_config, request.from)?;
 
         conn.manager().leave_subnet(subnet, from).await
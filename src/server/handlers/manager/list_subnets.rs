@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT
 //! List subnets in gateway actor
 
+use crate::amount::Amount;
 use crate::lotus::message::ipc::SubnetInfo;
 
 use crate::server::handlers::manager::check_subnet;
@@ -9,6 +10,7 @@ use crate::server::handlers::manager::subnet::SubnetManagerPool;
 use crate::server::JsonRPCRequestHandler;
 use anyhow::anyhow;
 use async_trait::async_trait;
+use futures::{stream, StreamExt};
 use fvm_shared::address::Address;
 use ipc_sdk::subnet_id::SubnetID;
 use serde::{Deserialize, Serialize};
@@ -16,10 +18,42 @@ use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 
+/// The maximum number of child subnets whose details are fetched concurrently.
+const DETAILS_PARALLELISM: usize = 8;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListSubnetsParams {
     pub gateway_address: String,
     pub subnet_id: String,
+    /// Include the details of each child subnet, saving the follow-up calls per subnet.
+    #[serde(default)]
+    pub details: bool,
+    /// The account whose balance in each child subnet is included in the details, the first
+    /// account of the child subnet in the config by default.
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedSubnet {
+    #[serde(flatten)]
+    pub info: SubnetInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<SubnetDetails>,
+}
+
+/// The details of a child subnet. What is read from the child itself is only known for the
+/// subnets in the config of the agent.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubnetDetails {
+    pub stake: Amount,
+    pub circ_supply: Amount,
+    /// The funds held by the gateway of the child, i.e. released and not yet checkpointed.
+    pub locked: Option<Amount>,
+    pub account: Option<String>,
+    /// The balance of `account` in the child.
+    pub balance: Option<Amount>,
+    /// Why some of the details could not be fetched.
+    pub error: Option<String>,
 }
 
 /// The create subnet json rpc method handler.
@@ -36,7 +70,7 @@ impl ListSubnetsHandler {
 #[async_trait]
 impl JsonRPCRequestHandler for ListSubnetsHandler {
     type Request = ListSubnetsParams;
-    type Response = HashMap<String, SubnetInfo>;
+    type Response = HashMap<String, ListedSubnet>;
 
     async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
         let subnet = SubnetID::from_str(&request.subnet_id)?;
@@ -50,9 +84,132 @@ impl JsonRPCRequestHandler for ListSubnetsHandler {
 
         let gateway_addr = Address::from_str(&request.gateway_address)?;
         let subnet_map = conn.manager().list_child_subnets(gateway_addr).await?;
-        Ok(subnet_map
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v))
-            .collect())
+        if !request.details {
+            return Ok(subnet_map
+                .into_iter()
+                .map(|(k, info)| (k.to_string(), ListedSubnet::from(info)))
+                .collect());
+        }
+
+        let account = request
+            .account
+            .as_deref()
+            .map(Address::from_str)
+            .transpose()?;
+        Ok(stream::iter(subnet_map)
+            .map(|(id, info)| async move {
+                let details = self.details(&id, &info, account).await;
+                (
+                    id.to_string(),
+                    ListedSubnet {
+                        info,
+                        details: Some(details),
+                    },
+                )
+            })
+            .buffer_unordered(DETAILS_PARALLELISM)
+            .collect()
+            .await)
+    }
+}
+
+impl ListSubnetsHandler {
+    /// Fetches the details of the child subnet `id`. The errors are reported in the details
+    /// instead of failing the whole list.
+    async fn details(
+        &self,
+        id: &SubnetID,
+        info: &SubnetInfo,
+        account: Option<Address>,
+    ) -> SubnetDetails {
+        let mut details = SubnetDetails {
+            stake: Amount::from(&info.stake),
+            circ_supply: Amount::from(&info.circ_supply),
+            locked: None,
+            account: None,
+            balance: None,
+            error: None,
+        };
+
+        let child = match self.pool.get(id) {
+            Some(child) => child,
+            None => {
+                tracing::debug!("subnet {id:} not configured, no details read from the child");
+                return details;
+            }
+        };
+        let account = account.or_else(|| child.subnet().accounts().first().cloned());
+        details.account = account.map(|a| a.to_string());
+
+        let r: anyhow::Result<()> = try {
+            let manager = child.manager();
+            let locked = manager
+                .balance_at(&child.subnet().gateway_addr(), None)
+                .await?;
+            details.locked = Some(Amount::from(locked));
+            if let Some(account) = account {
+                details.balance = Some(Amount::from(manager.wallet_balance(&account).await?));
+            }
+        };
+        if let Err(e) = r {
+            tracing::warn!("cannot read the details of subnet {id:}: {e:}");
+            details.error = Some(e.to_string());
+        }
+
+        details
+    }
+}
+
+impl From<SubnetInfo> for ListedSubnet {
+    fn from(info: SubnetInfo) -> Self {
+        Self {
+            info,
+            details: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::econ::TokenAmount;
+    use ipc_gateway::Status;
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::amount::Amount;
+    use crate::lotus::message::ipc::SubnetInfo;
+    use crate::server::handlers::manager::list_subnets::{ListedSubnet, SubnetDetails};
+
+    #[test]
+    fn test_listed_subnet() {
+        let info = || SubnetInfo {
+            id: SubnetID::from_str("/r123/f0100").unwrap(),
+            stake: TokenAmount::from_whole(1),
+            circ_supply: TokenAmount::from_whole(2),
+            status: Status::Active,
+        };
+
+        // without details, the subnets are listed as before
+        let json = serde_json::to_value(ListedSubnet::from(info())).unwrap();
+        assert_eq!(json["id"], "/r123/f0100");
+        assert_eq!(json["stake"], "1000000000000000000");
+        assert!(json.get("details").is_none());
+
+        let listed = ListedSubnet {
+            info: info(),
+            details: Some(SubnetDetails {
+                stake: Amount::from(TokenAmount::from_whole(1)),
+                circ_supply: Amount::from(TokenAmount::from_whole(2)),
+                locked: None,
+                account: Some(String::from("f0101")),
+                balance: Some(Amount::from(TokenAmount::from_whole(3))),
+                error: None,
+            }),
+        };
+        let json = serde_json::to_value(listed).unwrap();
+        assert_eq!(json["circ_supply"], "2000000000000000000");
+        assert_eq!(json["details"]["circ_supply"]["fil"], "2");
+        assert_eq!(json["details"]["balance"]["fil"], "3");
     }
 }
@@ -0,0 +1,162 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Lookup of a message of a subnet by its cid, or the hash of its transaction in evm subnets
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::manager::activity::{MessageLookup, MessageReceipt};
+use crate::manager::key_usage::KeyOperation;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LookupMessageParams {
+    pub subnet: String,
+    /// The cid of the message, or the hash of the transaction in evm subnets.
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReceiptEntry {
+    pub id: String,
+    pub epoch: ChainEpoch,
+    pub success: bool,
+    /// The exit code of the fvm message, not set for evm transactions.
+    pub exit_code: Option<u32>,
+    /// The hex encoded cbor return of the fvm message, not set for evm transactions.
+    pub return_data: Option<String>,
+    pub gas_used: u64,
+    pub gas_fee: Amount,
+}
+
+impl MessageReceiptEntry {
+    fn new(id: &str, r: &MessageReceipt) -> Self {
+        Self {
+            id: id.to_string(),
+            epoch: r.epoch,
+            success: r.success,
+            exit_code: r.exit_code,
+            return_data: r.return_data.as_ref().map(hex::encode),
+            gas_used: r.gas_used,
+            gas_fee: Amount::from(&r.gas_fee),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetMessageResponse {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub value: Amount,
+    /// The method called, always 0 for evm transactions.
+    pub method: u64,
+    pub nonce: u64,
+    /// The hex encoded cbor params of the fvm message or calldata of the evm transaction.
+    pub params: String,
+    /// The epoch the message was executed at, not set while it is pending.
+    pub epoch: Option<ChainEpoch>,
+    pub receipt: Option<MessageReceiptEntry>,
+    /// Whether the message is an IPC operation: it calls the gateway or a subnet actor, or it
+    /// was signed by the agent.
+    pub ipc: bool,
+    /// The method of the gateway or of the subnet actor called by the message.
+    pub ipc_method: Option<String>,
+    /// The operation the agent signed the message for, if it did since it started.
+    pub agent_operation: Option<KeyOperation>,
+}
+
+/// Returns the message of `request` from the manager of its subnet.
+async fn lookup(
+    pool: &SubnetManagerPool,
+    request: &LookupMessageParams,
+) -> anyhow::Result<(SubnetID, MessageLookup)> {
+    let subnet = SubnetID::from_str(&request.subnet)?;
+    let conn = match pool.get(&subnet) {
+        None => return Err(anyhow!("subnet {subnet:} not configured")),
+        Some(conn) => conn,
+    };
+    check_subnet(conn.subnet())?;
+    let message = conn.manager().lookup_message(&request.id).await?;
+    Ok((subnet, message))
+}
+
+/// The handler returning a message of a subnet with its receipt, to support the operators
+/// tracking what happened to a message.
+pub(crate) struct GetMessageHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl GetMessageHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for GetMessageHandler {
+    type Request = LookupMessageParams;
+    type Response = GetMessageResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let (subnet, m) = lookup(&self.pool, &request).await?;
+        // the hashes of evm transactions are recorded in lower case
+        let key_usage = &self.pool.state().key_usage;
+        let agent_operation = key_usage
+            .find(&subnet, &m.id)
+            .or_else(|| key_usage.find(&subnet, &m.id.to_lowercase()))
+            .map(|(_, usage)| usage.operation);
+
+        Ok(GetMessageResponse {
+            receipt: m
+                .receipt
+                .as_ref()
+                .map(|r| MessageReceiptEntry::new(&m.id, r)),
+            epoch: m.receipt.as_ref().map(|r| r.epoch),
+            ipc: m.ipc_method.is_some() || agent_operation.is_some(),
+            ipc_method: m.ipc_method,
+            agent_operation,
+            id: m.id,
+            from: m.from.to_string(),
+            to: m.to.to_string(),
+            value: Amount::from(&m.value),
+            method: m.method,
+            nonce: m.nonce,
+            params: hex::encode(m.params),
+        })
+    }
+}
+
+/// The handler returning the receipt of an executed message of a subnet.
+pub(crate) struct GetReceiptHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl GetReceiptHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for GetReceiptHandler {
+    type Request = LookupMessageParams;
+    type Response = MessageReceiptEntry;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let (_, m) = lookup(&self.pool, &request).await?;
+        match &m.receipt {
+            Some(r) => Ok(MessageReceiptEntry::new(&m.id, r)),
+            None => Err(anyhow!("message {} not executed yet", m.id)),
+        }
+    }
+}
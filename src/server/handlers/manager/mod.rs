@@ -8,30 +8,57 @@ use fvm_shared::address::Address;
 
 use crate::config::subnet::SubnetConfig;
 use crate::config::Subnet;
+use crate::lotus::message::ipc::PermissionMode;
+use crate::manager::SubnetManager;
+use ipc_sdk::subnet_id::SubnetID;
 
+pub mod checkpoint_diff;
+pub mod circulating_supply;
+pub mod compare_validators;
+pub mod cosign;
 pub mod create;
+pub mod decommission;
+pub mod diagnose_cross;
+pub mod federated_power;
+pub mod federation;
 pub mod fund;
+pub mod import;
 pub mod join;
+pub mod join_collateral;
 pub mod kill;
 pub mod leave;
 pub mod list_checkpoints;
 pub mod list_subnets;
+pub mod lookup_message;
 pub mod net_addr;
+pub mod nonces;
+pub mod offline_tx;
+pub mod onboarding;
+pub mod pending_bottomup;
+pub mod pending_messages;
+pub mod pending_releases;
 pub mod propagate;
 pub mod query_validators;
+pub mod read_state;
 pub mod release;
+pub mod replace_message;
+pub mod resend_topdown;
 pub mod rpc;
 pub mod send_cross;
 pub mod send_value;
+pub mod submission_time;
 pub mod subnet;
 pub mod topdown_executed;
+pub mod topology;
+pub mod validator_subnets;
+pub mod vote_history;
 pub mod worker_addr;
 
 pub(crate) fn check_subnet(subnet: &Subnet) -> Result<()> {
     match &subnet.config {
         SubnetConfig::Fvm(config) => {
             if config.auth_token.is_none() {
-                log::error!("subnet {:?} does not have auth token", subnet.id);
+                tracing::error!("subnet {:?} does not have auth token", subnet.id);
                 return Err(anyhow!("Internal server error"));
             }
         }
@@ -42,12 +69,29 @@ pub(crate) fn check_subnet(subnet: &Subnet) -> Result<()> {
     Ok(())
 }
 
+/// Checks that validators can join and leave the subnet on their own, which is not the case in
+/// subnets whose validator set is managed by the owner or fixed at genesis.
+pub(crate) async fn check_collateral_mode(
+    manager: &dyn SubnetManager,
+    subnet: &SubnetID,
+) -> Result<()> {
+    match manager.permission_mode(subnet).await? {
+        PermissionMode::Collateral => Ok(()),
+        PermissionMode::Federated => Err(anyhow!(
+            "subnet {subnet:} is federated, its validators are set by the subnet owner with ipc_setFederatedPower"
+        )),
+        PermissionMode::Static => Err(anyhow!(
+            "subnet {subnet:} has a static validator set fixed at genesis"
+        )),
+    }
+}
+
 pub(crate) fn parse_from(subnet: &Subnet, from: Option<String>) -> Result<Address> {
     let addr = match from {
         Some(addr) => Address::from_str(&addr)?,
         None => {
             if subnet.accounts().is_empty() {
-                log::error!("subnet does not have account defined, {:?}", subnet.id);
+                tracing::error!("subnet does not have account defined, {:?}", subnet.id);
                 return Err(anyhow!("Internal server error"));
             } else {
                 subnet.accounts()[0]
@@ -0,0 +1,116 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Applied and pending nonces of the cross messages between a subnet and its parent
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ipc_sdk::cross::CrossMsg;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoncesParams {
+    /// The child subnet, both it and its parent must be in the config
+    pub subnet: String,
+}
+
+/// The nonces of the cross messages in one direction.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectionNonces {
+    /// The nonce of the messages applied by the gateway of the destination subnet
+    pub applied: u64,
+    /// The highest nonce of the messages pending in the source subnet, if any
+    pub highest_pending: Option<u64>,
+    /// The number of messages pending in the source subnet
+    pub pending: usize,
+}
+
+impl DirectionNonces {
+    fn new(applied: u64, pending: &[CrossMsg]) -> Self {
+        Self {
+            applied,
+            highest_pending: pending.iter().map(|m| m.msg.nonce).max(),
+            pending: pending.len(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoncesResponse {
+    /// From the parent to the subnet, the messages pending being those not applied yet
+    pub top_down: DirectionNonces,
+    /// From the subnet to the parent, the messages pending being those waiting for the next
+    /// bottom-up checkpoint
+    pub bottom_up: DirectionNonces,
+}
+
+/// The nonces json rpc method handler.
+pub(crate) struct NoncesHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl NoncesHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for NoncesHandler {
+    type Request = NoncesParams;
+    type Response = NoncesResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let parent_id = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent = match self.pool.get(&parent_id) {
+            None => return Err(anyhow!("parent subnet {parent_id:} not configured")),
+            Some(conn) => conn,
+        };
+        let child = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("subnet {subnet:} not configured")),
+            Some(conn) => conn,
+        };
+        check_subnet(parent.subnet())?;
+        check_subnet(child.subnet())?;
+
+        let parent_gateway = parent.subnet().gateway_addr();
+        let child_gateway = child.subnet().gateway_addr();
+
+        let applied = child
+            .manager()
+            .applied_top_down_nonce(&child_gateway)
+            .await?;
+        let pending = parent
+            .manager()
+            .top_down_msgs_from(&parent_gateway, &subnet, applied)
+            .await?;
+        let top_down = DirectionNonces::new(applied, &pending);
+
+        let applied = parent
+            .manager()
+            .applied_bottom_up_nonce(&parent_gateway, &subnet)
+            .await?;
+        let (_, batch) = child
+            .manager()
+            .pending_bottom_up_msgs(&child_gateway)
+            .await?;
+        let bottom_up = DirectionNonces::new(applied, &batch.cross_msgs.unwrap_or_default());
+
+        tracing::debug!(
+            "nonces of subnet {subnet:}: top-down {top_down:?}, bottom-up {bottom_up:?}"
+        );
+
+        Ok(NoncesResponse {
+            top_down,
+            bottom_up,
+        })
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Build and broadcast transactions signed offline
+
+use crate::amount::parse_fil;
+use crate::checkpoint::{checkpoint_direction, setup_manager_from_subnet, CheckpointDirection};
+use crate::manager::offline::{OfflineCall, OfflineTx};
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::{check_collateral_mode, check_subnet, parse_from, JsonRPCRequestHandler};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildTxParams {
+    /// One of `fund`, `release`, `join`, `leave`, `send-value` or `checkpoint-vote`
+    pub op: String,
+    pub subnet: String,
+    pub from: Option<String>,
+    /// The recipient of fund, release and send-value, defaults to `from`
+    pub to: Option<String>,
+    /// In whole FIL, the collateral when joining
+    pub amount: Option<f64>,
+    pub validator_net_addr: Option<String>,
+    /// The worker address when joining, defaults to `from`
+    pub worker_addr: Option<String>,
+    /// The epoch of the bottom-up checkpoint to vote
+    pub epoch: Option<ChainEpoch>,
+}
+
+impl BuildTxParams {
+    /// The subnet whose chain executes the operation.
+    fn execution_subnet(&self) -> anyhow::Result<SubnetID> {
+        let subnet = SubnetID::from_str(&self.subnet)?;
+        match self.op.as_str() {
+            "fund" | "join" | "leave" | "checkpoint-vote" => {
+                subnet.parent().ok_or_else(|| anyhow!("no parent found"))
+            }
+            _ => Ok(subnet),
+        }
+    }
+
+    fn call(&self, from: Address) -> anyhow::Result<OfflineCall> {
+        let subnet = SubnetID::from_str(&self.subnet)?;
+        let to = self
+            .to
+            .as_ref()
+            .map(|r| Address::from_str(r))
+            .transpose()?
+            .unwrap_or(from);
+        let amount = || {
+            self.amount
+                .ok_or_else(|| anyhow!("amount is required for {}", self.op))
+                .and_then(|f| parse_fil("amount", f))
+        };
+
+        Ok(match self.op.as_str() {
+            "fund" => OfflineCall::Fund {
+                subnet,
+                to,
+                amount: amount()?,
+            },
+            "release" => OfflineCall::Release {
+                subnet,
+                to,
+                amount: amount()?,
+            },
+            "join" => OfflineCall::Join {
+                subnet,
+                collateral: amount()?,
+                validator_net_addr: self
+                    .validator_net_addr
+                    .clone()
+                    .ok_or_else(|| anyhow!("validator net address is required for join"))?,
+                worker_addr: self
+                    .worker_addr
+                    .as_ref()
+                    .map(|r| Address::from_str(r))
+                    .transpose()?
+                    .unwrap_or(from),
+            },
+            "leave" => OfflineCall::Leave { subnet },
+            "send-value" => OfflineCall::SendValue {
+                subnet,
+                to,
+                amount: amount()?,
+            },
+            op => return Err(anyhow!("unsupported operation: {op:}")),
+        })
+    }
+}
+
+/// The json rpc method handler that builds unsigned transactions to be signed offline.
+pub(crate) struct BuildOfflineTxHandler {
+    pool: Arc<SubnetManagerPool>,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+}
+
+impl BuildOfflineTxHandler {
+    pub(crate) fn new(
+        pool: Arc<SubnetManagerPool>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    ) -> Self {
+        Self {
+            pool,
+            fvm_wallet,
+            evm_keystore,
+        }
+    }
+
+    /// The vote of `validator` for the bottom-up checkpoint of the subnet at the epoch of
+    /// `request`, assembled by the checkpoint manager of the subnet as it would submit it.
+    async fn checkpoint_vote(
+        &self,
+        request: &BuildTxParams,
+        validator: Address,
+    ) -> anyhow::Result<OfflineCall> {
+        let epoch = request
+            .epoch
+            .ok_or_else(|| anyhow!("epoch is required for checkpoint-vote"))?;
+        let subnet_id = SubnetID::from_str(&request.subnet)?;
+        let subnets = self.pool.subnets();
+        let subnet = subnets
+            .get(&subnet_id)
+            .ok_or_else(|| anyhow!("subnet {subnet_id:} not configured"))?;
+
+        let managers = setup_manager_from_subnet(
+            &subnets,
+            subnet,
+            self.fvm_wallet.clone(),
+            self.evm_keystore.clone(),
+            self.pool.state(),
+        )
+        .await?;
+        let manager = managers
+            .iter()
+            .find(|m| checkpoint_direction(m.as_ref()) == CheckpointDirection::BottomUp)
+            .ok_or_else(|| {
+                anyhow!("bottom-up checkpoints of subnet {subnet_id:} not managed by the agent")
+            })?;
+        manager.offline_vote(epoch, &validator).await
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for BuildOfflineTxHandler {
+    type Request = BuildTxParams;
+    type Response = OfflineTx;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = request.execution_subnet()?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        let from = parse_from(subnet_config, request.from.clone())?;
+        let call = match request.op.as_str() {
+            "checkpoint-vote" => self.checkpoint_vote(&request, from).await?,
+            _ => request.call(from)?,
+        };
+        if let OfflineCall::Join { subnet, .. } | OfflineCall::Leave { subnet } = &call {
+            check_collateral_mode(conn.manager(), subnet).await?;
+        }
+        let description = call.to_string();
+
+        let payload = conn
+            .manager()
+            .build_unsigned_tx(subnet_config.gateway_addr(), from, call)
+            .await?;
+
+        OfflineTx::new(
+            subnet_config.network_type().into(),
+            &subnet,
+            &from,
+            description,
+            &payload,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendSignedTxParams {
+    pub tx: OfflineTx,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendSignedTxResponse {
+    /// The cid of the message in fvm subnets, the transaction hash in fevm subnets
+    pub id: String,
+}
+
+/// The json rpc method handler that broadcasts transactions signed offline.
+pub(crate) struct SendSignedTxHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl SendSignedTxHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for SendSignedTxHandler {
+    type Request = SendSignedTxParams;
+    type Response = SendSignedTxResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        request.tx.verify()?;
+
+        let subnet = SubnetID::from_str(&request.tx.subnet)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        let id = conn.manager().send_signed_tx(&request.tx).await?;
+        Ok(SendSignedTxResponse { id })
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Dry run of the onboarding of a validator in a subnet.
+//!
+//! Every condition the join of a prospective validator depends on is checked against the current
+//! state of the subnet actor in the parent, without sending anything, so that providers running
+//! validators for others can tell what is missing before the validator attempts to join.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::parse_fil;
+use crate::config::subnet::SubnetConfig;
+use crate::lotus::message::ipc::{PermissionMode, Validator};
+use crate::manager::offline::OfflineCall;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::join_collateral::accepting_validators;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnboardingDryRunParams {
+    pub subnet: String,
+    /// The address of the prospective validator, which does not need to be in the wallet of the
+    /// agent.
+    pub validator: String,
+    /// In whole FIL, the minimum collateral of the subnet if not set.
+    pub collateral: Option<f64>,
+    pub validator_net_addr: Option<String>,
+    /// The validator itself if not set.
+    pub worker_addr: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ready,
+    /// The join would fail, or is not possible at all.
+    Blocked,
+    /// The join would go through, but something may need attention, or could not be checked.
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessStep {
+    pub check: String,
+    pub status: ReadinessStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnboardingDryRunResponse {
+    pub subnet: String,
+    pub validator: String,
+    /// Whether no step is blocked.
+    pub ready: bool,
+    pub steps: Vec<ReadinessStep>,
+    /// The 1-based position of the validator in the power table once joined, by collateral.
+    pub expected_position: Option<usize>,
+}
+
+/// The onboarding dry run json rpc method handler.
+pub(crate) struct OnboardingDryRunHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl OnboardingDryRunHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+/// The steps are appended in the order a validator goes through them.
+#[derive(Default)]
+struct Report {
+    steps: Vec<ReadinessStep>,
+}
+
+impl Report {
+    fn push(&mut self, check: &str, status: ReadinessStatus, detail: impl ToString) {
+        self.steps.push(ReadinessStep {
+            check: check.to_string(),
+            status,
+            detail: detail.to_string(),
+        });
+    }
+
+    fn check(&mut self, check: &str, ok: bool, ready: impl ToString, blocked: impl ToString) {
+        if ok {
+            self.push(check, ReadinessStatus::Ready, ready);
+        } else {
+            self.push(check, ReadinessStatus::Blocked, blocked);
+        }
+    }
+
+    fn ready(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|s| s.status != ReadinessStatus::Blocked)
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for OnboardingDryRunHandler {
+    type Request = OnboardingDryRunParams;
+    type Response = OnboardingDryRunResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = self
+            .pool
+            .get(&parent)
+            .ok_or_else(|| anyhow!("target parent subnet not found"))?;
+        check_subnet(conn.subnet())?;
+        let manager = conn.manager();
+
+        let validator = Address::from_str(&request.validator)?;
+        let worker = match &request.worker_addr {
+            Some(addr) => Address::from_str(addr)?,
+            None => validator,
+        };
+        let requirements = manager.join_requirements(&subnet).await?;
+        let collateral = match request.collateral {
+            Some(c) => parse_fil("collateral", c)?,
+            None => requirements.min_validator_stake.clone(),
+        };
+        let mut report = Report::default();
+
+        let mode = manager.permission_mode(&subnet).await?;
+        let admission = accepting_validators(&subnet, &conn, mode, &requirements).await?;
+        report.check(
+            "subnet_active",
+            admission.is_open(),
+            format!("subnet {subnet:} accepts validators"),
+            format!("subnet {subnet:}: {admission:}"),
+        );
+
+        match mode {
+            PermissionMode::Collateral => report.push(
+                "permission_mode",
+                ReadinessStatus::Ready,
+                "validators join by staking collateral",
+            ),
+            PermissionMode::Federated => report.push(
+                "permission_mode",
+                ReadinessStatus::Blocked,
+                "the subnet is federated, the owner must set the power of the validator with ipc_setFederatedPower",
+            ),
+            PermissionMode::Static => report.push(
+                "permission_mode",
+                ReadinessStatus::Blocked,
+                "the validator set of the subnet is fixed at genesis",
+            ),
+        }
+
+        let validators = manager
+            .get_validator_set(&subnet, Some(conn.subnet().gateway_addr()))
+            .await?
+            .validator_set
+            .validators
+            .unwrap_or_default();
+        let joined = validators
+            .iter()
+            .any(|v| v.addr == validator.to_string() || v.addr == worker.to_string());
+        report.check(
+            "not_a_validator",
+            !joined,
+            format!("{validator:} is not a validator of the subnet yet"),
+            format!("{validator:} is already a validator of the subnet"),
+        );
+
+        report.check(
+            "collateral",
+            collateral >= requirements.min_validator_stake,
+            format!(
+                "{collateral:} FIL covers the minimum collateral of {} FIL",
+                requirements.min_validator_stake
+            ),
+            format!(
+                "{collateral:} FIL is below the minimum collateral of {} FIL",
+                requirements.min_validator_stake
+            ),
+        );
+
+        let join = OfflineCall::Join {
+            subnet: subnet.clone(),
+            collateral: collateral.clone(),
+            validator_net_addr: request.validator_net_addr.clone().unwrap_or_default(),
+            worker_addr: worker,
+        };
+        let fee = manager
+            .estimate_fee(conn.subnet().gateway_addr(), validator, join)
+            .await;
+        let balance = manager.wallet_balance(&validator).await?;
+        match fee {
+            Ok(fee) => {
+                let required = collateral.clone() + fee.clone();
+                report.check(
+                    "balance",
+                    balance >= required,
+                    format!("the balance of {balance:} FIL covers the collateral and a fee of up to {fee:} FIL"),
+                    format!("the balance of {balance:} FIL does not cover the collateral and a fee of up to {fee:} FIL, {} FIL missing", required - balance.clone()),
+                );
+            }
+            Err(e) => {
+                let status = if balance >= collateral {
+                    ReadinessStatus::Warning
+                } else {
+                    ReadinessStatus::Blocked
+                };
+                report.push(
+                    "balance",
+                    status,
+                    format!("the balance is {balance:} FIL, the fee of the join cannot be estimated: {e:}"),
+                );
+            }
+        }
+
+        match &request.validator_net_addr {
+            Some(addr) if is_multiaddr(addr) => {
+                report.push("net_addr", ReadinessStatus::Ready, addr)
+            }
+            Some(addr) => report.push(
+                "net_addr",
+                ReadinessStatus::Warning,
+                format!("{addr:} does not look like a multiaddr, other validators may not reach the validator"),
+            ),
+            None => report.push(
+                "net_addr",
+                ReadinessStatus::Warning,
+                "no net address given, it can be set after joining with ipc_setValidatorNetAddr",
+            ),
+        }
+
+        // fvm subnet actors only accept validators working with their own key
+        let same_worker =
+            matches!(conn.subnet().config, SubnetConfig::Fevm(_)) || worker == validator;
+        report.check(
+            "worker_addr",
+            same_worker,
+            format!("{worker:} signs for the validator in the subnet"),
+            "fvm subnets need the worker address to be the validator itself",
+        );
+
+        let expected_position = expected_position(&validators, &collateral);
+        if let Some(position) = expected_position {
+            let after = validators.len() as u64 + 1;
+            report.push(
+                "power_table",
+                ReadinessStatus::Ready,
+                format!(
+                    "position {position:} of {after:} validators by collateral, the subnet needs {} to be active",
+                    requirements.min_validators
+                ),
+            );
+        }
+
+        Ok(OnboardingDryRunResponse {
+            subnet: subnet.to_string(),
+            validator: validator.to_string(),
+            ready: report.ready(),
+            steps: report.steps,
+            expected_position,
+        })
+    }
+}
+
+/// Whether `addr` looks like a multiaddr, e.g. `/ip4/10.0.0.1/tcp/1347/p2p/12D3...`.
+fn is_multiaddr(addr: &str) -> bool {
+    addr.starts_with('/') && addr.split('/').filter(|s| !s.is_empty()).count() >= 2
+}
+
+/// Returns the 1-based position a validator staking `collateral` would take in the power table
+/// of `validators`, after the validators with the same weight. `None` if a weight is not a number.
+fn expected_position(validators: &[Validator], collateral: &TokenAmount) -> Option<usize> {
+    let mut ahead = 0;
+    for v in validators {
+        let weight = BigInt::from_str(&v.weight).ok()?;
+        if &weight >= collateral.atto() {
+            ahead += 1;
+        }
+    }
+    Some(ahead + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::econ::TokenAmount;
+
+    use crate::lotus::message::ipc::Validator;
+    use crate::server::handlers::manager::onboarding::{expected_position, is_multiaddr};
+
+    #[test]
+    fn test_expected_position() {
+        let validator = |weight: &str| Validator {
+            addr: String::from("f01001"),
+            net_addr: String::new(),
+            worker_addr: None,
+            weight: weight.to_string(),
+        };
+        let validators = vec![
+            validator("3000000000000000000"),
+            validator("1000000000000000000"),
+            validator("2000000000000000000"),
+        ];
+        assert_eq!(
+            expected_position(&validators, &TokenAmount::from_whole(2)),
+            Some(3)
+        );
+        assert_eq!(
+            expected_position(&validators, &TokenAmount::from_whole(5)),
+            Some(1)
+        );
+        assert_eq!(expected_position(&[], &TokenAmount::from_whole(1)), Some(1));
+        assert_eq!(
+            expected_position(&[validator("heavy")], &TokenAmount::from_whole(1)),
+            None
+        );
+
+        assert!(is_multiaddr("/ip4/10.0.0.1/tcp/1347"));
+        assert!(!is_multiaddr("10.0.0.1:1347"));
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bottom-up cross messages pending in the gateway for the next checkpoint
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::manager::clock::{EpochClock, EpochEta};
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingBottomUpMsgsParams {
+    /// The child subnet whose gateway accumulates the bottom-up messages
+    pub subnet_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingBottomUpMsg {
+    pub from: String,
+    pub to: String,
+    pub method: u64,
+    pub value: Amount,
+    pub nonce: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingBottomUpMsgsResponse {
+    /// The checkpoint the messages will be included in
+    pub checkpoint: EpochEta,
+    /// The fee accumulated for the batch of messages
+    pub fee: Amount,
+    pub msgs: Vec<PendingBottomUpMsg>,
+}
+
+/// The pending bottom-up messages json rpc method handler.
+pub(crate) struct PendingBottomUpMsgsHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl PendingBottomUpMsgsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for PendingBottomUpMsgsHandler {
+    type Request = PendingBottomUpMsgsParams;
+    type Response = PendingBottomUpMsgsResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet_id)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        let (epoch, batch) = conn
+            .manager()
+            .pending_bottom_up_msgs(&subnet_config.gateway_addr())
+            .await?;
+        tracing::debug!("pending bottom up msgs for epoch {epoch:}: {batch:?}");
+
+        let msgs = batch
+            .cross_msgs
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| {
+                Ok(PendingBottomUpMsg {
+                    from: c.msg.from.to_string()?,
+                    to: c.msg.to.to_string()?,
+                    method: c.msg.method,
+                    value: Amount::from(&c.msg.value),
+                    nonce: c.msg.nonce,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let clock = EpochClock::from_subnet(
+            &self.pool.state().epoch_calibrations,
+            subnet_config,
+            conn.manager(),
+        )
+        .await?;
+
+        Ok(PendingBottomUpMsgsResponse {
+            checkpoint: clock.eta(epoch),
+            fee: Amount::from(&batch.fee),
+            msgs,
+        })
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Messages of the accounts of the agent pending in the memory pool of a subnet
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::manager::activity::PendingMessage;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListPendingMessagesParams {
+    pub subnet: String,
+    /// The sender of the messages, the accounts of the subnet in the config if not set.
+    pub from: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingMessageEntry {
+    pub cid: String,
+    pub from: String,
+    pub to: String,
+    pub value: Amount,
+    pub method: u64,
+    pub nonce: u64,
+    pub gas_fee_cap: String,
+    pub gas_premium: String,
+    /// Whether the message waits for a message with a lower nonce that is not in the memory pool.
+    pub stuck: bool,
+}
+
+impl From<PendingMessage> for PendingMessageEntry {
+    fn from(m: PendingMessage) -> Self {
+        Self {
+            cid: m.id,
+            from: m.from.to_string(),
+            to: m.to.to_string(),
+            value: Amount::from(&m.value),
+            method: m.method,
+            nonce: m.nonce,
+            gas_fee_cap: m.gas_fee_cap.atto().to_string(),
+            gas_premium: m.gas_premium.atto().to_string(),
+            stuck: m.stuck,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListPendingMessagesResponse {
+    /// The pending messages, by sender and nonce.
+    pub messages: Vec<PendingMessageEntry>,
+}
+
+/// The handler listing the messages of the accounts of the agent that are pending in a subnet,
+/// to find the ones that are stuck.
+pub(crate) struct ListPendingMessagesHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl ListPendingMessagesHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListPendingMessagesHandler {
+    type Request = ListPendingMessagesParams;
+    type Response = ListPendingMessagesResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("subnet {subnet:} not configured")),
+            Some(conn) => conn,
+        };
+        check_subnet(conn.subnet())?;
+
+        let (from, accounts) = match request.from {
+            Some(from) => {
+                let from = Address::from_str(&from)?;
+                (Some(from), vec![from])
+            }
+            None => (None, conn.subnet().accounts()),
+        };
+
+        let mut messages = conn
+            .manager()
+            .pending_messages(from)
+            .await?
+            .into_iter()
+            .filter(|m| accounts.contains(&m.from))
+            .map(PendingMessageEntry::from)
+            .collect::<Vec<_>>();
+        messages.sort_by(|a, b| (&a.from, a.nonce).cmp(&(&b.from, b.nonce)));
+
+        let stuck = messages.iter().filter(|m| m.stuck).count();
+        if stuck > 0 {
+            tracing::warn!("{stuck:} pending messages of the agent are stuck in subnet {subnet:}");
+        }
+
+        Ok(ListPendingMessagesResponse { messages })
+    }
+}
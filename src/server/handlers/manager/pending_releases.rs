@@ -0,0 +1,183 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Releases pending in the gateway of a subnet for its next bottom-up checkpoint
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::METHOD_SEND;
+use ipc_sdk::cross::CrossMsg;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::manager::clock::{EpochClock, EpochEta};
+use crate::server::handlers::manager::check_subnet;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingReleasesParams {
+    /// The child subnet the funds are released from
+    pub subnet: String,
+    /// Only list the releases initiated by this account
+    pub from: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingRelease {
+    /// The account that initiated the release in the subnet
+    pub from: String,
+    /// The account credited in the parent
+    pub to: String,
+    pub amount: Amount,
+    pub nonce: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingReleasesResponse {
+    /// The checkpoint the releases will be included in
+    pub checkpoint: EpochEta,
+    /// The total amount of the releases listed
+    pub total: Amount,
+    pub releases: Vec<PendingRelease>,
+}
+
+/// Returns the initiating account and the recipient of `msg` if it is a release of funds from
+/// `subnet` to its parent, i.e. a plain transfer to the parent.
+fn release_accounts(
+    subnet: &SubnetID,
+    msg: &CrossMsg,
+) -> anyhow::Result<Option<(Address, Address)>> {
+    let msg = &msg.msg;
+    if msg.method != METHOD_SEND || !msg.params.bytes().is_empty() {
+        return Ok(None);
+    }
+    if msg.from.subnet()? != *subnet || Some(msg.to.subnet()?) != subnet.parent() {
+        return Ok(None);
+    }
+    Ok(Some((msg.from.raw_addr()?, msg.to.raw_addr()?)))
+}
+
+/// The pending releases json rpc method handler.
+pub(crate) struct PendingReleasesHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl PendingReleasesHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for PendingReleasesHandler {
+    type Request = PendingReleasesParams;
+    type Response = PendingReleasesResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+        let from = request.from.as_deref().map(Address::from_str).transpose()?;
+
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+
+        let (epoch, batch) = conn
+            .manager()
+            .pending_bottom_up_msgs(&subnet_config.gateway_addr())
+            .await?;
+
+        let mut total = TokenAmount::from_atto(0);
+        let mut releases = vec![];
+        for msg in batch.cross_msgs.unwrap_or_default() {
+            let (sender, recipient) = match release_accounts(&subnet, &msg)? {
+                Some(accounts) => accounts,
+                None => continue,
+            };
+            if from.is_some() && from != Some(sender) {
+                continue;
+            }
+            total += msg.msg.value.clone();
+            releases.push(PendingRelease {
+                from: sender.to_string(),
+                to: recipient.to_string(),
+                amount: Amount::from(&msg.msg.value),
+                nonce: msg.msg.nonce,
+            });
+        }
+        tracing::debug!(
+            "{} release(s) pending in subnet {subnet:} for epoch {epoch:}",
+            releases.len()
+        );
+
+        let clock = EpochClock::from_subnet(
+            &self.pool.state().epoch_calibrations,
+            subnet_config,
+            conn.manager(),
+        )
+        .await?;
+
+        Ok(PendingReleasesResponse {
+            checkpoint: clock.eta(epoch),
+            total: Amount::from(total),
+            releases,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::address::IPCAddress;
+    use ipc_sdk::cross::{CrossMsg, StorableMsg};
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::server::pending_releases::release_accounts;
+
+    fn msg(from: &SubnetID, to: &SubnetID, method: u64) -> CrossMsg {
+        CrossMsg {
+            msg: StorableMsg {
+                from: IPCAddress::new(from, &Address::new_id(100)).unwrap(),
+                to: IPCAddress::new(to, &Address::new_id(200)).unwrap(),
+                method,
+                params: RawBytes::default(),
+                value: TokenAmount::from_whole(1),
+                nonce: 0,
+            },
+            wrapped: false,
+        }
+    }
+
+    #[test]
+    fn test_release_accounts() {
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let parent = subnet.parent().unwrap();
+        let sibling = SubnetID::from_str("/r123/f0200").unwrap();
+
+        assert_eq!(
+            release_accounts(&subnet, &msg(&subnet, &parent, 0)).unwrap(),
+            Some((Address::new_id(100), Address::new_id(200)))
+        );
+        // a message to a sibling, or calling an actor in the parent, is not a release
+        assert_eq!(
+            release_accounts(&subnet, &msg(&subnet, &sibling, 0)).unwrap(),
+            None
+        );
+        assert_eq!(
+            release_accounts(&subnet, &msg(&subnet, &parent, 2)).unwrap(),
+            None
+        );
+    }
+}
@@ -2,10 +2,14 @@
 // SPDX-License-Identifier: MIT
 //! Propagate operation in the gateway actor
 
+use crate::amount::parse_fil;
+use crate::config::json_rpc_methods;
+use crate::events::AgentEvent;
 use crate::server::handlers::manager::subnet::SubnetManagerPool;
 use crate::server::{check_subnet, parse_from, JsonRPCRequestHandler};
 use anyhow::anyhow;
 use async_trait::async_trait;
+use fvm_shared::econ::TokenAmount;
 use ipc_sdk::subnet_id::SubnetID;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -16,6 +20,9 @@ pub struct PropagateParams {
     pub subnet: String,
     pub from: Option<String>,
     pub postbox_msg_key: Vec<u8>,
+    /// Extra fee attached to the propagation, in whole FIL, for messages whose fee does not cover
+    /// the cross-msg fee of the gateway
+    pub fee: Option<f64>,
 }
 
 /// The Propagate json rpc method handler.
@@ -43,17 +50,41 @@ impl JsonRPCRequestHandler for PropagateHandler {
 
         let subnet_config = conn.subnet();
         check_subnet(subnet_config)?;
+        if !subnet_config.features.propagate {
+            return Err(anyhow!(
+                "cross-msg propagation is disabled for subnet {}",
+                subnet_config.id
+            ));
+        }
 
         let from = parse_from(subnet_config, request.from)?;
         let subnet = SubnetID::from_str(&request.subnet)?;
+        let fee = match request.fee {
+            Some(fee) => parse_fil("fee", fee)?,
+            None => TokenAmount::from_atto(0),
+        };
+        if !fee.is_zero() {
+            tracing::info!("propagating in subnet {subnet:} with an extra fee of {fee:}");
+        }
 
         conn.manager()
             .propagate(
-                subnet,
+                subnet.clone(),
                 subnet_config.gateway_addr(),
                 from,
                 request.postbox_msg_key,
+                fee,
             )
-            .await
+            .await?;
+        self.pool.state().events.publish(AgentEvent::CrossMsgSent {
+            subnet: subnet.to_string(),
+            method: String::from(json_rpc_methods::PROPAGATE),
+            from: from.to_string(),
+            to: None,
+            value: None,
+            epoch: None,
+        });
+
+        Ok(())
     }
 }
@@ -0,0 +1,138 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! State of an actor of a subnet, decoded for the actors with a known schema
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use cid::Cid;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::amount::Amount;
+use crate::config::subnet::NetworkType;
+use crate::config::{ReloadableConfig, StateSchema};
+use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::schema::{check_state, SchemaRegistry};
+use crate::lotus::LotusClient;
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::server::handlers::manager::check_subnet;
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadStateParams {
+    pub subnet: String,
+    /// The fvm or evm address of the actor.
+    pub address: String,
+    /// Returns the raw state even if the actor has a known schema.
+    #[serde(default)]
+    pub raw: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadStateResponse {
+    pub address: String,
+    pub code: String,
+    pub balance: Amount,
+    /// The schema the state was decoded with, not set if it is returned raw.
+    pub schema: Option<StateSchema>,
+    /// The state decoded by the node, for the actors with a known schema.
+    pub state: Option<Value>,
+    /// The hex encoded cbor of the state, for the other actors.
+    pub raw: Option<String>,
+}
+
+/// The handler reading the state of an actor, decoded with the schema of its code if the agent
+/// knows it and as raw cbor otherwise.
+pub(crate) struct ReadStateHandler {
+    config: Arc<ReloadableConfig>,
+    state: Arc<AgentState>,
+}
+
+impl ReadStateHandler {
+    pub(crate) fn new(config: Arc<ReloadableConfig>, state: Arc<AgentState>) -> Self {
+        Self { config, state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ReadStateHandler {
+    type Request = ReadStateParams;
+    type Response = ReadStateResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet_id = SubnetID::from_str(&request.subnet)?;
+        let config = self.config.get_config();
+        let subnet = config
+            .subnets
+            .get(&subnet_id)
+            .ok_or_else(|| anyhow!("subnet {subnet_id:} not configured"))?;
+        check_subnet(subnet)?;
+        let address = if request.address.starts_with("0x") {
+            ethers_address_to_fil_address(&ethers::types::Address::from_str(&request.address)?)?
+        } else {
+            Address::from_str(&request.address)?
+        };
+
+        // fevm subnets are served by lotus nodes too
+        let client = LotusJsonRPCClient::from_subnet(subnet, self.state.clone());
+        let actor = client.state_get_actor(&address).await?;
+        let code = Cid::try_from(actor.code)?;
+        let head = Cid::try_from(actor.head)?;
+
+        let schema = if request.raw {
+            None
+        } else {
+            let version = client.state_network_version(vec![]).await?;
+            let manifest = client.state_actor_code_cids(version).await?;
+            let registry = SchemaRegistry::new(&manifest, &config.state_schemas)?;
+            registry.schema(&code).or_else(|| {
+                // gateways deployed outside of the bundle are only known by their address
+                (subnet.network_type() == NetworkType::Fvm && address == subnet.gateway_addr())
+                    .then_some(StateSchema::Gateway)
+            })
+        };
+
+        let mut response = ReadStateResponse {
+            address: address.to_string(),
+            code: code.to_string(),
+            balance: Amount::from(&TokenAmount::from_atto(BigInt::from_str(&actor.balance)?)),
+            schema: None,
+            state: None,
+            raw: None,
+        };
+        if let Some(schema) = schema {
+            let tipset = client
+                .chain_head()
+                .await?
+                .cids
+                .first()
+                .cloned()
+                .ok_or_else(|| anyhow!("empty chain head"))?;
+            let state = client
+                .read_state::<Value>(address, Cid::try_from(tipset)?)
+                .await?
+                .state;
+            match check_state(schema, &state) {
+                Ok(()) => {
+                    response.schema = Some(schema);
+                    response.state = Some(state);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    tracing::warn!("cannot decode the state of {address:}, returning it raw: {e:}")
+                }
+            }
+        }
+
+        response.raw = Some(hex::encode(client.chain_read_obj(head).await?));
+        Ok(response)
+    }
+}
@@ -2,8 +2,13 @@
 // SPDX-License-Identifier: MIT
 //! Release operation in the gateway actor
 
-use crate::server::handlers::manager::subnet::SubnetManagerPool;
-use crate::server::{check_subnet, handlers, parse_from, JsonRPCRequestHandler};
+use crate::amount::{parse_fil, Amount};
+use crate::checkpoint::next_checkpoint_epoch;
+use crate::config::json_rpc_methods;
+use crate::events::AgentEvent;
+use crate::manager::clock::{EpochClock, EpochEta};
+use crate::server::handlers::manager::subnet::{Connection, SubnetManagerPool};
+use crate::server::{check_subnet, parse_from, JsonRPCRequestHandler};
 use anyhow::anyhow;
 use async_trait::async_trait;
 use fvm_shared::address::Address;
@@ -22,6 +27,17 @@ pub struct ReleaseParams {
     pub amount: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReleaseResponse {
+    /// The epoch the release was executed in the subnet
+    pub epoch: ChainEpoch,
+    /// The amount released
+    pub amount: Amount,
+    /// Estimate of the bottom-up checkpoint that carries the released funds to the parent, if it
+    /// could be determined
+    pub eta: Option<EpochEta>,
+}
+
 /// The Release json rpc method handler.
 pub(crate) struct ReleaseHandler {
     pool: Arc<SubnetManagerPool>,
@@ -31,12 +47,33 @@ impl ReleaseHandler {
     pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
         Self { pool }
     }
+
+    /// Released funds leave the subnet with the bottom-up checkpoint of the window the release
+    /// was executed in.
+    async fn checkpoint_eta(
+        &self,
+        conn: &Connection,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<EpochEta> {
+        let period = conn
+            .manager()
+            .bottom_up_check_period(&conn.subnet().gateway_addr())
+            .await?;
+
+        let clock = EpochClock::from_subnet(
+            &self.pool.state().epoch_calibrations,
+            conn.subnet(),
+            conn.manager(),
+        )
+        .await?;
+        Ok(clock.eta(next_checkpoint_epoch(epoch, period)))
+    }
 }
 
 #[async_trait]
 impl JsonRPCRequestHandler for ReleaseHandler {
     type Request = ReleaseParams;
-    type Response = ChainEpoch;
+    type Response = ReleaseResponse;
 
     async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
         let subnet = SubnetID::from_str(&request.subnet)?;
@@ -48,7 +85,7 @@ impl JsonRPCRequestHandler for ReleaseHandler {
         let subnet_config = conn.subnet();
         check_subnet(subnet_config)?;
 
-        let amount = handlers::f64_to_token_amount(request.amount)?;
+        let amount = parse_fil("amount", request.amount)?;
         let from = parse_from(subnet_config, request.from)?;
         let to = request
             .to
@@ -56,8 +93,39 @@ impl JsonRPCRequestHandler for ReleaseHandler {
             .transpose()?
             .unwrap_or(from);
 
-        conn.manager()
-            .release(subnet, subnet_config.gateway_addr(), from, to, amount)
-            .await
+        let epoch = conn
+            .manager()
+            .release(
+                subnet.clone(),
+                subnet_config.gateway_addr(),
+                from,
+                to,
+                amount.clone(),
+            )
+            .await?;
+        self.pool.state().events.publish(AgentEvent::CrossMsgSent {
+            subnet: subnet.to_string(),
+            method: String::from(json_rpc_methods::RELEASE),
+            from: from.to_string(),
+            to: Some(to.to_string()),
+            value: Some(Amount::from(&amount)),
+            epoch: Some(epoch),
+        });
+
+        let eta = match self.checkpoint_eta(&conn, epoch).await {
+            Ok(eta) => Some(eta),
+            Err(e) => {
+                tracing::warn!(
+                    "cannot estimate the checkpoint of release in subnet {subnet:}: {e:}"
+                );
+                None
+            }
+        };
+
+        Ok(ReleaseResponse {
+            epoch,
+            amount: Amount::from(amount),
+            eta,
+        })
     }
 }
@@ -0,0 +1,95 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Replacement of a message of the agent pending in the memory pool of a subnet
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::subnet::FvmSigner;
+use crate::manager::activity::ReplacementGas;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::handlers::manager::{check_subnet, parse_from};
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceMessageParams {
+    pub subnet: String,
+    /// The sender of the message, the first account of the subnet in the config if not set.
+    pub from: Option<String>,
+    pub nonce: u64,
+    /// In attoFIL per unit of gas.
+    pub gas_fee_cap: String,
+    /// In attoFIL per unit of gas, at least 25% above the one of the pending message.
+    pub gas_premium: String,
+    /// The gas limit of the pending message is kept if not set.
+    pub gas_limit: Option<u64>,
+    /// Replaces the message with an empty send to the sender itself instead of sending it again.
+    #[serde(default)]
+    pub cancel: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceMessageResponse {
+    /// The cid of the replacement, or the hash of the transaction in evm subnets.
+    pub id: String,
+}
+
+/// The handler replacing a message of the agent stuck in the memory pool of a subnet with one
+/// paying more gas, or cancelling it.
+pub(crate) struct ReplaceMessageHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl ReplaceMessageHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ReplaceMessageHandler {
+    type Request = ReplaceMessageParams;
+    type Response = ReplaceMessageResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("subnet {subnet:} not configured")),
+            Some(conn) => conn,
+        };
+        let subnet_config = conn.subnet();
+        check_subnet(subnet_config)?;
+        // the node assigns its own nonce to the messages it signs
+        if subnet_config.fvm_signer() == FvmSigner::Node {
+            return Err(anyhow!(
+                "messages signed by the node of subnet {subnet:} cannot be replaced by the agent, use `lotus mpool replace`"
+            ));
+        }
+
+        let from = parse_from(subnet_config, request.from)?;
+        let gas = ReplacementGas {
+            gas_fee_cap: TokenAmount::from_atto(BigInt::from_str(&request.gas_fee_cap)?),
+            gas_premium: TokenAmount::from_atto(BigInt::from_str(&request.gas_premium)?),
+            gas_limit: request.gas_limit,
+        };
+
+        tracing::info!(
+            "json rpc: replacing the message of {from:} with nonce {} in subnet {subnet:}{}",
+            request.nonce,
+            if request.cancel { " to cancel it" } else { "" }
+        );
+        let id = conn
+            .manager()
+            .replace_message(from, request.nonce, gas, request.cancel)
+            .await?;
+
+        Ok(ReplaceMessageResponse { id })
+    }
+}
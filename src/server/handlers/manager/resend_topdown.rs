@@ -0,0 +1,273 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Resend a range of top-down messages to a subnet, to recover from incidents such as a reset of
+//! the child subnet.
+//!
+//! The messages are fetched from the parent and voted in a top-down checkpoint of the child as
+//! the regular top-down flow does, but only once the range is checked to start at the nonce the
+//! child applies next, so that no message is applied twice nor skipped.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_gateway::TopDownCheckpoint;
+use ipc_sdk::cross::CrossMsg;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::{check_subnet, parse_from, JsonRPCRequestHandler};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResendTopDownMsgsParams {
+    /// The child subnet, both it and its parent must be in the config
+    pub subnet: String,
+    /// The nonce of the first message to resend, the next one the child applies
+    pub from_nonce: u64,
+    /// The nonce of the last message to resend, all the pending ones if not set
+    pub to_nonce: Option<u64>,
+    /// The validator of the child voting the checkpoint, the default account of the child if
+    /// not set
+    pub from: Option<String>,
+    /// Only check the range and build the checkpoint, without voting it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResendTopDownMsgsResponse {
+    /// The epoch of the top-down checkpoint carrying the messages
+    pub epoch: ChainEpoch,
+    pub from_nonce: u64,
+    pub to_nonce: u64,
+    /// The number of messages in the checkpoint
+    pub msgs: usize,
+    /// The epoch the checkpoint was voted at in the child, unset in dry runs
+    pub submitted_at: Option<ChainEpoch>,
+}
+
+/// The resend top-down messages json rpc method handler.
+pub(crate) struct ResendTopDownMsgsHandler {
+    pool: Arc<SubnetManagerPool>,
+    /// The subnets whose messages are being resent, so that concurrent requests do not vote the
+    /// same messages twice.
+    resending: Mutex<HashSet<SubnetID>>,
+}
+
+impl ResendTopDownMsgsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self {
+            pool,
+            resending: Mutex::new(HashSet::new()),
+        }
+    }
+
+    async fn resend(
+        &self,
+        subnet: &SubnetID,
+        request: ResendTopDownMsgsParams,
+    ) -> anyhow::Result<ResendTopDownMsgsResponse> {
+        let parent_id = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent = match self.pool.get(&parent_id) {
+            None => return Err(anyhow!("parent subnet {parent_id:} not configured")),
+            Some(conn) => conn,
+        };
+        let child = match self.pool.get(subnet) {
+            None => return Err(anyhow!("subnet {subnet:} not configured")),
+            Some(conn) => conn,
+        };
+        check_subnet(parent.subnet())?;
+        check_subnet(child.subnet())?;
+
+        let from = parse_from(child.subnet(), request.from)?;
+        let child_gateway = child.subnet().gateway_addr();
+
+        let applied = child
+            .manager()
+            .applied_top_down_nonce(&child_gateway)
+            .await?;
+
+        // the checkpoint following the last executed one, as the regular top-down flow votes it
+        let last_executed = child
+            .manager()
+            .last_topdown_executed(&child_gateway)
+            .await?;
+        let period = child
+            .manager()
+            .top_down_check_period(&child_gateway)
+            .await?;
+        let epoch = last_executed + period;
+        let head = parent.manager().chain_head_epoch().await?;
+        if epoch > head {
+            return Err(anyhow!(
+                "the next top-down checkpoint of subnet {subnet:} is at epoch {epoch:}, the parent is only at epoch {head:}"
+            ));
+        }
+
+        let msgs = parent
+            .manager()
+            .top_down_msgs_at(&parent.subnet().gateway_addr(), subnet, applied, epoch)
+            .await?;
+        let top_down_msgs = msgs_in_range(applied, request.from_nonce, request.to_nonce, msgs)?;
+        let to_nonce = top_down_msgs.last().unwrap().msg.nonce;
+        let msgs = top_down_msgs.len();
+
+        tracing::info!(
+            "resending top-down messages {:}..={to_nonce:} to subnet {subnet:} at epoch {epoch:} as {from:}",
+            request.from_nonce
+        );
+
+        let submitted_at = if request.dry_run {
+            None
+        } else {
+            let checkpoint = TopDownCheckpoint {
+                epoch,
+                top_down_msgs,
+            };
+            Some(
+                child
+                    .manager()
+                    .vote_top_down_checkpoint(&child_gateway, from, checkpoint)
+                    .await?,
+            )
+        };
+
+        Ok(ResendTopDownMsgsResponse {
+            epoch,
+            from_nonce: request.from_nonce,
+            to_nonce,
+            msgs,
+            submitted_at,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ResendTopDownMsgsHandler {
+    type Request = ResendTopDownMsgsParams;
+    type Response = ResendTopDownMsgsResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        if !self.resending.lock().unwrap().insert(subnet.clone()) {
+            return Err(anyhow!(
+                "top-down messages are already being resent to subnet {subnet:}"
+            ));
+        }
+
+        let r = self.resend(&subnet, request).await;
+        self.resending.lock().unwrap().remove(&subnet);
+        r
+    }
+}
+
+/// Checks that the range of nonces starts at the nonce `applied` by the child, and returns the
+/// messages in it out of `msgs`, the ones pending from `applied` on. Fails if the range is empty
+/// or has gaps.
+fn msgs_in_range(
+    applied: u64,
+    from_nonce: u64,
+    to_nonce: Option<u64>,
+    msgs: Vec<CrossMsg>,
+) -> anyhow::Result<Vec<CrossMsg>> {
+    if from_nonce < applied {
+        return Err(anyhow!(
+            "the messages up to nonce {:} are already applied in the subnet, resending from nonce {from_nonce:} would apply them twice",
+            applied - 1
+        ));
+    }
+    if from_nonce > applied {
+        return Err(anyhow!(
+            "the subnet applies the message with nonce {applied:} next, resending from nonce {from_nonce:} would skip the messages in between"
+        ));
+    }
+    if let Some(to_nonce) = to_nonce && to_nonce < from_nonce {
+        return Err(anyhow!("empty range of nonces {from_nonce:}..={to_nonce:}"));
+    }
+
+    let msgs = msgs
+        .into_iter()
+        .filter(|m| to_nonce.map_or(true, |to| m.msg.nonce <= to))
+        .collect::<Vec<_>>();
+    if msgs.is_empty() {
+        return Err(anyhow!(
+            "no top-down message pending from nonce {from_nonce:}"
+        ));
+    }
+    for (expected, m) in (from_nonce..).zip(msgs.iter()) {
+        if m.msg.nonce != expected {
+            return Err(anyhow!(
+                "the top-down message with nonce {expected:} is missing in the parent"
+            ));
+        }
+    }
+    if let Some(to_nonce) = to_nonce && msgs.last().unwrap().msg.nonce < to_nonce {
+        return Err(anyhow!(
+            "the top-down messages are only pending up to nonce {:}, not {to_nonce:}",
+            msgs.last().unwrap().msg.nonce
+        ));
+    }
+
+    Ok(msgs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::address::IPCAddress;
+    use ipc_sdk::cross::{CrossMsg, StorableMsg};
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::server::resend_topdown::msgs_in_range;
+
+    fn msgs(nonces: &[u64]) -> Vec<CrossMsg> {
+        let subnet = SubnetID::from_str("/r123/f0100").unwrap();
+        let addr = IPCAddress::new(&subnet, &Address::new_id(100)).unwrap();
+        nonces
+            .iter()
+            .map(|nonce| CrossMsg {
+                msg: StorableMsg {
+                    from: addr.clone(),
+                    to: addr.clone(),
+                    method: 0,
+                    params: RawBytes::default(),
+                    value: TokenAmount::from_whole(1),
+                    nonce: *nonce,
+                },
+                wrapped: false,
+            })
+            .collect()
+    }
+
+    fn nonces(msgs: Vec<CrossMsg>) -> Vec<u64> {
+        msgs.into_iter().map(|m| m.msg.nonce).collect()
+    }
+
+    #[test]
+    fn test_msgs_in_range() {
+        let r = msgs_in_range(3, 3, None, msgs(&[3, 4, 5])).unwrap();
+        assert_eq!(nonces(r), vec![3, 4, 5]);
+        let r = msgs_in_range(3, 3, Some(4), msgs(&[3, 4, 5])).unwrap();
+        assert_eq!(nonces(r), vec![3, 4]);
+
+        // already applied, or skipping messages
+        assert!(msgs_in_range(3, 2, None, msgs(&[3, 4])).is_err());
+        assert!(msgs_in_range(3, 4, None, msgs(&[3, 4])).is_err());
+
+        // nothing to resend
+        assert!(msgs_in_range(3, 3, Some(2), msgs(&[3, 4])).is_err());
+        assert!(msgs_in_range(3, 3, None, vec![]).is_err());
+
+        // gaps, or not pending yet
+        assert!(msgs_in_range(3, 3, None, msgs(&[3, 5])).is_err());
+        assert!(msgs_in_range(3, 3, Some(6), msgs(&[3, 4])).is_err());
+    }
+}
@@ -2,24 +2,162 @@
 // SPDX-License-Identifier: MIT
 //! Send cross network message operation in the gateway actor
 
+use crate::amount::Amount;
+use crate::config::json_rpc_methods;
+use crate::events::AgentEvent;
 use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::handlers::manager::topology::{topdown_lag_status, NodeStatus};
 use crate::server::{check_subnet, parse_from, JsonRPCRequestHandler};
 use anyhow::anyhow;
 use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_gateway::Status;
 use ipc_sdk::cross::CrossMsg;
 use ipc_sdk::subnet_id::SubnetID;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
 
+/// The number of bottom-up checkpoint periods without a checkpoint of a subnet committed in its
+/// parent before its bottom-up checkpoints are reported as stalled.
+const MAX_BOTTOMUP_LAG_PERIODS: ChainEpoch = 3;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendCrossMsgParams {
     pub subnet: String,
     pub from: Option<String>,
     pub cross_message: CrossMsg,
+    /// Send the message even if a subnet of its route is not active, its checkpoints are stalled
+    /// or its fees are not covered.
+    #[serde(default)]
+    pub force: bool,
 }
 
-/// The WhitelistPropagator json rpc method handler.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendCrossMsgResponse {
+    /// The issues found along the route of a message sent with `force`.
+    pub warnings: Vec<String>,
+}
+
+/// A checkpoint the message is carried by on its route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Leg {
+    pub child: SubnetID,
+    pub parent: SubnetID,
+    pub bottom_up: bool,
+}
+
+/// Returns the checkpoints that carry a message from `from` to `to`: bottom-up up to their
+/// closest common ancestor, and top-down from there.
+pub(crate) fn route_legs(from: &SubnetID, to: &SubnetID) -> anyhow::Result<Vec<Leg>> {
+    let ancestors = |id: &SubnetID| {
+        let mut ids = vec![id.clone()];
+        while let Some(parent) = ids.last().unwrap().parent() {
+            ids.push(parent);
+        }
+        ids
+    };
+    let up = ancestors(from);
+    let down = ancestors(to);
+    let common = up
+        .iter()
+        .find(|id| down.contains(id))
+        .ok_or_else(|| anyhow!("subnets {from:} and {to:} are not in the same hierarchy"))?;
+
+    let mut legs = vec![];
+    for child in up.iter().take_while(|id| *id != common) {
+        legs.push(Leg {
+            child: child.clone(),
+            parent: child.parent().unwrap(),
+            bottom_up: true,
+        });
+    }
+    let mut top_down = down
+        .iter()
+        .take_while(|id| *id != common)
+        .map(|child| Leg {
+            child: child.clone(),
+            parent: child.parent().unwrap(),
+            bottom_up: false,
+        })
+        .collect::<Vec<_>>();
+    top_down.reverse();
+    legs.extend(top_down);
+    Ok(legs)
+}
+
+/// Checks that the child of `leg` is active and that its checkpoints in the direction of the
+/// leg are executed, returning the issue found if not.
+pub(crate) async fn check_leg(
+    pool: &SubnetManagerPool,
+    leg: &Leg,
+) -> anyhow::Result<Option<String>> {
+    let (child, parent) = match (pool.get(&leg.child), pool.get(&leg.parent)) {
+        (Some(c), Some(p)) => (c, p),
+        _ => return Err(anyhow!("subnet {} or its parent not configured", leg.child)),
+    };
+
+    let status = parent
+        .manager()
+        .list_child_subnets(parent.subnet().gateway_addr())
+        .await?
+        .remove(&leg.child)
+        .map(|info| info.status);
+    if !matches!(status, Some(Status::Active)) {
+        return Ok(Some(format!(
+            "subnet {} is {} in the gateway of {}",
+            leg.child,
+            status.map_or(String::from("not registered"), |s| format!("{s:?}")),
+            leg.parent
+        )));
+    }
+
+    let child_gateway = child.subnet().gateway_addr();
+    if leg.bottom_up {
+        let period = child
+            .manager()
+            .bottom_up_check_period(&child_gateway)
+            .await?;
+        let head = child.manager().chain_head_epoch().await?;
+        let window = MAX_BOTTOMUP_LAG_PERIODS * period;
+        if period <= 0 || head <= window {
+            return Ok(None);
+        }
+        let committed = parent
+            .manager()
+            .list_checkpoints(leg.child.clone(), head - window, head)
+            .await?;
+        if committed.is_empty() {
+            return Ok(Some(format!(
+                "no bottom-up checkpoint of subnet {} committed in {} over the last {window:} epochs",
+                leg.child, leg.parent
+            )));
+        }
+    } else {
+        let last_executed = child
+            .manager()
+            .last_topdown_executed(&child_gateway)
+            .await?;
+        let period = child
+            .manager()
+            .top_down_check_period(&child_gateway)
+            .await?;
+        let head = parent.manager().chain_head_epoch().await?;
+        if let (NodeStatus::Lagging, Some(detail)) = topdown_lag_status(head, last_executed, period)
+        {
+            return Ok(Some(format!(
+                "top-down checkpoints of subnet {}: {detail:}",
+                leg.child
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// The send cross message json rpc method handler.
 pub(crate) struct SendCrossMsgHandler {
     pool: Arc<SubnetManagerPool>,
 }
@@ -28,12 +166,92 @@ impl SendCrossMsgHandler {
     pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
         Self { pool }
     }
+
+    /// Checks that the message sent from `from` in the subnet `source` can make it to the end of
+    /// its route, returning the issues found. What cannot be checked, e.g. for the subnets not in
+    /// the config, is only logged.
+    async fn check_route(
+        &self,
+        source: &SubnetID,
+        from: &Address,
+        cross_msg: &CrossMsg,
+    ) -> anyhow::Result<Vec<String>> {
+        let destination = cross_msg.msg.to.subnet()?;
+        let legs = route_legs(source, &destination)?;
+        let mut issues = vec![];
+
+        for leg in legs.iter() {
+            match check_leg(&self.pool, leg).await {
+                Ok(Some(issue)) => issues.push(issue),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(
+                    "cannot check the checkpoints of subnet {} for a cross message: {e:}",
+                    leg.child
+                ),
+            }
+        }
+
+        // the message is propagated by the agent in the subnets between the source and the
+        // destination, paying the fee of their gateway
+        let mut fees = vec![(source.clone(), *from, cross_msg.msg.value.clone())];
+        let mut checked = HashSet::from([source.clone(), destination]);
+        for leg in legs.iter() {
+            for id in [&leg.child, &leg.parent] {
+                if !checked.insert(id.clone()) {
+                    continue;
+                }
+                match self
+                    .pool
+                    .get(id)
+                    .and_then(|c| c.subnet().accounts().first().cloned())
+                {
+                    Some(account) => fees.push((id.clone(), account, TokenAmount::default())),
+                    None => tracing::warn!(
+                        "no account of subnet {id:} in the config to propagate a cross message, its fee is not checked"
+                    ),
+                }
+            }
+        }
+        for (id, account, value) in fees {
+            if let Err(e) = self.check_fee(&id, &account, value, &mut issues).await {
+                tracing::warn!("cannot check the cross message fee in subnet {id:}: {e:}");
+            }
+        }
+
+        Ok(issues)
+    }
+
+    /// Checks that `account` can pay `value` and the cross message fee of the gateway of `subnet`.
+    async fn check_fee(
+        &self,
+        subnet: &SubnetID,
+        account: &Address,
+        value: TokenAmount,
+        issues: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let conn = self
+            .pool
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet not configured"))?;
+        let fee = conn
+            .manager()
+            .cross_msg_fee(&conn.subnet().gateway_addr())
+            .await?;
+        let required = value + fee;
+        let balance = conn.manager().wallet_balance(account).await?;
+        if balance < required {
+            issues.push(format!(
+                "balance of {account:} in subnet {subnet:} is {balance:}, {required:} required for the cross message fee"
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl JsonRPCRequestHandler for SendCrossMsgHandler {
     type Request = SendCrossMsgParams;
-    type Response = ();
+    type Response = SendCrossMsgResponse;
 
     async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
         let subnet = SubnetID::from_str(&request.subnet)?;
@@ -47,8 +265,74 @@ impl JsonRPCRequestHandler for SendCrossMsgHandler {
 
         let from = parse_from(subnet_config, request.from)?;
 
+        // funds sent along a stalled route are stuck in the gateway of an intermediate subnet
+        // until it recovers
+        let warnings = self
+            .check_route(&subnet, &from, &request.cross_message)
+            .await?;
+        if !warnings.is_empty() {
+            if !request.force {
+                return Err(anyhow!(
+                    "cross message not sent, set force to send it anyway: {}",
+                    warnings.join("; ")
+                ));
+            }
+            for w in warnings.iter() {
+                tracing::warn!("sending cross message anyway: {w:}");
+            }
+        }
+
+        let sent = AgentEvent::CrossMsgSent {
+            subnet: subnet.to_string(),
+            method: String::from(json_rpc_methods::SEND_CROSS_MSG),
+            from: from.to_string(),
+            to: request.cross_message.msg.to.to_string().ok(),
+            value: Some(Amount::from(&request.cross_message.msg.value)),
+            epoch: None,
+        };
         conn.manager()
             .send_cross_message(subnet_config.gateway_addr(), from, request.cross_message)
-            .await
+            .await?;
+        self.pool.state().events.publish(sent);
+        Ok(SendCrossMsgResponse { warnings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ipc_sdk::subnet_id::SubnetID;
+
+    use crate::server::handlers::manager::send_cross::{route_legs, Leg};
+
+    #[test]
+    fn test_route_legs() {
+        let id = |s: &str| SubnetID::from_str(s).unwrap();
+        let leg = |child: &str, bottom_up| Leg {
+            child: id(child),
+            parent: id(child).parent().unwrap(),
+            bottom_up,
+        };
+
+        // between siblings, up to their parent and down again
+        assert_eq!(
+            route_legs(&id("/r123/f0100"), &id("/r123/f0101")).unwrap(),
+            vec![leg("/r123/f0100", true), leg("/r123/f0101", false)]
+        );
+        assert_eq!(
+            route_legs(&id("/r123/f0100/f0200"), &id("/r123/f0101")).unwrap(),
+            vec![
+                leg("/r123/f0100/f0200", true),
+                leg("/r123/f0100", true),
+                leg("/r123/f0101", false)
+            ]
+        );
+        assert_eq!(
+            route_legs(&id("/r123"), &id("/r123/f0100/f0200")).unwrap(),
+            vec![leg("/r123/f0100", false), leg("/r123/f0100/f0200", false)]
+        );
+        assert!(route_legs(&id("/r123"), &id("/r123")).unwrap().is_empty());
+        assert!(route_legs(&id("/r123/f0100"), &id("/r124/f0100")).is_err());
     }
 }
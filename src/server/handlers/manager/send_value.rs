@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: MIT
 //! SendValue subnet handler and parameters
 
+use crate::amount::parse_fil;
 use crate::manager::evm::ethers_address_to_fil_address;
 use crate::server::handlers::manager::subnet::SubnetManagerPool;
 use crate::server::handlers::manager::{check_subnet, parse_from};
-use crate::server::{handlers, JsonRPCRequestHandler};
+use crate::server::JsonRPCRequestHandler;
 use anyhow::anyhow;
 use async_trait::async_trait;
 use fvm_shared::address::Address;
@@ -46,7 +47,7 @@ impl JsonRPCRequestHandler for SendValueHandler {
             Some(conn) => conn,
         };
 
-        let amount = handlers::f64_to_token_amount(request.amount)?;
+        let amount = parse_fil("amount", request.amount)?;
         let subnet_config = conn.subnet();
         check_subnet(subnet_config)?;
 
@@ -60,7 +61,9 @@ impl JsonRPCRequestHandler for SendValueHandler {
             }
         };
 
-        log::debug!("json rpc: received request to send amount: {amount:} from {from:} to {to:}");
+        tracing::debug!(
+            "json rpc: received request to send amount: {amount:} from {from:} to {to:}"
+        );
 
         conn.manager().send_value(from, to, amount).await?;
 
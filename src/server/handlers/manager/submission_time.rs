@@ -0,0 +1,182 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Estimate of when the agent submits the next checkpoints of a subnet
+
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::{
+    checkpoint_direction, earliest_vote_epoch, setup_manager_from_subnet, CheckpointManager,
+};
+use crate::config::subnet::VotePolicyKind;
+use crate::manager::clock::{EpochClock, EpochEta};
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateCheckpointSubmissionTimeParams {
+    pub subnet: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointSubmissionEstimate {
+    /// Either `bottom-up` or `top-down`
+    pub direction: String,
+    pub checkpoint_period: ChainEpoch,
+    pub last_executed_epoch: ChainEpoch,
+    /// The head of the subnet whose epochs drive the submissions: the child for bottom-up
+    /// checkpoints, the parent for top-down ones.
+    pub current_epoch: ChainEpoch,
+    /// The epoch of the next checkpoint the validators of the agent vote.
+    pub checkpoint_epoch: ChainEpoch,
+    /// The estimated time of the earliest submission of the vote, the epoch of the subnet whose
+    /// epochs drive the submissions at which the policy of the subnet allows it.
+    pub submission: EpochEta,
+    /// What the submission waits for besides the epoch, if anything.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateCheckpointSubmissionTimeResponse {
+    pub subnet: String,
+    /// The estimates of the directions checkpointed by the agent.
+    pub directions: Vec<CheckpointSubmissionEstimate>,
+}
+
+/// Predicts when the agent submits the next checkpoints of a subnet, so that jobs depending on
+/// them can be scheduled.
+pub(crate) struct EstimateCheckpointSubmissionTimeHandler {
+    pool: Arc<SubnetManagerPool>,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+}
+
+impl EstimateCheckpointSubmissionTimeHandler {
+    pub(crate) fn new(
+        pool: Arc<SubnetManagerPool>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    ) -> Self {
+        Self {
+            pool,
+            fvm_wallet,
+            evm_keystore,
+        }
+    }
+
+    async fn estimate(
+        &self,
+        manager: &dyn CheckpointManager,
+    ) -> anyhow::Result<CheckpointSubmissionEstimate> {
+        let period = manager.checkpoint_period();
+        let last_executed_epoch = manager.last_executed_epoch().await?;
+        let current_epoch = manager.current_epoch().await?;
+
+        let managed = manager.target_subnet().accounts();
+        let mut validators = manager.validators().await?;
+        validators.retain(|v| managed.contains(v));
+
+        // the next checkpoint is skipped if all the validators of the agent already voted it
+        let mut checkpoint_epoch = last_executed_epoch + period;
+        let mut voted = true;
+        for v in validators.iter() {
+            if manager.should_submit_in_epoch(v, checkpoint_epoch).await? {
+                voted = false;
+                break;
+            }
+        }
+        if voted && !validators.is_empty() {
+            checkpoint_epoch += period;
+        }
+
+        let epoch_subnet = manager.epoch_subnet();
+        let conn = self
+            .pool
+            .connect(epoch_subnet)
+            .ok_or_else(|| anyhow!("cannot connect to subnet {}", epoch_subnet.id))?;
+        let clock = EpochClock::from_subnet(
+            &self.pool.state().epoch_calibrations,
+            epoch_subnet,
+            conn.manager(),
+        )
+        .await?;
+        let policy = &manager.child_subnet().vote_policy;
+        let submission = clock.eta(earliest_vote_epoch(policy, checkpoint_epoch));
+
+        let condition = if validators.is_empty() {
+            Some(String::from(
+                "none of the validators of the subnet is managed by the agent",
+            ))
+        } else if self.pool.state().maintenance.is_enabled() {
+            Some(String::from(
+                "the submissions are paused while the agent is in maintenance",
+            ))
+        } else {
+            vote_condition(policy.kind).map(String::from)
+        };
+
+        Ok(CheckpointSubmissionEstimate {
+            direction: checkpoint_direction(manager).to_string(),
+            checkpoint_period: period,
+            last_executed_epoch,
+            current_epoch,
+            checkpoint_epoch,
+            submission,
+            condition,
+        })
+    }
+}
+
+/// What the policies that do not only wait for an epoch wait for.
+fn vote_condition(kind: VotePolicyKind) -> Option<&'static str> {
+    match kind {
+        VotePolicyKind::FollowMajority => Some("half of the other validators voting it"),
+        VotePolicyKind::ManualApproval => Some("the approval of an operator"),
+        VotePolicyKind::Default | VotePolicyKind::StrictLocal => None,
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for EstimateCheckpointSubmissionTimeHandler {
+    type Request = EstimateCheckpointSubmissionTimeParams;
+    type Response = EstimateCheckpointSubmissionTimeResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet_id = SubnetID::from_str(&request.subnet)?;
+        let subnets = self.pool.subnets();
+        let subnet = subnets
+            .get(&subnet_id)
+            .ok_or_else(|| anyhow!("subnet {subnet_id:} not configured"))?;
+        let managers = setup_manager_from_subnet(
+            &subnets,
+            subnet,
+            self.fvm_wallet.clone(),
+            self.evm_keystore.clone(),
+            self.pool.state(),
+        )
+        .await?;
+        if managers.is_empty() {
+            return Err(anyhow!(
+                "the agent does not manage the checkpoints of subnet {subnet_id:}"
+            ));
+        }
+
+        let mut directions = vec![];
+        for m in managers.iter() {
+            directions.push(self.estimate(m.as_ref()).await?);
+        }
+
+        Ok(EstimateCheckpointSubmissionTimeResponse {
+            subnet: subnet_id.to_string(),
+            directions,
+        })
+    }
+}
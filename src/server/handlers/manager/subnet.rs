@@ -2,13 +2,20 @@
 // SPDX-License-Identifier: MIT
 //! The shared subnet manager module for all subnet management related RPC method calls.
 
+use crate::checkpoint::{check_subnets_health, probe_subnet};
 use crate::config::subnet::SubnetConfig;
-use crate::config::{ReloadableConfig, Subnet};
+use crate::config::{
+    Config, ConfigIssue, ConfigValidation, ReloadableConfig, Subnet, SubnetValidation,
+};
+use crate::jsonrpc::pool::ConnectionStats;
 use crate::manager::{EthSubnetManager, LotusSubnetManager, SubnetManager};
+use crate::state::AgentState;
+use futures_util::future::join_all;
 use ipc_identity::PersistentKeyStore;
 use ipc_identity::Wallet;
 use ipc_sdk::subnet_id::SubnetID;
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 /// The subnet manager connection that holds the subnet config and the manager instance.
@@ -35,6 +42,7 @@ pub struct SubnetManagerPool {
     config: Arc<ReloadableConfig>,
     fvm_wallet: Arc<RwLock<Wallet>>,
     evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    state: Arc<AgentState>,
 }
 
 impl SubnetManagerPool {
@@ -42,45 +50,131 @@ impl SubnetManagerPool {
         reload_config: Arc<ReloadableConfig>,
         fvm_wallet: Arc<RwLock<Wallet>>,
         evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
     ) -> Self {
         Self {
             config: reload_config,
             fvm_wallet,
             evm_keystore,
+            state,
+        }
+    }
+
+    /// Get the state of the agent the managers of the pool share.
+    pub fn state(&self) -> &Arc<AgentState> {
+        &self.state
+    }
+
+    /// Get the subnets in the latest config.
+    pub fn subnets(&self) -> HashMap<SubnetID, Subnet> {
+        self.config.get_config().subnets.clone()
+    }
+
+    /// The use of the pooled connections to the nodes of the subnets in the latest config, for
+    /// the subnets a connection was made to.
+    pub fn connection_stats(&self) -> Vec<(SubnetID, ConnectionStats)> {
+        self.subnets()
+            .into_iter()
+            .filter_map(|(id, subnet)| {
+                Some((id, self.state.http_clients.stats(subnet.rpc_http())?))
+            })
+            .collect()
+    }
+
+    /// Checks that the nodes of `subnets` can be reached, recording the result in the subnet
+    /// health log.
+    pub async fn check_health(&self, subnets: &[Subnet]) {
+        check_subnets_health(
+            subnets,
+            self.fvm_wallet.clone(),
+            self.evm_keystore.clone(),
+            &self.state,
+        )
+        .await
+    }
+
+    /// Validates the subnets of `config`, see [`Config::validate`]. Unless `offline` is set, the
+    /// nodes of the subnets are probed as well, along with their gateways.
+    pub async fn validate_config(&self, config: &Config, offline: bool) -> ConfigValidation {
+        let mut validation = config.validate();
+        if offline {
+            return validation;
+        }
+
+        let probes = validation.subnets.iter_mut().filter_map(|v| {
+            let subnet = config
+                .subnets
+                .values()
+                .find(|s| s.id.to_string() == v.subnet)?;
+            Some(self.probe(subnet, v))
+        });
+        join_all(probes).await;
+        validation.refresh();
+        validation
+    }
+
+    async fn probe(&self, subnet: &Subnet, validation: &mut SubnetValidation) {
+        let conn = match self.connect(subnet) {
+            Some(conn) => conn,
+            None => {
+                validation.unreachable(subnet, "cannot connect to the node of the subnet");
+                return;
+            }
+        };
+        if let Err(e) = probe_subnet(subnet, conn.manager()).await {
+            validation.unreachable(subnet, e);
+            return;
+        }
+        validation.reachable = Some(true);
+
+        if let Err(e) = conn
+            .manager()
+            .bottom_up_check_period(&subnet.gateway_addr())
+            .await
+        {
+            validation.errors.push(ConfigIssue::new(
+                "config.gateway_addr",
+                format!("no compatible gateway at {}: {e:}", subnet.gateway_addr()),
+            ));
         }
     }
 
     /// Get the connection instance for the subnet.
     pub fn get(&self, subnet: &SubnetID) -> Option<Connection> {
         let config = self.config.get_config();
-        let subnets = &config.subnets;
-        match subnets.get(subnet) {
-            Some(subnet) => match &subnet.config {
-                SubnetConfig::Fvm(_) => {
-                    let manager = Box::new(LotusSubnetManager::from_subnet_with_wallet_store(
+        let subnet = config.subnets.get(subnet)?;
+        self.connect(subnet)
+    }
+
+    /// Get a connection instance for a subnet config that is not necessarily the one in the
+    /// config, e.g. to reach a subnet through another endpoint.
+    pub fn connect(&self, subnet: &Subnet) -> Option<Connection> {
+        match &subnet.config {
+            SubnetConfig::Fvm(_) => {
+                let manager = Box::new(LotusSubnetManager::from_subnet_with_wallet_store(
+                    subnet,
+                    self.fvm_wallet.clone(),
+                    self.state.clone(),
+                ));
+                Some(Connection {
+                    manager,
+                    subnet: subnet.clone(),
+                })
+            }
+            SubnetConfig::Fevm(_) => {
+                let manager = Box::new(
+                    EthSubnetManager::from_subnet_with_wallet_store(
                         subnet,
-                        self.fvm_wallet.clone(),
-                    ));
-                    Some(Connection {
-                        manager,
-                        subnet: subnet.clone(),
-                    })
-                }
-                SubnetConfig::Fevm(_) => {
-                    let manager = Box::new(
-                        EthSubnetManager::from_subnet_with_wallet_store(
-                            subnet,
-                            self.evm_keystore.clone(),
-                        )
-                        .ok()?,
-                    );
-                    Some(Connection {
-                        manager,
-                        subnet: subnet.clone(),
-                    })
-                }
-            },
-            None => None,
+                        self.evm_keystore.clone(),
+                        self.state.clone(),
+                    )
+                    .ok()?,
+                );
+                Some(Connection {
+                    manager,
+                    subnet: subnet.clone(),
+                })
+            }
         }
     }
 }
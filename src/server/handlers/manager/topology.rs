@@ -0,0 +1,255 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The hierarchy of the subnets configured in the agent, and of their children discovered in the
+//! gateways of the configured subnets, with the status of each subnet.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_gateway::Status;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::subnet::NetworkType;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+/// The number of top-down checkpoint periods a subnet can be behind its parent before being
+/// reported as lagging.
+pub const MAX_TOPDOWN_LAG_PERIODS: ChainEpoch = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TopologyParams {}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeStatus {
+    Healthy,
+    /// The top-down checkpoints executed in the subnet are behind its parent.
+    Lagging,
+    /// The subnet is not active in the gateway of its parent.
+    Inactive,
+    /// The node of the subnet cannot be reached by the agent.
+    Unreachable,
+    /// The subnet is not configured in the agent, so only its status in the parent is known.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyNode {
+    pub id: String,
+    /// The parent of the subnet, not set for the root.
+    pub parent: Option<String>,
+    /// Whether the subnet is configured in the agent, or only discovered in its parent.
+    pub configured: bool,
+    /// Either `fvm` or `fevm`, for configured subnets.
+    pub network_type: Option<String>,
+    pub status: NodeStatus,
+    /// Why the subnet is not healthy.
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TopologyResponse {
+    /// The subnets, sorted by id so that parents come before their children.
+    pub nodes: Vec<TopologyNode>,
+}
+
+/// The subnet topology json rpc method handler.
+pub(crate) struct TopologyHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl TopologyHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Lists the children of `subnet` in its gateway, with whether they are active.
+    async fn children(&self, subnet: &SubnetID) -> anyhow::Result<Vec<(SubnetID, Status)>> {
+        let conn = self
+            .pool
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet not configured"))?;
+        let children = conn
+            .manager()
+            .list_child_subnets(conn.subnet().gateway_addr())
+            .await?;
+        Ok(children
+            .into_iter()
+            .map(|(id, info)| (id, info.status))
+            .collect())
+    }
+
+    /// Returns the status of `subnet` based on how far behind its parent its top-down
+    /// checkpoints are.
+    async fn topdown_status(
+        &self,
+        subnet: &SubnetID,
+        parent: &SubnetID,
+    ) -> anyhow::Result<(NodeStatus, Option<String>)> {
+        let (child, parent) = match (self.pool.get(subnet), self.pool.get(parent)) {
+            (Some(c), Some(p)) => (c, p),
+            _ => return Ok((NodeStatus::Healthy, None)),
+        };
+
+        let gateway = child.subnet().gateway_addr();
+        let last_executed = child.manager().last_topdown_executed(&gateway).await?;
+        let period = child.manager().top_down_check_period(&gateway).await?;
+        let head = parent.manager().chain_head_epoch().await?;
+        Ok(topdown_lag_status(head, last_executed, period))
+    }
+}
+
+/// Compares the last top-down checkpoint executed in a subnet with the head of its parent.
+pub(crate) fn topdown_lag_status(
+    parent_head: ChainEpoch,
+    last_executed: ChainEpoch,
+    period: ChainEpoch,
+) -> (NodeStatus, Option<String>) {
+    let lag = parent_head - last_executed;
+    if period > 0 && lag > MAX_TOPDOWN_LAG_PERIODS * period {
+        (
+            NodeStatus::Lagging,
+            Some(format!(
+                "last top-down checkpoint executed at epoch {last_executed:}, {lag:} epochs behind the parent"
+            )),
+        )
+    } else {
+        (NodeStatus::Healthy, None)
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for TopologyHandler {
+    type Request = TopologyParams;
+    type Response = TopologyResponse;
+
+    async fn handle(&self, _request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnets = self.pool.subnets();
+
+        let mut nodes = BTreeMap::new();
+        for (id, subnet) in subnets.iter() {
+            let network_type = match subnet.network_type() {
+                NetworkType::Fvm => "fvm",
+                NetworkType::Fevm => "fevm",
+            };
+            nodes.insert(
+                id.to_string(),
+                TopologyNode {
+                    id: id.to_string(),
+                    parent: id.parent().map(|p| p.to_string()),
+                    configured: true,
+                    network_type: Some(String::from(network_type)),
+                    status: NodeStatus::Healthy,
+                    detail: None,
+                },
+            );
+        }
+
+        // the status of a subnet in the gateway of its parent takes precedence over its lag
+        let mut inactive = vec![];
+        let subnet_health = &self.pool.state().subnet_health;
+        for id in subnets.keys() {
+            if !subnet_health.is_healthy(id) {
+                continue;
+            }
+            let children = match self.children(id).await {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::warn!("cannot list the child subnets of {id:}: {e:}");
+                    continue;
+                }
+            };
+            for (child, status) in children {
+                let detail = (!matches!(status, Status::Active))
+                    .then(|| format!("{status:?} in the gateway of {id:}"));
+                match nodes.get_mut(&child.to_string()) {
+                    Some(node) => {
+                        if let Some(detail) = detail {
+                            node.status = NodeStatus::Inactive;
+                            node.detail = Some(detail);
+                            inactive.push(child);
+                        }
+                    }
+                    None => {
+                        let status = match detail {
+                            Some(_) => NodeStatus::Inactive,
+                            None => NodeStatus::Unknown,
+                        };
+                        nodes.insert(
+                            child.to_string(),
+                            TopologyNode {
+                                id: child.to_string(),
+                                parent: Some(id.to_string()),
+                                configured: false,
+                                network_type: None,
+                                status,
+                                detail,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        for (id, health) in subnet_health.all() {
+            if health.healthy {
+                continue;
+            }
+            if let Some(node) = nodes.get_mut(&id.to_string()) {
+                node.status = NodeStatus::Unreachable;
+                node.detail = health.error;
+            }
+        }
+
+        for id in subnets.keys() {
+            let parent = match id.parent() {
+                Some(p) => p,
+                None => continue,
+            };
+            if inactive.contains(id)
+                || !subnet_health.is_healthy(id)
+                || !subnet_health.is_healthy(&parent)
+            {
+                continue;
+            }
+            let (status, detail) = match self.topdown_status(id, &parent).await {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("cannot get the top-down lag of {id:}: {e:}");
+                    continue;
+                }
+            };
+            if let Some(node) = nodes.get_mut(&id.to_string()) {
+                node.status = status;
+                node.detail = detail;
+            }
+        }
+
+        Ok(TopologyResponse {
+            nodes: nodes.into_values().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::handlers::manager::topology::{topdown_lag_status, NodeStatus};
+
+    #[test]
+    fn test_topdown_lag_status() {
+        assert_eq!(topdown_lag_status(100, 95, 10).0, NodeStatus::Healthy);
+        assert_eq!(topdown_lag_status(100, 70, 10).0, NodeStatus::Healthy);
+
+        let (status, detail) = topdown_lag_status(100, 60, 10);
+        assert_eq!(status, NodeStatus::Lagging);
+        assert!(detail.unwrap().contains("40 epochs behind"));
+
+        // no period set in the gateway
+        assert_eq!(topdown_lag_status(100, 0, 0).0, NodeStatus::Healthy);
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The subnets a validator is part of
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::config::Subnet;
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListValidatorsAcrossSubnetsParams {
+    /// The fvm or evm address of the validator
+    pub address: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointVote {
+    /// Either `bottom-up` or `top-down`
+    pub direction: String,
+    /// The epoch of the checkpoint currently being voted
+    pub epoch: ChainEpoch,
+    pub voted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidatorSubnet {
+    pub subnet: String,
+    /// The stake of the validator as reported in the validator set
+    pub weight: String,
+    pub net_addr: String,
+    pub worker_addr: Option<String>,
+    /// The votes of the validator for the checkpoints of the current epoch, in the directions
+    /// checkpointed by the agent.
+    pub votes: Vec<CheckpointVote>,
+    /// The rewards accumulated by the validator and not withdrawn yet, `None` in fvm subnets,
+    /// whose subnet actor sends the rewards to the validators as they are distributed.
+    pub pending_rewards: Option<Amount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListValidatorsAcrossSubnetsResponse {
+    pub subnets: Vec<ValidatorSubnet>,
+    /// The subnets that could not be queried, with the error.
+    pub errors: HashMap<String, String>,
+}
+
+/// Lists the configured subnets whose validator set includes an address.
+pub(crate) struct ListValidatorsAcrossSubnetsHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl ListValidatorsAcrossSubnetsHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Returns the validator `address` in `subnet`, if it is part of its validator set.
+    async fn validator_subnet(
+        &self,
+        subnet: &Subnet,
+        address: &Address,
+    ) -> anyhow::Result<Option<ValidatorSubnet>> {
+        // the validator set is kept by the subnet actor in the parent
+        let parent = subnet
+            .id
+            .parent()
+            .ok_or_else(|| anyhow!("root subnets have no validator set"))?;
+        let conn = self
+            .pool
+            .get(&parent)
+            .ok_or_else(|| anyhow!("cannot connect to parent subnet"))?;
+
+        let response = conn
+            .manager()
+            .get_validator_set(&subnet.id, Some(conn.subnet().gateway_addr()))
+            .await?;
+        let validator = response
+            .validator_set
+            .validators
+            .unwrap_or_default()
+            .into_iter()
+            .find(|v| Address::from_str(&v.addr).map_or(false, |a| a == *address));
+        let validator = match validator {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        // the bottom-up checkpoints are voted in the subnet actor in the parent, and the
+        // top-down ones in the gateway of the subnet
+        let mut votes = vec![];
+        if subnet.features.bottom_up {
+            let (epoch, voted) = conn.manager().bottom_up_vote(&subnet.id, address).await?;
+            votes.push(CheckpointVote {
+                direction: String::from("bottom-up"),
+                epoch,
+                voted,
+            });
+        }
+        if subnet.features.top_down {
+            let child = self
+                .pool
+                .get(&subnet.id)
+                .ok_or_else(|| anyhow!("cannot connect to subnet"))?;
+            let (epoch, voted) = child
+                .manager()
+                .top_down_vote(&child.subnet().gateway_addr(), address)
+                .await?;
+            votes.push(CheckpointVote {
+                direction: String::from("top-down"),
+                epoch,
+                voted,
+            });
+        }
+
+        let pending_rewards = conn
+            .manager()
+            .pending_rewards(&subnet.id, address)
+            .await?
+            .map(Amount::from);
+
+        Ok(Some(ValidatorSubnet {
+            subnet: subnet.id.to_string(),
+            weight: validator.weight,
+            net_addr: validator.net_addr,
+            worker_addr: validator.worker_addr,
+            votes,
+            pending_rewards,
+        }))
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListValidatorsAcrossSubnetsHandler {
+    type Request = ListValidatorsAcrossSubnetsParams;
+    type Response = ListValidatorsAcrossSubnetsResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        // evm validators are recorded with their f410 address
+        let address = if request.address.starts_with("0x") {
+            ethers_address_to_fil_address(&ethers::types::Address::from_str(&request.address)?)?
+        } else {
+            Address::from_str(&request.address)?
+        };
+
+        let subnets = self.pool.subnets();
+        let mut configured = subnets
+            .values()
+            .filter(|s| s.id.parent().map_or(false, |p| subnets.contains_key(&p)))
+            .collect::<Vec<_>>();
+        configured.sort_by_key(|s| s.id.to_string());
+
+        let results = join_all(
+            configured
+                .iter()
+                .map(|s| self.validator_subnet(s, &address)),
+        )
+        .await;
+
+        let mut response = ListValidatorsAcrossSubnetsResponse {
+            subnets: vec![],
+            errors: HashMap::new(),
+        };
+        for (s, r) in configured.into_iter().zip(results) {
+            match r {
+                Ok(Some(v)) => response.subnets.push(v),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "cannot query validator {address:} in subnet {:}: {e:}",
+                        s.id
+                    );
+                    response.errors.insert(s.id.to_string(), e.to_string());
+                }
+            }
+        }
+
+        Ok(response)
+    }
+}
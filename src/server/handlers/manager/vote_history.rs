@@ -0,0 +1,231 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! History of the checkpoint votes of a validator in a subnet
+
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_identity::{PersistentKeyStore, Wallet};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::{checkpoint_direction, setup_manager_from_subnet, CheckpointManager};
+use crate::history::votes::{VoteRecord, VoteStatus};
+use crate::history::CheckpointStore;
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+/// The maximum number of checkpoints of a direction queried at once, one request to the node is
+/// made for each of those that are not cached.
+const MAX_VOTE_HISTORY_CHECKPOINTS: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryValidatorVoteHistoryParams {
+    pub subnet: String,
+    /// The fvm or evm address of the validator
+    pub validator: String,
+    pub from_epoch: ChainEpoch,
+    pub to_epoch: ChainEpoch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochVote {
+    pub epoch: ChainEpoch,
+    pub status: VoteStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectionVoteHistory {
+    /// Either `bottom-up` or `top-down`
+    pub direction: String,
+    pub checkpoint_period: ChainEpoch,
+    /// The votes on the executed checkpoints of the range, by epoch.
+    pub votes: Vec<EpochVote>,
+    pub voted: usize,
+    pub missed: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryValidatorVoteHistoryResponse {
+    pub subnet: String,
+    pub validator: String,
+    /// The history of the directions checkpointed by the agent.
+    pub directions: Vec<DirectionVoteHistory>,
+}
+
+/// Returns the per-epoch votes of a validator on the checkpoints of a subnet.
+pub(crate) struct QueryValidatorVoteHistoryHandler {
+    pool: Arc<SubnetManagerPool>,
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    store: CheckpointStore,
+}
+
+impl QueryValidatorVoteHistoryHandler {
+    pub(crate) fn new(
+        pool: Arc<SubnetManagerPool>,
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        store: CheckpointStore,
+    ) -> Self {
+        Self {
+            pool,
+            fvm_wallet,
+            evm_keystore,
+            store,
+        }
+    }
+
+    /// Returns the votes of `validator` on the checkpoints of `manager` executed between `from`
+    /// and `to`, caching those read from the chain.
+    async fn direction_history(
+        &self,
+        subnet: &SubnetID,
+        manager: &dyn CheckpointManager,
+        validator: &Address,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> anyhow::Result<DirectionVoteHistory> {
+        let direction = checkpoint_direction(manager).as_str();
+        let period = manager.checkpoint_period();
+        // the vote on a checkpoint that is not executed yet can still come
+        let to = to.min(manager.last_executed_epoch().await?);
+        let epochs = checkpoint_epochs(from, to, period);
+        if epochs.len() > MAX_VOTE_HISTORY_CHECKPOINTS {
+            return Err(anyhow!(
+                "range of {} {direction:} checkpoints, at most {MAX_VOTE_HISTORY_CHECKPOINTS:} can be queried at once",
+                epochs.len()
+            ));
+        }
+
+        let cached = self.store.read_votes(subnet, validator, direction).await?;
+        // the validator set is only known at the chain head
+        let in_set = manager.validators().await?.contains(validator);
+
+        let mut votes = vec![];
+        let mut records = vec![];
+        for epoch in epochs {
+            let status = match cached.get(&epoch) {
+                Some(status) => *status,
+                None if !in_set => VoteStatus::NotRequired,
+                None => {
+                    let status = if manager.should_submit_in_epoch(validator, epoch).await? {
+                        VoteStatus::Missed
+                    } else {
+                        VoteStatus::Voted
+                    };
+                    records.push(VoteRecord {
+                        validator: validator.to_string(),
+                        direction: String::from(direction),
+                        epoch,
+                        status,
+                    });
+                    status
+                }
+            };
+            votes.push(EpochVote { epoch, status });
+        }
+        self.store.append_votes(subnet, &records).await?;
+
+        Ok(DirectionVoteHistory {
+            direction: String::from(direction),
+            checkpoint_period: period,
+            voted: votes
+                .iter()
+                .filter(|v| v.status == VoteStatus::Voted)
+                .count(),
+            missed: votes
+                .iter()
+                .filter(|v| v.status == VoteStatus::Missed)
+                .count(),
+            votes,
+        })
+    }
+}
+
+/// Returns the epochs of the checkpoints of period `period` between `from` and `to`, both
+/// included.
+fn checkpoint_epochs(from: ChainEpoch, to: ChainEpoch, period: ChainEpoch) -> Vec<ChainEpoch> {
+    if period <= 0 {
+        return vec![];
+    }
+    // there is no checkpoint at genesis
+    let first = ((from.max(1) + period - 1) / period) * period;
+    (first..=to).step_by(period as usize).collect()
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for QueryValidatorVoteHistoryHandler {
+    type Request = QueryValidatorVoteHistoryParams;
+    type Response = QueryValidatorVoteHistoryResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        if request.from_epoch > request.to_epoch {
+            return Err(anyhow!("from_epoch is after to_epoch"));
+        }
+        let subnet_id = SubnetID::from_str(&request.subnet)?;
+        // evm validators are recorded with their f410 address
+        let validator = if request.validator.starts_with("0x") {
+            ethers_address_to_fil_address(&ethers::types::Address::from_str(&request.validator)?)?
+        } else {
+            Address::from_str(&request.validator)?
+        };
+
+        let subnets = self.pool.subnets();
+        let subnet = subnets
+            .get(&subnet_id)
+            .ok_or_else(|| anyhow!("subnet {subnet_id:} not configured"))?;
+        let managers = setup_manager_from_subnet(
+            &subnets,
+            subnet,
+            self.fvm_wallet.clone(),
+            self.evm_keystore.clone(),
+            self.pool.state(),
+        )
+        .await?;
+        if managers.is_empty() {
+            return Err(anyhow!(
+                "the agent does not manage the checkpoints of subnet {subnet_id:}"
+            ));
+        }
+
+        let mut directions = vec![];
+        for m in managers.iter() {
+            directions.push(
+                self.direction_history(
+                    &subnet_id,
+                    m.as_ref(),
+                    &validator,
+                    request.from_epoch,
+                    request.to_epoch,
+                )
+                .await?,
+            );
+        }
+
+        Ok(QueryValidatorVoteHistoryResponse {
+            subnet: subnet_id.to_string(),
+            validator: validator.to_string(),
+            directions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::handlers::manager::vote_history::checkpoint_epochs;
+
+    #[test]
+    fn test_checkpoint_epochs() {
+        assert_eq!(checkpoint_epochs(0, 30, 10), vec![10, 20, 30]);
+        assert_eq!(checkpoint_epochs(11, 39, 10), vec![20, 30]);
+        assert_eq!(checkpoint_epochs(20, 20, 10), vec![20]);
+        assert!(checkpoint_epochs(21, 29, 10).is_empty());
+        assert!(checkpoint_epochs(30, 20, 10).is_empty());
+    }
+}
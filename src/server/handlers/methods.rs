@@ -0,0 +1,124 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Listing of the json rpc methods served by the agent
+
+use serde::de::value::Error;
+use serde::de::{DeserializeOwned, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListMethodsParams {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsSchema {
+    /// The name of the type of the params, without its module path.
+    pub type_name: String,
+    /// The fields of the params, if they are a struct.
+    pub fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodInfo {
+    pub method: String,
+    pub namespace: String,
+    pub params: ParamsSchema,
+    /// Whether the method is served, methods can be disabled in the server config.
+    pub enabled: bool,
+}
+
+impl MethodInfo {
+    pub(crate) fn new(method: &str, params: ParamsSchema, enabled: bool) -> Self {
+        MethodInfo {
+            method: method.to_string(),
+            namespace: method
+                .split_once('_')
+                .map(|(namespace, _)| namespace.to_string())
+                .unwrap_or_default(),
+            params,
+            enabled,
+        }
+    }
+}
+
+/// Returns the schema of the params `T`, as derived from its `Deserialize` implementation.
+pub(crate) fn params_schema<T: DeserializeOwned>() -> ParamsSchema {
+    let mut recorder = FieldsRecorder { fields: None };
+    // the recorder always fails once it has seen the type, the error carries no information
+    let _ = T::deserialize(&mut recorder);
+
+    ParamsSchema {
+        type_name: short_type_name(std::any::type_name::<T>()),
+        fields: recorder.fields,
+    }
+}
+
+/// Strips the module path of every type in `name`, e.g. `alloc::vec::Vec<alloc::string::String>`
+/// becomes `Vec<String>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap_or_default());
+            path.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+/// A deserializer that records the fields of the struct being deserialized, if it is one, and
+/// fails without deserializing anything.
+struct FieldsRecorder {
+    fields: Option<Vec<String>>,
+}
+
+impl<'de, 'a> Deserializer<'de> for &'a mut FieldsRecorder {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.fields = Some(fields.iter().map(|f| f.to_string()).collect());
+        Err(serde::de::Error::custom("fields recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::fund::FundParams;
+    use crate::server::handlers::methods::{params_schema, MethodInfo};
+
+    #[test]
+    fn test_params_schema() {
+        let schema = params_schema::<FundParams>();
+        assert_eq!(schema.type_name, "FundParams");
+        assert_eq!(
+            schema.fields.unwrap(),
+            vec!["subnet", "from", "to", "amount", "source"]
+        );
+
+        let schema = params_schema::<Vec<String>>();
+        assert_eq!(schema.type_name, "Vec<String>");
+        assert!(schema.fields.is_none());
+
+        let info = MethodInfo::new("ipc_fund", params_schema::<FundParams>(), true);
+        assert_eq!(info.namespace, "ipc");
+    }
+}
@@ -10,58 +10,133 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::Value;
 
-pub use config::ReloadConfigParams;
-use fvm_shared::econ::TokenAmount;
+pub use config::{ReloadConfigParams, ValidateConfigParams};
 use ipc_identity::PersistentKeyStore;
-use manager::create::CreateSubnetHandler;
+use manager::create::{CreateSubnetHandler, PreviewCreateSubnetHandler};
 use manager::join::JoinSubnetHandler;
+use manager::join_collateral::EstimateJoinCollateralHandler;
 use manager::kill::KillSubnetHandler;
 use manager::leave::LeaveSubnetHandler;
+use manager::onboarding::OnboardingDryRunHandler;
 use manager::subnet::SubnetManagerPool;
 pub use manager::*;
 
 use crate::config::json_rpc_methods;
-use crate::config::ReloadableConfig;
-use crate::server::handlers::config::ReloadConfigHandler;
+use crate::config::{ReloadableConfig, RunMode};
+use crate::history::CheckpointStore;
+use crate::persistence::journal::CheckpointJournal;
+use crate::persistence::DataDir;
+use crate::server::auth::{authorize, AuthRejection};
+use crate::server::handlers::approval::{
+    approval_reason, AbortPendingOperationHandler, ApproveOperationParams,
+    ApproveOperationResponse, ListOperationApprovalsHandler,
+};
+use crate::server::handlers::config::{ReloadConfigHandler, ValidateConfigHandler};
+use crate::server::handlers::debug_tap::DebugTapHandler;
+use crate::server::handlers::health::HealthHandler;
+use crate::server::handlers::history::{
+    BackfillHistoryHandler, CheckpointHistoryHandler, CrossMsgsByAccountHandler,
+};
+use crate::server::handlers::jobs::{ControlJobHandler, ListJobsHandler};
+use crate::server::handlers::journal::ListCheckpointSubmissionsHandler;
+use crate::server::handlers::maintenance::MaintenanceHandler;
+use crate::server::handlers::manager::checkpoint_diff::BottomUpCheckpointDiffHandler;
+use crate::server::handlers::manager::circulating_supply::CirculatingSupplyHandler;
+use crate::server::handlers::manager::compare_validators::CompareValidatorSetsHandler;
+use crate::server::handlers::manager::cosign::CoSignVoteHandler;
+use crate::server::handlers::manager::decommission::DecommissionSubnetHandler;
+use crate::server::handlers::manager::diagnose_cross::DiagnoseCrossMsgHandler;
+use crate::server::handlers::manager::federated_power::SetFederatedPowerHandler;
+use crate::server::handlers::manager::federation::NotifyTopDownCheckpointHandler;
 use crate::server::handlers::manager::fund::FundHandler;
+use crate::server::handlers::manager::import::{
+    DiscoverSubnetsHandler, ImportSubnetsHandler, ListOwnSubnetsHandler,
+};
 use crate::server::handlers::manager::list_subnets::ListSubnetsHandler;
+use crate::server::handlers::manager::lookup_message::{GetMessageHandler, GetReceiptHandler};
+use crate::server::handlers::manager::offline_tx::{BuildOfflineTxHandler, SendSignedTxHandler};
 use crate::server::handlers::manager::propagate::PropagateHandler;
 use crate::server::handlers::manager::query_validators::QueryValidatorSetHandler;
 use crate::server::handlers::manager::release::ReleaseHandler;
 use crate::server::handlers::manager::send_cross::SendCrossMsgHandler;
+use crate::server::handlers::manager::topology::TopologyHandler;
+use crate::server::handlers::methods::{
+    params_schema, ListMethodsParams, MethodInfo, ParamsSchema,
+};
+use crate::server::handlers::notifications::{
+    ListNotificationsHandler, RedeliverNotificationsHandler,
+};
+use crate::server::handlers::quorum::CheckpointQuorumHandler;
 use crate::server::handlers::send_value::SendValueHandler;
+use crate::server::handlers::status::{AgentIdentity, SignedStatusHandler};
+use crate::server::handlers::submission_time::EstimateCheckpointSubmissionTimeHandler;
+use crate::server::handlers::validator_subnets::ListValidatorsAcrossSubnetsHandler;
+use crate::server::handlers::vote_approval::{ApproveVoteHandler, ListVoteApprovalsHandler};
+use crate::server::handlers::vote_history::QueryValidatorVoteHistoryHandler;
 use crate::server::handlers::wallet::balances::WalletBalancesHandler;
 use crate::server::handlers::wallet::new::WalletNewHandler;
 use crate::server::list_checkpoints::ListBottomUpCheckpointsHandler;
+use crate::server::middleware::Middleware;
 use crate::server::net_addr::SetValidatorNetAddrHandler;
+use crate::server::nonces::NoncesHandler;
+use crate::server::pending_bottomup::PendingBottomUpMsgsHandler;
+use crate::server::pending_messages::ListPendingMessagesHandler;
+use crate::server::pending_releases::PendingReleasesHandler;
+use crate::server::read_state::ReadStateHandler;
+use crate::server::replace_message::ReplaceMessageHandler;
+use crate::server::resend_topdown::ResendTopDownMsgsHandler;
 use crate::server::worker_addr::SetValidatorWorkerAddrHandler;
 use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
 use ipc_identity::Wallet;
 
 pub use self::config::{new_evm_keystore_from_config, new_evm_keystore_from_path};
 pub use self::config::{new_fvm_wallet_from_config, new_keystore_from_path};
 use self::rpc::RPCSubnetHandler;
 use self::topdown_executed::LastTopDownExecHandler;
+use self::wallet::activity::WalletActivityHandler;
 use self::wallet::export::WalletExportHandler;
-use self::wallet::import::WalletImportHandler;
+use self::wallet::faucet::FaucetRequestHandler;
+use self::wallet::import::{WalletImportHandler, WalletImportSecp256k1Handler};
+use self::wallet::key_usage::KeyUsageHandler;
 use self::wallet::remove::WalletRemoveHandler;
 
+pub mod approval;
 mod config;
+pub mod debug_tap;
+pub mod health;
+pub mod history;
+pub mod jobs;
+pub mod journal;
+pub mod maintenance;
 mod manager;
+pub mod methods;
+pub mod notifications;
+pub mod quorum;
+pub mod status;
+pub mod vote_approval;
 pub mod wallet;
 
 pub type Method = String;
-/// We only support up to 9 decimal digits for transaction
-const FIL_AMOUNT_NANO_DIGITS: u32 = 9;
 /// The collection of all json rpc handlers
 pub struct Handlers {
     handlers: HashMap<Method, Box<dyn HandlerWrapper>>,
+    /// The config the approval thresholds are read from, no operation needs approval if not set.
+    config: Option<Arc<ReloadableConfig>>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    /// The workload of the daemon, the methods that are not part of it are not served.
+    mode: RunMode,
+    /// The state of the agent, shared with the subsystems of the daemon.
+    state: Arc<AgentState>,
 }
 
 /// A util trait to avoid Box<dyn> and associated type mess in Handlers struct
 #[async_trait]
-trait HandlerWrapper: Send + Sync {
+pub(crate) trait HandlerWrapper: Send + Sync {
     async fn handle(&self, params: Value) -> Result<Value>;
+
+    /// The schema of the params of the handler, listed by `ipc_listMethods`.
+    fn params_schema(&self) -> ParamsSchema;
 }
 
 #[async_trait]
@@ -71,6 +146,10 @@ impl<H: JsonRPCRequestHandler + Send + Sync> HandlerWrapper for H {
         let r = self.handle(p).await?;
         Ok(serde_json::to_value(r)?)
     }
+
+    fn params_schema(&self) -> ParamsSchema {
+        params_schema::<H::Request>()
+    }
 }
 
 impl Handlers {
@@ -80,6 +159,10 @@ impl Handlers {
     pub fn empty_handlers() -> Self {
         Self {
             handlers: HashMap::new(),
+            config: None,
+            middlewares: vec![],
+            mode: RunMode::default(),
+            state: Arc::default(),
         }
     }
 
@@ -87,6 +170,7 @@ impl Handlers {
         config: Arc<ReloadableConfig>,
         fvm_wallet: Arc<RwLock<Wallet>>,
         evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
     ) -> Result<Self> {
         let mut handlers = HashMap::new();
 
@@ -95,13 +179,21 @@ impl Handlers {
 
         // subnet manager methods
         let pool = Arc::new(SubnetManagerPool::new(
-            config,
+            config.clone(),
             fvm_wallet.clone(),
             evm_keystore.clone(),
+            state.clone(),
         ));
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(ValidateConfigHandler::new(pool.clone(), config.clone()));
+        handlers.insert(String::from(json_rpc_methods::VALIDATE_CONFIG), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(CreateSubnetHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::CREATE_SUBNET), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(PreviewCreateSubnetHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::PREVIEW_CREATE_SUBNET), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(LeaveSubnetHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::LEAVE_SUBNET), h);
 
@@ -111,6 +203,12 @@ impl Handlers {
         let h: Box<dyn HandlerWrapper> = Box::new(JoinSubnetHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::JOIN_SUBNET), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(EstimateJoinCollateralHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::ESTIMATE_JOIN_COLLATERAL), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(OnboardingDryRunHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::ONBOARDING_DRY_RUN), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(RPCSubnetHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::RPC_SUBNET), h);
 
@@ -126,9 +224,22 @@ impl Handlers {
         let h: Box<dyn HandlerWrapper> = Box::new(SendCrossMsgHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::SEND_CROSS_MSG), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(DiagnoseCrossMsgHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::DIAGNOSE_CROSS_MSG), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(SendValueHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::SEND_VALUE), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(BuildOfflineTxHandler::new(
+            pool.clone(),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+        ));
+        handlers.insert(String::from(json_rpc_methods::BUILD_OFFLINE_TX), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(SendSignedTxHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::SEND_SIGNED_TX), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(WalletNewHandler::new(
             fvm_wallet.clone(),
             evm_keystore.clone(),
@@ -147,6 +258,12 @@ impl Handlers {
         ));
         handlers.insert(String::from(json_rpc_methods::WALLET_IMPORT), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(WalletImportSecp256k1Handler::new(
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+        ));
+        handlers.insert(String::from(json_rpc_methods::WALLET_IMPORT_SECP256K1), h);
+
         let _h: Box<dyn HandlerWrapper> = Box::new(WalletExportHandler::new(
             fvm_wallet.clone(),
             evm_keystore.clone(),
@@ -156,6 +273,43 @@ impl Handlers {
         // We can consider re-enabling once we have RPC authentication in the agent.
         // handlers.insert(String::from(json_rpc_methods::WALLET_EXPORT), h);
 
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(ListValidatorsAcrossSubnetsHandler::new(pool.clone()));
+        handlers.insert(
+            String::from(json_rpc_methods::LIST_VALIDATORS_ACROSS_SUBNETS),
+            h,
+        );
+
+        let h: Box<dyn HandlerWrapper> = Box::new(CoSignVoteHandler::new(
+            pool.clone(),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+        ));
+        handlers.insert(String::from(json_rpc_methods::CO_SIGN_VOTE), h);
+
+        let data_dir = DataDir::from_config(&config)?;
+        let store = CheckpointStore::new(&data_dir);
+        let h: Box<dyn HandlerWrapper> = Box::new(QueryValidatorVoteHistoryHandler::new(
+            pool.clone(),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+            store.clone(),
+        ));
+        handlers.insert(
+            String::from(json_rpc_methods::QUERY_VALIDATOR_VOTE_HISTORY),
+            h,
+        );
+
+        let h: Box<dyn HandlerWrapper> = Box::new(EstimateCheckpointSubmissionTimeHandler::new(
+            pool.clone(),
+            fvm_wallet.clone(),
+            evm_keystore.clone(),
+        ));
+        handlers.insert(
+            String::from(json_rpc_methods::ESTIMATE_CHECKPOINT_SUBMISSION_TIME),
+            h,
+        );
+
         let h: Box<dyn HandlerWrapper> = Box::new(WalletBalancesHandler::new(
             pool.clone(),
             fvm_wallet,
@@ -163,18 +317,124 @@ impl Handlers {
         ));
         handlers.insert(String::from(json_rpc_methods::WALLET_BALANCES), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(KeyUsageHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::KEY_USAGE), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(HealthHandler::new(pool.clone(), config.clone()));
+        handlers.insert(String::from(json_rpc_methods::HEALTH), h);
+
+        match AgentIdentity::load_or_generate(&data_dir) {
+            Ok(identity) => {
+                tracing::info!("identity of the agent: {:?}", identity.address());
+                let h: Box<dyn HandlerWrapper> = Box::new(SignedStatusHandler::new(
+                    HealthHandler::new(pool.clone(), config.clone()),
+                    Arc::new(identity),
+                ));
+                handlers.insert(String::from(json_rpc_methods::SIGNED_STATUS), h);
+            }
+            Err(e) => tracing::warn!(
+                "cannot load the identity of the agent, {} is not served: {e:}",
+                json_rpc_methods::SIGNED_STATUS
+            ),
+        }
+
+        let h: Box<dyn HandlerWrapper> = Box::new(DecommissionSubnetHandler::new(
+            pool.clone(),
+            config.clone(),
+            store.clone(),
+        ));
+        handlers.insert(String::from(json_rpc_methods::DECOMMISSION_SUBNET), h);
+
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(BackfillHistoryHandler::new(pool.clone(), store.clone()));
+        handlers.insert(String::from(json_rpc_methods::BACKFILL_HISTORY), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(CheckpointHistoryHandler::new(store.clone()));
+        handlers.insert(String::from(json_rpc_methods::CHECKPOINT_HISTORY), h);
+
+        let journal = CheckpointJournal::new(&data_dir);
+        let h: Box<dyn HandlerWrapper> = Box::new(ListCheckpointSubmissionsHandler::new(journal));
+        handlers.insert(
+            String::from(json_rpc_methods::LIST_CHECKPOINT_SUBMISSIONS),
+            h,
+        );
+
+        let h: Box<dyn HandlerWrapper> = Box::new(CrossMsgsByAccountHandler::new(
+            config.clone(),
+            store.clone(),
+        ));
+        handlers.insert(String::from(json_rpc_methods::GET_CROSS_MSGS_BY_ACCOUNT), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(WalletActivityHandler::new(
+            pool.clone(),
+            config.clone(),
+            store,
+        ));
+        handlers.insert(String::from(json_rpc_methods::WALLET_ACTIVITY), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(FaucetRequestHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::FAUCET_REQUEST), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ListJobsHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::LIST_JOBS), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ControlJobHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::CONTROL_JOB), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(MaintenanceHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::MAINTENANCE), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ListNotificationsHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::LIST_NOTIFICATIONS), h);
+
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(RedeliverNotificationsHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::REDELIVER_NOTIFICATIONS), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(DebugTapHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::DEBUG_TAP), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ListVoteApprovalsHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::LIST_VOTE_APPROVALS), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ApproveVoteHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::APPROVE_VOTE), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(CheckpointQuorumHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::CHECKPOINT_QUORUM), h);
+
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(ListOperationApprovalsHandler::new(state.clone()));
+        handlers.insert(String::from(json_rpc_methods::LIST_OPERATION_APPROVALS), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(AbortPendingOperationHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::ABORT_PENDING_OPERATION), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(SetValidatorNetAddrHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::SET_VALIDATOR_NET_ADDR), h);
 
         let h: Box<dyn HandlerWrapper> = Box::new(SetValidatorWorkerAddrHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::SET_VALIDATOR_WORKER_ADDR), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(SetFederatedPowerHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::SET_FEDERATED_POWER), h);
+
         let h: Box<dyn HandlerWrapper> = Box::new(ListSubnetsHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::LIST_CHILD_SUBNETS), h);
 
         let h: Box<dyn HandlerWrapper> = Box::new(ListSubnetsHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::LIST_CHILD_SUBNETS), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(DiscoverSubnetsHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::DISCOVER_SUBNETS), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ListOwnSubnetsHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::LIST_OWN_SUBNETS), h);
+
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(ImportSubnetsHandler::new(pool.clone(), config.clone()));
+        handlers.insert(String::from(json_rpc_methods::IMPORT_SUBNETS), h);
+
         let h: Box<dyn HandlerWrapper> =
             Box::new(ListBottomUpCheckpointsHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::LIST_BOTTOMUP_CHECKPOINTS), h);
@@ -182,36 +442,325 @@ impl Handlers {
         let h: Box<dyn HandlerWrapper> = Box::new(LastTopDownExecHandler::new(pool.clone()));
         handlers.insert(String::from(json_rpc_methods::LAST_TOPDOWN_EXECUTED), h);
 
+        let h: Box<dyn HandlerWrapper> = Box::new(TopologyHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::TOPOLOGY), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(PendingBottomUpMsgsHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::PENDING_BOTTOMUP_MSGS), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(PendingReleasesHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::PENDING_RELEASES), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(NoncesHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::NONCES), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ListPendingMessagesHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::LIST_PENDING_MESSAGES), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(GetMessageHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::GET_MESSAGE), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(GetReceiptHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::GET_RECEIPT), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ReplaceMessageHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::REPLACE_MESSAGE), h);
+
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(ReadStateHandler::new(config.clone(), state.clone()));
+        handlers.insert(String::from(json_rpc_methods::READ_STATE), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(ResendTopDownMsgsHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::RESEND_TOPDOWN_MSGS), h);
+
+        let h: Box<dyn HandlerWrapper> =
+            Box::new(NotifyTopDownCheckpointHandler::new(pool.clone()));
+        handlers.insert(
+            String::from(json_rpc_methods::NOTIFY_TOP_DOWN_CHECKPOINT),
+            h,
+        );
+
+        let h: Box<dyn HandlerWrapper> = Box::new(BottomUpCheckpointDiffHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::BOTTOMUP_CHECKPOINT_DIFF), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(CirculatingSupplyHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::GET_CIRCULATING_SUPPLY), h);
+
+        let h: Box<dyn HandlerWrapper> = Box::new(CompareValidatorSetsHandler::new(pool.clone()));
+        handlers.insert(String::from(json_rpc_methods::COMPARE_VALIDATOR_SETS), h);
+
         // query validator
         let h: Box<dyn HandlerWrapper> = Box::new(QueryValidatorSetHandler::new(pool));
         handlers.insert(String::from(json_rpc_methods::QUERY_VALIDATOR_SET), h);
 
-        Ok(Self { handlers })
+        Ok(Self {
+            handlers,
+            config: Some(config),
+            middlewares: vec![],
+            mode: RunMode::default(),
+            state,
+        })
+    }
+
+    /// Registers `handler` for `method`, to be served along the built-in methods. Methods are named
+    /// `<namespace>_<name>`, handlers that are not part of the agent should use their own
+    /// namespace instead of `ipc`. Methods that are already registered cannot be replaced.
+    ///
+    /// ```ignore
+    /// handlers.register("myorg_getTreasury", GetTreasuryHandler::new(client))?;
+    /// ```
+    pub fn register<H>(&mut self, method: impl Into<Method>, handler: H) -> Result<()>
+    where
+        H: JsonRPCRequestHandler + Send + Sync + 'static,
+    {
+        self.register_boxed(method.into(), Box::new(handler))
+    }
+
+    pub(crate) fn register_boxed(
+        &mut self,
+        method: Method,
+        handler: Box<dyn HandlerWrapper>,
+    ) -> Result<()> {
+        check_method_name(&method)?;
+        if self.handlers.contains_key(&method)
+            || method == json_rpc_methods::APPROVE_OPERATION
+            || method == json_rpc_methods::LIST_METHODS
+        {
+            return Err(anyhow!("method {method:} already registered"));
+        }
+        self.handlers.insert(method, handler);
+        Ok(())
+    }
+
+    /// Adds a middleware run around every request, after the ones already added, see
+    /// [`Middleware`].
+    pub fn add_middleware(&mut self, middleware: impl Middleware + 'static) {
+        self.add_boxed_middleware(Box::new(middleware));
+    }
+
+    pub(crate) fn add_boxed_middleware(&mut self, middleware: Box<dyn Middleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    /// Only serves the methods that are part of the workload of `mode`, see [`RunMode`].
+    pub fn set_mode(&mut self, mode: RunMode) {
+        self.mode = mode;
+    }
+
+    /// The state of the agent the handlers serve.
+    pub(crate) fn state(&self) -> &Arc<AgentState> {
+        &self.state
+    }
+
+    /// Checks that the `authorization` header of a request to `method` grants access to it, when
+    /// the config sets up authentication.
+    pub(crate) fn authorize(
+        &self,
+        method: &str,
+        authorization: Option<&str>,
+    ) -> std::result::Result<(), AuthRejection> {
+        let auth = self
+            .config
+            .as_ref()
+            .and_then(|c| c.get_config().server.auth.clone());
+        match auth {
+            Some(auth) => authorize(&auth, authorization, method),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn handle(&self, method: Method, mut params: Value) -> Result<Value> {
+        let _guard = self
+            .state
+            .shutdown
+            .start_request()
+            .ok_or_else(|| anyhow!("the agent is shutting down"))?;
+        let mut result: Result<Value> = try {
+            for m in self.middlewares.iter() {
+                m.before(&method, &mut params).await?;
+            }
+            self.handle_request(method.clone(), params).await?
+        };
+        for m in self.middlewares.iter().rev() {
+            m.after(&method, &mut result).await;
+        }
+        self.state.metrics.observe_json_rpc_request(
+            self.serves(&method).then_some(method.as_str()),
+            result.is_ok(),
+        );
+        result
+    }
+
+    /// Whether `method` is a method of the agent, served or not in its mode.
+    fn serves(&self, method: &str) -> bool {
+        self.handlers.contains_key(method)
+            || method == json_rpc_methods::APPROVE_OPERATION
+            || method == json_rpc_methods::LIST_METHODS
     }
 
-    pub async fn handle(&self, method: Method, params: Value) -> Result<Value> {
-        if let Some(wrapper) = self.handlers.get(&method) {
+    async fn handle_request(&self, method: Method, params: Value) -> Result<Value> {
+        if self.disabled_methods().contains(&method) {
+            return Err(anyhow!("method {method:} disabled in the config"));
+        }
+        if !self.mode.serves(&method) {
+            return Err(anyhow!("method {method:} not served in {} mode", self.mode));
+        }
+        if method == json_rpc_methods::APPROVE_OPERATION {
+            return self.approve_operation(params).await;
+        }
+        if method == json_rpc_methods::LIST_METHODS {
+            let _: ListMethodsParams = serde_json::from_value(params)?;
+            return Ok(serde_json::to_value(self.list_methods())?);
+        }
+
+        let approvals = self
+            .config
+            .as_ref()
+            .and_then(|c| c.get_config().server.approvals.clone());
+        if let Some(approvals) = approvals
+            && let Some(reason) = approval_reason(&approvals, &method, &params)?
+        {
+            let op = self
+                .state
+                .operation_approvals
+                .park(&method, params, reason, approvals.expiry());
+            return Err(anyhow!(
+                "{} needs approval, parked as operation {}: approve it with {} before it expires in {}s",
+                op.reason,
+                op.id,
+                json_rpc_methods::APPROVE_OPERATION,
+                approvals.expiry_secs
+            ));
+        }
+
+        self.dispatch(&method, params).await
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value> {
+        if let Some(wrapper) = self.handlers.get(method) {
             wrapper.handle(params).await
         } else {
             Err(anyhow!("method not supported"))
         }
     }
+
+    fn disabled_methods(&self) -> Vec<String> {
+        self.config
+            .as_ref()
+            .map(|c| c.get_config().server.disabled_methods.clone())
+            .unwrap_or_default()
+    }
+
+    /// Lists all the methods registered, built-in or custom, with the schema of their params.
+    pub fn list_methods(&self) -> Vec<MethodInfo> {
+        let disabled = self.disabled_methods();
+        let mut methods = self
+            .handlers
+            .iter()
+            .map(|(method, h)| (method.as_str(), h.params_schema()))
+            .chain([
+                (
+                    json_rpc_methods::APPROVE_OPERATION,
+                    params_schema::<ApproveOperationParams>(),
+                ),
+                (
+                    json_rpc_methods::LIST_METHODS,
+                    params_schema::<ListMethodsParams>(),
+                ),
+            ])
+            .map(|(method, params)| {
+                let enabled = !disabled.iter().any(|m| m == method) && self.mode.serves(method);
+                MethodInfo::new(method, params, enabled)
+            })
+            .collect::<Vec<_>>();
+        methods.sort_by(|a, b| a.method.cmp(&b.method));
+        methods
+    }
+
+    /// Approves or rejects a parked operation, executing it with its original params if approved.
+    /// It is handled here instead of in its own handler as it dispatches to the other handlers.
+    async fn approve_operation(&self, params: Value) -> Result<Value> {
+        let params: ApproveOperationParams = serde_json::from_value(params)?;
+        let operation = self
+            .state
+            .operation_approvals
+            .decide(params.id, params.approve)?;
+
+        let result = if params.approve {
+            tracing::info!(
+                "executing approved operation {} ({})",
+                operation.id,
+                operation.method
+            );
+            Some(
+                self.dispatch(&operation.method, operation.params.clone())
+                    .await?,
+            )
+        } else {
+            tracing::info!("operation {} ({}) rejected", operation.id, operation.method);
+            None
+        };
+
+        Ok(serde_json::to_value(ApproveOperationResponse {
+            operation,
+            result,
+        })?)
+    }
 }
 
-pub(crate) fn f64_to_token_amount(f: f64) -> anyhow::Result<TokenAmount> {
-    // no rounding, just the integer part
-    let nano = f64::trunc(f * (10u64.pow(FIL_AMOUNT_NANO_DIGITS) as f64));
-    Ok(TokenAmount::from_nano(nano as u128))
+/// Checks that `method` is named `<namespace>_<name>`, with alphanumeric namespace and name.
+fn check_method_name(method: &str) -> Result<()> {
+    let valid = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric());
+    match method.split_once('_') {
+        Some((namespace, name)) if valid(namespace) && valid(name) => Ok(()),
+        _ => Err(anyhow!(
+            "invalid method name {method:}, expected <namespace>_<name>"
+        )),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::server::handlers::f64_to_token_amount;
-    use fvm_shared::econ::TokenAmount;
+    use crate::server::handlers::Handlers;
+    use crate::server::JsonRPCRequestHandler;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl JsonRPCRequestHandler for EchoHandler {
+        type Request = String;
+        type Response = String;
+
+        async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+            Ok(request)
+        }
+    }
 
-    #[test]
-    fn test_amount() {
-        let amount = f64_to_token_amount(1000000.1f64).unwrap();
-        assert_eq!(amount, TokenAmount::from_nano(1000000100000000u128));
+    #[tokio::test]
+    async fn test_register_handler() {
+        let mut handlers = Handlers::empty_handlers();
+        handlers.register("myorg_echo", EchoHandler).unwrap();
+
+        let response = handlers
+            .handle(String::from("myorg_echo"), json!("hello"))
+            .await
+            .unwrap();
+        assert_eq!(response, json!("hello"));
+
+        assert!(handlers.register("myorg_echo", EchoHandler).is_err());
+        assert!(handlers
+            .register("ipc_approveOperation", EchoHandler)
+            .is_err());
+        assert!(handlers.register("echo", EchoHandler).is_err());
+        assert!(handlers.register("my org_echo", EchoHandler).is_err());
+
+        let methods = handlers.list_methods();
+        let echo = methods.iter().find(|m| m.method == "myorg_echo").unwrap();
+        assert_eq!(echo.namespace, "myorg");
+        assert_eq!(echo.params.type_name, "String");
+        assert!(echo.enabled);
+        assert!(methods.iter().any(|m| m.method == "ipc_listMethods"));
     }
 }
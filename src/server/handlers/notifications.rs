@@ -0,0 +1,73 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List and redelivery of the notifications of chain events
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::notify::Delivery;
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListNotificationsParams {}
+
+/// The list notifications json rpc method handler.
+pub(crate) struct ListNotificationsHandler {
+    state: Arc<AgentState>,
+}
+
+impl ListNotificationsHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListNotificationsHandler {
+    type Request = ListNotificationsParams;
+    type Response = Vec<Delivery>;
+
+    async fn handle(&self, _request: Self::Request) -> anyhow::Result<Self::Response> {
+        Ok(self.state.notifier.deliveries())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedeliverNotificationsParams {
+    pub subnet: String,
+    pub from_epoch: ChainEpoch,
+    /// Defaults to `from_epoch`.
+    pub to_epoch: Option<ChainEpoch>,
+}
+
+/// The redeliver notifications json rpc method handler, to post the notifications of chain events
+/// already delivered again, e.g. after the webhook lost them.
+pub(crate) struct RedeliverNotificationsHandler {
+    state: Arc<AgentState>,
+}
+
+impl RedeliverNotificationsHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for RedeliverNotificationsHandler {
+    type Request = RedeliverNotificationsParams;
+    type Response = Vec<Delivery>;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let to = request.to_epoch.unwrap_or(request.from_epoch);
+        self.state
+            .notifier
+            .redeliver(&subnet, request.from_epoch, to)
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The votes of all the validators on the pending checkpoints, counted by the quorum monitor
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::quorum::{PendingQuorum, QuorumReached};
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CheckpointQuorumParams {
+    /// Only return the directions of this subnet.
+    pub subnet: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointQuorumEntry {
+    pub subnet: String,
+    /// `bottom-up` or `top-down`
+    pub direction: String,
+    pub last_executed_epoch: ChainEpoch,
+    /// The votes of the checkpoints not executed yet, the oldest first.
+    pub pending: Vec<PendingQuorum>,
+    /// The epochs the oldest checkpoint short of its quorum has been waiting.
+    pub stalled_epochs: ChainEpoch,
+    /// The average epochs past the last checkpoints when they were seen executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub average_epochs_to_quorum: Option<f64>,
+    /// The average seconds the last checkpoints took from pending to executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub average_secs_to_quorum: Option<f64>,
+    /// The last checkpoints that reached their quorum, the latest last.
+    pub reached: Vec<QuorumReached>,
+}
+
+/// Returns the votes counted on the pending checkpoints of the subnets and their time to quorum.
+pub(crate) struct CheckpointQuorumHandler {
+    state: Arc<AgentState>,
+}
+
+impl CheckpointQuorumHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for CheckpointQuorumHandler {
+    type Request = CheckpointQuorumParams;
+    type Response = Vec<CheckpointQuorumEntry>;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = request
+            .subnet
+            .as_deref()
+            .map(SubnetID::from_str)
+            .transpose()?
+            .map(|s| s.to_string());
+
+        Ok(self
+            .state
+            .quorum_monitor
+            .all()
+            .into_iter()
+            .filter(|d| subnet.as_ref().map_or(true, |s| *s == d.subnet))
+            .map(|d| {
+                let average = d.average_time_to_quorum();
+                CheckpointQuorumEntry {
+                    stalled_epochs: d.stalled_epochs(),
+                    average_epochs_to_quorum: average.map(|(epochs, _)| epochs),
+                    average_secs_to_quorum: average.map(|(_, secs)| secs),
+                    subnet: d.subnet,
+                    direction: d.direction,
+                    last_executed_epoch: d.last_executed_epoch,
+                    pending: d.pending,
+                    reached: d.reached.into_iter().collect(),
+                }
+            })
+            .collect())
+    }
+}
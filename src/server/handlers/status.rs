@@ -0,0 +1,179 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Status of the agent signed with its identity key.
+//!
+//! Every agent has an identity key, generated in its data dir the first time the daemon starts, that
+//! signs the responses of `ipc_signedStatus`. Monitoring pins the address of the key of the agent
+//! and checks the signature of every status it gets, so that a status served by another agent, e.g.
+//! behind a load balancer shared by several agents, is not taken for the status of the agent.
+
+use std::fs::File;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::core::rand::thread_rng;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Signature};
+use ethers::utils::hash_message;
+use serde::{Deserialize, Serialize};
+
+use crate::manager::clock::now;
+use crate::persistence::DataDir;
+use crate::server::handlers::health::{HealthHandler, HealthParams, HealthResponse};
+use crate::server::JsonRPCRequestHandler;
+
+/// The key the agent signs its status with.
+pub struct AgentIdentity {
+    wallet: LocalWallet,
+}
+
+impl AgentIdentity {
+    /// Loads the identity key from `data_dir`, generating it if the agent does not have one yet.
+    pub fn load_or_generate(data_dir: &DataDir) -> Result<Self> {
+        let path = data_dir.identity_key();
+        if path.exists() {
+            let key = hex::decode(std::fs::read_to_string(&path)?.trim())?;
+            let wallet = LocalWallet::from_bytes(&key)
+                .map_err(|e| anyhow!("invalid identity key in {path:?}: {e:}"))?;
+            return Ok(Self { wallet });
+        }
+
+        let wallet = LocalWallet::new(&mut thread_rng());
+        std::fs::create_dir_all(data_dir.root())?;
+        let mut file = File::create(&path)?;
+        #[cfg(unix)]
+        ipc_identity::set_user_perm(&file)?;
+        file.write_all(hex::encode(wallet.signer().to_bytes()).as_bytes())?;
+        tracing::info!("generated the identity key of the agent in {path:?}");
+
+        Ok(Self { wallet })
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Signs `payload` as an Ethereum personal message.
+    pub(crate) fn sign(&self, payload: &str) -> Result<String> {
+        Ok(self.wallet.sign_hash(hash_message(payload))?.to_string())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SignedStatusParams {
+    /// A value chosen by the caller, signed along the status so that the response cannot be
+    /// replayed to another caller.
+    pub challenge: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedStatusResponse {
+    /// The address of the identity key of the agent.
+    pub identity: String,
+    /// The response of `ipc_health`, as the JSON string that is signed.
+    pub status: String,
+    /// The unix timestamp, in seconds, the status was signed at.
+    pub timestamp: u64,
+    pub challenge: Option<String>,
+    /// The signature of the identity key over the rest of the response, in hex.
+    pub signature: String,
+}
+
+impl SignedStatusResponse {
+    fn payload(&self) -> String {
+        format!(
+            "ipc_signedStatus/{}/{}/{}/{}",
+            self.identity,
+            self.timestamp,
+            self.challenge.as_deref().unwrap_or_default(),
+            self.status
+        )
+    }
+
+    /// Checks that the status was signed by the agent with the identity `identity`, for
+    /// `challenge`, and returns it.
+    pub fn verify(&self, identity: Address, challenge: Option<&str>) -> Result<HealthResponse> {
+        if self.challenge.as_deref() != challenge {
+            return Err(anyhow!("status signed for another challenge"));
+        }
+        Signature::from_str(&self.signature)?
+            .verify(self.payload(), identity)
+            .map_err(|_| anyhow!("status not signed by the agent {identity:?}"))?;
+        Ok(serde_json::from_str(&self.status)?)
+    }
+}
+
+/// Returns the health of the agent, as `ipc_health` does, signed with its identity key.
+pub(crate) struct SignedStatusHandler {
+    health: HealthHandler,
+    identity: Arc<AgentIdentity>,
+}
+
+impl SignedStatusHandler {
+    pub(crate) fn new(health: HealthHandler, identity: Arc<AgentIdentity>) -> Self {
+        Self { health, identity }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for SignedStatusHandler {
+    type Request = SignedStatusParams;
+    type Response = SignedStatusResponse;
+
+    async fn handle(&self, request: Self::Request) -> Result<Self::Response> {
+        let status = self.health.handle(HealthParams::default()).await?;
+        let mut response = SignedStatusResponse {
+            identity: format!("{:?}", self.identity.address()),
+            status: serde_json::to_string(&status)?,
+            timestamp: now(),
+            challenge: request.challenge,
+            signature: String::new(),
+        };
+        response.signature = self.identity.sign(&response.payload())?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::persistence::DataDir;
+    use crate::server::handlers::status::{AgentIdentity, SignedStatusResponse};
+
+    #[test]
+    fn test_signed_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(dir.path());
+        let identity = AgentIdentity::load_or_generate(&data_dir).unwrap();
+        // the key is kept across restarts
+        let address = identity.address();
+        assert_eq!(
+            AgentIdentity::load_or_generate(&data_dir)
+                .unwrap()
+                .address(),
+            address
+        );
+
+        let mut response = SignedStatusResponse {
+            identity: format!("{address:?}"),
+            status: String::from(r#"{"degraded":false,"subnets":[]}"#),
+            timestamp: 1690000000,
+            challenge: Some(String::from("abc")),
+            signature: String::new(),
+        };
+        response.signature = identity.sign(&response.payload()).unwrap();
+        let status = response.verify(address, Some("abc")).unwrap();
+        assert!(!status.degraded);
+
+        assert!(response.verify(address, Some("def")).is_err());
+        let other = tempfile::tempdir().unwrap();
+        let other = AgentIdentity::load_or_generate(&DataDir::new(other.path())).unwrap();
+        assert!(response.verify(other.address(), Some("abc")).is_err());
+
+        let mut tampered = response.clone();
+        tampered.status = String::from(r#"{"degraded":true,"subnets":[]}"#);
+        assert!(tampered.verify(address, Some("abc")).is_err());
+    }
+}
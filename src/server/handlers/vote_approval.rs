@@ -0,0 +1,75 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Approval of the checkpoint votes of the subnets with the `manual-approval` vote policy
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::VoteApproval;
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListVoteApprovalsParams {}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApproveVoteParams {
+    pub id: u64,
+    /// Whether to approve the vote, rejects it otherwise.
+    pub approve: bool,
+}
+
+/// Lists the votes waiting for, or decided by, an operator.
+pub(crate) struct ListVoteApprovalsHandler {
+    state: Arc<AgentState>,
+}
+
+impl ListVoteApprovalsHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ListVoteApprovalsHandler {
+    type Request = ListVoteApprovalsParams;
+    type Response = Vec<VoteApproval>;
+
+    async fn handle(&self, _request: Self::Request) -> anyhow::Result<Self::Response> {
+        Ok(self.state.vote_approvals.all())
+    }
+}
+
+/// Approves or rejects a pending vote.
+pub(crate) struct ApproveVoteHandler {
+    state: Arc<AgentState>,
+}
+
+impl ApproveVoteHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for ApproveVoteHandler {
+    type Request = ApproveVoteParams;
+    type Response = VoteApproval;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let approval = self
+            .state
+            .vote_approvals
+            .decide(request.id, request.approve)?;
+        tracing::info!(
+            "vote of validator {} at epoch {} in {} {:?} by the operator",
+            approval.validator,
+            approval.epoch,
+            approval.checkpoint,
+            approval.status
+        );
+        Ok(approval)
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Activity feed of the accounts of a subnet, for operators to account for what their accounts
+//! sent, received and spent in gas.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ReloadableConfig;
+use crate::history::CheckpointStore;
+use crate::manager::activity::{activity_feed, summarize, Activity, ActivitySummary};
+use crate::manager::key_usage::KeyOperation;
+use crate::manager::SubnetManager;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::JsonRPCRequestHandler;
+
+/// The number of epochs looked back from the chain head, if not requested, a day of 30s blocks.
+const DEFAULT_LOOKBACK_EPOCHS: ChainEpoch = 2880;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalletActivityParams {
+    pub subnet: String,
+    /// The account to list the activity of, all the accounts of the subnet in the config if not
+    /// set.
+    pub account: Option<String>,
+    /// Defaults to 2880 epochs before the chain head. The cross-net messages are filtered by the
+    /// epoch of their checkpoint.
+    pub from_epoch: Option<ChainEpoch>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountActivity {
+    pub account: String,
+    /// Whether the messages of the account were listed from the chain. If not, as evm nodes do
+    /// not index them, only the messages signed by the agent since it started are in the feed.
+    pub listed_on_chain: bool,
+    pub summary: ActivitySummary,
+    /// The latest first.
+    pub activity: Vec<Activity>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalletActivityResponse {
+    pub subnet: String,
+    pub from_epoch: ChainEpoch,
+    pub accounts: Vec<AccountActivity>,
+}
+
+/// The wallet activity json rpc method handler.
+pub(crate) struct WalletActivityHandler {
+    pool: Arc<SubnetManagerPool>,
+    config: Arc<ReloadableConfig>,
+    store: CheckpointStore,
+}
+
+impl WalletActivityHandler {
+    pub(crate) fn new(
+        pool: Arc<SubnetManagerPool>,
+        config: Arc<ReloadableConfig>,
+        store: CheckpointStore,
+    ) -> Self {
+        Self {
+            pool,
+            config,
+            store,
+        }
+    }
+
+    async fn account_activity(
+        &self,
+        subnet: &SubnetID,
+        manager: &dyn SubnetManager,
+        account: Address,
+        from_epoch: ChainEpoch,
+    ) -> anyhow::Result<AccountActivity> {
+        // the operations the agent signed with the account, by message id
+        let operations = self
+            .pool
+            .state()
+            .key_usage
+            .get(&account)
+            .map(|usage| {
+                usage
+                    .recent
+                    .into_iter()
+                    .filter(|u| u.subnet == subnet.to_string())
+                    .map(|u| (u.message, u.operation))
+                    .collect::<HashMap<String, KeyOperation>>()
+            })
+            .unwrap_or_default();
+
+        let (mut ids, listed_on_chain) = match manager
+            .list_account_messages(&account, from_epoch)
+            .await
+        {
+            Ok(ids) => (ids, true),
+            Err(e) => {
+                tracing::debug!("cannot list the messages of {account:} in subnet {subnet:}, only the ones of the agent are listed: {e:}");
+                (vec![], false)
+            }
+        };
+        for id in operations.keys() {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+
+        let mut messages = vec![];
+        for id in ids {
+            match manager.get_executed_message(&id).await {
+                Ok(m) if m.epoch >= from_epoch => messages.push(m),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "cannot get message {id:} of {account:} in subnet {subnet:}: {e:}"
+                    )
+                }
+            }
+        }
+
+        // the cross-net messages sent from the subnet are in its own history, the ones received
+        // in the subnet in the history of its children
+        let mut cross_msgs = self
+            .store
+            .cross_msgs_by_account(subnet, &account)
+            .await?
+            .into_iter()
+            .filter(|c| c.sent)
+            .collect::<Vec<_>>();
+        let children = self
+            .config
+            .get_config()
+            .subnets
+            .keys()
+            .filter(|id| id.parent().as_ref() == Some(subnet))
+            .cloned()
+            .collect::<Vec<_>>();
+        for child in children {
+            cross_msgs.extend(
+                self.store
+                    .cross_msgs_by_account(&child, &account)
+                    .await?
+                    .into_iter()
+                    .filter(|c| !c.sent),
+            );
+        }
+        cross_msgs.retain(|c| c.epoch >= from_epoch);
+
+        let activity = activity_feed(&account, &messages, &operations, &cross_msgs);
+        Ok(AccountActivity {
+            account: account.to_string(),
+            listed_on_chain,
+            summary: summarize(&activity),
+            activity,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for WalletActivityHandler {
+    type Request = WalletActivityParams;
+    type Response = WalletActivityResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let conn = self
+            .pool
+            .get(&subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+
+        let accounts = match &request.account {
+            Some(account) => vec![Address::from_str(account)?],
+            None => conn.subnet().accounts(),
+        };
+        let from_epoch = match request.from_epoch {
+            Some(epoch) => epoch,
+            None => (conn.manager().chain_head_epoch().await? - DEFAULT_LOOKBACK_EPOCHS).max(0),
+        };
+
+        let mut activity = vec![];
+        for account in accounts {
+            activity.push(
+                self.account_activity(&subnet, conn.manager(), account, from_epoch)
+                    .await?,
+            );
+        }
+
+        Ok(WalletActivityResponse {
+            subnet: subnet.to_string(),
+            from_epoch,
+            accounts: activity,
+        })
+    }
+}
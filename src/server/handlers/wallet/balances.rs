@@ -1,6 +1,7 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 
+use crate::amount::Amount;
 use crate::config::subnet::SubnetConfig;
 use crate::manager::evm::ethers_address_to_fil_address;
 use crate::manager::SubnetManager;
@@ -24,8 +25,8 @@ pub struct WalletBalancesParams {
     pub subnet: String,
 }
 
-/// Key is the address as string and value is the token amount as string
-pub type WalletBalancesResponse = HashMap<String, String>;
+/// Key is the address as string and value is its balance
+pub type WalletBalancesResponse = HashMap<String, Amount>;
 
 /// Send value between two addresses within a subnet
 pub(crate) struct WalletBalancesHandler {
@@ -72,7 +73,7 @@ impl WalletBalancesHandler {
             .into_iter()
             .collect::<anyhow::Result<Vec<(TokenAmount, &Address)>>>()?;
         for (balance, addr) in r {
-            hashmap.insert(addr.to_string(), balance.to_string());
+            hashmap.insert(addr.to_string(), Amount::from(balance));
         }
         Ok(hashmap)
     }
@@ -100,7 +101,7 @@ impl WalletBalancesHandler {
             .into_iter()
             .collect::<anyhow::Result<Vec<(TokenAmount, &ethers::types::H160)>>>()?;
         for (balance, addr) in r {
-            hashmap.insert(format!("{addr:?}"), balance.to_string());
+            hashmap.insert(format!("{addr:?}"), Amount::from(balance));
         }
         Ok(hashmap)
     }
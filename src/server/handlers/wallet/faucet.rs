@@ -0,0 +1,141 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Requests test funds for an account from the faucet of its subnet, and waits for them to land.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::amount::Amount;
+use crate::config::subnet::FaucetConfig;
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::server::handlers::manager::subnet::SubnetManagerPool;
+use crate::server::handlers::manager::{check_subnet, parse_from};
+use crate::server::JsonRPCRequestHandler;
+
+/// The time the funds are waited for by default, within the request timeout of the clients of
+/// the agent.
+const DEFAULT_FAUCET_TIMEOUT: Duration = Duration::from_secs(180);
+/// The interval between the checks of the balance of the account funded.
+const FAUCET_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// The time allowed to an http faucet to accept a request.
+const FAUCET_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaucetRequestParams {
+    pub subnet: String,
+    /// The account to fund, a fil or an eth address
+    pub address: String,
+    /// The account calling a faucet contract, the first account of the subnet if not set
+    pub from: Option<String>,
+    /// The seconds to wait for the funds to land, 180 if not set
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaucetRequestResponse {
+    pub address: String,
+    /// The balance of the account before the request
+    pub previous_balance: Amount,
+    pub balance: Amount,
+    /// The amount sent by the faucet
+    pub received: Amount,
+    /// The seconds it took for the funds to land
+    pub waited_secs: u64,
+}
+
+/// The faucet request json rpc method handler.
+pub(crate) struct FaucetRequestHandler {
+    pool: Arc<SubnetManagerPool>,
+}
+
+impl FaucetRequestHandler {
+    pub(crate) fn new(pool: Arc<SubnetManagerPool>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for FaucetRequestHandler {
+    type Request = FaucetRequestParams;
+    type Response = FaucetRequestResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let subnet = SubnetID::from_str(&request.subnet)?;
+        let conn = match self.pool.get(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+        let subnet_config = conn.subnet();
+        let faucet = subnet_config
+            .faucet
+            .clone()
+            .ok_or_else(|| anyhow!("no faucet configured for subnet {subnet:}"))?;
+
+        let to = match Address::from_str(&request.address) {
+            Ok(addr) => addr,
+            Err(_) => {
+                ethers_address_to_fil_address(&ethers::types::Address::from_str(&request.address)?)?
+            }
+        };
+        let timeout = request
+            .timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_FAUCET_TIMEOUT);
+
+        let previous_balance = conn.manager().wallet_balance(&to).await?;
+        match faucet {
+            FaucetConfig::Http { url } => {
+                tracing::info!("requesting test funds for {to:} in subnet {subnet:} from {url:}");
+                request_http_faucet(url, &request.address).await?;
+            }
+            FaucetConfig::Contract { address } => {
+                check_subnet(subnet_config)?;
+                let from = parse_from(subnet_config, request.from)?;
+                conn.manager().faucet_drip(address, from, to).await?;
+            }
+        }
+
+        let started = Instant::now();
+        loop {
+            let balance = conn.manager().wallet_balance(&to).await?;
+            if balance > previous_balance {
+                let received = balance.clone() - previous_balance.clone();
+                tracing::info!("received {received:} from the faucet of subnet {subnet:} in {to:}");
+                return Ok(FaucetRequestResponse {
+                    address: to.to_string(),
+                    previous_balance: Amount::from(previous_balance),
+                    balance: Amount::from(balance),
+                    received: Amount::from(received),
+                    waited_secs: started.elapsed().as_secs(),
+                });
+            }
+            if started.elapsed() >= timeout {
+                return Err(anyhow!(
+                    "the funds of the faucet did not land in {to:} within {}s",
+                    timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(FAUCET_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Posts the address to fund to the http faucet at `url`.
+async fn request_http_faucet(url: Url, address: &str) -> anyhow::Result<()> {
+    reqwest::Client::new()
+        .post(url)
+        .timeout(FAUCET_HTTP_TIMEOUT)
+        .json(&serde_json::json!({ "address": address }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
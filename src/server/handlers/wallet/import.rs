@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT
 //! wallet handlers and parameters
 
+use crate::manager::evm::ethers_address_to_fil_address;
 use crate::server::JsonRPCRequestHandler;
 use async_trait::async_trait;
 use base64::Engine;
@@ -10,7 +11,7 @@ use ipc_identity::json::KeyInfoJson;
 use ipc_identity::{EvmKeyInfo, EvmKeyStore, KeyInfo, PersistentKeyStore, Wallet};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(tag = "network_type")]
@@ -118,3 +119,77 @@ impl JsonRPCRequestHandler for WalletImportHandler {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Secp256k1ImportParams {
+    /// Hex encoded secp256k1 private key string
+    pub private_key: String,
+}
+
+impl Drop for Secp256k1ImportParams {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Secp256k1ImportResponse {
+    /// The f1 address of the key, used in fvm subnets
+    pub fvm_address: String,
+    /// The 0x address of the key, used in fevm subnets
+    pub evm_address: String,
+    /// The f410 address the 0x address is represented with in fevm subnets
+    pub fevm_address: String,
+}
+
+/// Imports a secp256k1 key in both the fvm wallet and the evm keystore, so that the same key can
+/// be used by both types of subnet managers.
+pub(crate) struct WalletImportSecp256k1Handler {
+    fvm_wallet: Arc<RwLock<Wallet>>,
+    evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+}
+
+impl WalletImportSecp256k1Handler {
+    pub(crate) fn new(
+        fvm_wallet: Arc<RwLock<Wallet>>,
+        evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    ) -> Self {
+        Self {
+            fvm_wallet,
+            evm_keystore,
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for WalletImportSecp256k1Handler {
+    type Request = Secp256k1ImportParams;
+    type Response = Secp256k1ImportResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let private_key = request
+            .private_key
+            .strip_prefix("0x")
+            .unwrap_or(&request.private_key);
+        let private_key = Zeroizing::new(hex::decode(private_key)?);
+
+        // The fvm wallet validates the key, import it there first so that an invalid key is not
+        // persisted in the evm keystore.
+        let fvm_address = self
+            .fvm_wallet
+            .write()
+            .unwrap()
+            .import(KeyInfo::new(SignatureType::Secp256k1, private_key.to_vec()))?;
+        let evm_address = self
+            .evm_keystore
+            .write()
+            .unwrap()
+            .put(EvmKeyInfo::new(private_key.to_vec()))?;
+
+        Ok(Secp256k1ImportResponse {
+            fvm_address: fvm_address.to_string(),
+            evm_address: format!("{evm_address:?}"),
+            fevm_address: ethers_address_to_fil_address(&evm_address)?.to_string(),
+        })
+    }
+}
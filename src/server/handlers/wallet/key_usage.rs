@@ -0,0 +1,76 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Key usage handler and parameters
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::manager::evm::ethers_address_to_fil_address;
+use crate::manager::key_usage::KeyUsageSummary;
+use crate::server::JsonRPCRequestHandler;
+use crate::state::AgentState;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyUsageParams {
+    /// The fvm or evm address of the key to review, all the keys used if not set.
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyUsageEntry {
+    pub address: String,
+    #[serde(flatten)]
+    pub usage: KeyUsageSummary,
+}
+
+pub type KeyUsageResponse = Vec<KeyUsageEntry>;
+
+/// Returns the signatures produced with the keys of the agent since it started.
+pub(crate) struct KeyUsageHandler {
+    state: Arc<AgentState>,
+}
+
+impl KeyUsageHandler {
+    pub(crate) fn new(state: Arc<AgentState>) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl JsonRPCRequestHandler for KeyUsageHandler {
+    type Request = KeyUsageParams;
+    type Response = KeyUsageResponse;
+
+    async fn handle(&self, request: Self::Request) -> anyhow::Result<Self::Response> {
+        let mut entries = match request.address {
+            Some(addr) => {
+                // evm keys are recorded with their f410 address
+                let addr = if addr.starts_with("0x") {
+                    ethers_address_to_fil_address(&ethers::types::Address::from_str(&addr)?)?
+                } else {
+                    Address::from_str(&addr)?
+                };
+                self.state
+                    .key_usage
+                    .get(&addr)
+                    .map(|usage| (addr, usage))
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            }
+            None => self.state.key_usage.all().into_iter().collect(),
+        };
+        entries.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.last_used));
+
+        Ok(entries
+            .into_iter()
+            .map(|(addr, usage)| KeyUsageEntry {
+                address: addr.to_string(),
+                usage,
+            })
+            .collect())
+    }
+}
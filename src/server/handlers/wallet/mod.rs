@@ -7,9 +7,12 @@ use serde::{Deserialize, Serialize};
 
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+pub mod activity;
 pub mod balances;
 pub mod export;
+pub mod faucet;
 pub mod import;
+pub mod key_usage;
 pub mod new;
 pub mod remove;
 
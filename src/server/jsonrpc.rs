@@ -1,31 +1,47 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use bytes::Bytes;
 use ipc_identity::PersistentKeyStore;
 use ipc_identity::Wallet;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Notify;
+use tokio::time::timeout;
 use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
+use tracing::Instrument;
 use warp::http::StatusCode;
 use warp::reject::Reject;
 use warp::reply::with_status;
 use warp::{Filter, Rejection, Reply};
 
-use crate::config::JSON_RPC_VERSION;
-use crate::config::{ReloadableConfig, JSON_RPC_ENDPOINT};
-use crate::server::request::JSONRPCRequest;
-use crate::server::response::{JSONRPCError, JSONRPCErrorResponse, JSONRPCResultResponse};
-use crate::server::Handlers;
+use crate::config::{json_rpc_methods, JSON_RPC_VERSION};
+use crate::config::{
+    ReloadableConfig, RunMode, HEALTHZ_ENDPOINT, JSON_RPC_ENDPOINT, READYZ_ENDPOINT, WS_ENDPOINT,
+};
+use crate::server::auth::AuthRejection;
+use crate::server::handlers::HandlerWrapper;
+use crate::server::health::HealthResponse;
+use crate::server::middleware::Middleware;
+use crate::server::request::{new_request_id, JSONRPCRequest};
+use crate::server::response::{JSONRPCErrorResponse, JSONRPCResultResponse};
+use crate::server::ws;
+use crate::server::{Handlers, JsonRPCRequestHandler, Method};
+use crate::state::AgentState;
 
 type ArcHandlers = Arc<Handlers>;
 
+/// The time the server waits for its connections to close once the requests in flight are served.
+const SERVER_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// The IPC JSON RPC node that contains all the methods and handlers. The underlying implementation
 /// is using `warp`.
 ///
-/// Note that currently only http json rpc is supported.
+/// The methods are served over http, and over websocket along with subscriptions to the events of
+/// the agent, see [`ws`]. The probes of the orchestrators are served at `/healthz` and `/readyz`.
 ///
 /// # Examples
 /// ```no_run
@@ -37,6 +53,7 @@ type ArcHandlers = Arc<Handlers>;
 /// use ipc_agent::config::ReloadableConfig;
 /// use ipc_agent::server::jsonrpc::JsonRPCServer;
 /// use ipc_agent::server::{new_evm_keystore_from_config, new_fvm_wallet_from_config};
+/// use ipc_agent::state::AgentState;
 /// use ipc_identity::Wallet;
 ///
 /// #[tokio::main]
@@ -45,7 +62,8 @@ type ArcHandlers = Arc<Handlers>;
 ///     let config = Arc::new(ReloadableConfig::new(path.to_string()).unwrap());
 ///     let fvm_wallet = Arc::new(RwLock::new(Wallet::new(new_fvm_wallet_from_config(config.clone()).unwrap())));
 ///     let evm_keystore = Arc::new(RwLock::new((new_evm_keystore_from_config(config.clone()).unwrap()));
-///     let server = JsonRPCServer::new(config, fvm_wallet, evm_keystore);
+///     let state = Arc::new(AgentState::new());
+///     let server = JsonRPCServer::new(config, fvm_wallet, evm_keystore, state);
 ///     Toplevel::new()
 ///         .start("JSON-RPC server subsystem", server.into_subsystem())
 ///         .catch_signals()
@@ -58,6 +76,11 @@ pub struct JsonRPCServer {
     config: Arc<ReloadableConfig>,
     fvm_wallet: Arc<RwLock<Wallet>>,
     evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+    /// The handlers registered by the user of the library, added to the built-in ones on start.
+    custom_handlers: Vec<(Method, Box<dyn HandlerWrapper>)>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    mode: RunMode,
+    state: Arc<AgentState>,
 }
 
 impl JsonRPCServer {
@@ -65,20 +88,49 @@ impl JsonRPCServer {
         config: Arc<ReloadableConfig>,
         fvm_wallet: Arc<RwLock<Wallet>>,
         evm_keystore: Arc<RwLock<PersistentKeyStore<ethers::types::Address>>>,
+        state: Arc<AgentState>,
     ) -> Self {
         Self {
             config,
             fvm_wallet,
             evm_keystore,
+            custom_handlers: vec![],
+            middlewares: vec![],
+            mode: RunMode::default(),
+            state,
         }
     }
+
+    /// Registers `handler` for `method`, to be served along the built-in methods once the server
+    /// starts, see [`Handlers::register`]. The server fails to start if the method is already
+    /// registered or its name is invalid.
+    pub fn register<H>(&mut self, method: impl Into<Method>, handler: H) -> &mut Self
+    where
+        H: JsonRPCRequestHandler + Send + Sync + 'static,
+    {
+        self.custom_handlers
+            .push((method.into(), Box::new(handler)));
+        self
+    }
+
+    /// Adds a middleware run around every request once the server starts, see [`Middleware`].
+    pub fn middleware(&mut self, middleware: impl Middleware + 'static) -> &mut Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Only serves the methods that are part of the workload of `mode`, all of them by default.
+    pub fn mode(&mut self, mode: RunMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
 }
 
 #[async_trait]
 impl IntoSubsystem<anyhow::Error> for JsonRPCServer {
     /// Runs the JSON-RPC server as a subsystem.
     async fn run(self, subsys: SubsystemHandle) -> Result<()> {
-        log::info!(
+        tracing::info!(
             "IPC agent rpc node listening at {:?}",
             self.config.get_config().server.json_rpc_address
         );
@@ -88,22 +140,61 @@ impl IntoSubsystem<anyhow::Error> for JsonRPCServer {
         let notify_recv = notify_send.clone();
 
         // Start the server.
-        let handlers = Arc::new(Handlers::new(
+        let mut handlers = Handlers::new(
             self.config.clone(),
             self.fvm_wallet.clone(),
             self.evm_keystore.clone(),
-        )?);
-        let (_, server) = warp::serve(json_rpc_filter(handlers)).bind_with_graceful_shutdown(
+            self.state.clone(),
+        )?;
+        for (method, handler) in self.custom_handlers {
+            tracing::info!("registering custom json rpc method: {method:}");
+            handlers.register_boxed(method, handler)?;
+        }
+        for middleware in self.middlewares {
+            handlers.add_boxed_middleware(middleware);
+        }
+        handlers.set_mode(self.mode);
+        let handlers = Arc::new(handlers);
+        let filter = probes_filter(handlers.clone())
+            .or(ws_filter(handlers.clone()))
+            .or(json_rpc_filter(handlers));
+        let (_, server) = warp::serve(filter).bind_with_graceful_shutdown(
             self.config.get_config().server.json_rpc_address,
             async move { notify_recv.notified().await },
         );
         let server_handle = tokio::spawn(server);
 
-        // Wait for the shutdown signal and gracefully shutdown.
+        // The debug tap and the notifier follow the config, so that they can be turned on and
+        // off with a reload.
+        let config = self.config.clone();
+        let state = self.state.clone();
+        let mut config_chan = config.new_subscriber();
+        let tap_handle = tokio::spawn(async move {
+            loop {
+                let c = config.get_config();
+                state.debug_tap.configure(c.server.debug_tap.as_ref());
+                state.notifier.configure(c.server.notifications.as_ref());
+                if let Err(RecvError::Closed) = config_chan.recv().await {
+                    break;
+                }
+            }
+        });
+
+        // Wait for the shutdown signal and gracefully shutdown, once the requests in flight are
+        // served. The new requests are refused meanwhile.
         subsys.on_shutdown_requested().await;
-        log::info!("Shutting down IPC agent rpc node");
+        tracing::info!("Shutting down IPC agent rpc node");
+        self.state
+            .shutdown
+            .drain(self.config.get_config().server.shutdown_timeout())
+            .await;
         notify_send.notify_waiters();
-        server_handle.await?;
+        tap_handle.abort();
+        // the open websocket connections would keep the server running
+        match timeout(SERVER_CLOSE_TIMEOUT, server_handle).await {
+            Ok(r) => r?,
+            Err(_) => tracing::warn!("closing the remaining connections of the rpc node"),
+        }
 
         Ok(())
     }
@@ -115,6 +206,7 @@ impl IntoSubsystem<anyhow::Error> for JsonRPCServer {
 /// - Listen to POST requests on the DEFAULT_JSON_RPC_ENDPOINT
 /// - Extract the body of the request.
 /// - Pass it to to the json_rpc_filter to deserialize into a jsonrpc request.
+/// - Extract the `Authorization` header, checked against the method requested.
 fn json_rpc_filter(
     handlers: ArcHandlers,
 ) -> impl Filter<Extract = (impl Reply,), Error = warp::Rejection> + Clone {
@@ -122,11 +214,63 @@ fn json_rpc_filter(
         .and(warp::path(JSON_RPC_ENDPOINT))
         .and(warp::body::bytes())
         .and_then(to_json_rpc_request)
+        .and(warp::header::optional::<String>("authorization"))
         .and(with_handlers(handlers))
         .and_then(handle_request)
         .recover(handle_rejection)
 }
 
+/// Create the websocket filter, that upgrades the connections to the WS_ENDPOINT and serves them
+/// with the `Authorization` header of their upgrade request.
+fn ws_filter(
+    handlers: ArcHandlers,
+) -> impl Filter<Extract = (impl Reply,), Error = warp::Rejection> + Clone {
+    warp::path(WS_ENDPOINT)
+        .and(warp::ws())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(with_handlers(handlers))
+        .map(
+            |socket: warp::ws::Ws, authorization: Option<String>, handlers: ArcHandlers| {
+                socket.on_upgrade(move |socket| ws::serve(socket, authorization, handlers))
+            },
+        )
+}
+
+/// Create the filter of the probes, that need no token: `/healthz` answers as long as the server
+/// runs, and `/readyz` only when `ipc_health` reports the agent ready.
+fn probes_filter(
+    handlers: ArcHandlers,
+) -> impl Filter<Extract = (impl Reply,), Error = warp::Rejection> + Clone {
+    let healthz = warp::get()
+        .and(warp::path(HEALTHZ_ENDPOINT))
+        .and(warp::path::end())
+        .map(|| with_status("OK", StatusCode::OK));
+    let readyz = warp::get()
+        .and(warp::path(READYZ_ENDPOINT))
+        .and(warp::path::end())
+        .and(with_handlers(handlers))
+        .then(readiness);
+    healthz.or(readyz)
+}
+
+async fn readiness(handlers: ArcHandlers) -> impl Reply {
+    let health = handlers
+        .handle(
+            String::from(json_rpc_methods::HEALTH),
+            serde_json::json!({}),
+        )
+        .await
+        .and_then(|r| Ok(serde_json::from_value::<HealthResponse>(r)?));
+    match health {
+        Ok(health) if health.ready => with_status("READY", StatusCode::OK),
+        Ok(_) => with_status("NOT_READY", StatusCode::SERVICE_UNAVAILABLE),
+        Err(e) => {
+            tracing::warn!("cannot check the readiness of the agent: {e:}");
+            with_status("NOT_READY", StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
 fn with_handlers(
     handlers: ArcHandlers,
 ) -> impl Filter<Extract = (ArcHandlers,), Error = std::convert::Infallible> + Clone {
@@ -136,7 +280,7 @@ fn with_handlers(
 // Filter that deserializes the body of the request into a jsonrpc request.
 async fn to_json_rpc_request(bytes: Bytes) -> Result<JSONRPCRequest, warp::Rejection> {
     serde_json::from_slice::<JSONRPCRequest>(bytes.as_ref()).map_err(|e| {
-        log::debug!("cannot deserialize {bytes:?} due to {e:?}");
+        tracing::debug!("cannot deserialize {bytes:?} due to {e:?}");
         warp::reject::custom(InvalidParameter)
     })
 }
@@ -144,9 +288,23 @@ async fn to_json_rpc_request(bytes: Bytes) -> Result<JSONRPCRequest, warp::Rejec
 /// Main function responsible for handling and routing jsonrpc requests to the right underlying handler according to the method
 async fn handle_request(
     json_rpc_request: JSONRPCRequest,
+    authorization: Option<String>,
     handlers: ArcHandlers,
 ) -> Result<impl Reply, warp::Rejection> {
-    log::debug!("received json rpc request = {:?}", json_rpc_request);
+    let request_id = new_request_id();
+    let span = json_rpc_request.span(&request_id);
+    serve_request(json_rpc_request, &request_id, authorization, handlers)
+        .instrument(span)
+        .await
+}
+
+async fn serve_request(
+    json_rpc_request: JSONRPCRequest,
+    request_id: &str,
+    authorization: Option<String>,
+    handlers: ArcHandlers,
+) -> Result<impl Reply, warp::Rejection> {
+    tracing::debug!("received json rpc request = {:?}", json_rpc_request);
 
     let JSONRPCRequest {
         id,
@@ -161,16 +319,19 @@ async fn handle_request(
         )));
     }
 
-    log::debug!("received method = {method:?} and params = {params:?}");
+    if let Err(e) = handlers.authorize(&method, authorization.as_deref()) {
+        tracing::warn!("refused request to method {method:}: {e:?}");
+        return Err(warp::reject::custom(e));
+    }
+
+    tracing::debug!("received method = {method:?} and params = {params:?}");
     match handlers.handle(method, params).await {
         Ok(response) => Ok(warp::reply::json(&JSONRPCResultResponse::new(id, response))),
         Err(e) => {
-            let error: JSONRPCError<()> = JSONRPCError {
-                code: -1,
-                data: None,
-                message: e.to_string(),
-            };
-            Ok(warp::reply::json(&JSONRPCErrorResponse::new(id, error)))
+            tracing::warn!("request failed: {e:}");
+            Ok(warp::reply::json(&JSONRPCErrorResponse::failed(
+                id, request_id, &e,
+            )))
         }
     }
 }
@@ -186,8 +347,15 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, warp::Rejection>
         Ok(with_status("NOT_FOUND", StatusCode::NOT_FOUND))
     } else if err.find::<InvalidParameter>().is_some() {
         Ok(with_status("BAD_REQUEST", StatusCode::BAD_REQUEST))
+    } else if let Some(e) = err.find::<AuthRejection>() {
+        match e {
+            AuthRejection::Unauthenticated => {
+                Ok(with_status("UNAUTHORIZED", StatusCode::UNAUTHORIZED))
+            }
+            AuthRejection::Forbidden => Ok(with_status("FORBIDDEN", StatusCode::FORBIDDEN)),
+        }
     } else {
-        log::error!("unhandled rejection: {:?}", err);
+        tracing::error!("unhandled rejection: {:?}", err);
         Ok(with_status(
             "INTERNAL_SERVER_ERROR",
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -201,9 +369,16 @@ mod tests {
 
     use warp::http::StatusCode;
 
-    use crate::config::{JSON_RPC_ENDPOINT, JSON_RPC_VERSION};
-    use crate::server::jsonrpc::{json_rpc_filter, ArcHandlers, JSONRPCResultResponse};
+    use crate::config::{
+        HEALTHZ_ENDPOINT, JSON_RPC_ENDPOINT, JSON_RPC_VERSION, READYZ_ENDPOINT, WS_ENDPOINT,
+    };
+    use crate::events::AgentEvent;
+    use crate::server::jsonrpc::{
+        json_rpc_filter, probes_filter, ws_filter, ArcHandlers, JSONRPCResultResponse,
+    };
     use crate::server::request::JSONRPCRequest;
+    use crate::server::response::{JSONRPCErrorResponse, RequestErrorData};
+    use crate::server::ws::SubscriptionNotification;
     use crate::server::Handlers;
 
     fn get_empty_handlers() -> ArcHandlers {
@@ -267,4 +442,81 @@ mod tests {
 
         assert_eq!(StatusCode::NOT_FOUND, value.status());
     }
+
+    #[tokio::test]
+    async fn test_probes() {
+        let filter = probes_filter(get_empty_handlers());
+
+        let value = warp::test::request()
+            .path(&format!("/{HEALTHZ_ENDPOINT:}"))
+            .reply(&filter)
+            .await;
+        assert_eq!(StatusCode::OK, value.status());
+
+        // not ready without the health of the subnets
+        let value = warp::test::request()
+            .path(&format!("/{READYZ_ENDPOINT:}"))
+            .reply(&filter)
+            .await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, value.status());
+    }
+
+    #[tokio::test]
+    async fn test_ws_subscription() {
+        let handlers = get_empty_handlers();
+        let filter = ws_filter(handlers.clone());
+        let mut client = warp::test::ws()
+            .path(&format!("/{WS_ENDPOINT:}"))
+            .handshake(filter)
+            .await
+            .unwrap();
+
+        client
+            .send_text(r#"{"jsonrpc":"2.0","id":1,"method":"ipc_subscribeCrossMsgs","params":{"subnet":"/r123/f0100"}}"#)
+            .await;
+        let msg = client.recv().await.unwrap();
+        let subscription =
+            serde_json::from_str::<JSONRPCResultResponse<u64>>(msg.to_str().unwrap()).unwrap();
+        assert_eq!(subscription.id, 1);
+
+        let sent = |subnet: &str| AgentEvent::CrossMsgSent {
+            subnet: String::from(subnet),
+            method: String::from("ipc_release"),
+            from: String::from("f0100"),
+            to: None,
+            value: None,
+            epoch: Some(10),
+        };
+        // only the events of the subnet subscribed to are pushed
+        let events = &handlers.state().events;
+        events.publish(sent("/r123/f0200"));
+        events.publish(sent("/r123/f0100"));
+        let msg = client.recv().await.unwrap();
+        let notification =
+            serde_json::from_str::<SubscriptionNotification>(msg.to_str().unwrap()).unwrap();
+        assert_eq!(notification.params.subscription, subscription.result);
+        assert_eq!(notification.params.result, sent("/r123/f0100"));
+
+        client
+            .send_text(format!(
+                r#"{{"jsonrpc":"2.0","id":2,"method":"ipc_unsubscribe","params":{{"subscription":{}}}}}"#,
+                subscription.result
+            ))
+            .await;
+        let msg = client.recv().await.unwrap();
+        let unsubscribed =
+            serde_json::from_str::<JSONRPCResultResponse<bool>>(msg.to_str().unwrap()).unwrap();
+        assert!(unsubscribed.result);
+
+        // the errors carry the correlation id of the request
+        client
+            .send_text(r#"{"jsonrpc":"2.0","id":3,"method":"ipc_unsubscribe","params":{"subscription":99}}"#)
+            .await;
+        let msg = client.recv().await.unwrap();
+        let failed =
+            serde_json::from_str::<JSONRPCErrorResponse<RequestErrorData>>(msg.to_str().unwrap())
+                .unwrap();
+        assert_eq!(failed.id, 3);
+        assert_eq!(failed.error.data.unwrap().request_id.len(), 16);
+    }
 }
@@ -0,0 +1,106 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Hooks run around the execution of the json rpc handlers.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// A hook around the execution of every json rpc request, registered with
+/// [`Handlers::add_middleware`](crate::server::Handlers::add_middleware). Middlewares can inspect
+/// and modify the params and results of requests, reject requests, e.g. to enforce custom
+/// policies, or record custom metrics.
+///
+/// The `before` hooks run in the order the middlewares were added, and the `after` hooks in the
+/// reverse order.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Runs before the handler of `method`. Returning an error rejects the request, with the
+    /// `before` hooks of the next middlewares and the handler not being run.
+    async fn before(&self, _method: &str, _params: &mut Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs after the handler of `method`, or after the request was rejected, with the result
+    /// returned to the client.
+    async fn after(&self, _method: &str, _result: &mut Result<Value>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    use crate::server::middleware::Middleware;
+    use crate::server::{Handlers, JsonRPCRequestHandler};
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl JsonRPCRequestHandler for EchoHandler {
+        type Request = String;
+        type Response = String;
+
+        async fn handle(&self, request: Self::Request) -> Result<Self::Response> {
+            Ok(request)
+        }
+    }
+
+    /// Rejects the requests to `blocked`, uppercases the params of the others.
+    struct Policy;
+
+    #[async_trait]
+    impl Middleware for Policy {
+        async fn before(&self, method: &str, params: &mut Value) -> Result<()> {
+            if method == "test_blocked" {
+                return Err(anyhow!("method blocked"));
+            }
+            if let Some(s) = params.as_str() {
+                *params = json!(s.to_uppercase());
+            }
+            Ok(())
+        }
+    }
+
+    /// Counts the failed requests.
+    struct Errors(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl Middleware for Errors {
+        async fn after(&self, _method: &str, result: &mut Result<Value>) {
+            if result.is_err() {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middlewares() {
+        let errors = Arc::new(AtomicUsize::new(0));
+        let mut handlers = Handlers::empty_handlers();
+        handlers.register("test_echo", EchoHandler).unwrap();
+        handlers.register("test_blocked", EchoHandler).unwrap();
+        handlers.add_middleware(Errors(errors.clone()));
+        handlers.add_middleware(Policy);
+
+        let r = handlers
+            .handle(String::from("test_echo"), json!("hello"))
+            .await
+            .unwrap();
+        assert_eq!(r, json!("HELLO"));
+
+        assert!(handlers
+            .handle(String::from("test_blocked"), json!("hello"))
+            .await
+            .is_err());
+        assert!(handlers
+            .handle(String::from("test_unknown"), json!("hello"))
+            .await
+            .is_err());
+        assert_eq!(errors.load(Ordering::Relaxed), 2);
+    }
+}
@@ -8,10 +8,13 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::fmt::Debug;
 
+pub mod auth;
 mod handlers;
 pub mod jsonrpc;
+pub mod middleware;
 pub mod request;
 pub mod response;
+pub mod ws;
 
 pub use handlers::*;
 
@@ -1,7 +1,9 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use ethers::core::rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::Span;
 
 /// The json rpc request param. It is the standard form our json-rpc and follows a structure similar
 /// to the one of the Ethereum RPC: https://ethereum.org/en/developers/docs/apis/json-rpc/#curl-examples
@@ -12,3 +14,20 @@ pub struct JSONRPCRequest {
     pub method: String,
     pub params: Value,
 }
+
+impl JSONRPCRequest {
+    /// The span the request is served in, whose fields structure its logs: the correlation id
+    /// generated for it, the id set by the client, its method and the subnet of its params, if
+    /// any. The calls to the nodes made to serve the request are logged in spans of their own
+    /// within it.
+    pub fn span(&self, request_id: &str) -> Span {
+        let subnet = self.params.get("subnet").and_then(Value::as_str);
+        tracing::info_span!("request", request_id, id = self.id, method = %self.method, subnet)
+    }
+}
+
+/// Generates the correlation id of a request. The ids set by the clients are often constant, so
+/// the agent tags every request with one of its own, returned with the errors of the request.
+pub fn new_request_id() -> String {
+    format!("{:016x}", thread_rng().gen::<u64>())
+}
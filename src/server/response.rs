@@ -3,6 +3,9 @@
 use crate::config::JSON_RPC_VERSION;
 use serde::{Deserialize, Serialize};
 
+/// The code of the errors returned by the handlers of the methods.
+const HANDLER_ERROR_CODE: i32 = -1;
+
 /// List of error codes for json rpc, see more: https://www.jsonrpc.org/specification#error_object
 const INVALID_REQUEST_CODE: i32 = -32600;
 
@@ -33,6 +36,13 @@ pub struct JSONRPCError<T> {
     pub data: Option<T>,
 }
 
+/// The data of the errors returned by the handlers, the correlation id of the failed request
+/// that its logs carry.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestErrorData {
+    pub request_id: String,
+}
+
 /// The json rpc error response. It is the standard form our json-rpc and follows the spec: https://www.jsonrpc.org/specification#response_object
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JSONRPCErrorResponse<T> {
@@ -63,3 +73,19 @@ impl<T: Serialize> JSONRPCErrorResponse<T> {
         }
     }
 }
+
+impl JSONRPCErrorResponse<RequestErrorData> {
+    /// The response to the request `id` whose handler failed with `error`.
+    pub fn failed(id: u64, request_id: &str, error: &anyhow::Error) -> Self {
+        Self::new(
+            id,
+            JSONRPCError {
+                code: HANDLER_ERROR_CODE,
+                message: error.to_string(),
+                data: Some(RequestErrorData {
+                    request_id: String::from(request_id),
+                }),
+            },
+        )
+    }
+}
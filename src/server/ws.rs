@@ -0,0 +1,330 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The websocket json rpc api.
+//!
+//! A websocket connection to the `/ws` endpoint serves the same methods as the http api, checked
+//! against the `Authorization` header of the request that opened it. It also serves subscriptions
+//! to the events of the agent, see [`crate::events`]: `ipc_subscribeCheckpoints` and
+//! `ipc_subscribeCrossMsgs` return the id of a subscription, whose events are pushed as
+//! `ipc_subscription` notifications until `ipc_unsubscribe` is called or the connection closed.
+//! A connection holds at most [`MAX_SUBSCRIPTIONS`], and is closed if its client does not read
+//! the messages queued for it fast enough, so that a slow client cannot make the agent buffer
+//! without bound.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use ipc_sdk::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+use warp::ws::{Message, WebSocket};
+
+use crate::config::{json_rpc_methods, JSON_RPC_VERSION};
+use crate::events::{AgentEvent, Topic};
+use crate::server::request::{new_request_id, JSONRPCRequest};
+use crate::server::response::{JSONRPCErrorResponse, JSONRPCResultResponse};
+use crate::server::Handlers;
+
+/// The number of responses and notifications queued for a connection before it is closed.
+const OUTGOING_CAPACITY: usize = 1024;
+/// The number of subscriptions a connection can hold at once.
+pub const MAX_SUBSCRIPTIONS: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscribeParams {
+    /// Only push the events of this subnet, or of the checkpoints and messages sent to it
+    pub subnet: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnsubscribeParams {
+    pub subscription: u64,
+}
+
+/// The notification of an event of a subscription, a json rpc request without id.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: SubscriptionEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionEvent {
+    pub subscription: u64,
+    pub result: AgentEvent,
+}
+
+/// Serves the requests of a websocket connection until it is closed.
+pub(crate) async fn serve(
+    socket: WebSocket,
+    authorization: Option<String>,
+    handlers: Arc<Handlers>,
+) {
+    let (mut sink, mut stream) = socket.split();
+    // the responses and the notifications of the subscriptions are all written by a single task
+    let (sender, mut outgoing) = channel::<String>(OUTGOING_CAPACITY);
+    let sender = Outgoing {
+        sender,
+        overflow: Arc::new(Notify::new()),
+    };
+    let overflow = sender.overflow.clone();
+    let writer = tokio::spawn(async move {
+        while let Some(text) = outgoing.recv().await {
+            if let Err(e) = sink.send(Message::text(text)).await {
+                tracing::debug!("cannot write to websocket: {e:}");
+                break;
+            }
+        }
+    });
+
+    let mut connection = Connection {
+        sender,
+        authorization,
+        handlers,
+        subscriptions: HashMap::new(),
+        next_subscription: 0,
+    };
+    loop {
+        let message = tokio::select! {
+            message = stream.next() => message,
+            _ = overflow.notified() => {
+                tracing::warn!("closing websocket connection not reading its messages");
+                break;
+            }
+        };
+        let message = match message {
+            None => break,
+            Some(Ok(m)) => m,
+            Some(Err(e)) => {
+                tracing::debug!("websocket connection failed: {e:}");
+                break;
+            }
+        };
+        if message.is_close() {
+            break;
+        }
+        // pings are answered by warp, and binary messages are not json rpc
+        if let Ok(text) = message.to_str() {
+            connection.handle(text);
+        }
+    }
+
+    connection.close();
+    writer.abort();
+}
+
+/// The queue of the messages written to a connection.
+#[derive(Clone)]
+struct Outgoing {
+    sender: Sender<String>,
+    /// Notified when the queue is full, to close the connection.
+    overflow: Arc<Notify>,
+}
+
+impl Outgoing {
+    /// Queues `message` to be written to the websocket. Returns `false` if it is closed, or
+    /// closing as its queue is full.
+    fn send(&self, message: &impl Serialize) -> bool {
+        let text = match serde_json::to_string(message) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::warn!("cannot serialize websocket message: {e:}");
+                return true;
+            }
+        };
+        match self.sender.try_send(text) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.overflow.notify_one();
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+struct Connection {
+    sender: Outgoing,
+    authorization: Option<String>,
+    handlers: Arc<Handlers>,
+    subscriptions: HashMap<u64, JoinHandle<()>>,
+    next_subscription: u64,
+}
+
+impl Connection {
+    fn handle(&mut self, text: &str) {
+        let request = match serde_json::from_str::<JSONRPCRequest>(text) {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::debug!("cannot deserialize {text:} due to {e:?}");
+                self.sender.send(&JSONRPCErrorResponse::invalid_request(0));
+                return;
+            }
+        };
+        let request_id = new_request_id();
+        let span = request.span(&request_id);
+        let _entered = span.enter();
+        let JSONRPCRequest {
+            id,
+            method,
+            params,
+            jsonrpc,
+        } = request;
+        tracing::debug!(
+            "received websocket request for method = {method:?} and params = {params:?}"
+        );
+
+        if jsonrpc != JSON_RPC_VERSION {
+            self.sender.send(&JSONRPCErrorResponse::invalid_request(id));
+            return;
+        }
+        if let Err(e) = self
+            .handlers
+            .authorize(&method, self.authorization.as_deref())
+        {
+            tracing::warn!("refused websocket request to method {method:}: {e:?}");
+            send_result(&self.sender, id, &request_id, Err(anyhow!("{e:?}")));
+            return;
+        }
+
+        let result = match method.as_str() {
+            json_rpc_methods::SUBSCRIBE_CHECKPOINTS => self.subscribe(Topic::Checkpoints, params),
+            json_rpc_methods::SUBSCRIBE_CROSS_MSGS => self.subscribe(Topic::CrossMsgs, params),
+            json_rpc_methods::UNSUBSCRIBE => self.unsubscribe(params),
+            _ => {
+                // the other methods can take a while, the connection keeps being served meanwhile
+                let handlers = self.handlers.clone();
+                let sender = self.sender.clone();
+                tokio::spawn(
+                    async move {
+                        let result = handlers.handle(method, params).await;
+                        send_result(&sender, id, &request_id, result);
+                    }
+                    .instrument(span.clone()),
+                );
+                return;
+            }
+        };
+        send_result(&self.sender, id, &request_id, result);
+    }
+
+    fn subscribe(&mut self, topic: Topic, params: Value) -> Result<Value> {
+        let params = match params {
+            Value::Null => SubscribeParams { subnet: None },
+            params => serde_json::from_value::<SubscribeParams>(params)?,
+        };
+        let subnet = params
+            .subnet
+            .as_deref()
+            .map(SubnetID::from_str)
+            .transpose()?;
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return Err(anyhow!(
+                "too many subscriptions, at most {MAX_SUBSCRIPTIONS:} per connection"
+            ));
+        }
+
+        self.next_subscription += 1;
+        let id = self.next_subscription;
+        // subscribed before the id is returned, so that no event is missed after it
+        let mut events = self.handlers.state().events.subscribe();
+        let sender = self.sender.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("websocket subscription {id:} missed {n:} events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if event.topic() != topic || subnet.as_ref().map_or(false, |s| !event.concerns(s)) {
+                    continue;
+                }
+                let notification = SubscriptionNotification {
+                    jsonrpc: String::from(JSON_RPC_VERSION),
+                    method: String::from(json_rpc_methods::SUBSCRIPTION),
+                    params: SubscriptionEvent {
+                        subscription: id,
+                        result: event,
+                    },
+                };
+                if !sender.send(&notification) {
+                    break;
+                }
+            }
+        });
+        self.subscriptions.insert(id, task);
+        tracing::debug!("websocket subscription {id:} to {topic:?} events");
+
+        Ok(serde_json::to_value(id)?)
+    }
+
+    fn unsubscribe(&mut self, params: Value) -> Result<Value> {
+        let params = serde_json::from_value::<UnsubscribeParams>(params)?;
+        let task = self
+            .subscriptions
+            .remove(&params.subscription)
+            .ok_or_else(|| anyhow!("unknown subscription {}", params.subscription))?;
+        task.abort();
+        Ok(Value::Bool(true))
+    }
+
+    fn close(self) {
+        for (_, task) in self.subscriptions {
+            task.abort();
+        }
+    }
+}
+
+/// Sends the response to the request `id`, tagged with `request_id` by the agent.
+fn send_result(sender: &Outgoing, id: u64, request_id: &str, result: Result<Value>) {
+    match result {
+        Ok(value) => sender.send(&JSONRPCResultResponse::new(id, value)),
+        Err(e) => {
+            tracing::warn!("websocket request failed: {e:}");
+            sender.send(&JSONRPCErrorResponse::failed(id, request_id, &e))
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use tokio::sync::mpsc::channel;
+    use tokio::sync::Notify;
+
+    use crate::server::ws::Outgoing;
+
+    #[tokio::test]
+    async fn test_outgoing_overflow() {
+        let (sender, mut outgoing) = channel::<String>(2);
+        let sender = Outgoing {
+            sender,
+            overflow: Arc::new(Notify::new()),
+        };
+
+        assert!(sender.send(&json!(1)));
+        assert!(sender.send(&json!(2)));
+        // the queue is full, the connection is closed
+        assert!(!sender.send(&json!(3)));
+        sender.overflow.notified().await;
+
+        assert_eq!(outgoing.recv().await.unwrap(), "1");
+        drop(outgoing);
+        assert!(!sender.send(&json!(4)));
+    }
+}
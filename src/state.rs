@@ -0,0 +1,110 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The in-memory state of the agent, shared by its subsystems and the handlers of its json rpc
+//! api.
+//!
+//! The daemon creates the state once and passes it to every subsystem, so that e.g. the nonces
+//! reserved by the checkpoint managers are seen by the handlers sending messages from the same
+//! accounts. Clients created on their own, outside of the daemon, get a state of their own.
+
+use std::sync::Arc;
+
+use crate::checkpoint::quorum::QuorumLog;
+use crate::checkpoint::{TopDownNotices, VoteApprovals};
+use crate::events::EventBus;
+use crate::history::backfill::RunningBackfills;
+use crate::jobs::JobRegistry;
+use crate::jsonrpc::pool::HttpClientPool;
+use crate::jsonrpc::tap::DebugTap;
+use crate::lotus::version::NodeApis;
+use crate::manager::approval::OperationApprovals;
+use crate::manager::clock::CalibrationLog;
+use crate::manager::funds::UnderfundedSigners;
+use crate::manager::health::{SubmissionLog, SubnetHealthLog};
+use crate::manager::key_usage::KeyUsageLog;
+use crate::manager::maintenance::MaintenanceMode;
+use crate::manager::nonce::NonceManager;
+use crate::manager::shutdown::Shutdown;
+use crate::metrics::{Counters, Metrics};
+use crate::notify::Notifier;
+
+pub struct AgentState {
+    /// The nonces of the accounts of the agent.
+    pub nonces: NonceManager,
+    /// The signatures produced with the keys of the agent.
+    pub key_usage: KeyUsageLog,
+    /// The validators whose checkpoint votes are skipped for lack of funds.
+    pub underfunded_signers: UnderfundedSigners,
+    /// The health of the subnets of the agent.
+    pub subnet_health: SubnetHealthLog,
+    /// The last checkpoints submitted by the agent.
+    pub last_submissions: SubmissionLog,
+    /// The calibrations of the subnets measured so far.
+    pub epoch_calibrations: CalibrationLog,
+    /// The API of the nodes the agent talked to, by endpoint.
+    pub(crate) node_apis: NodeApis,
+    /// The HTTP clients of the nodes, by endpoint.
+    pub http_clients: HttpClientPool,
+    /// The tap on the requests of the agent to the nodes.
+    pub debug_tap: Arc<DebugTap>,
+    /// The votes waiting for the approval of an operator.
+    pub vote_approvals: VoteApprovals,
+    /// The operations waiting for the approval of an operator.
+    pub operation_approvals: OperationApprovals,
+    /// The votes of the checkpoints of the subnets checkpointed by the agent.
+    pub quorum_monitor: QuorumLog,
+    /// The top-down checkpoints notified to and by this agent.
+    pub top_down_notices: TopDownNotices,
+    /// The subnets whose backfill is running.
+    pub backfills: RunningBackfills,
+    /// The background jobs of the agent.
+    pub jobs: Arc<JobRegistry>,
+    /// The maintenance mode of the agent.
+    pub maintenance: Arc<MaintenanceMode>,
+    /// The shutdown of the agent.
+    pub shutdown: Shutdown,
+    /// The notifier of the agent, following the config.
+    pub notifier: Notifier,
+    /// The events pushed to the websocket subscribers.
+    pub events: EventBus,
+    /// The counters of the metrics of the agent.
+    pub counters: Counters,
+    /// The metrics of the current process.
+    pub metrics: Arc<Metrics>,
+}
+
+impl AgentState {
+    pub fn new() -> Self {
+        let jobs = Arc::new(JobRegistry::new());
+        let maintenance = Arc::new(MaintenanceMode::new(jobs.clone()));
+        Self {
+            nonces: NonceManager::new(),
+            key_usage: KeyUsageLog::new(),
+            underfunded_signers: UnderfundedSigners::new(),
+            subnet_health: SubnetHealthLog::new(),
+            last_submissions: SubmissionLog::new(),
+            epoch_calibrations: CalibrationLog::new(),
+            node_apis: NodeApis::new(),
+            http_clients: HttpClientPool::new(),
+            debug_tap: Arc::new(DebugTap::new()),
+            vote_approvals: VoteApprovals::new(),
+            operation_approvals: OperationApprovals::new(),
+            quorum_monitor: QuorumLog::new(),
+            top_down_notices: TopDownNotices::new(),
+            backfills: RunningBackfills::default(),
+            jobs,
+            shutdown: Shutdown::new(maintenance.clone()),
+            maintenance,
+            notifier: Notifier::new(),
+            events: EventBus::new(),
+            counters: Counters::new(),
+            metrics: Arc::new(Metrics::new()),
+        }
+    }
+}
+
+impl Default for AgentState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
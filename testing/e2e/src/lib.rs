@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: MIT
 use fvm_shared::address::Address;
 use ipc_agent::cli::{
-    CommandLineHandler, CreateSubnet, CreateSubnetArgs, GlobalArguments, JoinSubnet,
-    JoinSubnetArgs, KillSubnet, KillSubnetArgs, LeaveSubnet, LeaveSubnetArgs,
+    CommandLineHandler, CreateSubnet, CreateSubnetArgs, CreateSubnetConsensus, GlobalArguments,
+    JoinSubnet, JoinSubnetArgs, KillSubnet, KillSubnetArgs, LeaveSubnet, LeaveSubnetArgs,
 };
 use ipc_sdk::subnet_id::SubnetID;
 use std::str::FromStr;
@@ -28,6 +28,11 @@ impl TestClient {
             min_validators: 0,
             bottomup_check_period: 10,
             topdown_check_period: 10,
+            consensus: CreateSubnetConsensus::Mir,
+            active_validators_limit: None,
+            permission_mode: None,
+            supply_source_token: None,
+            preview: false,
         };
 
         let raw = CreateSubnet::create(&global, &args).await?;
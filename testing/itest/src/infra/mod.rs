@@ -9,7 +9,7 @@ use crate::infra::subnet::{spawn_first_node, spawn_other_nodes, SubnetNode};
 use crate::infra::util::trim_newline;
 use anyhow::anyhow;
 use fvm_shared::address::Address;
-use ipc_agent::config::subnet::FVMSubnet;
+use ipc_agent::config::subnet::{CheckpointSigning, FVMSubnet};
 use ipc_agent::config::{Config, Subnet};
 use ipc_sdk::subnet_id::SubnetID;
 use std::str::FromStr;
@@ -292,6 +292,18 @@ impl SubnetInfra {
         Ok(Subnet {
             id: self.config.id.clone().unwrap(),
             network_name: self.config.name.clone(),
+            block_time_secs: None,
+            checkpoint_period_poll_secs: None,
+            gas: Default::default(),
+            mode: Default::default(),
+            features: Default::default(),
+            vote_policy: Default::default(),
+            checkpoint_signing: CheckpointSigning::None,
+            co_signing: None,
+            submission_delay: None,
+            federation: None,
+            faucet: None,
+            headers: Default::default(),
             config: ipc_agent::config::subnet::SubnetConfig::Fvm(FVMSubnet {
                 gateway_addr: Address::from_str("t064")?,
                 jsonrpc_api_http: format!(
@@ -299,8 +311,15 @@ impl SubnetInfra {
                     self.nodes.as_ref().unwrap()[0].node.tcp_port
                 )
                 .parse()?,
+                jsonrpc_api_ws: None,
+                expected_network_name: None,
+                retry: Default::default(),
+                connections: Default::default(),
+                message_wait: Default::default(),
                 auth_token: Some(admin_token),
                 accounts,
+                signer: Default::default(),
+                decoding: Default::default(),
             }),
         })
     }
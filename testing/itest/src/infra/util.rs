@@ -31,10 +31,14 @@ pub async fn create_subnet(
         from,
         parent,
         name,
+        consensus: Default::default(),
         min_validator_stake: DEFAULT_MIN_STAKE,
         min_validators,
         bottomup_check_period: 10,
         topdown_check_period: 10,
+        active_validators_limit: None,
+        permission_mode: None,
+        supply_source: None,
     };
     client.create_subnet(params).await
 }
@@ -50,7 +50,8 @@ async fn test_fund_and_release() {
     let fund_epoch = ipc_client
         .fund(&subnet, Some(addr.clone()), Some(addr.clone()), amount)
         .await
-        .unwrap();
+        .unwrap()
+        .epoch;
     println!("fund epoch: {fund_epoch:}");
     loop {
         let epoch = ipc_client.last_top_down_executed(&subnet).await.unwrap();
@@ -64,7 +65,8 @@ async fn test_fund_and_release() {
     let epoch = ipc_client
         .release(&subnet, Some(addr.clone()), Some(addr.clone()), amount)
         .await
-        .unwrap();
+        .unwrap()
+        .epoch;
     println!("release epoch: {epoch:}");
     loop {
         let checkpoints = ipc_client